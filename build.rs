@@ -20,9 +20,17 @@ fn main() {
 
     #[cfg(target_os = "windows")]
     {
-        let mut res = winres::WindowsResource::new();
-        // Use the generated .ico
-        res.set_icon("icons/app.ico");
-        res.compile().expect("Failed to embed Windows icon");
+        println!("cargo:rerun-if-changed=icons/app.ico");
+        // Use the generated .ico; without it the executable keeps the default icon and the window rasterizes
+        // the embedded SVG.
+        if std::path::Path::new("icons/app.ico").exists() {
+            let mut res = winres::WindowsResource::new();
+            res.set_icon("icons/app.ico");
+            res.compile().expect("Failed to embed Windows icon");
+        } else {
+            println!(
+                "cargo:warning=icons/app.ico not found; run `cargo run -p xtask -- icons` to embed the executable icon"
+            );
+        }
     }
 }
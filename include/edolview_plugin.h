@@ -0,0 +1,57 @@
+/* EdolView native plugin interface (ABI version 1).
+ *
+ * Build a shared library that exports `edolview_plugin_v1` and place it in the `plugins`
+ * directory next to the edolview executable. Images are interleaved 32-bit floats, row-major,
+ * with 1 to 4 channels. All functions may be called from worker threads.
+ */
+#ifndef EDOLVIEW_PLUGIN_H
+#define EDOLVIEW_PLUGIN_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define EDOLVIEW_PLUGIN_ABI_VERSION 1
+
+#if defined(_WIN32)
+#define EDOLVIEW_PLUGIN_EXPORT __declspec(dllexport)
+#else
+#define EDOLVIEW_PLUGIN_EXPORT __attribute__((visibility("default")))
+#endif
+
+typedef struct EdolviewImage {
+    int32_t width;
+    int32_t height;
+    int32_t channels;
+    float *data; /* width * height * channels values, within the viewer's decode limits */
+} EdolviewImage;
+
+typedef struct EdolviewFilter {
+    const char *name;
+    /* Allocate and fill `output` from `input`. Returns 0 on success. */
+    int32_t (*apply)(const EdolviewImage *input, EdolviewImage *output);
+} EdolviewFilter;
+
+typedef struct EdolviewPluginV1 {
+    uint32_t abi_version; /* EDOLVIEW_PLUGIN_ABI_VERSION */
+    const char *name;
+    /* Comma-separated file extensions handled by `load`, e.g. "raw,bin", or NULL. */
+    const char *extensions;
+    /* Decode the file at the UTF-8 `path` into `output`. Returns 0 on success. May be NULL. */
+    int32_t (*load)(const char *path, EdolviewImage *output);
+    uint32_t filter_count;
+    const EdolviewFilter *filters; /* `filter_count` entries; must not be NULL when `filter_count` > 0. */
+    /* Release pixel data allocated by `load` or a filter. Required when either is provided. */
+    void (*free_image)(EdolviewImage *image);
+} EdolviewPluginV1;
+
+/* Entry point; the returned descriptor must stay valid while the library is loaded. */
+EDOLVIEW_PLUGIN_EXPORT const EdolviewPluginV1 *edolview_plugin_v1(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* EDOLVIEW_PLUGIN_H */
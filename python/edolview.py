@@ -1,3 +1,6 @@
+from __future__ import annotations
+
+import json
 import socket
 from struct import pack
 import importlib.util
@@ -16,6 +19,12 @@ def _area_interpolate(im, scale):
     return (buf / (scale * scale)).astype(im.dtype)
 
 def _parse_dtype(dtype):
+    """numpy dtype -> wire dtype code.
+
+    Codes 0-7 are OpenCV depths: 0 uint8, 1 int8, 2 uint16, 3 int16, 4 int32, 5 float32, 6 float64, 7 float16.
+    Code 8 is uint32, which has no OpenCV depth. Samples are little-endian unless the metadata sets byte_order.
+    """
+    if dtype == np.uint32:  return 8
     if dtype == np.float64: return 6
     if dtype == np.float32: return 5
     if dtype == np.float16: return 7
@@ -52,7 +61,9 @@ class EdolView:
         self.host = host
         self.port = port
 
-    def send_image(self, name: str, image: np.ndarray, float_to_half: bool, do_compression: bool = False, downscale_factor: int = 1):
+    def send_image(self, name: str, image: np.ndarray, float_to_half: bool, do_compression: bool = False, downscale_factor: int = 1, bgr: bool = False, metadata: dict | None = None):
+        # bgr=True: channels are stored B, G, R(, A) as returned by cv2.imread
+        # metadata: JSON-serializable values shown with the image, e.g. {'iteration': 1200, 'loss': 0.031}
         # torch -> numpy 변환
         if not isinstance(image, np.ndarray):
             torch_spec = importlib.util.find_spec('torch')
@@ -86,9 +97,12 @@ class EdolView:
         if do_compression:
             if np.issubdtype(image.dtype, np.integer) and cv2_spec is not None:
                 import cv2
+                # cv2.imencode expects BGR(A); the PNG itself is always stored as RGB(A)
                 img_enc = image
-                if image.shape[2] == 3:
+                if not bgr and image.shape[2] == 3:
                     img_enc = image[:, :, ::-1]
+                elif not bgr and image.shape[2] == 4:
+                    img_enc = image[:, :, [2, 1, 0, 3]]
                 ok, buf = cv2.imencode('.png', img_enc)
                 if not ok:
                     raise Exception('cv2.imencode(.png) failed')
@@ -108,9 +122,16 @@ class EdolView:
 
         nbytes_uncompressed = image.nbytes
         H, W, C = image.shape
-        dtype_code = _parse_dtype(image.dtype)
+        dtype_code = _parse_dtype(image.dtype.newbyteorder('='))
 
         compression_bytes = compression.encode('utf-8')
+        meta = dict(metadata or {})
+        if bgr and compression != 'png' and image.shape[2] >= 3:
+            meta['channel_order'] = 'bgr'
+        if compression != 'png' and image.dtype.byteorder == '>':
+            meta['byte_order'] = 'big'
+        if meta:
+            compression_bytes += b'\0' + json.dumps(meta).encode('utf-8')
         extra_bytes = b''.join([
             pack('!Q', nbytes_uncompressed),     # u64
             pack('!III', H, W, C),               # 3×u32
@@ -118,6 +139,23 @@ class EdolView:
             compression_bytes                    # utf-8
         ])
 
+        self._send(name, extra_bytes, buf_bytes, compression)
+
+    def send_yuv(self, name: str, frame, width: int, height: int, fmt: str = 'nv12'):
+        # frame: bytes or uint8 array holding one nv12, i420 or yuyv frame, e.g. a decoder output buffer
+        fmt = fmt.lower()
+        if fmt not in ('nv12', 'i420', 'yuyv'):
+            raise Exception('yuv format not supported: ' + fmt)
+        buf_bytes = np.ascontiguousarray(frame, dtype=np.uint8).tobytes() if not isinstance(frame, bytes) else frame
+        extra_bytes = b''.join([
+            pack('!Q', len(buf_bytes)),          # u64
+            pack('!III', height, width, 3),      # 3×u32
+            pack('!I', 0),                       # u32, 8-bit
+            fmt.encode('utf-8')                  # utf-8
+        ])
+        self._send(name, extra_bytes, buf_bytes, fmt)
+
+    def _send(self, name: str, extra_bytes: bytes, buf_bytes: bytes, compression: str):
         name_bytes = name.encode('utf-8')
 
         name_len = len(name_bytes)
@@ -136,8 +174,13 @@ class EdolView:
             s.sendall(buf_bytes)
             s.close()
 
-def send(address: str, name: str, image: np.ndarray, float_to_half: bool = False, do_compression: bool = False, downscale_factor: int = 1):
+def send(address: str, name: str, image: np.ndarray, float_to_half: bool = False, do_compression: bool = False, downscale_factor: int = 1, bgr: bool = False, metadata: dict | None = None):
     host, port_str = address.split(":")
     port = int(port_str)
     edolview = EdolView(host, port)
-    edolview.send_image(name, image, float_to_half, do_compression, downscale_factor)
\ No newline at end of file
+    edolview.send_image(name, image, float_to_half, do_compression, downscale_factor, bgr, metadata)
+
+def send_yuv(address: str, name: str, frame, width: int, height: int, fmt: str = 'nv12'):
+    host, port_str = address.split(":")
+    edolview = EdolView(host, int(port_str))
+    edolview.send_yuv(name, frame, width, height, fmt)
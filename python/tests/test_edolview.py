@@ -0,0 +1,40 @@
+"""Run from the python directory with `python -m unittest discover tests`.
+
+The extra block sent here is stored in tests/fixtures/python_extra_metadata.bin at the repository root, which the
+socket tests of the viewer parse. Set EDOLVIEW_UPDATE_FIXTURES=1 to rewrite it after an intended protocol change.
+"""
+import os
+import pathlib
+import unittest
+
+import numpy as np
+
+import edolview
+
+FIXTURE = pathlib.Path(__file__).resolve().parents[2] / 'tests' / 'fixtures' / 'python_extra_metadata.bin'
+
+
+class SendTest(unittest.TestCase):
+    def setUp(self):
+        self.sent = []
+        self._send = edolview.EdolView._send
+        edolview.EdolView._send = lambda _, name, extra_bytes, buf_bytes, compression: self.sent.append(
+            (name, extra_bytes, buf_bytes, compression))
+
+    def tearDown(self):
+        edolview.EdolView._send = self._send
+
+    def test_send_passes_metadata_in_the_extra_block(self):
+        image = np.arange(18, dtype=np.uint8).reshape(2, 3, 3)
+        edolview.send('127.0.0.1:0', 'frame', image, bgr=True, metadata={'iteration': 1200, 'camera': 'left'})
+
+        self.assertEqual(len(self.sent), 1)
+        name, extra_bytes, buf_bytes, compression = self.sent[0]
+        self.assertEqual((name, buf_bytes, compression), ('frame', image.tobytes(), 'raw'))
+        if os.environ.get('EDOLVIEW_UPDATE_FIXTURES'):
+            FIXTURE.write_bytes(extra_bytes)
+        self.assertEqual(extra_bytes, FIXTURE.read_bytes())
+
+
+if __name__ == '__main__':
+    unittest.main()
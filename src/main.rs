@@ -10,7 +10,10 @@ use crate::ui::ViewerApp;
 
 mod control;
 mod model;
+mod plugin;
+mod recovery;
 mod res;
+mod script;
 mod settings;
 mod supported_image;
 mod ui;
@@ -20,7 +23,9 @@ mod util;
 #[cfg(debug_assertions)]
 mod debug;
 
-const ICON_DATA: &[u8] = include_bytes!("../icons/icon.png");
+/// Source of the generated `icons/` files, embedded so a build never depends on them.
+const ICON_SVG: &[u8] = include_bytes!("../icon.svg");
+const ICON_SIZE: u32 = 256;
 #[cfg(target_os = "windows")]
 const WINDOWS_APP_USER_MODEL_ID: &str = "kr.edoli.edolview";
 
@@ -179,6 +184,11 @@ fn configure_wgpu(options: &mut eframe::NativeOptions) {
 struct Args {
     /// Image file paths to open (optional)
     images: Vec<PathBuf>,
+
+    /// Start without the socket listener, file watchers, plugins and custom colormaps, drawing with the simplest
+    /// shader path; helps tell whether one of them crashes the app on startup
+    #[arg(long)]
+    safe_mode: bool,
 }
 
 fn main() -> Result<()> {
@@ -191,6 +201,13 @@ fn main() -> Result<()> {
         crate::settings::AppSettings::default()
     });
 
+    // Scan plugins before the UI starts so extension checks never load libraries during a frame.
+    if args.safe_mode {
+        crate::plugin::disable_plugins();
+    } else {
+        crate::plugin::plugins();
+    }
+
     if !args.images.is_empty() && settings.external_open_mode == crate::settings::ExternalOpenMode::ExistingWindow {
         match crate::control::try_forward_paths_to_last_active(&args.images) {
             Ok(true) => return Ok(()),
@@ -204,15 +221,8 @@ fn main() -> Result<()> {
         std::env::remove_var("WAYLAND_DISPLAY");
     }
 
-    let icon = match image::load_from_memory(ICON_DATA) {
-        Ok(icon) => {
-            let icon = icon.into_rgba8();
-            egui::IconData {
-                width: icon.width(),
-                height: icon.height(),
-                rgba: icon.into_raw(),
-            }
-        }
+    let icon = match app_icon() {
+        Ok(icon) => icon,
         Err(error) => {
             eprintln!("Failed to load icon image; continuing without icon: {error}");
             egui::IconData::default()
@@ -222,7 +232,12 @@ fn main() -> Result<()> {
     let mut native_options = eframe::NativeOptions {
         viewport: ViewportBuilder::default()
             .with_title("edolview-rs")
-            .with_inner_size(egui::vec2(1280.0, 720.0))
+            .with_inner_size(
+                settings
+                    .ui_state
+                    .window_size
+                    .map_or(egui::vec2(1280.0, 720.0), |[width, height]| egui::vec2(width, height)),
+            )
             .with_min_inner_size(egui::vec2(720.0, 480.0))
             .with_icon(icon),
         renderer: eframe::Renderer::Wgpu,
@@ -233,13 +248,28 @@ fn main() -> Result<()> {
     if let Err(e) = eframe::run_native(
         "edolview-rs",
         native_options,
-        Box::new(|_cc| Ok(Box::new(ViewerApp::new().with_paths(args.images)))),
+        Box::new(|_cc| Ok(Box::new(ViewerApp::new(args.safe_mode).with_paths(args.images)))),
     ) {
         return Err(eyre!("eframe initialization failed: {e}"));
     }
     Ok(())
 }
 
+/// Window icon rasterized from the embedded SVG.
+fn app_icon() -> Result<egui::IconData, String> {
+    let size = egui::SizeHint::Size {
+        width: ICON_SIZE,
+        height: ICON_SIZE,
+        maintain_aspect_ratio: true,
+    };
+    let image = crate::ui::icon::load_svg_bytes_with_size(ICON_SVG, size, &resvg::usvg::Options::default())?;
+    Ok(egui::IconData {
+        width: image.width() as u32,
+        height: image.height() as u32,
+        rgba: image.pixels.iter().flat_map(|color| color.to_srgba_unmultiplied()).collect(),
+    })
+}
+
 #[cfg(target_os = "windows")]
 fn set_windows_app_user_model_id() {
     use windows_sys::Win32::UI::Shell::SetCurrentProcessExplicitAppUserModelID;
@@ -259,6 +289,14 @@ fn set_windows_app_user_model_id() {}
 mod tests {
     use super::*;
 
+    #[test]
+    fn app_icon_is_rasterized_from_the_embedded_svg() {
+        let icon = app_icon().unwrap();
+        assert_eq!((icon.width, icon.height), (ICON_SIZE, ICON_SIZE));
+        assert_eq!(icon.rgba.len(), (ICON_SIZE * ICON_SIZE * 4) as usize);
+        assert!(icon.rgba.chunks_exact(4).any(|pixel| pixel[3] == 255));
+    }
+
     #[test]
     fn webgl2_limits_are_rejected_for_image_compute() {
         assert!(!supports_image_compute_limits(&wgpu::Limits::downlevel_webgl2_defaults()));
@@ -0,0 +1,136 @@
+//! Freehand strokes drawn over images during reviews, kept per asset in image coordinates so they follow the
+//! view.
+
+use std::collections::HashMap;
+
+use eframe::egui::{Color32, Pos2};
+
+/// Pressure used for the thinnest part of a stroke, so light pen touches stay visible.
+const MIN_PRESSURE: f32 = 0.15;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StrokePoint {
+    /// Position in image pixels.
+    pub pos: Pos2,
+    /// Pen pressure in `[0, 1]`; mouse input uses 1.
+    pub pressure: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnnotationStroke {
+    pub points: Vec<StrokePoint>,
+    pub color: Color32,
+    /// Width at full pressure, in image pixels.
+    pub width: f32,
+}
+
+impl AnnotationStroke {
+    pub fn new(color: Color32, width: f32) -> Self {
+        Self {
+            points: Vec::new(),
+            color,
+            width,
+        }
+    }
+
+    /// Width of the stroke at `point`, in image pixels.
+    pub fn width_at(&self, point: &StrokePoint) -> f32 {
+        self.width * point.pressure.clamp(MIN_PRESSURE, 1.0)
+    }
+
+    /// Whether the stroke passes within `radius` image pixels of `pos`.
+    fn hits(&self, pos: Pos2, radius: f32) -> bool {
+        let reach = |point: &StrokePoint| radius + self.width_at(point) * 0.5;
+        match self.points.as_slice() {
+            [point] => point.pos.distance(pos) <= reach(point),
+            points => points
+                .windows(2)
+                .any(|segment| distance_to_segment(pos, segment[0].pos, segment[1].pos) <= reach(&segment[1])),
+        }
+    }
+}
+
+/// Strokes of every annotated asset, by asset hash.
+#[derive(Default)]
+pub struct Annotations {
+    strokes: HashMap<String, Vec<AnnotationStroke>>,
+}
+
+impl Annotations {
+    pub fn strokes(&self, hash: &str) -> &[AnnotationStroke] {
+        self.strokes.get(hash).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn add(&mut self, hash: &str, stroke: AnnotationStroke) {
+        if !stroke.points.is_empty() {
+            self.strokes.entry(hash.to_string()).or_default().push(stroke);
+        }
+    }
+
+    /// Remove the strokes passing within `radius` image pixels of `pos`, returning whether any were removed.
+    pub fn erase_at(&mut self, hash: &str, pos: Pos2, radius: f32) -> bool {
+        let Some(strokes) = self.strokes.get_mut(hash) else {
+            return false;
+        };
+        let count = strokes.len();
+        strokes.retain(|stroke| !stroke.hits(pos, radius));
+        strokes.len() != count
+    }
+
+    /// Remove the most recent stroke, returning whether there was one.
+    pub fn undo(&mut self, hash: &str) -> bool {
+        self.strokes.get_mut(hash).and_then(Vec::pop).is_some()
+    }
+
+    pub fn clear(&mut self, hash: &str) {
+        self.strokes.remove(hash);
+    }
+}
+
+fn distance_to_segment(pos: Pos2, a: Pos2, b: Pos2) -> f32 {
+    let ab = b - a;
+    let length_sq = ab.length_sq();
+    let t = if length_sq > 0.0 {
+        ((pos - a).dot(ab) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    pos.distance(a + ab * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eframe::egui::pos2;
+
+    fn stroke(points: &[(f32, f32)]) -> AnnotationStroke {
+        AnnotationStroke {
+            points: points
+                .iter()
+                .map(|&(x, y)| StrokePoint {
+                    pos: pos2(x, y),
+                    pressure: 1.0,
+                })
+                .collect(),
+            color: Color32::RED,
+            width: 2.0,
+        }
+    }
+
+    #[test]
+    fn erasing_removes_strokes_near_the_eraser() {
+        let mut annotations = Annotations::default();
+        annotations.add("a", stroke(&[(0.0, 0.0), (100.0, 0.0)]));
+        annotations.add("a", stroke(&[(0.0, 50.0)]));
+        annotations.add("a", stroke(&[]));
+        assert_eq!(annotations.strokes("a").len(), 2);
+
+        assert!(!annotations.erase_at("a", pos2(50.0, 10.0), 5.0));
+        assert!(annotations.erase_at("a", pos2(50.0, 4.0), 5.0));
+        assert_eq!(annotations.strokes("a"), &[stroke(&[(0.0, 50.0)])]);
+        assert!(!annotations.erase_at("b", pos2(0.0, 50.0), 5.0));
+
+        assert!(annotations.undo("a"));
+        assert!(!annotations.undo("a"));
+    }
+}
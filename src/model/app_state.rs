@@ -1,21 +1,73 @@
 use std::{
-    path::PathBuf,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
 
-use clipboard_rs::{Clipboard, ClipboardContext, ContentFormat};
 use color_eyre::eyre::{eyre, Result};
+use eframe::egui::{Color32, Pos2};
 use indexmap::IndexMap;
 
 use crate::{
     model::{
-        AssetType, ClipboardAsset, ComparisonAsset, ComparisonMode, FileAsset, Image, ImageData, Recti, SharedAsset,
-        SocketInfo, SocketState, Statistics,
+        estimate_white_balance, neutral_patch, Annotations, Asset, AssetType, ClipboardAsset, ColorLut3d,
+        ComparisonAsset, ComparisonMode, ContentHashes, FileAsset, GridView, HeatmapOverlay, Image, ImageData,
+        ImageLabels, ImageMemory, LensDistortion, Proofing, RatingFilter, Ratings, Recti, SegmentationMask,
+        SelectionMask, SharedAsset, SocketAsset, SocketInfo, SocketState, Statistics, VectorOverlay,
     },
-    ui::gpu::ShaderParams,
+    settings::{
+        AssetGrouping, BackgroundSettings, ExportOptions, OverlayVisibility, PixelValueSettings, SocketHistorySettings,
+        ViewPreset,
+    },
+    ui::gpu::{MagnificationFilter, ShaderParams},
     util::math_ext::{vec2i, Vec2i},
 };
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SelectionTool {
+    #[default]
+    Rectangle,
+    Lasso,
+    Polygon,
+    Wand,
+}
+
+impl SelectionTool {
+    pub const ALL: [Self; 4] = [Self::Rectangle, Self::Lasso, Self::Polygon, Self::Wand];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Rectangle => "Rectangle",
+            Self::Lasso => "Lasso",
+            Self::Polygon => "Polygon",
+            Self::Wand => "Magic wand",
+        }
+    }
+}
+
+/// What the eyedropper sets from a clicked pixel: an end of the display range, or the white balance from the
+/// neutral patch around it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointPickTarget {
+    Black,
+    White,
+    Neutral,
+}
+
+/// Pending eyedropper pick. `channel` is -1 for the shared min/max, otherwise the per-channel control it belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PointPicker {
+    pub target: PointPickTarget,
+    pub channel: i32,
+}
+
+/// The frame before the primary image in its sequence, for the temporal difference.
+pub enum PreviousFrame {
+    Asset(SharedAsset),
+    /// A file of the folder that is not in the image list yet.
+    File(PathBuf),
+}
+
 pub struct AppState {
     pub path: Option<PathBuf>,
     pub asset: Option<SharedAsset>,
@@ -23,11 +75,27 @@ pub struct AppState {
     pub asset_secondary: Option<SharedAsset>,
     pub comparison_mode: ComparisonMode,
     pub comparison_blend: f32,
+    /// Factor on the difference of `Diff` comparisons.
+    pub comparison_diff_gain: f32,
     pub comparison_notice: Option<String>,
     pub shader_params: ShaderParams,
     pub cursor_pos: Option<Vec2i>,
+    /// Continuous image coordinates under the pointer; pixel `(x, y)` covers `[x, x + 1) × [y, y + 1)`.
+    pub cursor_subpixel: Option<Pos2>,
     pub cursor_on_secondary: bool,
     pub marquee_rect: Recti,
+    pub selection_tool: SelectionTool,
+    /// Pixel mask of a lasso/polygon selection; `marquee_rect` holds its bounds while it is active.
+    pub selection_mask: Option<Arc<SelectionMask>>,
+    /// Vertices (image coordinates) of a lasso or polygon that is still being drawn.
+    pub selection_draft: Vec<Pos2>,
+    /// Per-channel tolerance of the magic wand, in normalized pixel values.
+    pub wand_tolerance: f32,
+    pub point_picker: Option<PointPicker>,
+    /// Output transform applied to the final display color, e.g. the monitor ICC profile.
+    pub output_lut: Option<Arc<ColorLut3d>>,
+    /// Look LUT (e.g. a grading `.cube`) and the file it was loaded from.
+    pub look_lut: Option<(PathBuf, Arc<ColorLut3d>)>,
 
     pub channel_index: i32,
     pub colormap_rgb: String,
@@ -36,16 +104,52 @@ pub struct AppState {
     pub colormap_mono_list: Vec<String>,
 
     pub is_show_background: bool,
+    pub background: BackgroundSettings,
     pub is_show_pixel_value: bool,
+    pub pixel_value: PixelValueSettings,
     pub is_show_crosshair: bool,
+    pub is_show_image_border: bool,
+    pub overlays_by_asset_type: BTreeMap<String, OverlayVisibility>,
+    overlay_asset_type: Option<AssetType>,
     pub is_show_sidebar: bool,
     pub is_show_statusbar: bool,
 
-    // Copy behavior: when true, Ctrl+C copies marquee at original pixel size regardless of zoom.
-    pub copy_use_original_size: bool,
+    /// Keep zoom, pan and selection when switching between images of the same size.
+    pub lock_view: bool,
+    /// Show the alpha channel as plain grayscale, whatever the display settings.
+    pub alpha_view: bool,
+    /// Dragging draws annotation strokes instead of panning.
+    pub annotate: bool,
+    pub annotation_color: Color32,
+    /// Stroke width at full pressure, in screen points at the zoom the stroke is drawn.
+    pub annotation_width: f32,
+    pub annotations: Annotations,
+    /// Detections or segmentations loaded from a file and drawn over the images they belong to.
+    pub vector_overlay: Option<VectorOverlay>,
+    pub is_show_vector_overlay: bool,
+    /// Label image drawn as a colored layer over the shown image.
+    pub segmentation_mask: Option<SegmentationMask>,
+    pub is_show_segmentation_mask: bool,
+    /// Single-channel image from the list blended through a colormap over the shown image.
+    pub heatmap: Option<HeatmapOverlay>,
+    pub is_show_heatmap: bool,
+    /// Parameters of the undistort preview, kept while it is off.
+    pub lens_distortion: Option<LensDistortion>,
+    /// Simulated viewing conditions, applied to the display only.
+    pub proofing: Proofing,
+    /// Number keys toggle labels of the shown image.
+    pub labeling: bool,
+    pub labels: ImageLabels,
+    pub ratings: Ratings,
+    /// Files skipped by the previous/next file navigation.
+    pub rating_filter: RatingFilter,
+    /// What copies and saves write: display render or stored samples, at which size, with which metadata.
+    pub export_options: ExportOptions,
 
     // File navigation + watcher
     pub file_nav: crate::model::FileNav,
+    /// Started with `--safe-mode`: no directory watcher and only the built-in colormaps.
+    pub safe_mode: bool,
 
     pub statistics: Statistics,
 
@@ -53,6 +157,37 @@ pub struct AppState {
     pub socket_info: Arc<Mutex<SocketInfo>>,
 
     pub assets: IndexMap<String, SharedAsset>,
+    pub asset_grouping: AssetGrouping,
+    pub is_show_asset_thumbnails: bool,
+    pub magnification_filter: MagnificationFilter,
+    /// Manual group name by asset hash.
+    pub manual_asset_groups: HashMap<String, String>,
+    /// Assets picked for the N-up grid comparison.
+    pub grid: GridView,
+    pub content_hashes: ContentHashes,
+    /// Hashes of socket assets in arrival order, oldest first.
+    socket_history: VecDeque<String>,
+    /// Images received under one name in replace mode by hash, oldest first, including the listed one.
+    socket_versions: HashMap<String, VecDeque<SharedAsset>>,
+}
+
+/// Assets of one image list group with their index in [`AppState::assets`].
+pub struct AssetGroup {
+    /// `None` when the list is not grouped.
+    pub label: Option<String>,
+    pub rows: Vec<(usize, String, SharedAsset)>,
+}
+
+/// Name with a trailing counter removed, so `train/iter_0100` and `train/iter_0200` share `train/iter`.
+fn name_prefix(name: &str) -> &str {
+    let prefix = name
+        .trim_end_matches(|c: char| c.is_ascii_digit())
+        .trim_end_matches(['_', '-', '.', ' ', '/', ':']);
+    if prefix.is_empty() {
+        name
+    } else {
+        prefix
+    }
 }
 
 fn list_colormaps(rel_dir: &str) -> Vec<String> {
@@ -84,34 +219,98 @@ impl AppState {
             asset_secondary: None,
             comparison_mode: ComparisonMode::Diff,
             comparison_blend: 0.5,
+            comparison_diff_gain: 1.0,
             comparison_notice: None,
             shader_params: ShaderParams::default(),
             cursor_pos: None,
+            cursor_subpixel: None,
             cursor_on_secondary: false,
             marquee_rect: Recti::ZERO,
+            selection_tool: SelectionTool::Rectangle,
+            selection_mask: None,
+            selection_draft: Vec::new(),
+            wand_tolerance: 0.05,
+            point_picker: None,
+            output_lut: None,
+            look_lut: None,
             channel_index: -1,
             colormap_rgb: String::from("rgb"),
             colormap_mono: String::from("gray"),
             colormap_rgb_list: list_colormaps("colormap/rgb"),
             colormap_mono_list: list_colormaps("colormap/mono"),
             is_show_background: true,
+            background: BackgroundSettings::default(),
             is_show_pixel_value: true,
+            pixel_value: PixelValueSettings::default(),
             is_show_crosshair: false,
+            is_show_image_border: false,
+            overlays_by_asset_type: BTreeMap::new(),
+            overlay_asset_type: None,
             is_show_sidebar: true,
             is_show_statusbar: true,
-            copy_use_original_size: true,
+            lock_view: false,
+            alpha_view: false,
+            annotate: false,
+            annotation_color: crate::res::ANNOTATION_DEFAULT_COLOR,
+            annotation_width: 3.0,
+            annotations: Annotations::default(),
+            vector_overlay: None,
+            is_show_vector_overlay: true,
+            segmentation_mask: None,
+            is_show_segmentation_mask: true,
+            heatmap: None,
+            is_show_heatmap: true,
+            lens_distortion: None,
+            proofing: Proofing::default(),
+            labeling: false,
+            labels: ImageLabels::default(),
+            ratings: Ratings::default(),
+            rating_filter: RatingFilter::default(),
+            export_options: ExportOptions::default(),
             file_nav: crate::model::FileNav::new(),
+            safe_mode: false,
             statistics: Statistics::default(),
             socket_state: Arc::new(SocketState::new()),
             socket_info: Arc::new(Mutex::new(SocketInfo::new())),
             assets: IndexMap::new(),
+            asset_grouping: AssetGrouping::default(),
+            is_show_asset_thumbnails: false,
+            magnification_filter: MagnificationFilter::default(),
+            manual_asset_groups: HashMap::new(),
+            grid: GridView::default(),
+            content_hashes: ContentHashes::default(),
+            socket_history: VecDeque::new(),
+            socket_versions: HashMap::new(),
         }
     }
 
+    /// Apply the display settings and colormaps of `preset`, keeping the current colormap when one is not installed.
+    pub fn set_view_preset(&mut self, preset: ViewPreset) {
+        self.shader_params = preset.shader_params;
+
+        if self.colormap_rgb_list.contains(&preset.colormap_rgb) {
+            self.colormap_rgb = preset.colormap_rgb;
+        }
+        if self.colormap_mono_list.contains(&preset.colormap_mono) {
+            self.colormap_mono = preset.colormap_mono;
+        }
+    }
+
+    /// Skip the directory watcher and offer only the built-in `rgb` and `gray` colormaps.
+    pub fn enable_safe_mode(&mut self) {
+        self.safe_mode = true;
+        self.file_nav.stop_dir_watcher();
+        self.colormap_rgb = "rgb".to_owned();
+        self.colormap_mono = "gray".to_owned();
+        self.colormap_rgb_list = vec![self.colormap_rgb.clone()];
+        self.colormap_mono_list = vec![self.colormap_mono.clone()];
+    }
+
     pub fn load_from_path(&mut self, path: PathBuf) -> Result<()> {
         #[cfg(debug_assertions)]
         let _timer = crate::util::timer::ScopedTimer::new("Total image load time [from path]");
 
+        let path = crate::model::resolve_archive_path(&path)?;
         let hash_str = FileAsset::hash_from_path(&path)?;
 
         if self.assets.contains_key(&hash_str) {
@@ -134,6 +333,46 @@ impl AppState {
         self.sync_file_navigation_for_path(&path);
     }
 
+    /// Add a file loaded in the background to the image list without showing it. An entry for the same path is
+    /// replaced in place and keeps its selection and manual group.
+    pub fn add_file_asset(&mut self, path: &Path, hash: String, image: ImageData) {
+        if self.assets.contains_key(&hash) {
+            return;
+        }
+        let name = path.to_string_lossy().to_string();
+        let asset: SharedAsset = Arc::new(FileAsset::new(name.clone(), hash.clone(), image));
+        let previous = self
+            .assets
+            .values()
+            .position(|existing| existing.asset_type() == AssetType::File && existing.name() == name);
+        let Some((index, (old_hash, _))) =
+            previous.and_then(|index| Some((index, self.assets.shift_remove_index(index)?)))
+        else {
+            self.assets.insert(hash, asset);
+            return;
+        };
+        self.assets.shift_insert(index, hash.clone(), asset.clone());
+        if let Some(group) = self.manual_asset_groups.remove(&old_hash) {
+            self.manual_asset_groups.insert(hash, group);
+        }
+        if self.asset_primary.as_ref().is_some_and(|primary| primary.hash() == old_hash) {
+            self.set_primary_asset(asset);
+        } else if self
+            .asset_secondary
+            .as_ref()
+            .is_some_and(|secondary| secondary.hash() == old_hash)
+        {
+            self.set_secondary_asset(Some(asset));
+        }
+    }
+
+    /// Display a partially decoded file without adding it to the image list; the next
+    /// [`Self::apply_loaded_file_asset`] or asset update replaces it.
+    pub fn show_loading_preview(&mut self, path: &Path, hash: &str, image: ImageData) {
+        let path_str = path.to_string_lossy().to_string();
+        self.asset = Some(Arc::new(FileAsset::new(path_str, format!("{hash}|loading"), image)));
+    }
+
     pub fn set_file_asset_primary_by_hash_and_path(&mut self, hash: &str, path: &PathBuf) {
         self.set_asset_primary_by_hash(hash);
         // `set_asset_primary_by_hash` syncs file navigation from `asset_primary.name()`.
@@ -147,15 +386,23 @@ impl AppState {
         #[cfg(debug_assertions)]
         let _timer_path = crate::util::timer::ScopedTimer::new("Path scan");
 
+        // Images inside an archive navigate across the whole archive as one virtual directory.
+        let archive = crate::model::split_archive_entry_path(path).map(|(archive, _)| archive);
+        let nav_dir = archive.clone().or_else(|| path.parent().map(|dir| dir.to_path_buf()));
+
         // Refresh directory listing and select current index
-        if let Some(dir) = path.parent() {
-            if self.file_nav.check_is_current_dir(dir) {
+        if let Some(dir) = nav_dir {
+            if self.file_nav.check_is_current_dir(&dir) {
                 // Same directory, no need to refresh
                 return;
             }
-            self.file_nav.refresh_dir_listing_for(dir.to_path_buf());
+            self.file_nav.refresh_dir_listing_for(dir.clone());
             self.file_nav.select_index_for_path(path);
-            let _ = self.file_nav.start_dir_watcher(dir.to_path_buf());
+            if archive.is_some() {
+                self.file_nav.stop_dir_watcher();
+            } else if !self.safe_mode {
+                let _ = self.file_nav.start_dir_watcher(dir);
+            }
         } else {
             self.file_nav.clear();
         }
@@ -172,42 +419,21 @@ impl AppState {
         }
     }
 
-    pub fn load_from_clipboard(&mut self) -> Result<()> {
-        #[cfg(debug_assertions)]
-        let _timer = crate::util::timer::ScopedTimer::new("Total image load time [from clipboard]");
-
-        let image = ImageData::load_from_clipboard()
-            .or_else(|_| ImageData::load_from_url(arboard::Clipboard::new().unwrap().get_text()?.as_str()));
-
-        if image.is_err() {
-            let ctx = ClipboardContext::new().unwrap();
-
-            if !ctx.has(ContentFormat::Files) {
-                return Err(eyre!("Clipboard does not contain files"));
-            }
-            let uris = ctx.get_files().unwrap();
-            if uris.is_empty() {
-                return Err(eyre!("Clipboard does not contain image or file data"));
-            }
-
-            let paths: Vec<PathBuf> = uris.iter().map(PathBuf::from).collect();
-
-            for path in &paths {
-                if path.exists() && path.is_file() {
-                    let image = ImageData::load_from_path(path)?;
-                    let path_str = path.to_string_lossy().to_string();
-                    let hash_str = FileAsset::hash_from_path(path)?;
-                    self.set_primary_asset(Arc::new(FileAsset::new(path_str, hash_str, image)));
-                }
-            }
-        } else {
-            self.set_primary_asset(Arc::new(ClipboardAsset::new(image?)));
+    /// Exchange the primary and secondary images, e.g. the eyes of a stereo pair for crossed-eye viewing.
+    pub fn swap_comparison_assets(&mut self) {
+        if self.asset_secondary.is_none() {
+            return;
         }
+        std::mem::swap(&mut self.asset_primary, &mut self.asset_secondary);
+        self.sync_primary_file_navigation();
+        self.update_asset();
+    }
 
+    /// Show an image read from the clipboard by [`crate::model::read_clipboard`].
+    pub fn apply_clipboard_image(&mut self, image: ImageData) {
+        self.set_primary_asset(Arc::new(ClipboardAsset::new(image)));
         self.path = None;
         self.file_nav.clear();
-
-        Ok(())
     }
 
     pub fn set_asset_primary_by_hash(&mut self, hash: &str) {
@@ -230,6 +456,208 @@ impl AppState {
         self.validate_marquee_rect();
     }
 
+    /// Show an image received over the socket and evict old socket images beyond the history limits.
+    pub fn add_socket_asset(&mut self, asset: SocketAsset, history: &SocketHistorySettings) {
+        let asset = if history.replace_same_name {
+            asset
+        } else {
+            asset.with_unique_hash()
+        };
+        let hash = asset.hash().to_string();
+        let asset: SharedAsset = Arc::new(asset);
+        if history.replace_same_name {
+            let versions = self.socket_versions.entry(hash.clone()).or_default();
+            versions.push_back(asset.clone());
+            while history.max_per_name > 0 && versions.len() > history.max_per_name {
+                versions.pop_front();
+            }
+        }
+        self.socket_history.retain(|h| *h != hash);
+        self.socket_history.push_back(hash);
+        self.set_primary_asset(asset);
+        self.evict_socket_assets(history);
+    }
+
+    /// Earlier images received with the same name as the socket asset `hash`, oldest first.
+    pub fn socket_versions(&self, hash: &str) -> Option<&VecDeque<SharedAsset>> {
+        self.socket_versions.get(hash).filter(|versions| versions.len() > 1)
+    }
+
+    /// Images received over the socket, oldest first, including the earlier versions kept for each name.
+    pub fn socket_history_images(&self) -> Vec<ImageData> {
+        self.socket_history
+            .iter()
+            .flat_map(|hash| match self.socket_versions.get(hash) {
+                Some(versions) => versions.iter().map(|asset| asset.image().clone()).collect::<Vec<_>>(),
+                None => self.assets.get(hash).map(|asset| asset.image().clone()).into_iter().collect(),
+            })
+            .collect()
+    }
+
+    /// Remove the listed assets `remove` returns true for, along with their manual groups, their place in the
+    /// socket history and the hidden earlier versions of socket images.
+    pub fn remove_assets(&mut self, mut remove: impl FnMut(&str) -> bool) {
+        self.assets.retain(|hash, _| !remove(hash));
+        self.manual_asset_groups.retain(|hash, _| self.assets.contains_key(hash));
+        self.socket_history.retain(|hash| self.assets.contains_key(hash));
+        self.socket_versions.retain(|hash, _| self.assets.contains_key(hash));
+    }
+
+    /// The frame before `asset`: its earlier socket version, the previous socket image with the same name, or the
+    /// previous file in the folder.
+    pub fn previous_frame(&self, asset: &SharedAsset) -> Option<PreviousFrame> {
+        match asset.asset_type() {
+            AssetType::Socket => {
+                if let Some(versions) = self.socket_versions.get(asset.hash()) {
+                    let index = versions.iter().position(|version| Arc::ptr_eq(version, asset))?;
+                    return index.checked_sub(1).map(|index| PreviousFrame::Asset(versions[index].clone()));
+                }
+                let index = self.socket_history.iter().position(|hash| hash == asset.hash())?;
+                self.socket_history
+                    .range(..index)
+                    .rev()
+                    .filter_map(|hash| self.assets.get(hash))
+                    .find(|earlier| earlier.name() == asset.name())
+                    .map(|earlier| PreviousFrame::Asset(earlier.clone()))
+            }
+            AssetType::File => {
+                let files = &self.file_nav.files_in_dir;
+                let index = files.iter().position(|path| path.as_path() == Path::new(asset.name()))?;
+                let path = &files[index.checked_sub(1)?];
+                let name = path.to_string_lossy();
+                let listed = self
+                    .assets
+                    .values()
+                    .find(|listed| listed.asset_type() == AssetType::File && listed.name() == name);
+                Some(match listed {
+                    Some(listed) => PreviousFrame::Asset(listed.clone()),
+                    None => PreviousFrame::File(path.clone()),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// CPU pixel memory held by listed assets and hidden socket versions. Images shared by several assets are
+    /// counted once.
+    pub fn asset_memory_bytes(&self) -> usize {
+        let mut seen = HashSet::new();
+        self.assets
+            .values()
+            .chain(self.socket_versions.values().flatten())
+            .map(|asset| asset.image())
+            .filter(|image| seen.insert(image.id()))
+            .map(|image| image.spec().total_bytes())
+            .sum()
+    }
+
+    /// Memory of each listed asset, followed by a row for hidden socket versions when there are any. Images shared
+    /// by several assets are reported once.
+    pub fn memory_report(&self) -> Vec<(String, ImageMemory)> {
+        let mut seen = HashSet::new();
+        let mut rows: Vec<(String, ImageMemory)> = self
+            .assets
+            .values()
+            .filter(|asset| seen.insert(asset.image().id()))
+            .map(|asset| (asset.name().to_string(), asset.image().memory_usage()))
+            .collect();
+        let mut versions = ImageMemory::default();
+        let mut version_count = 0;
+        for version in self.socket_versions.values().flatten() {
+            if seen.insert(version.image().id()) {
+                versions += version.image().memory_usage();
+                version_count += 1;
+            }
+        }
+        if version_count > 0 {
+            rows.push((format!("{version_count} earlier socket versions"), versions));
+        }
+        if let Some(asset) = &self.asset {
+            if seen.insert(asset.image().id()) {
+                rows.push((format!("{} (displayed)", asset.name()), asset.image().memory_usage()));
+            }
+        }
+        rows
+    }
+
+    /// Release GPU textures and analysis caches of images that are not on screen.
+    pub fn free_caches(&self) {
+        let visible: HashSet<u64> = [&self.asset, &self.asset_primary, &self.asset_secondary]
+            .into_iter()
+            .flatten()
+            .map(|asset| asset.image().id())
+            .collect();
+        for asset in self.assets.values().chain(self.socket_versions.values().flatten()) {
+            if !visible.contains(&asset.image().id()) {
+                asset.image().free_caches();
+            }
+        }
+    }
+
+    fn evict_socket_assets(&mut self, history: &SocketHistorySettings) {
+        self.socket_history.retain(|hash| self.assets.contains_key(hash));
+        self.socket_versions.retain(|hash, _| self.assets.contains_key(hash));
+        let protected: Vec<&str> = [&self.asset_primary, &self.asset_secondary]
+            .into_iter()
+            .flatten()
+            .map(|asset| asset.hash())
+            .collect();
+        let mut evicted = HashSet::new();
+
+        if !history.replace_same_name && history.max_per_name > 0 {
+            let mut counts: HashMap<&str, usize> = HashMap::new();
+            for hash in self.socket_history.iter().rev() {
+                let count = counts.entry(self.assets[hash].name()).or_default();
+                *count += 1;
+                if *count > history.max_per_name && !protected.contains(&hash.as_str()) {
+                    evicted.insert(hash.clone());
+                }
+            }
+        }
+
+        if history.memory_budget_mib > 0 {
+            let budget = history.memory_budget_mib * 1024 * 1024;
+            let mut total: usize = self
+                .socket_history
+                .iter()
+                .filter(|hash| !evicted.contains(*hash))
+                .map(|hash| self.assets[hash].image().spec().total_bytes())
+                .sum();
+            let is_listed = |hash: &str, version: &SharedAsset| Arc::ptr_eq(&self.assets[hash], version);
+            total += self
+                .socket_versions
+                .iter()
+                .flat_map(|(hash, versions)| versions.iter().filter(|v| !is_listed(hash, v)))
+                .map(|version| version.image().spec().total_bytes())
+                .sum::<usize>();
+            // Hidden earlier versions go before anything in the list.
+            for (hash, versions) in self.socket_versions.iter_mut() {
+                versions.retain(|version| {
+                    if total <= budget || Arc::ptr_eq(&self.assets[hash], version) {
+                        return true;
+                    }
+                    total -= version.image().spec().total_bytes();
+                    false
+                });
+            }
+            for hash in &self.socket_history {
+                if total <= budget {
+                    break;
+                }
+                if evicted.contains(hash) || protected.contains(&hash.as_str()) {
+                    continue;
+                }
+                total -= self.assets[hash].image().spec().total_bytes();
+                evicted.insert(hash.clone());
+            }
+        }
+
+        if evicted.is_empty() {
+            return;
+        }
+        self.remove_assets(|hash| evicted.contains(hash));
+    }
+
     pub fn set_asset_secondary_by_hash(&mut self, hash: &str) {
         self.asset_secondary = self.assets.get(hash).cloned();
 
@@ -276,6 +704,77 @@ impl AppState {
         true
     }
 
+    fn asset_group_label(
+        &self,
+        hash: &str,
+        asset: &SharedAsset,
+        duplicates: &HashMap<u64, Vec<String>>,
+    ) -> Option<String> {
+        let label = match self.asset_grouping {
+            AssetGrouping::None => return None,
+            AssetGrouping::Directory if asset.asset_type() == AssetType::File => Path::new(asset.name())
+                .parent()
+                .map(|dir| dir.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            AssetGrouping::NamePrefix if asset.asset_type() == AssetType::Socket => {
+                name_prefix(asset.name()).to_string()
+            }
+            AssetGrouping::Manual => self
+                .manual_asset_groups
+                .get(hash)
+                .cloned()
+                .unwrap_or_else(|| "Ungrouped".to_string()),
+            AssetGrouping::Content => match self.content_hashes.get(asset.image()) {
+                Some(content) if duplicates.contains_key(&content) => format!("Identical {content:016x}"),
+                Some(_) => "Unique".to_string(),
+                None if self.content_hashes.is_pending(asset.image()) => "Hashing...".to_string(),
+                None => "Not hashed".to_string(),
+            },
+            _ => format!("{:?}", asset.asset_type()),
+        };
+        Some(label)
+    }
+
+    /// Image list rows split by the current grouping mode, with groups in order of first appearance.
+    pub fn asset_groups(&self) -> Vec<AssetGroup> {
+        let mut groups: Vec<AssetGroup> = Vec::new();
+        let duplicates = match self.asset_grouping {
+            AssetGrouping::Content => self.content_hashes.duplicates(&self.assets),
+            _ => HashMap::new(),
+        };
+        for (index, (hash, asset)) in self.assets.iter().enumerate() {
+            let label = self.asset_group_label(hash, asset, &duplicates);
+            let row = (index, hash.clone(), asset.clone());
+            match groups.iter_mut().find(|group| group.label == label) {
+                Some(group) => group.rows.push(row),
+                None => groups.push(AssetGroup { label, rows: vec![row] }),
+            }
+        }
+        groups
+    }
+
+    /// Manual group names in use, sorted.
+    pub fn manual_group_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.manual_asset_groups.values().cloned().collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Assets to show in the grid when grid mode is on and at least two of them are still listed.
+    pub fn grid_assets(&self) -> Option<Vec<SharedAsset>> {
+        let assets = self.grid.assets(&self.assets);
+        (self.grid.enabled && assets.len() >= 2).then_some(assets)
+    }
+
+    /// The heatmap asset to blend over the shown image, when the heatmap is shown and still listed.
+    pub fn heatmap_asset(&self) -> Option<SharedAsset> {
+        self.heatmap
+            .as_ref()
+            .filter(|_| self.is_show_heatmap)
+            .and_then(|heatmap| heatmap.asset(&self.assets))
+    }
+
     pub fn is_comparison(&self) -> bool {
         if let Some(asset) = &self.asset {
             asset.asset_type() == AssetType::Comparison
@@ -312,6 +811,7 @@ impl AppState {
                         asset_secondary.clone(),
                         self.comparison_mode,
                         self.comparison_blend,
+                        self.comparison_diff_gain,
                     );
                     self.comparison_notice = comparison_notice;
                     self.asset = Some(Arc::new(comp_asset));
@@ -367,17 +867,173 @@ impl AppState {
         } else {
             self.marquee_rect = Recti::ZERO;
         }
+        // Any edit of the rectangle itself replaces a lasso/polygon selection.
+        if self
+            .selection_mask
+            .as_ref()
+            .is_some_and(|mask| mask.bounds() != self.marquee_rect)
+        {
+            self.selection_mask = None;
+        }
+    }
+
+    /// Select the pixels inside the closed polygon `points` (image coordinates).
+    pub fn set_selection_polygon(&mut self, points: Vec<Pos2>) {
+        let mask = self.asset.as_ref().and_then(|asset| {
+            let spec = asset.image().spec();
+            SelectionMask::from_polygon(points, spec.width, spec.height)
+        });
+        self.set_selection_mask(mask);
+    }
+
+    /// Replace the selection with `mask`, or clear it when `None`.
+    pub fn set_selection_mask(&mut self, mask: Option<SelectionMask>) {
+        match mask {
+            Some(mask) => {
+                self.marquee_rect = mask.bounds();
+                self.selection_mask = Some(Arc::new(mask));
+            }
+            None => self.reset_marquee_rect(),
+        }
+    }
+
+    /// Set the white balance from the neutral patch of `image` around `pixel` and end picking mode. On error the
+    /// eyedropper stays active for another try.
+    pub fn apply_picked_neutral(&mut self, image: &ImageData, pixel: Vec2i) -> Result<()> {
+        let spec = image.spec();
+        let gains = estimate_white_balance(image, neutral_patch(pixel, spec.width, spec.height))?;
+        self.shader_params.white_balance = Some(gains);
+        self.point_picker = None;
+        Ok(())
+    }
+
+    /// Apply the pending eyedropper pick using the clicked pixel's `values` and end picking mode. The values are
+    /// mapped through the scale mode first, as the shader compares scaled values with the display range.
+    pub fn apply_picked_point(&mut self, values: &[f32]) -> Result<()> {
+        let Some(picker) = self.point_picker.take() else {
+            return Ok(());
+        };
+        if picker.target == PointPickTarget::Neutral {
+            return Ok(());
+        }
+        let params = &mut self.shader_params;
+        let is_black = picker.target == PointPickTarget::Black;
+        if picker.channel >= 0 {
+            let c = picker.channel as usize;
+            let Some(&value) = values.get(c) else {
+                return Err(eyre!("the pixel has no channel {c}"));
+            };
+            let value = params.scale_mode_channels[c].apply(value);
+            if !value.is_finite() {
+                return Err(eyre!("the picked value {value} is not finite"));
+            }
+            params.auto_minmax_channels[c] = false;
+            if is_black {
+                params.min_v_channels[c] = value;
+            } else {
+                params.max_v_channels[c] = value;
+            }
+            return Ok(());
+        }
+
+        // With a single channel displayed use its value; in color view pick the value that keeps every color
+        // channel of the clicked pixel inside the range.
+        let color_values = match usize::try_from(self.channel_index) {
+            Ok(c) => values.get(c..c + 1).unwrap_or_default(),
+            Err(_) => &values[..values.len().min(3)],
+        };
+        let candidates = color_values.iter().map(|&v| params.scale_mode.apply(v));
+        let value = if is_black {
+            candidates.fold(f32::INFINITY, f32::min)
+        } else {
+            candidates.fold(f32::NEG_INFINITY, f32::max)
+        };
+        if !value.is_finite() {
+            return Err(eyre!("the picked pixel has no finite value in the displayed channels"));
+        }
+        params.auto_minmax = false;
+        if is_black {
+            params.min_v = value;
+        } else {
+            params.max_v = value;
+        }
+        Ok(())
+    }
+
+    pub fn overlay_visibility(&self) -> OverlayVisibility {
+        OverlayVisibility {
+            background: self.is_show_background,
+            pixel_value: self.is_show_pixel_value,
+            crosshair: self.is_show_crosshair,
+            image_border: self.is_show_image_border,
+        }
+    }
+
+    pub fn set_overlay_visibility(&mut self, visibility: OverlayVisibility) {
+        self.is_show_background = visibility.background;
+        self.is_show_pixel_value = visibility.pixel_value;
+        self.is_show_crosshair = visibility.crosshair;
+        self.is_show_image_border = visibility.image_border;
+    }
+
+    /// Overlay visibility per asset type, including the live state of the current asset type.
+    pub fn overlays_by_asset_type_snapshot(&self) -> BTreeMap<String, OverlayVisibility> {
+        let mut overlays = self.overlays_by_asset_type.clone();
+        if let Some(asset_type) = self.overlay_asset_type {
+            overlays.insert(asset_type.key().to_string(), self.overlay_visibility());
+        }
+        overlays
+    }
+
+    /// When the active asset switches to a different asset type, stash the overlays for the previous
+    /// type and restore the ones last used with the new type.
+    pub fn sync_overlays_with_asset_type(&mut self) {
+        let Some(asset_type) = self.asset.as_ref().map(|asset| asset.asset_type()) else {
+            return;
+        };
+        if self.overlay_asset_type == Some(asset_type) {
+            return;
+        }
+        if let Some(prev) = self.overlay_asset_type {
+            self.overlays_by_asset_type
+                .insert(prev.key().to_string(), self.overlay_visibility());
+        }
+        if let Some(visibility) = self.overlays_by_asset_type.get(asset_type.key()).copied() {
+            self.set_overlay_visibility(visibility);
+        }
+        self.overlay_asset_type = Some(asset_type);
     }
 
     pub fn reset_marquee_rect(&mut self) {
         self.marquee_rect = Recti::ZERO;
+        self.selection_mask = None;
+        self.selection_draft.clear();
+    }
+
+    /// The next file in the directory that passes the rating filter.
+    pub fn next_file_path(&self) -> Option<PathBuf> {
+        self.file_nav
+            .step_where(true, |path| self.rating_filter.accepts(self.ratings.get(path)))
+    }
+
+    pub fn prev_file_path(&self) -> Option<PathBuf> {
+        self.file_nav
+            .step_where(false, |path| self.rating_filter.accepts(self.ratings.get(path)))
+    }
+
+    /// Path of the shown image when it is a file.
+    pub fn primary_file_path(&self) -> Option<PathBuf> {
+        self.asset_primary
+            .as_ref()
+            .filter(|asset| asset.asset_type() == AssetType::File)
+            .map(|asset| PathBuf::from(asset.name()))
     }
 
     pub fn navigate_next(&mut self) -> Result<Option<PathBuf>> {
         if let Some(asset) = &self.asset {
             match asset.asset_type() {
                 AssetType::File => {
-                    if let Some(path) = self.file_nav.navigate_next() {
+                    if let Some(path) = self.next_file_path() {
                         self.load_from_path(path.clone())?;
                         return Ok(Some(path));
                     }
@@ -392,7 +1048,7 @@ impl AppState {
         if let Some(asset) = &self.asset {
             match asset.asset_type() {
                 AssetType::File => {
-                    if let Some(path) = self.file_nav.navigate_prev() {
+                    if let Some(path) = self.prev_file_path() {
                         self.load_from_path(path.clone())?;
                         return Ok(Some(path));
                     }
@@ -466,3 +1122,188 @@ impl AppState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::gpu::ScaleMode;
+
+    #[test]
+    fn name_prefix_drops_trailing_counters() {
+        assert_eq!(name_prefix("train/iter_0100"), "train/iter");
+        assert_eq!(name_prefix("depth-12"), "depth");
+        assert_eq!(name_prefix("pred"), "pred");
+        assert_eq!(name_prefix("0042"), "0042");
+    }
+
+    /// Pick `values` as the black point of the shared range and as the white point of channel 1 in `scale_mode`.
+    fn picked_range(scale_mode: ScaleMode, values: &[f32]) -> (f32, f32) {
+        let mut state = AppState::empty();
+        state.shader_params.scale_mode = scale_mode;
+        state.shader_params.scale_mode_channels = [scale_mode; 4];
+        for (target, channel) in [(PointPickTarget::Black, -1), (PointPickTarget::White, 1)] {
+            state.point_picker = Some(PointPicker { target, channel });
+            state.apply_picked_point(values).unwrap();
+        }
+        (state.shader_params.min_v, state.shader_params.max_v_channels[1])
+    }
+
+    #[test]
+    fn picked_points_are_linear_values_in_linear_mode() {
+        assert_eq!(picked_range(ScaleMode::Linear, &[-2.0, 3.0, 0.5]), (-2.0, 3.0));
+    }
+
+    #[test]
+    fn picked_points_are_inverted_in_inverse_mode() {
+        // 1/-2 is the smallest of -0.5, 1/3 and 2.
+        let (black, white) = picked_range(ScaleMode::Inverse, &[-2.0, 3.0, 0.5]);
+        assert!((black + 0.5).abs() < 1e-6, "{black}");
+        assert!((white - 1.0 / 3.0).abs() < 1e-6, "{white}");
+    }
+
+    #[test]
+    fn picked_points_are_signed_log_values_in_log_mode() {
+        let (black, white) = picked_range(ScaleMode::Log, &[-2.0, 3.0, 0.5]);
+        assert!((black + 3.0f32.ln()).abs() < 1e-6, "{black}");
+        assert!((white - 4.0f32.ln()).abs() < 1e-6, "{white}");
+    }
+
+    #[test]
+    fn picked_points_are_magnitudes_in_absolute_mode() {
+        assert_eq!(picked_range(ScaleMode::Absolute, &[-2.0, 3.0, 0.5]), (0.5, 3.0));
+    }
+
+    #[test]
+    fn picking_a_missing_channel_fails() {
+        let mut state = AppState::empty();
+        state.point_picker = Some(PointPicker {
+            target: PointPickTarget::White,
+            channel: 3,
+        });
+        assert!(state.apply_picked_point(&[1.0, 2.0, 3.0]).is_err());
+    }
+
+    fn socket_asset(name: &str, width: i32) -> SocketAsset {
+        let spec = crate::model::ImageSpec::new(width, 1, 1, crate::model::PixelType::F32);
+        SocketAsset::new(name.to_string(), ImageData::from_f32(spec, vec![0.0; width as usize]).unwrap())
+    }
+
+    #[test]
+    fn socket_history_evicts_oldest_assets() {
+        let mut state = AppState::empty();
+        let mut history = SocketHistorySettings {
+            replace_same_name: true,
+            max_per_name: 2,
+            memory_budget_mib: 0,
+        };
+        for _ in 0..3 {
+            state.add_socket_asset(socket_asset("depth", 4), &history);
+        }
+        assert_eq!(state.assets.len(), 1);
+        let versions = state.socket_versions("depth").unwrap();
+        assert_eq!(versions.len(), 2);
+        assert!(Arc::ptr_eq(versions.back().unwrap(), &state.assets["depth"]));
+
+        history.replace_same_name = false;
+        for _ in 0..3 {
+            state.add_socket_asset(socket_asset("loss", 4), &history);
+        }
+        let names: Vec<&str> = state.assets.values().map(|asset| asset.name()).collect();
+        assert_eq!(names, ["depth", "loss", "loss"]);
+
+        // A 1 MiB budget holds the new 256K-pixel image only; the current primary is never evicted.
+        history.memory_budget_mib = 1;
+        state.add_socket_asset(socket_asset("large", 256 * 1024), &history);
+        let names: Vec<&str> = state.assets.values().map(|asset| asset.name()).collect();
+        assert_eq!(names, ["large"]);
+    }
+
+    #[test]
+    fn removed_socket_assets_leave_the_history() {
+        let mut state = AppState::empty();
+        let history = SocketHistorySettings {
+            replace_same_name: false,
+            max_per_name: 0,
+            memory_budget_mib: 0,
+        };
+        for name in ["loss", "loss"] {
+            state.add_socket_asset(socket_asset(name, 2), &history);
+        }
+        let first = state.assets.keys().next().unwrap().clone();
+        state.remove_assets(|hash| hash == first);
+        assert_eq!(state.assets.len(), 1);
+        assert_eq!(state.socket_history_images().len(), 1);
+    }
+
+    #[test]
+    fn clearing_assets_drops_hidden_socket_versions() {
+        let mut state = AppState::empty();
+        let history = SocketHistorySettings {
+            replace_same_name: true,
+            max_per_name: 0,
+            memory_budget_mib: 0,
+        };
+        for _ in 0..3 {
+            state.add_socket_asset(socket_asset("loss", 2), &history);
+        }
+        assert_eq!(state.socket_versions("loss").map(|versions| versions.len()), Some(3));
+        state.remove_assets(|_| true);
+        assert!(state.socket_versions.is_empty());
+        assert!(state.socket_history_images().is_empty());
+    }
+
+    #[test]
+    fn previous_frame_follows_socket_history_and_folder_order() {
+        let mut state = AppState::empty();
+        let mut history = SocketHistorySettings {
+            replace_same_name: false,
+            max_per_name: 0,
+            memory_budget_mib: 0,
+        };
+        for name in ["render", "normal", "render"] {
+            state.add_socket_asset(socket_asset(name, 2), &history);
+        }
+        let renders: Vec<SharedAsset> = state.assets.values().filter(|a| a.name() == "render").cloned().collect();
+        let previous = |state: &AppState, asset| match state.previous_frame(asset) {
+            Some(PreviousFrame::Asset(previous)) => Some(previous),
+            _ => None,
+        };
+        assert!(previous(&state, &renders[1]).is_some_and(|p| Arc::ptr_eq(&p, &renders[0])));
+        assert!(previous(&state, &renders[0]).is_none());
+
+        let removed = renders[0].hash().to_string();
+        state.remove_assets(|hash| hash == removed);
+        assert!(previous(&state, &renders[1]).is_none());
+
+        history.replace_same_name = true;
+        for _ in 0..2 {
+            state.add_socket_asset(socket_asset("depth", 2), &history);
+        }
+        let versions = state.socket_versions("depth").unwrap();
+        let first = versions[0].clone();
+        assert!(previous(&state, &state.assets["depth"].clone()).is_some_and(|p| Arc::ptr_eq(&p, &first)));
+
+        state.file_nav.files_in_dir = vec![PathBuf::from("/seq/0001.exr"), PathBuf::from("/seq/0002.exr")];
+        let image = socket_asset("unused", 2).image().clone();
+        let frame: SharedAsset = Arc::new(FileAsset::new("/seq/0002.exr".to_string(), "2".to_string(), image));
+        assert!(
+            matches!(state.previous_frame(&frame), Some(PreviousFrame::File(path)) if path == Path::new("/seq/0001.exr"))
+        );
+    }
+
+    #[test]
+    fn watched_file_replaces_its_previous_version() {
+        let mut state = AppState::empty();
+        let image = || socket_asset("unused", 2).image().clone();
+        state.add_file_asset(Path::new("/renders/a.exr"), "a|1".to_string(), image());
+        state.add_file_asset(Path::new("/renders/b.exr"), "b|1".to_string(), image());
+        state.set_asset_primary_by_hash("a|1");
+        state.manual_asset_groups.insert("a|1".to_string(), "beauty".to_string());
+
+        state.add_file_asset(Path::new("/renders/a.exr"), "a|2".to_string(), image());
+        let hashes: Vec<&str> = state.assets.keys().map(String::as_str).collect();
+        assert_eq!(hashes, ["a|2", "b|1"]);
+        assert_eq!(state.asset_primary.as_ref().map(|asset| asset.hash()), Some("a|2"));
+        assert_eq!(state.manual_asset_groups.get("a|2").map(String::as_str), Some("beauty"));
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    num::NonZeroUsize,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock, Mutex},
+    time::SystemTime,
+};
+
+use color_eyre::eyre::{eyre, Result};
+use lru::LruCache;
+
+/// Tar archives whose entry index is kept, most recently used first.
+const TAR_INDEX_CAPACITY: NonZeroUsize = NonZeroUsize::new(4).unwrap();
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ArchiveKind {
+    Zip,
+    Tar,
+}
+
+fn archive_kind(path: &Path) -> Option<ArchiveKind> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "zip" => Some(ArchiveKind::Zip),
+        "tar" => Some(ArchiveKind::Tar),
+        _ => None,
+    }
+}
+
+#[inline]
+pub fn is_archive_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .map(|s| crate::supported_image::is_archive_extension(&s.to_ascii_lowercase()))
+        .unwrap_or(false)
+}
+
+/// Images that can be decoded from memory. HEIF is decoded by libheif from a file path, so its entries are left
+/// out.
+fn is_supported_entry(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_ascii_lowercase())
+        .is_some_and(|ext| {
+            crate::supported_image::is_supported_image_extension(&ext)
+                && !crate::supported_image::is_heif_extension(&ext)
+        })
+}
+
+/// Split a virtual path such as `images.zip/sub/a.png` into the archive file and the entry name inside it.
+/// Entry names always use `/` separators, matching how zip and tar store them.
+pub fn split_archive_entry_path(path: &Path) -> Option<(PathBuf, String)> {
+    let archive = path
+        .ancestors()
+        .skip(1)
+        .find(|ancestor| is_archive_path(ancestor) && ancestor.is_file())?;
+    let entry = path
+        .strip_prefix(archive)
+        .ok()?
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    if entry.is_empty() {
+        return None;
+    }
+    Some((archive.to_path_buf(), entry))
+}
+
+/// When `path` is an archive, resolve it to the virtual path of its first image so it can be opened like a file.
+pub fn resolve_archive_path(path: &Path) -> Result<PathBuf> {
+    if !is_archive_path(path) || !path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+    let entries = list_archive_images(path)?;
+    let first = entries
+        .first()
+        .ok_or_else(|| eyre!("Archive contains no supported images: {}", path.display()))?;
+    Ok(path.join(first))
+}
+
+/// List supported image entries in the archive, sorted case-insensitively.
+pub fn list_archive_images(archive: &Path) -> Result<Vec<String>> {
+    #[cfg(debug_assertions)]
+    let _timer = crate::util::timer::ScopedTimer::new("Archive listing");
+
+    let kind = archive_kind(archive).ok_or_else(|| eyre!("Unsupported archive: {}", archive.display()))?;
+    let mut entries = match kind {
+        ArchiveKind::Zip => list_zip_images(BufReader::new(File::open(archive)?))?,
+        ArchiveKind::Tar => tar_index(archive)?.keys().cloned().collect(),
+    };
+    entries.sort_by_key(|name| name.to_ascii_lowercase());
+    Ok(entries)
+}
+
+/// Read a single entry from the archive into memory. Only the requested entry is decompressed.
+pub fn read_archive_entry(archive: &Path, entry: &str) -> Result<Vec<u8>> {
+    let kind = archive_kind(archive).ok_or_else(|| eyre!("Unsupported archive: {}", archive.display()))?;
+    // A single encoded image never needs more than its decoded size as the viewer stores it.
+    let limit = crate::model::decode_limits().max_memory_bytes();
+    match kind {
+        ArchiveKind::Zip => read_zip_entry(BufReader::new(File::open(archive)?), entry, limit),
+        ArchiveKind::Tar => {
+            let range = tar_index(archive)?
+                .get(entry)
+                .cloned()
+                .ok_or_else(|| eyre!("Archive entry not found: {entry}"))?;
+            check_entry_size(entry, range.end - range.start, limit)?;
+            read_tar_range(File::open(archive)?, range)
+        }
+    }
+}
+
+fn list_zip_images<R: Read + Seek>(reader: R) -> Result<Vec<String>> {
+    let mut zip = zip::ZipArchive::new(reader)?;
+    let mut entries = Vec::new();
+    for index in 0..zip.len() {
+        let file = zip.by_index_raw(index)?;
+        let name = file.name()?;
+        if file.is_file() && is_supported_entry(&name) {
+            entries.push(name.into_owned());
+        }
+    }
+    Ok(entries)
+}
+
+/// Reject entries declaring more than `limit` bytes before reserving memory for them.
+fn check_entry_size(entry: &str, size: u64, limit: u64) -> Result<()> {
+    if size > limit {
+        return Err(eyre!(
+            "Archive entry {entry} of {size} bytes exceeds the decode memory limit of {limit} bytes"
+        ));
+    }
+    Ok(())
+}
+
+/// Read `entry` into memory, never reading more than `limit` bytes whatever size the archive declares.
+fn read_zip_entry<R: Read + Seek>(reader: R, entry: &str, limit: u64) -> Result<Vec<u8>> {
+    let mut zip = zip::ZipArchive::new(reader)?;
+    let file = zip.by_name(entry)?;
+    check_entry_size(entry, file.size(), limit)?;
+    let mut bytes = Vec::with_capacity(file.size() as usize);
+    file.take(limit).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Byte ranges of the supported images in a tar archive. Tar has no central directory, so the archive is
+/// scanned once when it is opened and entries are read straight from their offsets afterwards.
+type TarIndex = HashMap<String, Range<u64>>;
+
+/// Path, size and modification time of an indexed archive, so a rewritten archive is indexed again.
+type TarIndexKey = (PathBuf, u64, Option<SystemTime>);
+
+static TAR_INDEXES: LazyLock<Mutex<LruCache<TarIndexKey, Arc<TarIndex>>>> =
+    LazyLock::new(|| Mutex::new(LruCache::new(TAR_INDEX_CAPACITY)));
+
+fn tar_index(archive: &Path) -> Result<Arc<TarIndex>> {
+    let metadata = std::fs::metadata(archive)?;
+    let key = (archive.to_path_buf(), metadata.len(), metadata.modified().ok());
+    if let Some(index) = TAR_INDEXES.lock().unwrap().get(&key) {
+        return Ok(index.clone());
+    }
+
+    let index = Arc::new(index_tar(BufReader::new(File::open(archive)?))?);
+    TAR_INDEXES.lock().unwrap().put(key, index.clone());
+    Ok(index)
+}
+
+fn index_tar<R: Read>(reader: R) -> Result<TarIndex> {
+    let mut tar = tar::Archive::new(reader);
+    let mut entries = HashMap::new();
+    for file in tar.entries()? {
+        let file = file?;
+        if !file.header().entry_type().is_file() {
+            continue;
+        }
+        let name = file.path()?.to_string_lossy().replace('\\', "/");
+        if is_supported_entry(&name) {
+            let start = file.raw_file_position();
+            entries.insert(name, start..start + file.size());
+        }
+    }
+    Ok(entries)
+}
+
+fn read_tar_range<R: Read + Seek>(mut reader: R, range: Range<u64>) -> Result<Vec<u8>> {
+    reader.seek(SeekFrom::Start(range.start))?;
+    let mut bytes = vec![0; (range.end - range.start) as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use super::*;
+
+    fn build_zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for (name, bytes) in entries {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(bytes).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn build_tar(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, bytes) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(bytes.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, *bytes).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn lists_only_supported_images_and_reads_entries() {
+        let entries: &[(&str, &[u8])] = &[
+            ("b.png", b"png"),
+            ("notes.txt", b"txt"),
+            ("photo.heic", b"heic"),
+            ("sub/a.EXR", b"exr"),
+        ];
+        for kind in [ArchiveKind::Zip, ArchiveKind::Tar] {
+            let (listed, read) = match kind {
+                ArchiveKind::Zip => {
+                    let bytes = build_zip(entries);
+                    (
+                        list_zip_images(Cursor::new(&bytes)).unwrap(),
+                        read_zip_entry(Cursor::new(&bytes), "sub/a.EXR", 3).unwrap(),
+                    )
+                }
+                ArchiveKind::Tar => {
+                    let bytes = build_tar(entries);
+                    let index = index_tar(Cursor::new(&bytes)).unwrap();
+                    let mut listed: Vec<String> = index.keys().cloned().collect();
+                    listed.sort();
+                    let read = read_tar_range(Cursor::new(&bytes), index["sub/a.EXR"].clone()).unwrap();
+                    (listed, read)
+                }
+            };
+            assert_eq!(listed, vec!["b.png".to_string(), "sub/a.EXR".to_string()]);
+            assert_eq!(read, b"exr");
+        }
+    }
+
+    #[test]
+    fn entries_beyond_the_limit_are_rejected_before_reading() {
+        let bytes = build_zip(&[("big.png", &[0; 64])]);
+        assert_eq!(read_zip_entry(Cursor::new(&bytes), "big.png", 64).unwrap().len(), 64);
+        let err = read_zip_entry(Cursor::new(&bytes), "big.png", 63).unwrap_err();
+        assert!(err.to_string().contains("decode memory limit"), "{err}");
+    }
+
+    #[test]
+    fn tar_indexes_follow_archive_changes_and_stay_bounded() {
+        let dir = std::env::temp_dir().join(format!("edolview-tar-index-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("images.tar");
+        std::fs::write(&archive, build_tar(&[("a.png", b"a")])).unwrap();
+        assert_eq!(list_archive_images(&archive).unwrap(), ["a.png"]);
+        std::fs::write(&archive, build_tar(&[("a.png", b"a"), ("b.png", b"bb")])).unwrap();
+        assert_eq!(list_archive_images(&archive).unwrap(), ["a.png", "b.png"]);
+        assert_eq!(read_archive_entry(&archive, "b.png").unwrap(), b"bb");
+
+        for index in 0..TAR_INDEX_CAPACITY.get() + 2 {
+            let other = dir.join(format!("other-{index}.tar"));
+            std::fs::write(&other, build_tar(&[("c.png", b"c")])).unwrap();
+            list_archive_images(&other).unwrap();
+        }
+        assert!(TAR_INDEXES.lock().unwrap().len() <= TAR_INDEX_CAPACITY.get());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn archive_entry_path_requires_an_existing_archive_file() {
+        assert_eq!(split_archive_entry_path(Path::new("missing.zip/a.png")), None);
+        assert!(is_archive_path(Path::new("images.ZIP")));
+        assert!(!is_archive_path(Path::new("image.png")));
+    }
+}
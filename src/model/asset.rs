@@ -5,13 +5,28 @@ use crate::model::{Image, ImageData};
 
 pub type SharedAsset = Arc<dyn Asset<ImageData>>;
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum AssetType {
     File,
     Clipboard,
     Socket,
     Url,
     Comparison,
+    Filtered,
+}
+
+impl AssetType {
+    /// Stable key used when persisting per-asset-type settings.
+    pub fn key(self) -> &'static str {
+        match self {
+            AssetType::File => "file",
+            AssetType::Clipboard => "clipboard",
+            AssetType::Socket => "socket",
+            AssetType::Url => "url",
+            AssetType::Comparison => "comparison",
+            AssetType::Filtered => "filtered",
+        }
+    }
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
@@ -19,6 +34,8 @@ pub enum ComparisonMode {
     Diff,
     Blend,
     Split,
+    /// Red-cyan anaglyph of a stereo pair: red from the primary (left eye), green and blue from the secondary.
+    Anaglyph,
 }
 
 pub trait Asset<T: Image> {
@@ -26,6 +43,11 @@ pub trait Asset<T: Image> {
     fn image(&self) -> &T;
     fn hash(&self) -> &str;
     fn asset_type(&self) -> AssetType;
+
+    /// Key/value pairs attached by the sender of the asset or read from the file header, in display order.
+    fn metadata(&self) -> &[(String, String)] {
+        &[]
+    }
 }
 
 pub struct FileAsset {
@@ -40,7 +62,11 @@ impl FileAsset {
     }
 
     pub fn hash_from_path(path: &PathBuf) -> Result<String> {
-        let meta = std::fs::metadata(path)?;
+        // Archive entries share the modification state of the archive that contains them.
+        let meta = match crate::model::split_archive_entry_path(path) {
+            Some((archive, _)) => std::fs::metadata(archive)?,
+            None => std::fs::metadata(path)?,
+        };
         let modified = meta.modified()?;
         let duration = modified.duration_since(std::time::UNIX_EPOCH).unwrap();
 
@@ -68,6 +94,10 @@ impl Asset<ImageData> for FileAsset {
     fn asset_type(&self) -> AssetType {
         AssetType::File
     }
+
+    fn metadata(&self) -> &[(String, String)] {
+        self.image.metadata()
+    }
 }
 
 pub struct ClipboardAsset {
@@ -109,12 +139,34 @@ impl Asset<ImageData> for ClipboardAsset {
 
 pub struct SocketAsset {
     name: String,
+    hash: String,
     image: ImageData,
+    metadata: Vec<(String, String)>,
 }
 
+static SOCKET_COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
 impl SocketAsset {
+    /// The name doubles as the hash, so a new image with the same name replaces the previous one.
     pub fn new(name: String, image: ImageData) -> Self {
-        Self { name, image }
+        Self {
+            hash: name.clone(),
+            name,
+            image,
+            metadata: Vec::new(),
+        }
+    }
+
+    /// Give the asset its own hash so it is kept next to earlier images with the same name.
+    pub fn with_unique_hash(mut self) -> Self {
+        let index = SOCKET_COUNTER.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        self.hash = format!("{}#{index}", self.name);
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata: Vec<(String, String)>) -> Self {
+        self.metadata = metadata;
+        self
     }
 }
 
@@ -128,12 +180,16 @@ impl Asset<ImageData> for SocketAsset {
     }
 
     fn hash(&self) -> &str {
-        &self.name
+        &self.hash
     }
 
     fn asset_type(&self) -> AssetType {
         AssetType::Socket
     }
+
+    fn metadata(&self) -> &[(String, String)] {
+        &self.metadata
+    }
 }
 
 pub struct UrlAsset {
@@ -163,6 +219,45 @@ impl Asset<ImageData> for UrlAsset {
     fn asset_type(&self) -> AssetType {
         AssetType::Url
     }
+
+    fn metadata(&self) -> &[(String, String)] {
+        self.image.metadata()
+    }
+}
+
+/// Image produced by running a filter on another asset.
+pub struct FilteredAsset {
+    name: String,
+    image: ImageData,
+}
+
+static FILTERED_COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+impl FilteredAsset {
+    pub fn new(name: &str, image: ImageData) -> Self {
+        Self {
+            name: format!("{name} {}", FILTERED_COUNTER.fetch_add(1, std::sync::atomic::Ordering::SeqCst)),
+            image,
+        }
+    }
+}
+
+impl Asset<ImageData> for FilteredAsset {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn image(&self) -> &ImageData {
+        &self.image
+    }
+
+    fn hash(&self) -> &str {
+        &self.name
+    }
+
+    fn asset_type(&self) -> AssetType {
+        AssetType::Filtered
+    }
 }
 
 pub struct ComparisonAsset {
@@ -264,6 +359,7 @@ impl ComparisonAsset {
         asset_secondary: SharedAsset,
         mode: ComparisonMode,
         blend_alpha: f32,
+        diff_gain: f32,
     ) -> (Self, Option<String>) {
         let name = format!(
             "Comparison ({:?}): {} vs {}",
@@ -277,7 +373,12 @@ impl ComparisonAsset {
         let spec1 = img1.spec();
         let spec2 = img2.spec();
 
-        let strategy = ChannelComparisonStrategy::from_channels(spec1.channels, spec2.channels);
+        // Anaglyphs take red from one image and green and blue from the other, so any channel counts combine.
+        let strategy = if mode == ComparisonMode::Anaglyph {
+            ChannelComparisonStrategy::Match
+        } else {
+            ChannelComparisonStrategy::from_channels(spec1.channels, spec2.channels)
+        };
         let comparison_notices = build_comparison_notices(strategy, &spec1, &spec2);
 
         if mode == ComparisonMode::Split {
@@ -290,7 +391,12 @@ impl ComparisonAsset {
             );
         }
 
-        let Some(output_channels) = strategy.output_channels(spec1.channels, spec2.channels) else {
+        let output_channels = if mode == ComparisonMode::Anaglyph {
+            Some(3)
+        } else {
+            strategy.output_channels(spec1.channels, spec2.channels)
+        };
+        let Some(output_channels) = output_channels else {
             return (
                 Self {
                     name,
@@ -306,6 +412,7 @@ impl ComparisonAsset {
             crate::model::ImageSpec::new(spec1.width, spec1.height, output_channels, spec1.dtype),
             mode,
             blend_alpha,
+            diff_gain,
             strategy.gpu_code(),
         );
 
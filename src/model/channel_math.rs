@@ -0,0 +1,265 @@
+//! Single-channel images derived from per-pixel channel expressions such as `r - g` or `max()`, for hunting
+//! chroma artifacts and demosaic errors.
+
+use color_eyre::eyre::{eyre, Result};
+
+use crate::model::{Image, ImageData, ImageSpec, PixelType};
+
+/// Expressions offered as quick views, with their menu labels.
+pub const CHANNEL_MATH_PRESETS: [(&str, &str); 7] = [
+    ("R \u{2212} G", "r - g"),
+    ("B \u{2212} G", "b - g"),
+    ("R / G", "r / g"),
+    ("B / G", "b / g"),
+    ("Max of channels", "max()"),
+    ("Min of channels", "min()"),
+    ("Chroma spread", "max() - min()"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Function {
+    Min,
+    Max,
+    Mean,
+    Abs,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Channel(usize),
+    Const(f32),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    /// A function of its arguments; `min`, `max` and `mean` without arguments use up to the first three channels,
+    /// leaving out the alpha of RGBA images.
+    Call(Function, Vec<Expr>),
+}
+
+/// A parsed channel expression. Channels are `r`, `g`, `b`, `a` or `c0`, `c1`, ...; operators are `+ - * /`
+/// and the functions are `min`, `max`, `mean` and `abs`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelExpr {
+    expr: Expr,
+}
+
+impl ChannelExpr {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            pos: 0,
+        };
+        let expr = parser.sum()?;
+        parser.skip_whitespace();
+        if parser.pos < parser.chars.len() {
+            return Err(eyre!(
+                "Unexpected '{}' at position {}",
+                parser.chars[parser.pos],
+                parser.pos + 1
+            ));
+        }
+        Ok(Self { expr })
+    }
+
+    /// Evaluate the expression for every pixel of `image` into a single-channel float image. Values stay in the
+    /// normalized range the viewer stores, so `r - g` of an 8-bit image lies in `[-1, 1]`.
+    pub fn apply(&self, image: &ImageData) -> Result<ImageData> {
+        let spec = image.spec();
+        let channels = spec.channels.max(0) as usize;
+        if let Some(channel) = self.expr.max_channel().filter(|&channel| channel >= channels) {
+            return Err(eyre!("Channel {channel} is not in this {channels}-channel image"));
+        }
+        let data = image
+            .data()
+            .ok_or_else(|| eyre!("CPU pixel data is unavailable for this image"))?;
+        let pixels = data.chunks_exact(channels.max(1)).map(|pixel| self.expr.eval(pixel)).collect();
+        ImageData::from_f32(ImageSpec::new(spec.width, spec.height, 1, PixelType::F32), pixels)
+    }
+}
+
+impl Expr {
+    fn eval(&self, pixel: &[f32]) -> f32 {
+        match self {
+            Self::Channel(channel) => pixel[*channel],
+            Self::Const(value) => *value,
+            Self::Neg(expr) => -expr.eval(pixel),
+            Self::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.eval(pixel), rhs.eval(pixel));
+                match op {
+                    '+' => lhs + rhs,
+                    '-' => lhs - rhs,
+                    '*' => lhs * rhs,
+                    _ => lhs / rhs,
+                }
+            }
+            Self::Call(function, args) => {
+                let values: Vec<f32> = if args.is_empty() {
+                    pixel[..pixel.len().min(3)].to_vec()
+                } else {
+                    args.iter().map(|arg| arg.eval(pixel)).collect()
+                };
+                match function {
+                    Function::Min => values.into_iter().fold(f32::INFINITY, f32::min),
+                    Function::Max => values.into_iter().fold(f32::NEG_INFINITY, f32::max),
+                    Function::Mean => values.iter().sum::<f32>() / values.len().max(1) as f32,
+                    Function::Abs => values[0].abs(),
+                }
+            }
+        }
+    }
+
+    fn max_channel(&self) -> Option<usize> {
+        match self {
+            Self::Channel(channel) => Some(*channel),
+            Self::Const(_) => None,
+            Self::Neg(expr) => expr.max_channel(),
+            Self::Binary(_, lhs, rhs) => lhs.max_channel().max(rhs.max_channel()),
+            Self::Call(_, args) => args.iter().filter_map(Expr::max_channel).max(),
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    /// The next non-whitespace character, consumed when it is one of `options`.
+    fn eat(&mut self, options: &[char]) -> Option<char> {
+        self.skip_whitespace();
+        let c = *self.chars.get(self.pos)?;
+        options.contains(&c).then(|| {
+            self.pos += 1;
+            c
+        })
+    }
+
+    fn sum(&mut self) -> Result<Expr> {
+        let mut expr = self.product()?;
+        while let Some(op) = self.eat(&['+', '-', '\u{2212}']) {
+            let op = if op == '+' { '+' } else { '-' };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.product()?));
+        }
+        Ok(expr)
+    }
+
+    fn product(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        while let Some(op) = self.eat(&['*', '/']) {
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat(&['-', '\u{2212}']).is_some() {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Expr> {
+        if self.eat(&['(']).is_some() {
+            let expr = self.sum()?;
+            self.expect(')')?;
+            return Ok(expr);
+        }
+        self.skip_whitespace();
+        let start = self.pos;
+        let Some(&first) = self.chars.get(start) else {
+            return Err(eyre!("Unexpected end of expression"));
+        };
+        if first.is_ascii_digit() || first == '.' {
+            while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_digit() || *c == '.') {
+                self.pos += 1;
+            }
+            let number: String = self.chars[start..self.pos].iter().collect();
+            return number.parse().map(Expr::Const).map_err(|_| eyre!("Invalid number '{number}'"));
+        }
+        if !first.is_ascii_alphabetic() {
+            return Err(eyre!("Unexpected '{first}' at position {}", start + 1));
+        }
+        while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_alphanumeric()) {
+            self.pos += 1;
+        }
+        let name: String = self.chars[start..self.pos].iter().collect::<String>().to_ascii_lowercase();
+        let function = match name.as_str() {
+            "min" => Some(Function::Min),
+            "max" => Some(Function::Max),
+            "mean" => Some(Function::Mean),
+            "abs" => Some(Function::Abs),
+            _ => None,
+        };
+        if let Some(function) = function {
+            return self.call(function, &name);
+        }
+        match name.as_str() {
+            "r" => Ok(Expr::Channel(0)),
+            "g" => Ok(Expr::Channel(1)),
+            "b" => Ok(Expr::Channel(2)),
+            "a" => Ok(Expr::Channel(3)),
+            _ => name
+                .strip_prefix('c')
+                .and_then(|index| index.parse().ok())
+                .map(Expr::Channel)
+                .ok_or_else(|| eyre!("Unknown name '{name}'")),
+        }
+    }
+
+    fn call(&mut self, function: Function, name: &str) -> Result<Expr> {
+        self.expect('(')?;
+        let mut args = Vec::new();
+        if self.eat(&[')']).is_none() {
+            loop {
+                args.push(self.sum()?);
+                if self.eat(&[',']).is_none() {
+                    self.expect(')')?;
+                    break;
+                }
+            }
+        }
+        if function == Function::Abs && args.len() != 1 {
+            return Err(eyre!("{name}() takes one argument"));
+        }
+        Ok(Expr::Call(function, args))
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        self.eat(&[c])
+            .map(|_| ())
+            .ok_or_else(|| eyre!("Expected '{c}' at position {}", self.pos + 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_channel_expressions() {
+        let spec = ImageSpec::new(2, 1, 3, PixelType::F32);
+        let image = ImageData::from_f32(spec, vec![0.5, 0.25, 1.0, 0.25, 0.5, 0.0]).unwrap();
+        let eval = |text: &str| {
+            let derived = ChannelExpr::parse(text).unwrap().apply(&image).unwrap();
+            assert_eq!(derived.spec().channels, 1);
+            derived.data().unwrap().to_vec()
+        };
+        assert_eq!(eval("r - g"), [0.25, -0.25]);
+        assert_eq!(eval("R / G"), [2.0, 0.5]);
+        assert_eq!(eval("max() - min()"), [0.75, 0.5]);
+        assert_eq!(eval("-(c2 * 2) + abs(r \u{2212} 1)"), [-1.5, 0.75]);
+        assert_eq!(eval("mean(r, b)"), [0.75, 0.125]);
+
+        assert!(ChannelExpr::parse("r +").is_err());
+        assert!(ChannelExpr::parse("max(r, g").is_err());
+        assert!(ChannelExpr::parse("q").is_err());
+        assert!(ChannelExpr::parse("abs()").is_err());
+        assert!(ChannelExpr::parse("a - r").unwrap().apply(&image).is_err());
+    }
+}
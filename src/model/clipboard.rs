@@ -0,0 +1,370 @@
+//! Clipboard reading, tried from the richest representation down: encoded PNG (keeps 16-bit samples and
+//! alpha), a Windows DIBv5 bitmap, the platform's RGBA image, copied files, and finally text holding a URL or path.
+//! Writing mirrors it: 16-bit images are also placed as a 16-bit PNG next to the rendered 8-bit bitmap.
+
+use std::{io::Cursor, path::PathBuf};
+
+use clipboard_rs::{Clipboard, ClipboardContext, ContentFormat};
+use color_eyre::eyre::{eyre, Result};
+
+use super::{image_io::DecodedImage, Image, ImageData, PixelType, Recti};
+
+pub enum ClipboardPayload {
+    Image(ImageData),
+    /// Copied files, or a path pasted as text; loaded like dropped files.
+    Files(Vec<PathBuf>),
+}
+
+#[cfg(target_os = "windows")]
+const PNG_FORMAT: &str = "PNG";
+#[cfg(target_os = "macos")]
+const PNG_FORMAT: &str = "public.png";
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const PNG_FORMAT: &str = "image/png";
+
+/// Read the clipboard. Blocks on URL downloads and decoding, so call it off the UI thread.
+pub fn read_clipboard() -> Result<ClipboardPayload> {
+    #[cfg(debug_assertions)]
+    let _timer = crate::util::timer::ScopedTimer::new("Clipboard read");
+
+    let ctx = ClipboardContext::new().map_err(|e| eyre!("Failed to open clipboard: {e}"))?;
+    let formats = ctx.available_formats().unwrap_or_default();
+
+    if formats.iter().any(|format| format == PNG_FORMAT) {
+        if let Ok(png) = ctx.get_buffer(PNG_FORMAT) {
+            match ImageData::from_bytes(&png) {
+                Ok(image) => return Ok(ClipboardPayload::Image(image)),
+                Err(e) => eprintln!("Clipboard PNG could not be decoded: {e}"),
+            }
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    if let Some(dib) = windows_dibv5() {
+        match decode_dib(&dib) {
+            Ok(image) => return Ok(ClipboardPayload::Image(image)),
+            Err(e) => eprintln!("Clipboard DIBv5 could not be decoded: {e}"),
+        }
+    }
+
+    if let Ok(image) = arboard_image() {
+        return Ok(ClipboardPayload::Image(image));
+    }
+
+    if ctx.has(ContentFormat::Files) {
+        let files: Vec<PathBuf> = ctx.get_files().unwrap_or_default().iter().map(PathBuf::from).collect();
+        let files: Vec<PathBuf> = files.into_iter().filter(|path| path.is_file()).collect();
+        if !files.is_empty() {
+            return Ok(ClipboardPayload::Files(files));
+        }
+    }
+
+    let text = ctx.get_text().unwrap_or_default();
+    let text = text.trim();
+    if text.starts_with("http://") || text.starts_with("https://") {
+        return ImageData::load_from_url(text).map(ClipboardPayload::Image);
+    }
+    let path = PathBuf::from(text.strip_prefix("file://").unwrap_or(text));
+    if !text.is_empty() && path.is_file() {
+        return Ok(ClipboardPayload::Files(vec![path]));
+    }
+
+    Err(eyre!("Clipboard does not contain an image, files or an image URL"))
+}
+
+fn arboard_image() -> Result<ImageData> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| eyre!("Failed to open clipboard: {e}"))?;
+    let image_data = clipboard
+        .get_image()
+        .map_err(|e| eyre!("Failed to get image from clipboard: {e}"))?;
+
+    let width = image_data.width as i32;
+    let height = image_data.height as i32;
+    let bytes = std::borrow::Cow::into_owned(image_data.bytes);
+    if width <= 0 || height <= 0 {
+        return Err(eyre!("Invalid clipboard image dimensions"));
+    }
+    let channels = (bytes.len() / (width as usize) / (height as usize)) as i32;
+    if channels <= 0 || channels > 4 {
+        return Err(eyre!("Invalid clipboard image dimensions or channels"));
+    }
+
+    ImageData::from_decoded(DecodedImage::new(
+        width as u32,
+        height as u32,
+        channels,
+        PixelType::U8,
+        super::image_io::DecodedPixels::U8(bytes),
+    )?)
+}
+
+/// Encode the stored samples of `rect` in a 16-bit image as a 16-bit PNG, bypassing display adjustments.
+pub fn encode_png16(image: &ImageData, rect: Recti) -> Result<Vec<u8>> {
+    let spec = image.spec();
+    if spec.dtype != PixelType::U16 {
+        return Err(eyre!("{} images have no 16-bit representation", spec.dtype.name()));
+    }
+    let (x0, y0, width, height) = rect.xywh();
+    if width <= 0 || height <= 0 || x0 < 0 || y0 < 0 || x0 + width > spec.width || y0 + height > spec.height {
+        return Err(eyre!("Invalid copy region {rect} for a {}x{} image", spec.width, spec.height));
+    }
+
+    let mut samples = Vec::with_capacity(width as usize * height as usize * spec.channels as usize);
+    for y in y0..y0 + height {
+        for x in x0..x0 + width {
+            let pixel = image.get_pixel_at(x, y)?;
+            samples.extend(
+                pixel
+                    .iter()
+                    .map(|&v| (v * u16::MAX as f32).round().clamp(0.0, u16::MAX as f32) as u16),
+            );
+        }
+    }
+    let (width, height) = (width as u32, height as u32);
+    let invalid = || eyre!("16-bit sample count does not match the copy region");
+    let image = match spec.channels {
+        1 => {
+            image::DynamicImage::ImageLuma16(image::ImageBuffer::from_raw(width, height, samples).ok_or_else(invalid)?)
+        }
+        2 => {
+            image::DynamicImage::ImageLumaA16(image::ImageBuffer::from_raw(width, height, samples).ok_or_else(invalid)?)
+        }
+        3 => image::DynamicImage::ImageRgb16(image::ImageBuffer::from_raw(width, height, samples).ok_or_else(invalid)?),
+        _ => {
+            image::DynamicImage::ImageRgba16(image::ImageBuffer::from_raw(width, height, samples).ok_or_else(invalid)?)
+        }
+    };
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)?;
+    Ok(png)
+}
+
+/// Place rendered RGBA8 pixels on the clipboard, with `png` as an extra full-precision representation when given.
+/// Windows receives a DIBv5 (keeps alpha) and the registered `PNG` format; other platforms, which exchange
+/// images as PNG anyway, receive only the PNG.
+pub fn write_clipboard_image(width: usize, height: usize, rgba: Vec<u8>, png: Option<Vec<u8>>) -> Result<()> {
+    let Some(png) = png else {
+        let mut clipboard = arboard::Clipboard::new().map_err(|e| eyre!("Failed to open clipboard: {e}"))?;
+        return clipboard
+            .set_image(arboard::ImageData {
+                width,
+                height,
+                bytes: std::borrow::Cow::Owned(rgba),
+            })
+            .map_err(|e| eyre!("Failed to set clipboard image: {e}"));
+    };
+
+    #[cfg(target_os = "windows")]
+    {
+        set_windows_clipboard(&[(None, encode_dibv5(width, height, &rgba)), (Some(PNG_FORMAT), png)])
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let ctx = ClipboardContext::new().map_err(|e| eyre!("Failed to open clipboard: {e}"))?;
+        ctx.set(vec![clipboard_rs::ClipboardContent::Other(PNG_FORMAT.to_string(), png)])
+            .map_err(|e| eyre!("Failed to set clipboard PNG: {e}"))
+    }
+}
+
+/// Place files on the clipboard so they can be pasted into file managers, chats and documents.
+pub fn write_clipboard_files(paths: &[PathBuf]) -> Result<()> {
+    let ctx = ClipboardContext::new().map_err(|e| eyre!("Failed to open clipboard: {e}"))?;
+    let files = paths.iter().map(|path| path.to_string_lossy().to_string()).collect();
+    ctx.set_files(files).map_err(|e| eyre!("Failed to set clipboard files: {e}"))
+}
+
+/// Pack top-down RGBA8 pixels as a `CF_DIBV5` block: a bottom-up 32-bit BI_BITFIELDS bitmap with an alpha mask.
+#[cfg(any(target_os = "windows", test))]
+fn encode_dibv5(width: usize, height: usize, rgba: &[u8]) -> Vec<u8> {
+    const HEADER_SIZE: usize = 124;
+    const MASKS: [u32; 4] = [0x00ff_0000, 0x0000_ff00, 0x0000_00ff, 0xff00_0000];
+    let mut dib = Vec::with_capacity(HEADER_SIZE + 12 + rgba.len());
+    dib.extend_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+    dib.extend_from_slice(&(width as i32).to_le_bytes());
+    dib.extend_from_slice(&(height as i32).to_le_bytes());
+    dib.extend_from_slice(&1_u16.to_le_bytes()); // planes
+    dib.extend_from_slice(&32_u16.to_le_bytes()); // bits per pixel
+    dib.extend_from_slice(&3_u32.to_le_bytes()); // BI_BITFIELDS
+    dib.extend_from_slice(&(rgba.len() as u32).to_le_bytes());
+    dib.extend_from_slice(&[0; 16]); // resolution and palette counts
+    for mask in MASKS {
+        dib.extend_from_slice(&mask.to_le_bytes());
+    }
+    dib.extend_from_slice(b"BGRs"); // LCS_sRGB, stored little-endian
+    dib.resize(HEADER_SIZE, 0);
+    // Readers expect the color masks repeated after the header for BI_BITFIELDS.
+    for mask in &MASKS[..3] {
+        dib.extend_from_slice(&mask.to_le_bytes());
+    }
+    for row in rgba.chunks_exact(width * 4).rev() {
+        for pixel in row.chunks_exact(4) {
+            dib.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+        }
+    }
+    dib
+}
+
+/// Decode a packed DIB (bitmap info header followed by pixels), as stored in `CF_DIB` and `CF_DIBV5`.
+pub(crate) fn decode_dib(dib: &[u8]) -> Result<ImageData> {
+    let decoder = image::codecs::bmp::BmpDecoder::new_without_file_header(Cursor::new(dib))?;
+    let image = image::DynamicImage::from_decoder(decoder)?;
+    let (width, height) = (image.width(), image.height());
+    let (channels, bytes) = if image.color().has_alpha() {
+        (4, image.into_rgba8().into_raw())
+    } else {
+        (3, image.into_rgb8().into_raw())
+    };
+    ImageData::from_decoded(DecodedImage::new(
+        width,
+        height,
+        channels,
+        PixelType::U8,
+        super::image_io::DecodedPixels::U8(bytes),
+    )?)
+}
+
+/// The raw `CF_DIBV5` clipboard block, which unlike `CF_DIB` declares an alpha mask.
+#[cfg(target_os = "windows")]
+fn windows_dibv5() -> Option<Vec<u8>> {
+    use windows_sys::Win32::System::{
+        DataExchange::{CloseClipboard, GetClipboardData, IsClipboardFormatAvailable, OpenClipboard},
+        Memory::{GlobalLock, GlobalSize, GlobalUnlock},
+        Ole::CF_DIBV5,
+    };
+
+    // SAFETY: the clipboard is opened and closed on this thread, and the global memory block is only read while
+    // locked and before the clipboard is closed.
+    unsafe {
+        if IsClipboardFormatAvailable(CF_DIBV5 as u32) == 0 || OpenClipboard(std::ptr::null_mut()) == 0 {
+            return None;
+        }
+        let mut dib = None;
+        let handle = GetClipboardData(CF_DIBV5 as u32);
+        if !handle.is_null() {
+            let data = GlobalLock(handle) as *const u8;
+            if !data.is_null() {
+                dib = Some(std::slice::from_raw_parts(data, GlobalSize(handle)).to_vec());
+                GlobalUnlock(handle);
+            }
+        }
+        CloseClipboard();
+        dib
+    }
+}
+
+/// Replace the clipboard with the given blocks; `None` names `CF_DIBV5`, other names are registered formats.
+#[cfg(target_os = "windows")]
+fn set_windows_clipboard(blocks: &[(Option<&str>, Vec<u8>)]) -> Result<()> {
+    use windows_sys::Win32::{
+        Foundation::GlobalFree,
+        System::{
+            DataExchange::{CloseClipboard, EmptyClipboard, OpenClipboard, RegisterClipboardFormatW, SetClipboardData},
+            Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE},
+            Ole::CF_DIBV5,
+        },
+    };
+
+    // SAFETY: each block is copied into a fresh movable global allocation while locked. Ownership passes to the
+    // clipboard when SetClipboardData succeeds; otherwise the allocation is freed here.
+    unsafe {
+        if OpenClipboard(std::ptr::null_mut()) == 0 {
+            return Err(eyre!("Failed to open clipboard"));
+        }
+        EmptyClipboard();
+        let mut stored = 0;
+        for (name, data) in blocks {
+            let format = match name {
+                None => CF_DIBV5 as u32,
+                Some(name) => {
+                    let wide: Vec<u16> = name.encode_utf16().chain([0]).collect();
+                    RegisterClipboardFormatW(wide.as_ptr())
+                }
+            };
+            let memory = GlobalAlloc(GMEM_MOVEABLE, data.len());
+            if format == 0 || memory.is_null() {
+                continue;
+            }
+            let target = GlobalLock(memory) as *mut u8;
+            if target.is_null() {
+                GlobalFree(memory);
+                continue;
+            }
+            std::ptr::copy_nonoverlapping(data.as_ptr(), target, data.len());
+            GlobalUnlock(memory);
+            if SetClipboardData(format, memory).is_null() {
+                GlobalFree(memory);
+            } else {
+                stored += 1;
+            }
+        }
+        CloseClipboard();
+        if stored == 0 {
+            return Err(eyre!("Failed to set clipboard data"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::math_ext::vec2i;
+
+    #[test]
+    fn decodes_a_dibv5_with_alpha() {
+        // BITMAPV5HEADER for a 1x1 bottom-up 32-bit BI_BITFIELDS bitmap with an alpha mask.
+        let mut dib = Vec::new();
+        dib.extend_from_slice(&124_u32.to_le_bytes());
+        dib.extend_from_slice(&1_i32.to_le_bytes());
+        dib.extend_from_slice(&1_i32.to_le_bytes());
+        dib.extend_from_slice(&1_u16.to_le_bytes());
+        dib.extend_from_slice(&32_u16.to_le_bytes());
+        dib.extend_from_slice(&3_u32.to_le_bytes());
+        dib.extend_from_slice(&4_u32.to_le_bytes());
+        dib.extend_from_slice(&[0; 16]);
+        for mask in [0x00ff_0000_u32, 0x0000_ff00, 0x0000_00ff, 0xff00_0000] {
+            dib.extend_from_slice(&mask.to_le_bytes());
+        }
+        dib.resize(124, 0);
+        // Windows repeats the color masks after the header for BI_BITFIELDS.
+        for mask in [0x00ff_0000_u32, 0x0000_ff00, 0x0000_00ff] {
+            dib.extend_from_slice(&mask.to_le_bytes());
+        }
+        // Pixel stored as B, G, R, A.
+        dib.extend_from_slice(&[0, 128, 255, 51]);
+
+        let image = decode_dib(&dib).unwrap();
+        assert_eq!(image.spec().channels, 4);
+        assert_eq!(
+            image.get_pixel_at(0, 0).unwrap().to_vec(),
+            vec![1.0, 128.0 / 255.0, 0.0, 51.0 / 255.0]
+        );
+    }
+
+    #[test]
+    fn clipboard_encodings_round_trip() {
+        // Top-down 1x2 RGBA: opaque red above half-transparent blue.
+        let rgba = [255, 0, 0, 255, 0, 0, 255, 128];
+        let image = decode_dib(&encode_dibv5(1, 2, &rgba)).unwrap();
+        assert_eq!(image.get_pixel_at(0, 0).unwrap().to_vec(), vec![1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(image.get_pixel_at(0, 1).unwrap().to_vec(), vec![0.0, 0.0, 1.0, 128.0 / 255.0]);
+
+        let samples: [u16; 6] = [1, 2, 3, 40000, 50000, 65535];
+        let source = ImageData::from_raw_bytes(
+            bytemuck::cast_slice(&samples),
+            2,
+            1,
+            3,
+            PixelType::U16,
+            crate::model::ChannelOrder::Rgb,
+        )
+        .unwrap();
+        let png = encode_png16(&source, Recti::from_min_size(vec2i(1, 0), vec2i(1, 1))).unwrap();
+        let decoded = image::load_from_memory(&png).unwrap();
+        assert_eq!(decoded.as_rgb16().unwrap().as_raw(), &[40000, 50000, 65535]);
+
+        assert!(encode_png16(&source, Recti::from_min_size(vec2i(1, 0), vec2i(2, 1))).is_err());
+        let eight_bit = decode_dib(&encode_dibv5(1, 1, &rgba[..4])).unwrap();
+        assert!(encode_png16(&eight_bit, Recti::from_min_size(vec2i(0, 0), vec2i(1, 1))).is_err());
+    }
+}
@@ -0,0 +1,181 @@
+use std::path::Path;
+
+use color_eyre::eyre::{eyre, Result};
+
+/// Grid size used when baking an output transform into a LUT.
+pub const OUTPUT_LUT_SIZE: usize = 33;
+
+/// 3D lookup table over display-encoded RGB in `[0, 1]`. Entries are stored red-fastest, then green, then blue,
+/// which matches the x/y/z layout of the 3D texture the viewer uploads.
+#[derive(Clone, Debug)]
+pub struct ColorLut3d {
+    size: usize,
+    data: Vec<[f32; 4]>,
+}
+
+impl ColorLut3d {
+    /// Build a LUT by evaluating `f` at every grid point.
+    pub fn from_fn(size: usize, mut f: impl FnMut([f32; 3]) -> [f32; 3]) -> Self {
+        let size = size.max(2);
+        let scale = 1.0 / (size - 1) as f32;
+        let mut data = Vec::with_capacity(size * size * size);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    let [x, y, z] = f([r as f32 * scale, g as f32 * scale, b as f32 * scale]);
+                    data.push([x, y, z, 1.0]);
+                }
+            }
+        }
+        Self { size, data }
+    }
+
+    /// Bake the transform from sRGB to the monitor profile stored in the ICC file at `path`.
+    pub fn from_icc_profile(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path).map_err(|e| eyre!("Failed to read ICC profile '{}': {e}", path.display()))?;
+        let monitor = moxcms::ColorProfile::new_from_slice(&bytes)
+            .map_err(|e| eyre!("Invalid ICC profile '{}': {e:?}", path.display()))?;
+        let transform = moxcms::ColorProfile::new_srgb()
+            .create_transform_f32(
+                moxcms::Layout::Rgb,
+                &monitor,
+                moxcms::Layout::Rgb,
+                moxcms::TransformOptions::default(),
+            )
+            .map_err(|e| eyre!("Unsupported ICC profile '{}': {e:?}", path.display()))?;
+
+        let grid = Self::from_fn(OUTPUT_LUT_SIZE, |rgb| rgb);
+        let src: Vec<f32> = grid.data.iter().flat_map(|v| [v[0], v[1], v[2]]).collect();
+        let mut dst = vec![0.0; src.len()];
+        transform
+            .transform(&src, &mut dst)
+            .map_err(|e| eyre!("Failed to apply ICC profile '{}': {e:?}", path.display()))?;
+        let data = dst.chunks_exact(3).map(|v| [v[0], v[1], v[2], 1.0]).collect();
+        Ok(Self { size: grid.size, data })
+    }
+
+    /// Load an Adobe/Resolve `.cube` 3D LUT. Tables with a custom input domain are resampled to `[0, 1]`.
+    pub fn load_cube(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| eyre!("Failed to read LUT '{}': {e}", path.display()))?;
+        Self::parse_cube(&text).map_err(|e| eyre!("Invalid LUT '{}': {e}", path.display()))
+    }
+
+    pub fn parse_cube(text: &str) -> Result<Self> {
+        let mut size = None;
+        let mut domain_min = [0.0f32; 3];
+        let mut domain_max = [1.0f32; 3];
+        let mut data = Vec::new();
+
+        let parse_triplet = |fields: &[&str]| -> Result<[f32; 3]> {
+            match fields {
+                [r, g, b] => Ok([r.parse()?, g.parse()?, b.parse()?]),
+                _ => Err(eyre!("Expected 3 values, got {}", fields.len())),
+            }
+        };
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let Some((&keyword, args)) = fields.split_first() else {
+                continue;
+            };
+            let with_line = |e: color_eyre::Report| eyre!("line {}: {e}", index + 1);
+            match keyword {
+                "TITLE" => {}
+                "LUT_1D_SIZE" => return Err(eyre!("1D LUTs are not supported")),
+                "LUT_3D_SIZE" => {
+                    let value: usize = args.first().ok_or_else(|| eyre!("Missing LUT_3D_SIZE"))?.parse()?;
+                    if !(2..=256).contains(&value) {
+                        return Err(eyre!("Unsupported LUT_3D_SIZE {value}"));
+                    }
+                    size = Some(value);
+                }
+                "DOMAIN_MIN" => domain_min = parse_triplet(args).map_err(with_line)?,
+                "DOMAIN_MAX" => domain_max = parse_triplet(args).map_err(with_line)?,
+                _ if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) => {}
+                _ => {
+                    let [r, g, b] = parse_triplet(&fields).map_err(with_line)?;
+                    data.push([r, g, b, 1.0]);
+                }
+            }
+        }
+
+        let size = size.ok_or_else(|| eyre!("Missing LUT_3D_SIZE"))?;
+        if data.len() != size * size * size {
+            return Err(eyre!("Expected {} entries, found {}", size * size * size, data.len()));
+        }
+        if (0..3).any(|c| domain_max[c] <= domain_min[c]) {
+            return Err(eyre!("Invalid input domain"));
+        }
+        let lut = Self { size, data };
+        if domain_min == [0.0; 3] && domain_max == [1.0; 3] {
+            return Ok(lut);
+        }
+        Ok(Self::from_fn(size, |rgb| {
+            lut.sample([0, 1, 2].map(|c| (rgb[c] - domain_min[c]) / (domain_max[c] - domain_min[c])))
+        }))
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn data(&self) -> &[[f32; 4]] {
+        &self.data
+    }
+
+    /// Trilinearly interpolate the LUT at `rgb`, clamping the input to `[0, 1]`.
+    pub fn sample(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let max = (self.size - 1) as f32;
+        let coords = rgb.map(|v| v.clamp(0.0, 1.0) * max);
+        let lo = coords.map(|v| (v.floor() as usize).min(self.size - 2));
+        let t = [0, 1, 2].map(|i| coords[i] - lo[i] as f32);
+        let at = |r: usize, g: usize, b: usize| self.data[r + self.size * (g + self.size * b)];
+
+        let mut out = [0.0; 3];
+        for corner in 0..8 {
+            let (dr, dg, db) = (corner & 1, (corner >> 1) & 1, (corner >> 2) & 1);
+            let weight = [dr, dg, db]
+                .iter()
+                .zip(t)
+                .map(|(&d, t)| if d == 1 { t } else { 1.0 - t })
+                .product::<f32>();
+            let value = at(lo[0] + dr, lo[1] + dg, lo[2] + db);
+            for c in 0..3 {
+                out[c] += weight * value[c];
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cube_files() {
+        let invert =
+            "TITLE \"invert\"\n# comment\nLUT_3D_SIZE 2\n\n1 1 1\n0 1 1\n1 0 1\n0 0 1\n1 1 0\n0 1 0\n1 0 0\n0 0 0\n";
+        let lut = ColorLut3d::parse_cube(invert).unwrap();
+        assert_eq!(lut.size(), 2);
+        assert_eq!(lut.sample([0.25, 0.5, 1.0]), [0.75, 0.5, 0.0]);
+
+        assert!(ColorLut3d::parse_cube("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+        assert!(ColorLut3d::parse_cube("LUT_1D_SIZE 2\n0 0 0\n1 1 1\n").is_err());
+    }
+
+    #[test]
+    fn srgb_profile_bakes_to_identity() {
+        let path = std::env::temp_dir().join(format!("edolview_srgb_{}.icc", std::process::id()));
+        std::fs::write(&path, moxcms::ColorProfile::new_srgb().encode().unwrap()).unwrap();
+        let lut = ColorLut3d::from_icc_profile(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        for rgb in [[0.0, 0.0, 0.0], [1.0, 1.0, 1.0], [0.25, 0.5, 0.8]] {
+            let out = lut.sample(rgb);
+            for c in 0..3 {
+                assert!((out[c] - rgb[c]).abs() < 0.01, "{rgb:?} -> {out:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! One image tiling rendered thumbnails of a directory with their file names, for overviews of render outputs.
+
+use std::{
+    path::Path,
+    sync::{Arc, LazyLock},
+};
+
+use color_eyre::eyre::{eyre, Result};
+use resvg::{tiny_skia, usvg};
+
+const PADDING: u32 = 8;
+const LABEL_HEIGHT: u32 = 20;
+const LABEL_FONT_SIZE: f32 = 13.0;
+const BACKGROUND: [u8; 3] = [32, 32, 32];
+
+/// System fonts for the labels, loaded once on first use.
+static LABEL_FONTS: LazyLock<Arc<usvg::fontdb::Database>> = LazyLock::new(|| {
+    let mut fonts = usvg::fontdb::Database::new();
+    fonts.load_system_fonts();
+    Arc::new(fonts)
+});
+
+pub struct ContactSheetTile {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+/// Size of a tile for an image of `width`×`height` that fits a `cell`×`cell` square.
+pub fn contact_sheet_tile_size(width: u32, height: u32, cell: u32) -> (u32, u32) {
+    let scale = cell as f32 / width.max(height).max(1) as f32;
+    let fit = |v: u32| ((v as f32 * scale).round() as u32).clamp(1, cell);
+    (fit(width), fit(height))
+}
+
+/// Tile `tiles` row by row, `columns` per row, each centered in a `cell`×`cell` square above its file name.
+pub fn compose_contact_sheet(tiles: &[ContactSheetTile], columns: u32, cell: u32) -> Result<(u32, u32, Vec<u8>)> {
+    if tiles.is_empty() {
+        return Err(eyre!("No images for the contact sheet"));
+    }
+    let columns = columns.clamp(1, tiles.len() as u32);
+    let rows = (tiles.len() as u32).div_ceil(columns);
+    let (stride_x, stride_y) = (cell + PADDING, cell + LABEL_HEIGHT + PADDING);
+    let (width, height) = (columns * stride_x + PADDING, rows * stride_y + PADDING);
+
+    let mut sheet = [BACKGROUND[0], BACKGROUND[1], BACKGROUND[2], 255].repeat(width as usize * height as usize);
+    let mut labels = String::new();
+    for (index, tile) in tiles.iter().enumerate() {
+        if tile.rgba.len() != tile.width as usize * tile.height as usize * 4 {
+            return Err(eyre!("Tile {} has the wrong number of bytes", tile.name));
+        }
+        let (column, row) = (index as u32 % columns, index as u32 / columns);
+        let (cell_x, cell_y) = (PADDING + column * stride_x, PADDING + row * stride_y);
+        let x0 = cell_x + cell.saturating_sub(tile.width) / 2;
+        let y0 = cell_y + cell.saturating_sub(tile.height) / 2;
+        for (y, src_row) in tile.rgba.chunks_exact(tile.width as usize * 4).enumerate() {
+            let start = ((y0 as usize + y) * width as usize + x0 as usize) * 4;
+            let dst_row = &mut sheet[start..start + src_row.len()];
+            for (dst, src) in dst_row.chunks_exact_mut(4).zip(src_row.chunks_exact(4)) {
+                let alpha = src[3] as u32;
+                for c in 0..3 {
+                    dst[c] = ((src[c] as u32 * alpha + dst[c] as u32 * (255 - alpha) + 127) / 255) as u8;
+                }
+            }
+        }
+        labels.push_str(&format!(
+            r#"<text x="{}" y="{}">{}</text>"#,
+            cell_x + cell / 2,
+            cell_y + cell + LABEL_HEIGHT * 3 / 4,
+            escape_xml(&truncate_label(&tile.name, cell))
+        ));
+    }
+
+    // Labels are drawn by resvg over the opaque sheet, whose straight and premultiplied pixels are the same.
+    let svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}"><g font-family="sans-serif" font-size="{LABEL_FONT_SIZE}" fill="#dddddd" text-anchor="middle">{labels}</g></svg>"##
+    );
+    let options = usvg::Options {
+        fontdb: Arc::clone(&LABEL_FONTS),
+        ..usvg::Options::default()
+    };
+    let tree = usvg::Tree::from_str(&svg, &options)?;
+    let size = tiny_skia::IntSize::from_wh(width, height).ok_or_else(|| eyre!("Contact sheet is too large"))?;
+    let mut pixmap = tiny_skia::Pixmap::from_vec(sheet, size).ok_or_else(|| eyre!("Contact sheet is too large"))?;
+    resvg::render(&tree, tiny_skia::Transform::identity(), &mut pixmap.as_mut());
+    Ok((width, height, pixmap.take()))
+}
+
+pub fn save_contact_sheet(path: &Path, tiles: &[ContactSheetTile], columns: u32, cell: u32) -> Result<()> {
+    let (width, height, rgba) = compose_contact_sheet(tiles, columns, cell)?;
+    image::save_buffer(path, &rgba, width, height, image::ColorType::Rgba8)?;
+    Ok(())
+}
+
+/// Shorten `name` with an ellipsis to roughly fit `width` pixels of label text.
+fn truncate_label(name: &str, width: u32) -> String {
+    let max_chars = (width as f32 / (LABEL_FONT_SIZE * 0.55)).max(4.0) as usize;
+    if name.chars().count() <= max_chars {
+        return name.to_string();
+    }
+    let head: String = name.chars().take(max_chars - 1).collect();
+    format!("{head}…")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_thumbnails_in_rows_under_labels() {
+        assert_eq!(contact_sheet_tile_size(400, 200, 100), (100, 50));
+        let tile = |name: &str, rgba: [u8; 4]| ContactSheetTile {
+            name: name.to_string(),
+            width: 2,
+            height: 1,
+            rgba: rgba.repeat(2),
+        };
+        let tiles = [
+            tile("a<&>.png", [255, 0, 0, 255]),
+            tile("b.png", [0, 0, 255, 0]),
+            tile("c.png", [0, 255, 0, 255]),
+        ];
+
+        let (width, height, rgba) = compose_contact_sheet(&tiles, 2, 2).unwrap();
+        assert_eq!((width, height), (2 * 10 + 8, 2 * 30 + 8));
+        let pixel = |x: u32, y: u32| &rgba[((y * width + x) * 4) as usize..][..4];
+        // Tiles are centered vertically in their 2x2 cell.
+        assert_eq!(pixel(8, 8), [255, 0, 0, 255]);
+        assert_eq!(pixel(18, 8), [32, 32, 32, 255]);
+        assert_eq!(pixel(9, 38), [0, 255, 0, 255]);
+        assert!(compose_contact_sheet(&[], 2, 2).is_err());
+        assert_eq!(truncate_label("a_very_long_file_name.exr", 36), "a_ve…");
+    }
+}
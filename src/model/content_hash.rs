@@ -0,0 +1,118 @@
+//! XXH64 hashes of decoded pixels, to spot image list entries with identical content under different names.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::mpsc,
+};
+
+use eframe::egui;
+use indexmap::IndexMap;
+use xxhash_rust::xxh64::Xxh64;
+
+use crate::model::{Image, ImageData, SharedAsset};
+
+/// Hash of an image's size, channel count and pixel values. `None` for GPU-derived images without CPU pixels.
+pub fn content_hash(image: &ImageData) -> Option<u64> {
+    let spec = image.spec();
+    let data = image.data()?;
+    let mut hasher = Xxh64::new(0);
+    for v in [spec.width, spec.height, spec.channels] {
+        hasher.update(&v.to_le_bytes());
+    }
+    hasher.update(bytemuck::cast_slice(data));
+    Some(hasher.digest())
+}
+
+/// Content hashes of image list assets, computed one at a time on a background worker and keyed by image id.
+#[derive(Default)]
+pub struct ContentHashes {
+    hashes: HashMap<u64, u64>,
+    started: HashSet<u64>,
+    /// Started on the first image to hash; the worker exits once this is dropped.
+    worker: Option<HashWorker>,
+}
+
+struct HashWorker {
+    jobs_tx: mpsc::Sender<ImageData>,
+    results_rx: mpsc::Receiver<(u64, Option<u64>)>,
+}
+
+impl HashWorker {
+    fn spawn(ctx: &egui::Context) -> Self {
+        let (jobs_tx, jobs_rx) = mpsc::channel::<ImageData>();
+        let (results_tx, results_rx) = mpsc::channel();
+        let repaint_ctx = ctx.clone();
+        std::thread::spawn(move || {
+            for image in jobs_rx {
+                if results_tx.send((image.id(), content_hash(&image))).is_err() {
+                    return;
+                }
+                repaint_ctx.request_repaint();
+            }
+        });
+        Self { jobs_tx, results_rx }
+    }
+}
+
+impl ContentHashes {
+    pub fn get(&self, image: &ImageData) -> Option<u64> {
+        self.hashes.get(&image.id()).copied()
+    }
+
+    /// Whether `image` is still being hashed.
+    pub fn is_pending(&self, image: &ImageData) -> bool {
+        self.started.contains(&image.id()) && !self.hashes.contains_key(&image.id())
+    }
+
+    /// Collect finished hashes, forget removed images and start hashing new ones.
+    pub fn update(&mut self, ctx: &egui::Context, assets: &IndexMap<String, SharedAsset>) {
+        if let Some(worker) = &self.worker {
+            while let Ok((image_id, hash)) = worker.results_rx.try_recv() {
+                if let Some(hash) = hash {
+                    self.hashes.insert(image_id, hash);
+                }
+            }
+        }
+        let ids: HashSet<u64> = assets.values().map(|asset| asset.image().id()).collect();
+        self.hashes.retain(|id, _| ids.contains(id));
+        self.started.retain(|id| ids.contains(id));
+
+        for asset in assets.values() {
+            let image = asset.image();
+            if image.data().is_none() || !self.started.insert(image.id()) {
+                continue;
+            }
+            let worker = self.worker.get_or_insert_with(|| HashWorker::spawn(ctx));
+            let _ = worker.jobs_tx.send(image.clone());
+        }
+    }
+
+    /// Content hashes shared by more than one asset, with the names of those assets in list order.
+    pub fn duplicates(&self, assets: &IndexMap<String, SharedAsset>) -> HashMap<u64, Vec<String>> {
+        let mut names: HashMap<u64, Vec<String>> = HashMap::new();
+        for asset in assets.values() {
+            if let Some(hash) = self.get(asset.image()) {
+                names.entry(hash).or_default().push(asset.name().to_string());
+            }
+        }
+        names.retain(|_, names| names.len() > 1);
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ImageSpec, PixelType};
+
+    #[test]
+    fn hashes_pixels_and_shape() {
+        let image = |width, height, data: Vec<f32>| {
+            ImageData::from_f32(ImageSpec::new(width, height, 1, PixelType::F32), data).unwrap()
+        };
+        let hash = |image: ImageData| content_hash(&image).unwrap();
+        assert_eq!(hash(image(2, 1, vec![0.5, 1.0])), hash(image(2, 1, vec![0.5, 1.0])));
+        assert_ne!(hash(image(2, 1, vec![0.5, 1.0])), hash(image(2, 1, vec![0.5, 0.0])));
+        assert_ne!(hash(image(2, 1, vec![0.5, 1.0])), hash(image(1, 2, vec![0.5, 1.0])));
+    }
+}
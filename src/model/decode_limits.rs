@@ -0,0 +1,79 @@
+//! Size limits checked by the decoders before they allocate pixel buffers, so malformed headers with absurd
+//! dimensions fail with an error instead of exhausting memory.
+
+use std::sync::RwLock;
+
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+static DECODE_LIMITS: RwLock<DecodeLimits> = RwLock::new(DecodeLimits::DEFAULT);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DecodeLimits {
+    /// Largest accepted width or height in pixels.
+    pub max_dimension: u32,
+    /// Largest accepted decoded size in MiB, counted as 32-bit float samples as the viewer stores them.
+    pub max_memory_mib: u64,
+}
+
+impl DecodeLimits {
+    pub const DEFAULT: Self = Self {
+        max_dimension: 1 << 20,
+        max_memory_mib: 16 * 1024,
+    };
+
+    pub fn max_memory_bytes(&self) -> u64 {
+        self.max_memory_mib.saturating_mul(1024 * 1024)
+    }
+
+    /// Reject images whose dimensions or decoded `f32` size exceed the limits.
+    pub fn check(&self, width: u64, height: u64, channels: u64) -> Result<()> {
+        if width > self.max_dimension as u64 || height > self.max_dimension as u64 {
+            return Err(eyre!(
+                "Image dimensions {width}x{height} exceed the decode limit of {} pixels per side",
+                self.max_dimension
+            ));
+        }
+        let bytes = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(channels.max(1)))
+            .and_then(|samples| samples.checked_mul(4));
+        if bytes.is_none_or(|bytes| bytes > self.max_memory_bytes()) {
+            return Err(eyre!(
+                "Image of {width}x{height}x{channels} exceeds the decode memory limit of {} MiB",
+                self.max_memory_mib
+            ));
+        }
+        Ok(())
+    }
+
+    /// The same limits for decoders from the `image` crate, which apply them to their own allocations.
+    pub fn image_limits(&self) -> image::Limits {
+        let mut limits = image::Limits::default();
+        limits.max_image_width = Some(self.max_dimension);
+        limits.max_image_height = Some(self.max_dimension);
+        limits.max_alloc = Some(self.max_memory_bytes());
+        limits
+    }
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Limits applied to every decode from now on.
+pub fn set_decode_limits(limits: DecodeLimits) {
+    *DECODE_LIMITS.write().unwrap() = limits;
+}
+
+pub fn decode_limits() -> DecodeLimits {
+    *DECODE_LIMITS.read().unwrap()
+}
+
+/// Check `width`×`height`×`channels` against the current limits.
+pub fn check_decode_limits(width: u64, height: u64, channels: u64) -> Result<()> {
+    decode_limits().check(width, height, channels)
+}
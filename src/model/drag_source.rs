@@ -0,0 +1,210 @@
+//! Dragging exported files out of the window into other applications. Windows hands the file to the shell's own
+//! drag loop and macOS starts an AppKit dragging session; other platforms have no drag source, and the viewer
+//! offers no drag-out binding there. A drag has to start on the UI thread while the mouse button that began it is
+//! still held.
+
+use std::path::Path;
+
+use color_eyre::eyre::{eyre, Report, Result};
+use raw_window_handle::HasWindowHandle;
+
+/// Whether [`start_file_drag`] can start a drag on this platform.
+pub const FILE_DRAG_SUPPORTED: bool = cfg!(any(target_os = "windows", target_os = "macos"));
+
+/// Start dragging `path` out of `window` and return while the drag runs. On Windows the shell's modal drag loop
+/// runs on a thread of its own so the viewer keeps drawing, and a failure there is passed to `on_error`. On macOS
+/// AppKit runs the drag and every failure is returned here.
+pub fn start_file_drag(
+    window: &impl HasWindowHandle,
+    path: &Path,
+    on_error: impl FnOnce(Report) + Send + 'static,
+) -> Result<()> {
+    let handle = window
+        .window_handle()
+        .map_err(|e| eyre!("Window handle is not available: {e}"))?
+        .as_raw();
+    #[cfg(target_os = "windows")]
+    if let raw_window_handle::RawWindowHandle::Win32(handle) = handle {
+        return windows::start_drag(handle.hwnd.get() as _, path, on_error);
+    }
+    #[cfg(target_os = "macos")]
+    if let raw_window_handle::RawWindowHandle::AppKit(handle) = handle {
+        return macos::start_drag(handle.ns_view, path);
+    }
+    let _ = (handle, path, on_error);
+    Err(eyre!("Dragging images out of the window is not supported on this platform"))
+}
+
+#[cfg(target_os = "windows")]
+mod windows {
+    use std::{os::windows::ffi::OsStrExt, path::Path};
+
+    use color_eyre::eyre::{eyre, Report, Result};
+    use windows_sys::{
+        core::{IUnknown_Vtbl, GUID},
+        Win32::{
+            Foundation::HWND,
+            System::{
+                Ole::{OleInitialize, OleUninitialize, DROPEFFECT_COPY},
+                Threading::{AttachThreadInput, GetCurrentThreadId},
+            },
+            UI::Shell::{ILCreateFromPathW, ILFindLastID, ILFree, SHCreateDataObject, SHDoDragDrop},
+        },
+    };
+
+    const IID_IDATA_OBJECT: GUID = GUID::from_u128(0x0000010e_0000_0000_c000_000000000046);
+
+    fn wide(path: &Path) -> Vec<u16> {
+        path.as_os_str().encode_wide().chain(std::iter::once(0)).collect()
+    }
+
+    /// Run the drag on a new thread in its own single-threaded apartment, since `SHDoDragDrop` does not return before
+    /// the drop and would block the UI thread for the whole drag.
+    pub(super) fn start_drag(hwnd: HWND, path: &Path, on_error: impl FnOnce(Report) + Send + 'static) -> Result<()> {
+        // SAFETY: GetCurrentThreadId has no preconditions.
+        let ui_thread = unsafe { GetCurrentThreadId() };
+        // Window handles are plain identifiers that any thread may pass to the shell.
+        let hwnd = hwnd as usize;
+        let path = path.to_path_buf();
+        std::thread::Builder::new()
+            .name("file-drag".to_string())
+            .spawn(move || {
+                if let Err(err) = drag(hwnd as HWND, ui_thread, &path) {
+                    on_error(err);
+                }
+            })
+            .map_err(|e| eyre!("Failed to start the drag thread: {e}"))?;
+        Ok(())
+    }
+
+    /// Drag through the shell's data object for the file, which carries `CF_HDROP` and the shell formats that
+    /// Explorer, chat apps and editors read, with the shell's default drop source and drag image. The drag loop
+    /// reads the button state from the input of `ui_thread`, which saw the press that started the drag.
+    fn drag(hwnd: HWND, ui_thread: u32, path: &Path) -> Result<()> {
+        let folder = path.parent().ok_or_else(|| eyre!("Dragged file has no parent folder"))?;
+        // SAFETY: OLE is initialized as a single-threaded apartment on this new thread for the duration of the
+        // drag and uninitialized once, the input attachment is undone before returning, the item ID lists are freed
+        // after the data object that copied them is released, and the data object is a COM object whose first
+        // field is its vtable pointer.
+        unsafe {
+            let initialized = OleInitialize(std::ptr::null());
+            if initialized < 0 {
+                return Err(eyre!("Failed to initialize OLE for the drag: HRESULT {initialized:#010x}"));
+            }
+            let this_thread = GetCurrentThreadId();
+            let attached = AttachThreadInput(this_thread, ui_thread, 1) != 0;
+            let item = ILCreateFromPathW(wide(path).as_ptr());
+            let folder_item = ILCreateFromPathW(wide(folder).as_ptr());
+            let result = if item.is_null() || folder_item.is_null() {
+                Err(eyre!("Failed to resolve {} in the shell", path.display()))
+            } else {
+                let child = ILFindLastID(item) as *const _;
+                let mut data_object = std::ptr::null_mut();
+                let created = SHCreateDataObject(
+                    folder_item,
+                    1,
+                    &child,
+                    std::ptr::null_mut(),
+                    &IID_IDATA_OBJECT,
+                    &mut data_object,
+                );
+                if created < 0 || data_object.is_null() {
+                    Err(eyre!("Failed to create drag data: HRESULT {created:#010x}"))
+                } else {
+                    let mut effect = 0;
+                    let dragged = SHDoDragDrop(hwnd, data_object, std::ptr::null_mut(), DROPEFFECT_COPY, &mut effect);
+                    let vtable = *(data_object as *const *const IUnknown_Vtbl);
+                    ((*vtable).Release)(data_object);
+                    if dragged < 0 {
+                        Err(eyre!("Drag and drop failed: HRESULT {dragged:#010x}"))
+                    } else {
+                        Ok(())
+                    }
+                }
+            };
+            if !item.is_null() {
+                ILFree(item);
+            }
+            if !folder_item.is_null() {
+                ILFree(folder_item);
+            }
+            if attached {
+                AttachThreadInput(this_thread, ui_thread, 0);
+            }
+            OleUninitialize();
+            result
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use std::{cell::RefCell, ffi::c_void, path::Path, ptr::NonNull};
+
+    use color_eyre::eyre::{eyre, Result};
+    use objc2::{
+        define_class, msg_send, rc::Retained, runtime::ProtocolObject, AnyThread, MainThreadMarker, MainThreadOnly,
+    };
+    use objc2_app_kit::{
+        NSApplication, NSDragOperation, NSDraggingContext, NSDraggingItem, NSDraggingSession, NSDraggingSource,
+        NSEventType, NSView, NSWorkspace,
+    };
+    use objc2_foundation::{NSArray, NSObject, NSObjectProtocol, NSPoint, NSRect, NSSize, NSString, NSURL};
+
+    /// Side of the file icon that follows the pointer, in points.
+    const ICON_SIZE: f64 = 64.0;
+
+    define_class!(
+        /// Offers dragged files for copying only, so a drop never moves the temporary export away.
+        #[unsafe(super(NSObject))]
+        #[thread_kind = MainThreadOnly]
+        #[name = "EdolviewFileDragSource"]
+        struct FileDragSource;
+
+        unsafe impl NSObjectProtocol for FileDragSource {}
+
+        unsafe impl NSDraggingSource for FileDragSource {
+            #[unsafe(method(draggingSession:sourceOperationMaskForDraggingContext:))]
+            fn operation_mask(&self, _session: &NSDraggingSession, _context: NSDraggingContext) -> NSDragOperation {
+                NSDragOperation::Copy
+            }
+        }
+    );
+
+    thread_local! {
+        /// Source of the running drag; AppKit does not retain it.
+        static DRAG_SOURCE: RefCell<Option<Retained<FileDragSource>>> = const { RefCell::new(None) };
+    }
+
+    pub(super) fn start_drag(ns_view: NonNull<c_void>, path: &Path) -> Result<()> {
+        let mtm = MainThreadMarker::new().ok_or_else(|| eyre!("Drags have to start on the main thread"))?;
+        // SAFETY: winit hands out the content view of a live window, and this runs on the main thread.
+        let view: &NSView = unsafe { ns_view.cast().as_ref() };
+        let event = NSApplication::sharedApplication(mtm)
+            .currentEvent()
+            .filter(|event| matches!(event.r#type(), NSEventType::LeftMouseDown | NSEventType::LeftMouseDragged))
+            .ok_or_else(|| eyre!("Drags have to start while the left mouse button is held"))?;
+
+        let path = NSString::from_str(&path.to_string_lossy());
+        let url = NSURL::fileURLWithPath(&path);
+        let item = NSDraggingItem::initWithPasteboardWriter(NSDraggingItem::alloc(), ProtocolObject::from_ref(&*url));
+        let icon = NSWorkspace::sharedWorkspace().iconForFile(&path);
+        let location = view.convertPoint_fromView(event.locationInWindow(), None);
+        let frame = NSRect::new(
+            NSPoint::new(location.x - ICON_SIZE / 2.0, location.y - ICON_SIZE / 2.0),
+            NSSize::new(ICON_SIZE, ICON_SIZE),
+        );
+        // SAFETY: The dragging frame contents have to be an image, which `iconForFile` returns.
+        unsafe { item.setDraggingFrame_contents(frame, Some(&icon)) };
+
+        // SAFETY: `FileDragSource` adds no instance variables, so NSObject's `init` fully initializes it.
+        let source: Retained<FileDragSource> = unsafe { msg_send![mtm.alloc::<FileDragSource>(), init] };
+        view.beginDraggingSessionWithItems_event_source(
+            &NSArray::from_retained_slice(&[item]),
+            &event,
+            ProtocolObject::from_ref(&*source),
+        );
+        DRAG_SOURCE.with(|current| current.replace(Some(source)));
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+//! Source file metadata carried into exported renders when the user keeps it. Renders are new images, so by
+//! default nothing is written; EXIF in particular can hold GPS positions and camera serial numbers.
+
+use std::{io::Cursor, path::Path};
+
+use color_eyre::eyre::Result;
+use image::{codecs::jpeg::JpegEncoder, ImageDecoder, ImageEncoder};
+
+/// PNG `iTXt` keyword and JPEG APP1 namespace of XMP packets.
+const XMP_PNG_KEYWORD: &str = "XML:com.adobe.xmp";
+const XMP_JPEG_NAMESPACE: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SourceMetadata {
+    pub icc: Option<Vec<u8>>,
+    pub exif: Option<Vec<u8>>,
+    pub xmp: Option<Vec<u8>>,
+}
+
+impl SourceMetadata {
+    /// Read the ICC profile, EXIF and XMP blocks of an image file verbatim. The EXIF orientation is kept too:
+    /// images are shown and rendered in their stored orientation, so it still applies to the render.
+    pub fn read(path: &Path) -> Result<Self> {
+        let mut decoder = image::ImageReader::open(path)?.with_guessed_format()?.into_decoder()?;
+        Ok(Self {
+            icc: decoder.icc_profile()?,
+            exif: decoder.exif_metadata()?,
+            xmp: decoder.xmp_metadata()?,
+        })
+    }
+}
+
+/// Encode RGBA8 pixels as PNG with `metadata` in `iCCP`, `eXIf` and `iTXt` chunks.
+pub fn encode_png_with_metadata(width: u32, height: u32, rgba: &[u8], metadata: &SourceMetadata) -> Result<Vec<u8>> {
+    let mut png = Vec::new();
+    let mut info = png::Info::with_size(width, height);
+    info.color_type = png::ColorType::Rgba;
+    info.bit_depth = png::BitDepth::Eight;
+    info.icc_profile = metadata.icc.as_deref().map(Into::into);
+    info.exif_metadata = metadata.exif.as_deref().map(Into::into);
+    let mut encoder = png::Encoder::with_info(&mut png, info)?;
+    if let Some(xmp) = &metadata.xmp {
+        encoder.add_itxt_chunk(XMP_PNG_KEYWORD.to_string(), String::from_utf8_lossy(xmp).into_owned())?;
+    }
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(rgba)?;
+    writer.finish()?;
+    Ok(png)
+}
+
+/// Encode RGB8 pixels as JPEG with `metadata` in APP2 (ICC), APP1 (EXIF) and APP1 (XMP) segments.
+pub fn encode_jpeg_with_metadata(
+    width: u32,
+    height: u32,
+    rgb: &[u8],
+    quality: u8,
+    metadata: &SourceMetadata,
+) -> Result<Vec<u8>> {
+    let mut jpeg = Vec::new();
+    let mut encoder = JpegEncoder::new_with_quality(Cursor::new(&mut jpeg), quality);
+    if let Some(icc) = &metadata.icc {
+        encoder.set_icc_profile(icc.clone())?;
+    }
+    if let Some(exif) = &metadata.exif {
+        encoder.set_exif_metadata(exif.clone())?;
+    }
+    encoder.write_image(rgb, width, height, image::ExtendedColorType::Rgb8)?;
+    if let Some(xmp) = metadata
+        .xmp
+        .as_deref()
+        .filter(|xmp| xmp.len() + XMP_JPEG_NAMESPACE.len() + 2 <= 0xffff)
+    {
+        let mut segment = vec![0xff, 0xe1];
+        segment.extend_from_slice(&((xmp.len() + XMP_JPEG_NAMESPACE.len() + 2) as u16).to_be_bytes());
+        segment.extend_from_slice(XMP_JPEG_NAMESPACE);
+        segment.extend_from_slice(xmp);
+        let at = after_jfif_header(&jpeg);
+        jpeg.splice(at..at, segment);
+    }
+    Ok(jpeg)
+}
+
+/// Offset just past SOI and a JFIF APP0 segment, where other application segments may follow.
+fn after_jfif_header(jpeg: &[u8]) -> usize {
+    match jpeg {
+        [0xff, 0xd8, 0xff, 0xe0, high, low, ..] => (4 + u16::from_be_bytes([*high, *low]) as usize).min(jpeg.len()),
+        _ => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_metadata_through_png_and_jpeg() {
+        let icc = moxcms::ColorProfile::new_srgb().encode().unwrap();
+        // Little-endian EXIF with one IFD entry: orientation 6 (rotated 90 degrees).
+        let exif = vec![
+            b'I', b'I', 42, 0, 8, 0, 0, 0, 1, 0, 0x12, 0x01, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        let metadata = SourceMetadata {
+            icc: Some(icc),
+            exif: Some(exif),
+            xmp: Some(b"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/>".to_vec()),
+        };
+        let dir = std::env::temp_dir();
+        let id = std::process::id();
+
+        let png_path = dir.join(format!("edolview-metadata-{id}.png"));
+        std::fs::write(&png_path, encode_png_with_metadata(1, 1, &[1, 2, 3, 255], &metadata).unwrap()).unwrap();
+        let jpeg_path = dir.join(format!("edolview-metadata-{id}.jpg"));
+        std::fs::write(&jpeg_path, encode_jpeg_with_metadata(1, 1, &[1, 2, 3], 90, &metadata).unwrap()).unwrap();
+
+        for path in [png_path, jpeg_path] {
+            let read = SourceMetadata::read(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(read, metadata, "{}", path.display());
+        }
+
+        let stripped = encode_png_with_metadata(1, 1, &[1, 2, 3, 255], &SourceMetadata::default()).unwrap();
+        assert!(image::load_from_memory(&stripped).is_ok());
+    }
+}
@@ -47,7 +47,7 @@ impl FileNav {
             .and_then(|s| s.to_str())
             .map(|s| s.to_ascii_lowercase())
             .unwrap_or_default();
-        crate::supported_image::is_supported_image_extension(ext.as_str())
+        crate::plugin::is_openable_extension(ext.as_str())
     }
 
     #[inline]
@@ -82,7 +82,12 @@ impl FileNav {
         let dir_abs = canonicalize_friendly(&dir).unwrap_or(dir.clone());
         self.dir_path = Some(dir_abs.clone());
         let mut files = Vec::new();
-        if let Ok(entries) = std::fs::read_dir(&dir_abs) {
+        if crate::model::is_archive_path(&dir_abs) && dir_abs.is_file() {
+            match crate::model::list_archive_images(&dir_abs) {
+                Ok(entries) => files.extend(entries.iter().map(|entry| dir_abs.join(entry))),
+                Err(e) => eprintln!("Failed to list archive {}: {e}", dir_abs.display()),
+            }
+        } else if let Ok(entries) = std::fs::read_dir(&dir_abs) {
             for ent in entries.flatten() {
                 let p = ent.path();
                 if p.is_file() && Self::is_supported_image(&p) {
@@ -90,7 +95,10 @@ impl FileNav {
                 }
             }
         }
-        Self::sort_paths_case_insensitive(&mut files);
+        if !crate::model::is_archive_path(&dir_abs) {
+            // Archive entries are already ordered by their full in-archive path.
+            Self::sort_paths_case_insensitive(&mut files);
+        }
         self.files_in_dir = files;
         self.pending_changed = false;
         self.last_change_instant = None;
@@ -106,31 +114,30 @@ impl FileNav {
     }
 
     pub fn navigate_next(&mut self) -> Option<PathBuf> {
-        if let (Some(_), Some(cur_idx)) = (self.dir_path.clone(), self.current_file_index) {
-            if self.files_in_dir.is_empty() {
-                return None;
-            }
-            let next = (cur_idx + 1) % self.files_in_dir.len();
-            let path = self.files_in_dir[next].clone();
-            return Some(path);
-        }
-        None
+        self.step_where(true, |_| true)
     }
 
     pub fn navigate_prev(&mut self) -> Option<PathBuf> {
-        if let (Some(_), Some(cur_idx)) = (self.dir_path.clone(), self.current_file_index) {
-            if self.files_in_dir.is_empty() {
-                return None;
-            }
-            let prev = if cur_idx == 0 {
-                self.files_in_dir.len() - 1
-            } else {
-                cur_idx - 1
-            };
-            let path = self.files_in_dir[prev].clone();
-            return Some(path);
-        }
-        None
+        self.step_where(false, |_| true)
+    }
+
+    /// The closest file after (or before) the current one that `accept` takes, wrapping around the directory.
+    pub fn step_where(&self, forward: bool, accept: impl Fn(&Path) -> bool) -> Option<PathBuf> {
+        let (Some(_), Some(cur_idx)) = (self.dir_path.as_ref(), self.current_file_index) else {
+            return None;
+        };
+        let len = self.files_in_dir.len();
+        (1..=len)
+            .map(|offset| {
+                if forward {
+                    (cur_idx + offset) % len
+                } else {
+                    (cur_idx + len - offset % len) % len
+                }
+            })
+            .map(|index| &self.files_in_dir[index])
+            .find(|path| accept(path))
+            .cloned()
     }
 
     pub fn start_dir_watcher(&mut self, dir: PathBuf) -> Result<()> {
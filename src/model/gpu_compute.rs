@@ -9,6 +9,15 @@ use crate::util::math_ext::Vec2i;
 
 const WORKGROUP_SIZE: u32 = 256;
 const REDUCTION_GROUPS: u32 = 1024;
+/// Block size of the first pyramid level. Zooms between 1/4 and 1 sample the full-resolution texture, which
+/// keeps the whole pyramid at about 1/12 of the base pixel count.
+const PYRAMID_FIRST_FACTOR: u32 = 4;
+/// Pyramid levels stop once the longest side fits in this many pixels.
+const PYRAMID_MIN_SIDE: u32 = 256;
+/// Weights of the scales of MS-SSIM, finest first.
+const MS_SSIM_WEIGHTS: [f64; 5] = [0.0448, 0.2856, 0.3001, 0.2363, 0.1333];
+/// Side of the SSIM window; smaller regions have no full window.
+const MS_SSIM_MIN_SIDE: i32 = 11;
 
 static GPU_COMPUTE: LazyLock<RwLock<Option<Arc<GpuComputeContext>>>> = LazyLock::new(|| RwLock::new(None));
 
@@ -34,6 +43,15 @@ pub struct GpuImageTexture {
     pub spec: ImageSpec,
 }
 
+impl GpuImageTexture {
+    /// Texture memory estimated from its size and format; drivers may pad rows or add tiling overhead.
+    pub fn bytes(&self) -> usize {
+        let size = self.texture.size();
+        let texel = self.texture.format().block_copy_size(None).unwrap_or(16) as usize;
+        size.width as usize * size.height as usize * size.depth_or_array_layers as usize * texel
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
 struct ComputeParams {
@@ -76,6 +94,7 @@ pub struct GpuComputeContext {
     sum_pipeline: std::sync::OnceLock<wgpu::ComputePipeline>,
     vector_mean_pipeline: std::sync::OnceLock<wgpu::ComputePipeline>,
     compare_pipeline: std::sync::OnceLock<wgpu::ComputePipeline>,
+    downsample_pipeline: std::sync::OnceLock<wgpu::ComputePipeline>,
     ssim_pipeline: std::sync::OnceLock<wgpu::ComputePipeline>,
     rgb_upload_layout: wgpu::BindGroupLayout,
     rgb_upload_pipeline: wgpu::ComputePipeline,
@@ -261,6 +280,7 @@ impl GpuComputeContext {
             sum_pipeline: std::sync::OnceLock::new(),
             vector_mean_pipeline: std::sync::OnceLock::new(),
             compare_pipeline: std::sync::OnceLock::new(),
+            downsample_pipeline: std::sync::OnceLock::new(),
             ssim_pipeline: std::sync::OnceLock::new(),
             rgb_upload_layout,
             rgb_upload_pipeline,
@@ -292,6 +312,7 @@ impl GpuComputeContext {
         if matches!(image.pixels, crate::model::image_io::DecodedPixels::Raw(_))
             && !image.transform.flip_y
             && !image.transform.swap_bytes
+            && !image.swaps_red_blue()
             && image.transform.scale == 1.0
             && image.layout.planes == 1
             && image.layout.input_channels == image.channels as usize
@@ -474,7 +495,9 @@ impl GpuComputeContext {
                 format: [pixel_kind, image.color.shader_kind(), base_y as u32, row_count as u32],
                 transform: [
                     0,
-                    u32::from(image.transform.flip_y) | (u32::from(image.transform.swap_bytes) << 1),
+                    u32::from(image.transform.flip_y)
+                        | (u32::from(image.transform.swap_bytes) << 1)
+                        | (u32::from(image.swaps_red_blue()) << 2),
                     image.transform.scale.to_bits(),
                     0,
                 ],
@@ -702,7 +725,7 @@ impl GpuComputeContext {
         rhs: &GpuImageTexture,
         output_spec: &ImageSpec,
         mode: ComparisonMode,
-        alpha: f32,
+        amount: f32,
         strategy: u32,
     ) -> Result<Arc<GpuImageTexture>> {
         let output = create_empty_rgba_texture(
@@ -718,12 +741,17 @@ impl GpuComputeContext {
                 ComparisonMode::Diff => 0,
                 ComparisonMode::Blend => 1,
                 ComparisonMode::Split => 2,
+                ComparisonMode::Anaglyph => 3,
             },
             lhs.spec.channels as u32,
             rhs.spec.channels as u32,
             strategy,
         ];
-        params.values[0] = alpha.clamp(0.0, 1.0);
+        // The gain of the difference, or the blend factor.
+        params.values[0] = match mode {
+            ComparisonMode::Diff => amount,
+            _ => amount.clamp(0.0, 1.0),
+        };
         let output_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("edolview comparison dummy output"),
             size: 4,
@@ -741,6 +769,54 @@ impl GpuComputeContext {
         Ok(Arc::new(output))
     }
 
+    /// Box-filtered levels of `image`, each `(factor, texture)` covering blocks of `factor`×`factor` base pixels.
+    /// Factors start at [`PYRAMID_FIRST_FACTOR`] and double until the longest side reaches [`PYRAMID_MIN_SIDE`].
+    pub fn pyramid(&self, image: &GpuImageTexture) -> Result<Vec<(u32, Arc<GpuImageTexture>)>> {
+        let (width, height) = (image.spec.width as u32, image.spec.height as u32);
+        let output_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("edolview pyramid dummy output"),
+            size: 4,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let mut levels: Vec<(u32, Arc<GpuImageTexture>)> = Vec::new();
+        let mut factor = PYRAMID_FIRST_FACTOR;
+        while width.max(height).div_ceil(factor / 2) > PYRAMID_MIN_SIDE {
+            let (source, block) = match levels.last() {
+                Some((_, previous)) => (previous.as_ref(), 2),
+                None => (image, PYRAMID_FIRST_FACTOR),
+            };
+            let output = self.downsample(source, block, &output_buffer, "edolview pyramid level");
+            levels.push((factor, Arc::new(output)));
+            factor *= 2;
+        }
+        Ok(levels)
+    }
+
+    /// `source` averaged over blocks of `block`×`block` pixels; partial blocks at the edges average what they cover.
+    fn downsample(
+        &self,
+        source: &GpuImageTexture,
+        block: u32,
+        output_buffer: &wgpu::Buffer,
+        label: &'static str,
+    ) -> GpuImageTexture {
+        let width = (source.spec.width as u32).div_ceil(block);
+        let height = (source.spec.height as u32).div_ceil(block);
+        let output = create_empty_rgba_texture(&self.device, width, height, source.spec.channels, label);
+        let mut params = ComputeParams::new(&output.spec, Recti::ZERO);
+        params.operation[0] = block;
+        let (params_buffer, bind_group) = self.bind(source, None, output_buffer, &params, Some(&output));
+        self.dispatch_2d(
+            self.compute_pipeline(&self.downsample_pipeline, "downsample_image"),
+            &bind_group,
+            width.div_ceil(16),
+            height.div_ceil(16),
+        );
+        drop(params_buffer);
+        output
+    }
+
     pub fn minmax(&self, image: &GpuImageTexture, rect: Recti) -> Result<(Vec<f32>, Vec<f32>)> {
         let channels = image.spec.channels as usize;
         let init = [u32::MAX, u32::MAX, u32::MAX, u32::MAX, 0, 0, 0, 0];
@@ -769,11 +845,12 @@ impl GpuComputeContext {
         )
     }
 
-    pub fn histogram(&self, image: &GpuImageTexture) -> Result<Vec<Vec<f32>>> {
+    /// 256 bins over 0..1 per channel of the pixels in `rect`, or of the whole image when `rect` is empty.
+    pub fn histogram(&self, image: &GpuImageTexture, rect: Recti) -> Result<Vec<Vec<f32>>> {
         let size = 4 * 256 * std::mem::size_of::<u32>();
         let zeros = vec![0u8; size];
         let output = self.storage_buffer(&zeros, "edolview histogram output");
-        let params = ComputeParams::new(&image.spec, Recti::ZERO);
+        let params = ComputeParams::new(&image.spec, rect);
         let groups = reduction_group_count(&params);
         self.run_readback(
             self.compute_pipeline(&self.histogram_pipeline, "histogram"),
@@ -895,6 +972,96 @@ impl GpuComputeContext {
         )
     }
 
+    /// Standard deviation per channel inside `rect`, multiplied by `scale`. The mean is taken first so the squared
+    /// deviations are summed without the cancellation of `E[x²] - E[x]²`.
+    pub fn std(&self, image: &GpuImageTexture, rect: Recti, scale: f64) -> Result<Vec<f64>> {
+        let mean = self.mean(image, rect, MeanDim::All)?;
+        if mean.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut params = ComputeParams::new(&image.spec, rect);
+        params.operation[0] = 3;
+        for (value, mean) in params.values.iter_mut().zip(&mean) {
+            *value = *mean as f32;
+        }
+        let groups = reduction_group_count(&params);
+        let size = groups as u64 * 16;
+        let output = self.empty_storage_buffer(size, "edolview deviation partials");
+        self.run_readback(
+            self.compute_pipeline(&self.sum_pipeline, "sum_values"),
+            image,
+            None,
+            &output,
+            &params,
+            groups,
+            1,
+            size,
+            |bytes| {
+                let partials: &[f32] = bytemuck::cast_slice(bytes);
+                let count = (params.roi[2] as f64) * (params.roi[3] as f64);
+                (0..image.spec.channels as usize)
+                    .map(|channel| {
+                        let sum: f64 = partials.chunks_exact(4).map(|partial| partial[channel] as f64).sum();
+                        scale * (sum / count).sqrt()
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    /// Mean absolute difference over every channel inside `rect`, multiplied by `scale`.
+    pub fn mae(&self, lhs: &GpuImageTexture, rhs: &GpuImageTexture, rect: Recti, scale: f64) -> Result<f64> {
+        ensure_compatible(lhs, rhs)?;
+        let mut params = ComputeParams::new(&lhs.spec, rect);
+        params.operation[0] = 2;
+        let groups = reduction_group_count(&params);
+        let size = groups as u64 * 16;
+        let output = self.empty_storage_buffer(size, "edolview absolute error partials");
+        self.run_readback(
+            self.compute_pipeline(&self.sum_pipeline, "sum_values"),
+            lhs,
+            Some(rhs),
+            &output,
+            &params,
+            groups,
+            1,
+            size,
+            |bytes| {
+                let values: &[f32] = bytemuck::cast_slice(bytes);
+                let absolute_error: f64 = values.iter().map(|&v| v as f64).sum();
+                let sample_count = params.roi[2] as f64 * params.roi[3] as f64 * lhs.spec.channels as f64;
+                scale * absolute_error / sample_count
+            },
+        )
+    }
+
+    /// Multi-scale SSIM over five dyadic scales with the weights of Wang et al. (2003). Every scale uses the full
+    /// SSIM rather than only its contrast and structure terms. Scales smaller than the SSIM window are skipped and
+    /// the weights of the others renormalized.
+    pub fn ms_ssim(&self, lhs: &GpuImageTexture, rhs: &GpuImageTexture, rect: Recti) -> Result<f64> {
+        ensure_compatible(lhs, rhs)?;
+        let scales = ms_ssim_scales(normalized_rect(&lhs.spec, rect));
+        if scales.is_empty() {
+            return Err(eyre!("Region is smaller than the {MS_SSIM_MIN_SIDE} px SSIM window"));
+        }
+        let dummy_output = self.empty_storage_buffer(4, "edolview ms-ssim dummy output");
+        let mut level: Option<(GpuImageTexture, GpuImageTexture)> = None;
+        let mut ssims = Vec::with_capacity(scales.len());
+        for (scale, (rect, weight)) in scales.into_iter().enumerate() {
+            if scale > 0 {
+                let (a, b) = level.as_ref().map_or((lhs, rhs), |(a, b)| (a, b));
+                let next = (
+                    self.downsample(a, 2, &dummy_output, "edolview ms-ssim level"),
+                    self.downsample(b, 2, &dummy_output, "edolview ms-ssim level"),
+                );
+                level = Some(next);
+            }
+            let (a, b) = level.as_ref().map_or((lhs, rhs), |(a, b)| (a, b));
+            ssims.push((self.ssim(a, b, rect)?, weight));
+        }
+        Ok(combine_ms_ssim(&ssims))
+    }
+
     pub fn ssim(&self, lhs: &GpuImageTexture, rhs: &GpuImageTexture, rect: Recti) -> Result<f64> {
         ensure_compatible(lhs, rhs)?;
         let params = ComputeParams::new(&lhs.spec, rect);
@@ -1186,6 +1353,8 @@ fn create_empty_rgba_texture(
             height: height as i32,
             channels,
             dtype: PixelType::F32,
+            gray_alpha: false,
+            preview_scale: 1,
         },
     }
 }
@@ -1209,6 +1378,37 @@ fn normalized_rect(spec: &ImageSpec, rect: Recti) -> Recti {
     }
 }
 
+/// Region and weight of each MS-SSIM scale, finest first. Every scale halves the region of the one before,
+/// rounding outward as the 2× downsampling does, and the first scale smaller than the SSIM window ends the list.
+fn ms_ssim_scales(rect: Recti) -> Vec<(Recti, f64)> {
+    let mut scales: Vec<(Recti, f64)> = Vec::new();
+    for weight in MS_SSIM_WEIGHTS {
+        let rect = match scales.last() {
+            Some((previous, _)) => Recti::from_min_size(
+                Vec2i::new(previous.min.x / 2, previous.min.y / 2),
+                Vec2i::new(
+                    (previous.max.x + 1) / 2 - previous.min.x / 2,
+                    (previous.max.y + 1) / 2 - previous.min.y / 2,
+                ),
+            ),
+            None => rect,
+        };
+        if rect.width().min(rect.height()) < MS_SSIM_MIN_SIDE {
+            break;
+        }
+        scales.push((rect, weight));
+    }
+    scales
+}
+
+/// Weighted geometric mean of the SSIM of each scale, with the weights of the scales present renormalized.
+/// SSIM can be negative, so each is floored at a small positive value first.
+fn combine_ms_ssim(ssims: &[(f64, f64)]) -> f64 {
+    let weight_total: f64 = ssims.iter().map(|(_, weight)| weight).sum();
+    let product: f64 = ssims.iter().map(|(ssim, weight)| ssim.max(1e-6).powf(*weight)).product();
+    product.powf(1.0 / weight_total)
+}
+
 fn reduction_group_count(params: &ComputeParams) -> u32 {
     let count = params.roi[2].saturating_mul(params.roi[3]);
     count.div_ceil(WORKGROUP_SIZE).clamp(1, REDUCTION_GROUPS)
@@ -1336,10 +1536,17 @@ fn sum_values(
         let a = channel_mask(textureLoad(source_a, roi_pixel(index), 0), params.image.z);
         if (params.operation.x == 0u) {
             sum += a;
+        } else if (params.operation.x == 3u) {
+            let d = channel_mask(a - params.values, params.image.z);
+            sum += d * d;
         } else {
             let b = channel_mask(textureLoad(source_b, roi_pixel(index), 0), params.image.z);
             let d = a - b;
-            sum += d * d;
+            if (params.operation.x == 2u) {
+                sum += abs(d);
+            } else {
+                sum += d * d;
+            }
         }
         index += stride;
     }
@@ -1418,13 +1625,39 @@ fn compare_images(@builtin(global_invocation_id) gid: vec3<u32>) {
     if (gid.x >= params.image.x || gid.y >= params.image.y) { return; }
     let coord = vec2<i32>(gid.xy);
     let pair = normalize_channels(textureLoad(source_a, coord, 0), textureLoad(source_b, coord, 0));
-    var value = pair[0] - pair[1];
+    var value = (pair[0] - pair[1]) * params.values.x;
     if (params.operation.x == 1u) {
         value = mix(pair[0], pair[1], params.values.x);
+    } else if (params.operation.x == 3u) {
+        // Images with fewer than three channels count as gray.
+        let left = textureLoad(source_a, coord, 0);
+        let right = textureLoad(source_b, coord, 0);
+        let right_rgb = select(right.xxx, right.xyz, params.operation.z >= 3u);
+        value = vec4<f32>(left.x, right_rgb.y, right_rgb.z, 1.0);
     }
     textureStore(destination, coord, value);
 }
 
+@compute @workgroup_size(16, 16)
+fn downsample_image(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= params.image.x || gid.y >= params.image.y) { return; }
+    let block = i32(params.operation.x);
+    let extent = vec2<i32>(textureDimensions(source_a));
+    let origin = vec2<i32>(gid.xy) * block;
+    var sum = vec4<f32>(0.0);
+    var count = 0.0;
+    for (var y = 0; y < block; y++) {
+        for (var x = 0; x < block; x++) {
+            let coord = origin + vec2<i32>(x, y);
+            if (coord.x < extent.x && coord.y < extent.y) {
+                sum += textureLoad(source_a, coord, 0);
+                count += 1.0;
+            }
+        }
+    }
+    textureStore(destination, vec2<i32>(gid.xy), sum / max(count, 1.0));
+}
+
 fn reflect101(value: i32, length: i32) -> i32 {
     if (length <= 1) { return 0; }
     var result = value;
@@ -1669,6 +1902,7 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
         if params.image.w > 1u { output.y = input[1]; }
         if params.image.w > 2u { output.z = input[2]; }
         if params.image.w > 3u { output.w = input[3]; }
+        if (params.transform.y & 4u) != 0u { output = output.zyxw; }
     } else if color == 1u {
         let entries = 1u << params.memory.w;
         let index = u32(round(input[0] * f32(entries - 1u)));
@@ -1721,34 +1955,52 @@ fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
 }
 "#;
 
+/// Process-wide compute context on the first available adapter, shared by every GPU test so textures and
+/// renderers all live on the installed device.
+#[cfg(test)]
+pub(crate) fn test_gpu_compute() -> Arc<GpuComputeContext> {
+    static CONTEXT: std::sync::OnceLock<Arc<GpuComputeContext>> = std::sync::OnceLock::new();
+    Arc::clone(CONTEXT.get_or_init(|| {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            compatible_surface: None,
+        }))
+        .expect("GPU adapter");
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("edolview compute test device"),
+            required_features: wgpu::Features::empty(),
+            required_limits: adapter.limits(),
+            experimental_features: wgpu::ExperimentalFeatures::disabled(),
+            memory_hints: wgpu::MemoryHints::MemoryUsage,
+            trace: wgpu::Trace::Off,
+        }))
+        .expect("GPU device");
+        install_gpu_compute(&device, &queue, adapter.get_info().backend);
+        gpu_compute().unwrap()
+    }))
+}
+
+#[cfg(test)]
+impl GpuComputeContext {
+    pub(crate) fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    pub(crate) fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::model::image_io::{DecodedColor, DecodedImage, DecodedLayout, DecodedPixels, DecodedTransform};
-    use crate::model::{Image, ImageData, PixelType};
+    use crate::model::{ChannelOrder, Image, ImageData, PixelType};
 
     fn context() -> Arc<GpuComputeContext> {
-        static CONTEXT: std::sync::OnceLock<Arc<GpuComputeContext>> = std::sync::OnceLock::new();
-        Arc::clone(CONTEXT.get_or_init(|| {
-            let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
-            let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                force_fallback_adapter: false,
-                compatible_surface: None,
-            }))
-            .expect("GPU adapter");
-            let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
-                label: Some("edolview compute test device"),
-                required_features: wgpu::Features::empty(),
-                required_limits: adapter.limits(),
-                experimental_features: wgpu::ExperimentalFeatures::disabled(),
-                memory_hints: wgpu::MemoryHints::MemoryUsage,
-                trace: wgpu::Trace::Off,
-            }))
-            .expect("GPU device");
-            install_gpu_compute(&device, &queue, adapter.get_info().backend);
-            gpu_compute().unwrap()
-        }))
+        test_gpu_compute()
     }
 
     fn test_image(offset: f32) -> ImageData {
@@ -1816,6 +2068,139 @@ mod tests {
         total / (spec.width * spec.height * spec.channels) as f64
     }
 
+    /// Fixed 4-channel image with structure at several scales, shifted by `phase`.
+    fn pattern_image(width: i32, height: i32, phase: f32) -> ImageData {
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .flat_map(|(x, y)| {
+                (0..4).map(move |channel| {
+                    let fine = ((x * 7 + y * 3 + channel * 5) % 11) as f32 / 10.0;
+                    let coarse =
+                        ((x as f32 * 0.2 + phase).sin() * (y as f32 * 0.15 + channel as f32).cos() + 1.0) / 2.0;
+                    0.3 * fine + 0.7 * coarse
+                })
+            })
+            .collect();
+        ImageData::from_f32(ImageSpec::new(width, height, 4, PixelType::F32), pixels).unwrap()
+    }
+
+    /// 2×2 box average; blocks cut off by an odd edge average the pixels they cover, like `downsample_image`.
+    fn cpu_downsample(image: &ImageData) -> ImageData {
+        let spec = image.spec();
+        let pixels = image.pixels().unwrap();
+        let (width, height, channels) = ((spec.width + 1) / 2, (spec.height + 1) / 2, spec.channels);
+        let mut output = Vec::with_capacity((width * height * channels) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                for channel in 0..channels {
+                    let block: Vec<f32> = [(0, 0), (1, 0), (0, 1), (1, 1)]
+                        .into_iter()
+                        .map(|(dx, dy)| (2 * x + dx, 2 * y + dy))
+                        .filter(|&(sx, sy)| sx < spec.width && sy < spec.height)
+                        .map(|(sx, sy)| pixels[((sy * spec.width + sx) * channels + channel) as usize])
+                        .collect();
+                    output.push(block.iter().sum::<f32>() / block.len() as f32);
+                }
+            }
+        }
+        ImageData::from_f32(ImageSpec::new(width, height, channels, PixelType::F32), output).unwrap()
+    }
+
+    /// Samples of `channel` inside `rect`.
+    fn cpu_samples(image: &ImageData, rect: Recti, channel: i32) -> Vec<f64> {
+        let spec = image.spec();
+        let pixels = image.pixels().unwrap();
+        (rect.min.y..rect.max.y)
+            .flat_map(|y| (rect.min.x..rect.max.x).map(move |x| (x, y)))
+            .map(|(x, y)| pixels[((y * spec.width + x) * spec.channels + channel) as usize] as f64)
+            .collect()
+    }
+
+    #[test]
+    fn ms_ssim_scales_halve_the_region_until_the_window_no_longer_fits() {
+        let rect = |x, y, w, h| Recti::from_min_size(Vec2i::new(x, y), Vec2i::new(w, h));
+        let scales = ms_ssim_scales(rect(0, 0, 100, 90));
+        let rects: Vec<Recti> = scales.iter().map(|(rect, _)| *rect).collect();
+        assert_eq!(
+            rects,
+            [
+                rect(0, 0, 100, 90),
+                rect(0, 0, 50, 45),
+                rect(0, 0, 25, 23),
+                rect(0, 0, 13, 12)
+            ]
+        );
+        let weights: Vec<f64> = scales.iter().map(|(_, weight)| *weight).collect();
+        assert_eq!(weights, MS_SSIM_WEIGHTS[..4]);
+
+        // Odd offsets round outward so the halved region still covers every downsampled pixel of the original.
+        let rects: Vec<Recti> = ms_ssim_scales(rect(5, 3, 31, 27)).into_iter().map(|(rect, _)| rect).collect();
+        assert_eq!(rects, [rect(5, 3, 31, 27), rect(2, 1, 16, 14)]);
+
+        assert_eq!(ms_ssim_scales(rect(0, 0, 400, 400)).len(), MS_SSIM_WEIGHTS.len());
+        assert_eq!(ms_ssim_scales(rect(0, 0, 11, 11)).len(), 1);
+        assert!(ms_ssim_scales(rect(0, 0, 10, 500)).is_empty());
+    }
+
+    #[test]
+    fn ms_ssim_renormalizes_the_weights_of_the_scales_present() {
+        let [w0, w1, ..] = MS_SSIM_WEIGHTS;
+        assert!((combine_ms_ssim(&[(1.0, w0), (1.0, w1)]) - 1.0).abs() < 1e-12);
+        assert!((combine_ms_ssim(&[(0.8, w0)]) - 0.8).abs() < 1e-12);
+        let expected = (0.9f64.ln() * w0 + 0.5f64.ln() * w1) / (w0 + w1);
+        assert!((combine_ms_ssim(&[(0.9, w0), (0.5, w1)]) - expected.exp()).abs() < 1e-12);
+        // Negative SSIM is floored instead of turning the geometric mean into NaN.
+        let floored = (1e-6f64.ln() * w0 + 0.9f64.ln() * w1) / (w0 + w1);
+        assert!((combine_ms_ssim(&[(-0.2, w0), (0.9, w1)]) - floored.exp()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn gpu_mae_std_and_ms_ssim_match_cpu_references() {
+        let compute = context();
+        let lhs = pattern_image(48, 44, 0.0);
+        let rhs = pattern_image(48, 44, 0.4);
+        let lhs_texture = lhs.gpu_texture().unwrap();
+        let rhs_texture = rhs.gpu_texture().unwrap();
+        let full = Recti::from_min_size(Vec2i::new(0, 0), Vec2i::new(48, 44));
+        let inner = Recti::from_min_size(Vec2i::new(3, 5), Vec2i::new(37, 33));
+
+        for rect in [full, inner] {
+            let (mut absolute_error, mut samples) = (0.0, 0.0);
+            for channel in 0..4 {
+                let a = cpu_samples(&lhs, rect, channel);
+                let b = cpu_samples(&rhs, rect, channel);
+                absolute_error += a.iter().zip(&b).map(|(a, b)| (a - b).abs()).sum::<f64>();
+                samples += a.len() as f64;
+            }
+            let mae = compute.mae(&lhs_texture, &rhs_texture, rect, 255.0).unwrap();
+            assert!((mae - 255.0 * absolute_error / samples).abs() < 1e-3, "{rect:?}: {mae}");
+
+            let std = compute.std(&lhs_texture, rect, 2.0).unwrap();
+            for channel in 0..4 {
+                let a = cpu_samples(&lhs, rect, channel);
+                let mean = a.iter().sum::<f64>() / a.len() as f64;
+                let variance = a.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / a.len() as f64;
+                let expected = 2.0 * variance.sqrt();
+                assert!((std[channel as usize] - expected).abs() < 1e-5, "{rect:?} {channel}: {std:?}");
+            }
+        }
+
+        let (mut a, mut b) = (lhs, rhs);
+        let mut ssims = Vec::new();
+        for (scale, (rect, weight)) in ms_ssim_scales(full).into_iter().enumerate() {
+            if scale > 0 {
+                (a, b) = (cpu_downsample(&a), cpu_downsample(&b));
+            }
+            assert_eq!((a.spec().width, a.spec().height), (rect.width(), rect.height()));
+            ssims.push((cpu_ssim(&a, &b), weight));
+        }
+        assert_eq!(ssims.len(), 3);
+        let expected = combine_ms_ssim(&ssims);
+        let actual = compute.ms_ssim(&lhs_texture, &rhs_texture, full).unwrap();
+        assert!((actual - expected).abs() < 1e-4, "{actual} vs {expected}");
+        assert!(expected > 0.05 && expected < 0.95, "{expected}");
+    }
+
     #[test]
     fn gpu_statistics_match_expected_values() {
         let compute = context();
@@ -1839,27 +2224,66 @@ mod tests {
         assert_eq!(columns.len(), 64 * 4);
         assert_eq!(rows.len(), 32 * 4);
 
-        let histogram = compute.histogram(&texture).unwrap();
+        let std = compute.std(&texture, Recti::ZERO, 1.0).unwrap();
+        // Uniform over 2048 steps of 1/2048: sqrt((n² - 1) / 12) / n.
+        assert!((std[0] - (2048.0f64 * 2048.0 - 1.0).sqrt() / 12f64.sqrt() / 2048.0).abs() < 1e-5);
+        assert!((std[1] - std[0] / 2.0).abs() < 1e-5);
+        assert!(std[3].abs() < 1e-6);
+
+        let histogram = compute.histogram(&texture, Recti::ZERO).unwrap();
         assert_eq!(histogram.len(), 4);
         assert_eq!(histogram[0].iter().sum::<f32>(), 2048.0);
         assert_eq!(histogram[3].iter().sum::<f32>(), 0.0);
+        let corner = compute
+            .histogram(&texture, Recti::from_min_size(Vec2i::new(0, 0), Vec2i::new(16, 8)))
+            .unwrap();
+        assert_eq!(corner[0].iter().sum::<f32>(), 128.0);
+        assert_eq!(corner[0][0], 8.0);
+    }
+
+    #[test]
+    fn pyramid_levels_preserve_the_mean() {
+        let compute = context();
+        let spec = ImageSpec::new(2048, 96, 1, PixelType::F32);
+        let pixels = (0..spec.width * spec.height).map(|index| (index % 7) as f32).collect();
+        let image = ImageData::from_f32(spec, pixels).unwrap();
+        let texture = image.gpu_texture().unwrap();
+        let base_mean = compute.mean(&texture, Recti::ZERO, MeanDim::All).unwrap()[0];
+
+        let levels = compute.pyramid(&texture).unwrap();
+        let shapes: Vec<_> = levels
+            .iter()
+            .map(|(factor, level)| (*factor, level.spec.width, level.spec.height))
+            .collect();
+        assert_eq!(shapes, [(4, 512, 24), (8, 256, 12)]);
+        for (_, level) in &levels {
+            let mean = compute.mean(level, Recti::ZERO, MeanDim::All).unwrap()[0];
+            assert!((mean - base_mean).abs() < 1e-4, "{mean} != {base_mean}");
+        }
     }
 
     #[test]
     fn native_upload_normalizes_and_fuses_layout_and_palette() {
         let compute = context();
 
-        let rgba = ImageData::from_raw_bytes(&[0, 64, 128, 255, 255, 128, 64, 0], 2, 1, 4, PixelType::U8).unwrap();
+        let bytes = [0, 64, 128, 255, 255, 128, 64, 0];
+        let rgba = ImageData::from_raw_bytes(&bytes, 2, 1, 4, PixelType::U8, ChannelOrder::Rgb).unwrap();
         let (mins, maxs) = compute.minmax(&rgba.gpu_texture().unwrap(), Recti::ZERO).unwrap();
         assert_eq!(mins, vec![0.0, 64.0 / 255.0, 64.0 / 255.0, 0.0]);
         assert_eq!(maxs, vec![1.0, 128.0 / 255.0, 128.0 / 255.0, 1.0]);
 
+        let bgra = ImageData::from_raw_bytes(&bytes, 2, 1, 4, PixelType::U8, ChannelOrder::Bgr).unwrap();
+        let (mins, maxs) = compute.minmax(&bgra.gpu_texture().unwrap(), Recti::ZERO).unwrap();
+        assert_eq!(mins, vec![64.0 / 255.0, 64.0 / 255.0, 0.0, 0.0]);
+        assert_eq!(maxs, vec![128.0 / 255.0, 128.0 / 255.0, 1.0, 1.0]);
+
         let rgb16 = ImageData::from_raw_bytes(
             bytemuck::cast_slice(&[0_u16, 32768, u16::MAX, u16::MAX, 16384, 0]),
             2,
             1,
             3,
             PixelType::U16,
+            ChannelOrder::Rgb,
         )
         .unwrap();
         let (mins, maxs) = compute.minmax(&rgb16.gpu_texture().unwrap(), Recti::ZERO).unwrap();
@@ -1940,6 +2364,7 @@ mod tests {
                 flip_y: true,
                 swap_bytes: true,
                 scale: 2.0,
+                ..DecodedTransform::default()
             },
         )
         .unwrap();
@@ -2045,7 +2470,17 @@ mod tests {
         let actual_ssim = compute.ssim(&lhs_texture, &rhs_texture, Recti::ZERO).unwrap();
         assert!((actual_ssim - expected_ssim).abs() < 1e-4);
 
-        let derived = ImageData::derived_comparison(lhs, rhs, lhs_texture.spec.clone(), ComparisonMode::Diff, 0.5, 0);
+        let mae = compute.mae(&lhs_texture, &rhs_texture, Recti::ZERO, 2.0).unwrap();
+        assert!((mae - 2.0 * 0.00625).abs() < 1e-6);
+        let identical_ms_ssim = compute.ms_ssim(&lhs_texture, &lhs_texture, Recti::ZERO).unwrap();
+        assert!((identical_ms_ssim - 1.0).abs() < 1e-4);
+        let ms_ssim = compute.ms_ssim(&lhs_texture, &rhs_texture, Recti::ZERO).unwrap();
+        assert!(ms_ssim > 0.0 && ms_ssim < 1.0, "{ms_ssim}");
+        let tiny = Recti::from_min_size(Vec2i::new(0, 0), Vec2i::new(8, 8));
+        assert!(compute.ms_ssim(&lhs_texture, &rhs_texture, tiny).is_err());
+
+        let derived =
+            ImageData::derived_comparison(lhs, rhs, lhs_texture.spec.clone(), ComparisonMode::Diff, 0.5, 1.0, 0);
         let diff = derived.gpu_texture().unwrap();
         let (mins, maxs) = compute.minmax(&diff, Recti::ZERO).unwrap();
         for (channel, expected) in [-0.01, -0.005, 0.01, 0.0].into_iter().enumerate() {
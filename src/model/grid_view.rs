@@ -0,0 +1,110 @@
+//! N-up grid comparison: up to nine assets from the image list shown side by side with one shared pan and zoom.
+
+use indexmap::IndexMap;
+
+use super::SharedAsset;
+
+/// Most panes of a grid, filling a 3×3 layout.
+pub const GRID_MAX_PANES: usize = 9;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GridLayout {
+    #[default]
+    TwoByTwo,
+    ThreeByThree,
+}
+
+impl GridLayout {
+    pub const ALL: [Self; 2] = [Self::TwoByTwo, Self::ThreeByThree];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::TwoByTwo => "2\u{00d7}2",
+            Self::ThreeByThree => "3\u{00d7}3",
+        }
+    }
+
+    /// Columns and rows of the layout.
+    pub fn dims(self) -> (usize, usize) {
+        match self {
+            Self::TwoByTwo => (2, 2),
+            Self::ThreeByThree => (3, 3),
+        }
+    }
+
+    pub fn capacity(self) -> usize {
+        let (columns, rows) = self.dims();
+        columns * rows
+    }
+}
+
+/// Assets picked for the grid, by asset hash in pane order.
+#[derive(Default)]
+pub struct GridView {
+    pub enabled: bool,
+    pub layout: GridLayout,
+    hashes: Vec<String>,
+}
+
+impl GridView {
+    pub fn contains(&self, hash: &str) -> bool {
+        self.hashes.iter().any(|h| h == hash)
+    }
+
+    /// Add the asset to the grid, or remove it when it is already there. Returns `false` when the grid is full.
+    pub fn toggle(&mut self, hash: &str) -> bool {
+        if let Some(index) = self.hashes.iter().position(|h| h == hash) {
+            self.hashes.remove(index);
+            return true;
+        }
+        if self.hashes.len() >= GRID_MAX_PANES {
+            return false;
+        }
+        self.hashes.push(hash.to_string());
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.hashes.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// The picked assets still in `assets`, as many as the layout has panes.
+    pub fn assets(&self, assets: &IndexMap<String, SharedAsset>) -> Vec<SharedAsset> {
+        self.hashes
+            .iter()
+            .filter_map(|hash| assets.get(hash).cloned())
+            .take(self.layout.capacity())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggled_assets_fill_the_grid_in_order() {
+        let mut grid = GridView::default();
+        for index in 0..GRID_MAX_PANES {
+            assert!(grid.toggle(&format!("hash{index}")));
+        }
+        assert!(!grid.toggle("one too many"));
+        assert!(grid.toggle("hash0"));
+        assert!(!grid.contains("hash0"));
+        assert_eq!(grid.len(), GRID_MAX_PANES - 1);
+        assert!(grid.toggle("hash0"));
+        assert_eq!(grid.hashes.last().map(String::as_str), Some("hash0"));
+
+        assert_eq!(GridLayout::TwoByTwo.capacity(), 4);
+        assert_eq!(GridLayout::ThreeByThree.dims(), (3, 3));
+        assert!(grid.assets(&IndexMap::new()).is_empty());
+    }
+}
@@ -0,0 +1,71 @@
+//! A second, single-channel image from the image list blended through a colormap over the shown image, e.g. an
+//! attention map or an error map over the original photo.
+
+use indexmap::IndexMap;
+
+use super::{MinMaxTotal, SharedAsset};
+
+pub struct HeatmapOverlay {
+    /// Hash of the heatmap asset in the image list.
+    pub hash: String,
+    /// Mono colormap the heatmap values are drawn with.
+    pub colormap: String,
+    pub opacity: f32,
+    /// Map the heatmap's own value range onto the colormap instead of `min_v..max_v`.
+    pub auto_range: bool,
+    pub min_v: f32,
+    pub max_v: f32,
+}
+
+impl HeatmapOverlay {
+    pub fn new(hash: String) -> Self {
+        Self {
+            hash,
+            colormap: "inferno".to_string(),
+            opacity: 0.5,
+            auto_range: true,
+            min_v: 0.0,
+            max_v: 1.0,
+        }
+    }
+
+    /// The heatmap asset, while it is still in the image list.
+    pub fn asset(&self, assets: &IndexMap<String, SharedAsset>) -> Option<SharedAsset> {
+        assets.get(&self.hash).cloned()
+    }
+
+    /// Values mapped to the ends of the colormap. Only the first channel is drawn, so only its range counts; a
+    /// constant heatmap gets a unit range so it still maps to the low end.
+    pub fn range(&self, min_max: &MinMaxTotal) -> (f32, f32) {
+        let (min, max) = if self.auto_range {
+            (min_max.min(0), min_max.max(0))
+        } else {
+            (self.min_v, self.max_v)
+        };
+        if max > min {
+            (min, max)
+        } else {
+            (min, min + 1.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_follows_the_first_channel_or_the_manual_limits() {
+        let mut heatmap = HeatmapOverlay::new("hash".to_string());
+        let min_max = MinMaxTotal::new(vec![-0.5, 0.0], vec![2.0, 9.0]);
+        assert_eq!(heatmap.range(&min_max), (-0.5, 2.0));
+        assert_eq!(heatmap.range(&MinMaxTotal::new(vec![3.0], vec![3.0])), (3.0, 4.0));
+
+        heatmap.auto_range = false;
+        heatmap.min_v = 0.25;
+        heatmap.max_v = 0.75;
+        assert_eq!(heatmap.range(&min_max), (0.25, 0.75));
+        heatmap.max_v = 0.0;
+        assert_eq!(heatmap.range(&min_max), (0.25, 1.25));
+    }
+}
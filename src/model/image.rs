@@ -1,14 +1,18 @@
-use crate::model::{gpu_compute, GpuImageTexture, MeanDim, MeanProcessor, Recti};
+use crate::model::{gpu_compute, ChannelOrder, GpuImageTexture, MeanCache, MeanDim, MeanProcessor, Recti};
+use crate::util::math_ext::vec2i;
 use color_eyre::eyre::{eyre, Result};
 use std::f64;
 use std::sync::{Arc, LazyLock, Mutex};
 use std::{
     fs, mem,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::atomic::{AtomicU64, Ordering},
     sync::OnceLock,
 };
 
+/// Pixel count from which images get a downsampled GPU pyramid for low zoom levels.
+pub const PYRAMID_MIN_PIXELS: u64 = 16 * 1024 * 1024;
+
 /// Source component type. Numeric values intentionally match the existing
 /// socket protocol's legacy depth codes so external clients remain compatible.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -25,6 +29,17 @@ pub enum PixelType {
 }
 
 impl PixelType {
+    pub const ALL: [Self; 8] = [
+        Self::U8,
+        Self::I8,
+        Self::U16,
+        Self::I16,
+        Self::I32,
+        Self::F16,
+        Self::F32,
+        Self::F64,
+    ];
+
     pub fn from_protocol_code(code: u32) -> Result<Self> {
         match code {
             0 => Ok(Self::U8),
@@ -83,6 +98,11 @@ pub struct ImageSpec {
     pub height: i32,
     pub channels: i32,
     pub dtype: PixelType,
+    /// Two channels holding luminance and alpha, as declared by the source file.
+    pub gray_alpha: bool,
+    /// Pixels of the full image per pixel side of a reduced loading preview, which the viewer draws enlarged to
+    /// the size of the image that replaces it; 1 for complete images.
+    pub preview_scale: u32,
 }
 
 // data of ImageSpec should be always f32
@@ -93,9 +113,18 @@ impl ImageSpec {
             height,
             channels,
             dtype,
+            gray_alpha: false,
+            preview_scale: 1,
         }
     }
 
+    /// Size in full-image pixels. A reduced loading preview covers `preview_scale` times its own size, which can
+    /// exceed the final image by less than `preview_scale` pixels.
+    pub fn full_size(&self) -> (i32, i32) {
+        let scale = self.preview_scale as i32;
+        (self.width * scale, self.height * scale)
+    }
+
     pub fn total_bytes(&self) -> usize {
         (self.width as usize) * (self.height as usize) * (self.channels as usize) * mem::size_of::<f32>()
     }
@@ -104,6 +133,15 @@ impl ImageSpec {
         (self.channels as usize) * mem::size_of::<f32>()
     }
 
+    /// Index of the alpha channel: the fourth of RGBA images, or the second of declared gray+alpha images.
+    pub fn alpha_channel(&self) -> Option<usize> {
+        match self.channels {
+            4 => Some(3),
+            2 if self.gray_alpha => Some(1),
+            _ => None,
+        }
+    }
+
     pub fn pixel_values_to_string<T: Into<f64> + Copy>(&self, vals: &[T]) -> String {
         let alpha = self.dtype.alpha();
         let is_float = self.dtype.is_floating();
@@ -125,6 +163,9 @@ pub trait Image {
     fn spec(&self) -> ImageSpec;
     fn data(&self) -> Option<&[f32]>;
     fn gpu_texture(&self) -> Result<Arc<GpuImageTexture>>;
+    fn gpu_pyramid(&self) -> GpuPyramid {
+        Vec::new()
+    }
     fn get_pixel_at(&self, x: i32, y: i32) -> Result<PixelValues<'_>> {
         let spec = self.spec();
         if x < 0 || x >= spec.width || y < 0 || y >= spec.height {
@@ -138,6 +179,76 @@ pub trait Image {
             .map(|data| PixelValues::Borrowed(&data[start / 4..end / 4]))
             .ok_or_else(|| eyre!("CPU pixel data is unavailable for this GPU-derived image"))
     }
+
+    /// Bounding box of pixels with any finite, non-zero color channel, or `None` when there are none. Alpha is not
+    /// tested, so opaque black borders are not content. Rows and columns are scanned inward from the edges, so
+    /// images with content near the borders finish early.
+    fn content_rect(&self) -> Result<Option<Recti>> {
+        let spec = self.spec();
+        let data = self
+            .data()
+            .ok_or_else(|| eyre!("CPU pixel data is unavailable for this image"))?;
+        let (width, height) = (spec.width as usize, spec.height as usize);
+        let channels = spec.channels as usize;
+        // The alpha channel, when there is one, is the last one.
+        let color_channels = spec.alpha_channel().unwrap_or(channels);
+        let is_content = |x: usize, y: usize| {
+            let start = (y * width + x) * channels;
+            data[start..start + color_channels].iter().any(|v| v.is_finite() && *v != 0.0)
+        };
+        let row_has_content = |y: usize| (0..width).any(|x| is_content(x, y));
+
+        let Some(top) = (0..height).find(|&y| row_has_content(y)) else {
+            return Ok(None);
+        };
+        // Row `top` has content, so the remaining scans always find an edge.
+        let bottom = (top..height).rev().find(|&y| row_has_content(y)).unwrap_or(top);
+        let col_has_content = |x: usize| (top..=bottom).any(|y| is_content(x, y));
+        let left = (0..width).find(|&x| col_has_content(x)).unwrap_or(0);
+        let right = (left..width).rev().find(|&x| col_has_content(x)).unwrap_or(left);
+
+        Ok(Some(Recti::from_min_max(
+            vec2i(left as i32, top as i32),
+            vec2i(right as i32 + 1, bottom as i32 + 1),
+        )))
+    }
+
+    /// Average value over a square footprint of `footprint` image pixels per side centered at (`x`, `y`) in image
+    /// coordinates, weighting edge pixels by the covered area. Use `1 / zoom` as the footprint to get the value a
+    /// screen pixel represents when zoomed out; footprints of one pixel or less return the pixel under the center.
+    fn sample_area(&self, x: f32, y: f32, footprint: f32) -> Result<Vec<f32>> {
+        let spec = self.spec();
+        let (Some(data), true) = (self.data(), footprint > 1.0) else {
+            return self
+                .get_pixel_at(x.floor() as i32, y.floor() as i32)
+                .map(|pixel| pixel.to_vec());
+        };
+        let half = footprint * 0.5;
+        let x_range = (x - half).max(0.0)..(x + half).min(spec.width as f32);
+        let y_range = (y - half).max(0.0)..(y + half).min(spec.height as f32);
+        if x_range.is_empty() || y_range.is_empty() {
+            return Err(eyre!("Coordinates out of bounds"));
+        }
+
+        let channels = spec.channels as usize;
+        let coverage = |range: &std::ops::Range<f32>, i: usize| {
+            (range.end.min(i as f32 + 1.0) - range.start.max(i as f32)).max(0.0) as f64
+        };
+        let mut sum = vec![0.0f64; channels];
+        let mut total_weight = 0.0f64;
+        for j in y_range.start.floor() as usize..y_range.end.ceil() as usize {
+            let wy = coverage(&y_range, j);
+            for i in x_range.start.floor() as usize..x_range.end.ceil() as usize {
+                let weight = wy * coverage(&x_range, i);
+                let start = (j * spec.width as usize + i) * channels;
+                for (acc, &v) in sum.iter_mut().zip(&data[start..start + channels]) {
+                    *acc += v as f64 * weight;
+                }
+                total_weight += weight;
+            }
+        }
+        Ok(sum.into_iter().map(|v| (v / total_weight) as f32).collect())
+    }
 }
 
 pub static MEAN_PROCESSOR: LazyLock<MeanProcessor> = LazyLock::new(MeanProcessor::new);
@@ -259,6 +370,28 @@ impl MinMaxTotal {
     }
 }
 
+/// Downsampled GPU levels as `(factor, texture)` pairs, finest first.
+pub type GpuPyramid = Vec<(u32, Arc<GpuImageTexture>)>;
+
+/// Memory attributed to one image, in bytes.
+#[derive(Clone, Copy, Default)]
+pub struct ImageMemory {
+    /// Decoded CPU pixels. Comparison images compute on the GPU and hold none.
+    pub pixels: usize,
+    /// Histogram and integral table.
+    pub analysis: usize,
+    /// Estimated size of the texture and its pyramid levels.
+    pub gpu: usize,
+}
+
+impl std::ops::AddAssign for ImageMemory {
+    fn add_assign(&mut self, other: Self) {
+        self.pixels += other.pixels;
+        self.analysis += other.analysis;
+        self.gpu += other.gpu;
+    }
+}
+
 #[derive(Clone)]
 pub struct ImageData(Arc<ImageDataInner>);
 
@@ -266,10 +399,12 @@ struct ImageDataInner {
     id: u64,
     spec: ImageSpec,
     storage: ImageStorage,
-    gpu: OnceLock<Arc<GpuImageTexture>>,
-    gpu_init: Mutex<()>,
+    /// Uploaded texture. The lock is held while uploading so concurrent callers share one upload.
+    gpu: Mutex<Option<Arc<GpuImageTexture>>>,
     hist: OnceLock<Vec<Vec<f32>>>,
     minmax: OnceLock<MinMaxTotal>,
+    pyramid: Mutex<Option<GpuPyramid>>,
+    mean_cache: Arc<Mutex<MeanCache>>,
 }
 
 enum ImageStorage {
@@ -286,6 +421,8 @@ struct DerivedImage {
     secondary: ImageData,
     mode: crate::model::ComparisonMode,
     blend_alpha: f32,
+    /// Factor on the difference in `Diff` mode, for making small differences visible.
+    diff_gain: f32,
     channel_strategy: u32,
 }
 
@@ -306,10 +443,11 @@ impl ImageData {
             id: new_id(),
             spec,
             storage: ImageStorage::Cpu(decoded),
-            gpu: OnceLock::new(),
-            gpu_init: Mutex::new(()),
+            gpu: Mutex::new(None),
             hist: OnceLock::new(),
             minmax: OnceLock::new(),
+            pyramid: Mutex::new(None),
+            mean_cache: Arc::default(),
         })))
     }
 
@@ -318,10 +456,11 @@ impl ImageData {
             id: new_id(),
             spec: ImageSpec::new(0, 0, 0, dtype),
             storage: ImageStorage::Empty,
-            gpu: OnceLock::new(),
-            gpu_init: Mutex::new(()),
+            gpu: Mutex::new(None),
             hist: OnceLock::new(),
             minmax: OnceLock::new(),
+            pyramid: Mutex::new(None),
+            mean_cache: Arc::default(),
         }))
     }
 
@@ -331,6 +470,7 @@ impl ImageData {
         spec: ImageSpec,
         mode: crate::model::ComparisonMode,
         blend_alpha: f32,
+        diff_gain: f32,
         channel_strategy: u32,
     ) -> Self {
         Self(Arc::new(ImageDataInner {
@@ -341,15 +481,33 @@ impl ImageData {
                 secondary,
                 mode,
                 blend_alpha,
+                diff_gain,
                 channel_strategy,
             }),
-            gpu: OnceLock::new(),
-            gpu_init: Mutex::new(()),
+            gpu: Mutex::new(None),
             hist: OnceLock::new(),
             minmax: OnceLock::new(),
+            pyramid: Mutex::new(None),
+            mean_cache: Arc::default(),
         }))
     }
 
+    /// How the file stored the pixels when the decoder converted them, e.g. `PNG, indexed 4-bit`.
+    pub fn source_format(&self) -> Option<&str> {
+        match &self.0.storage {
+            ImageStorage::Cpu(image) => image.source_format.as_deref(),
+            ImageStorage::Derived(_) | ImageStorage::Empty => None,
+        }
+    }
+
+    /// Header fields the decoder reported, such as the exposure of a Radiance HDR file.
+    pub fn metadata(&self) -> &[(String, String)] {
+        match &self.0.storage {
+            ImageStorage::Cpu(image) => &image.metadata,
+            ImageStorage::Derived(_) | ImageStorage::Empty => &[],
+        }
+    }
+
     pub fn pixels(&self) -> Option<&[f32]> {
         match &self.0.storage {
             ImageStorage::Cpu(image) => image.f32_pixels(),
@@ -377,13 +535,17 @@ impl ImageData {
             return vec![];
         }
         self.gpu_texture()
-            .and_then(|texture| gpu_compute()?.histogram(&texture))
+            .and_then(|texture| gpu_compute()?.histogram(&texture, Recti::ZERO))
             .unwrap_or_else(|error| {
                 eprintln!("GPU histogram failed: {error}");
                 Vec::new()
             })
     }
 
+    pub(crate) fn mean_cache(&self) -> &Arc<Mutex<MeanCache>> {
+        &self.0.mean_cache
+    }
+
     pub fn hist(&self) -> &Vec<Vec<f32>> {
         self.0.hist.get_or_init(|| self.compute_hist())
     }
@@ -419,19 +581,50 @@ impl ImageData {
         height: i32,
         channels: i32,
         pixel_type: PixelType,
+        order: ChannelOrder,
     ) -> Result<ImageData> {
-        if width <= 0 || height <= 0 || !(1..=4).contains(&channels) {
-            return Err(eyre!("Invalid raw image dimensions or channels"));
-        }
-        let element_count = width as usize * height as usize * channels as usize;
-        let expected_bytes = element_count * pixel_type.bytes();
-        if bytes.len() != expected_bytes {
-            return Err(eyre!("Unexpected raw image size: {} != {expected_bytes}", bytes.len()));
-        }
+        let element_count = raw_element_count(bytes, width, height, channels, pixel_type.bytes())?;
         let pixels = decoded_pixels_from_bytes(bytes, element_count, pixel_type)?;
         let decoded =
             crate::model::image_io::DecodedImage::new(width as u32, height as u32, channels, pixel_type, pixels)?;
-        Self::from_decoded(decoded)
+        Self::from_decoded(decoded.with_channel_order(order))
+    }
+
+    /// Unsigned 32-bit samples, normalized by `u32::MAX` like 32-bit integer TIFFs.
+    pub fn from_raw_u32_bytes(
+        bytes: &[u8],
+        width: i32,
+        height: i32,
+        channels: i32,
+        order: ChannelOrder,
+    ) -> Result<ImageData> {
+        raw_element_count(bytes, width, height, channels, mem::size_of::<u32>())?;
+        let values = bytes
+            .chunks_exact(mem::size_of::<u32>())
+            .map(|chunk| u32::from_ne_bytes(chunk.try_into().expect("exact scalar chunk")))
+            .collect();
+        let decoded = crate::model::image_io::DecodedImage::new(
+            width as u32,
+            height as u32,
+            channels,
+            PixelType::F32,
+            crate::model::image_io::DecodedPixels::U32(values),
+        )?;
+        Self::from_decoded(decoded.with_channel_order(order))
+    }
+
+    /// Samples of a headerless binary file described by `layout`, converted from its byte order.
+    pub fn from_raw_file(file: &[u8], layout: &crate::model::RawLayout) -> Result<ImageData> {
+        let mut data = layout.data(file)?.to_vec();
+        crate::model::to_native_endian(&mut data, layout.pixel_type.bytes(), layout.big_endian);
+        Self::from_raw_bytes(
+            &data,
+            layout.width as i32,
+            layout.height as i32,
+            layout.channels as i32,
+            layout.pixel_type,
+            ChannelOrder::Rgb,
+        )
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Result<ImageData> {
@@ -439,18 +632,36 @@ impl ImageData {
     }
 
     pub fn load_from_path(path: &PathBuf) -> Result<ImageData> {
+        if let Some((archive, entry)) = crate::model::split_archive_entry_path(path) {
+            return Self::load_from_archive_entry(&archive, &entry);
+        }
+
         if !path.exists() {
             return Err(eyre!("Image does not exist: {:?}", path));
         }
 
         let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+        if !crate::supported_image::is_supported_image_extension(&ext) {
+            if let Some(plugin) = crate::plugin::plugins().loader_for(&ext) {
+                return plugin.load_image(path);
+            }
+        }
         let decoded = {
             #[cfg(debug_assertions)]
             let _timer = crate::util::timer::ScopedTimer::new("Image read");
+            let _perf = crate::util::perf::PerfScope::new("Decode");
             if ext == "pfm" {
                 crate::model::image_io::decode_pfm_owned(fs::read(path)?)?
             } else if ext == "flo" {
                 crate::model::image_io::decode_flo_owned(fs::read(path)?)?
+            } else if crate::supported_image::is_raw_binary_extension(&ext) {
+                let layout =
+                    crate::model::raw_file_layout(path).ok_or_else(|| crate::model::RawLayoutRequired(path.clone()))?;
+                return Self::from_raw_file(&fs::read(path)?, &layout);
+            } else if ext == "yuv" {
+                let frame =
+                    crate::model::yuv_file_frame(path).ok_or_else(|| crate::model::YuvFrameRequired(path.clone()))?;
+                crate::model::decode_yuv(&fs::read(path)?, frame)?
             } else if crate::supported_image::is_heif_extension(ext.as_str()) {
                 #[cfg(not(feature = "heif"))]
                 return Err(eyre!("HEIF support is not enabled"));
@@ -466,30 +677,57 @@ impl ImageData {
         Self::from_decoded(decoded)
     }
 
-    pub fn load_from_clipboard() -> Result<ImageData> {
-        #[cfg(debug_assertions)]
-        let _timer = crate::util::timer::ScopedTimer::new("Clipboard read");
-
-        let mut clipboard = arboard::Clipboard::new().map_err(|e| eyre!("Failed to open clipboard: {e}"))?;
-        let image_data = clipboard
-            .get_image()
-            .map_err(|e| eyre!("Failed to get image from clipboard: {e}"))?;
-
-        let width = image_data.width as i32;
-        let height = image_data.height as i32;
-        let bytes = std::borrow::Cow::into_owned(image_data.bytes);
-        let channels = (bytes.len() / (width as usize) / (height as usize)) as i32;
-        if width <= 0 || height <= 0 || channels <= 0 || channels > 4 {
-            return Err(eyre!("Invalid clipboard image dimensions or channels"));
+    /// Like [`Self::load_from_path`], but large PNGs and TIFFs, and with the `turbojpeg` feature large JPEGs,
+    /// report partially decoded images through `on_partial`.
+    pub fn load_from_path_incremental(path: &PathBuf, on_partial: &mut dyn FnMut(ImageData)) -> Result<ImageData> {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+        let is_jpeg = matches!(ext.as_str(), "jpg" | "jpeg" | "jpe" | "jfif");
+        if !(matches!(ext.as_str(), "png" | "tif" | "tiff") || (cfg!(feature = "turbojpeg") && is_jpeg))
+            || !path.is_file()
+        {
+            return Self::load_from_path(path);
         }
+        let _perf = crate::util::perf::PerfScope::new("Decode");
+        let decoded = crate::model::image_io::decode_path_incremental(path, &mut |partial| {
+            if let Ok(image) = Self::from_decoded(partial) {
+                on_partial(image);
+            }
+        })?;
+        Self::from_decoded(decoded)
+    }
 
-        Self::from_decoded(crate::model::image_io::DecodedImage::new(
-            width as u32,
-            height as u32,
-            channels,
-            PixelType::U8,
-            crate::model::image_io::DecodedPixels::U8(bytes),
-        )?)
+    pub fn load_from_archive_entry(archive: &Path, entry: &str) -> Result<ImageData> {
+        let bytes = {
+            #[cfg(debug_assertions)]
+            let _timer = crate::util::timer::ScopedTimer::new("Archive entry read");
+            crate::model::read_archive_entry(archive, entry)?
+        };
+
+        let ext = Path::new(entry)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let _perf = crate::util::perf::PerfScope::new("Decode");
+        let decoded = if ext == "pfm" {
+            crate::model::image_io::decode_pfm_owned(bytes)?
+        } else if ext == "flo" {
+            crate::model::image_io::decode_flo_owned(bytes)?
+        } else if crate::supported_image::is_raw_binary_extension(&ext) {
+            let entry_path = archive.join(entry);
+            let layout =
+                crate::model::raw_file_layout(&entry_path).ok_or(crate::model::RawLayoutRequired(entry_path))?;
+            return Self::from_raw_file(&bytes, &layout);
+        } else if ext == "yuv" {
+            let entry_path = archive.join(entry);
+            let frame = crate::model::yuv_file_frame(&entry_path).ok_or(crate::model::YuvFrameRequired(entry_path))?;
+            crate::model::decode_yuv(&bytes, frame)?
+        } else if crate::supported_image::is_heif_extension(ext.as_str()) {
+            return Err(eyre!("HEIF images cannot be opened from archives"));
+        } else {
+            crate::model::image_io::decode_bytes(&bytes)?
+        };
+        Self::from_decoded(decoded)
     }
 
     pub fn load_from_url(url: &str) -> Result<ImageData> {
@@ -504,29 +742,32 @@ impl ImageData {
     pub(crate) fn from_decoded(decoded: crate::model::image_io::DecodedImage) -> Result<ImageData> {
         #[cfg(debug_assertions)]
         let _timer = crate::util::timer::ScopedTimer::new("Image read postprocess");
-        let spec = ImageSpec::new(decoded.width, decoded.height, decoded.channels, decoded.pixel_type);
+        let mut spec = ImageSpec::new(decoded.width, decoded.height, decoded.channels, decoded.pixel_type);
+        spec.gray_alpha = decoded.gray_alpha;
+        spec.preview_scale = decoded.preview_scale;
         Ok(Self(Arc::new(ImageDataInner {
             id: new_id(),
             spec,
             storage: ImageStorage::Cpu(decoded),
-            gpu: OnceLock::new(),
-            gpu_init: Mutex::new(()),
+            gpu: Mutex::new(None),
             hist: OnceLock::new(),
             minmax: OnceLock::new(),
+            pyramid: Mutex::new(None),
+            mean_cache: Arc::default(),
         })))
     }
 
     pub fn gpu_texture(&self) -> Result<Arc<GpuImageTexture>> {
-        if let Some(texture) = self.0.gpu.get() {
-            return Ok(Arc::clone(texture));
-        }
-        let _init_guard = self.0.gpu_init.lock().unwrap();
-        if let Some(texture) = self.0.gpu.get() {
+        let mut gpu = self.0.gpu.lock().unwrap();
+        if let Some(texture) = gpu.as_ref() {
             return Ok(Arc::clone(texture));
         }
         let compute = gpu_compute()?;
         let texture = match &self.0.storage {
-            ImageStorage::Cpu(image) => compute.upload_decoded(image)?,
+            ImageStorage::Cpu(image) => {
+                let _perf = crate::util::perf::PerfScope::new("Texture upload");
+                compute.upload_decoded(image)?
+            }
             ImageStorage::Derived(derived) => {
                 let primary = derived.primary.gpu_texture()?;
                 let secondary = derived.secondary.gpu_texture()?;
@@ -535,14 +776,74 @@ impl ImageData {
                     &secondary,
                     &self.0.spec,
                     derived.mode,
-                    derived.blend_alpha,
+                    match derived.mode {
+                        crate::model::ComparisonMode::Diff => derived.diff_gain,
+                        _ => derived.blend_alpha,
+                    },
                     derived.channel_strategy,
                 )?
             }
             ImageStorage::Empty => return Err(eyre!("Image is empty")),
         };
-        let _ = self.0.gpu.set(Arc::clone(&texture));
-        Ok(self.0.gpu.get().cloned().unwrap_or(texture))
+        *gpu = Some(Arc::clone(&texture));
+        Ok(texture)
+    }
+
+    /// Downsampled GPU levels for drawing large images at low zoom, as `(factor, texture)` pairs. Images below
+    /// [`PYRAMID_MIN_PIXELS`] have no levels. Built on first use and kept with the image.
+    pub fn gpu_pyramid(&self) -> GpuPyramid {
+        let mut pyramid = self.0.pyramid.lock().unwrap();
+        pyramid
+            .get_or_insert_with(|| {
+                let spec = self.spec();
+                if (spec.width as u64) * (spec.height as u64) < PYRAMID_MIN_PIXELS {
+                    return Vec::new();
+                }
+                self.gpu_texture()
+                    .and_then(|texture| gpu_compute()?.pyramid(&texture))
+                    .unwrap_or_else(|error| {
+                        eprintln!("GPU pyramid failed: {error}");
+                        Vec::new()
+                    })
+            })
+            .clone()
+    }
+
+    /// Bytes held by the pixels and by the caches built from them.
+    pub fn memory_usage(&self) -> ImageMemory {
+        let pixels = match &self.0.storage {
+            ImageStorage::Cpu(_) => self.spec().total_bytes(),
+            ImageStorage::Derived(_) | ImageStorage::Empty => 0,
+        };
+        let histogram = self
+            .0
+            .hist
+            .get()
+            .map_or(0, |hist| hist.iter().map(|bins| bins.len() * std::mem::size_of::<f32>()).sum());
+        let integral = self.0.mean_cache.lock().unwrap().integral_bytes();
+        let texture = self.0.gpu.lock().unwrap().as_ref().map_or(0, |texture| texture.bytes());
+        let pyramid = self
+            .0
+            .pyramid
+            .lock()
+            .unwrap()
+            .iter()
+            .flatten()
+            .map(|(_, level)| level.bytes())
+            .sum::<usize>();
+        ImageMemory {
+            pixels,
+            analysis: histogram + integral,
+            gpu: texture + pyramid,
+        }
+    }
+
+    /// Release the GPU texture, pyramid and integral table. They are rebuilt on next use; the pixels and the small
+    /// histogram stay.
+    pub fn free_caches(&self) {
+        *self.0.gpu.lock().unwrap() = None;
+        *self.0.pyramid.lock().unwrap() = None;
+        self.0.mean_cache.lock().unwrap().release();
     }
 
     fn pixel_values(&self, x: i32, y: i32) -> Result<Vec<f32>> {
@@ -558,17 +859,20 @@ impl ImageData {
             ImageStorage::Derived(derived) => {
                 let mut lhs = derived.primary.pixel_values(x, y)?;
                 let mut rhs = derived.secondary.pixel_values(x, y)?;
+                if derived.mode == crate::model::ComparisonMode::Anaglyph {
+                    return Ok((0..3).map(|channel| anaglyph_value(&lhs, &rhs, channel)).collect());
+                }
                 normalize_pixel_channels(&mut lhs, &mut rhs, derived.channel_strategy, spec.channels as usize);
                 Ok(lhs
                     .iter()
                     .zip(rhs.iter())
                     .take(spec.channels as usize)
                     .map(|(&a, &b)| match derived.mode {
-                        crate::model::ComparisonMode::Diff => a - b,
+                        crate::model::ComparisonMode::Diff => (a - b) * derived.diff_gain,
                         crate::model::ComparisonMode::Blend => {
                             a * (1.0 - derived.blend_alpha) + b * derived.blend_alpha
                         }
-                        crate::model::ComparisonMode::Split => a,
+                        crate::model::ComparisonMode::Split | crate::model::ComparisonMode::Anaglyph => a,
                     })
                     .collect())
             }
@@ -583,17 +887,27 @@ impl ImageData {
         }
         match &self.0.storage {
             ImageStorage::Cpu(image) => image.normalized_scalar(pixel_index, channel),
+            ImageStorage::Derived(derived) if derived.mode == crate::model::ComparisonMode::Anaglyph => {
+                let secondary_channels = derived.secondary.spec().channels as usize;
+                if channel == 0 {
+                    derived.primary.scalar_at(pixel_index, 0)
+                } else {
+                    derived
+                        .secondary
+                        .scalar_at(pixel_index, if secondary_channels >= 3 { channel } else { 0 })
+                }
+            }
             ImageStorage::Derived(derived) => {
                 let primary_channel = if derived.channel_strategy == 1 { 0 } else { channel };
                 let secondary_channel = if derived.channel_strategy == 2 { 0 } else { channel };
                 let lhs = derived.primary.scalar_at(pixel_index, primary_channel)?;
                 let rhs = derived.secondary.scalar_at(pixel_index, secondary_channel)?;
                 Some(match derived.mode {
-                    crate::model::ComparisonMode::Diff => lhs - rhs,
+                    crate::model::ComparisonMode::Diff => (lhs - rhs) * derived.diff_gain,
                     crate::model::ComparisonMode::Blend => {
                         lhs * (1.0 - derived.blend_alpha) + rhs * derived.blend_alpha
                     }
-                    crate::model::ComparisonMode::Split => lhs,
+                    crate::model::ComparisonMode::Split | crate::model::ComparisonMode::Anaglyph => lhs,
                 })
             }
             ImageStorage::Empty => None,
@@ -638,6 +952,10 @@ impl Image for ImageData {
         ImageData::gpu_texture(self)
     }
 
+    fn gpu_pyramid(&self) -> GpuPyramid {
+        ImageData::gpu_pyramid(self)
+    }
+
     fn get_pixel_at(&self, x: i32, y: i32) -> Result<PixelValues<'_>> {
         if let Some(pixels) = self.pixels() {
             let spec = self.spec();
@@ -658,6 +976,18 @@ impl Image for ImageData {
     }
 }
 
+fn raw_element_count(bytes: &[u8], width: i32, height: i32, channels: i32, sample_bytes: usize) -> Result<usize> {
+    if width <= 0 || height <= 0 || !(1..=4).contains(&channels) {
+        return Err(eyre!("Invalid raw image dimensions or channels"));
+    }
+    let element_count = width as usize * height as usize * channels as usize;
+    let expected_bytes = element_count * sample_bytes;
+    if bytes.len() != expected_bytes {
+        return Err(eyre!("Unexpected raw image size: {} != {expected_bytes}", bytes.len()));
+    }
+    Ok(element_count)
+}
+
 fn decoded_pixels_from_bytes(
     bytes: &[u8],
     element_count: usize,
@@ -694,10 +1024,25 @@ fn decoded_pixels_from_bytes(
         PixelType::F64 => crate::model::image_io::DecodedPixels::F32(
             read_values!(f64).into_iter().map(|value| value as f32).collect(),
         ),
-        PixelType::F16 => crate::model::image_io::DecodedPixels::F16(read_values!(u16)),
+        PixelType::F16 => crate::model::image_io::DecodedPixels::F32(
+            read_values!(u16)
+                .into_iter()
+                .map(|bits| half::f16::from_bits(bits).to_f32())
+                .collect(),
+        ),
     })
 }
 
+/// Channel `channel` of the red-cyan anaglyph of `left` and `right`: red from the left eye, green and blue from the
+/// right. Pixels with fewer than three channels count as gray.
+fn anaglyph_value(left: &[f32], right: &[f32], channel: usize) -> f32 {
+    match channel {
+        0 => left[0],
+        _ if right.len() >= 3 => right[channel],
+        _ => right[0],
+    }
+}
+
 fn normalize_pixel_channels(lhs: &mut Vec<f32>, rhs: &mut Vec<f32>, strategy: u32, output_channels: usize) {
     match strategy {
         1 => lhs.resize(output_channels, lhs[0]),
@@ -720,6 +1065,20 @@ mod tests {
     use super::*;
     use std::time::Instant;
 
+    #[test]
+    fn content_rect_ignores_alpha() {
+        let mut pixels = vec![0.0; 3 * 2 * 4];
+        // Every pixel is opaque; only (2, 1) has color.
+        pixels.iter_mut().skip(3).step_by(4).for_each(|alpha| *alpha = 1.0);
+        pixels[(3 + 2) * 4] = 0.5;
+        let image = ImageData::from_f32(ImageSpec::new(3, 2, 4, PixelType::F32), pixels).unwrap();
+        let content = image.content_rect().unwrap().unwrap();
+        assert_eq!((content.min, content.max), (vec2i(2, 1), vec2i(3, 2)));
+
+        let blank = ImageData::from_f32(ImageSpec::new(2, 2, 4, PixelType::F32), vec![0.0; 16]).unwrap();
+        assert!(blank.content_rect().unwrap().is_none());
+    }
+
     #[test]
     fn from_f32_keeps_the_input_allocation() {
         let pixels = vec![0.0, 0.25, 0.5, 1.0];
@@ -735,7 +1094,9 @@ mod tests {
         let mut bytes = vec![0_u8];
         bytes.extend_from_slice(bytemuck::cast_slice(&values));
 
-        let image = ImageData::from_raw_bytes(&bytes[1..], values.len() as i32, 1, 1, PixelType::U16).unwrap();
+        let image =
+            ImageData::from_raw_bytes(&bytes[1..], values.len() as i32, 1, 1, PixelType::U16, ChannelOrder::Rgb)
+                .unwrap();
 
         let expected: Vec<f32> = values.iter().map(|&value| value as f32 / u16::MAX as f32).collect();
         let actual: Vec<f32> = (0..values.len()).map(|x| image.get_pixel_at(x as i32, 0).unwrap()[0]).collect();
@@ -744,16 +1105,71 @@ mod tests {
         let float_values = [0.125_f32, 0.5, 1.0];
         let mut float_bytes = vec![0_u8];
         float_bytes.extend_from_slice(bytemuck::cast_slice(&float_values));
-        let float_image =
-            ImageData::from_raw_bytes(&float_bytes[1..], float_values.len() as i32, 1, 1, PixelType::F32).unwrap();
+        let float_image = ImageData::from_raw_bytes(
+            &float_bytes[1..],
+            float_values.len() as i32,
+            1,
+            1,
+            PixelType::F32,
+            ChannelOrder::Rgb,
+        )
+        .unwrap();
         assert_eq!(float_image.pixels().unwrap(), float_values);
     }
 
     #[test]
-    fn image_rs_decodes_the_embedded_png() {
-        let image = ImageData::from_bytes(include_bytes!("../../icons/icon.png")).unwrap();
+    fn bgr_raw_bytes_read_back_in_rgb_order() {
+        let bytes = [0_u8, 51, 255, 128, 102, 204, 0, 255];
+        let bgra = ImageData::from_raw_bytes(&bytes, 2, 1, 4, PixelType::U8, ChannelOrder::Bgr).unwrap();
+        assert!(bgra.pixels().is_none(), "BGR input must not take the canonical fast path");
+        assert_eq!(bgra.pixel_values(0, 0).unwrap(), vec![1.0, 0.2, 0.0, 128.0 / 255.0]);
+
+        // Gray and gray+alpha have no red or blue sample to swap.
+        let gray = ImageData::from_raw_bytes(&bytes, 4, 1, 2, PixelType::U8, ChannelOrder::Bgr).unwrap();
+        assert_eq!(gray.pixel_values(0, 0).unwrap(), vec![0.0, 0.2]);
+
+        let values = [0.25_f32, 0.5, 0.75];
+        let bgr = ImageData::from_raw_bytes(bytemuck::cast_slice(&values), 1, 1, 3, PixelType::F32, ChannelOrder::Bgr)
+            .unwrap();
+        assert_eq!(bgr.pixel_values(0, 0).unwrap(), vec![0.75, 0.5, 0.25]);
+    }
+
+    #[test]
+    fn anaglyph_takes_red_from_the_left_eye() {
+        let left = ImageData::from_f32(ImageSpec::new(1, 1, 1, PixelType::F32), vec![0.25]).unwrap();
+        let right = ImageData::from_f32(ImageSpec::new(1, 1, 3, PixelType::F32), vec![0.5, 0.75, 1.0]).unwrap();
+        let spec = ImageSpec::new(1, 1, 3, PixelType::F32);
+        let mode = crate::model::ComparisonMode::Anaglyph;
+
+        let anaglyph = ImageData::derived_comparison(left.clone(), right.clone(), spec.clone(), mode, 0.5, 1.0, 0);
+        assert_eq!(anaglyph.pixel_values(0, 0).unwrap(), vec![0.25, 0.75, 1.0]);
+        assert_eq!(anaglyph.scalar_at(0, 2), Some(1.0));
+
+        let swapped = ImageData::derived_comparison(right, left, spec, mode, 0.5, 1.0, 0);
+        assert_eq!(swapped.pixel_values(0, 0).unwrap(), vec![0.5, 0.25, 0.25]);
+        assert_eq!(swapped.scalar_at(0, 1), Some(0.25));
+    }
+
+    #[test]
+    fn sample_area_weights_partially_covered_pixels() {
+        let image = ImageData::from_f32(ImageSpec::new(4, 1, 1, PixelType::F32), vec![0.0, 1.0, 2.0, 3.0]).unwrap();
+
+        assert_eq!(image.sample_area(2.2, 0.5, 0.5).unwrap(), [2.0]);
+        assert_eq!(image.sample_area(1.5, 0.5, 2.0).unwrap(), [1.0]);
+        assert_eq!(image.sample_area(2.0, 0.5, 3.0).unwrap(), [1.5]);
+        // The footprint is clipped to the image bounds.
+        assert_eq!(image.sample_area(0.0, 0.5, 2.0).unwrap(), [0.0]);
+    }
+
+    #[test]
+    fn image_rs_decodes_rgba_png() {
+        let mut png = Vec::new();
+        image::RgbaImage::from_pixel(3, 2, image::Rgba([10, 20, 30, 255]))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let image = ImageData::from_bytes(&png).unwrap();
         let spec = image.spec();
-        assert!(spec.width > 0 && spec.height > 0);
+        assert_eq!((spec.width, spec.height), (3, 2));
         assert_eq!(spec.channels, 4);
         assert_eq!(spec.dtype, PixelType::U8);
     }
@@ -1,7 +1,7 @@
-use crate::model::PixelType;
+use crate::model::{check_decode_limits, decode_limits, PixelType};
 use color_eyre::eyre::{eyre, Result};
 use exr::prelude::{read, MetaData, ReadChannels, ReadLayers, ReadSpecificChannel, Text, Vec2};
-use image::{DynamicImage, ImageFormat, ImageReader};
+use image::{codecs::hdr::HdrDecoder, DynamicImage, ImageFormat, ImageReader};
 use std::{
     fs::File,
     io::{BufRead, BufReader, Cursor, Read, Seek, SeekFrom},
@@ -9,17 +9,26 @@ use std::{
     sync::Once,
 };
 use tiff::{
-    decoder::{Decoder as TiffDecoder, DecodingResult, Limits as TiffLimits},
+    decoder::{BufferLayoutPreference, ChunkType, Decoder as TiffDecoder, DecodingResult, Limits as TiffLimits},
     ColorType as TiffColorType,
 };
 
 const EXR_MAGIC: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+const PNG_MAGIC: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
 const JP2_MAGIC: [u8; 8] = [0x00, 0x00, 0x00, 0x0c, 0x6a, 0x50, 0x20, 0x20];
 const J2C_MAGIC: [u8; 4] = [0xff, 0x4f, 0xff, 0x51];
 const TIFF_LE_MAGIC: [u8; 4] = [b'I', b'I', 42, 0];
 const TIFF_BE_MAGIC: [u8; 4] = [b'M', b'M', 0, 42];
 const BIG_TIFF_LE_MAGIC: [u8; 4] = [b'I', b'I', 43, 0];
 const BIG_TIFF_BE_MAGIC: [u8; 4] = [b'M', b'M', 0, 43];
+const HDR_MAGIC: &[u8] = b"#?RADIANCE";
+#[cfg(feature = "turbojpeg")]
+const JPEG_MAGIC: [u8; 3] = [0xff, 0xd8, 0xff];
+
+/// Decoded pixel count from which large images are shown while they decode.
+const INCREMENTAL_MIN_PIXELS: u64 = 16 * 1024 * 1024;
+/// Number of partial images reported before the full decode finishes.
+const INCREMENTAL_UPDATES: usize = 4;
 
 static DECODER_HOOKS: Once = Once::new();
 
@@ -111,12 +120,35 @@ pub(crate) struct DecodedLayout {
     pub input_channels: usize,
 }
 
+/// Order of the first three samples of a direct color pixel as stored by the source.
+///
+/// Every decoder in this module produces RGB. Producers that hand over foreign buffers, such as raw socket payloads
+/// from OpenCV, declare BGR here instead of swapping samples themselves; the swap is applied once during upload and
+/// when sampling on the CPU.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChannelOrder {
+    #[default]
+    Rgb,
+    Bgr,
+}
+
+impl ChannelOrder {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "rgb" | "rgba" => Some(Self::Rgb),
+            "bgr" | "bgra" => Some(Self::Bgr),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub(crate) struct DecodedTransform {
     pub data_offset_bytes: usize,
     pub flip_y: bool,
     pub swap_bytes: bool,
     pub scale: f32,
+    pub channel_order: ChannelOrder,
 }
 
 impl Default for DecodedTransform {
@@ -126,6 +158,7 @@ impl Default for DecodedTransform {
             flip_y: false,
             swap_bytes: false,
             scale: 1.0,
+            channel_order: ChannelOrder::Rgb,
         }
     }
 }
@@ -168,6 +201,16 @@ pub struct DecodedImage {
     pub(crate) layout: DecodedLayout,
     pub(crate) color: DecodedColor,
     pub(crate) transform: DecodedTransform,
+    /// Container and stored color layout when the decoder converted them, e.g. `PNG, indexed 4-bit`.
+    pub source_format: Option<String>,
+    /// Header fields worth showing next to the image, in file order.
+    pub metadata: Vec<(String, String)>,
+    /// Two channels that the file declares as luminance and alpha, rather than two independent values such as
+    /// flow vectors.
+    pub gray_alpha: bool,
+    /// Pixels of the full image per pixel side when this is a reduced preview of it, see
+    /// [`crate::model::ImageSpec::preview_scale`].
+    pub preview_scale: u32,
 }
 
 impl DecodedImage {
@@ -235,6 +278,7 @@ impl DecodedImage {
         if width <= 0 || height <= 0 || !(1..=4).contains(&channels) {
             return Err(eyre!("Invalid decoded image dimensions or channels"));
         }
+        check_decode_limits(width as u64, height as u64, channels as u64)?;
         if !(1..=5).contains(&layout.input_channels) || layout.planes == 0 || layout.planes > 5 {
             return Err(eyre!("Invalid decoded image channel or plane layout"));
         }
@@ -296,9 +340,31 @@ impl DecodedImage {
             layout,
             color,
             transform,
+            source_format: None,
+            metadata: Vec::new(),
+            gray_alpha: false,
+            preview_scale: 1,
         })
     }
 
+    pub(crate) fn with_gray_alpha(mut self, gray_alpha: bool) -> Self {
+        self.gray_alpha = gray_alpha && self.channels == 2;
+        self
+    }
+
+    pub(crate) fn with_channel_order(mut self, order: ChannelOrder) -> Self {
+        self.transform.channel_order = order;
+        self
+    }
+
+    /// True when the stored order has to be swapped to RGB. Only direct pixels with at least three channels have
+    /// a red and blue sample; palettes and color-space conversions already produce RGB.
+    pub(crate) fn swaps_red_blue(&self) -> bool {
+        self.transform.channel_order == ChannelOrder::Bgr
+            && matches!(self.color, DecodedColor::Direct)
+            && self.channels >= 3
+    }
+
     pub(crate) fn f32_pixels(&self) -> Option<&[f32]> {
         if self.is_canonical_direct() {
             self.pixels.f32_slice()
@@ -315,7 +381,8 @@ impl DecodedImage {
                     data_offset_bytes: 0,
                     flip_y: false,
                     swap_bytes: false,
-                    scale: 1.0
+                    scale: 1.0,
+                    channel_order: ChannelOrder::Rgb,
                 }
             )
             && !matches!(self.pixels, DecodedPixels::Raw(_))
@@ -337,7 +404,12 @@ impl DecodedImage {
         let mut output = [0.0_f32; 4];
         let channels = self.channels as usize;
         match &self.color {
-            DecodedColor::Direct => output[..channels].copy_from_slice(&source[..channels]),
+            DecodedColor::Direct => {
+                output[..channels].copy_from_slice(&source[..channels]);
+                if self.swaps_red_blue() {
+                    output.swap(0, 2);
+                }
+            }
             DecodedColor::Palette(palette) => {
                 let entries = 1_usize << self.layout.bit_depth;
                 let index = (source[0] * (entries - 1) as f32).round() as usize;
@@ -467,14 +539,116 @@ pub fn decode_path(path: &Path) -> Result<DecodedImage> {
     if is_tiff(magic) {
         return decode_tiff_reader(reader);
     }
+    if magic.starts_with(HDR_MAGIC) {
+        return decode_hdr_reader(reader);
+    }
+    if is_pfm(magic) {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        return decode_pfm_owned(bytes);
+    }
+    #[cfg(feature = "turbojpeg")]
+    if magic.starts_with(&JPEG_MAGIC) {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        return decode_jpeg(&bytes);
+    }
+    let header = magic[..magic.len().min(STORED_HEADER_BYTES)].to_vec();
     let mut image_reader = ImageReader::new(reader).with_guessed_format()?;
+    image_reader.limits(decode_limits().image_limits());
     if image_reader.format().is_none() {
         if let Ok(format) = ImageFormat::from_path(path) {
             image_reader.set_format(format);
         }
     }
+    let format = image_reader.format();
     let image = image_reader.decode()?;
-    decoded_dynamic_image(image)
+    postprocess(image, format, &header)
+}
+
+/// Like [`decode_path`], but large non-interlaced PNGs report the rows decoded so far through `on_partial`, and
+/// large chunky TIFFs the strips or rows of tiles decoded so far. Rows that are not decoded yet are zero in the
+/// partial images. With the `turbojpeg` feature, large JPEGs
+/// first report a reduced decode, which the viewer shows enlarged until the full image arrives.
+pub fn decode_path_incremental(path: &Path, on_partial: &mut dyn FnMut(DecodedImage)) -> Result<DecodedImage> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let magic = reader.fill_buf()?;
+    #[cfg(feature = "turbojpeg")]
+    if magic.starts_with(&JPEG_MAGIC) {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        return decode_jpeg_incremental(&bytes, on_partial);
+    }
+    if is_tiff(magic) {
+        return decode_tiff_incremental(reader, INCREMENTAL_MIN_PIXELS, on_partial);
+    }
+    if magic.starts_with(&PNG_MAGIC) {
+        if let Some(image) = decode_png_incremental(reader, INCREMENTAL_MIN_PIXELS, on_partial)? {
+            return Ok(image);
+        }
+    }
+    decode_path(path)
+}
+
+/// Decode row by row; returns `None` without decoding pixels when the PNG is interlaced, indexed or smaller than
+/// `min_pixels`, so the caller can use the regular decoder. Indexed images go through [`postprocess`], which needs
+/// every pixel before it can reduce channels.
+fn decode_png_incremental<R: BufRead + Seek>(
+    reader: R,
+    min_pixels: u64,
+    on_partial: &mut dyn FnMut(DecodedImage),
+) -> Result<Option<DecodedImage>> {
+    let mut decoder = png::Decoder::new(reader);
+    // Matches image-rs: palettes and low bit depths expand to 8 bits, tRNS to an alpha channel.
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info()?;
+    let info = reader.info();
+    let (width, height) = (info.width, info.height);
+    if info.interlaced || info.color_type == png::ColorType::Indexed || (width as u64) * (height as u64) < min_pixels {
+        return Ok(None);
+    }
+    let bits = info.bit_depth as u8;
+    let stored = match info.color_type {
+        png::ColorType::Grayscale => StoredColor::Gray { bits },
+        png::ColorType::GrayscaleAlpha => StoredColor::GrayAlpha { bits },
+        png::ColorType::Rgb => StoredColor::Rgb { bits },
+        png::ColorType::Rgba => StoredColor::Rgba { bits },
+        png::ColorType::Indexed => StoredColor::Indexed { bits },
+    };
+    let source_format = format!("PNG, {}", stored.describe());
+    let (color_type, bit_depth) = reader.output_color_type();
+    let channels = color_type.samples() as i32;
+    check_decode_limits(width as u64, height as u64, channels as u64)?;
+    let row_bytes = reader.output_line_size(width).ok_or_else(|| eyre!("PNG row size overflow"))?;
+    let mut bytes = vec![0u8; row_bytes * height as usize];
+    let rows_per_update = (height as usize).div_ceil(INCREMENTAL_UPDATES + 1).max(1);
+
+    for y in 0..height as usize {
+        let decoded = reader.next_row()?.ok_or_else(|| eyre!("PNG ended after {y} rows"))?;
+        bytes[y * row_bytes..(y + 1) * row_bytes].copy_from_slice(decoded.data());
+        if (y + 1) % rows_per_update == 0 && y + 1 < height as usize {
+            on_partial(png_image(width, height, channels, bit_depth, bytes.clone())?);
+        }
+    }
+    let mut image = png_image(width, height, channels, bit_depth, bytes)?;
+    image.source_format = Some(source_format);
+    // Two output channels only come from gray+alpha, including gray with a tRNS key color.
+    image.gray_alpha = channels == 2;
+    Ok(Some(image))
+}
+
+fn png_image(width: u32, height: u32, channels: i32, bit_depth: png::BitDepth, bytes: Vec<u8>) -> Result<DecodedImage> {
+    match bit_depth {
+        png::BitDepth::Eight => DecodedImage::new(width, height, channels, PixelType::U8, DecodedPixels::U8(bytes)),
+        png::BitDepth::Sixteen => {
+            let values = bytes
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            DecodedImage::new(width, height, channels, PixelType::U16, DecodedPixels::U16(values))
+        }
+        depth => Err(eyre!("Unexpected PNG output bit depth {depth:?}")),
+    }
 }
 
 pub fn decode_bytes(bytes: &[u8]) -> Result<DecodedImage> {
@@ -488,21 +662,40 @@ pub fn decode_bytes(bytes: &[u8]) -> Result<DecodedImage> {
     if is_tiff(bytes) {
         return decode_tiff_reader(Cursor::new(bytes));
     }
-    let image_reader = ImageReader::new(Cursor::new(bytes)).with_guessed_format()?;
+    if bytes.starts_with(HDR_MAGIC) {
+        return decode_hdr_reader(Cursor::new(bytes));
+    }
+    if is_pfm(bytes) {
+        return decode_pfm(bytes);
+    }
+    #[cfg(feature = "turbojpeg")]
+    if bytes.starts_with(&JPEG_MAGIC) {
+        return decode_jpeg(bytes);
+    }
+    let mut image_reader = ImageReader::new(Cursor::new(bytes)).with_guessed_format()?;
+    image_reader.limits(decode_limits().image_limits());
+    // TGA deliberately has no fixed magic signature. Try its strict header
+    // parser only after every signature-based format has been ruled out.
+    let format = image_reader.format().unwrap_or(ImageFormat::Tga);
     let image = if image_reader.format().is_some() {
         image_reader.decode()?
     } else {
-        // TGA deliberately has no fixed magic signature. Try its strict header
-        // parser only after every signature-based format has been ruled out.
-        ImageReader::with_format(Cursor::new(bytes), ImageFormat::Tga).decode()?
+        let mut tga_reader = ImageReader::with_format(Cursor::new(bytes), format);
+        tga_reader.limits(decode_limits().image_limits());
+        tga_reader.decode()?
     };
-    decoded_dynamic_image(image)
+    postprocess(image, Some(format), bytes)
 }
 
 fn is_jpeg2000(bytes: &[u8]) -> bool {
     bytes.starts_with(&JP2_MAGIC) || bytes.starts_with(&J2C_MAGIC)
 }
 
+/// `PF` (RGB) or `Pf` (gray) followed by whitespace. Other Netpbm formats use a digit after `P`.
+fn is_pfm(bytes: &[u8]) -> bool {
+    matches!(bytes, [b'P', b'F' | b'f', next, ..] if next.is_ascii_whitespace())
+}
+
 fn is_tiff(bytes: &[u8]) -> bool {
     [TIFF_LE_MAGIC, TIFF_BE_MAGIC, BIG_TIFF_LE_MAGIC, BIG_TIFF_BE_MAGIC]
         .iter()
@@ -514,6 +707,8 @@ enum ExrLayout {
     Rgb,
     Rgba,
     Mono(Text),
+    /// Up to four channels of a layer without RGB names, such as `motion.u` and `motion.v`.
+    Channels(Vec<Text>),
 }
 
 #[derive(Clone)]
@@ -531,6 +726,7 @@ impl ExrSelection {
             ExrLayout::Rgb => 3,
             ExrLayout::Rgba => 4,
             ExrLayout::Mono(_) => 1,
+            ExrLayout::Channels(ref names) => names.len(),
         }
     }
 
@@ -565,12 +761,26 @@ impl ExrPixels {
         let start = (y as usize * self.selection.width + x as usize) * N;
         self.values[start..start + N].copy_from_slice(&values);
     }
+
+    /// Copy one channel of a layer, given in data-window order, into the interleaved buffer.
+    fn set_channel(&mut self, data_width: usize, channel: usize, samples: impl Iterator<Item = f32>) {
+        let channels = self.selection.channels();
+        for (index, value) in samples.enumerate() {
+            let x = (index % data_width) as i64 + self.selection.data_offset_x as i64;
+            let y = (index / data_width) as i64 + self.selection.data_offset_y as i64;
+            if x < 0 || y < 0 || x >= self.selection.width as i64 || y >= self.selection.height as i64 {
+                continue;
+            }
+            self.values[(y as usize * self.selection.width + x as usize) * channels + channel] = value;
+        }
+    }
 }
 
 fn decode_exr_reader<R: BufRead + Seek>(reader: R) -> Result<DecodedImage> {
     let exr_reader = exr::block::read(reader, false)?;
     let selection = select_exr_layout(exr_reader.meta_data())?;
     selection.value_count()?;
+    check_decode_limits(selection.width as u64, selection.height as u64, selection.channels() as u64)?;
 
     let pixels = match selection.layout.clone() {
         ExrLayout::Rgb => {
@@ -619,6 +829,27 @@ fn decode_exr_reader<R: BufRead + Seek>(reader: R) -> Result<DecodedImage> {
                 .from_chunks(exr_reader)?;
             image.layer_data.channel_data.pixels.values
         }
+        ExrLayout::Channels(names) => {
+            let image = read()
+                .no_deep_data()
+                .largest_resolution_level()
+                .all_channels()
+                .first_valid_layer()
+                .all_attributes()
+                .from_chunks(exr_reader)?;
+            let layer = image.layer_data;
+            let mut pixels = ExrPixels::new(selection.clone());
+            for (channel, name) in names.iter().enumerate() {
+                let samples = layer
+                    .channel_data
+                    .list
+                    .iter()
+                    .find(|data| &data.name == name)
+                    .ok_or_else(|| eyre!("EXR channel {name} is missing"))?;
+                pixels.set_channel(layer.size.width(), channel, samples.sample_data.values_as_f32());
+            }
+            pixels.values
+        }
     };
 
     DecodedImage::new(
@@ -663,7 +894,22 @@ fn select_exr_layout(meta: &MetaData) -> Result<ExrSelection> {
         return exr_selection(header, ExrLayout::Mono(channel_name));
     }
 
-    Err(eyre!("EXR has no non-deep RGB/RGBA layer or exactly one-channel mono layer"))
+    if let Some(header) = meta
+        .headers
+        .iter()
+        .find(|header| !header.deep && !header.channels.list.is_empty())
+    {
+        let names = header
+            .channels
+            .list
+            .iter()
+            .take(4)
+            .map(|channel| channel.name.clone())
+            .collect();
+        return exr_selection(header, ExrLayout::Channels(names));
+    }
+
+    Err(eyre!("EXR has no non-deep layer with channels"))
 }
 
 fn exr_selection(header: &exr::meta::header::Header, layout: ExrLayout) -> Result<ExrSelection> {
@@ -696,13 +942,16 @@ fn decode_jpeg2000_reader<R: Read + Seek>(mut reader: R) -> Result<DecodedImage>
         image.color_space(),
         hayro_jpeg2000::ColorSpace::Gray | hayro_jpeg2000::ColorSpace::RGB
     ) {
-        let dynamic = ImageReader::new(Cursor::new(bytes)).with_guessed_format()?.decode()?;
+        let mut image_reader = ImageReader::new(Cursor::new(bytes)).with_guessed_format()?;
+        image_reader.limits(decode_limits().image_limits());
+        let dynamic = image_reader.decode()?;
         return decoded_dynamic_image(dynamic);
     }
 
     let width = image.width();
     let height = image.height();
     let channels = image.color_space().num_channels() as usize + usize::from(image.has_alpha());
+    check_decode_limits(width as u64, height as u64, channels as u64)?;
     let pixel_count = width as usize * height as usize;
     pixel_count
         .checked_mul(channels)
@@ -814,7 +1063,17 @@ impl TiffColorLayout {
     }
 }
 
-fn decode_tiff_reader<R: Read + Seek>(mut reader: R) -> Result<DecodedImage> {
+fn decode_tiff_reader<R: Read + Seek>(reader: R) -> Result<DecodedImage> {
+    decode_tiff_incremental(reader, u64::MAX, &mut |_| {})
+}
+
+/// Like [`decode_tiff_reader`], but chunky images of at least `min_pixels` with whole-byte direct samples report
+/// the strips or rows of tiles decoded so far through `on_partial`. Rows that are not decoded yet are zero.
+fn decode_tiff_incremental<R: Read + Seek>(
+    mut reader: R,
+    min_pixels: u64,
+    on_partial: &mut dyn FnMut(DecodedImage),
+) -> Result<DecodedImage> {
     // tiff 0.11 rejects palette photometric images while constructing the
     // decoder, before callers can retrieve ColorMap. Inspect that small piece
     // of metadata first and only copy/patch the compressed stream for this
@@ -839,7 +1098,16 @@ fn decode_tiff_reader<R: Read + Seek>(mut reader: R) -> Result<DecodedImage> {
             ),
             TiffPrepatch::Lab { bit_depth } => (TiffColorType::Lab(bit_depth), TiffColorLayout::Lab, None),
         };
-        return decode_tiff_image(decoder, width, height, color_type, color_layout, palette);
+        return decode_tiff_image(
+            decoder,
+            width,
+            height,
+            color_type,
+            color_layout,
+            palette,
+            min_pixels,
+            on_partial,
+        );
     }
 
     let mut decoder = TiffDecoder::new(reader)?;
@@ -847,7 +1115,7 @@ fn decode_tiff_reader<R: Read + Seek>(mut reader: R) -> Result<DecodedImage> {
     let color_type = decoder.colortype()?;
     let color_layout = tiff_color_layout(color_type)?;
 
-    decode_tiff_image(decoder, width, height, color_type, color_layout, None)
+    decode_tiff_image(decoder, width, height, color_type, color_layout, None, min_pixels, on_partial)
 }
 
 fn tiff_color_layout(color_type: TiffColorType) -> Result<TiffColorLayout> {
@@ -1058,6 +1326,7 @@ fn read_tiff_short_values<R: Read + Seek>(
     Ok(bytes.chunks_exact(2).map(|bytes| order.u16(bytes)).collect())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn decode_tiff_image<R: Read + Seek>(
     mut decoder: TiffDecoder<R>,
     width: u32,
@@ -1065,8 +1334,11 @@ fn decode_tiff_image<R: Read + Seek>(
     color_type: TiffColorType,
     color_layout: TiffColorLayout,
     palette: Option<Vec<u16>>,
+    min_pixels: u64,
+    on_partial: &mut dyn FnMut(DecodedImage),
 ) -> Result<DecodedImage> {
     let bit_depth = color_type.bit_depth();
+    check_decode_limits(width as u64, height as u64, color_layout.input_channels() as u64)?;
 
     let pixel_count = width as usize * height as usize;
     let packed_bytes = pixel_count
@@ -1077,10 +1349,59 @@ fn decode_tiff_image<R: Read + Seek>(
     let mut limits = TiffLimits::default();
     limits.decoding_buffer_size = limits.decoding_buffer_size.max(packed_bytes);
     limits.intermediate_buffer_size = limits.intermediate_buffer_size.max(packed_bytes.min(512 * 1024 * 1024));
-    decoder = decoder.with_limits(limits);
+    decoder = decoder.with_limits(limits.clone());
 
     let mut result = DecodingResult::U8(Vec::new());
-    let buffer_layout = decoder.read_image_to_buffer(&mut result)?;
+    let mut buffer_layout = decoder.image_buffer_layout()?;
+    let incremental = pixel_count as u64 >= min_pixels
+        && buffer_layout.planes == 1
+        && matches!(color_layout, TiffColorLayout::Direct { .. })
+        && bit_depth.is_multiple_of(8);
+    if buffer_layout.planes > 1 && decoder.get_chunk_type() == ChunkType::Tile && bit_depth.is_multiple_of(8) {
+        result.resize_to(&buffer_layout, &limits)?;
+        read_planar_tiles(&mut decoder, &mut result, &buffer_layout, width, height, bit_depth)?;
+    } else if incremental {
+        result.resize_to(&buffer_layout, &limits)?;
+        let pixel_bytes = color_layout.input_channels() * bit_depth as usize / 8;
+        read_chunky_chunks(
+            &mut decoder,
+            &mut result,
+            &buffer_layout,
+            width,
+            height,
+            pixel_bytes,
+            &mut |result| {
+                let partial = tiff_image(
+                    copy_decoding_result(result),
+                    &buffer_layout,
+                    width,
+                    height,
+                    color_type,
+                    color_layout,
+                    None,
+                );
+                if let Ok(partial) = partial {
+                    on_partial(partial);
+                }
+            },
+        )?;
+    } else {
+        buffer_layout = decoder.read_image_to_buffer(&mut result)?;
+    }
+    tiff_image(result, &buffer_layout, width, height, color_type, color_layout, palette)
+}
+
+/// Interpret `result`, read with `buffer_layout`, as the pixels of a `width`×`height` TIFF.
+fn tiff_image(
+    result: DecodingResult,
+    buffer_layout: &BufferLayoutPreference,
+    width: u32,
+    height: u32,
+    color_type: TiffColorType,
+    color_layout: TiffColorLayout,
+    palette: Option<Vec<u16>>,
+) -> Result<DecodedImage> {
+    let bit_depth = color_type.bit_depth();
     let source_sample_bytes = match &result {
         DecodingResult::U8(_) | DecodingResult::I8(_) => 1,
         DecodingResult::U16(_) | DecodingResult::I16(_) | DecodingResult::F16(_) => 2,
@@ -1140,6 +1461,121 @@ fn decode_tiff_image<R: Read + Seek>(
         },
         color,
     )
+    .map(|image| image.with_gray_alpha(matches!(color_type, TiffColorType::GrayA(_))))
+}
+
+/// Read a chunky image of `pixel_bytes` per pixel strip by strip or tile by tile into the buffer described by
+/// `layout`, calling `on_rows` with the buffer after each strip or row of tiles except the last, at most
+/// [`INCREMENTAL_UPDATES`] times.
+fn read_chunky_chunks<R: Read + Seek>(
+    decoder: &mut TiffDecoder<R>,
+    result: &mut DecodingResult,
+    layout: &BufferLayoutPreference,
+    width: u32,
+    height: u32,
+    pixel_bytes: usize,
+    on_rows: &mut dyn FnMut(&DecodingResult),
+) -> Result<()> {
+    let (chunk_width, chunk_height) = decoder.chunk_dimensions();
+    let chunks_across = width.div_ceil(chunk_width);
+    let chunk_rows = height.div_ceil(chunk_height);
+    let row_stride = layout.row_stride.map_or(width as usize * pixel_bytes, |stride| stride.get());
+    let rows_per_update = (height as usize).div_ceil(INCREMENTAL_UPDATES + 1).max(1);
+    let mut next_update = rows_per_update;
+    let mut chunk = Vec::new();
+    for chunk_row in 0..chunk_rows {
+        for chunk_x in 0..chunks_across {
+            let index = chunk_row * chunks_across + chunk_x;
+            let chunk_layout = decoder.image_chunk_buffer_layout(index)?;
+            chunk.resize(chunk_layout.len, 0);
+            decoder.read_chunk_bytes(index, &mut chunk)?;
+
+            let (data_width, data_height) = decoder.chunk_data_dimensions(index);
+            let row_bytes = data_width as usize * pixel_bytes;
+            let chunk_row_stride = chunk_layout.row_stride.map_or(row_bytes, |stride| stride.get());
+            let (x0, y0) = ((chunk_x * chunk_width) as usize, (chunk_row * chunk_height) as usize);
+            let mut buffer = result.as_buffer(0);
+            let buffer = buffer.as_bytes_mut();
+            for row in 0..data_height as usize {
+                let target = (y0 + row) * row_stride + x0 * pixel_bytes;
+                let source = row * chunk_row_stride;
+                buffer
+                    .get_mut(target..target + row_bytes)
+                    .ok_or_else(|| eyre!("TIFF chunk exceeds the image buffer"))?
+                    .copy_from_slice(&chunk[source..source + row_bytes]);
+            }
+        }
+        let rows_done = ((chunk_row + 1) * chunk_height).min(height) as usize;
+        if rows_done >= next_update && rows_done < height as usize {
+            on_rows(result);
+            next_update = rows_done / rows_per_update * rows_per_update + rows_per_update;
+        }
+    }
+    Ok(())
+}
+
+fn copy_decoding_result(result: &DecodingResult) -> DecodingResult {
+    match result {
+        DecodingResult::U8(values) => DecodingResult::U8(values.clone()),
+        DecodingResult::U16(values) => DecodingResult::U16(values.clone()),
+        DecodingResult::U32(values) => DecodingResult::U32(values.clone()),
+        DecodingResult::U64(values) => DecodingResult::U64(values.clone()),
+        DecodingResult::F16(values) => DecodingResult::F16(values.clone()),
+        DecodingResult::F32(values) => DecodingResult::F32(values.clone()),
+        DecodingResult::F64(values) => DecodingResult::F64(values.clone()),
+        DecodingResult::I8(values) => DecodingResult::I8(values.clone()),
+        DecodingResult::I16(values) => DecodingResult::I16(values.clone()),
+        DecodingResult::I32(values) => DecodingResult::I32(values.clone()),
+        DecodingResult::I64(values) => DecodingResult::I64(values.clone()),
+    }
+}
+
+/// Read a tiled planar image tile by tile into the planar buffer described by `layout`.
+///
+/// tiff 0.11 locates the bottom row of tiles by the absolute tile index, so tiles of every plane after the first
+/// are read with their full padded height and overflow the image buffer. Tiles are stored padded in the file,
+/// so each one is read whole into its own buffer and only the rows inside the image are copied.
+fn read_planar_tiles<R: Read + Seek>(
+    decoder: &mut TiffDecoder<R>,
+    result: &mut DecodingResult,
+    layout: &BufferLayoutPreference,
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+) -> Result<()> {
+    let sample_bytes = bit_depth as usize / 8;
+    let (tile_width, tile_height) = decoder.chunk_dimensions();
+    let tiles_across = width.div_ceil(tile_width);
+    let tiles_per_plane = tiles_across * height.div_ceil(tile_height);
+    let row_stride = layout.row_stride.map_or(width as usize * sample_bytes, |stride| stride.get());
+    let plane_stride = layout.plane_stride.map_or(row_stride * height as usize, |stride| stride.get());
+    let mut buffer = result.as_buffer(0);
+    let buffer = buffer.as_bytes_mut();
+    let mut tile = Vec::new();
+    for plane in 0..layout.planes as u32 {
+        for index in 0..tiles_per_plane {
+            let chunk = plane * tiles_per_plane + index;
+            let tile_layout = decoder.image_chunk_buffer_layout(chunk)?;
+            tile.resize(tile_layout.len, 0);
+            decoder.read_chunk_bytes(chunk, &mut tile)?;
+
+            // Data size of the same tile in the first plane, where the library gets the padding right.
+            let (data_width, data_height) = decoder.chunk_data_dimensions(index);
+            let tile_row_stride = tile_layout.row_stride.map_or(data_width as usize * sample_bytes, |s| s.get());
+            let row_bytes = data_width as usize * sample_bytes;
+            let (x0, y0) = ((index % tiles_across) * tile_width, (index / tiles_across) * tile_height);
+            for row in 0..data_height as usize {
+                let target =
+                    plane as usize * plane_stride + (y0 as usize + row) * row_stride + x0 as usize * sample_bytes;
+                let source = row * tile_row_stride;
+                buffer
+                    .get_mut(target..target + row_bytes)
+                    .ok_or_else(|| eyre!("TIFF tile exceeds the image buffer"))?
+                    .copy_from_slice(&tile[source..source + row_bytes]);
+            }
+        }
+    }
+    Ok(())
 }
 
 fn decoded_tiff_pixels(result: DecodingResult, bit_depth: u8) -> (DecodedPixels, PixelType) {
@@ -1283,6 +1719,120 @@ fn tiff_lab_to_rgb(l: f32, a: f32, b: f32) -> [f32; 3] {
     })
 }
 
+/// Header bytes kept for [`stored_color`]; covers the PNG IHDR chunk, the BMP info header and the GIF screen
+/// descriptor.
+const STORED_HEADER_BYTES: usize = 64;
+
+/// Color layout stored in the file before the image-rs decoder expanded it to 8-bit gray or RGB(A).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StoredColor {
+    /// Palette indices of `bits` bits.
+    Indexed {
+        bits: u8,
+    },
+    Gray {
+        bits: u8,
+    },
+    GrayAlpha {
+        bits: u8,
+    },
+    Rgb {
+        bits: u8,
+    },
+    Rgba {
+        bits: u8,
+    },
+}
+
+impl StoredColor {
+    fn describe(self) -> String {
+        match self {
+            Self::Indexed { bits } => format!("indexed {bits}-bit"),
+            Self::Gray { bits } => format!("gray {bits}-bit"),
+            Self::GrayAlpha { bits } => format!("gray+alpha {bits}-bit"),
+            Self::Rgb { bits } => format!("RGB {bits}-bit"),
+            Self::Rgba { bits } => format!("RGBA {bits}-bit"),
+        }
+    }
+}
+
+/// Read the stored color layout from a PNG, BMP or GIF header. Other formats and truncated headers return `None`.
+fn stored_color(format: ImageFormat, header: &[u8]) -> Option<StoredColor> {
+    match format {
+        ImageFormat::Png => {
+            // Signature, IHDR length and type, width, height, then bit depth and color type.
+            let (&bits, &color_type) = (header.get(24)?, header.get(25)?);
+            match color_type {
+                0 => Some(StoredColor::Gray { bits }),
+                2 => Some(StoredColor::Rgb { bits }),
+                3 => Some(StoredColor::Indexed { bits }),
+                4 => Some(StoredColor::GrayAlpha { bits }),
+                6 => Some(StoredColor::Rgba { bits }),
+                _ => None,
+            }
+        }
+        ImageFormat::Bmp => {
+            let info_size = u32::from_le_bytes(header.get(14..18)?.try_into().ok()?);
+            // OS/2 core headers store 16-bit dimensions, which moves the bit count forward.
+            let offset = if info_size == 12 { 24 } else { 28 };
+            let bits = u16::from_le_bytes(header.get(offset..offset + 2)?.try_into().ok()?);
+            match bits {
+                1 | 2 | 4 | 8 => Some(StoredColor::Indexed { bits: bits as u8 }),
+                24 => Some(StoredColor::Rgb { bits: 8 }),
+                32 => Some(StoredColor::Rgba { bits: 8 }),
+                _ => None,
+            }
+        }
+        ImageFormat::Gif => {
+            // Logical screen descriptor flags; the low bits give the global palette size.
+            let flags = *header.get(10)?;
+            Some(StoredColor::Indexed {
+                bits: (flags & 0x07) + 1,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Convert an image-rs decode and record how the file stored it. Palette images are expanded to RGB(A) by
+/// the decoder; an alpha channel that is opaque everywhere is dropped, and palettes of grays (such as 1-bit
+/// scans) collapse to a single gray channel.
+fn postprocess(image: DynamicImage, format: Option<ImageFormat>, header: &[u8]) -> Result<DecodedImage> {
+    let stored = format.and_then(|format| stored_color(format, header));
+    let image = match stored {
+        Some(StoredColor::Indexed { .. }) => reduce_indexed_channels(image),
+        _ => image,
+    };
+    let mut decoded = decoded_dynamic_image(image)?;
+    decoded.source_format = format.map(|format| {
+        let name = format!("{format:?}").to_ascii_uppercase();
+        match stored {
+            Some(stored) => format!("{name}, {}", stored.describe()),
+            None => name,
+        }
+    });
+    Ok(decoded)
+}
+
+fn reduce_indexed_channels(image: DynamicImage) -> DynamicImage {
+    let image = match image {
+        DynamicImage::ImageRgba8(rgba) if rgba.pixels().all(|pixel| pixel[3] == u8::MAX) => {
+            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(rgba).into_rgb8())
+        }
+        other => other,
+    };
+    let is_gray = |pixel: &[u8]| pixel[0] == pixel[1] && pixel[1] == pixel[2];
+    match image {
+        DynamicImage::ImageRgb8(rgb) if rgb.pixels().all(|pixel| is_gray(&pixel.0)) => {
+            DynamicImage::ImageLuma8(DynamicImage::ImageRgb8(rgb).into_luma8())
+        }
+        DynamicImage::ImageRgba8(rgba) if rgba.pixels().all(|pixel| is_gray(&pixel.0)) => {
+            DynamicImage::ImageLumaA8(DynamicImage::ImageRgba8(rgba).into_luma_alpha8())
+        }
+        other => other,
+    }
+}
+
 fn decoded_dynamic_image(image: DynamicImage) -> Result<DecodedImage> {
     match image {
         DynamicImage::ImageLuma8(image) => {
@@ -1292,6 +1842,7 @@ fn decoded_dynamic_image(image: DynamicImage) -> Result<DecodedImage> {
         DynamicImage::ImageLumaA8(image) => {
             let (width, height) = image.dimensions();
             DecodedImage::new(width, height, 2, PixelType::U8, DecodedPixels::U8(image.into_raw()))
+                .map(|image| image.with_gray_alpha(true))
         }
         DynamicImage::ImageRgb8(image) => {
             let (width, height) = image.dimensions();
@@ -1308,6 +1859,7 @@ fn decoded_dynamic_image(image: DynamicImage) -> Result<DecodedImage> {
         DynamicImage::ImageLumaA16(image) => {
             let (width, height) = image.dimensions();
             DecodedImage::new(width, height, 2, PixelType::U16, DecodedPixels::U16(image.into_raw()))
+                .map(|image| image.with_gray_alpha(true))
         }
         DynamicImage::ImageRgb16(image) => {
             let (width, height) = image.dimensions();
@@ -1333,6 +1885,43 @@ fn decoded_dynamic_image(image: DynamicImage) -> Result<DecodedImage> {
     }
 }
 
+/// Decode a Radiance RGBE file. `EXPOSURE` records how much the pixels were scaled after rendering, so it is
+/// divided out to restore radiance; it and the other header fields are kept as metadata.
+fn decode_hdr_reader<R: BufRead>(reader: R) -> Result<DecodedImage> {
+    let decoder = HdrDecoder::new(reader)?;
+    let header = decoder.metadata();
+    check_decode_limits(header.width as u64, header.height as u64, 3)?;
+    let mut pixels = match DynamicImage::from_decoder(decoder)? {
+        DynamicImage::ImageRgb32F(image) => image.into_raw(),
+        other => other.into_rgb32f().into_raw(),
+    };
+    let exposure = header.exposure.filter(|exposure| exposure.is_finite() && *exposure > 0.0);
+    if let Some(exposure) = exposure.filter(|exposure| *exposure != 1.0) {
+        for value in &mut pixels {
+            *value /= exposure;
+        }
+    }
+
+    let mut decoded = DecodedImage::new(header.width, header.height, 3, PixelType::F32, DecodedPixels::F32(pixels))?;
+    if let Some(exposure) = exposure {
+        decoded.metadata.push(("Exposure".into(), format!("{exposure} (divided out)")));
+    }
+    if let Some(aspect) = header.pixel_aspect_ratio {
+        decoded.metadata.push(("Pixel aspect".into(), aspect.to_string()));
+    }
+    if let Some((r, g, b)) = header.color_correction {
+        decoded.metadata.push(("Color correction".into(), format!("{r} {g} {b}")));
+    }
+    for (key, value) in header.custom_attributes {
+        let known = ["FORMAT", "EXPOSURE", "PIXASPECT", "COLORCORR"];
+        if !key.is_empty() && !known.contains(&key.as_str()) {
+            decoded.metadata.push((key, value.trim().to_string()));
+        }
+    }
+    decoded.source_format = Some("HDR, RGBE".into());
+    Ok(decoded)
+}
+
 #[derive(Clone, Copy)]
 struct PfmHeader {
     width: u32,
@@ -1378,6 +1967,7 @@ fn parse_pfm_header(bytes: &[u8]) -> Result<PfmHeader> {
     if width == 0 || height == 0 || scale == 0.0 || !scale.is_finite() {
         return Err(eyre!("Invalid PFM dimensions or scale"));
     }
+    check_decode_limits(width as u64, height as u64, channels as u64)?;
 
     // The binary payload starts after the line terminator following scale.
     while matches!(bytes.get(offset), Some(b' ' | b'\t')) {
@@ -1397,8 +1987,9 @@ fn parse_pfm_header(bytes: &[u8]) -> Result<PfmHeader> {
         .checked_mul(height as usize)
         .ok_or_else(|| eyre!("PFM image size overflow"))?;
     let byte_count = value_count.checked_mul(4).ok_or_else(|| eyre!("PFM byte size overflow"))?;
-    bytes
-        .get(offset..offset + byte_count)
+    offset
+        .checked_add(byte_count)
+        .and_then(|end| bytes.get(offset..end))
         .ok_or_else(|| eyre!("PFM pixel payload is truncated"))?;
     Ok(PfmHeader {
         width,
@@ -1463,6 +2054,7 @@ fn decode_pfm_rgb_owned(bytes: Vec<u8>, header: PfmHeader) -> Result<DecodedImag
             flip_y: true,
             swap_bytes: !header.little_endian,
             scale: header.scale,
+            ..DecodedTransform::default()
         },
     )
 }
@@ -1510,6 +2102,7 @@ pub fn decode_flo_owned(bytes: Vec<u8>) -> Result<DecodedImage> {
     if width <= 0 || height <= 0 {
         return Err(eyre!(".flo: invalid dimensions: {width}x{height}"));
     }
+    check_decode_limits(width as u64, height as u64, 2)?;
     let value_count = width as usize * height as usize * 2;
     let data_bytes = value_count.checked_mul(4).ok_or_else(|| eyre!(".flo data size overflow"))?;
     bytes
@@ -1640,9 +2233,214 @@ pub fn decode_heif(path: &Path) -> Result<DecodedImage> {
     }
 }
 
+/// Decode a JPEG through libjpeg-turbo. CMYK and YCCK files, which TurboJPEG cannot convert to RGB, go through
+/// image-rs instead.
+#[cfg(feature = "turbojpeg")]
+fn decode_jpeg(bytes: &[u8]) -> Result<DecodedImage> {
+    let decompressor = turbojpeg::Decompressor::new()?;
+    let header = decompressor.read_header(bytes)?;
+    if header.channels().is_none() {
+        let mut image_reader = ImageReader::with_format(Cursor::new(bytes), ImageFormat::Jpeg);
+        image_reader.limits(decode_limits().image_limits());
+        return postprocess(image_reader.decode()?, Some(ImageFormat::Jpeg), bytes);
+    }
+    decompressor.decompress(bytes, header, 1)
+}
+
+/// Like [`decode_jpeg`], but JPEGs of at least a quarter of [`INCREMENTAL_MIN_PIXELS`] first report a 1/2 or
+/// 1/4 scaled decode through `on_partial`, which is much cheaper than the full decode that follows. The preview
+/// keeps its reduced size and is marked with its scale, so the viewer enlarges it instead of holding a
+/// full-resolution copy.
+#[cfg(feature = "turbojpeg")]
+fn decode_jpeg_incremental(bytes: &[u8], on_partial: &mut dyn FnMut(DecodedImage)) -> Result<DecodedImage> {
+    let decompressor = turbojpeg::Decompressor::new()?;
+    let header = decompressor.read_header(bytes)?;
+    if let (Some(_), Some(denominator)) = (header.channels(), jpeg_preview_denominator(header.width, header.height)) {
+        // A preview that fails to decode is dropped; the full decode reports the error.
+        if let Ok(mut preview) = decompressor.decompress(bytes, header, denominator) {
+            preview.preview_scale = denominator;
+            on_partial(preview);
+        }
+    }
+    decode_jpeg(bytes)
+}
+
+/// Scale denominator for the navigation preview of a `width`×`height` JPEG: 4 from [`INCREMENTAL_MIN_PIXELS`]
+/// up, 2 from a quarter of that, and `None` for images small enough to decode fully right away.
+#[cfg(feature = "turbojpeg")]
+fn jpeg_preview_denominator(width: u32, height: u32) -> Option<u32> {
+    let pixels = width as u64 * height as u64;
+    if pixels >= INCREMENTAL_MIN_PIXELS {
+        Some(4)
+    } else if pixels >= INCREMENTAL_MIN_PIXELS / 4 {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// Bindings for the part of the TurboJPEG API the viewer uses. It reports libjpeg errors as return codes, so a
+/// corrupt file cannot unwind through C or abort a `panic = "abort"` build.
+#[cfg(feature = "turbojpeg")]
+mod turbojpeg {
+    use super::{DecodedImage, DecodedPixels};
+    use crate::model::{check_decode_limits, PixelType};
+    use color_eyre::eyre::{eyre, Result};
+    use std::ffi::{c_char, c_int, c_uchar, c_ulong, c_void, CStr};
+
+    // Links the static libjpeg-turbo build that provides the symbols below.
+    use mozjpeg_sys as _;
+
+    const TJPF_RGB: c_int = 0;
+    const TJPF_GRAY: c_int = 6;
+    const TJCS_RGB: c_int = 0;
+    const TJCS_YCBCR: c_int = 1;
+    const TJCS_GRAY: c_int = 2;
+    const TJFLAG_FASTUPSAMPLE: c_int = 256;
+    const TJFLAG_FASTDCT: c_int = 2048;
+    const TJFLAG_ACCURATEDCT: c_int = 4096;
+
+    extern "C" {
+        fn tjInitDecompress() -> *mut c_void;
+        fn tjDecompressHeader3(
+            handle: *mut c_void,
+            jpeg_buf: *const c_uchar,
+            jpeg_size: c_ulong,
+            width: *mut c_int,
+            height: *mut c_int,
+            jpeg_subsamp: *mut c_int,
+            jpeg_colorspace: *mut c_int,
+        ) -> c_int;
+        fn tjDecompress2(
+            handle: *mut c_void,
+            jpeg_buf: *const c_uchar,
+            jpeg_size: c_ulong,
+            dst_buf: *mut c_uchar,
+            width: c_int,
+            pitch: c_int,
+            height: c_int,
+            pixel_format: c_int,
+            flags: c_int,
+        ) -> c_int;
+        fn tjGetErrorStr2(handle: *mut c_void) -> *mut c_char;
+        fn tjDestroy(handle: *mut c_void) -> c_int;
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    pub(super) struct Header {
+        pub(super) width: u32,
+        pub(super) height: u32,
+        colorspace: c_int,
+    }
+
+    impl Header {
+        /// Output channels, or `None` for color spaces TurboJPEG cannot convert to RGB.
+        pub(super) fn channels(&self) -> Option<i32> {
+            match self.colorspace {
+                TJCS_GRAY => Some(1),
+                TJCS_RGB | TJCS_YCBCR => Some(3),
+                _ => None,
+            }
+        }
+    }
+
+    pub(super) struct Decompressor(*mut c_void);
+
+    impl Decompressor {
+        pub(super) fn new() -> Result<Self> {
+            // SAFETY: Takes no arguments; a null handle is rejected below.
+            let handle = unsafe { tjInitDecompress() };
+            if handle.is_null() {
+                return Err(eyre!("Failed to initialize the JPEG decoder"));
+            }
+            Ok(Self(handle))
+        }
+
+        pub(super) fn read_header(&self, bytes: &[u8]) -> Result<Header> {
+            let (mut width, mut height, mut subsampling, mut colorspace) = (0, 0, 0, 0);
+            // SAFETY: The handle is live and the pointer/length pair describes `bytes`.
+            let status = unsafe {
+                tjDecompressHeader3(
+                    self.0,
+                    bytes.as_ptr(),
+                    jpeg_size(bytes)?,
+                    &mut width,
+                    &mut height,
+                    &mut subsampling,
+                    &mut colorspace,
+                )
+            };
+            if status != 0 || width <= 0 || height <= 0 {
+                return Err(self.error("Failed to read JPEG header"));
+            }
+            Ok(Header {
+                width: width as u32,
+                height: height as u32,
+                colorspace,
+            })
+        }
+
+        /// Decode at 1/`denominator` of the full size (1, 2, 4 or 8), scaling in the DCT domain.
+        pub(super) fn decompress(&self, bytes: &[u8], header: Header, denominator: u32) -> Result<DecodedImage> {
+            let channels = header.channels().ok_or_else(|| eyre!("Unsupported JPEG color space"))?;
+            let width = header.width.div_ceil(denominator);
+            let height = header.height.div_ceil(denominator);
+            check_decode_limits(width as u64, height as u64, channels as u64)?;
+            let mut pixels = vec![0u8; width as usize * height as usize * channels as usize];
+            let (pixel_format, flags) = match (channels, denominator) {
+                (1, 1) => (TJPF_GRAY, TJFLAG_ACCURATEDCT),
+                (1, _) => (TJPF_GRAY, TJFLAG_FASTDCT),
+                (_, 1) => (TJPF_RGB, TJFLAG_ACCURATEDCT),
+                _ => (TJPF_RGB, TJFLAG_FASTDCT | TJFLAG_FASTUPSAMPLE),
+            };
+            // SAFETY: `pixels` holds `height` tightly packed rows of `width` pixels in `pixel_format`, the
+            // size TurboJPEG picks for a scaling factor of 1/`denominator`.
+            let status = unsafe {
+                tjDecompress2(
+                    self.0,
+                    bytes.as_ptr(),
+                    jpeg_size(bytes)?,
+                    pixels.as_mut_ptr(),
+                    width as c_int,
+                    0,
+                    height as c_int,
+                    pixel_format,
+                    flags,
+                )
+            };
+            if status != 0 {
+                return Err(self.error("Failed to decode JPEG image"));
+            }
+            let mut image = DecodedImage::new(width, height, channels, PixelType::U8, DecodedPixels::U8(pixels))?;
+            image.source_format = Some("JPEG".into());
+            Ok(image)
+        }
+
+        fn error(&self, context: &str) -> color_eyre::Report {
+            // SAFETY: TurboJPEG returns a NUL-terminated message owned by the handle.
+            let message = unsafe { CStr::from_ptr(tjGetErrorStr2(self.0)) };
+            eyre!("{context}: {}", message.to_string_lossy())
+        }
+    }
+
+    impl Drop for Decompressor {
+        fn drop(&mut self) {
+            // SAFETY: The handle came from `tjInitDecompress` and is destroyed once.
+            unsafe {
+                tjDestroy(self.0);
+            }
+        }
+    }
+
+    fn jpeg_size(bytes: &[u8]) -> Result<c_ulong> {
+        c_ulong::try_from(bytes.len()).map_err(|_| eyre!("JPEG file is too large"))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::DecodeLimits;
     use exr::prelude::{Image as ExrImage, SpecificChannels, WritableImage};
     use image::{DynamicImage, ImageBuffer, ImageFormat, Rgb};
 
@@ -1699,6 +2497,206 @@ mod tests {
         assert_eq!(normalized_values(&image), vec![2.0, 4.0, 6.0, 8.0]);
     }
 
+    /// PFM file with `rows` given top to bottom; they are written bottom-up as the format requires.
+    fn encode_pfm(header: &str, rows: &[&[f32]], big_endian: bool) -> Vec<u8> {
+        let mut bytes = header.as_bytes().to_vec();
+        for value in rows.iter().rev().flat_map(|row| row.iter()) {
+            if big_endian {
+                bytes.extend_from_slice(&value.to_be_bytes());
+            } else {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn decodes_big_endian_color_pfm() {
+        let rows: [&[f32]; 2] = [&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], &[-1.0, 0.5, 0.25, 1e-3, 1e3, 7.0]];
+        let bytes = encode_pfm("PF\n2 2\n1.0\n", &rows, true);
+        let image = decode_pfm(&bytes).unwrap();
+        assert_eq!((image.width, image.height, image.channels), (2, 2, 3));
+        assert_eq!(image.pixel_type, PixelType::F32);
+        assert_eq!(normalized_values(&image), rows.concat());
+    }
+
+    #[test]
+    fn pfm_scale_magnitude_multiplies_values_for_both_byte_orders() {
+        let rows: [&[f32]; 1] = [&[1.0, -2.0, 4.0]];
+        for (header, big_endian) in [("Pf\n3 1\n0.5\n", true), ("Pf\n3 1\n-0.5\n", false)] {
+            let image = decode_pfm(&encode_pfm(header, &rows, big_endian)).unwrap();
+            assert_eq!(normalized_values(&image), vec![0.5, -1.0, 2.0], "{header:?}");
+        }
+        for (header, big_endian) in [("PF\n1 1\n4\n", true), ("PF\n1 1\n-4\n", false)] {
+            let image = decode_pfm(&encode_pfm(header, &[&[0.25, 0.5, 1.0]], big_endian)).unwrap();
+            assert_eq!(normalized_values(&image), vec![1.0, 2.0, 4.0], "{header:?}");
+        }
+    }
+
+    #[test]
+    fn pfm_header_accepts_comments_crlf_and_split_lines() {
+        let rows: [&[f32]; 2] = [&[1.0, 2.0], &[3.0, 4.0]];
+        let bytes = encode_pfm("Pf\r\n# written by a test\n2\n2 \n-1.0 \r\n", &rows, false);
+        let image = decode_pfm(&bytes).unwrap();
+        assert_eq!((image.width, image.height, image.channels), (2, 2, 1));
+        assert_eq!(normalized_values(&image), rows.concat());
+        assert_eq!(normalized_values(&decode_pfm_owned(bytes).unwrap()), rows.concat());
+    }
+
+    #[test]
+    fn rejects_malformed_pfm() {
+        let values: [&[f32]; 1] = [&[1.0, 2.0]];
+        for header in [
+            "PG\n2 1\n-1.0\n",
+            "Pf\n0 1\n-1.0\n",
+            "Pf\n2 1\n0\n",
+            "Pf\n2 1\nnan\n",
+            "Pf\n2 1\n-1.0",
+        ] {
+            assert!(decode_pfm(&encode_pfm(header, &values, false)).is_err(), "{header:?}");
+        }
+        let mut truncated = encode_pfm("Pf\n2 1\n-1.0\n", &values, false);
+        truncated.pop();
+        assert!(decode_pfm(&truncated).is_err());
+    }
+
+    #[test]
+    fn rejects_headers_beyond_decode_limits_before_allocating() {
+        let huge_pfm = encode_pfm("PF\n2000000 1\n-1.0\n", &[&[0.0; 3]], false);
+        let error = decode_pfm(&huge_pfm).err().unwrap().to_string();
+        assert!(error.contains("decode limit"), "{error}");
+
+        let mut huge_flo = 202021.25_f32.to_le_bytes().to_vec();
+        huge_flo.extend_from_slice(&60_000_i32.to_le_bytes());
+        huge_flo.extend_from_slice(&60_000_i32.to_le_bytes());
+        let error = decode_flo(&huge_flo).err().unwrap().to_string();
+        assert!(error.contains("decode memory limit"), "{error}");
+
+        let limits = DecodeLimits {
+            max_dimension: 100,
+            max_memory_mib: 1,
+        };
+        assert!(limits.check(100, 100, 4).is_ok());
+        assert!(limits.check(101, 1, 1).is_err());
+        assert!(limits.check(100, 100, 27).is_err());
+        assert!(limits.check(u64::MAX / 2, 3, 1).is_err());
+    }
+
+    /// Mutate valid files of each sniffed format with a fixed seed and decode them; every input must produce
+    /// an image or an error without panicking.
+    #[test]
+    fn mutated_headers_decode_or_fail_without_panicking() {
+        let mut seeds = vec![
+            encode_pfm("PF\n2 2\n-1.0\n", &[&[0.5; 6], &[1.0; 6]], false),
+            encode_pfm("Pf\n3 1\n1.0\n", &[&[0.25, 0.5, 0.75]], true),
+            include_bytes!("../../tests/fixtures/palette_4bit.png").to_vec(),
+            include_bytes!("../../tests/fixtures/scan_1bit.bmp").to_vec(),
+            include_bytes!("../../tests/fixtures/gray_2bit.gif").to_vec(),
+        ];
+        let mut flo = 202021.25_f32.to_le_bytes().to_vec();
+        flo.extend_from_slice(&2_i32.to_le_bytes());
+        flo.extend_from_slice(&1_i32.to_le_bytes());
+        flo.extend(std::iter::repeat_n(0, 16));
+        seeds.push(flo);
+        let mut hdr = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n".to_vec();
+        hdr.extend_from_slice(&[128, 64, 32, 129, 1, 2, 3, 128]);
+        seeds.push(hdr);
+
+        // xorshift64, so failures reproduce.
+        let mut state = 0x9E37_79B9_7F4A_7C15_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for seed in &seeds {
+            for _ in 0..300 {
+                let mut bytes = seed.clone();
+                // Headers are where lengths and dimensions live, so most mutations land in the first 64 bytes.
+                for _ in 0..1 + next() % 4 {
+                    let range = if next() % 4 == 0 {
+                        bytes.len()
+                    } else {
+                        bytes.len().min(64)
+                    };
+                    let index = next() as usize % range;
+                    match next() % 3 {
+                        0 => bytes[index] = next() as u8,
+                        1 => bytes[index] = [0x00, 0xFF, 0x7F, 0x80][next() as usize % 4],
+                        _ => bytes.truncate(index.max(1)),
+                    }
+                }
+                let _ = decode_bytes(&bytes);
+                if is_pfm(&bytes) {
+                    let _ = decode_pfm_owned(bytes.clone());
+                }
+                let _ = decode_flo(&bytes);
+            }
+        }
+    }
+
+    #[test]
+    fn sniffs_pfm_without_an_extension() {
+        let bytes = encode_pfm("PF\n1 1\n-1.0\n", &[&[0.25, 0.5, 0.75]], false);
+        let image = decode_bytes(&bytes).unwrap();
+        assert_eq!(image.channels, 3);
+        assert_eq!(normalized_values(&image), vec![0.25, 0.5, 0.75]);
+        assert!(!is_pfm(b"P6\n1 1\n255\n"));
+    }
+
+    #[test]
+    fn decodes_flat_rgbe_and_divides_out_the_exposure() {
+        let mut bytes =
+            b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\nEXPOSURE=2.0\nPIXASPECT=1.5\nSOFTWARE=test\n\n-Y 2 +X 2\n".to_vec();
+        bytes.extend_from_slice(&[128, 64, 32, 129, 255, 0, 0, 128, 0, 0, 0, 0, 1, 2, 3, 140]);
+        let image = decode_bytes(&bytes).unwrap();
+        assert_eq!((image.width, image.height, image.channels), (2, 2, 3));
+        assert_eq!(image.pixel_type, PixelType::F32);
+        // Reference values from rgbe.c: mantissa * 2^(exponent - 136), halved by EXPOSURE=2.
+        assert_eq!(
+            normalized_values(&image),
+            vec![
+                0.5,
+                0.25,
+                0.125,
+                255.0 / 512.0,
+                0.0,
+                0.0,
+                0.0,
+                0.0,
+                0.0,
+                8.0,
+                16.0,
+                24.0
+            ]
+        );
+        let metadata: Vec<(&str, &str)> = image.metadata.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        assert_eq!(
+            metadata,
+            [
+                ("Exposure", "2 (divided out)"),
+                ("Pixel aspect", "1.5"),
+                ("SOFTWARE", "test")
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_run_length_encoded_rgbe_scanlines() {
+        let mut bytes = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 8\n".to_vec();
+        bytes.extend_from_slice(&[2, 2, 0, 8]);
+        bytes.extend_from_slice(&[128 + 8, 128, 128 + 8, 64]);
+        bytes.push(8);
+        bytes.extend(0..8u8);
+        bytes.extend_from_slice(&[128 + 8, 129]);
+        let image = decode_bytes(&bytes).unwrap();
+        assert_eq!((image.width, image.height, image.channels), (8, 1, 3));
+        let expected: Vec<f32> = (0..8).flat_map(|x| [1.0, 0.5, x as f32 / 128.0]).collect();
+        assert_eq!(normalized_values(&image), expected);
+        assert!(image.metadata.is_empty());
+    }
+
     #[test]
     fn decodes_middlebury_flo() {
         let mut bytes = 202021.25_f32.to_le_bytes().to_vec();
@@ -1710,6 +2708,7 @@ mod tests {
         let image = decode_flo(&bytes).unwrap();
         assert_eq!((image.width, image.height, image.channels), (2, 1, 2));
         assert_eq!(normalized_values(&image), vec![1.0, -1.0, 2.0, -2.0]);
+        assert!(!image.gray_alpha, "flow vectors are not luminance and alpha");
     }
 
     #[test]
@@ -1733,6 +2732,88 @@ mod tests {
         }
     }
 
+    #[test]
+    fn incremental_png_reports_partial_rows_and_matches_full_decode() {
+        let (width, height) = (3_u32, 10_u32);
+        let pixels: Vec<u16> = (0..width * height * 2).map(|i| (i * 997) as u16).collect();
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut bytes, width, height);
+            encoder.set_color(png::ColorType::GrayscaleAlpha);
+            encoder.set_depth(png::BitDepth::Sixteen);
+            let data: Vec<u8> = pixels.iter().flat_map(|v| v.to_be_bytes()).collect();
+            encoder.write_header().unwrap().write_image_data(&data).unwrap();
+        }
+
+        let mut partial_rows = Vec::new();
+        let decoded = decode_png_incremental(Cursor::new(&bytes), 0, &mut |partial| {
+            let values = normalized_values(&partial);
+            let filled = values
+                .chunks(width as usize * 2)
+                .take_while(|row| row.iter().any(|&v| v != 0.0));
+            partial_rows.push(filled.count());
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(partial_rows, [2, 4, 6, 8]);
+        assert_eq!(decoded.pixel_type, PixelType::U16);
+        assert!(decoded.gray_alpha);
+        let full = decode_bytes(&bytes).unwrap();
+        assert!(full.gray_alpha);
+        assert_eq!(normalized_values(&decoded), normalized_values(&full));
+
+        assert!(decode_png_incremental(Cursor::new(&bytes), u64::MAX, &mut |_| {})
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn expands_palettes_and_low_bit_depths_from_fixture_files() {
+        let palette = decode_bytes(include_bytes!("../../tests/fixtures/palette_4bit.png")).unwrap();
+        assert_eq!((palette.width, palette.height, palette.channels), (4, 2, 3));
+        assert_eq!(palette.source_format.as_deref(), Some("PNG, indexed 4-bit"));
+        assert_eq!(
+            normalized_values(&palette)[..12],
+            [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0]
+        );
+
+        let row0 = [1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0];
+        let expected: Vec<f32> = row0.into_iter().chain([1.0; 9]).collect();
+        let gray = decode_bytes(include_bytes!("../../tests/fixtures/gray_1bit.png")).unwrap();
+        assert_eq!((gray.width, gray.height, gray.channels), (9, 2, 1));
+        assert_eq!(gray.source_format.as_deref(), Some("PNG, gray 1-bit"));
+        assert_eq!(normalized_values(&gray), expected);
+
+        let scan = decode_bytes(include_bytes!("../../tests/fixtures/scan_1bit.bmp")).unwrap();
+        assert_eq!((scan.width, scan.height, scan.channels), (9, 2, 1));
+        assert_eq!(scan.source_format.as_deref(), Some("BMP, indexed 1-bit"));
+        assert_eq!(normalized_values(&scan), expected);
+
+        let gif = decode_bytes(include_bytes!("../../tests/fixtures/gray_2bit.gif")).unwrap();
+        assert_eq!((gif.width, gif.height, gif.channels), (4, 2, 1));
+        assert_eq!(gif.source_format.as_deref(), Some("GIF, indexed 2-bit"));
+        let levels = [0.0, 85.0 / 255.0, 170.0 / 255.0, 1.0];
+        let expected: Vec<f32> = levels.into_iter().chain(levels.into_iter().rev()).collect();
+        assert_eq!(normalized_values(&gif), expected);
+    }
+
+    #[test]
+    fn decodes_multi_channel_exr_without_rgb_names() {
+        let channels = SpecificChannels::build()
+            .with_channel::<f32>("motion.u")
+            .with_channel::<f32>("motion.v")
+            .with_pixel_fn(|position| (position.x() as f32, -(position.y() as f32)));
+        let mut bytes = Cursor::new(Vec::new());
+        ExrImage::from_channels((2, 2), channels)
+            .write()
+            .to_buffered(&mut bytes)
+            .unwrap();
+
+        let decoded = decode_bytes(bytes.get_ref()).unwrap();
+        assert_eq!((decoded.width, decoded.height, decoded.channels), (2, 2, 2));
+        assert_eq!(normalized_values(&decoded), vec![0.0, 0.0, 1.0, 0.0, 0.0, -1.0, 1.0, -1.0]);
+    }
+
     #[test]
     fn dedicated_tiff_path_decodes_signed_and_float_gray_samples() {
         use tiff::encoder::{colortype, TiffEncoder};
@@ -1758,6 +2839,196 @@ mod tests {
         assert_eq!(normalized_values(&decoded), vec![-0.25, 0.5, 2.0]);
     }
 
+    /// Little-endian, uncompressed 32-bit float TIFF. `chunks` are the strips or tiles in file order; with
+    /// `tile` set they are square tiles of that size, otherwise strips of `height / strips per plane` rows.
+    fn encode_float_tiff(
+        width: u32,
+        height: u32,
+        samples: u16,
+        planar: bool,
+        tile: Option<u32>,
+        chunks: &[Vec<f32>],
+    ) -> Vec<u8> {
+        const SHORT: u16 = 3;
+        const LONG: u16 = 4;
+        let mut bytes = b"II\x2a\x00\0\0\0\0".to_vec();
+        let (mut offsets, mut counts) = (Vec::new(), Vec::new());
+        for chunk in chunks {
+            offsets.push(bytes.len() as u32);
+            counts.push((chunk.len() * 4) as u32);
+            bytes.extend(chunk.iter().flat_map(|value| value.to_le_bytes()));
+        }
+        let planes = if planar { samples as usize } else { 1 };
+        let per_sample = |value: u32| vec![value; samples as usize];
+        let mut entries: Vec<(u16, u16, Vec<u32>)> = vec![
+            (256, LONG, vec![width]),
+            (257, LONG, vec![height]),
+            (258, SHORT, per_sample(32)),
+            (259, SHORT, vec![1]),
+            (262, SHORT, vec![if samples >= 3 { 2 } else { 1 }]),
+            (277, SHORT, vec![samples as u32]),
+            (284, SHORT, vec![if planar { 2 } else { 1 }]),
+            (339, SHORT, per_sample(3)),
+        ];
+        match tile {
+            Some(size) => entries.extend([
+                (322, LONG, vec![size]),
+                (323, LONG, vec![size]),
+                (324, LONG, offsets),
+                (325, LONG, counts),
+            ]),
+            None => entries.extend([
+                (273, LONG, offsets),
+                (278, LONG, vec![height / (chunks.len() / planes) as u32]),
+                (279, LONG, counts),
+            ]),
+        }
+        entries.sort_by_key(|(tag, _, _)| *tag);
+
+        let encode_values = |kind: u16, values: &[u32]| -> Vec<u8> {
+            values
+                .iter()
+                .flat_map(|&value| match kind {
+                    SHORT => (value as u16).to_le_bytes().to_vec(),
+                    _ => value.to_le_bytes().to_vec(),
+                })
+                .collect()
+        };
+        let mut inline = Vec::new();
+        for (_, kind, values) in &entries {
+            let encoded = encode_values(*kind, values);
+            if encoded.len() <= 4 {
+                inline.push(encoded);
+            } else {
+                inline.push((bytes.len() as u32).to_le_bytes().to_vec());
+                bytes.extend(encoded);
+            }
+        }
+        let ifd_offset = bytes.len() as u32;
+        bytes[4..8].copy_from_slice(&ifd_offset.to_le_bytes());
+        bytes.extend((entries.len() as u16).to_le_bytes());
+        for ((tag, kind, values), mut value) in entries.iter().zip(inline) {
+            bytes.extend(tag.to_le_bytes());
+            bytes.extend(kind.to_le_bytes());
+            bytes.extend((values.len() as u32).to_le_bytes());
+            value.resize(4, 0);
+            bytes.extend(value);
+        }
+        bytes.extend(0_u32.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn decodes_planar_float_tiff_strips() {
+        let (width, height) = (3, 2);
+        let value = |x: u32, y: u32, c: u32| (x + 10 * y) as f32 + c as f32 * 0.25;
+        let plane = |c: u32| {
+            (0..height)
+                .flat_map(|y| (0..width).map(move |x| value(x, y, c)))
+                .collect::<Vec<_>>()
+        };
+        let planar = encode_float_tiff(width, height, 3, true, None, &[plane(0), plane(1), plane(2)]);
+        let chunky_rows: Vec<Vec<f32>> = (0..height)
+            .map(|y| (0..width).flat_map(|x| (0..3).map(move |c| value(x, y, c))).collect())
+            .collect();
+        let chunky = encode_float_tiff(width, height, 3, false, None, &chunky_rows);
+
+        let expected = chunky_rows.concat();
+        for bytes in [planar, chunky] {
+            let decoded = decode_bytes(&bytes).unwrap();
+            assert_eq!((decoded.width, decoded.height, decoded.channels), (3, 2, 3));
+            assert_eq!(decoded.pixel_type, PixelType::F32);
+            assert_eq!(normalized_values(&decoded), expected);
+        }
+    }
+
+    #[test]
+    fn decodes_tiled_planar_float_tiff() {
+        let (width, height, tile) = (20_u32, 3_u32, 16_u32);
+        let value = |x: u32, y: u32, c: u32| -((x + 100 * y) as f32) - c as f32 * 1000.0;
+        let mut tiles = Vec::new();
+        for c in 0..3 {
+            for tile_x in [0, tile] {
+                tiles.push(
+                    (0..tile)
+                        .flat_map(|y| (0..tile).map(move |x| (tile_x + x, y)))
+                        .map(|(x, y)| if x < width && y < height { value(x, y, c) } else { 0.0 })
+                        .collect(),
+                );
+            }
+        }
+        let decoded = decode_bytes(&encode_float_tiff(width, height, 3, true, Some(tile), &tiles)).unwrap();
+        assert_eq!((decoded.width, decoded.height, decoded.channels), (20, 3, 3));
+        let expected: Vec<f32> = (0..height)
+            .flat_map(|y| (0..width).flat_map(move |x| (0..3).map(move |c| value(x, y, c))))
+            .collect();
+        assert_eq!(normalized_values(&decoded), expected);
+    }
+
+    #[test]
+    fn incremental_tiff_reports_strips_and_tile_rows_and_matches_full_decode() {
+        let value = |x: u32, y: u32, c: u32| 1.0 + (x + 100 * y) as f32 + c as f32 * 0.25;
+        let filled_rows = |partial: &DecodedImage, width: u32| {
+            normalized_values(partial)
+                .chunks(width as usize * 3)
+                .take_while(|row| row.iter().any(|&v| v != 0.0))
+                .count()
+        };
+
+        let (width, height) = (3_u32, 10_u32);
+        let strips: Vec<Vec<f32>> = (0..height)
+            .map(|y| (0..width).flat_map(|x| (0..3).map(move |c| value(x, y, c))).collect())
+            .collect();
+        let tiled_width = 20_u32;
+        let tiled_height = 40_u32;
+        let tile = 16_u32;
+        let mut tiles = Vec::new();
+        for tile_y in (0..tiled_height).step_by(tile as usize) {
+            for tile_x in [0, tile] {
+                tiles.push(
+                    (0..tile)
+                        .flat_map(|y| (0..tile).map(move |x| (tile_x + x, tile_y + y)))
+                        .flat_map(|(x, y)| {
+                            (0..3).map(move |c| {
+                                if x < tiled_width && y < tiled_height {
+                                    value(x, y, c)
+                                } else {
+                                    0.0
+                                }
+                            })
+                        })
+                        .collect(),
+                );
+            }
+        }
+
+        for (bytes, width, expected_rows) in [
+            (
+                encode_float_tiff(width, height, 3, false, None, &strips),
+                width,
+                vec![2, 4, 6, 8],
+            ),
+            (
+                encode_float_tiff(tiled_width, tiled_height, 3, false, Some(tile), &tiles),
+                tiled_width,
+                vec![16, 32],
+            ),
+        ] {
+            let mut partial_rows = Vec::new();
+            let decoded = decode_tiff_incremental(Cursor::new(&bytes), 0, &mut |partial| {
+                partial_rows.push(filled_rows(&partial, width));
+            })
+            .unwrap();
+            assert_eq!(partial_rows, expected_rows);
+            assert_eq!(decoded.pixel_type, PixelType::F32);
+            assert_eq!(normalized_values(&decoded), normalized_values(&decode_bytes(&bytes).unwrap()));
+
+            let mut calls = 0;
+            decode_tiff_incremental(Cursor::new(&bytes), u64::MAX, &mut |_| calls += 1).unwrap();
+            assert_eq!(calls, 0);
+        }
+    }
+
     #[test]
     fn image_rs_round_trips_enabled_ldr_formats() {
         let source = DynamicImage::ImageRgb8(ImageBuffer::from_fn(3, 2, |x, y| {
@@ -1796,4 +3067,63 @@ mod tests {
         assert_eq!((decoded.width, decoded.height, decoded.channels), (3, 2, 4));
         assert_eq!(decoded.pixel_type, PixelType::U16);
     }
+
+    #[cfg(feature = "turbojpeg")]
+    fn encode_jpeg(image: DynamicImage) -> Vec<u8> {
+        let mut encoded = Cursor::new(Vec::new());
+        image.write_to(&mut encoded, ImageFormat::Jpeg).unwrap();
+        encoded.into_inner()
+    }
+
+    #[cfg(feature = "turbojpeg")]
+    #[test]
+    fn turbojpeg_decode_matches_image_rs() {
+        let source = DynamicImage::ImageRgb8(ImageBuffer::from_fn(37, 21, |x, y| {
+            Rgb([(x * 6) as u8, (y * 11) as u8, ((x + y) * 4) as u8])
+        }));
+        let bytes = encode_jpeg(source);
+        let decoded = decode_bytes(&bytes).unwrap();
+        assert_eq!((decoded.width, decoded.height, decoded.channels), (37, 21, 3));
+        assert_eq!(decoded.source_format.as_deref(), Some("JPEG"));
+        let reference = image::load_from_memory_with_format(&bytes, ImageFormat::Jpeg)
+            .unwrap()
+            .into_rgb8();
+        let DecodedPixels::U8(pixels) = &decoded.pixels else {
+            panic!("expected 8-bit pixels");
+        };
+        let max_difference = pixels
+            .iter()
+            .zip(reference.as_raw())
+            .map(|(a, b)| a.abs_diff(*b))
+            .max()
+            .unwrap();
+        assert!(max_difference <= 4, "max difference {max_difference}");
+
+        let gray = encode_jpeg(DynamicImage::ImageLuma8(ImageBuffer::from_fn(8, 8, |x, _| {
+            image::Luma([(x * 30) as u8])
+        })));
+        assert_eq!(decode_bytes(&gray).unwrap().channels, 1);
+    }
+
+    #[cfg(feature = "turbojpeg")]
+    #[test]
+    fn turbojpeg_scales_in_the_dct_domain() {
+        let bytes = encode_jpeg(DynamicImage::ImageRgb8(ImageBuffer::from_pixel(37, 21, Rgb([200, 100, 50]))));
+        let decompressor = turbojpeg::Decompressor::new().unwrap();
+        let header = decompressor.read_header(&bytes).unwrap();
+        assert_eq!((header.width, header.height), (37, 21));
+        for (denominator, size) in [(1, (37, 21)), (2, (19, 11)), (4, (10, 6))] {
+            let image = decompressor.decompress(&bytes, header, denominator).unwrap();
+            assert_eq!((image.width, image.height), size, "1/{denominator}");
+        }
+        assert!(decompressor.read_header(b"not a jpeg").is_err());
+    }
+
+    #[cfg(feature = "turbojpeg")]
+    #[test]
+    fn jpeg_previews_scale_with_image_size() {
+        assert_eq!(jpeg_preview_denominator(1920, 1080), None);
+        assert_eq!(jpeg_preview_denominator(2048, 2048), Some(2));
+        assert_eq!(jpeg_preview_denominator(4096, 4096), Some(4));
+    }
 }
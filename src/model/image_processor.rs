@@ -1,6 +1,6 @@
 use std::sync::{
     atomic::{AtomicBool, AtomicU64, Ordering},
-    Arc, Mutex,
+    mpsc, Arc, Mutex, Weak,
 };
 
 use color_eyre::eyre::{eyre, Result};
@@ -14,14 +14,45 @@ pub enum MeanDim {
     Row,
 }
 
-/// CPU summed-area table using the same normalized f32 values as `ImageData`.
-/// Accumulation is f64 to preserve the precision of the former integral table.
+/// Rows and columns between the sampled lines of the integral table.
+const INTEGRAL_STEP: usize = 32;
+
+/// CPU summed-area table using the same normalized f32 values as `ImageData`, sampled every `INTEGRAL_STEP` rows
+/// and every `INTEGRAL_STEP` columns so it needs about 1/16 of a full table. Queries add the few pixels between
+/// sampled lines directly. Accumulation is f64 to preserve the precision of the former integral table.
 pub(crate) struct IntegralImage {
     width: usize,
     height: usize,
     channels: usize,
-    stride: usize,
-    values: Vec<f64>,
+    /// Sums over `[0, x) x [0, k * STEP)` for every x in `0..=width`, one line per sampled row k.
+    row_samples: Vec<f64>,
+    /// Sums over `[0, k * STEP) x [0, y)` for every y in `0..=height`; each row holds one entry per sampled column k.
+    col_samples: Vec<f64>,
+    col_sample_count: usize,
+}
+
+/// Collects the sampled lines of the integral table while the builders stream full integral rows.
+struct IntegralSampler<'a> {
+    width: usize,
+    channels: usize,
+    col_sample_count: usize,
+    row_samples: &'a mut Vec<f64>,
+    col_samples: &'a mut Vec<f64>,
+}
+
+impl IntegralSampler<'_> {
+    /// Record integral row `y` (sums over `[0, x) x [0, y)` for x in `0..=width`).
+    #[inline]
+    fn push_row(&mut self, y: usize, row: &[f64]) {
+        if y.is_multiple_of(INTEGRAL_STEP) {
+            self.row_samples.extend_from_slice(row);
+        }
+        for k in 0..self.col_sample_count {
+            let start = k * INTEGRAL_STEP * self.channels;
+            self.col_samples.extend_from_slice(&row[start..start + self.channels]);
+        }
+        debug_assert_eq!(row.len(), (self.width + 1) * self.channels);
+    }
 }
 
 #[inline(always)]
@@ -29,31 +60,31 @@ fn build_cpu_integral<const CHANNELS: usize>(
     pixels: &[f32],
     width: usize,
     height: usize,
-    stride: usize,
-    values: &mut [f64],
-    active_image_id: &AtomicU64,
-    image_id: u64,
+    row: &mut [f64],
+    sampler: &mut IntegralSampler,
+    generation: &AtomicU64,
+    expected_generation: u64,
 ) -> bool {
     let input = pixels.as_ptr();
-    let output = values.as_mut_ptr();
+    let output = row.as_mut_ptr();
     for y in 0..height {
-        if active_image_id.load(Ordering::Acquire) != image_id {
+        if generation.load(Ordering::Acquire) != expected_generation {
             return false;
         }
         let mut row_sum = [0.0f64; CHANNELS];
         for x in 0..width {
             let pixel = (y * width + x) * CHANNELS;
-            let dst = ((y + 1) * stride + x + 1) * CHANNELS;
-            let above = (y * stride + x + 1) * CHANNELS;
+            let dst = (x + 1) * CHANNELS;
             for (channel, row_sum) in row_sum.iter_mut().enumerate() {
-                // ImageData validates the pixel count, and the integral allocation
-                // includes the one-pixel top/left border used by these offsets.
+                // ImageData validates the pixel count, and `row` holds width + 1 entries including the
+                // zero left border, so every offset is in bounds. The entry still holds row y's value.
                 unsafe {
                     *row_sum += *input.add(pixel + channel) as f64;
-                    *output.add(dst + channel) = *output.add(above + channel) + *row_sum;
+                    *output.add(dst + channel) += *row_sum;
                 }
             }
         }
+        sampler.push_row(y + 1, row);
     }
     true
 }
@@ -62,19 +93,19 @@ fn build_cpu_integral_rgba(
     pixels: &[f32],
     width: usize,
     height: usize,
-    stride: usize,
-    values: &mut [f64],
-    active_image_id: &AtomicU64,
-    image_id: u64,
+    row: &mut [f64],
+    sampler: &mut IntegralSampler,
+    generation: &AtomicU64,
+    expected_generation: u64,
 ) -> bool {
     #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
     if std::arch::is_x86_feature_detected!("avx2") {
         // The runtime feature check satisfies the target_feature contract.
         return unsafe {
-            build_cpu_integral_rgba_avx2(pixels, width, height, stride, values, active_image_id, image_id)
+            build_cpu_integral_rgba_avx2(pixels, width, height, row, sampler, generation, expected_generation)
         };
     }
-    build_cpu_integral::<4>(pixels, width, height, stride, values, active_image_id, image_id)
+    build_cpu_integral::<4>(pixels, width, height, row, sampler, generation, expected_generation)
 }
 
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
@@ -83,10 +114,10 @@ unsafe fn build_cpu_integral_rgba_avx2(
     pixels: &[f32],
     width: usize,
     height: usize,
-    stride: usize,
-    values: &mut [f64],
-    active_image_id: &AtomicU64,
-    image_id: u64,
+    row: &mut [f64],
+    sampler: &mut IntegralSampler,
+    generation: &AtomicU64,
+    expected_generation: u64,
 ) -> bool {
     #[cfg(target_arch = "x86")]
     use std::arch::x86::*;
@@ -94,20 +125,20 @@ unsafe fn build_cpu_integral_rgba_avx2(
     use std::arch::x86_64::*;
 
     let input = pixels.as_ptr();
-    let output = values.as_mut_ptr();
     for y in 0..height {
-        if active_image_id.load(Ordering::Acquire) != image_id {
+        if generation.load(Ordering::Acquire) != expected_generation {
             return false;
         }
+        let output = row.as_mut_ptr();
         let mut row_sum = _mm256_setzero_pd();
         for x in 0..width {
             let pixel = (y * width + x) * 4;
-            let dst = ((y + 1) * stride + x + 1) * 4;
-            let above = (y * stride + x + 1) * 4;
+            let dst = (x + 1) * 4;
             row_sum = _mm256_add_pd(row_sum, _mm256_cvtps_pd(_mm_loadu_ps(input.add(pixel))));
-            let total = _mm256_add_pd(_mm256_loadu_pd(output.add(above)), row_sum);
+            let total = _mm256_add_pd(_mm256_loadu_pd(output.add(dst)), row_sum);
             _mm256_storeu_pd(output.add(dst), total);
         }
+        sampler.push_row(y + 1, row);
     }
     true
 }
@@ -117,13 +148,13 @@ fn build_derived_integral<const CHANNELS: usize>(
     image: &ImageData,
     width: usize,
     height: usize,
-    stride: usize,
-    values: &mut [f64],
-    active_image_id: &AtomicU64,
-    image_id: u64,
+    row: &mut [f64],
+    sampler: &mut IntegralSampler,
+    generation: &AtomicU64,
+    expected_generation: u64,
 ) -> Result<bool> {
     for y in 0..height {
-        if active_image_id.load(Ordering::Acquire) != image_id {
+        if generation.load(Ordering::Acquire) != expected_generation {
             return Ok(false);
         }
         let mut row_sum = [0.0f64; CHANNELS];
@@ -133,19 +164,21 @@ fn build_derived_integral<const CHANNELS: usize>(
                 .normalized_pixel_at(pixel)
                 .ok_or_else(|| eyre!("Image pixels are unavailable for integral precompute"))?;
             debug_assert_eq!(channels, CHANNELS);
-            let dst = ((y + 1) * stride + x + 1) * CHANNELS;
-            let above = (y * stride + x + 1) * CHANNELS;
+            let dst = (x + 1) * CHANNELS;
             for channel in 0..CHANNELS {
                 row_sum[channel] += pixel_values[channel] as f64;
-                values[dst + channel] = values[above + channel] + row_sum[channel];
+                row[dst + channel] += row_sum[channel];
             }
         }
+        sampler.push_row(y + 1, row);
     }
     Ok(true)
 }
 
 impl IntegralImage {
-    pub(crate) fn build(image: &ImageData, active_image_id: &AtomicU64) -> Result<Option<Self>> {
+    /// Build the table for `image`, stopping early (returning `None`) once `generation` no longer equals
+    /// `expected_generation`.
+    pub(crate) fn build(image: &ImageData, generation: &AtomicU64, expected_generation: u64) -> Result<Option<Self>> {
         let spec = image.spec();
         if spec.width <= 0 || spec.height <= 0 || !(1..=4).contains(&spec.channels) {
             return Err(eyre!("Invalid image dimensions for integral table"));
@@ -153,59 +186,79 @@ impl IntegralImage {
         let width = spec.width as usize;
         let height = spec.height as usize;
         let channels = spec.channels as usize;
-        let stride = width + 1;
-        let element_count = stride
+        let row_sample_count = height / INTEGRAL_STEP + 1;
+        let col_sample_count = width / INTEGRAL_STEP + 1;
+        let row_samples_len = row_sample_count
+            .checked_mul(width + 1)
+            .and_then(|value| value.checked_mul(channels))
+            .ok_or_else(|| eyre!("Integral table size overflow"))?;
+        let col_samples_len = col_sample_count
             .checked_mul(height + 1)
             .and_then(|value| value.checked_mul(channels))
             .ok_or_else(|| eyre!("Integral table size overflow"))?;
-        let mut values = Vec::new();
-        values
-            .try_reserve_exact(element_count)
+        let mut row_samples = Vec::new();
+        let mut col_samples = Vec::new();
+        row_samples
+            .try_reserve_exact(row_samples_len)
+            .and_then(|_| col_samples.try_reserve_exact(col_samples_len))
             .map_err(|error| eyre!("Failed to allocate integral table: {error}"))?;
-        values.resize(element_count, 0.0);
 
-        let image_id = image.id();
+        let mut sampler = IntegralSampler {
+            width,
+            channels,
+            col_sample_count,
+            row_samples: &mut row_samples,
+            col_samples: &mut col_samples,
+        };
+        let mut row = vec![0.0; (width + 1) * channels];
+        sampler.push_row(0, &row);
+        let (row, sampler) = (&mut row, &mut sampler);
         let complete = match (image.pixels(), channels) {
             (Some(pixels), 1) => {
-                build_cpu_integral::<1>(pixels, width, height, stride, &mut values, active_image_id, image_id)
+                build_cpu_integral::<1>(pixels, width, height, row, sampler, generation, expected_generation)
             }
             (Some(pixels), 2) => {
-                build_cpu_integral::<2>(pixels, width, height, stride, &mut values, active_image_id, image_id)
+                build_cpu_integral::<2>(pixels, width, height, row, sampler, generation, expected_generation)
             }
             (Some(pixels), 3) => {
-                build_cpu_integral::<3>(pixels, width, height, stride, &mut values, active_image_id, image_id)
+                build_cpu_integral::<3>(pixels, width, height, row, sampler, generation, expected_generation)
             }
             (Some(pixels), 4) => {
-                build_cpu_integral_rgba(pixels, width, height, stride, &mut values, active_image_id, image_id)
+                build_cpu_integral_rgba(pixels, width, height, row, sampler, generation, expected_generation)
             }
             (None, 1) => {
-                build_derived_integral::<1>(image, width, height, stride, &mut values, active_image_id, image_id)?
+                build_derived_integral::<1>(image, width, height, row, sampler, generation, expected_generation)?
             }
             (None, 2) => {
-                build_derived_integral::<2>(image, width, height, stride, &mut values, active_image_id, image_id)?
+                build_derived_integral::<2>(image, width, height, row, sampler, generation, expected_generation)?
             }
             (None, 3) => {
-                build_derived_integral::<3>(image, width, height, stride, &mut values, active_image_id, image_id)?
+                build_derived_integral::<3>(image, width, height, row, sampler, generation, expected_generation)?
             }
             (None, 4) => {
-                build_derived_integral::<4>(image, width, height, stride, &mut values, active_image_id, image_id)?
+                build_derived_integral::<4>(image, width, height, row, sampler, generation, expected_generation)?
             }
             _ => unreachable!("channel count was validated above"),
         };
         if !complete {
             return Ok(None);
         }
+        debug_assert_eq!(row_samples.len(), row_samples_len);
+        debug_assert_eq!(col_samples.len(), col_samples_len);
 
         Ok(Some(Self {
             width,
             height,
             channels,
-            stride,
-            values,
+            row_samples,
+            col_samples,
+            col_sample_count,
         }))
     }
 
-    pub(crate) fn mean(&self, rect: Recti, dim: MeanDim) -> Result<Vec<f64>> {
+    /// Mean over `rect`. `image` must be the image the table was built from; it supplies the pixels between
+    /// sampled lines.
+    pub(crate) fn mean(&self, image: &ImageData, rect: Recti, dim: MeanDim) -> Result<Vec<f64>> {
         let rect = rect.validate();
         if rect.empty() {
             return Ok(Vec::new());
@@ -220,112 +273,174 @@ impl IntegralImage {
         {
             return Err(eyre!("Mean rectangle is outside the image"));
         }
-        let x = x as usize;
-        let y = y as usize;
-        let width = width as usize;
-        let height = height as usize;
+        let (x0, y0) = (x as usize, y as usize);
+        let (x1, y1) = (x0 + width as usize, y0 + height as usize);
+        let pixels = PixelSource::new(image, self.channels)?;
 
         match dim {
             MeanDim::All => {
-                let mut result = vec![0.0; self.channels];
-                let divisor = (width * height) as f64;
-                for (channel, value) in result.iter_mut().enumerate() {
-                    *value = self.rect_sum(x, y, width, height, channel) / divisor;
+                let mut sum = vec![0.0; self.channels];
+                let (k0, k1) = (y0.div_ceil(INTEGRAL_STEP), y1 / INTEGRAL_STEP);
+                if k0 < k1 {
+                    let line = |k: usize, x: usize| (k * (self.width + 1) + x) * self.channels;
+                    for (channel, sum) in sum.iter_mut().enumerate() {
+                        let s = &self.row_samples;
+                        *sum = s[line(k1, x1) + channel] - s[line(k1, x0) + channel] - s[line(k0, x1) + channel]
+                            + s[line(k0, x0) + channel];
+                    }
+                    for y in (y0..k0 * INTEGRAL_STEP).chain(k1 * INTEGRAL_STEP..y1) {
+                        self.add_row_sum(&pixels, y, x0, x1, &mut sum);
+                    }
+                } else {
+                    for y in y0..y1 {
+                        self.add_row_sum(&pixels, y, x0, x1, &mut sum);
+                    }
                 }
-                Ok(result)
+                let divisor = ((x1 - x0) * (y1 - y0)) as f64;
+                Ok(sum.into_iter().map(|value| value / divisor).collect())
             }
             MeanDim::Column => {
-                let mut result = vec![0.0; width * self.channels];
-                let top_left = (y * self.stride + x) * self.channels;
-                let bottom_left = ((y + height) * self.stride + x) * self.channels;
-                let divisor = height as f64;
-                for (offset, value) in result.iter_mut().enumerate() {
-                    *value = (self.values[bottom_left + self.channels + offset]
-                        - self.values[bottom_left + offset]
-                        - self.values[top_left + self.channels + offset]
-                        + self.values[top_left + offset])
-                        / divisor;
+                let mut result = vec![0.0; (x1 - x0) * self.channels];
+                let divisor = (y1 - y0) as f64;
+                for (x, sum) in (x0..x1).zip(result.chunks_exact_mut(self.channels)) {
+                    self.add_column_sum(&pixels, x, y0, y1, sum);
+                    sum.iter_mut().for_each(|value| *value /= divisor);
                 }
                 Ok(result)
             }
             MeanDim::Row => {
-                let mut result = vec![0.0; height * self.channels];
-                let row_stride = self.stride * self.channels;
-                let right_offset = width * self.channels;
-                let divisor = width as f64;
-                let values = self.values.as_ptr();
-                let output = result.as_mut_ptr();
-                for row in 0..height {
-                    let top_left = ((y + row) * self.stride + x) * self.channels;
-                    let bottom_left = top_left + row_stride;
-                    for channel in 0..self.channels {
-                        // Bounds were established by the rectangle validation above, and all
-                        // offsets address the allocated (width + 1) x (height + 1) table.
-                        unsafe {
-                            *output.add(row * self.channels + channel) = (*values
-                                .add(bottom_left + right_offset + channel)
-                                - *values.add(bottom_left + channel)
-                                - *values.add(top_left + right_offset + channel)
-                                + *values.add(top_left + channel))
-                                / divisor;
-                        }
-                    }
+                let mut result = vec![0.0; (y1 - y0) * self.channels];
+                let divisor = (x1 - x0) as f64;
+                for (y, sum) in (y0..y1).zip(result.chunks_exact_mut(self.channels)) {
+                    self.add_row_sum(&pixels, y, x0, x1, sum);
+                    sum.iter_mut().for_each(|value| *value /= divisor);
                 }
                 Ok(result)
             }
         }
     }
 
-    #[inline]
-    fn rect_sum(&self, x: usize, y: usize, width: usize, height: usize, channel: usize) -> f64 {
-        let top_left = (y * self.stride + x) * self.channels + channel;
-        let top_right = (y * self.stride + x + width) * self.channels + channel;
-        let bottom_left = ((y + height) * self.stride + x) * self.channels + channel;
-        let bottom_right = ((y + height) * self.stride + x + width) * self.channels + channel;
-        self.values[bottom_right] - self.values[bottom_left] - self.values[top_right] + self.values[top_left]
+    /// Add the sum of row `y` over `[x0, x1)` to `sum`, using the sampled columns for the aligned middle part.
+    fn add_row_sum(&self, pixels: &PixelSource, y: usize, x0: usize, x1: usize, sum: &mut [f64]) {
+        let (k0, k1) = (x0.div_ceil(INTEGRAL_STEP), x1 / INTEGRAL_STEP);
+        if k0 >= k1 {
+            pixels.add_span(y * self.width + x0, 1, x1 - x0, sum);
+            return;
+        }
+        let at = |y: usize, k: usize| (y * self.col_sample_count + k) * self.channels;
+        let s = &self.col_samples;
+        for (channel, sum) in sum.iter_mut().enumerate() {
+            *sum += s[at(y + 1, k1) + channel] - s[at(y, k1) + channel] - s[at(y + 1, k0) + channel]
+                + s[at(y, k0) + channel];
+        }
+        pixels.add_span(y * self.width + x0, 1, k0 * INTEGRAL_STEP - x0, sum);
+        pixels.add_span(y * self.width + k1 * INTEGRAL_STEP, 1, x1 - k1 * INTEGRAL_STEP, sum);
+    }
+
+    /// Add the sum of column `x` over `[y0, y1)` to `sum`, using the sampled rows for the aligned middle part.
+    fn add_column_sum(&self, pixels: &PixelSource, x: usize, y0: usize, y1: usize, sum: &mut [f64]) {
+        let (k0, k1) = (y0.div_ceil(INTEGRAL_STEP), y1 / INTEGRAL_STEP);
+        if k0 >= k1 {
+            pixels.add_span(y0 * self.width + x, self.width, y1 - y0, sum);
+            return;
+        }
+        let at = |k: usize, x: usize| (k * (self.width + 1) + x) * self.channels;
+        let s = &self.row_samples;
+        for (channel, sum) in sum.iter_mut().enumerate() {
+            *sum += s[at(k1, x + 1) + channel] - s[at(k1, x) + channel] - s[at(k0, x + 1) + channel]
+                + s[at(k0, x) + channel];
+        }
+        pixels.add_span(y0 * self.width + x, self.width, k0 * INTEGRAL_STEP - y0, sum);
+        pixels.add_span(k1 * INTEGRAL_STEP * self.width + x, self.width, y1 - k1 * INTEGRAL_STEP, sum);
     }
 
     pub(crate) fn bytes(&self) -> usize {
-        self.values.len() * std::mem::size_of::<f64>()
+        (self.row_samples.len() + self.col_samples.len()) * std::mem::size_of::<f64>()
     }
 }
 
-struct MeanCache {
-    image_id: u64,
+/// Pixel access for the unsampled parts of an integral query.
+enum PixelSource<'a> {
+    Cpu { pixels: &'a [f32], channels: usize },
+    Derived(&'a ImageData),
+}
+
+impl<'a> PixelSource<'a> {
+    fn new(image: &'a ImageData, channels: usize) -> Result<Self> {
+        if image.spec().channels as usize != channels {
+            return Err(eyre!("Integral table does not match the image"));
+        }
+        Ok(match image.pixels() {
+            Some(pixels) => Self::Cpu { pixels, channels },
+            None => Self::Derived(image),
+        })
+    }
+
+    /// Add `count` pixels starting at pixel index `start`, `step` pixels apart, to `sum`.
+    fn add_span(&self, start: usize, step: usize, count: usize, sum: &mut [f64]) {
+        for index in (0..count).map(|i| start + i * step) {
+            match self {
+                Self::Cpu { pixels, channels } => {
+                    let pixel = &pixels[index * channels..(index + 1) * channels];
+                    for (sum, &value) in sum.iter_mut().zip(pixel) {
+                        *sum += value as f64;
+                    }
+                }
+                Self::Derived(image) => {
+                    if let Some((values, channels)) = image.normalized_pixel_at(index) {
+                        for (sum, &value) in sum.iter_mut().zip(&values[..channels]) {
+                            *sum += value as f64;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Integral table state of one image. It lives in the image, so switching between comparison inputs keeps
+/// both tables.
+#[derive(Default)]
+pub(crate) struct MeanCache {
     building: bool,
     integral: Option<IntegralImage>,
 }
 
-impl Default for MeanCache {
-    fn default() -> Self {
-        Self {
-            image_id: u64::MAX,
-            building: false,
-            integral: None,
-        }
+impl MeanCache {
+    pub(crate) fn integral_bytes(&self) -> usize {
+        self.integral.as_ref().map_or(0, IntegralImage::bytes)
+    }
+
+    /// Drop the table. A build in flight still stores its result when it finishes.
+    pub(crate) fn release(&mut self) {
+        self.integral = None;
     }
 }
 
 pub struct MeanProcessor {
-    cache: Arc<Mutex<MeanCache>>,
-    active_image_id: Arc<AtomicU64>,
+    /// Bumped when precompute is disabled so running builds stop and their results are dropped.
+    generation: Arc<AtomicU64>,
     precompute_enabled: AtomicBool,
+    /// Caches that hold a table or have a build in flight, so disabling precompute can release them.
+    caches: Mutex<Vec<Weak<Mutex<MeanCache>>>>,
 }
 
 impl MeanProcessor {
     pub fn new() -> Self {
         Self {
-            cache: Arc::new(Mutex::new(MeanCache::default())),
-            active_image_id: Arc::new(AtomicU64::new(u64::MAX)),
+            generation: Arc::new(AtomicU64::new(0)),
             precompute_enabled: AtomicBool::new(true),
+            caches: Mutex::new(Vec::new()),
         }
     }
 
     pub fn set_precompute_enabled(&self, enabled: bool) {
         let was_enabled = self.precompute_enabled.swap(enabled, Ordering::AcqRel);
         if !enabled && was_enabled {
-            self.active_image_id.store(u64::MAX, Ordering::Release);
-            *self.cache.lock().unwrap() = MeanCache::default();
+            self.generation.fetch_add(1, Ordering::AcqRel);
+            for cache in self.caches.lock().unwrap().drain(..).filter_map(|cache| cache.upgrade()) {
+                *cache.lock().unwrap() = MeanCache::default();
+            }
         }
     }
 
@@ -336,16 +451,8 @@ impl MeanProcessor {
     /// Uses the O(1) summed-area query whenever precompute is ready. The GPU
     /// reduction remains a no-copy fallback during the short build window.
     pub fn compute(&self, image: &ImageData, rect: Recti, dim: MeanDim) -> Result<Vec<f64>> {
-        if rect.validate().empty() {
-            return Ok(Vec::new());
-        }
-        {
-            let cache = self.cache.lock().unwrap();
-            if cache.image_id == image.id() {
-                if let Some(integral) = cache.integral.as_ref() {
-                    return integral.mean(rect, dim);
-                }
-            }
+        if let Some(result) = self.compute_cached(image, rect, dim) {
+            return result;
         }
 
         if self.precompute_enabled() {
@@ -355,59 +462,65 @@ impl MeanProcessor {
         gpu_compute()?.mean(&texture, rect, dim)
     }
 
+    /// Mean from the summed-area table if it is ready for `image`; never falls back to the GPU.
+    pub fn compute_cached(&self, image: &ImageData, rect: Recti, dim: MeanDim) -> Option<Result<Vec<f64>>> {
+        if rect.validate().empty() {
+            return Some(Ok(Vec::new()));
+        }
+        let cache = image.mean_cache().lock().unwrap();
+        cache.integral.as_ref().map(|integral| integral.mean(image, rect, dim))
+    }
+
     pub fn precompute_async(&self, image: &ImageData) {
         if !self.precompute_enabled() {
             return;
         }
-        let image_id = image.id();
+        let cache = Arc::clone(image.mean_cache());
         {
-            let mut cache = self.cache.lock().unwrap();
-            if cache.image_id == image_id && (cache.building || cache.integral.is_some()) {
+            let mut state = cache.lock().unwrap();
+            if state.building || state.integral.is_some() {
                 return;
             }
-            cache.image_id = image_id;
-            cache.building = true;
-            cache.integral = None;
+            state.building = true;
         }
-        self.active_image_id.store(image_id, Ordering::Release);
+        self.register(&cache);
 
         let image = image.clone();
-        let cache = Arc::clone(&self.cache);
-        let active_image_id = Arc::clone(&self.active_image_id);
+        let generation = Arc::clone(&self.generation);
+        let expected_generation = generation.load(Ordering::Acquire);
         std::thread::spawn(move || {
-            let result = IntegralImage::build(&image, &active_image_id);
-            let mut cache = cache.lock().unwrap();
-            if cache.image_id != image_id {
+            let result = IntegralImage::build(&image, &generation, expected_generation);
+            let mut state = cache.lock().unwrap();
+            state.building = false;
+            if generation.load(Ordering::Acquire) != expected_generation {
                 return;
             }
-            cache.building = false;
             match result {
-                Ok(Some(integral)) => cache.integral = Some(integral),
+                Ok(Some(integral)) => state.integral = Some(integral),
                 Ok(None) => {}
                 Err(error) => eprintln!("Mean integral precompute failed: {error}"),
             }
         });
     }
 
+    fn register(&self, cache: &Arc<Mutex<MeanCache>>) {
+        let mut caches = self.caches.lock().unwrap();
+        caches.retain(|cache| cache.strong_count() > 0);
+        caches.push(Arc::downgrade(cache));
+    }
+
     #[cfg(test)]
     fn install_integral(&self, image: &ImageData, integral: IntegralImage) {
-        self.active_image_id.store(image.id(), Ordering::Release);
-        *self.cache.lock().unwrap() = MeanCache {
-            image_id: image.id(),
+        *image.mean_cache().lock().unwrap() = MeanCache {
             building: false,
             integral: Some(integral),
         };
+        self.register(image.mean_cache());
     }
 
     #[cfg(test)]
-    fn clear_integral(&self) {
-        self.active_image_id.store(u64::MAX, Ordering::Release);
-        *self.cache.lock().unwrap() = MeanCache::default();
-    }
-
-    #[cfg(test)]
-    fn cached_integral_bytes(&self) -> usize {
-        self.cache.lock().unwrap().integral.as_ref().map_or(0, IntegralImage::bytes)
+    fn cached_integral_bytes(&self, image: &ImageData) -> usize {
+        image.mean_cache().lock().unwrap().integral_bytes()
     }
 }
 
@@ -417,6 +530,70 @@ impl Default for MeanProcessor {
     }
 }
 
+/// Image id and selection a mean was computed for.
+type MeanKey = (u64, Recti);
+
+/// Selection mean for per-frame readouts. Values come from the summed-area table when it is ready; otherwise the
+/// slower GPU reduction runs on a worker thread and no value is shown until it arrives.
+#[derive(Default)]
+pub struct AsyncMean {
+    /// Image and rect of `value`, recorded once they are tried, even if that failed.
+    key: Option<MeanKey>,
+    value: Vec<f64>,
+    pending: Option<(MeanKey, mpsc::Receiver<Result<Vec<f64>>>)>,
+}
+
+impl AsyncMean {
+    /// Latest mean of `rect` in `image`, empty while it is computed or when it failed. `on_ready` is called from
+    /// the worker thread when a new value is available.
+    pub fn get(&mut self, image: &ImageData, rect: Recti, on_ready: impl FnOnce() + Send + 'static) -> &[f64] {
+        if let Some((pending_key, rx)) = &self.pending {
+            match rx.try_recv() {
+                Ok(result) => {
+                    if self.key == Some(*pending_key) {
+                        match result {
+                            Ok(value) => self.value = value,
+                            Err(error) => eprintln!("Selection mean failed: {error}"),
+                        }
+                    }
+                    self.pending = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => self.pending = None,
+            }
+        }
+
+        let key = (image.id(), rect);
+        if self.key == Some(key) {
+            return &self.value;
+        }
+        self.value.clear();
+        match super::image::MEAN_PROCESSOR.compute_cached(image, rect, MeanDim::All) {
+            Some(result) => {
+                self.key = Some(key);
+                self.pending = None;
+                match result {
+                    Ok(value) => self.value = value,
+                    Err(error) => eprintln!("Selection mean failed: {error}"),
+                }
+            }
+            None if self.pending.is_none() => {
+                let image = image.clone();
+                let (tx, rx) = mpsc::channel();
+                std::thread::spawn(move || {
+                    let _ = tx.send(image.mean_value_in_rect(rect, MeanDim::All));
+                    on_ready();
+                });
+                self.key = Some(key);
+                self.pending = Some((key, rx));
+            }
+            // Only one reduction runs at a time; a newer rect is picked up once the running one finishes.
+            None => {}
+        }
+        &self.value
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -428,14 +605,14 @@ mod tests {
         let image =
             ImageData::from_f32(spec, vec![1.0, 10.0, 2.0, 20.0, 3.0, 30.0, 4.0, 40.0, 5.0, 50.0, 6.0, 60.0]).unwrap();
         let active = AtomicU64::new(image.id());
-        let integral = IntegralImage::build(&image, &active).unwrap().unwrap();
+        let integral = IntegralImage::build(&image, &active, image.id()).unwrap().unwrap();
         let full = Recti::from_min_size(crate::util::math_ext::vec2i(0, 0), crate::util::math_ext::vec2i(3, 2));
-        assert_eq!(integral.mean(full, MeanDim::All).unwrap(), vec![3.5, 35.0]);
+        assert_eq!(integral.mean(&image, full, MeanDim::All).unwrap(), vec![3.5, 35.0]);
         assert_eq!(
-            integral.mean(full, MeanDim::Column).unwrap(),
+            integral.mean(&image, full, MeanDim::Column).unwrap(),
             vec![2.5, 25.0, 3.5, 35.0, 4.5, 45.0]
         );
-        assert_eq!(integral.mean(full, MeanDim::Row).unwrap(), vec![2.0, 20.0, 5.0, 50.0]);
+        assert_eq!(integral.mean(&image, full, MeanDim::Row).unwrap(), vec![2.0, 20.0, 5.0, 50.0]);
     }
 
     #[test]
@@ -449,10 +626,10 @@ mod tests {
             let image =
                 ImageData::from_f32(ImageSpec::new(width, height, channels, PixelType::F32), pixels.clone()).unwrap();
             let active = AtomicU64::new(image.id());
-            let integral = IntegralImage::build(&image, &active).unwrap().unwrap();
+            let integral = IntegralImage::build(&image, &active, image.id()).unwrap().unwrap();
             let full =
                 Recti::from_min_size(crate::util::math_ext::vec2i(0, 0), crate::util::math_ext::vec2i(width, height));
-            let actual = integral.mean(full, MeanDim::All).unwrap();
+            let actual = integral.mean(&image, full, MeanDim::All).unwrap();
             for channel in 0..channels as usize {
                 let expected = pixels
                     .chunks_exact(channels as usize)
@@ -464,18 +641,60 @@ mod tests {
         }
     }
 
+    #[test]
+    fn sampled_integral_matches_direct_sums_across_sample_lines() {
+        for channels in [1, 4] {
+            let (width, height) = (75, 70);
+            let pixels: Vec<f32> = (0..width * height * channels)
+                .map(|index| ((index * 7919) % 1000) as f32 / 999.0)
+                .collect();
+            let image =
+                ImageData::from_f32(ImageSpec::new(width, height, channels, PixelType::F32), pixels.clone()).unwrap();
+            let active = AtomicU64::new(image.id());
+            let integral = IntegralImage::build(&image, &active, image.id()).unwrap().unwrap();
+            let pixel = |x: i32, y: i32, c: usize| pixels[((y * width + x) * channels) as usize + c] as f64;
+
+            for (x, y, w, h) in [(0, 0, 75, 70), (3, 5, 60, 61), (31, 33, 2, 2), (10, 40, 5, 29)] {
+                let rect = Recti::from_min_size(crate::util::math_ext::vec2i(x, y), crate::util::math_ext::vec2i(w, h));
+                let all = integral.mean(&image, rect, MeanDim::All).unwrap();
+                let columns = integral.mean(&image, rect, MeanDim::Column).unwrap();
+                let rows = integral.mean(&image, rect, MeanDim::Row).unwrap();
+                for c in 0..channels as usize {
+                    let total: f64 = (y..y + h)
+                        .flat_map(|j| (x..x + w).map(move |i| (i, j)))
+                        .map(|(i, j)| pixel(i, j, c))
+                        .sum();
+                    assert!((all[c] - total / (w * h) as f64).abs() < 1e-9);
+                    for i in 0..w {
+                        let expected = (y..y + h).map(|j| pixel(x + i, j, c)).sum::<f64>() / h as f64;
+                        assert!((columns[i as usize * channels as usize + c] - expected).abs() < 1e-9);
+                    }
+                    for j in 0..h {
+                        let expected = (x..x + w).map(|i| pixel(i, y + j, c)).sum::<f64>() / w as f64;
+                        assert!((rows[j as usize * channels as usize + c] - expected).abs() < 1e-9);
+                    }
+                }
+            }
+        }
+    }
+
     #[test]
     fn disabling_precompute_discards_the_cached_integral() {
         let image = ImageData::from_f32(ImageSpec::new(2, 2, 1, PixelType::F32), vec![1.0; 4]).unwrap();
-        let active = AtomicU64::new(image.id());
-        let integral = IntegralImage::build(&image, &active).unwrap().unwrap();
+        let other = ImageData::from_f32(ImageSpec::new(2, 2, 1, PixelType::F32), vec![2.0; 4]).unwrap();
         let processor = MeanProcessor::new();
-        processor.install_integral(&image, integral);
-        assert!(processor.cached_integral_bytes() > 0);
+        for image in [&image, &other] {
+            let integral = IntegralImage::build(image, &AtomicU64::new(0), 0).unwrap().unwrap();
+            processor.install_integral(image, integral);
+        }
+        // Each image keeps its own table, so installing the second does not evict the first.
+        assert!(processor.cached_integral_bytes(&image) > 0);
+        assert!(processor.cached_integral_bytes(&other) > 0);
 
         processor.set_precompute_enabled(false);
         assert!(!processor.precompute_enabled());
-        assert_eq!(processor.cached_integral_bytes(), 0);
+        assert_eq!(processor.cached_integral_bytes(&image), 0);
+        assert_eq!(processor.cached_integral_bytes(&other), 0);
     }
 
     #[test]
@@ -490,11 +709,11 @@ mod tests {
         let disabled_processor = MeanProcessor::new();
         disabled_processor.set_precompute_enabled(false);
         disabled_processor.precompute_async(&image);
-        assert_eq!(disabled_processor.cached_integral_bytes(), 0);
+        assert_eq!(disabled_processor.cached_integral_bytes(&image), 0);
 
         let active = AtomicU64::new(image.id());
         let table_started = std::time::Instant::now();
-        let integral = IntegralImage::build(&image, &active).unwrap().unwrap();
+        let integral = IntegralImage::build(&image, &active, image.id()).unwrap().unwrap();
         eprintln!(
             "8K EXR: {}x{}x{}, decode={:.1?}, decoded pixels={:.1} MiB, disabled integral=0 MiB, integral={:.1?}, enabled integral={:.1} MiB",
             image.spec().width,
@@ -0,0 +1,102 @@
+//! Class labels assigned to images for dataset triage, keyed by asset name (the path of files) and exported as a
+//! path to labels table.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use super::{statistics_report::csv_field, StatisticsFormat};
+
+/// Number of labels, one per number key.
+pub const LABEL_COUNT: usize = 9;
+
+/// Labels of every labeled image, as label indices in `0..LABEL_COUNT`.
+#[derive(Default)]
+pub struct ImageLabels {
+    labels: BTreeMap<String, BTreeSet<usize>>,
+}
+
+impl ImageLabels {
+    pub fn labels(&self, name: &str) -> impl Iterator<Item = usize> + '_ {
+        self.labels.get(name).into_iter().flatten().copied()
+    }
+
+    /// Add `label` to the image, or remove it when the image already has it. Returns whether the image has it now.
+    pub fn toggle(&mut self, name: &str, label: usize) -> bool {
+        debug_assert!(label < LABEL_COUNT);
+        let labels = self.labels.entry(name.to_string()).or_default();
+        let added = labels.insert(label);
+        if !added {
+            labels.remove(&label);
+            if labels.is_empty() {
+                self.labels.remove(name);
+            }
+        }
+        added
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Number of images with each label.
+    pub fn counts(&self) -> [usize; LABEL_COUNT] {
+        let mut counts = [0; LABEL_COUNT];
+        for label in self.labels.values().flatten() {
+            counts[*label] += 1;
+        }
+        counts
+    }
+
+    pub fn clear(&mut self) {
+        self.labels.clear();
+    }
+
+    /// The labeled images with their label names from `names`, one row per image.
+    pub fn encode(&self, format: StatisticsFormat, names: &[String; LABEL_COUNT]) -> String {
+        let rows = self
+            .labels
+            .iter()
+            .map(|(path, labels)| (path, labels.iter().map(|&label| names[label].as_str()).collect::<Vec<_>>()));
+        match format {
+            StatisticsFormat::Csv => {
+                let mut csv = String::from("path,labels\n");
+                for (path, labels) in rows {
+                    let _ = writeln!(csv, "{},{}", csv_field(path), csv_field(&labels.join(";")));
+                }
+                csv
+            }
+            StatisticsFormat::Json => {
+                let map: BTreeMap<_, _> = rows.collect();
+                serde_json::to_string_pretty(&map).unwrap_or_default()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggled_labels_are_exported_by_name() {
+        let names = std::array::from_fn(|index| format!("class{}", index + 1));
+        let mut labels = ImageLabels::default();
+        assert!(labels.toggle("b.png", 2));
+        assert!(labels.toggle("a,1.png", 0));
+        assert!(labels.toggle("a,1.png", 4));
+        assert!(labels.toggle("c.png", 8));
+        assert!(!labels.toggle("c.png", 8));
+        assert_eq!(labels.labels("a,1.png").collect::<Vec<_>>(), [0, 4]);
+        assert_eq!(labels.counts()[..3], [1, 0, 1]);
+
+        assert_eq!(
+            labels.encode(StatisticsFormat::Csv, &names),
+            "path,labels\n\"a,1.png\",class1;class5\nb.png,class3\n"
+        );
+        let json: serde_json::Value = serde_json::from_str(&labels.encode(StatisticsFormat::Json, &names)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "a,1.png": ["class1", "class5"], "b.png": ["class3"] })
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Camera intrinsics and OpenCV distortion coefficients for previewing the undistorted image, to check calibration
+//! results against checkerboard captures.
+
+use color_eyre::eyre::{eyre, Result};
+use eframe::egui::{vec2, Vec2};
+use serde::{Deserialize, Serialize};
+
+/// Pinhole intrinsics in pixels and the radial-tangential coefficients of OpenCV's `k1, k2, p1, p2, k3` model.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LensDistortion {
+    pub fx: f32,
+    pub fy: f32,
+    pub cx: f32,
+    pub cy: f32,
+    pub k1: f32,
+    pub k2: f32,
+    pub p1: f32,
+    pub p2: f32,
+    pub k3: f32,
+}
+
+impl LensDistortion {
+    /// No distortion, with the principal point at the center of a `width`×`height` image and a focal length of
+    /// its longer side.
+    pub fn for_image(width: i32, height: i32) -> Self {
+        let focal = width.max(height).max(1) as f32;
+        Self {
+            fx: focal,
+            fy: focal,
+            cx: (width as f32 - 1.0) / 2.0,
+            cy: (height as f32 - 1.0) / 2.0,
+            k1: 0.0,
+            k2: 0.0,
+            p1: 0.0,
+            p2: 0.0,
+            k3: 0.0,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        [self.fx, self.fy].iter().all(|f| f.is_finite() && *f > 0.0)
+    }
+
+    /// Where the undistorted `point` lands in the captured image, both in pixels with pixel centers at integer
+    /// coordinates as in OpenCV. The image shader evaluates the same mapping per output pixel.
+    pub fn distort(&self, point: Vec2) -> Vec2 {
+        let x = (point.x - self.cx) / self.fx;
+        let y = (point.y - self.cy) / self.fy;
+        let r2 = x * x + y * y;
+        let radial = 1.0 + r2 * (self.k1 + r2 * (self.k2 + r2 * self.k3));
+        let xd = x * radial + 2.0 * self.p1 * x * y + self.p2 * (r2 + 2.0 * x * x);
+        let yd = y * radial + self.p1 * (r2 + 2.0 * y * y) + 2.0 * self.p2 * x * y;
+        vec2(xd * self.fx + self.cx, yd * self.fy + self.cy)
+    }
+
+    /// Set the coefficients from OpenCV's `distCoeffs` as text, e.g. `[-0.28, 0.07, 0.0002, 0.00002, 0.0]`. `k3` is
+    /// optional; higher-order terms of the rational model are not supported.
+    pub fn set_coefficients(&mut self, text: &str) -> Result<()> {
+        let values = parse_numbers(text)?;
+        let [k1, k2, p1, p2, k3] = match values[..] {
+            [k1, k2, p1, p2] => [k1, k2, p1, p2, 0.0],
+            [k1, k2, p1, p2, k3] => [k1, k2, p1, p2, k3],
+            _ => {
+                return Err(eyre!(
+                    "Expected 4 or 5 coefficients (k1, k2, p1, p2[, k3]), got {}",
+                    values.len()
+                ))
+            }
+        };
+        (self.k1, self.k2, self.p1, self.p2, self.k3) = (k1, k2, p1, p2, k3);
+        Ok(())
+    }
+
+    /// Set the intrinsics from OpenCV's 3×3 camera matrix as text, in row order.
+    pub fn set_camera_matrix(&mut self, text: &str) -> Result<()> {
+        let values = parse_numbers(text)?;
+        let [fx, _, cx, _, fy, cy, _, _, _] = values[..] else {
+            return Err(eyre!("Expected the 9 values of a 3×3 camera matrix, got {}", values.len()));
+        };
+        (self.fx, self.fy, self.cx, self.cy) = (fx, fy, cx, cy);
+        Ok(())
+    }
+}
+
+/// Numbers in `text`, separated by commas, semicolons, brackets or whitespace as printed by numpy or OpenCV.
+fn parse_numbers(text: &str) -> Result<Vec<f32>> {
+    text.split(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | '[' | ']'))
+        .filter(|token| !token.is_empty())
+        .map(|token| token.parse::<f32>().map_err(|_| eyre!("'{token}' is not a number")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distorts_like_opencv_and_parses_printed_arrays() {
+        let mut lens = LensDistortion::for_image(101, 51);
+        assert_eq!((lens.cx, lens.cy), (50.0, 25.0));
+        assert_eq!(lens.distort(vec2(10.0, 3.0)), vec2(10.0, 3.0));
+
+        lens.set_camera_matrix("[[100, 0, 50],\n [0, 100, 25],\n [0, 0, 1]]").unwrap();
+        lens.set_coefficients("[[-0.5 0 0 0]]").unwrap();
+        // 50 px right of the center is 0.5 in normalized coordinates, pulled in by 1 - 0.5 * 0.25.
+        assert_eq!(lens.distort(vec2(100.0, 25.0)), vec2(93.75, 25.0));
+        assert_eq!(lens.distort(vec2(50.0, 25.0)), vec2(50.0, 25.0));
+
+        lens.set_coefficients("0, 0, 0.01, 0, 0").unwrap();
+        let shifted = lens.distort(vec2(100.0, 25.0));
+        assert!((shifted.y - 25.25).abs() < 1e-4, "{shifted:?}");
+
+        assert!(lens.set_coefficients("1, 2, 3").is_err());
+        assert!(lens.set_camera_matrix("1, x").is_err());
+        assert!(!LensDistortion { fx: 0.0, ..lens }.is_valid());
+    }
+}
@@ -1,20 +1,83 @@
+mod annotation;
 mod app_state;
+mod archive;
 mod asset;
+mod channel_math;
+mod clipboard;
+mod color_lut;
+mod contact_sheet;
+mod content_hash;
+mod decode_limits;
+mod drag_source;
+mod export_metadata;
 mod file_nav;
 mod gpu_compute;
+mod grid_view;
+mod heatmap_overlay;
 mod image;
 mod image_io;
 mod image_processor;
+mod labels;
+mod lens_distortion;
+mod notes;
+mod pixel_probe;
+mod print;
+mod proofing;
+mod ratings;
+mod raw_file;
 mod recti;
+mod sample_export;
+mod segmentation_mask;
+mod selection_mask;
+mod sequence_writer;
 mod socket;
+mod statistics_report;
 mod statistics_worker;
+mod temp_export;
+mod timeline;
+mod vector_overlay;
+mod watch_folders;
+mod white_balance;
+mod yuv;
 
+pub use annotation::*;
 pub use app_state::*;
+pub use archive::*;
 pub use asset::*;
+pub use channel_math::*;
+pub use clipboard::*;
+pub use color_lut::*;
+pub use contact_sheet::*;
+pub use content_hash::*;
+pub use decode_limits::*;
+pub use drag_source::*;
+pub use export_metadata::*;
 pub use file_nav::*;
 pub use gpu_compute::*;
+pub use grid_view::*;
+pub use heatmap_overlay::*;
 pub use image::*;
+pub use image_io::ChannelOrder;
 pub use image_processor::*;
+pub use labels::*;
+pub use lens_distortion::*;
+pub use notes::*;
+pub use pixel_probe::*;
+pub use print::*;
+pub use proofing::*;
+pub use ratings::*;
+pub use raw_file::*;
 pub use recti::*;
+pub use sample_export::*;
+pub use segmentation_mask::*;
+pub use selection_mask::*;
+pub use sequence_writer::*;
 pub use socket::*;
+pub use statistics_report::*;
 pub use statistics_worker::*;
+pub use temp_export::*;
+pub use timeline::*;
+pub use vector_overlay::*;
+pub use watch_folders::*;
+pub use white_balance::*;
+pub use yuv::*;
@@ -0,0 +1,141 @@
+//! Free-text notes on image files, stored as `<file name>.note.txt` sidecars next to the image so inspection findings
+//! travel with the file.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{eyre, Result};
+
+/// Characters of context on each side of a search hit in [`NoteMatch::snippet`].
+const SNIPPET_CONTEXT: usize = 30;
+
+/// Sidecar path of the note on `image`.
+pub fn note_path(image: &Path) -> PathBuf {
+    let mut name = image.file_name().unwrap_or_default().to_os_string();
+    name.push(".note.txt");
+    image.with_file_name(name)
+}
+
+/// Whether notes can be stored for `image`: archive entries have no folder to put a sidecar in.
+pub fn can_have_note(image: &Path) -> bool {
+    image.parent().is_some_and(Path::is_dir)
+}
+
+/// The note on `image`, empty when it has none.
+pub fn read_note(image: &Path) -> Result<String> {
+    let path = note_path(image);
+    match fs::read_to_string(&path) {
+        Ok(note) => Ok(note),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(eyre!("Failed to read note {}: {e}", path.display())),
+    }
+}
+
+/// Store `note` on `image`; an empty note removes the sidecar.
+pub fn write_note(image: &Path, note: &str) -> Result<()> {
+    let path = note_path(image);
+    if note.trim().is_empty() {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(eyre!("Failed to remove note {}: {e}", path.display()))
+            }
+            _ => Ok(()),
+        };
+    }
+    fs::write(&path, note).map_err(|e| eyre!("Failed to write note {}: {e}", path.display()))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NoteMatch {
+    pub image: PathBuf,
+    /// The line around the first hit.
+    pub snippet: String,
+}
+
+/// Images among `images` whose notes contain `query`, ignoring case.
+pub fn search_notes(images: &[PathBuf], query: &str) -> Vec<NoteMatch> {
+    // Lowercased char by char like the note lines below.
+    let query: String = query.trim().chars().flat_map(char::to_lowercase).collect();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    images
+        .iter()
+        .filter_map(|image| {
+            let note = fs::read_to_string(note_path(image)).ok()?;
+            let snippet = note.lines().find_map(|line| {
+                let chars: Vec<char> = line.chars().collect();
+                // Case mappings can change the length, as with `İ`, so keep the original char index of each byte of
+                // the lowercase line and cut the snippet from the original chars.
+                let mut lower = String::new();
+                let mut origin = Vec::new();
+                for (index, c) in chars.iter().enumerate() {
+                    for lowered in c.to_lowercase() {
+                        lower.push(lowered);
+                        origin.extend(std::iter::repeat_n(index, lowered.len_utf8()));
+                    }
+                }
+                let start = lower.find(&query)?;
+                let (hit, end) = (origin[start], origin[start + query.len() - 1] + 1);
+                let from = hit.saturating_sub(SNIPPET_CONTEXT);
+                let to = (end + SNIPPET_CONTEXT).min(chars.len());
+                let mut snippet: String = chars[from..to].iter().collect();
+                if from > 0 {
+                    snippet.insert(0, '\u{2026}');
+                }
+                if to < chars.len() {
+                    snippet.push('\u{2026}');
+                }
+                Some(snippet)
+            })?;
+            Some(NoteMatch {
+                image: image.clone(),
+                snippet,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notes_are_stored_next_to_images_and_searchable() {
+        let dir = std::env::temp_dir().join(format!("edolview-notes-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let images: Vec<PathBuf> = ["a.png", "b.exr", "c.png"].iter().map(|name| dir.join(name)).collect();
+        assert_eq!(note_path(&images[0]), dir.join("a.png.note.txt"));
+        assert!(can_have_note(&images[0]));
+
+        write_note(&images[0], "Banding in the sky\nCheck the gradient").unwrap();
+        write_note(&images[1], "clean").unwrap();
+        assert_eq!(read_note(&images[1]).unwrap(), "clean");
+        assert_eq!(read_note(&images[2]).unwrap(), "");
+
+        let matches = search_notes(&images, "BANDING");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].image, images[0]);
+        assert_eq!(matches[0].snippet, "Banding in the sky");
+        assert!(search_notes(&images, "  ").is_empty());
+
+        write_note(&images[1], "").unwrap();
+        assert!(!note_path(&images[1]).exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn snippets_follow_the_original_line_when_lowercasing_changes_its_length() {
+        let dir = std::env::temp_dir().join(format!("edolview-notes-case-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let image = dir.join("a.png");
+        write_note(&image, &format!("{}x", "\u{130}".repeat(40))).unwrap();
+
+        let matches = search_notes(std::slice::from_ref(&image), "X");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].snippet, format!("\u{2026}{}x", "\u{130}".repeat(30)));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
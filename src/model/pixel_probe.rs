@@ -0,0 +1,96 @@
+//! One pixel read from every listed image of the same size, for following a pixel across iterations of an
+//! algorithm.
+
+use std::fmt::Write;
+
+use indexmap::IndexMap;
+
+use super::{statistics_report::csv_field, Image, SharedAsset};
+use crate::util::math_ext::Vec2i;
+
+pub struct ProbeSample {
+    pub hash: String,
+    pub name: String,
+    /// Channel values in the stored range, formatted like the status bar.
+    pub values: Vec<String>,
+}
+
+/// `pixel` of every asset that is `width`×`height`, in image list order.
+pub fn probe_assets(
+    assets: &IndexMap<String, SharedAsset>,
+    (width, height): (i32, i32),
+    pixel: Vec2i,
+) -> Vec<ProbeSample> {
+    assets
+        .iter()
+        .filter_map(|(hash, asset)| {
+            let image = asset.image();
+            let spec = image.spec();
+            if (spec.width, spec.height) != (width, height) {
+                return None;
+            }
+            let values = image.get_pixel_at(pixel.x, pixel.y).ok()?;
+            Some(ProbeSample {
+                hash: hash.clone(),
+                name: asset.name().to_string(),
+                values: values.iter().map(|&value| spec.pixel_values_to_string(&[value])).collect(),
+            })
+        })
+        .collect()
+}
+
+/// Most channels among `samples`, the number of value columns of the probe table.
+pub fn probe_channels(samples: &[ProbeSample]) -> usize {
+    samples.iter().map(|sample| sample.values.len()).max().unwrap_or(0)
+}
+
+/// The probe table as CSV with one row per image: `asset,x,y,c0,c1,...`.
+pub fn probe_csv(pixel: Vec2i, samples: &[ProbeSample]) -> String {
+    let channels = probe_channels(samples);
+    let mut csv = String::from("asset,x,y");
+    for channel in 0..channels {
+        let _ = write!(csv, ",c{channel}");
+    }
+    csv.push('\n');
+    for sample in samples {
+        let _ = write!(csv, "{},{},{}", csv_field(&sample.name), pixel.x, pixel.y);
+        for channel in 0..channels {
+            csv.push(',');
+            csv.push_str(sample.values.get(channel).map_or("", String::as_str));
+        }
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::model::{FileAsset, ImageData, ImageSpec, PixelType};
+    use crate::util::math_ext::vec2i;
+
+    #[test]
+    fn probes_images_of_the_same_size() {
+        let mut assets: IndexMap<String, SharedAsset> = IndexMap::new();
+        let mut add = |name: &str, width, channels, dtype, data: Vec<f32>| {
+            let image = ImageData::from_f32(ImageSpec::new(width, 1, channels, dtype), data).unwrap();
+            let asset: SharedAsset = Arc::new(FileAsset::new(name.to_string(), name.to_string(), image));
+            assets.insert(name.to_string(), asset);
+        };
+        add("iter1.exr", 2, 1, PixelType::F32, vec![0.25, 0.5]);
+        add("iter2,final.png", 2, 3, PixelType::U8, vec![0.0, 0.0, 0.0, 1.0, 0.5, 0.0]);
+        add("other_size.png", 3, 1, PixelType::U8, vec![0.0; 3]);
+
+        let samples = probe_assets(&assets, (2, 1), vec2i(1, 0));
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].values, ["0.5000"]);
+        assert_eq!(samples[1].values, ["255", "128", "0"]);
+        assert_eq!(
+            probe_csv(vec2i(1, 0), &samples),
+            "asset,x,y,c0,c1,c2\niter1.exr,1,0,0.5000,,\n\"iter2,final.png\",1,0,255,128,0\n"
+        );
+        assert!(probe_assets(&assets, (2, 1), vec2i(2, 0)).is_empty());
+    }
+}
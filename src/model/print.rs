@@ -0,0 +1,238 @@
+//! Hardcopies of the rendered view. The image is placed on a single-page PDF, which every platform's print
+//! spooler accepts, and the PDF is handed to the system printer.
+
+use std::{io::Write, path::Path};
+
+use color_eyre::eyre::{eyre, Result};
+use flate2::{write::ZlibEncoder, Compression};
+
+/// PDF user space units per inch.
+const POINTS_PER_INCH: f32 = 72.0;
+/// Pixels per inch at 100% scale, the CSS and Windows reference density.
+const PIXELS_PER_INCH: f32 = 96.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaperSize {
+    A4,
+    Letter,
+}
+
+impl PaperSize {
+    pub const ALL: [Self; 2] = [Self::A4, Self::Letter];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::A4 => "A4",
+            Self::Letter => "Letter",
+        }
+    }
+
+    /// Portrait width and height in points.
+    fn points(self) -> (f32, f32) {
+        match self {
+            Self::A4 => (595.28, 841.89),
+            Self::Letter => (612.0, 792.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PrintLayout {
+    pub paper: PaperSize,
+    pub landscape: bool,
+    /// Scale the image to fill the printable area; otherwise print at 96 pixels per inch.
+    pub fit_to_page: bool,
+    pub margin_mm: f32,
+}
+
+impl Default for PrintLayout {
+    fn default() -> Self {
+        Self {
+            paper: PaperSize::A4,
+            landscape: false,
+            fit_to_page: true,
+            margin_mm: 10.0,
+        }
+    }
+}
+
+impl PrintLayout {
+    /// Page width and height in points.
+    pub fn page_size(&self) -> (f32, f32) {
+        let (w, h) = self.paper.points();
+        if self.landscape {
+            (h, w)
+        } else {
+            (w, h)
+        }
+    }
+
+    pub fn margin_points(&self) -> f32 {
+        self.margin_mm.max(0.0) / 25.4 * POINTS_PER_INCH
+    }
+
+    /// Where an image of `width`×`height` pixels lands on the page, as `(x, y, w, h)` in points from the top-left
+    /// corner. The image is centered in the printable area; at 100% scale it may overflow and be clipped.
+    pub fn placement(&self, width: u32, height: u32) -> (f32, f32, f32, f32) {
+        let (page_w, page_h) = self.page_size();
+        let margin = self.margin_points().min(page_w / 2.0).min(page_h / 2.0);
+        let (area_w, area_h) = (page_w - 2.0 * margin, page_h - 2.0 * margin);
+        let (width, height) = (width.max(1) as f32, height.max(1) as f32);
+        let scale = if self.fit_to_page {
+            (area_w / width).min(area_h / height)
+        } else {
+            POINTS_PER_INCH / PIXELS_PER_INCH
+        };
+        let (w, h) = (width * scale, height * scale);
+        (margin + (area_w - w) / 2.0, margin + (area_h - h) / 2.0, w, h)
+    }
+}
+
+/// Write top-down RGBA8 pixels as a one-page PDF laid out by `layout`. Alpha is composited over white paper.
+pub fn write_print_pdf(path: &Path, width: u32, height: u32, rgba: &[u8], layout: &PrintLayout) -> Result<()> {
+    if width == 0 || height == 0 || rgba.len() != width as usize * height as usize * 4 {
+        return Err(eyre!("Invalid print image {width}x{height} with {} bytes", rgba.len()));
+    }
+    std::fs::write(path, print_pdf(width, height, rgba, layout)?)?;
+    Ok(())
+}
+
+fn print_pdf(width: u32, height: u32, rgba: &[u8], layout: &PrintLayout) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    for pixel in rgba.chunks_exact(4) {
+        let alpha = pixel[3] as u32;
+        let rgb = [0, 1, 2].map(|c| ((pixel[c] as u32 * alpha + 255 * (255 - alpha) + 127) / 255) as u8);
+        encoder.write_all(&rgb)?;
+    }
+    let image = encoder.finish()?;
+
+    let (page_w, page_h) = layout.page_size();
+    let (x, y, w, h) = layout.placement(width, height);
+    // PDF space grows upwards from the bottom-left corner.
+    let content = format!("q {w:.3} 0 0 {h:.3} {x:.3} {:.3} cm /Im0 Do Q\n", page_h - y - h);
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    let mut object = |pdf: &mut Vec<u8>, dict: String, stream: Option<&[u8]>| {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{dict}\n", offsets.len()).as_bytes());
+        if let Some(stream) = stream {
+            pdf.extend_from_slice(b"stream\n");
+            pdf.extend_from_slice(stream);
+            pdf.extend_from_slice(b"\nendstream\n");
+        }
+        pdf.extend_from_slice(b"endobj\n");
+    };
+    object(&mut pdf, "<< /Type /Catalog /Pages 2 0 R >>".into(), None);
+    object(&mut pdf, "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".into(), None);
+    object(
+        &mut pdf,
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {page_w:.2} {page_h:.2}] \
+             /Resources << /XObject << /Im0 4 0 R >> >> /Contents 5 0 R >>"
+        ),
+        None,
+    );
+    object(
+        &mut pdf,
+        format!(
+            "<< /Type /XObject /Subtype /Image /Width {width} /Height {height} /ColorSpace /DeviceRGB \
+             /BitsPerComponent 8 /Filter /FlateDecode /Length {} >>",
+            image.len()
+        ),
+        Some(&image),
+    );
+    object(&mut pdf, format!("<< /Length {} >>", content.len()), Some(content.as_bytes()));
+
+    let xref = pdf.len();
+    pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1).as_bytes());
+    for offset in &offsets {
+        pdf.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+    }
+    pdf.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            offsets.len() + 1
+        )
+        .as_bytes(),
+    );
+    Ok(pdf)
+}
+
+/// Send a PDF to the default printer: through CUPS `lp` on Linux and macOS, and through the shell's print verb
+/// for PDFs on Windows, which uses the registered PDF reader.
+pub fn send_to_printer(path: &Path) -> Result<()> {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        use windows_sys::Win32::UI::Shell::ShellExecuteW;
+
+        let wide = |s: &std::ffi::OsStr| s.encode_wide().chain(std::iter::once(0)).collect::<Vec<u16>>();
+        let verb = wide("print".as_ref());
+        let file = wide(path.as_os_str());
+        // SAFETY: both strings are NUL-terminated and outlive the call.
+        let result = unsafe {
+            ShellExecuteW(
+                std::ptr::null_mut(),
+                verb.as_ptr(),
+                file.as_ptr(),
+                std::ptr::null(),
+                std::ptr::null(),
+                0,
+            )
+        };
+        // Values up to 32 are error codes.
+        if result as isize <= 32 {
+            return Err(eyre!(
+                "No application is registered to print PDF files (error {})",
+                result as isize
+            ));
+        }
+        Ok(())
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let output = std::process::Command::new("lp")
+            .arg("-t")
+            .arg("edolview")
+            .arg(path)
+            .output()
+            .map_err(|e| eyre!("Failed to run lp: {e}"))?;
+        if !output.status.success() {
+            return Err(eyre!("lp failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn places_the_image_and_writes_a_one_page_pdf() {
+        let layout = PrintLayout {
+            paper: PaperSize::Letter,
+            landscape: true,
+            fit_to_page: true,
+            margin_mm: 25.4,
+        };
+        assert_eq!(layout.page_size(), (792.0, 612.0));
+        // A 2:1 image is limited by the 648pt printable width and centered vertically.
+        assert_eq!(layout.placement(200, 100), (72.0, 144.0, 648.0, 324.0));
+        let actual_size = PrintLayout {
+            fit_to_page: false,
+            ..layout
+        };
+        assert_eq!(actual_size.placement(96, 96).2, 72.0);
+
+        let pdf = print_pdf(2, 1, &[255, 0, 0, 255, 0, 0, 0, 0], &layout).unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.contains("/MediaBox [0 0 792.00 612.00]"));
+        assert!(text.contains("q 648.000 0 0 324.000 72.000 144.000 cm /Im0 Do Q"));
+        let xref = text.rfind("startxref\n").unwrap() + "startxref\n".len();
+        let xref: usize = text[xref..].lines().next().unwrap().parse().unwrap();
+        assert!(pdf[xref..].starts_with(b"xref\n0 6\n"));
+    }
+}
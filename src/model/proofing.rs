@@ -0,0 +1,99 @@
+//! Soft proofing of viewing conditions: a dimmer display and a warmer or cooler white point, applied to the shown
+//! colors only, so content can be checked for other viewing environments without changing exports.
+
+/// White point that leaves the colors unchanged, the correlated color temperature of D65.
+pub const NEUTRAL_WHITE_POINT: f32 = 6504.0;
+pub const WHITE_POINT_RANGE: std::ops::RangeInclusive<f32> = 2500.0..=10000.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Proofing {
+    pub enabled: bool,
+    /// Factor on the linear light of the display, 1 for full brightness.
+    pub brightness: f32,
+    /// Color temperature of the simulated white, in kelvin.
+    pub white_point: f32,
+}
+
+impl Default for Proofing {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            brightness: 1.0,
+            white_point: NEUTRAL_WHITE_POINT,
+        }
+    }
+}
+
+impl Proofing {
+    /// Linear RGB gains of the simulated display, or `None` while proofing is off.
+    pub fn gains(&self) -> Option<[f32; 3]> {
+        if !self.enabled {
+            return None;
+        }
+        let white = white_point_gains(self.white_point);
+        Some(white.map(|gain| gain * self.brightness.clamp(0.0, 1.0)))
+    }
+
+    /// Short description of the simulated conditions, for the badge over the image.
+    pub fn label(&self) -> String {
+        format!("Proofing · {:.0}% · {:.0} K", self.brightness * 100.0, self.white_point)
+    }
+}
+
+/// Linear sRGB gains that turn D65 white into the white of a black body at `kelvin`, scaled so the strongest
+/// channel keeps its level.
+pub fn white_point_gains(kelvin: f32) -> [f32; 3] {
+    let target = planckian_rgb(kelvin.clamp(*WHITE_POINT_RANGE.start(), *WHITE_POINT_RANGE.end()));
+    let neutral = planckian_rgb(NEUTRAL_WHITE_POINT);
+    let gains: [f64; 3] = std::array::from_fn(|i| target[i] / neutral[i]);
+    let max = gains.iter().copied().fold(f64::MIN, f64::max);
+    gains.map(|gain| (gain / max) as f32)
+}
+
+/// Linear sRGB of the Planckian locus at `kelvin` with unit luminance, from the cubic fit of Kim et al. (2002).
+fn planckian_rgb(kelvin: f32) -> [f64; 3] {
+    let t = kelvin as f64;
+    let x = if t <= 4000.0 {
+        -0.2661239e9 / t.powi(3) - 0.2343589e6 / t.powi(2) + 0.8776956e3 / t + 0.179910
+    } else {
+        -3.0258469e9 / t.powi(3) + 2.1070379e6 / t.powi(2) + 0.2226347e3 / t + 0.240390
+    };
+    let y = if t <= 2222.0 {
+        -1.1063814 * x.powi(3) - 1.34811020 * x.powi(2) + 2.18555832 * x - 0.20219683
+    } else if t <= 4000.0 {
+        -0.9549476 * x.powi(3) - 1.37418593 * x.powi(2) + 2.09137015 * x - 0.16748867
+    } else {
+        3.0817580 * x.powi(3) - 5.87338670 * x.powi(2) + 3.75112997 * x - 0.37001483
+    };
+    let (cx, cz) = (x / y, (1.0 - x - y) / y);
+    [
+        3.2404542 * cx - 1.5371385 - 0.4985314 * cz,
+        -0.9692660 * cx + 1.8760108 + 0.0415560 * cz,
+        0.0556434 * cx - 0.2040259 + 1.0572252 * cz,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warmer_white_points_lower_blue_and_brightness_scales_all() {
+        for gain in white_point_gains(NEUTRAL_WHITE_POINT) {
+            assert!((gain - 1.0).abs() < 1e-6);
+        }
+        let [r, g, b] = white_point_gains(3000.0);
+        assert!(r == 1.0 && g < r && b < g, "{r} {g} {b}");
+        let [r, g, b] = white_point_gains(9000.0);
+        assert!(b == 1.0 && r < g && g < b, "{r} {g} {b}");
+
+        let mut proofing = Proofing::default();
+        assert_eq!(proofing.gains(), None);
+        proofing.enabled = true;
+        proofing.brightness = 0.5;
+        for gain in proofing.gains().unwrap() {
+            assert!((gain - 0.5).abs() < 1e-6);
+        }
+        assert_eq!(proofing.label(), "Proofing · 50% · 6504 K");
+    }
+}
@@ -0,0 +1,182 @@
+//! Star ratings and pick/reject flags of image files for culling, kept in `ratings.json` next to the settings so
+//! they survive restarts.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+pub const MAX_STARS: u8 = 5;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Flag {
+    #[default]
+    None,
+    Pick,
+    Reject,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rating {
+    /// `0..=MAX_STARS`, 0 is unrated.
+    pub stars: u8,
+    pub flag: Flag,
+}
+
+impl Rating {
+    /// Stars as `★★★☆☆`, followed by the flag when there is one.
+    pub fn label(self) -> String {
+        let mut label: String = (0..MAX_STARS)
+            .map(|star| if star < self.stars { '\u{2605}' } else { '\u{2606}' })
+            .collect();
+        match self.flag {
+            Flag::None => {}
+            Flag::Pick => label.push_str(" Pick"),
+            Flag::Reject => label.push_str(" Reject"),
+        }
+        label
+    }
+}
+
+/// Which files the previous/next file navigation visits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RatingFilter {
+    #[default]
+    All,
+    Picked,
+    NotRejected,
+    /// At least this many stars.
+    MinStars(u8),
+}
+
+impl RatingFilter {
+    pub const ALL: [Self; 8] = [
+        Self::All,
+        Self::Picked,
+        Self::NotRejected,
+        Self::MinStars(1),
+        Self::MinStars(2),
+        Self::MinStars(3),
+        Self::MinStars(4),
+        Self::MinStars(5),
+    ];
+
+    pub fn label(self) -> String {
+        match self {
+            Self::All => "All images".to_string(),
+            Self::Picked => "Picked".to_string(),
+            Self::NotRejected => "Not rejected".to_string(),
+            Self::MinStars(stars) => format!("{stars}\u{2605} and up"),
+        }
+    }
+
+    pub fn accepts(self, rating: Rating) -> bool {
+        match self {
+            Self::All => true,
+            Self::Picked => rating.flag == Flag::Pick,
+            Self::NotRejected => rating.flag != Flag::Reject,
+            Self::MinStars(stars) => rating.stars >= stars,
+        }
+    }
+}
+
+/// Ratings of every rated file, by canonical path. Unrated files have no entry.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Ratings {
+    ratings: BTreeMap<PathBuf, Rating>,
+}
+
+impl Ratings {
+    pub fn get(&self, path: &Path) -> Rating {
+        self.ratings.get(&rating_key(path)).copied().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, path: &Path, rating: Rating) {
+        let key = rating_key(path);
+        if rating == Rating::default() {
+            self.ratings.remove(&key);
+        } else {
+            self.ratings.insert(key, rating);
+        }
+    }
+
+    /// Ratings saved by [`Ratings::save`], or none when nothing was saved yet.
+    pub fn load() -> Result<Self> {
+        Self::load_from(&ratings_path())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&ratings_path())
+    }
+
+    fn load_from(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(body) => serde_json::from_str(&body).map_err(|e| eyre!("Invalid ratings file {}: {e}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(eyre!("Failed to read ratings file {}: {e}", path.display())),
+        }
+    }
+
+    fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .map_err(|e| eyre!("Failed to write ratings file {}: {e}", path.display()))
+    }
+}
+
+/// Paths are canonicalized so a file opened through a relative path and the same file reached by navigation share a
+/// rating. Archive entries, which do not exist on disk, are kept as given.
+fn rating_key(path: &Path) -> PathBuf {
+    dunce::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn ratings_path() -> PathBuf {
+    crate::util::path_ext::app_config_dir().join("ratings.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ratings_round_trip_and_filter() {
+        let dir = std::env::temp_dir().join(format!("edolview-ratings-{}", std::process::id()));
+        let picked = Rating {
+            stars: 4,
+            flag: Flag::Pick,
+        };
+        let rejected = Rating {
+            stars: 0,
+            flag: Flag::Reject,
+        };
+        let mut ratings = Ratings::default();
+        ratings.set(Path::new("/images/a.png"), picked);
+        ratings.set(Path::new("/images/b.png"), rejected);
+        ratings.set(Path::new("/images/c.png"), picked);
+        ratings.set(Path::new("/images/c.png"), Rating::default());
+
+        let path = dir.join("ratings.json");
+        ratings.save_to(&path).unwrap();
+        let loaded = Ratings::load_from(&path).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(loaded.get(Path::new("/images/a.png")), picked);
+        assert_eq!(loaded.get(Path::new("/images/c.png")), Rating::default());
+        assert_eq!(loaded.ratings.len(), 2);
+        assert_eq!(picked.label(), "\u{2605}\u{2605}\u{2605}\u{2605}\u{2606} Pick");
+
+        assert!(RatingFilter::Picked.accepts(picked));
+        assert!(!RatingFilter::Picked.accepts(Rating::default()));
+        assert!(!RatingFilter::NotRejected.accepts(rejected));
+        assert!(RatingFilter::MinStars(4).accepts(picked));
+        assert!(!RatingFilter::MinStars(5).accepts(picked));
+        assert!(Ratings::load_from(&dir.join("missing.json")).unwrap().ratings.is_empty());
+    }
+}
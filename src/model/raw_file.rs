@@ -0,0 +1,209 @@
+//! Headerless binary dumps (`.raw`, `.bin`) as written by scientific code and sensor tools. The layout is not
+//! stored in the file, so the user describes it once per file and the choice is kept for the session.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex},
+};
+
+use color_eyre::eyre::{eyre, Result};
+
+use super::PixelType;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RawLayout {
+    pub width: u32,
+    pub height: u32,
+    pub channels: u32,
+    pub pixel_type: PixelType,
+    pub big_endian: bool,
+    /// Bytes skipped before the first sample.
+    pub header_bytes: u64,
+}
+
+impl Default for RawLayout {
+    fn default() -> Self {
+        Self {
+            width: 512,
+            height: 512,
+            channels: 1,
+            pixel_type: PixelType::U8,
+            big_endian: false,
+            header_bytes: 0,
+        }
+    }
+}
+
+impl RawLayout {
+    /// Bytes of sample data after the header.
+    pub fn data_bytes(&self) -> Option<u64> {
+        (self.width as u64)
+            .checked_mul(self.height as u64)?
+            .checked_mul(self.channels as u64)?
+            .checked_mul(self.pixel_type.bytes() as u64)
+    }
+
+    /// The sample bytes of `file`, or an error naming how much is missing.
+    pub fn data<'a>(&self, file: &'a [u8]) -> Result<&'a [u8]> {
+        if self.width == 0 || self.height == 0 || !(1..=4).contains(&self.channels) {
+            return Err(eyre!("invalid raw layout {}x{}x{}", self.width, self.height, self.channels));
+        }
+        let len = self.data_bytes().ok_or_else(|| eyre!("raw layout is too large"))?;
+        let end = self
+            .header_bytes
+            .checked_add(len)
+            .ok_or_else(|| eyre!("raw layout is too large"))?;
+        if end > file.len() as u64 {
+            return Err(eyre!(
+                "{}x{}x{} {} after a {}-byte header needs {end} bytes, file has {}",
+                self.width,
+                self.height,
+                self.channels,
+                self.pixel_type.name(),
+                self.header_bytes,
+                file.len()
+            ));
+        }
+        Ok(&file[self.header_bytes as usize..end as usize])
+    }
+}
+
+/// Returned by loaders when a raw file has no layout yet; the viewer asks for one and retries.
+#[derive(Debug)]
+pub struct RawLayoutRequired(pub PathBuf);
+
+impl fmt::Display for RawLayoutRequired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "layout of raw file {} is unknown", self.0.display())
+    }
+}
+
+impl std::error::Error for RawLayoutRequired {}
+
+static FILE_LAYOUTS: LazyLock<Mutex<HashMap<PathBuf, RawLayout>>> = LazyLock::new(Default::default);
+
+pub fn set_raw_file_layout(path: &Path, layout: RawLayout) {
+    FILE_LAYOUTS.lock().unwrap().insert(path.to_path_buf(), layout);
+}
+
+pub fn raw_file_layout(path: &Path) -> Option<RawLayout> {
+    FILE_LAYOUTS.lock().unwrap().get(path).copied()
+}
+
+/// Reverse the bytes of every `sample_bytes`-wide sample in place when `big_endian` differs from this machine.
+pub(crate) fn to_native_endian(bytes: &mut [u8], sample_bytes: usize, big_endian: bool) {
+    if sample_bytes > 1 && big_endian != cfg!(target_endian = "big") {
+        bytes.chunks_exact_mut(sample_bytes).for_each(<[u8]>::reverse);
+    }
+}
+
+/// Sample `layout` from `file` on a grid of at most `max_side` pixels per side and map it to 8-bit RGBA for a
+/// quick look. Integer types are scaled by their range; floats by the sampled minimum and maximum.
+pub fn raw_preview(file: &[u8], layout: &RawLayout, max_side: u32) -> Result<(u32, u32, Vec<u8>)> {
+    let data = layout.data(file)?;
+    let step = layout.width.max(layout.height).div_ceil(max_side.max(1)).max(1);
+    let (preview_w, preview_h) = (layout.width.div_ceil(step), layout.height.div_ceil(step));
+    let channels = layout.channels as usize;
+    let sample_bytes = layout.pixel_type.bytes();
+
+    let mut values = Vec::with_capacity(preview_w as usize * preview_h as usize * channels);
+    for y in (0..layout.height).step_by(step as usize) {
+        for x in (0..layout.width).step_by(step as usize) {
+            let pixel = (y as usize * layout.width as usize + x as usize) * channels;
+            for c in 0..channels {
+                let start = (pixel + c) * sample_bytes;
+                let mut sample = [0_u8; 8];
+                sample[..sample_bytes].copy_from_slice(&data[start..start + sample_bytes]);
+                to_native_endian(&mut sample[..sample_bytes], sample_bytes, layout.big_endian);
+                values.push(sample_value(&sample[..sample_bytes], layout.pixel_type));
+            }
+        }
+    }
+
+    let (low, high) = if layout.pixel_type.is_floating() {
+        let finite = values.iter().copied().filter(|v| v.is_finite());
+        let low = finite.clone().fold(f64::INFINITY, f64::min);
+        let high = finite.fold(f64::NEG_INFINITY, f64::max);
+        if low < high {
+            (low, high)
+        } else {
+            (0.0, 1.0)
+        }
+    } else {
+        let high = layout.pixel_type.alpha();
+        let low = if matches!(layout.pixel_type, PixelType::I8 | PixelType::I16 | PixelType::I32) {
+            -high
+        } else {
+            0.0
+        };
+        (low, high)
+    };
+    let to_u8 = |v: f64| (((v - low) / (high - low)).clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    let mut rgba = Vec::with_capacity(preview_w as usize * preview_h as usize * 4);
+    for pixel in values.chunks_exact(channels) {
+        let [r, g, b, a] = match *pixel {
+            [l] => [l, l, l, high],
+            [l, a] => [l, l, l, a],
+            [r, g, b] => [r, g, b, high],
+            [r, g, b, a] => [r, g, b, a],
+            _ => unreachable!("raw layouts have 1 to 4 channels"),
+        };
+        rgba.extend_from_slice(&[to_u8(r), to_u8(g), to_u8(b), to_u8(a)]);
+    }
+    Ok((preview_w, preview_h, rgba))
+}
+
+fn sample_value(bytes: &[u8], pixel_type: PixelType) -> f64 {
+    match pixel_type {
+        PixelType::U8 => bytes[0] as f64,
+        PixelType::I8 => bytes[0] as i8 as f64,
+        PixelType::U16 => u16::from_ne_bytes([bytes[0], bytes[1]]) as f64,
+        PixelType::I16 => i16::from_ne_bytes([bytes[0], bytes[1]]) as f64,
+        PixelType::F16 => half::f16::from_ne_bytes([bytes[0], bytes[1]]).to_f64(),
+        PixelType::I32 => i32::from_ne_bytes(bytes.try_into().expect("4-byte sample")) as f64,
+        PixelType::F32 => f32::from_ne_bytes(bytes.try_into().expect("4-byte sample")) as f64,
+        PixelType::F64 => f64::from_ne_bytes(bytes.try_into().expect("8-byte sample")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Image, ImageData};
+
+    #[test]
+    fn reads_big_endian_samples_after_a_header() {
+        let mut file = b"HEAD".to_vec();
+        for value in [0_u16, 0x8000, 0xffff, 0x0100] {
+            file.extend_from_slice(&value.to_be_bytes());
+        }
+        let layout = RawLayout {
+            width: 2,
+            height: 2,
+            channels: 1,
+            pixel_type: PixelType::U16,
+            big_endian: true,
+            header_bytes: 4,
+        };
+
+        let image = ImageData::from_raw_file(&file, &layout).unwrap();
+        assert_eq!(image.get_pixel_at(1, 1).unwrap()[0], 256.0 / 65535.0);
+        assert_eq!(image.get_pixel_at(0, 1).unwrap()[0], 1.0);
+
+        let (w, h, rgba) = raw_preview(&file, &layout, 1).unwrap();
+        assert_eq!((w, h), (1, 1));
+        assert_eq!(rgba, [0, 0, 0, 255]);
+        let (_, _, rgba) = raw_preview(&file, &layout, 2).unwrap();
+        assert_eq!(rgba[4..8], [128, 128, 128, 255]);
+
+        let too_long = RawLayout {
+            header_bytes: 5,
+            ..layout
+        };
+        assert!(ImageData::from_raw_file(&file, &too_long).is_err());
+        assert!(raw_preview(&file, &too_long, 8).is_err());
+    }
+}
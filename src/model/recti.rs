@@ -132,6 +132,44 @@ impl Recti {
         self.min.x >= self.max.x || self.min.y >= self.max.y
     }
 
+    /// Grows the rect by `amount` on every side. Negative amounts shrink it, collapsing an axis to its center
+    /// once it runs out of room.
+    #[must_use]
+    pub fn expand(self, amount: i32) -> Self {
+        let rect = self.validate();
+        let axis = |lo: i32, hi: i32| {
+            if hi - lo + 2 * amount >= 0 {
+                (lo - amount, hi + amount)
+            } else {
+                let center = lo + (hi - lo) / 2;
+                (center, center)
+            }
+        };
+        let (x0, x1) = axis(rect.min.x, rect.max.x);
+        let (y0, y1) = axis(rect.min.y, rect.max.y);
+        Self::from_min_max(vec2i(x0, y0), vec2i(x1, y1))
+    }
+
+    /// Keeps the top-left corner and rounds width and height up to a multiple of `multiple`.
+    #[must_use]
+    pub fn snap_size_to_multiple(self, multiple: i32) -> Self {
+        let rect = self.validate();
+        let round_up = |v: i32| v + (multiple - v.rem_euclid(multiple)) % multiple;
+        Self::from_min_size(rect.min, vec2i(round_up(rect.width()), round_up(rect.height())))
+    }
+
+    /// Expands the rect outward so every edge lies on a `multiple`-pixel grid line.
+    #[must_use]
+    pub fn snap_to_grid(self, multiple: i32) -> Self {
+        let rect = self.validate();
+        let floor = |v: i32| v.div_euclid(multiple) * multiple;
+        let ceil = |v: i32| floor(v + multiple - 1);
+        Self::from_min_max(
+            vec2i(floor(rect.min.x), floor(rect.min.y)),
+            vec2i(ceil(rect.max.x), ceil(rect.max.y)),
+        )
+    }
+
     pub fn aspect_ratio(&self) -> f32 {
         self.width() as f32 / self.height() as f32
     }
@@ -234,4 +272,13 @@ mod tests {
         let rect: Recti = "10 * 2, 20 / 2, 1920 / 2, (3 + 1) * 5".parse().unwrap();
         assert_eq!(rect.to_string(), "20, 10, 960, 20");
     }
+
+    #[test]
+    fn expands_snaps_and_collapses() {
+        let rect: Recti = "3, 5, 7, 2".parse().unwrap();
+        assert_eq!(rect.expand(2).to_string(), "1, 3, 11, 6");
+        assert_eq!(rect.expand(-2).to_string(), "5, 6, 3, 0");
+        assert_eq!(rect.snap_size_to_multiple(2).to_string(), "3, 5, 8, 2");
+        assert_eq!(rect.snap_to_grid(8).to_string(), "0, 0, 16, 8");
+    }
 }
@@ -0,0 +1,438 @@
+//! Stored samples of an image region written without the display mapping, for saves and copies that have to
+//! keep the data rather than what is on screen.
+
+use std::io::Cursor;
+
+use color_eyre::eyre::{eyre, Result};
+use exr::prelude::{f16, AnyChannel, AnyChannels, FlatSamples, SmallVec, WritableImage};
+use tiff::encoder::{colortype, TiffEncoder};
+
+use crate::{
+    model::{encode_png16, Image, ImageData, PixelType, Recti},
+    settings::ExportData,
+};
+
+/// Encode the samples of `rect` in `image` as `data` asks, in the format of [`ExportData::sample_extension`].
+pub fn encode_samples(image: &ImageData, rect: Recti, data: ExportData) -> Result<Vec<u8>> {
+    let spec = image.spec();
+    match data.sample_extension(spec.dtype) {
+        None => Err(eyre!("Display exports are rendered, not encoded from samples")),
+        Some("png") if spec.dtype == PixelType::U16 => encode_png16(image, rect),
+        Some("png") => encode_png8(image, rect),
+        Some(_) => encode_exr(image, rect, data == ExportData::Original && spec.dtype == PixelType::F16),
+    }
+}
+
+/// File format of [`encode_save_as`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SaveFormat {
+    #[default]
+    Png,
+    Jpeg,
+    Tiff,
+    Exr,
+    Pfm,
+}
+
+impl SaveFormat {
+    pub const ALL: [Self; 5] = [Self::Png, Self::Jpeg, Self::Tiff, Self::Exr, Self::Pfm];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Png => "PNG",
+            Self::Jpeg => "JPEG",
+            Self::Tiff => "TIFF",
+            Self::Exr => "OpenEXR",
+            Self::Pfm => "PFM",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Tiff => "tiff",
+            Self::Exr => "exr",
+            Self::Pfm => "pfm",
+        }
+    }
+
+    /// Sample depths the format can store, smallest first.
+    pub fn depths(self) -> &'static [SampleDepth] {
+        match self {
+            Self::Png => &[SampleDepth::U8, SampleDepth::U16],
+            Self::Jpeg => &[SampleDepth::U8],
+            Self::Tiff => &[SampleDepth::U8, SampleDepth::U16, SampleDepth::F32],
+            Self::Exr => &[SampleDepth::F16, SampleDepth::F32],
+            Self::Pfm => &[SampleDepth::F32],
+        }
+    }
+
+    /// `requested` when the format stores it, else the smallest depth of the same kind that holds it, else the
+    /// smallest that holds it, else the largest.
+    pub fn resolve_depth(self, requested: SampleDepth) -> SampleDepth {
+        let depths = self.depths();
+        let holds = |depth: &&SampleDepth| depth.bits() >= requested.bits();
+        depths
+            .iter()
+            .find(|depth| **depth == requested)
+            .or_else(|| {
+                depths
+                    .iter()
+                    .filter(holds)
+                    .find(|depth| depth.is_float() == requested.is_float())
+            })
+            .or_else(|| depths.iter().find(holds))
+            .or(depths.last())
+            .copied()
+            .unwrap_or(requested)
+    }
+}
+
+/// Sample type written by [`encode_save_as`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleDepth {
+    U8,
+    U16,
+    F16,
+    F32,
+}
+
+impl SampleDepth {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::U8 => "8-bit",
+            Self::U16 => "16-bit",
+            Self::F16 => "16-bit float",
+            Self::F32 => "32-bit float",
+        }
+    }
+
+    /// The depth that holds `dtype` samples, or the closest one.
+    pub fn of(dtype: PixelType) -> Self {
+        match dtype {
+            PixelType::U8 | PixelType::I8 => Self::U8,
+            PixelType::U16 | PixelType::I16 => Self::U16,
+            PixelType::F16 => Self::F16,
+            PixelType::I32 | PixelType::F32 | PixelType::F64 => Self::F32,
+        }
+    }
+
+    fn bits(self) -> u32 {
+        match self {
+            Self::U8 => 8,
+            Self::U16 | Self::F16 => 16,
+            Self::F32 => 32,
+        }
+    }
+
+    fn is_float(self) -> bool {
+        matches!(self, Self::F16 | Self::F32)
+    }
+}
+
+/// Choices of the Save As dialog.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SaveAsOptions {
+    pub format: SaveFormat,
+    /// `None` keeps the depth of the image, as far as the format allows.
+    pub depth: Option<SampleDepth>,
+    /// JPEG quality from 1 to 100.
+    pub jpeg_quality: u8,
+}
+
+impl Default for SaveAsOptions {
+    fn default() -> Self {
+        Self {
+            format: SaveFormat::default(),
+            depth: None,
+            jpeg_quality: 90,
+        }
+    }
+}
+
+impl SaveAsOptions {
+    /// Depth written for a `dtype` image.
+    pub fn depth_for(&self, dtype: PixelType) -> SampleDepth {
+        self.format.resolve_depth(self.depth.unwrap_or(SampleDepth::of(dtype)))
+    }
+}
+
+/// Encode the samples of `rect` in `image` as `options` asks. Integer depths span the full range of the target
+/// type, so an image written at its own depth keeps its stored values; float depths keep the values the viewer
+/// works with, 0 to 1 for integer images.
+pub fn encode_save_as(image: &ImageData, rect: Recti, options: &SaveAsOptions) -> Result<Vec<u8>> {
+    let spec = image.spec();
+    let depth = options.depth_for(spec.dtype);
+    let scale = match depth {
+        SampleDepth::U8 => u8::MAX as f32,
+        SampleDepth::U16 => u16::MAX as f32,
+        SampleDepth::F16 | SampleDepth::F32 => 1.0,
+    };
+    let planes = region_samples(image, rect, scale)?;
+    let (width, height) = (rect.width() as u32, rect.height() as u32);
+    let format = options.format;
+    match format {
+        SaveFormat::Png => {
+            encode_with_image(fit_channels(planes, format, true)?, width, height, depth, |image, out| {
+                image.write_to(out, image::ImageFormat::Png)
+            })
+        }
+        SaveFormat::Jpeg => {
+            encode_with_image(fit_channels(planes, format, false)?, width, height, depth, |image, out| {
+                image::codecs::jpeg::JpegEncoder::new_with_quality(out, options.jpeg_quality.clamp(1, 100))
+                    .encode_image(image)
+            })
+        }
+        SaveFormat::Tiff => encode_tiff(fit_channels(planes, format, false)?, width, height, depth),
+        SaveFormat::Exr => encode_exr_planes(planes, width, height, depth == SampleDepth::F16),
+        SaveFormat::Pfm => Ok(encode_pfm(&fit_channels(planes, format, false)?, width, height)),
+    }
+}
+
+/// Arrange `planes` as gray, gray and alpha, RGB or RGBA for a format that stores at most four channels. Alpha is
+/// dropped when the format cannot store it, or, with `gray_alpha` false, gray is spread to RGB to keep it.
+fn fit_channels(mut planes: Vec<Vec<f32>>, format: SaveFormat, gray_alpha: bool) -> Result<Vec<Vec<f32>>> {
+    let keeps_alpha = matches!(format, SaveFormat::Png | SaveFormat::Tiff);
+    match planes.len() {
+        1 | 3 => {}
+        2 if !keeps_alpha => planes.truncate(1),
+        2 if !gray_alpha => {
+            let alpha = planes.pop().unwrap_or_default();
+            let gray = planes[0].clone();
+            planes.extend([gray.clone(), gray, alpha]);
+        }
+        2 => {}
+        4 if !keeps_alpha => planes.truncate(3),
+        4 => {}
+        channels => return Err(eyre!("{} cannot store {channels} channels", format.label())),
+    }
+    Ok(planes)
+}
+
+/// Interleave `planes` into an 8 or 16-bit image and write it with `write`.
+fn encode_with_image(
+    planes: Vec<Vec<f32>>,
+    width: u32,
+    height: u32,
+    depth: SampleDepth,
+    write: impl FnOnce(&image::DynamicImage, &mut Cursor<&mut Vec<u8>>) -> image::ImageResult<()>,
+) -> Result<Vec<u8>> {
+    let invalid = || eyre!("Sample count does not match the export region");
+    let image = if depth == SampleDepth::U8 {
+        let samples = interleave(&planes, |v| v.round().clamp(0.0, 255.0) as u8);
+        match planes.len() {
+            1 => image::DynamicImage::ImageLuma8(
+                image::ImageBuffer::from_raw(width, height, samples).ok_or_else(invalid)?,
+            ),
+            2 => image::DynamicImage::ImageLumaA8(
+                image::ImageBuffer::from_raw(width, height, samples).ok_or_else(invalid)?,
+            ),
+            3 => image::DynamicImage::ImageRgb8(
+                image::ImageBuffer::from_raw(width, height, samples).ok_or_else(invalid)?,
+            ),
+            _ => image::DynamicImage::ImageRgba8(
+                image::ImageBuffer::from_raw(width, height, samples).ok_or_else(invalid)?,
+            ),
+        }
+    } else {
+        let samples = interleave(&planes, |v| v.round().clamp(0.0, 65535.0) as u16);
+        match planes.len() {
+            1 => image::DynamicImage::ImageLuma16(
+                image::ImageBuffer::from_raw(width, height, samples).ok_or_else(invalid)?,
+            ),
+            2 => image::DynamicImage::ImageLumaA16(
+                image::ImageBuffer::from_raw(width, height, samples).ok_or_else(invalid)?,
+            ),
+            3 => image::DynamicImage::ImageRgb16(
+                image::ImageBuffer::from_raw(width, height, samples).ok_or_else(invalid)?,
+            ),
+            _ => image::DynamicImage::ImageRgba16(
+                image::ImageBuffer::from_raw(width, height, samples).ok_or_else(invalid)?,
+            ),
+        }
+    };
+    let mut bytes = Vec::new();
+    write(&image, &mut Cursor::new(&mut bytes))?;
+    Ok(bytes)
+}
+
+fn encode_tiff(planes: Vec<Vec<f32>>, width: u32, height: u32, depth: SampleDepth) -> Result<Vec<u8>> {
+    let mut bytes = Cursor::new(Vec::new());
+    let mut encoder = TiffEncoder::new(&mut bytes)?;
+    let to_u8 = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+    let to_u16 = |v: f32| v.round().clamp(0.0, 65535.0) as u16;
+    match (planes.len(), depth) {
+        (1, SampleDepth::U8) => encoder.write_image::<colortype::Gray8>(width, height, &interleave(&planes, to_u8)),
+        (1, SampleDepth::U16) => encoder.write_image::<colortype::Gray16>(width, height, &interleave(&planes, to_u16)),
+        (1, _) => encoder.write_image::<colortype::Gray32Float>(width, height, &interleave(&planes, |v| v)),
+        (3, SampleDepth::U8) => encoder.write_image::<colortype::RGB8>(width, height, &interleave(&planes, to_u8)),
+        (3, SampleDepth::U16) => encoder.write_image::<colortype::RGB16>(width, height, &interleave(&planes, to_u16)),
+        (3, _) => encoder.write_image::<colortype::RGB32Float>(width, height, &interleave(&planes, |v| v)),
+        (_, SampleDepth::U8) => encoder.write_image::<colortype::RGBA8>(width, height, &interleave(&planes, to_u8)),
+        (_, SampleDepth::U16) => encoder.write_image::<colortype::RGBA16>(width, height, &interleave(&planes, to_u16)),
+        (_, _) => encoder.write_image::<colortype::RGBA32Float>(width, height, &interleave(&planes, |v| v)),
+    }?;
+    Ok(bytes.into_inner())
+}
+
+/// Portable float map: little-endian rows from the bottom up, gray or RGB.
+fn encode_pfm(planes: &[Vec<f32>], width: u32, height: u32) -> Vec<u8> {
+    let magic = if planes.len() == 1 { "Pf" } else { "PF" };
+    let mut bytes = format!("{magic}\n{width} {height}\n-1.0\n").into_bytes();
+    let samples = interleave(planes, f32::to_le_bytes);
+    let row_len = width as usize * planes.len();
+    for row in samples.chunks_exact(row_len.max(1)).rev() {
+        bytes.extend(row.iter().flatten());
+    }
+    bytes
+}
+
+fn interleave<T>(planes: &[Vec<f32>], convert: impl Fn(f32) -> T) -> Vec<T> {
+    let convert = &convert;
+    (0..planes[0].len())
+        .flat_map(|index| planes.iter().map(move |plane| convert(plane[index])))
+        .collect()
+}
+
+/// Samples of `rect` in the stored value range, channel by channel.
+fn region_channels(image: &ImageData, rect: Recti) -> Result<Vec<Vec<f32>>> {
+    region_samples(image, rect, image.spec().dtype.alpha() as f32)
+}
+
+/// Samples of `rect` multiplied by `scale`, channel by channel.
+fn region_samples(image: &ImageData, rect: Recti, scale: f32) -> Result<Vec<Vec<f32>>> {
+    let spec = image.spec();
+    let (x0, y0, width, height) = rect.xywh();
+    if width <= 0 || height <= 0 || x0 < 0 || y0 < 0 || x0 + width > spec.width || y0 + height > spec.height {
+        return Err(eyre!("Invalid export region {rect} for a {}x{} image", spec.width, spec.height));
+    }
+    let data = image
+        .data()
+        .ok_or_else(|| eyre!("CPU pixel data is unavailable for this image"))?;
+    let channels = spec.channels.max(1) as usize;
+    let mut planes = vec![Vec::with_capacity(width as usize * height as usize); channels];
+    for y in y0..y0 + height {
+        let row = (y as usize * spec.width as usize + x0 as usize) * channels;
+        for pixel in data[row..row + width as usize * channels].chunks_exact(channels) {
+            for (plane, &v) in planes.iter_mut().zip(pixel) {
+                plane.push(v * scale);
+            }
+        }
+    }
+    Ok(planes)
+}
+
+fn encode_png8(image: &ImageData, rect: Recti) -> Result<Vec<u8>> {
+    let planes = fit_channels(region_channels(image, rect)?, SaveFormat::Png, true)?;
+    encode_with_image(
+        planes,
+        rect.width() as u32,
+        rect.height() as u32,
+        SampleDepth::U8,
+        |image, out| image.write_to(out, image::ImageFormat::Png),
+    )
+}
+
+fn encode_exr(image: &ImageData, rect: Recti, half: bool) -> Result<Vec<u8>> {
+    encode_exr_planes(region_channels(image, rect)?, rect.width() as u32, rect.height() as u32, half)
+}
+
+fn encode_exr_planes(planes: Vec<Vec<f32>>, width: u32, height: u32, half: bool) -> Result<Vec<u8>> {
+    let names: Vec<String> = match planes.len() {
+        1 => vec!["Y".into()],
+        2 => vec!["Y".into(), "A".into()],
+        3 => vec!["R".into(), "G".into(), "B".into()],
+        4 => vec!["R".into(), "G".into(), "B".into(), "A".into()],
+        channels => (0..channels).map(|index| format!("c{index}")).collect(),
+    };
+    let channels: SmallVec<[AnyChannel<FlatSamples>; 4]> = names
+        .into_iter()
+        .zip(planes)
+        .map(|(name, plane)| {
+            let samples = if half {
+                FlatSamples::F16(plane.into_iter().map(f16::from_f32).collect())
+            } else {
+                FlatSamples::F32(plane)
+            };
+            AnyChannel::new(name.as_str(), samples)
+        })
+        .collect();
+    let exr_image = exr::prelude::Image::from_channels((width as usize, height as usize), AnyChannels::sort(channels));
+    let mut bytes = Cursor::new(Vec::new());
+    exr_image
+        .write()
+        .to_buffered(&mut bytes)
+        .map_err(|e| eyre!("Failed to encode OpenEXR: {e}"))?;
+    Ok(bytes.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{model::ImageSpec, util::math_ext::vec2i};
+
+    #[test]
+    fn encodes_samples_in_the_requested_type() {
+        let rect = Recti::from_min_size(vec2i(1, 0), vec2i(1, 2));
+        let u8_rgb = ImageData::from_f32(
+            ImageSpec::new(2, 2, 3, PixelType::U8),
+            [0, 0, 0, 255, 128, 1, 0, 0, 0, 2, 3, 4].map(|v| v as f32 / 255.0).to_vec(),
+        )
+        .unwrap();
+        let png = encode_samples(&u8_rgb, rect, ExportData::Original).unwrap();
+        let decoded = image::load_from_memory(&png).unwrap().into_rgb8();
+        assert_eq!(decoded.dimensions(), (1, 2));
+        assert_eq!(decoded.into_raw(), [255, 128, 1, 2, 3, 4]);
+
+        let exr = encode_samples(&u8_rgb, rect, ExportData::RawFloat).unwrap();
+        let decoded = ImageData::from_decoded(crate::model::image_io::decode_bytes(&exr).unwrap()).unwrap();
+        assert_eq!(decoded.spec().dtype, PixelType::F32);
+        assert_eq!(decoded.data().unwrap(), [255.0, 128.0, 1.0, 2.0, 3.0, 4.0]);
+
+        assert!(encode_samples(&u8_rgb, rect, ExportData::Display).is_err());
+        let outside = Recti::from_min_size(vec2i(1, 1), vec2i(2, 2));
+        assert!(encode_samples(&u8_rgb, outside, ExportData::Original).is_err());
+    }
+
+    #[test]
+    fn saves_as_the_chosen_format_and_depth() {
+        assert_eq!(SaveFormat::Png.resolve_depth(SampleDepth::F32), SampleDepth::U16);
+        assert_eq!(SaveFormat::Tiff.resolve_depth(SampleDepth::F16), SampleDepth::F32);
+        assert_eq!(SaveFormat::Exr.resolve_depth(SampleDepth::U8), SampleDepth::F16);
+
+        let values = [0u16, 1000, 65535, 30000, 2, 40000];
+        let u16_rgb = ImageData::from_f32(
+            ImageSpec::new(1, 2, 3, PixelType::U16),
+            values.map(|v| v as f32 / 65535.0).to_vec(),
+        )
+        .unwrap();
+        let rect = Recti::from_min_size(vec2i(0, 0), vec2i(1, 2));
+        let save = |format, depth| {
+            let options = SaveAsOptions {
+                format,
+                depth,
+                ..SaveAsOptions::default()
+            };
+            encode_save_as(&u16_rgb, rect, &options).unwrap()
+        };
+
+        let png = image::load_from_memory(&save(SaveFormat::Png, None)).unwrap().into_rgb16();
+        assert_eq!(png.into_raw(), values);
+        let png8 = image::load_from_memory(&save(SaveFormat::Png, Some(SampleDepth::U8)))
+            .unwrap()
+            .into_rgb8();
+        assert_eq!(png8.into_raw(), [0, 4, 255, 117, 0, 156]);
+        let jpeg = image::load_from_memory(&save(SaveFormat::Jpeg, None)).unwrap();
+        assert_eq!((jpeg.width(), jpeg.height()), (1, 2));
+
+        for format in [SaveFormat::Tiff, SaveFormat::Pfm] {
+            let decoded = crate::model::image_io::decode_bytes(&save(format, Some(SampleDepth::F32))).unwrap();
+            assert_eq!(decoded.pixel_type, PixelType::F32);
+            let rows: Vec<f32> = (0..2)
+                .flat_map(|y| decoded.normalized_pixel(0, y).unwrap().0[..3].to_vec())
+                .collect();
+            assert_eq!(rows, u16_rgb.data().unwrap(), "{format:?}");
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Label images drawn as a translucent colored layer over the shown image, for checking semantic segmentation
+//! ground truth and predictions.
+
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use color_eyre::eyre::{eyre, Result};
+use eframe::egui::Color32;
+
+use super::{Image, ImageData};
+use crate::res::overlay_class_color;
+
+static NEXT_REVISION: AtomicU64 = AtomicU64::new(1);
+
+pub struct MaskClass {
+    pub label: u32,
+    pub color: Color32,
+    /// Number of pixels with this label.
+    pub pixels: usize,
+    pub visible: bool,
+}
+
+pub struct SegmentationMask {
+    /// File name of the mask.
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /// Classes present in the mask, by ascending label.
+    pub classes: Vec<MaskClass>,
+    pub opacity: f32,
+    /// Index into `classes` of every pixel, row by row.
+    class_of_pixel: Vec<u32>,
+    /// Changes with every change of the colored layer, so the texture drawn from it can be cached.
+    revision: u64,
+}
+
+impl SegmentationMask {
+    /// Load a paletted PNG, whose palette gives the class colors, or a single-channel integer image of labels.
+    pub fn load(path: &Path) -> Result<Self> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let is_png = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+        if is_png {
+            if let Some(mask) = load_paletted_png(path, &name)? {
+                return Ok(mask);
+            }
+        }
+        let image = ImageData::load_from_path(&PathBuf::from(path))?;
+        let spec = image.spec();
+        if spec.channels != 1 || spec.dtype.is_floating() {
+            return Err(eyre!("'{name}' is not a paletted PNG or a single-channel integer label image"));
+        }
+        let scale = spec.dtype.alpha();
+        let mut labels = Vec::with_capacity((spec.width * spec.height) as usize);
+        for y in 0..spec.height {
+            for x in 0..spec.width {
+                let value = image.get_pixel_at(x, y)?[0] as f64 * scale;
+                labels.push(value.round() as u32);
+            }
+        }
+        Ok(Self::from_labels(name, spec.width as u32, spec.height as u32, &labels, None))
+    }
+
+    /// Mask of `labels`, colored from `palette` where it has an entry for the label. Label 0 is taken as the
+    /// background and starts hidden.
+    pub fn from_labels(name: String, width: u32, height: u32, labels: &[u32], palette: Option<&[Color32]>) -> Self {
+        let mut present: Vec<u32> = labels.to_vec();
+        present.sort_unstable();
+        present.dedup();
+
+        let mut classes: Vec<MaskClass> = present
+            .iter()
+            .map(|&label| MaskClass {
+                label,
+                color: palette
+                    .and_then(|palette| palette.get(label as usize).copied())
+                    .unwrap_or_else(|| overlay_class_color(label as usize)),
+                pixels: 0,
+                visible: label != 0,
+            })
+            .collect();
+        let class_of_pixel = labels
+            .iter()
+            .map(|label| {
+                let index = present.binary_search(label).unwrap_or_default();
+                classes[index].pixels += 1;
+                index as u32
+            })
+            .collect();
+        Self {
+            name,
+            width,
+            height,
+            classes,
+            opacity: 0.5,
+            class_of_pixel,
+            revision: NEXT_REVISION.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn set_visible(&mut self, index: usize, visible: bool) {
+        if let Some(class) = self.classes.get_mut(index).filter(|class| class.visible != visible) {
+            class.visible = visible;
+            self.revision = NEXT_REVISION.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn set_all_visible(&mut self, visible: bool) {
+        for index in 0..self.classes.len() {
+            self.set_visible(index, visible);
+        }
+    }
+
+    /// Unmultiplied RGBA of the colored layer at full opacity; hidden classes are transparent.
+    pub fn rgba(&self) -> Vec<u8> {
+        let colors: Vec<[u8; 4]> = self
+            .classes
+            .iter()
+            .map(|class| {
+                let [r, g, b, _] = class.color.to_array();
+                [r, g, b, if class.visible { 255 } else { 0 }]
+            })
+            .collect();
+        self.class_of_pixel.iter().flat_map(|&index| colors[index as usize]).collect()
+    }
+}
+
+/// Indices and palette of a paletted PNG, or `None` for other PNGs.
+fn load_paletted_png(path: &Path, name: &str) -> Result<Option<SegmentationMask>> {
+    let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    decoder.set_transformations(png::Transformations::IDENTITY);
+    let mut reader = decoder.read_info()?;
+    let info = reader.info();
+    if info.color_type != png::ColorType::Indexed {
+        return Ok(None);
+    }
+    let (width, height) = (info.width, info.height);
+    let bits = info.bit_depth as usize;
+    let palette: Vec<Color32> = info
+        .palette
+        .as_deref()
+        .unwrap_or_default()
+        .chunks_exact(3)
+        .map(|rgb| Color32::from_rgb(rgb[0], rgb[1], rgb[2]))
+        .collect();
+
+    let mut bytes = vec![0; reader.output_buffer_size().ok_or_else(|| eyre!("PNG '{name}' is too large"))?];
+    let frame = reader.next_frame(&mut bytes)?;
+    let mut labels = Vec::with_capacity(width as usize * height as usize);
+    for row in bytes[..frame.buffer_size()].chunks_exact(frame.line_size) {
+        labels.extend((0..width as usize).map(|x| {
+            let bit = x * bits;
+            let shift = 8 - bits - bit % 8;
+            ((row[bit / 8] >> shift) & ((1u16 << bits) - 1) as u8) as u32
+        }));
+    }
+    Ok(Some(SegmentationMask::from_labels(
+        name.to_string(),
+        width,
+        height,
+        &labels,
+        Some(&palette),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_classes_and_colors_visible_ones() {
+        let palette = [Color32::BLACK, Color32::RED];
+        let mut mask = SegmentationMask::from_labels("m.png".to_string(), 2, 2, &[0, 1, 1, 7], Some(&palette));
+        let labels: Vec<_> = mask
+            .classes
+            .iter()
+            .map(|class| (class.label, class.pixels, class.visible))
+            .collect();
+        assert_eq!(labels, [(0, 1, false), (1, 2, true), (7, 1, true)]);
+        assert_eq!(mask.classes[1].color, Color32::RED);
+        assert_eq!(mask.classes[2].color, overlay_class_color(7));
+
+        let rgba = mask.rgba();
+        assert_eq!(&rgba[..8], &[0, 0, 0, 0, 255, 0, 0, 255]);
+
+        let revision = mask.revision();
+        mask.set_visible(1, true);
+        assert_eq!(mask.revision(), revision);
+        mask.set_all_visible(false);
+        assert_ne!(mask.revision(), revision);
+        assert!(mask.rgba().chunks_exact(4).all(|pixel| pixel[3] == 0));
+    }
+}
@@ -0,0 +1,370 @@
+use std::path::Path;
+
+use color_eyre::eyre::{eyre, Result};
+use eframe::egui::Pos2;
+
+use crate::{
+    model::{Image, Recti},
+    util::math_ext::{vec2i, Vec2i},
+};
+
+const HIST_BINS: usize = 256;
+
+/// Pixel mask of a lasso, polygon or magic-wand selection. Only the bounding box of the selection is stored.
+pub struct SelectionMask {
+    /// Outline line segments in image coordinates, for drawing.
+    outline: Vec<[Pos2; 2]>,
+    bounds: Recti,
+    mask: Vec<bool>,
+    count: usize,
+}
+
+impl SelectionMask {
+    /// Rasterize a closed polygon given in image coordinates. A pixel is selected when its center is inside
+    /// the polygon (even-odd rule). Returns `None` when no pixel of the image is covered.
+    pub fn from_polygon(points: Vec<Pos2>, image_width: i32, image_height: i32) -> Option<Self> {
+        if points.len() < 3 {
+            return None;
+        }
+        let image_rect = Recti::from_min_size(vec2i(0, 0), vec2i(image_width, image_height));
+        let bounds = points
+            .iter()
+            .fold(Recti::bound_two_pos(points[0], points[0]), |rect, p| {
+                rect.union(Recti::bound_two_pos(*p, *p))
+            })
+            .intersect(image_rect);
+        if bounds.empty() {
+            return None;
+        }
+
+        let (width, height) = (bounds.width() as usize, bounds.height() as usize);
+        let mut mask = vec![false; width * height];
+        let mut crossings = Vec::new();
+        for row in 0..height {
+            let y = (bounds.min.y + row as i32) as f32 + 0.5;
+            crossings.clear();
+            for (a, b) in points.iter().zip(points.iter().cycle().skip(1)) {
+                if (a.y <= y) != (b.y <= y) {
+                    crossings.push(a.x + (y - a.y) / (b.y - a.y) * (b.x - a.x));
+                }
+            }
+            crossings.sort_by(f32::total_cmp);
+            for span in crossings.chunks_exact(2) {
+                let column = |x: f32| ((x - 0.5).ceil() as i32 - bounds.min.x).clamp(0, width as i32) as usize;
+                let (start, end) = (column(span[0]), column(span[1]));
+                if start < end {
+                    mask[row * width + start..row * width + end].fill(true);
+                }
+            }
+        }
+
+        let count = mask.iter().filter(|&&selected| selected).count();
+        if count == 0 {
+            return None;
+        }
+        let outline = points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .map(|(a, b)| [*a, *b])
+            .collect();
+        Some(Self {
+            outline,
+            bounds,
+            mask,
+            count,
+        })
+    }
+
+    /// Select the 4-connected region around `seed` whose channel values are all within `tolerance` of the seed
+    /// pixel (normalized values). NaN only matches NaN. Returns `None` without CPU pixel data.
+    pub fn from_flood_fill(image: &impl Image, seed: Vec2i, tolerance: f32) -> Option<Self> {
+        #[cfg(debug_assertions)]
+        let _timer = crate::util::timer::ScopedTimer::new("Selection flood fill");
+
+        let spec = image.spec();
+        let data = image.data()?;
+        let (width, height) = (spec.width as usize, spec.height as usize);
+        let channels = spec.channels as usize;
+        if seed.x < 0 || seed.y < 0 || seed.x >= spec.width || seed.y >= spec.height {
+            return None;
+        }
+        let pixel = |index: usize| &data[index * channels..(index + 1) * channels];
+        let seed_index = seed.y as usize * width + seed.x as usize;
+        let seed_value = pixel(seed_index).to_vec();
+        let matches = |index: usize| {
+            pixel(index).iter().zip(&seed_value).all(|(&v, &s)| {
+                if s.is_nan() {
+                    v.is_nan()
+                } else {
+                    (v - s).abs() <= tolerance
+                }
+            })
+        };
+
+        let mut visited = vec![false; width * height];
+        let mut stack = vec![seed_index];
+        visited[seed_index] = true;
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (seed.x as usize, seed.y as usize, 0, 0);
+        while let Some(index) = stack.pop() {
+            let (x, y) = (index % width, index / width);
+            (min_x, min_y) = (min_x.min(x), min_y.min(y));
+            (max_x, max_y) = (max_x.max(x), max_y.max(y));
+            let neighbors = [
+                (x > 0).then(|| index - 1),
+                (x + 1 < width).then(|| index + 1),
+                (y > 0).then(|| index - width),
+                (y + 1 < height).then(|| index + width),
+            ];
+            for neighbor in neighbors.into_iter().flatten() {
+                if !visited[neighbor] && matches(neighbor) {
+                    visited[neighbor] = true;
+                    stack.push(neighbor);
+                }
+            }
+        }
+
+        let bounds = Recti::from_min_max(vec2i(min_x as i32, min_y as i32), vec2i(max_x as i32 + 1, max_y as i32 + 1));
+        let mask: Vec<bool> = (min_y..=max_y)
+            .flat_map(|y| visited[y * width + min_x..=y * width + max_x].iter().copied())
+            .collect();
+        Some(Self::from_mask(bounds, mask))
+    }
+
+    fn from_mask(bounds: Recti, mask: Vec<bool>) -> Self {
+        let count = mask.iter().filter(|&&selected| selected).count();
+        let outline = mask_outline(bounds, &mask);
+        Self {
+            outline,
+            bounds,
+            mask,
+            count,
+        }
+    }
+
+    /// Outline line segments in image coordinates.
+    pub fn outline(&self) -> &[[Pos2; 2]] {
+        &self.outline
+    }
+
+    pub fn bounds(&self) -> Recti {
+        self.bounds
+    }
+
+    pub fn pixel_count(&self) -> usize {
+        self.count
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        if x < self.bounds.min.x || x >= self.bounds.max.x || y < self.bounds.min.y || y >= self.bounds.max.y {
+            return false;
+        }
+        let index = (y - self.bounds.min.y) as usize * self.bounds.width() as usize + (x - self.bounds.min.x) as usize;
+        self.mask[index]
+    }
+
+    fn selected_pixels(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        let width = self.bounds.width() as usize;
+        self.mask
+            .iter()
+            .enumerate()
+            .filter(|(_, &selected)| selected)
+            .map(move |(index, _)| {
+                (
+                    self.bounds.min.x + (index % width) as i32,
+                    self.bounds.min.y + (index / width) as i32,
+                )
+            })
+    }
+
+    /// Mean, standard deviation and histogram of the masked pixels. Mean and std are multiplied by `scale`;
+    /// the histogram uses the same 256 bins over 0..1 as the full-image histogram.
+    pub fn statistics(&self, image: &impl Image, scale: f64) -> Result<MaskedStatistics> {
+        let spec = image.spec();
+        if self.bounds.max.x > spec.width || self.bounds.max.y > spec.height {
+            return Err(eyre!("Selection mask exceeds image bounds"));
+        }
+        let data = image
+            .data()
+            .ok_or_else(|| eyre!("CPU pixel data is unavailable for this GPU-derived image"))?;
+        let channels = spec.channels as usize;
+        let mut sum = vec![0.0f64; channels];
+        let mut sum_sq = vec![0.0f64; channels];
+        let mut hist = vec![vec![0.0f32; HIST_BINS]; channels];
+        for (x, y) in self.selected_pixels() {
+            let start = (y as usize * spec.width as usize + x as usize) * channels;
+            for (c, &v) in data[start..start + channels].iter().enumerate() {
+                sum[c] += v as f64;
+                sum_sq[c] += (v as f64) * (v as f64);
+                if (0.0..1.0).contains(&v) {
+                    hist[c][((v * HIST_BINS as f32) as usize).min(HIST_BINS - 1)] += 1.0;
+                }
+            }
+        }
+
+        let n = self.count as f64;
+        let mean: Vec<f64> = sum.iter().map(|s| s / n).collect();
+        let std = sum_sq
+            .iter()
+            .zip(&mean)
+            .map(|(sq, m)| (sq / n - m * m).max(0.0).sqrt() * scale)
+            .collect();
+        Ok(MaskedStatistics {
+            count: self.count,
+            mean: mean.into_iter().map(|m| m * scale).collect(),
+            std,
+            hist,
+        })
+    }
+
+    /// Save the mask at full image size as an 8-bit grayscale PNG (255 = selected).
+    pub fn save_png(&self, path: &Path, image_width: i32, image_height: i32) -> Result<()> {
+        if image_width <= 0 || image_height <= 0 {
+            return Err(eyre!("Invalid mask size: {}x{}", image_width, image_height));
+        }
+        let mut pixels = vec![0u8; image_width as usize * image_height as usize];
+        for (x, y) in self.selected_pixels() {
+            if x < image_width && y < image_height {
+                pixels[y as usize * image_width as usize + x as usize] = u8::MAX;
+            }
+        }
+        image::save_buffer_with_format(
+            path,
+            &pixels,
+            image_width as u32,
+            image_height as u32,
+            image::ColorType::L8,
+            image::ImageFormat::Png,
+        )?;
+        Ok(())
+    }
+}
+
+/// Pixel-edge segments between selected and unselected pixels, with collinear runs merged.
+fn mask_outline(bounds: Recti, mask: &[bool]) -> Vec<[Pos2; 2]> {
+    let (width, height) = (bounds.width() as usize, bounds.height() as usize);
+    let selected = |x: isize, y: isize| {
+        x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height && mask[y as usize * width + x as usize]
+    };
+    let point = |x: usize, y: usize| Pos2::new((bounds.min.x + x as i32) as f32, (bounds.min.y + y as i32) as f32);
+    let mut segments = Vec::new();
+
+    // Horizontal edges lie on row boundary `y`, between pixel rows y - 1 and y.
+    for y in 0..=height {
+        let mut run_start = None;
+        for x in 0..=width {
+            let (xi, yi) = (x as isize, y as isize);
+            let is_edge = x < width && selected(xi, yi - 1) != selected(xi, yi);
+            match (is_edge, run_start) {
+                (true, None) => run_start = Some(x),
+                (false, Some(start)) => {
+                    segments.push([point(start, y), point(x, y)]);
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+    // Vertical edges lie on column boundary `x`, between pixel columns x - 1 and x.
+    for x in 0..=width {
+        let mut run_start = None;
+        for y in 0..=height {
+            let (xi, yi) = (x as isize, y as isize);
+            let is_edge = y < height && selected(xi - 1, yi) != selected(xi, yi);
+            match (is_edge, run_start) {
+                (true, None) => run_start = Some(y),
+                (false, Some(start)) => {
+                    segments.push([point(x, start), point(x, y)]);
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+    segments
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MaskedStatistics {
+    pub count: usize,
+    pub mean: Vec<f64>,
+    pub std: Vec<f64>,
+    pub hist: Vec<Vec<f32>>,
+}
+
+impl MaskedStatistics {
+    /// Flatten into the statistics worker's `Vec<f64>` result: `[count, mean.., std.., hist..]`.
+    pub fn pack(&self) -> Vec<f64> {
+        std::iter::once(self.count as f64)
+            .chain(self.mean.iter().copied())
+            .chain(self.std.iter().copied())
+            .chain(self.hist.iter().flatten().map(|&v| v as f64))
+            .collect()
+    }
+
+    pub fn unpack(values: &[f64]) -> Option<Self> {
+        let channels = values.len().checked_sub(1)? / (2 + HIST_BINS);
+        if channels == 0 || values.len() != 1 + channels * (2 + HIST_BINS) || !values[0].is_finite() {
+            return None;
+        }
+        let (mean, rest) = values[1..].split_at(channels);
+        let (std, hist) = rest.split_at(channels);
+        Some(Self {
+            count: values[0] as usize,
+            mean: mean.to_vec(),
+            std: std.to_vec(),
+            hist: hist
+                .chunks_exact(HIST_BINS)
+                .map(|bins| bins.iter().map(|&v| v as f32).collect())
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eframe::egui::pos2;
+
+    use super::*;
+    use crate::model::{ImageData, ImageSpec, PixelType};
+
+    #[test]
+    fn rasterizes_triangle_by_pixel_centers() {
+        // Right triangle covering the lower-left half of a 4x4 square.
+        let mask = SelectionMask::from_polygon(vec![pos2(0.0, 0.0), pos2(4.0, 4.0), pos2(0.0, 4.0)], 8, 8).unwrap();
+        assert_eq!(mask.bounds().to_string(), "0, 0, 4, 4");
+        assert_eq!(mask.pixel_count(), 6);
+        assert!(mask.contains(0, 3));
+        assert!(!mask.contains(3, 0));
+        assert!(!mask.contains(5, 5));
+    }
+
+    #[test]
+    fn flood_fills_connected_region_within_tolerance() {
+        #[rustfmt::skip]
+        let pixels = vec![
+            0.0, 0.0, 1.0, 1.0,
+            0.0, 0.95, 1.0, 0.0,
+            1.0, 0.0, 0.0, 0.0,
+        ];
+        let image = ImageData::from_f32(ImageSpec::new(4, 3, 1, PixelType::F32), pixels).unwrap();
+        let mask = SelectionMask::from_flood_fill(&image, vec2i(2, 0), 0.1).unwrap();
+        assert_eq!(mask.bounds().to_string(), "1, 0, 3, 2");
+        assert_eq!(mask.pixel_count(), 4);
+        assert!(!mask.contains(0, 2));
+
+        let single = SelectionMask::from_flood_fill(&image, vec2i(0, 2), 0.1).unwrap();
+        assert_eq!(single.pixel_count(), 1);
+        assert_eq!(single.outline().len(), 4);
+    }
+
+    #[test]
+    fn packs_and_unpacks_statistics() {
+        let stats = MaskedStatistics {
+            count: 3,
+            mean: vec![0.5, 0.25],
+            std: vec![0.1, 0.2],
+            hist: vec![vec![1.0; HIST_BINS], vec![2.0; HIST_BINS]],
+        };
+        assert_eq!(MaskedStatistics::unpack(&stats.pack()), Some(stats));
+    }
+}
@@ -0,0 +1,149 @@
+//! Encoders for rendered image sequences. GIFs are written in process; MP4s are piped to an `ffmpeg` found on
+//! `PATH`, since no video encoder is bundled.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+};
+
+use color_eyre::eyre::{eyre, Result};
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame, RgbaImage,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SequenceFormat {
+    Gif,
+    Mp4,
+}
+
+impl SequenceFormat {
+    pub const ALL: [Self; 2] = [Self::Gif, Self::Mp4];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Gif => "GIF",
+            Self::Mp4 => "MP4 (ffmpeg)",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Gif => "gif",
+            Self::Mp4 => "mp4",
+        }
+    }
+}
+
+/// Writes same-sized RGBA8 frames to an animation or video file.
+pub enum SequenceWriter {
+    Gif {
+        encoder: GifEncoder<BufWriter<File>>,
+        size: (u32, u32),
+        delay: Delay,
+    },
+    Mp4 {
+        ffmpeg: Child,
+        stdin: ChildStdin,
+        path: PathBuf,
+    },
+}
+
+impl SequenceWriter {
+    /// Start writing `path`. MP4 frames are encoded as H.264 in 4:2:0, which needs an even `size`.
+    pub fn create(path: &Path, format: SequenceFormat, size: (u32, u32), fps: u32) -> Result<Self> {
+        let fps = fps.max(1);
+        match format {
+            SequenceFormat::Gif => {
+                let mut encoder = GifEncoder::new_with_speed(BufWriter::new(File::create(path)?), 10);
+                encoder.set_repeat(Repeat::Infinite)?;
+                Ok(Self::Gif {
+                    encoder,
+                    size,
+                    delay: Delay::from_numer_denom_ms(1000, fps),
+                })
+            }
+            SequenceFormat::Mp4 => {
+                if !size.0.is_multiple_of(2) || !size.1.is_multiple_of(2) {
+                    return Err(eyre!("MP4 frames need an even size, got {}x{}", size.0, size.1));
+                }
+                let mut ffmpeg = Command::new("ffmpeg")
+                    .args(["-y", "-loglevel", "error", "-f", "rawvideo", "-pix_fmt", "rgba", "-s"])
+                    .arg(format!("{}x{}", size.0, size.1))
+                    .arg("-r")
+                    .arg(fps.to_string())
+                    .args(["-i", "-", "-c:v", "libx264", "-pix_fmt", "yuv420p"])
+                    .arg(path)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                    .map_err(|e| eyre!("Failed to start ffmpeg, which MP4 export needs on PATH: {e}"))?;
+                let stdin = ffmpeg.stdin.take().ok_or_else(|| eyre!("ffmpeg has no stdin"))?;
+                Ok(Self::Mp4 {
+                    ffmpeg,
+                    stdin,
+                    path: path.to_path_buf(),
+                })
+            }
+        }
+    }
+
+    pub fn write_frame(&mut self, rgba: Vec<u8>) -> Result<()> {
+        match self {
+            Self::Gif { encoder, size, delay } => {
+                let buffer = RgbaImage::from_raw(size.0, size.1, rgba).ok_or_else(|| eyre!("Frame size mismatch"))?;
+                encoder.encode_frame(Frame::from_parts(buffer, 0, 0, *delay))?;
+            }
+            Self::Mp4 { stdin, .. } => stdin
+                .write_all(&rgba)
+                .map_err(|e| eyre!("ffmpeg stopped accepting frames: {e}"))?,
+        }
+        Ok(())
+    }
+
+    /// Flush the file, waiting for ffmpeg to finish encoding.
+    pub fn finish(self) -> Result<()> {
+        match self {
+            // The GIF trailer is written when the encoder drops.
+            Self::Gif { encoder, .. } => drop(encoder),
+            Self::Mp4 { ffmpeg, stdin, path } => {
+                drop(stdin);
+                let output = ffmpeg.wait_with_output()?;
+                if !output.status.success() {
+                    return Err(eyre!(
+                        "ffmpeg failed to write {}: {}",
+                        path.display(),
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_a_looping_gif() {
+        let path = std::env::temp_dir().join(format!("edolview-sequence-{}.gif", std::process::id()));
+        let mut writer = SequenceWriter::create(&path, SequenceFormat::Gif, (2, 1), 4).unwrap();
+        writer.write_frame(vec![255, 0, 0, 255, 0, 0, 255, 255]).unwrap();
+        writer.write_frame(vec![0, 255, 0, 255, 0, 0, 0, 255]).unwrap();
+        assert!(writer.write_frame(vec![0; 4]).is_err());
+        writer.finish().unwrap();
+
+        let decoder = image::codecs::gif::GifDecoder::new(std::io::BufReader::new(File::open(&path).unwrap())).unwrap();
+        let frames = image::AnimationDecoder::into_frames(decoder).collect_frames().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].delay().numer_denom_ms(), (250, 1));
+        assert_eq!(frames[1].buffer().get_pixel(0, 0).0, [0, 255, 0, 255]);
+    }
+}
@@ -1,8 +1,8 @@
 use crate::{
-    model::{ImageData, PixelType, SocketAsset},
+    model::{check_decode_limits, decode_limits, ChannelOrder, ImageData, PixelType, SocketAsset, YuvFormat, YuvFrame},
     util::concurrency::NotifierSender,
 };
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{eyre, Result};
 use flate2::read::ZlibDecoder;
 use std::{
     io::{self, Read},
@@ -203,22 +203,72 @@ struct Extra {
     nbytes: u64,
     dtype: u32,
     shape: [u32; 3],
-    compression: String, // "png" | "zlib"
+    compression: String, // "png" | "zlib" | "raw" | "nv12" | "i420" | "yuyv"
+    /// Sample order of `raw` and `zlib` payloads, from the `channel_order` metadata key. Encoded payloads carry
+    /// their own order.
+    channel_order: ChannelOrder,
+    /// Byte order of `raw` and `zlib` samples, from the `byte_order` metadata key ("little" or "big").
+    big_endian: bool,
+    /// Bytes between row starts of `raw` and `zlib` payloads, from the `row_stride` metadata key. Rows are tightly
+    /// packed when absent.
+    row_stride: Option<u64>,
+    /// Optional JSON object sent after the compression name, flattened to display strings.
+    metadata: Vec<(String, String)>,
 }
 
-fn read_exact_len(stream: &mut TcpStream, len: usize) -> io::Result<Vec<u8>> {
+/// Fixed-size prefix of the extra block: nbytes, shape (h, w, c) and dtype, all big-endian.
+const EXTRA_HEADER_LEN: usize = 24;
+
+/// Element type of a `raw` or `zlib` payload. Codes 0-7 are OpenCV depths; 8 is unsigned 32-bit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PayloadDtype {
+    Pixel(PixelType),
+    U32,
+}
+
+const DTYPE_U32: u32 = 8;
+
+/// Longest image name a client may send, checked before allocating for it.
+const MAX_NAME_LEN: u64 = 64 * 1024;
+/// Longest extra block (header, compression name and metadata JSON) a client may send.
+const MAX_EXTRA_LEN: u64 = 1024 * 1024;
+
+impl PayloadDtype {
+    fn from_code(code: u32) -> Result<Self> {
+        if code == DTYPE_U32 {
+            Ok(Self::U32)
+        } else {
+            PixelType::from_protocol_code(code).map(Self::Pixel)
+        }
+    }
+
+    fn bytes(self) -> usize {
+        match self {
+            Self::Pixel(pixel_type) => pixel_type.bytes(),
+            Self::U32 => 4,
+        }
+    }
+}
+
+fn read_exact_len(stream: &mut impl Read, len: usize) -> io::Result<Vec<u8>> {
     let mut buf = vec![0u8; len];
     stream.read_exact(&mut buf)?;
     Ok(buf)
 }
 
-fn read_u64(stream: &mut TcpStream) -> io::Result<u64> {
+fn read_u64(stream: &mut impl Read) -> io::Result<u64> {
     let mut buf = [0u8; 8];
     stream.read_exact(&mut buf)?;
     Ok(u64::from_be_bytes(buf))
 }
 
 fn parse_extra(bytes: &[u8]) -> Result<Extra> {
+    if bytes.len() < EXTRA_HEADER_LEN {
+        return Err(eyre!(
+            "extra metadata is {} bytes, expected at least {EXTRA_HEADER_LEN}",
+            bytes.len()
+        ));
+    }
     let nbytes = u64::from_be_bytes(bytes[0..8].try_into()?);
     let shape = [
         u32::from_be_bytes(bytes[8..12].try_into()?),
@@ -226,22 +276,84 @@ fn parse_extra(bytes: &[u8]) -> Result<Extra> {
         u32::from_be_bytes(bytes[16..20].try_into()?),
     ];
     let dtype = u32::from_be_bytes(bytes[20..24].try_into()?);
-    let compression = String::from_utf8(bytes[24..bytes.len()].to_vec())?
-        .trim_end_matches(char::from(0))
-        .to_string();
+    // The compression name may be followed by a NUL and a JSON metadata object; older clients pad with NULs only.
+    let tail = std::str::from_utf8(&bytes[EXTRA_HEADER_LEN..])?;
+    let (compression, metadata) = tail.split_once(char::from(0)).unwrap_or((tail, ""));
+    let mut metadata = parse_metadata(metadata.trim_matches(char::from(0)))?;
+    let channel_order = match take_metadata(&mut metadata, "channel_order") {
+        Some(name) => ChannelOrder::from_name(&name).ok_or_else(|| eyre!("unsupported channel_order: {name}"))?,
+        None => ChannelOrder::Rgb,
+    };
+    let big_endian = match take_metadata(&mut metadata, "byte_order").as_deref() {
+        None | Some("little") => false,
+        Some("big") => true,
+        Some(name) => return Err(eyre!("unsupported byte_order: {name}, expected little or big")),
+    };
+    let row_stride = take_metadata(&mut metadata, "row_stride")
+        .map(|stride| {
+            stride
+                .parse::<u64>()
+                .map_err(|_| eyre!("row_stride must be a byte count, got {stride}"))
+        })
+        .transpose()?;
 
     Ok(Extra {
         nbytes,
         dtype,
         shape,
-        compression,
+        compression: compression.to_string(),
+        channel_order,
+        big_endian,
+        row_stride,
+        metadata,
     })
 }
 
-fn handle_client(stream: &mut TcpStream) -> Result<SocketAsset> {
+/// Remove a protocol key from the display metadata and return its value.
+fn take_metadata(metadata: &mut Vec<(String, String)>, key: &str) -> Option<String> {
+    let index = metadata.iter().position(|(k, _)| k == key)?;
+    Some(metadata.remove(index).1)
+}
+
+fn parse_metadata(json: &str) -> Result<Vec<(String, String)>> {
+    if json.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|e| eyre!("invalid metadata JSON: {e}"))?;
+    let serde_json::Value::Object(map) = value else {
+        return Err(eyre!("metadata must be a JSON object"));
+    };
+    Ok(map
+        .into_iter()
+        .map(|(key, value)| {
+            let text = match value {
+                serde_json::Value::String(text) => text,
+                value => value.to_string(),
+            };
+            (key, text)
+        })
+        .collect())
+}
+
+fn handle_client(stream: &mut impl Read) -> Result<SocketAsset> {
     let name_len = read_u64(stream)?;
     let extra_len = read_u64(stream)?;
     let buf_len = read_u64(stream)?;
+    // The lengths come off the wire, so bound them before sizing any buffer.
+    if name_len > MAX_NAME_LEN {
+        return Err(eyre!("name is {name_len} bytes, at most {MAX_NAME_LEN} are accepted"));
+    }
+    if extra_len > MAX_EXTRA_LEN {
+        return Err(eyre!(
+            "extra metadata is {extra_len} bytes, at most {MAX_EXTRA_LEN} are accepted"
+        ));
+    }
+    let max_payload = decode_limits().max_memory_bytes();
+    if buf_len > max_payload {
+        return Err(eyre!(
+            "payload is {buf_len} bytes, over the decode memory limit of {max_payload} bytes"
+        ));
+    }
 
     // 2) name, extra(json), buf(bytes)
     let name_bytes = read_exact_len(stream, name_len as usize)?;
@@ -249,54 +361,417 @@ fn handle_client(stream: &mut TcpStream) -> Result<SocketAsset> {
 
     let extra_bytes = read_exact_len(stream, extra_len as usize)?;
     let extra = parse_extra(&extra_bytes)?;
+    if extra.compression == "raw" && buf_len != extra.nbytes {
+        return Err(eyre!("raw payload is {buf_len} bytes but nbytes is {}", extra.nbytes));
+    }
 
     let payload = read_exact_len(stream, buf_len as usize)?;
 
-    let mat = match extra.compression.as_str() {
+    let image = decode_payload(&extra, &payload)?;
+    Ok(SocketAsset::new(name, image).with_metadata(extra.metadata))
+}
+
+fn decode_payload(extra: &Extra, payload: &[u8]) -> Result<ImageData> {
+    match extra.compression.as_str() {
         "zlib" => {
-            validate_raw_extra(&extra)?;
-            let pixel_type = PixelType::from_protocol_code(extra.dtype)?;
+            let dtype = validate_raw_extra(extra)?;
             #[cfg(debug_assertions)]
             let _timer = crate::util::timer::ScopedTimer::new("Zlib decode");
 
-            let channels = extra.shape[2] as i32;
-
-            let mut z = ZlibDecoder::new(payload.as_slice());
+            let mut z = ZlibDecoder::new(payload);
             let mut raw = vec![0u8; extra.nbytes as usize];
-            z.read_exact(&mut raw)?;
-            ImageData::from_raw_bytes(&raw, extra.shape[1] as i32, extra.shape[0] as i32, channels, pixel_type)?
+            z.read_exact(&mut raw)
+                .map_err(|e| eyre!("zlib payload does not inflate to {} bytes: {e}", extra.nbytes))?;
+            decode_raw(extra, dtype, &raw)
         }
-        "png" => ImageData::from_bytes(&payload)?,
-        "exr" => ImageData::from_bytes(&payload)?,
-        "cv" => ImageData::from_bytes(&payload)?,
+        "png" | "exr" | "cv" => ImageData::from_bytes(payload),
         "raw" => {
-            validate_raw_extra(&extra)?;
-            let pixel_type = PixelType::from_protocol_code(extra.dtype)?;
-            let channels = extra.shape[2] as i32;
+            let dtype = validate_raw_extra(extra)?;
+            if payload.len() as u64 != extra.nbytes {
+                return Err(eyre!("raw payload is {} bytes but nbytes is {}", payload.len(), extra.nbytes));
+            }
+            decode_raw(extra, dtype, payload)
+        }
+        name => match YuvFormat::from_name(name) {
+            Some(format) => {
+                let frame = validate_yuv_extra(extra, format)?;
+                if payload.len() as u64 != extra.nbytes {
+                    return Err(eyre!(
+                        "{name} payload is {} bytes but nbytes is {}",
+                        payload.len(),
+                        extra.nbytes
+                    ));
+                }
+                ImageData::from_decoded(crate::model::decode_yuv(payload, frame)?)
+            }
+            None => Err(eyre!("unsupported compression: {}", extra.compression)),
+        },
+    }
+}
 
-            ImageData::from_raw_bytes(&payload, extra.shape[1] as i32, extra.shape[0] as i32, channels, pixel_type)?
+fn decode_raw(extra: &Extra, dtype: PayloadDtype, bytes: &[u8]) -> Result<ImageData> {
+    let [height, width, channels] = extra.shape.map(|v| v as i32);
+    let packed;
+    let bytes = if extra.row_stride.is_some() || extra.big_endian {
+        packed = pack_rows(extra, dtype, bytes);
+        &packed
+    } else {
+        bytes
+    };
+    match dtype {
+        PayloadDtype::Pixel(pixel_type) => {
+            ImageData::from_raw_bytes(bytes, width, height, channels, pixel_type, extra.channel_order)
         }
-        _ => {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("unsupported compression: {}", extra.compression),
-            )
-            .into())
+        PayloadDtype::U32 => ImageData::from_raw_u32_bytes(bytes, width, height, channels, extra.channel_order),
+    }
+}
+
+/// Drop row padding and convert samples to native byte order.
+fn pack_rows(extra: &Extra, dtype: PayloadDtype, bytes: &[u8]) -> Vec<u8> {
+    let [height, width, channels] = extra.shape.map(|v| v as usize);
+    let row_bytes = width * channels * dtype.bytes();
+    let stride = extra.row_stride.map_or(row_bytes, |stride| stride as usize);
+    let mut packed = Vec::with_capacity(row_bytes * height);
+    for row in 0..height {
+        packed.extend_from_slice(&bytes[row * stride..row * stride + row_bytes]);
+    }
+    crate::model::to_native_endian(&mut packed, dtype.bytes(), extra.big_endian);
+    packed
+}
+
+/// Check that `dtype`, `shape`, `row_stride` and `nbytes` describe the same buffer before allocating for it.
+/// A strided buffer may omit the padding after its last row.
+fn validate_raw_extra(extra: &Extra) -> Result<PayloadDtype> {
+    let dtype = PayloadDtype::from_code(extra.dtype)?;
+    let [height, width, channels] = extra.shape;
+    if height == 0 || width == 0 || height > i32::MAX as u32 || width > i32::MAX as u32 {
+        return Err(eyre!("invalid shape {height}x{width}x{channels}"));
+    }
+    if !(1..=4).contains(&channels) {
+        return Err(eyre!("unsupported channel count {channels}, expected 1 to 4"));
+    }
+    check_decode_limits(width as u64, height as u64, channels as u64)?;
+    let too_large = || eyre!("shape {height}x{width}x{channels} is too large");
+    let row_bytes = (width as u64)
+        .checked_mul(channels as u64)
+        .and_then(|n| n.checked_mul(dtype.bytes() as u64))
+        .ok_or_else(too_large)?;
+    let Some(stride) = extra.row_stride else {
+        let expected = row_bytes.checked_mul(height as u64).ok_or_else(too_large)?;
+        if extra.nbytes != expected {
+            return Err(eyre!(
+                "nbytes {} does not match shape {height}x{width}x{channels} of dtype {} ({expected} bytes)",
+                extra.nbytes,
+                extra.dtype
+            ));
         }
+        return Ok(dtype);
     };
 
-    Ok(SocketAsset::new(name, mat))
+    if stride < row_bytes {
+        return Err(eyre!("row_stride {stride} is shorter than a {row_bytes}-byte row"));
+    }
+    let padded = stride.checked_mul(height as u64).ok_or_else(too_large)?;
+    let trimmed = padded - (stride - row_bytes);
+    if extra.nbytes != padded && extra.nbytes != trimmed {
+        return Err(eyre!(
+            "nbytes {} does not match {height} rows of {row_bytes} bytes with row_stride {stride} ({padded} or {trimmed} bytes)",
+            extra.nbytes
+        ));
+    }
+
+    Ok(dtype)
 }
 
-fn validate_raw_extra(extra: &Extra) -> io::Result<()> {
-    if extra.nbytes == 0 || extra.dtype > 7 {
-        return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid extra metadata"));
+/// YUV payloads give the frame size as the first two shape entries and must be 8-bit; the channel entry is ignored.
+fn validate_yuv_extra(extra: &Extra, format: YuvFormat) -> Result<YuvFrame> {
+    if extra.dtype != 0 {
+        return Err(eyre!(
+            "{} payloads must be 8-bit (dtype 0), got dtype {}",
+            format.label(),
+            extra.dtype
+        ));
     }
+    let [height, width, _] = extra.shape;
+    if height == 0 || width == 0 || height > i32::MAX as u32 || width > i32::MAX as u32 {
+        return Err(eyre!("invalid {} frame size {width}x{height}", format.label()));
+    }
+    // Frames decode to RGB.
+    check_decode_limits(width as u64, height as u64, 3)?;
+    let frame = YuvFrame { width, height, format };
+    let expected = frame
+        .bytes()
+        .ok_or_else(|| eyre!("{} frame {width}x{height} is too large", format.label()))?;
+    if extra.nbytes != expected as u64 {
+        return Err(eyre!(
+            "nbytes {} does not match {} frame {width}x{height} ({expected} bytes)",
+            extra.nbytes,
+            format.label()
+        ));
+    }
+    Ok(frame)
+}
 
-    let [height, width, channels] = extra.shape;
-    if height == 0 || width == 0 || channels == 0 || channels > 4 {
-        return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid extra metadata"));
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        model::{Asset, Image},
+        util::concurrency::mpsc_with_notify,
+    };
+
+    #[test]
+    fn shutdown_releases_the_listening_port() {
+        let (tx, _rx, _nx) = mpsc_with_notify::<SocketAsset>();
+        let mut server = start_socket_listener("127.0.0.1:0", tx.clone(), Arc::new(SocketState::new())).unwrap();
+        let addr = server.address();
+        server.shutdown().unwrap();
+
+        let mut restarted = start_socket_listener(&addr.to_string(), tx, Arc::new(SocketState::new())).unwrap();
+        assert_eq!(restarted.address(), addr);
+        restarted.shutdown().unwrap();
+    }
+
+    fn extra_bytes(nbytes: u64, shape: [u32; 3], dtype: u32, compression: &str) -> Vec<u8> {
+        let mut bytes = nbytes.to_be_bytes().to_vec();
+        for value in shape.into_iter().chain([dtype]) {
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+        bytes.extend_from_slice(compression.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn parses_and_validates_extra_metadata() {
+        let extra = parse_extra(&extra_bytes(24, [1, 2, 3], 5, "raw\0\0")).unwrap();
+        assert_eq!((extra.nbytes, extra.shape, extra.dtype), (24, [1, 2, 3], 5));
+        assert_eq!(extra.compression, "raw");
+        assert_eq!(validate_raw_extra(&extra).unwrap(), PayloadDtype::Pixel(PixelType::F32));
+
+        assert!(parse_extra(&[0; 12]).is_err());
+        for (nbytes, shape, dtype) in [
+            (23, [1, 2, 3], 5),
+            (24, [0, 2, 3], 5),
+            (10, [1, 2, 5], 0),
+            (6, [1, 2, 3], 9),
+        ] {
+            let extra = parse_extra(&extra_bytes(nbytes, shape, dtype, "raw")).unwrap();
+            assert!(validate_raw_extra(&extra).is_err(), "{nbytes} {shape:?} {dtype}");
+        }
+        let extra = parse_extra(&extra_bytes(u64::MAX, [u32::MAX >> 1, u32::MAX >> 1, 4], 6, "raw")).unwrap();
+        assert!(validate_raw_extra(&extra).is_err());
+    }
+
+    #[test]
+    fn parses_optional_metadata_after_compression() {
+        let extra = parse_extra(&extra_bytes(
+            4,
+            [1, 1, 1],
+            5,
+            "raw\0{\"iteration\": 1200, \"camera\": \"left\", \"loss\": 0.25}\0\0",
+        ))
+        .unwrap();
+        assert_eq!(extra.compression, "raw");
+        assert_eq!(
+            extra.metadata,
+            [
+                ("camera".to_string(), "left".to_string()),
+                ("iteration".to_string(), "1200".to_string()),
+                ("loss".to_string(), "0.25".to_string()),
+            ]
+        );
+
+        assert!(parse_extra(&extra_bytes(4, [1, 1, 1], 5, "raw\0[1, 2]")).is_err());
+        assert!(parse_extra(&extra_bytes(4, [1, 1, 1], 5, "raw\0{")).is_err());
     }
 
-    Ok(())
+    /// The fixture is the extra block the Python client sends, checked by `python/tests/test_edolview.py`.
+    #[test]
+    fn parses_the_python_client_extra_block() {
+        let extra = parse_extra(include_bytes!("../../tests/fixtures/python_extra_metadata.bin")).unwrap();
+        assert_eq!((extra.nbytes, extra.shape, extra.dtype), (18, [2, 3, 3], 0));
+        assert_eq!(extra.compression, "raw");
+        assert_eq!(extra.channel_order, ChannelOrder::Bgr);
+        assert_eq!(
+            extra.metadata,
+            [
+                ("camera".to_string(), "left".to_string()),
+                ("iteration".to_string(), "1200".to_string()),
+            ]
+        );
+        assert_eq!(validate_raw_extra(&extra).unwrap(), PayloadDtype::Pixel(PixelType::U8));
+    }
+
+    #[test]
+    fn decodes_f16_and_u32_payloads() {
+        let halves = [0.5_f32, -2.0, 1.0].map(|v| half::f16::from_f32(v).to_bits());
+        let extra = parse_extra(&extra_bytes(6, [1, 3, 1], 7, "raw")).unwrap();
+        let image = decode_payload(&extra, bytemuck::cast_slice(&halves)).unwrap();
+        assert_eq!(image.pixels().unwrap(), [0.5, -2.0, 1.0]);
+
+        let words = [0_u32, u32::MAX];
+        let extra = parse_extra(&extra_bytes(8, [1, 1, 2], DTYPE_U32, "raw")).unwrap();
+        let image = decode_payload(&extra, bytemuck::cast_slice(&words)).unwrap();
+        let pixel = image.get_pixel_at(0, 0).unwrap();
+        assert_eq!((pixel[0], pixel[1]), (0.0, 1.0));
+
+        assert!(decode_payload(&extra, &[0; 4]).is_err());
+    }
+
+    #[test]
+    fn every_payload_kind_arrives_in_rgb_order() {
+        use std::io::Write as _;
+
+        // One orange pixel: red 255, green 128, blue 0.
+        let rgb = [255_u8, 128, 0];
+        let bgr = [0_u8, 128, 255];
+        let expected = vec![1.0, 128.0 / 255.0, 0.0];
+
+        let extra = parse_extra(&extra_bytes(3, [1, 1, 3], 0, "raw")).unwrap();
+        assert_eq!(extra.channel_order, ChannelOrder::Rgb);
+        assert_eq!(
+            decode_payload(&extra, &rgb).unwrap().get_pixel_at(0, 0).unwrap().to_vec(),
+            expected
+        );
+
+        let extra = parse_extra(&extra_bytes(3, [1, 1, 3], 0, "raw\0{\"channel_order\": \"bgr\"}")).unwrap();
+        assert!(extra.metadata.is_empty(), "channel_order is consumed, not shown");
+        assert_eq!(
+            decode_payload(&extra, &bgr).unwrap().get_pixel_at(0, 0).unwrap().to_vec(),
+            expected
+        );
+
+        let mut z = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::fast());
+        z.write_all(&bgr).unwrap();
+        let extra = parse_extra(&extra_bytes(3, [1, 1, 3], 0, "zlib\0{\"channel_order\": \"BGR\"}")).unwrap();
+        assert_eq!(
+            decode_payload(&extra, &z.finish().unwrap())
+                .unwrap()
+                .get_pixel_at(0, 0)
+                .unwrap()
+                .to_vec(),
+            expected
+        );
+
+        let mut png = Vec::new();
+        image::RgbImage::from_raw(1, 1, rgb.to_vec())
+            .unwrap()
+            .write_to(&mut io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let extra = parse_extra(&extra_bytes(3, [1, 1, 3], 0, "png")).unwrap();
+        assert_eq!(
+            decode_payload(&extra, &png).unwrap().get_pixel_at(0, 0).unwrap().to_vec(),
+            expected
+        );
+
+        assert!(parse_extra(&extra_bytes(3, [1, 1, 3], 0, "raw\0{\"channel_order\": \"rbg\"}")).is_err());
+    }
+
+    #[test]
+    fn unpacks_strided_big_endian_payloads() {
+        // Two rows of two big-endian u16 samples, each row padded to 6 bytes; the last padding is omitted.
+        let payload = [0x00, 0x01, 0xff, 0xff, 0xee, 0xee, 0x80, 0x00, 0x00, 0x00];
+        let header = |nbytes, stride: &str| {
+            let metadata = format!("raw\0{{\"byte_order\": \"big\", \"row_stride\": {stride}, \"note\": 1}}");
+            parse_extra(&extra_bytes(nbytes, [2, 2, 1], 2, &metadata)).unwrap()
+        };
+
+        let extra = header(10, "6");
+        assert_eq!(extra.metadata, [("note".to_string(), "1".to_string())]);
+        let image = decode_payload(&extra, &payload).unwrap();
+        assert_eq!(image.get_pixel_at(0, 0).unwrap()[0], 1.0 / 65535.0);
+        assert_eq!(image.get_pixel_at(1, 0).unwrap()[0], 1.0);
+        assert_eq!(image.get_pixel_at(0, 1).unwrap()[0], 32768.0 / 65535.0);
+
+        let mut padded = payload.to_vec();
+        padded.extend_from_slice(&[0xee, 0xee]);
+        assert!(decode_payload(&header(12, "6"), &padded).is_ok());
+
+        assert!(validate_raw_extra(&header(10, "3")).is_err());
+        assert!(validate_raw_extra(&header(11, "6")).is_err());
+        assert!(parse_extra(&extra_bytes(8, [2, 2, 1], 2, "raw\0{\"byte_order\": \"middle\"}")).is_err());
+        assert!(parse_extra(&extra_bytes(8, [2, 2, 1], 2, "raw\0{\"row_stride\": -4}")).is_err());
+    }
+
+    #[test]
+    fn decodes_yuv_payloads() {
+        // 2x2 gray frame in NV12: Y=126 with neutral chroma is mid gray.
+        let nv12 = [126_u8, 126, 126, 126, 128, 128];
+        let extra = parse_extra(&extra_bytes(6, [2, 2, 3], 0, "nv12")).unwrap();
+        let image = decode_payload(&extra, &nv12).unwrap();
+        assert_eq!((image.spec().width, image.spec().height, image.spec().channels), (2, 2, 3));
+        assert_eq!(image.get_pixel_at(1, 1).unwrap().to_vec(), vec![128.0 / 255.0; 3]);
+
+        assert!(decode_payload(&extra, &nv12[..5]).is_err());
+        for (nbytes, shape, dtype, name) in [
+            (6, [2, 2, 3], 5, "nv12"),
+            (4, [2, 2, 3], 0, "i420"),
+            (8, [0, 2, 3], 0, "yuyv"),
+        ] {
+            let extra = parse_extra(&extra_bytes(nbytes, shape, dtype, name)).unwrap();
+            assert!(
+                decode_payload(&extra, &vec![0; nbytes as usize]).is_err(),
+                "{name} {shape:?} {dtype}"
+            );
+        }
+    }
+
+    fn message(name: &[u8], extra: &[u8], buf_len: u64, payload: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for len in [name.len() as u64, extra.len() as u64, buf_len] {
+            bytes.extend_from_slice(&len.to_be_bytes());
+        }
+        [name, extra, payload]
+            .into_iter()
+            .for_each(|part| bytes.extend_from_slice(part));
+        bytes
+    }
+
+    #[test]
+    fn rejects_wire_lengths_before_allocating() {
+        let extra = extra_bytes(4, [1, 1, 4], 0, "raw");
+        let asset = handle_client(&mut &message(b"ok", &extra, 4, &[1, 2, 3, 4])[..]).unwrap();
+        assert_eq!(asset.name(), "ok");
+
+        // Only the length prefixes are sent, so reading any of these bodies would fail with an EOF instead.
+        let mut huge_name = message(b"", &extra, 4, &[]);
+        huge_name[..8].copy_from_slice(&u64::MAX.to_be_bytes());
+        let mut huge_extra = message(b"", &extra, 4, &[]);
+        huge_extra[8..16].copy_from_slice(&u64::MAX.to_be_bytes());
+        for (bytes, expected) in [
+            (huge_name, "at most"),
+            (huge_extra, "at most"),
+            (message(b"", &extra, u64::MAX, &[]), "decode memory limit"),
+            (message(b"", &extra, 5, &[]), "nbytes is 4"),
+        ] {
+            let err = handle_client(&mut &bytes[..]).err().unwrap();
+            assert!(err.to_string().contains(expected), "{err}");
+        }
+    }
+
+    #[test]
+    fn rejects_consistent_headers_beyond_the_decode_limits() {
+        // 65536x65536 RGBA floats fit the per-side limit but not the memory limit; a side of 2^21 exceeds the
+        // per-side limit. The payloads are empty, so decoding has to fail before sizing a buffer from nbytes.
+        let side = 1 << 16;
+        let nbytes = side * side * 4 * 4;
+        for compression in ["raw", "zlib"] {
+            let extra = parse_extra(&extra_bytes(nbytes, [side as u32, side as u32, 4], 5, compression)).unwrap();
+            let err = decode_payload(&extra, &[]).err().unwrap();
+            assert!(err.to_string().contains("decode memory limit"), "{compression}: {err}");
+        }
+        let extra = parse_extra(&extra_bytes(1 << 21, [1 << 21, 1, 1], 0, "zlib")).unwrap();
+        let err = decode_payload(&extra, &[]).err().unwrap();
+        assert!(err.to_string().contains("pixels per side"), "{err}");
+
+        let frame = YuvFrame {
+            width: 1 << 21,
+            height: 2,
+            format: YuvFormat::from_name("nv12").unwrap(),
+        };
+        let nbytes = frame.bytes().unwrap() as u64;
+        let extra = parse_extra(&extra_bytes(nbytes, [2, 1 << 21, 3], 0, "nv12")).unwrap();
+        let err = decode_payload(&extra, &[]).err().unwrap();
+        assert!(err.to_string().contains("pixels per side"), "{err}");
+    }
 }
@@ -0,0 +1,230 @@
+//! Numbers from the sidebar statistics and histogram gathered into one CSV or JSON document for reports.
+
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::{
+    model::{Image, ImageData, Recti},
+    util::{math_ext::vec2i, series::channel_label},
+};
+
+#[derive(Debug, Serialize)]
+pub struct ChannelStatistics {
+    pub channel: String,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub std: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImageStatistics {
+    /// `primary` or `secondary`.
+    pub role: &'static str,
+    pub name: String,
+    pub channels: Vec<ChannelStatistics>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Metric {
+    pub name: &'static str,
+    pub value: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatisticsReport {
+    /// Region the statistics cover, as `[x, y, width, height]`.
+    pub region: [i32; 4],
+    pub images: Vec<ImageStatistics>,
+    pub metrics: Vec<Metric>,
+    /// Histogram bin counts per channel, as shown in the sidebar.
+    pub histogram: Vec<Vec<f32>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatisticsFormat {
+    Csv,
+    Json,
+}
+
+impl StatisticsFormat {
+    pub const ALL: [Self; 2] = [Self::Csv, Self::Json];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Csv => "CSV",
+            Self::Json => "JSON",
+        }
+    }
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Csv => "statistics.csv",
+            Self::Json => "statistics.json",
+        }
+    }
+}
+
+impl ImageStatistics {
+    /// Per-channel statistics of `image` inside `rect`, or the whole image when `rect` is empty, in the stored
+    /// value range shown by the sidebar. `None` for GPU-derived images without CPU pixels.
+    pub fn compute(role: &'static str, name: &str, image: &ImageData, rect: Recti) -> Option<Self> {
+        let spec = image.spec();
+        let data = image.data()?;
+        let rect = statistics_region(rect, spec.width, spec.height);
+        let channels = spec.channels.max(0) as usize;
+        let scale = spec.dtype.alpha();
+
+        let mut sums = vec![(f64::INFINITY, f64::NEG_INFINITY, 0.0, 0.0); channels];
+        for y in rect.min.y..rect.max.y {
+            let row = (y as usize * spec.width as usize + rect.min.x as usize) * channels;
+            let row = &data[row..row + rect.width() as usize * channels];
+            for pixel in row.chunks_exact(channels) {
+                for (sum, &v) in sums.iter_mut().zip(pixel) {
+                    let v = v as f64;
+                    sum.0 = sum.0.min(v);
+                    sum.1 = sum.1.max(v);
+                    sum.2 += v;
+                    sum.3 += v * v;
+                }
+            }
+        }
+        let count = (rect.width() as f64 * rect.height() as f64).max(1.0);
+        let channels = sums
+            .into_iter()
+            .enumerate()
+            .map(|(index, (min, max, sum, sum_sq))| {
+                let mean = sum / count;
+                ChannelStatistics {
+                    channel: channel_label(index, channels),
+                    min: min * scale,
+                    max: max * scale,
+                    mean: mean * scale,
+                    std: (sum_sq / count - mean * mean).max(0.0).sqrt() * scale,
+                }
+            })
+            .collect();
+        Some(Self {
+            role,
+            name: name.to_string(),
+            channels,
+        })
+    }
+}
+
+impl StatisticsReport {
+    pub fn encode(&self, format: StatisticsFormat) -> String {
+        match format {
+            StatisticsFormat::Csv => self.to_csv(),
+            StatisticsFormat::Json => self.to_json(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Tables for the statistics, metrics and histogram, separated by blank lines.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        let [x, y, width, height] = self.region;
+        let _ = writeln!(csv, "x,y,width,height\n{x},{y},{width},{height}");
+
+        if !self.images.is_empty() {
+            csv.push_str("\nimage,name,channel,min,max,mean,std\n");
+            for image in &self.images {
+                for c in &image.channels {
+                    let _ = writeln!(
+                        csv,
+                        "{},{},{},{},{},{},{}",
+                        image.role,
+                        csv_field(&image.name),
+                        c.channel,
+                        c.min,
+                        c.max,
+                        c.mean,
+                        c.std
+                    );
+                }
+            }
+        }
+
+        if !self.metrics.is_empty() {
+            csv.push_str("\nmetric,value\n");
+            for metric in &self.metrics {
+                let _ = writeln!(csv, "{},{}", metric.name, metric.value);
+            }
+        }
+
+        if let Some(bins) = self.histogram.first().map(Vec::len) {
+            let channels = self.histogram.len();
+            csv.push_str("\nbin");
+            for index in 0..channels {
+                let _ = write!(csv, ",{}", channel_label(index, channels));
+            }
+            csv.push('\n');
+            for bin in 0..bins {
+                let _ = write!(csv, "{bin}");
+                for channel in &self.histogram {
+                    let _ = write!(csv, ",{}", channel.get(bin).copied().unwrap_or_default());
+                }
+                csv.push('\n');
+            }
+        }
+        csv
+    }
+}
+
+/// `rect` clipped to the image, or the whole image when it is empty.
+fn statistics_region(rect: Recti, width: i32, height: i32) -> Recti {
+    let image = Recti::from_min_size(vec2i(0, 0), vec2i(width, height));
+    if rect.empty() {
+        image
+    } else {
+        rect.intersect(image)
+    }
+}
+
+/// Quote a CSV field that contains separators or quotes.
+pub(super) fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ImageSpec, PixelType};
+
+    #[test]
+    fn reports_channel_statistics_as_csv_and_json() {
+        let spec = ImageSpec::new(2, 2, 2, PixelType::F32);
+        let image = ImageData::from_f32(spec, vec![1.0, 0.0, 3.0, 0.0, 5.0, 2.0, 7.0, 2.0]).unwrap();
+        let left_column = Recti::from_min_size(vec2i(0, 0), vec2i(1, 2));
+        let stats = ImageStatistics::compute("primary", "a,b.exr", &image, left_column).unwrap();
+        assert_eq!(stats.channels[0].mean, 3.0);
+        assert_eq!(stats.channels[0].std, 2.0);
+        assert_eq!((stats.channels[1].min, stats.channels[1].max), (0.0, 2.0));
+
+        let report = StatisticsReport {
+            region: [0, 0, 1, 2],
+            images: vec![stats],
+            metrics: vec![Metric {
+                name: "psnr",
+                value: 30.5,
+            }],
+            histogram: vec![vec![1.0, 2.0], vec![3.0, 4.0]],
+        };
+        let csv = report.to_csv();
+        assert!(csv.contains("primary,\"a,b.exr\",R,1,5,3,2\n"));
+        assert!(csv.contains("\nmetric,value\npsnr,30.5\n"));
+        assert!(csv.ends_with("\nbin,R,G\n0,1,3\n1,2,4\n"));
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["images"][0]["channels"][1]["channel"], "G");
+        assert_eq!(json["metrics"][0]["value"], 30.5);
+    }
+}
@@ -2,11 +2,17 @@
 
 use std::{
     collections::HashSet,
-    sync::mpsc::{Receiver, Sender, TryRecvError},
+    sync::{
+        mpsc::{Receiver, Sender, TryRecvError},
+        Arc,
+    },
     thread,
 };
 
-use super::{gpu_compute, Image, ImageData, Recti};
+use super::{gpu_compute, Image, ImageData, MaskedStatistics, Recti, SelectionMask};
+use crate::util::math_ext::vec2i;
+
+const HISTOGRAM_BINS: usize = 256;
 
 #[derive(PartialEq, Eq, Hash, Clone)]
 pub enum StatisticsType {
@@ -18,6 +24,9 @@ pub enum StatisticsType {
     SSIM,
     MSSSIM,
     FSIM,
+    Masked,
+    AlphaCoverage,
+    Histogram,
 }
 
 impl StatisticsType {
@@ -31,6 +40,9 @@ impl StatisticsType {
             StatisticsType::SSIM => 1,
             StatisticsType::MSSSIM => 1,
             StatisticsType::FSIM => 1,
+            StatisticsType::Masked => 1,
+            StatisticsType::AlphaCoverage => 3,
+            StatisticsType::Histogram => 1,
         }
     }
 }
@@ -46,6 +58,9 @@ impl std::fmt::Display for StatisticsType {
             StatisticsType::SSIM => "SSIM",
             StatisticsType::MSSSIM => "MSSSIM",
             StatisticsType::FSIM => "FSIM",
+            StatisticsType::Masked => "Masked",
+            StatisticsType::AlphaCoverage => "Alpha coverage",
+            StatisticsType::Histogram => "Histogram",
         };
         write!(f, "{s}")
     }
@@ -77,6 +92,42 @@ pub struct ValueWithScope<T> {
     pub scope: Option<StatisticsScope>,
 }
 
+/// Per-channel histogram from the flat result of [`StatisticsWorker::run_histogram`]; empty for the NaN placeholder
+/// of a failed computation.
+pub fn unpack_histogram(values: &[f64]) -> Vec<Vec<f32>> {
+    if !values.len().is_multiple_of(HISTOGRAM_BINS) || values.iter().any(|v| v.is_nan()) {
+        return Vec::new();
+    }
+    values
+        .chunks_exact(HISTOGRAM_BINS)
+        .map(|bins| bins.iter().map(|&v| v as f32).collect())
+        .collect()
+}
+
+/// Optional metrics of the statistics panel. Min/max, and PSNR/RMSE of a comparison, are always computed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StatisticsMetrics {
+    pub std: bool,
+    /// Shown from the PSNR/RMSE result, so it costs nothing extra.
+    pub mse: bool,
+    pub mae: bool,
+    /// SSIM filters every pixel with an 11×11 window, which is slow on large regions.
+    pub ssim: bool,
+    pub ms_ssim: bool,
+}
+
+impl Default for StatisticsMetrics {
+    fn default() -> Self {
+        Self {
+            std: true,
+            mse: true,
+            mae: true,
+            ssim: false,
+            ms_ssim: false,
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct MinMax {
     pub min: Vec<f64>,
@@ -89,11 +140,77 @@ pub struct PSNRRMSE {
     pub rmse: f64,
 }
 
+/// Fractions of the pixels in a region that are fully transparent, partially transparent and opaque.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AlphaCoverage {
+    pub transparent: f64,
+    pub partial: f64,
+    pub opaque: f64,
+}
+
+impl AlphaCoverage {
+    /// Coverage of the alpha channel of `image` inside `rect`, or the whole image when `rect` is empty.
+    pub fn compute(image: &ImageData, rect: Recti) -> color_eyre::Result<Self> {
+        let spec = image.spec();
+        let alpha = spec
+            .alpha_channel()
+            .ok_or_else(|| color_eyre::eyre::eyre!("Image has no alpha channel"))?;
+        let data = image
+            .data()
+            .ok_or_else(|| color_eyre::eyre::eyre!("CPU pixel data is unavailable for this image"))?;
+        let bounds = Recti::from_min_size(vec2i(0, 0), vec2i(spec.width, spec.height));
+        let rect = if rect.empty() { bounds } else { rect.intersect(bounds) };
+        let channels = spec.channels as usize;
+        let (mut transparent, mut opaque) = (0usize, 0usize);
+        for y in rect.min.y..rect.max.y {
+            let row = (y as usize * spec.width as usize + rect.min.x as usize) * channels;
+            for pixel in data[row..row + rect.width().max(0) as usize * channels].chunks_exact(channels) {
+                if pixel[alpha] <= 0.0 {
+                    transparent += 1;
+                } else if pixel[alpha] >= 1.0 {
+                    opaque += 1;
+                }
+            }
+        }
+        let count = (rect.width().max(0) as f64 * rect.height().max(0) as f64).max(1.0);
+        let (transparent, opaque) = (transparent as f64 / count, opaque as f64 / count);
+        Ok(Self {
+            transparent,
+            partial: (1.0 - transparent - opaque).max(0.0),
+            opaque,
+        })
+    }
+
+    pub fn pack(&self) -> Vec<f64> {
+        vec![self.transparent, self.partial, self.opaque]
+    }
+
+    /// `None` for the NaN placeholder of a failed computation.
+    pub fn unpack(values: &[f64]) -> Option<Self> {
+        match values {
+            [transparent, partial, opaque] if !transparent.is_nan() => Some(Self {
+                transparent: *transparent,
+                partial: *partial,
+                opaque: *opaque,
+            }),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct Statistics {
     pub psnr_rmse: ValueWithScope<PSNRRMSE>,
     pub ssim: ValueWithScope<f64>,
+    pub ms_ssim: ValueWithScope<f64>,
+    pub mae: ValueWithScope<f64>,
     pub min_max: ValueWithScope<MinMax>,
+    /// Standard deviation per channel.
+    pub std: ValueWithScope<Vec<f64>>,
+    pub masked: ValueWithScope<Option<MaskedStatistics>>,
+    pub alpha_coverage: ValueWithScope<Option<AlphaCoverage>>,
+    /// Histogram of the marquee region; the whole-image histogram is cached on the image instead.
+    pub histogram: ValueWithScope<Vec<Vec<f32>>>,
 }
 
 pub struct StatisticsWorker {
@@ -139,6 +256,77 @@ impl StatisticsWorker {
         });
     }
 
+    pub fn run_masked(&mut self, image: ImageData, mask: Arc<SelectionMask>, scale: f64, scope: StatisticsScope) {
+        self.run(StatisticsType::Masked, scope, move |_| {
+            #[cfg(debug_assertions)]
+            let _timer = crate::util::timer::ScopedTimer::new("Statistics::Masked");
+
+            mask.statistics(&image, scale).map(|stats| stats.pack())
+        });
+    }
+
+    pub fn run_std(&mut self, image: ImageData, scale: f64, scope: StatisticsScope) {
+        self.run(StatisticsType::Std, scope, move |scope| {
+            #[cfg(debug_assertions)]
+            let _timer = crate::util::timer::ScopedTimer::new("Statistics::Std");
+
+            let texture = image.gpu_texture()?;
+            gpu_compute()?.std(&texture, scope.rect, scale)
+        });
+    }
+
+    pub fn run_mae(&mut self, image1: ImageData, image2: ImageData, scale: f64, scope: StatisticsScope) {
+        if image1.spec().width <= 0 || image2.spec().width <= 0 {
+            return;
+        }
+
+        self.run(StatisticsType::MAE, scope, move |scope| {
+            #[cfg(debug_assertions)]
+            let _timer = crate::util::timer::ScopedTimer::new("Statistics::MAE");
+
+            let texture1 = image1.gpu_texture()?;
+            let texture2 = image2.gpu_texture()?;
+            Ok::<Vec<f64>, color_eyre::Report>(vec![gpu_compute()?.mae(&texture1, &texture2, scope.rect, scale)?])
+        });
+    }
+
+    pub fn run_ms_ssim(&mut self, image1: ImageData, image2: ImageData, scope: StatisticsScope) {
+        if image1.spec().width <= 0 || image2.spec().width <= 0 {
+            return;
+        }
+
+        self.run(StatisticsType::MSSSIM, scope, move |scope| {
+            #[cfg(debug_assertions)]
+            let _timer = crate::util::timer::ScopedTimer::new("Statistics::MSSSIM");
+
+            let texture1 = image1.gpu_texture()?;
+            let texture2 = image2.gpu_texture()?;
+            Ok::<Vec<f64>, color_eyre::Report>(vec![gpu_compute()?.ms_ssim(&texture1, &texture2, scope.rect)?])
+        });
+    }
+
+    /// Whether a computation of `stat_type` is running or waiting to run again.
+    pub fn is_busy(&self, stat_type: &StatisticsType) -> bool {
+        self.processing.contains(stat_type) || self.pending.contains(stat_type)
+    }
+
+    pub fn run_histogram(&mut self, image: ImageData, scope: StatisticsScope) {
+        self.run(StatisticsType::Histogram, scope, move |scope| {
+            #[cfg(debug_assertions)]
+            let _timer = crate::util::timer::ScopedTimer::new("Statistics::Histogram");
+
+            let texture = image.gpu_texture()?;
+            let histogram = gpu_compute()?.histogram(&texture, scope.rect)?;
+            Ok::<Vec<f64>, color_eyre::Report>(histogram.into_iter().flatten().map(f64::from).collect())
+        });
+    }
+
+    pub fn run_alpha_coverage(&mut self, image: ImageData, scope: StatisticsScope) {
+        self.run(StatisticsType::AlphaCoverage, scope, move |scope| {
+            AlphaCoverage::compute(&image, scope.rect).map(|coverage| coverage.pack())
+        });
+    }
+
     pub fn run_psnr(
         &mut self,
         image1: ImageData,
@@ -252,3 +440,37 @@ impl StatisticsWorker {
         invalidated
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ImageSpec, PixelType};
+
+    #[test]
+    fn alpha_coverage_counts_transparent_partial_and_opaque_pixels() {
+        let spec = ImageSpec {
+            gray_alpha: true,
+            ..ImageSpec::new(4, 1, 2, PixelType::U8)
+        };
+        let image = ImageData::from_f32(spec, vec![0.5, 0.0, 0.5, 0.25, 0.5, 1.0, 0.5, 1.0]).unwrap();
+        let coverage = AlphaCoverage::compute(&image, Recti::ZERO).unwrap();
+        assert_eq!(coverage, AlphaCoverage::unpack(&coverage.pack()).unwrap());
+        assert_eq!((coverage.transparent, coverage.partial, coverage.opaque), (0.25, 0.25, 0.5));
+
+        let left = Recti::from_min_size(vec2i(0, 0), vec2i(2, 1));
+        assert_eq!(AlphaCoverage::compute(&image, left).unwrap().opaque, 0.0);
+        assert!(AlphaCoverage::unpack(&[f64::NAN; 3]).is_none());
+
+        let rgb = ImageData::from_f32(ImageSpec::new(1, 1, 3, PixelType::U8), vec![0.0; 3]).unwrap();
+        assert!(AlphaCoverage::compute(&rgb, Recti::ZERO).is_err());
+    }
+
+    #[test]
+    fn histogram_unpacks_into_channels_of_256_bins() {
+        let values: Vec<f64> = (0..512).map(|bin| (bin / 256) as f64).collect();
+        let histogram = unpack_histogram(&values);
+        assert_eq!(histogram.len(), 2);
+        assert!(histogram[0].iter().all(|&v| v == 0.0) && histogram[1].iter().all(|&v| v == 1.0));
+        assert!(unpack_histogram(&[f64::NAN]).is_empty());
+    }
+}
@@ -0,0 +1,95 @@
+//! Temporary files handed to other applications by copying a file to the clipboard or dragging it out of the window.
+//! Every export gets a directory of its own, so a later export never replaces a file that an earlier paste or drop
+//! may still be reading. The directories are removed when the viewer exits.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use color_eyre::eyre::Result;
+
+/// Exports left behind by a viewer that did not exit cleanly are removed once they are this old.
+const STALE_EXPORT_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+static NEXT_EXPORT: AtomicU64 = AtomicU64::new(0);
+static EXPORT_DIRS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+fn exports_root() -> PathBuf {
+    std::env::temp_dir().join("edolview").join("exports")
+}
+
+/// Path for a new temporary export named `file_name`, in a directory that no other export uses.
+pub fn temp_export_path(file_name: &str) -> Result<PathBuf> {
+    let index = NEXT_EXPORT.fetch_add(1, Ordering::Relaxed);
+    let dir = exports_root().join(format!("{}-{index}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    if let Ok(mut dirs) = EXPORT_DIRS.lock() {
+        dirs.push(dir.clone());
+    }
+    Ok(dir.join(file_name))
+}
+
+/// Remove one export made by [`temp_export_path`] that was never handed to another application.
+pub fn discard_temp_export(path: &Path) {
+    let Some(dir) = path.parent() else {
+        return;
+    };
+    if let Ok(mut dirs) = EXPORT_DIRS.lock() {
+        dirs.retain(|existing| existing != dir);
+    }
+    let _ = fs::remove_dir_all(dir);
+}
+
+/// Remove this session's exports, and those of earlier sessions older than [`STALE_EXPORT_AGE`]. Files pasted from
+/// the clipboard after this are gone, so call it only when the viewer exits.
+pub fn remove_temp_exports() {
+    let dirs = EXPORT_DIRS
+        .lock()
+        .map(|mut dirs| std::mem::take(&mut *dirs))
+        .unwrap_or_default();
+    for dir in dirs {
+        let _ = fs::remove_dir_all(dir);
+    }
+    let Ok(entries) = fs::read_dir(exports_root()) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let stale = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age >= STALE_EXPORT_AGE);
+        if stale {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_export_gets_its_own_file_until_discarded() {
+        let first = temp_export_path("image.png").unwrap();
+        let second = temp_export_path("image.png").unwrap();
+        assert_ne!(first, second);
+        assert_eq!(first.file_name(), second.file_name());
+
+        fs::write(&first, b"first").unwrap();
+        fs::write(&second, b"second").unwrap();
+        assert_eq!(fs::read(&first).unwrap(), b"first");
+
+        discard_temp_export(&first);
+        discard_temp_export(&second);
+        assert!(!first.exists() && !second.exists());
+        assert!(!first.parent().unwrap().exists());
+    }
+}
@@ -0,0 +1,237 @@
+//! A statistic of every frame of a sequence (the files of the current folder or the socket history), for plotting
+//! how it evolves over frames or training steps.
+
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use color_eyre::eyre::{eyre, Result};
+
+use super::{Image, ImageData, Recti};
+use crate::util::math_ext::{vec2i, Vec2i};
+
+/// PSNR of identical frames, which would be infinite and flatten the rest of the plot.
+pub const TIMELINE_MAX_PSNR: f64 = 100.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimelineSource {
+    #[default]
+    Directory,
+    SocketHistory,
+}
+
+impl TimelineSource {
+    pub const ALL: [Self; 2] = [Self::Directory, Self::SocketHistory];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Directory => "Files in folder",
+            Self::SocketHistory => "Socket history",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimelineStatistic {
+    #[default]
+    PixelValue,
+    RoiMean,
+    Psnr,
+}
+
+impl TimelineStatistic {
+    pub const ALL: [Self; 3] = [Self::PixelValue, Self::RoiMean, Self::Psnr];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::PixelValue => "Pixel value",
+            Self::RoiMean => "Selection mean",
+            Self::Psnr => "PSNR vs reference",
+        }
+    }
+}
+
+pub enum TimelineFrame {
+    File(PathBuf),
+    Loaded(ImageData),
+}
+
+pub struct TimelineRequest {
+    pub frames: Vec<TimelineFrame>,
+    pub statistic: TimelineStatistic,
+    pub pixel: Vec2i,
+    /// Region of the selection mean and PSNR; empty for the whole frame.
+    pub rect: Recti,
+    pub reference: Option<ImageData>,
+}
+
+pub struct Timeline {
+    /// One series per channel, or a single series for PSNR, with one value per used frame.
+    pub series: Vec<Vec<f64>>,
+    /// Frames left out because they could not be loaded or do not match the pixel, region or reference.
+    pub skipped: usize,
+    /// Scale from normalized values to the stored range, for the plot labels.
+    pub value_scale: f64,
+}
+
+/// Compute `request` frame by frame, reporting `(done, total)` after each frame. Stops early with an error once
+/// `cancel` is set.
+pub fn compute_timeline(
+    request: TimelineRequest,
+    progress: impl Fn(usize, usize),
+    cancel: &AtomicBool,
+) -> Result<Timeline> {
+    if request.statistic == TimelineStatistic::Psnr && request.reference.is_none() {
+        return Err(eyre!("Choose a reference image for PSNR"));
+    }
+    let total = request.frames.len();
+    let mut series: Vec<Vec<f64>> = Vec::new();
+    let mut skipped = 0;
+    let mut value_scale = None;
+    for (index, frame) in request.frames.into_iter().enumerate() {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(eyre!("Cancelled"));
+        }
+        let image = match frame {
+            TimelineFrame::File(path) => ImageData::load_from_path(&path).ok(),
+            TimelineFrame::Loaded(image) => Some(image),
+        };
+        let values = image.as_ref().and_then(|image| {
+            frame_statistic(
+                image,
+                request.statistic,
+                request.pixel,
+                request.rect,
+                request.reference.as_ref(),
+            )
+        });
+        match values {
+            // Channel counts can change within a sequence; only frames matching the first one are kept.
+            Some(values) if series.is_empty() || series.len() == values.len() => {
+                if series.is_empty() {
+                    series = vec![Vec::with_capacity(total); values.len()];
+                }
+                for (channel, value) in values.into_iter().enumerate() {
+                    series[channel].push(value);
+                }
+                if let Some(image) = image.filter(|_| request.statistic != TimelineStatistic::Psnr) {
+                    value_scale.get_or_insert(image.spec().dtype.alpha());
+                }
+            }
+            _ => skipped += 1,
+        }
+        progress(index + 1, total);
+    }
+    if series.is_empty() {
+        return Err(eyre!("No frame could be measured"));
+    }
+    Ok(Timeline {
+        series,
+        skipped,
+        value_scale: value_scale.unwrap_or(1.0),
+    })
+}
+
+/// The statistic of one frame: normalized channel values for the pixel and the mean, or the PSNR in dB of the
+/// normalized values against `reference`. `None` when the frame does not cover the pixel or region, or does not
+/// match the reference.
+pub fn frame_statistic(
+    image: &ImageData,
+    statistic: TimelineStatistic,
+    pixel: Vec2i,
+    rect: Recti,
+    reference: Option<&ImageData>,
+) -> Option<Vec<f64>> {
+    let spec = image.spec();
+    let bounds = Recti::from_min_size(vec2i(0, 0), vec2i(spec.width, spec.height));
+    let rect = if rect.empty() { bounds } else { rect };
+    if statistic != TimelineStatistic::PixelValue && rect.intersect(bounds) != rect {
+        return None;
+    }
+    match statistic {
+        TimelineStatistic::PixelValue => {
+            let values = image.get_pixel_at(pixel.x, pixel.y).ok()?;
+            Some(values.iter().map(|&value| value as f64).collect())
+        }
+        TimelineStatistic::RoiMean => {
+            let channels = spec.channels as usize;
+            let mut sums = vec![0.0; channels];
+            for_each_pixel(image, rect, |_, _, values| {
+                sums.iter_mut().zip(values).for_each(|(sum, &value)| *sum += value as f64);
+            })?;
+            let count = rect.width() as f64 * rect.height() as f64;
+            Some(sums.into_iter().map(|sum| sum / count).collect())
+        }
+        TimelineStatistic::Psnr => {
+            let reference = reference?;
+            let reference_spec = reference.spec();
+            if (reference_spec.width, reference_spec.height, reference_spec.channels)
+                != (spec.width, spec.height, spec.channels)
+            {
+                return None;
+            }
+            let mut squared_error = 0.0;
+            for_each_pixel(image, rect, |x, y, values| {
+                if let Ok(expected) = reference.get_pixel_at(x, y) {
+                    for (value, expected) in values.iter().zip(expected.iter()) {
+                        squared_error += (*value as f64 - *expected as f64).powi(2);
+                    }
+                }
+            })?;
+            let mse = squared_error / (rect.width() as f64 * rect.height() as f64 * spec.channels as f64);
+            Some(vec![(10.0 * (1.0 / mse).log10()).min(TIMELINE_MAX_PSNR)])
+        }
+    }
+}
+
+/// Call `f` with the position and normalized channel values of every pixel in `rect`.
+fn for_each_pixel(image: &ImageData, rect: Recti, mut f: impl FnMut(i32, i32, &[f32])) -> Option<()> {
+    for y in rect.min.y..rect.max.y {
+        for x in rect.min.x..rect.max.x {
+            f(x, y, &image.get_pixel_at(x, y).ok()?);
+        }
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ImageSpec, PixelType};
+
+    #[test]
+    fn measures_every_frame_and_skips_mismatches() {
+        let frame =
+            |width, data: Vec<f32>| ImageData::from_f32(ImageSpec::new(width, 1, 1, PixelType::F32), data).unwrap();
+        let request = |statistic, reference: Option<ImageData>| TimelineRequest {
+            frames: vec![
+                TimelineFrame::Loaded(frame(2, vec![0.0, 0.5])),
+                TimelineFrame::Loaded(frame(2, vec![0.25, 1.0])),
+                TimelineFrame::Loaded(frame(1, vec![0.5])),
+            ],
+            statistic,
+            pixel: vec2i(1, 0),
+            rect: Recti::from_min_size(vec2i(0, 0), vec2i(2, 1)),
+            reference,
+        };
+        let cancel = AtomicBool::new(false);
+
+        let pixel = compute_timeline(request(TimelineStatistic::PixelValue, None), |_, _| {}, &cancel).unwrap();
+        assert_eq!(pixel.series, [vec![0.5, 1.0]]);
+        assert_eq!(pixel.skipped, 1);
+
+        let mean = compute_timeline(request(TimelineStatistic::RoiMean, None), |_, _| {}, &cancel).unwrap();
+        assert_eq!(mean.series, [vec![0.25, 0.625]]);
+
+        assert!(compute_timeline(request(TimelineStatistic::Psnr, None), |_, _| {}, &cancel).is_err());
+        let reference = frame(2, vec![0.0, 0.5]);
+        let psnr = compute_timeline(request(TimelineStatistic::Psnr, Some(reference)), |_, _| {}, &cancel).unwrap();
+        assert_eq!(psnr.series[0][0], TIMELINE_MAX_PSNR);
+        // MSE of (0.25² + 0.5²) / 2 = 0.15625.
+        assert!((psnr.series[0][1] - 10.0 * (1.0f64 / 0.15625).log10()).abs() < 1e-9);
+
+        cancel.store(true, Ordering::Relaxed);
+        assert!(compute_timeline(request(TimelineStatistic::PixelValue, None), |_, _| {}, &cancel).is_err());
+    }
+}
@@ -0,0 +1,376 @@
+//! Boxes, polygons and keypoints loaded from a COCO-style JSON or a CSV file and drawn over the images they
+//! belong to, for inspecting the output of detection and segmentation models.
+
+use std::{collections::HashMap, path::Path};
+
+use color_eyre::eyre::{eyre, Result};
+use eframe::egui::{pos2, Pos2, Rect};
+use serde::Deserialize;
+use serde_json::Value;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OverlayShape {
+    /// Bounding box in image pixels.
+    Box(Rect),
+    Polygon(Vec<Pos2>),
+    Points(Vec<Pos2>),
+}
+
+impl OverlayShape {
+    fn bounds(&self) -> Rect {
+        match self {
+            Self::Box(rect) => *rect,
+            Self::Polygon(points) | Self::Points(points) => Rect::from_points(points),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OverlayObject {
+    /// Index into [`VectorOverlay::classes`].
+    pub class: usize,
+    /// Detection confidence, when the file has one.
+    pub score: Option<f32>,
+    pub shapes: Vec<OverlayShape>,
+}
+
+impl OverlayObject {
+    /// Top-left corner of the object, where its label goes.
+    pub fn anchor(&self) -> Option<Pos2> {
+        self.shapes
+            .iter()
+            .map(OverlayShape::bounds)
+            .reduce(|a, b| a.union(b))
+            .map(|rect| rect.min)
+    }
+}
+
+pub struct VectorOverlay {
+    /// File name of the overlay.
+    pub name: String,
+    pub classes: Vec<String>,
+    /// Whether each class is drawn.
+    pub visible: Vec<bool>,
+    /// Objects scoring below this are hidden; objects without a score are always shown.
+    pub min_score: f32,
+    /// Objects by image file name, or by image id when the file has no names.
+    by_image: HashMap<String, Vec<OverlayObject>>,
+    /// Objects without an image, drawn over every image.
+    shared: Vec<OverlayObject>,
+}
+
+impl VectorOverlay {
+    /// Load a `.csv` file, or a COCO-style `.json` dataset or result list.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| eyre!("Failed to read overlay '{}': {e}", path.display()))?;
+        let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let is_csv = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let overlay = if is_csv {
+            Self::parse_csv(&text, name)
+        } else {
+            Self::parse_coco(&text, name)
+        };
+        overlay.map_err(|e| eyre!("Invalid overlay '{}': {e}", path.display()))
+    }
+
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            classes: Vec::new(),
+            visible: Vec::new(),
+            min_score: 0.0,
+            by_image: HashMap::new(),
+            shared: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, image: Option<String>, object: OverlayObject) {
+        match image {
+            Some(image) => self.by_image.entry(image).or_default().push(object),
+            None => self.shared.push(object),
+        }
+    }
+
+    /// Parse a COCO dataset (`images`, `annotations`, `categories`) or a COCO result list of annotations.
+    /// Polygon segmentations are drawn instead of the bounding box; run-length encoded masks fall back to it.
+    pub fn parse_coco(text: &str, name: String) -> Result<Self> {
+        let document: CocoDocument = serde_json::from_str(text)?;
+        let (images, annotations, categories) = match document {
+            CocoDocument::Dataset(dataset) => (dataset.images, dataset.annotations, dataset.categories),
+            CocoDocument::Results(annotations) => (Vec::new(), annotations, Vec::new()),
+        };
+        let file_names: HashMap<String, String> = images
+            .into_iter()
+            .map(|image| (id_key(&image.id), file_name(&image.file_name).to_string()))
+            .collect();
+
+        let mut class_index: HashMap<String, usize> = HashMap::new();
+        let mut classes = Vec::new();
+        for category in categories {
+            class_index.insert(id_key(&category.id), classes.len());
+            classes.push(category.name);
+        }
+        let mut overlay = Self::new(name);
+
+        for annotation in annotations {
+            let category = id_key(&annotation.category_id);
+            let class = *class_index.entry(category.clone()).or_insert_with(|| {
+                classes.push(category);
+                classes.len() - 1
+            });
+            let mut shapes: Vec<_> = match &annotation.segmentation {
+                Some(Value::Array(polygons)) => polygons
+                    .iter()
+                    .filter_map(|polygon| {
+                        let coords: Vec<f32> = serde_json::from_value(polygon.clone()).ok()?;
+                        let points = coord_pairs(&coords);
+                        (points.len() >= 3).then_some(OverlayShape::Polygon(points))
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            if shapes.is_empty() {
+                if let Some([x, y, w, h]) = annotation.bbox {
+                    shapes.push(OverlayShape::Box(Rect::from_min_size(pos2(x, y), [w, h].into())));
+                }
+            }
+            if let Some(keypoints) = &annotation.keypoints {
+                let points: Vec<_> = keypoints
+                    .chunks_exact(3)
+                    .filter(|keypoint| keypoint[2] > 0.0)
+                    .map(|keypoint| pos2(keypoint[0], keypoint[1]))
+                    .collect();
+                if !points.is_empty() {
+                    shapes.push(OverlayShape::Points(points));
+                }
+            }
+            if shapes.is_empty() {
+                continue;
+            }
+            let image = annotation.image_id.as_ref().map(|id| {
+                let id = id_key(id);
+                file_names.get(&id).cloned().unwrap_or(id)
+            });
+            let object = OverlayObject {
+                class,
+                score: annotation.score,
+                shapes,
+            };
+            overlay.push(image, object);
+        }
+        overlay.visible = vec![true; classes.len()];
+        overlay.classes = classes;
+        Ok(overlay)
+    }
+
+    /// Parse rows of `image,label,shape,coordinates...`, where shape is `box` (x, y, width, height), `polygon`
+    /// (x1, y1, x2, y2, ...) or `points` (x1, y1, ...). An empty image applies to every image, and a header row
+    /// is skipped.
+    pub fn parse_csv(text: &str, name: String) -> Result<Self> {
+        let mut overlay = Self::new(name);
+        let mut class_index: HashMap<String, usize> = HashMap::new();
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').map(|field| field.trim().trim_matches('"')).collect();
+            let line_number = line_index + 1;
+            let [image, label, shape, coords @ ..] = fields.as_slice() else {
+                return Err(eyre!("line {line_number}: expected image, label, shape and coordinates"));
+            };
+            let kind = shape.to_ascii_lowercase();
+            if !matches!(kind.as_str(), "box" | "polygon" | "points") && line_index == 0 {
+                continue;
+            }
+            let coords = coords
+                .iter()
+                .map(|value| value.parse::<f32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| eyre!("line {line_number}: {e}"))?;
+            let shape = match (kind.as_str(), coords.as_slice()) {
+                ("box", &[x, y, w, h]) => OverlayShape::Box(Rect::from_min_size(pos2(x, y), [w, h].into())),
+                ("polygon", coords) if coords.len() >= 6 && coords.len() % 2 == 0 => {
+                    OverlayShape::Polygon(coord_pairs(coords))
+                }
+                ("points", coords) if !coords.is_empty() && coords.len() % 2 == 0 => {
+                    OverlayShape::Points(coord_pairs(coords))
+                }
+                ("box" | "polygon" | "points", _) => {
+                    return Err(eyre!("line {line_number}: wrong number of coordinates for {kind}"))
+                }
+                _ => return Err(eyre!("line {line_number}: unknown shape '{shape}'")),
+            };
+            let class = *class_index.entry(label.to_string()).or_insert_with(|| {
+                overlay.classes.push(label.to_string());
+                overlay.classes.len() - 1
+            });
+            let image = (!image.is_empty()).then(|| file_name(image).to_string());
+            let object = OverlayObject {
+                class,
+                score: None,
+                shapes: vec![shape],
+            };
+            overlay.push(image, object);
+        }
+        overlay.visible = vec![true; overlay.classes.len()];
+        Ok(overlay)
+    }
+
+    /// Objects of the image named `image_name` (a path or file name). COCO results only know image ids, so a
+    /// file stem such as `000000397133` also finds the objects of image 397133.
+    pub fn objects(&self, image_name: &str) -> impl Iterator<Item = &OverlayObject> {
+        let name = file_name(image_name);
+        let stem = Path::new(name).file_stem().and_then(|stem| stem.to_str()).unwrap_or(name);
+        let numeric_stem = stem.parse::<u64>().ok().map(|id| id.to_string());
+        let objects = [Some(name), Some(stem), numeric_stem.as_deref()]
+            .into_iter()
+            .flatten()
+            .find_map(|key| self.by_image.get(key));
+        objects.into_iter().flatten().chain(&self.shared)
+    }
+
+    /// Whether `object` passes the class visibility and score filter.
+    pub fn is_shown(&self, object: &OverlayObject) -> bool {
+        self.visible.get(object.class).copied().unwrap_or(true) && object.score.is_none_or(|s| s >= self.min_score)
+    }
+
+    /// Number of objects of each class in the image named `image_name`.
+    pub fn class_counts(&self, image_name: &str) -> Vec<usize> {
+        let mut counts = vec![0; self.classes.len()];
+        for object in self.objects(image_name) {
+            counts[object.class] += 1;
+        }
+        counts
+    }
+
+    /// Whether any object has a score, so the score filter applies.
+    pub fn has_scores(&self) -> bool {
+        self.by_image
+            .values()
+            .flatten()
+            .chain(&self.shared)
+            .any(|object| object.score.is_some())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CocoDocument {
+    Dataset(CocoDataset),
+    Results(Vec<CocoAnnotation>),
+}
+
+#[derive(Deserialize)]
+struct CocoDataset {
+    #[serde(default)]
+    images: Vec<CocoImage>,
+    annotations: Vec<CocoAnnotation>,
+    #[serde(default)]
+    categories: Vec<CocoCategory>,
+}
+
+#[derive(Deserialize)]
+struct CocoImage {
+    id: Value,
+    file_name: String,
+}
+
+#[derive(Deserialize)]
+struct CocoCategory {
+    id: Value,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct CocoAnnotation {
+    #[serde(default)]
+    image_id: Option<Value>,
+    #[serde(default)]
+    category_id: Value,
+    #[serde(default)]
+    bbox: Option<[f32; 4]>,
+    /// Polygon lists, or a run-length encoded mask that is not drawn.
+    #[serde(default)]
+    segmentation: Option<Value>,
+    /// `x, y, visibility` triples.
+    #[serde(default)]
+    keypoints: Option<Vec<f32>>,
+    #[serde(default)]
+    score: Option<f32>,
+}
+
+/// Ids are numbers in COCO but strings in some exports; both become the same map key.
+fn id_key(id: &Value) -> String {
+    match id {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+fn coord_pairs(coords: &[f32]) -> Vec<Pos2> {
+    coords.chunks_exact(2).map(|pair| pos2(pair[0], pair[1])).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_coco_datasets_results_and_csv() {
+        let dataset = r#"{
+            "images": [{"id": 7, "file_name": "train/cat.jpg"}],
+            "categories": [{"id": 3, "name": "cat"}, {"id": 5, "name": "dog"}],
+            "annotations": [
+                {"image_id": 7, "category_id": 3, "bbox": [1, 2, 3, 4], "segmentation": [[0, 0, 4, 0, 4, 4]]},
+                {"image_id": 7, "category_id": 5, "bbox": [10, 20, 5, 5], "segmentation": {"counts": "abc"},
+                 "keypoints": [1, 1, 2, 0, 0, 0]}
+            ]
+        }"#;
+        let overlay = VectorOverlay::parse_coco(dataset, "gt.json".to_string()).unwrap();
+        assert_eq!(overlay.classes, ["cat", "dog"]);
+        let objects: Vec<_> = overlay.objects("/data/cat.jpg").collect();
+        assert_eq!(objects.len(), 2);
+        assert_eq!(
+            objects[0].shapes,
+            [OverlayShape::Polygon(vec![
+                pos2(0.0, 0.0),
+                pos2(4.0, 0.0),
+                pos2(4.0, 4.0)
+            ])]
+        );
+        assert_eq!(
+            objects[1].shapes,
+            [
+                OverlayShape::Box(Rect::from_min_size(pos2(10.0, 20.0), [5.0, 5.0].into())),
+                OverlayShape::Points(vec![pos2(1.0, 1.0)]),
+            ]
+        );
+        assert_eq!(objects[1].anchor(), Some(pos2(1.0, 1.0)));
+        assert_eq!(overlay.objects("dog.jpg").count(), 0);
+
+        let results = r#"[{"image_id": 397133, "category_id": 1, "bbox": [0, 0, 1, 1], "score": 0.4},
+                          {"image_id": 397133, "category_id": 1, "bbox": [0, 0, 2, 2], "score": 0.9}]"#;
+        let mut overlay = VectorOverlay::parse_coco(results, "detections.json".to_string()).unwrap();
+        assert_eq!(overlay.classes, ["1"]);
+        assert_eq!(overlay.class_counts("000000397133.jpg"), [2]);
+        overlay.min_score = 0.5;
+        assert_eq!(overlay.objects("000000397133.jpg").filter(|o| overlay.is_shown(o)).count(), 1);
+        overlay.visible[0] = false;
+        assert_eq!(overlay.objects("000000397133.jpg").filter(|o| overlay.is_shown(o)).count(), 0);
+
+        let csv = "image,label,shape,coordinates\na.png,car,box,1,2,3,4\n,logo,points,5,6\nb.png,car,polygon,0,0,1,0\n";
+        assert!(VectorOverlay::parse_csv(csv, "boxes.csv".to_string()).is_err());
+        let csv = "image,label,shape,coordinates\na.png,car,box,1,2,3,4\n,logo,points,5,6\n";
+        let overlay = VectorOverlay::parse_csv(csv, "boxes.csv".to_string()).unwrap();
+        assert_eq!(overlay.classes, ["car", "logo"]);
+        assert_eq!(overlay.class_counts("a.png"), [1, 1]);
+        assert_eq!(overlay.class_counts("b.png"), [0, 1]);
+    }
+}
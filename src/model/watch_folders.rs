@@ -0,0 +1,176 @@
+//! Folders watched for new images independently of the open directory, so render outputs show up in the image
+//! list as they are written.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use color_eyre::eyre::Result;
+use eframe::egui;
+use notify::{event::*, recommended_watcher, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{model::FileNav, settings::WatchFolder};
+
+/// Time without events on a file before it counts as written. Renderers often write large files in chunks.
+pub const WATCH_FOLDER_SETTLE_DELAY: Duration = Duration::from_millis(500);
+
+pub struct WatchedFile {
+    pub path: PathBuf,
+    pub auto_select: bool,
+}
+
+#[derive(Default)]
+pub struct WatchFolders {
+    folders: Vec<WatchFolder>,
+    watcher: Option<RecommendedWatcher>,
+    event_rx: Option<mpsc::Receiver<Result<notify::Event, notify::Error>>>,
+    /// Files with recent events and the time of the last one.
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl WatchFolders {
+    /// Watch `folders`, replacing the previous set. Folders that cannot be watched are reported and skipped.
+    pub fn configure(&mut self, ctx: &egui::Context, folders: &[WatchFolder]) -> Vec<String> {
+        self.watcher = None;
+        self.event_rx = None;
+        self.pending.clear();
+        self.folders = folders.to_vec();
+        if folders.is_empty() {
+            return Vec::new();
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let repaint_ctx = ctx.clone();
+        let mut watcher = match recommended_watcher(move |res| {
+            let _ = tx.send(res);
+            repaint_ctx.request_repaint();
+        }) {
+            Ok(watcher) => watcher,
+            Err(e) => return vec![format!("Failed to start the folder watcher: {e}")],
+        };
+        let mut errors = Vec::new();
+        for folder in &mut self.folders {
+            folder.path = std::fs::canonicalize(&folder.path).unwrap_or_else(|_| folder.path.clone());
+            if let Err(e) = watcher.watch(&folder.path, RecursiveMode::NonRecursive) {
+                errors.push(format!("Failed to watch {}: {e}", folder.path.display()));
+            }
+        }
+        self.watcher = Some(watcher);
+        self.event_rx = Some(rx);
+        errors
+    }
+
+    /// Whether files are waiting to settle, so the caller can poll again after [`WATCH_FOLDER_SETTLE_DELAY`].
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// New or rewritten images that match a folder's rules and have had no events for
+    /// [`WATCH_FOLDER_SETTLE_DELAY`].
+    pub fn poll(&mut self) -> Vec<WatchedFile> {
+        if let Some(rx) = &self.event_rx {
+            for event in rx.try_iter().flatten() {
+                let written = match event.kind {
+                    EventKind::Create(_) | EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Any) => {
+                        event.paths.as_slice()
+                    }
+                    // The last path is the new name of a rename, such as a temporary file moved into place.
+                    EventKind::Modify(ModifyKind::Name(RenameMode::To | RenameMode::Both | RenameMode::Any)) => {
+                        event.paths.last().map(std::slice::from_ref).unwrap_or_default()
+                    }
+                    _ => continue,
+                };
+                for path in written {
+                    if self.rule_for(path).is_some() {
+                        self.pending.insert(path.clone(), Instant::now());
+                    }
+                }
+            }
+        }
+
+        let settled: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(_, last_event)| last_event.elapsed() >= WATCH_FOLDER_SETTLE_DELAY)
+            .map(|(path, _)| path.clone())
+            .collect();
+        settled
+            .into_iter()
+            .filter_map(|path| {
+                self.pending.remove(&path);
+                let auto_select = self.rule_for(&path)?.auto_select;
+                path.is_file().then_some(WatchedFile { path, auto_select })
+            })
+            .collect()
+    }
+
+    fn rule_for(&self, path: &Path) -> Option<&WatchFolder> {
+        let name = path.file_name()?.to_str()?;
+        if !FileNav::is_supported_image(path) {
+            return None;
+        }
+        self.folders
+            .iter()
+            .find(|folder| path.parent() == Some(folder.path.as_path()) && matches_include(&folder.include, name))
+    }
+}
+
+/// Whether `name` matches one of the comma-separated globs in `include`; an empty list matches everything.
+fn matches_include(include: &str, name: &str) -> bool {
+    let mut patterns = include
+        .split(',')
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .peekable();
+    patterns.peek().is_none() || patterns.any(|pattern| matches_glob(pattern, name))
+}
+
+/// Case-insensitive match of `*` (any run of characters) and `?` (one character) wildcards.
+fn matches_glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position after the last `*` and the name position it is currently matched up to.
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    p = star_p;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn include_globs_match_file_names() {
+        assert!(matches_glob("*.exr", "beauty.0001.EXR"));
+        assert!(matches_glob("beauty_*_v?.png", "beauty_final_v2.png"));
+        assert!(!matches_glob("beauty_*_v?.png", "beauty_final_v10.png"));
+        assert!(matches_glob("*a*b", "xaxxab"));
+        assert!(!matches_glob("*.exr", "beauty.exr.tmp"));
+        assert!(matches_include("", "any.png"));
+        assert!(matches_include("*.exr, *.png", "depth.png"));
+        assert!(!matches_include("*.exr, *.png", "depth.jpg"));
+    }
+}
@@ -0,0 +1,65 @@
+//! Display-time white balance: per-channel gains that make a neutral patch, or the average of the whole image
+//! under the gray-world assumption, come out gray.
+
+use color_eyre::eyre::{eyre, Result};
+
+use super::{Image, ImageData, MeanDim, Recti};
+use crate::util::math_ext::{vec2i, Vec2i};
+
+/// Half the side of the square averaged around an eyedropper click, so sensor noise does not skew the gains.
+pub const NEUTRAL_PATCH_RADIUS: i32 = 2;
+
+/// Gains that map the RGB `mean` to gray, normalized so green keeps its level. `None` when a channel is not
+/// positive, as there is no gain that balances it.
+pub fn white_balance_gains(mean: &[f64]) -> Option<[f32; 3]> {
+    let [r, g, b] = *mean.get(..3)? else {
+        return None;
+    };
+    if [r, g, b].iter().any(|&v| !v.is_finite() || v <= 0.0) {
+        return None;
+    }
+    Some([(g / r) as f32, 1.0, (g / b) as f32])
+}
+
+/// The patch of [`NEUTRAL_PATCH_RADIUS`] around `pixel`, clipped to a `width`×`height` image.
+pub fn neutral_patch(pixel: Vec2i, width: i32, height: i32) -> Recti {
+    let radius = vec2i(NEUTRAL_PATCH_RADIUS, NEUTRAL_PATCH_RADIUS);
+    Recti::from_min_max(pixel - radius, pixel + radius + vec2i(1, 1))
+        .intersect(Recti::from_min_size(vec2i(0, 0), vec2i(width, height)))
+}
+
+/// Gains that neutralize the mean color of `rect`; the whole image gives the gray-world estimate.
+pub fn estimate_white_balance(image: &ImageData, rect: Recti) -> Result<[f32; 3]> {
+    let spec = image.spec();
+    if spec.channels < 3 {
+        return Err(eyre!("White balance needs an RGB image"));
+    }
+    if rect.empty() {
+        return Err(eyre!("The patch is outside the image"));
+    }
+    let mean = image.mean_value_in_rect(rect, MeanDim::All)?;
+    white_balance_gains(&mean).ok_or_else(|| eyre!("The patch has a channel without signal to balance"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ImageSpec, PixelType};
+
+    #[test]
+    fn balances_the_mean_color_to_gray() {
+        assert_eq!(white_balance_gains(&[0.5, 0.25, 0.125, 1.0]), Some([0.5, 1.0, 2.0]));
+        assert_eq!(white_balance_gains(&[0.5, 0.25, 0.0]), None);
+        assert_eq!(white_balance_gains(&[0.5]), None);
+
+        assert_eq!(neutral_patch(vec2i(0, 5), 8, 8), Recti::from_min_max(vec2i(0, 3), vec2i(3, 8)));
+
+        let pixels = [[0.4, 0.2, 0.1], [0.8, 0.4, 0.2]].concat();
+        let image = ImageData::from_f32(ImageSpec::new(2, 1, 3, PixelType::F32), pixels).unwrap();
+        let gains = estimate_white_balance(&image, neutral_patch(vec2i(0, 0), 2, 1)).unwrap();
+        for (gain, expected) in gains.iter().zip([0.5, 1.0, 2.0]) {
+            assert!((gain - expected).abs() < 1e-5, "{gains:?}");
+        }
+        assert!(estimate_white_balance(&image, Recti::ZERO).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+//! Raw 8-bit YUV frames as written by video pipelines. Nothing in the data describes the frame, so the size and
+//! layout come from the socket header, the file name (`clip_1920x1080_nv12.yuv`) or the user.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex},
+};
+
+use color_eyre::eyre::{eyre, Result};
+
+use super::{
+    image_io::{DecodedImage, DecodedPixels},
+    PixelType,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum YuvFormat {
+    /// Full-resolution Y plane followed by one interleaved UV plane at half width and height.
+    Nv12,
+    /// Y, U and V planes; U and V at half width and height.
+    I420,
+    /// Packed Y0 U Y1 V for each pair of pixels.
+    Yuyv,
+}
+
+impl YuvFormat {
+    pub const ALL: [Self; 3] = [Self::Nv12, Self::I420, Self::Yuyv];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Nv12 => "NV12",
+            Self::I420 => "I420",
+            Self::Yuyv => "YUYV",
+        }
+    }
+
+    /// Accepts the names used by FFmpeg and V4L2 as well.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "nv12" => Some(Self::Nv12),
+            "i420" | "iyuv" | "yuv420p" => Some(Self::I420),
+            "yuyv" | "yuy2" | "yuyv422" => Some(Self::Yuyv),
+            _ => None,
+        }
+    }
+
+    pub fn frame_bytes(self, width: u32, height: u32) -> Option<usize> {
+        let (width, height) = (width as usize, height as usize);
+        let luma = width.checked_mul(height)?;
+        match self {
+            Self::Nv12 | Self::I420 => {
+                luma.checked_add(width.div_ceil(2).checked_mul(height.div_ceil(2))?.checked_mul(2)?)
+            }
+            Self::Yuyv => width.div_ceil(2).checked_mul(4)?.checked_mul(height),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct YuvFrame {
+    pub width: u32,
+    pub height: u32,
+    pub format: YuvFormat,
+}
+
+impl YuvFrame {
+    pub fn bytes(&self) -> Option<usize> {
+        self.format.frame_bytes(self.width, self.height)
+    }
+}
+
+/// Returned by loaders when a `.yuv` file has no frame layout yet; the viewer asks for one and retries.
+#[derive(Debug)]
+pub struct YuvFrameRequired(pub PathBuf);
+
+impl fmt::Display for YuvFrameRequired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "frame size and format of {} are unknown", self.0.display())
+    }
+}
+
+impl std::error::Error for YuvFrameRequired {}
+
+static FILE_FRAMES: LazyLock<Mutex<HashMap<PathBuf, YuvFrame>>> = LazyLock::new(Default::default);
+
+/// Remember the layout chosen for `path` for the rest of the session, so reloads and navigation reuse it.
+pub fn set_yuv_file_frame(path: &Path, frame: YuvFrame) {
+    FILE_FRAMES.lock().unwrap().insert(path.to_path_buf(), frame);
+}
+
+/// Layout chosen by the user for `path`, falling back to a `WIDTHxHEIGHT` and format token in the file name.
+/// Without a format token the file is assumed to be I420, FFmpeg's default for `.yuv`.
+pub fn yuv_file_frame(path: &Path) -> Option<YuvFrame> {
+    if let Some(frame) = FILE_FRAMES.lock().unwrap().get(path) {
+        return Some(*frame);
+    }
+    let stem = path.file_stem()?.to_str()?;
+    let tokens = stem.split(['_', '-', '.', ' ']);
+    let mut size = None;
+    let mut format = None;
+    for token in tokens {
+        if let Some((w, h)) = token.to_ascii_lowercase().split_once('x') {
+            if let (Ok(width), Ok(height)) = (w.parse::<u32>(), h.parse::<u32>()) {
+                size = Some((width, height));
+                continue;
+            }
+        }
+        format = format.or_else(|| YuvFormat::from_name(token));
+    }
+    let (width, height) = size.filter(|&(w, h)| w > 0 && h > 0)?;
+    Some(YuvFrame {
+        width,
+        height,
+        format: format.unwrap_or(YuvFormat::I420),
+    })
+}
+
+/// Convert the first frame in `bytes` to 8-bit RGB using BT.601 video-range coefficients, the convention of
+/// camera and codec pipelines (and of OpenCV's `COLOR_YUV2RGB_*`). Chroma is replicated, not interpolated.
+pub(crate) fn decode_yuv(bytes: &[u8], frame: YuvFrame) -> Result<DecodedImage> {
+    let YuvFrame { width, height, format } = frame;
+    if width == 0 || height == 0 {
+        return Err(eyre!("invalid YUV frame size {width}x{height}"));
+    }
+    let frame_bytes = frame.bytes().ok_or_else(|| eyre!("YUV frame {width}x{height} is too large"))?;
+    if bytes.len() < frame_bytes {
+        return Err(eyre!(
+            "{} {width}x{height} needs {frame_bytes} bytes, got {}",
+            format.label(),
+            bytes.len()
+        ));
+    }
+
+    let (w, h) = (width as usize, height as usize);
+    let chroma_w = w.div_ceil(2);
+    let chroma_plane = chroma_w * h.div_ceil(2);
+    let mut rgb = Vec::with_capacity(w * h * 3);
+    for y in 0..h {
+        for x in 0..w {
+            let (luma, u, v) = match format {
+                YuvFormat::Nv12 => {
+                    let uv = w * h + (y / 2) * chroma_w * 2 + (x / 2) * 2;
+                    (bytes[y * w + x], bytes[uv], bytes[uv + 1])
+                }
+                YuvFormat::I420 => {
+                    let chroma = (y / 2) * chroma_w + x / 2;
+                    let u_plane = w * h;
+                    (
+                        bytes[y * w + x],
+                        bytes[u_plane + chroma],
+                        bytes[u_plane + chroma_plane + chroma],
+                    )
+                }
+                YuvFormat::Yuyv => {
+                    let pair = y * chroma_w * 4 + (x / 2) * 4;
+                    (bytes[pair + (x % 2) * 2], bytes[pair + 1], bytes[pair + 3])
+                }
+            };
+            rgb.extend_from_slice(&bt601_to_rgb(luma, u, v));
+        }
+    }
+
+    let mut image = DecodedImage::new(width, height, 3, PixelType::U8, DecodedPixels::U8(rgb))?;
+    image.source_format = Some(format!("YUV, {}", format.label()));
+    Ok(image)
+}
+
+fn bt601_to_rgb(y: u8, u: u8, v: u8) -> [u8; 3] {
+    let y = 1.164 * (y as f32 - 16.0);
+    let u = u as f32 - 128.0;
+    let v = v as f32 - 128.0;
+    [y + 1.596 * v, y - 0.392 * u - 0.813 * v, y + 2.017 * u].map(|c| c.round().clamp(0.0, 255.0) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(image: &DecodedImage) -> Vec<u8> {
+        image.pixels.bytes().to_vec()
+    }
+
+    #[test]
+    fn converts_every_layout_to_the_same_rgb() {
+        // 4x2 frame: black and white under neutral chroma, then two pixels with the BT.601 encoding of red
+        // (Y=81, U=90, V=240) in the right chroma block. Both rows are the same.
+        let luma = [16_u8, 235, 81, 81];
+        let (u, v) = (90_u8, 240_u8);
+        let frame = |format| YuvFrame {
+            width: 4,
+            height: 2,
+            format,
+        };
+
+        let nv12 = [&luma[..], &luma, &[128, 128, u, v]].concat();
+        let i420 = [&luma[..], &luma, &[128, u], &[128, v]].concat();
+        let yuyv = [[16, 128, 235, 128, 81, u, 81, v]; 2].concat();
+
+        let expected = rgb(&decode_yuv(&nv12, frame(YuvFormat::Nv12)).unwrap());
+        assert_eq!(expected, [[0, 0, 0, 255, 255, 255, 254, 0, 0, 254, 0, 0]; 2].concat());
+        assert_eq!(rgb(&decode_yuv(&i420, frame(YuvFormat::I420)).unwrap()), expected);
+        assert_eq!(rgb(&decode_yuv(&yuyv, frame(YuvFormat::Yuyv)).unwrap()), expected);
+
+        assert!(decode_yuv(&nv12[..11], frame(YuvFormat::Nv12)).is_err());
+    }
+
+    #[test]
+    fn odd_sizes_round_chroma_up() {
+        assert_eq!(YuvFormat::I420.frame_bytes(3, 3), Some(9 + 2 * 4));
+        assert_eq!(YuvFormat::Yuyv.frame_bytes(3, 1), Some(8));
+        let image = decode_yuv(
+            &[128; 17],
+            YuvFrame {
+                width: 3,
+                height: 3,
+                format: YuvFormat::Nv12,
+            },
+        )
+        .unwrap();
+        assert_eq!((image.width, image.height), (3, 3));
+    }
+
+    #[test]
+    fn reads_the_frame_layout_from_the_file_name() {
+        let frame = |name: &str| yuv_file_frame(Path::new(name));
+        assert_eq!(
+            frame("/tmp/decoder_out_1920x1080_nv12.yuv"),
+            Some(YuvFrame {
+                width: 1920,
+                height: 1080,
+                format: YuvFormat::Nv12
+            })
+        );
+        assert_eq!(frame("foreman-352x288.yuv").map(|f| f.format), Some(YuvFormat::I420));
+        assert_eq!(
+            frame("cam.YUY2.640X480.yuv").map(|f| (f.width, f.format)),
+            Some((640, YuvFormat::Yuyv))
+        );
+        assert_eq!(frame("capture.yuv"), None);
+
+        let path = Path::new("/tmp/capture.yuv");
+        let chosen = YuvFrame {
+            width: 4,
+            height: 2,
+            format: YuvFormat::Yuyv,
+        };
+        set_yuv_file_frame(path, chosen);
+        assert_eq!(yuv_file_frame(path), Some(chosen));
+    }
+}
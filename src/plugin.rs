@@ -0,0 +1,494 @@
+//! Native plugins that add image loaders and filters without rebuilding the viewer.
+//!
+//! A plugin is a dynamic library placed in the `plugins` directory next to the executable. It exports
+//! `const EdolviewPluginV1* edolview_plugin_v1(void)`; see `include/edolview_plugin.h` for the C declarations.
+//! All plugin functions may be called from worker threads, so they must be thread-safe.
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    model::{check_decode_limits, Image, ImageData, ImageSpec, PixelType},
+    util::path_ext::exe_dir_or_cwd,
+};
+
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+const PLUGIN_ENTRY_SYMBOL: &[u8] = b"edolview_plugin_v1\0";
+
+/// Interleaved 32-bit float image exchanged with plugins. Row-major, `channels` values per pixel.
+#[repr(C)]
+pub struct EdolviewImage {
+    pub width: i32,
+    pub height: i32,
+    pub channels: i32,
+    pub data: *mut f32,
+}
+
+#[repr(C)]
+pub struct EdolviewFilter {
+    pub name: *const c_char,
+    /// Fill `output` (allocated by the plugin, released with `free_image`) from `input`. Returns 0 on success.
+    pub apply: unsafe extern "C" fn(input: *const EdolviewImage, output: *mut EdolviewImage) -> i32,
+}
+
+#[repr(C)]
+pub struct EdolviewPluginV1 {
+    pub abi_version: u32,
+    pub name: *const c_char,
+    /// Comma-separated lowercase file extensions handled by `load`, or null.
+    pub extensions: *const c_char,
+    /// Decode the file at the UTF-8 `path` into `output`. Returns 0 on success.
+    pub load: Option<unsafe extern "C" fn(path: *const c_char, output: *mut EdolviewImage) -> i32>,
+    pub filter_count: u32,
+    pub filters: *const EdolviewFilter,
+    /// Release pixel data that `load` or a filter allocated.
+    pub free_image: Option<unsafe extern "C" fn(image: *mut EdolviewImage)>,
+}
+
+pub struct Plugin {
+    pub name: String,
+    pub path: PathBuf,
+    pub extensions: Vec<String>,
+    pub filters: Vec<String>,
+    descriptor: *const EdolviewPluginV1,
+    // Keeps the code behind `descriptor` mapped for the lifetime of the registry.
+    _library: libloading::Library,
+}
+
+// SAFETY: the descriptor is immutable static data inside the library, which the registry keeps loaded forever,
+// and the ABI requires plugin functions to be thread-safe.
+unsafe impl Send for Plugin {}
+unsafe impl Sync for Plugin {}
+
+#[derive(Default)]
+pub struct PluginRegistry {
+    pub plugins: Vec<Plugin>,
+    /// Libraries in the plugin directory that could not be loaded, with the reason.
+    pub errors: Vec<String>,
+}
+
+static PLUGINS: OnceLock<PluginRegistry> = OnceLock::new();
+
+/// Plugins found in the plugin directory; the directory is scanned on first use.
+pub fn plugins() -> &'static PluginRegistry {
+    PLUGINS.get_or_init(|| PluginRegistry::load_dir(&plugin_dir()))
+}
+
+/// Leave the plugin directory unscanned for the rest of the session, so no plugin library is ever loaded.
+pub fn disable_plugins() {
+    let _ = PLUGINS.set(PluginRegistry::default());
+}
+
+pub fn plugin_dir() -> PathBuf {
+    exe_dir_or_cwd().join("plugins")
+}
+
+/// Whether files with this lowercase extension can be opened by a built-in decoder or a loader plugin.
+pub fn is_openable_extension(ext: &str) -> bool {
+    crate::supported_image::is_supported_image_extension(ext) || plugins().loader_for(ext).is_some()
+}
+
+/// Built-in image extensions followed by those registered by loader plugins.
+pub fn openable_extensions() -> Vec<&'static str> {
+    let mut extensions = crate::supported_image::supported_image_extensions();
+    for ext in plugins().extensions() {
+        if !extensions.contains(&ext) {
+            extensions.push(ext);
+        }
+    }
+    extensions
+}
+
+fn is_library_path(path: &Path) -> bool {
+    let ext = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+    ext.eq_ignore_ascii_case(std::env::consts::DLL_EXTENSION)
+}
+
+/// Copy a nul-terminated UTF-8 string owned by the plugin.
+///
+/// # Safety
+/// `ptr` must be null or point to a nul-terminated string that stays valid during the call.
+unsafe fn plugin_string(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(ptr).to_string_lossy().into_owned()
+    }
+}
+
+/// Name, extensions and filter names copied out of a validated descriptor.
+struct PluginInfo {
+    name: String,
+    extensions: Vec<String>,
+    filters: Vec<String>,
+}
+
+/// Check the descriptor returned by a plugin's entry point and copy out its strings.
+///
+/// # Safety
+/// `descriptor` must be null or point to a descriptor whose string and filter pointers are null or valid as the
+/// plugin ABI describes.
+unsafe fn read_descriptor(descriptor: *const EdolviewPluginV1) -> Result<PluginInfo> {
+    if descriptor.is_null() {
+        return Err(eyre!("edolview_plugin_v1 returned null"));
+    }
+    let desc = &*descriptor;
+    if desc.abi_version != PLUGIN_ABI_VERSION {
+        return Err(eyre!(
+            "Unsupported plugin ABI version {} (expected {PLUGIN_ABI_VERSION})",
+            desc.abi_version
+        ));
+    }
+    if (desc.load.is_some() || desc.filter_count > 0) && desc.free_image.is_none() {
+        return Err(eyre!("Plugin does not provide free_image"));
+    }
+    if desc.filter_count > 0 && desc.filters.is_null() {
+        return Err(eyre!("Plugin declares {} filters but no filter array", desc.filter_count));
+    }
+
+    let extensions = if desc.load.is_some() {
+        plugin_string(desc.extensions)
+            .split(',')
+            .map(|ext| ext.trim().trim_start_matches('.').to_ascii_lowercase())
+            .filter(|ext| !ext.is_empty())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let filters = if desc.filter_count == 0 {
+        Vec::new()
+    } else {
+        std::slice::from_raw_parts(desc.filters, desc.filter_count as usize)
+            .iter()
+            .map(|filter| plugin_string(filter.name))
+            .collect()
+    };
+    Ok(PluginInfo {
+        name: plugin_string(desc.name),
+        extensions,
+        filters,
+    })
+}
+
+/// Copy an image that `desc`'s plugin allocated into viewer memory and release the plugin's buffer, also when the
+/// image is rejected.
+fn take_image(desc: &EdolviewPluginV1, mut image: EdolviewImage) -> Result<ImageData> {
+    let invalid = || {
+        eyre!(
+            "Plugin returned an invalid image ({}x{}, {} channels)",
+            image.width,
+            image.height,
+            image.channels
+        )
+    };
+    let result = if image.data.is_null() || image.width <= 0 || image.height <= 0 || !(1..=4).contains(&image.channels)
+    {
+        Err(invalid())
+    } else {
+        (image.width as usize)
+            .checked_mul(image.height as usize)
+            .and_then(|pixels| pixels.checked_mul(image.channels as usize))
+            .ok_or_else(invalid)
+            .and_then(|len| {
+                // Bound the read, since the ABI gives no buffer length to check against.
+                check_decode_limits(image.width as u64, image.height as u64, image.channels as u64)?;
+                Ok(len)
+            })
+            .and_then(|len| {
+                // SAFETY: the ABI requires `data` to hold width * height * channels floats.
+                let pixels = unsafe { std::slice::from_raw_parts(image.data, len) }.to_vec();
+                ImageData::from_f32(
+                    ImageSpec::new(image.width, image.height, image.channels, PixelType::F32),
+                    pixels,
+                )
+            })
+    };
+    if let Some(free_image) = desc.free_image {
+        // SAFETY: `image` was filled by this plugin and is released exactly once.
+        unsafe { free_image(&mut image) };
+    }
+    result
+}
+
+impl PluginRegistry {
+    fn load_dir(dir: &Path) -> Self {
+        let mut registry = Self::default();
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return registry;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && is_library_path(path))
+            .collect();
+        paths.sort();
+        for path in paths {
+            match Plugin::load(&path) {
+                Ok(plugin) => registry.plugins.push(plugin),
+                Err(err) => {
+                    eprintln!("Failed to load plugin {}: {err}", path.display());
+                    registry.errors.push(format!("{}: {err}", path.display()));
+                }
+            }
+        }
+        registry
+    }
+
+    pub fn loader_for(&self, ext: &str) -> Option<&Plugin> {
+        self.plugins.iter().find(|plugin| plugin.extensions.iter().any(|e| e == ext))
+    }
+
+    pub fn extensions(&self) -> impl Iterator<Item = &str> {
+        self.plugins
+            .iter()
+            .flat_map(|plugin| plugin.extensions.iter().map(String::as_str))
+    }
+}
+
+impl Plugin {
+    fn load(path: &Path) -> Result<Self> {
+        // SAFETY: loading a library runs its initializers; plugins are trusted code installed by the user.
+        let library = unsafe { libloading::Library::new(path) }?;
+        // SAFETY: the entry point signature is fixed by the plugin ABI.
+        let descriptor = unsafe {
+            let entry = library.get::<unsafe extern "C" fn() -> *const EdolviewPluginV1>(PLUGIN_ENTRY_SYMBOL)?;
+            entry()
+        };
+        // SAFETY: a non-null descriptor points to static data that lives as long as `library`.
+        let PluginInfo {
+            name,
+            extensions,
+            filters,
+        } = unsafe { read_descriptor(descriptor) }?;
+        let name = if name.is_empty() {
+            path.file_stem().unwrap_or_default().to_string_lossy().into_owned()
+        } else {
+            name
+        };
+
+        Ok(Self {
+            name,
+            path: path.to_path_buf(),
+            extensions,
+            filters,
+            descriptor,
+            _library: library,
+        })
+    }
+
+    fn descriptor(&self) -> &EdolviewPluginV1 {
+        // SAFETY: validated in `load` and kept alive by `_library`.
+        unsafe { &*self.descriptor }
+    }
+
+    fn take_image(&self, image: EdolviewImage) -> Result<ImageData> {
+        take_image(self.descriptor(), image)
+    }
+
+    pub fn load_image(&self, path: &Path) -> Result<ImageData> {
+        let load = self
+            .descriptor()
+            .load
+            .ok_or_else(|| eyre!("Plugin {} cannot load images", self.name))?;
+        let c_path = CString::new(path.to_string_lossy().into_owned())?;
+        let mut output = EdolviewImage {
+            width: 0,
+            height: 0,
+            channels: 0,
+            data: std::ptr::null_mut(),
+        };
+        // SAFETY: both pointers are valid for the duration of the call.
+        let status = unsafe { load(c_path.as_ptr(), &mut output) };
+        if status != 0 {
+            if !output.data.is_null() {
+                let _ = self.take_image(output);
+            }
+            return Err(eyre!("Plugin {} failed to load {} (code {status})", self.name, path.display()));
+        }
+        self.take_image(output)
+    }
+
+    pub fn apply_filter(&self, index: usize, image: &ImageData) -> Result<ImageData> {
+        let desc = self.descriptor();
+        if index >= self.filters.len() {
+            return Err(eyre!("Unknown filter {index} in plugin {}", self.name));
+        }
+        // SAFETY: `index` is within the static filter array checked above.
+        let filter = unsafe { &*desc.filters.add(index) };
+        let spec = image.spec();
+        let data = image
+            .data()
+            .ok_or_else(|| eyre!("CPU pixel data is unavailable for this image"))?;
+        let input = EdolviewImage {
+            width: spec.width,
+            height: spec.height,
+            channels: spec.channels,
+            // Filters only read the input; the pointer is mutable to share the struct with outputs.
+            data: data.as_ptr().cast_mut(),
+        };
+        let mut output = EdolviewImage {
+            width: 0,
+            height: 0,
+            channels: 0,
+            data: std::ptr::null_mut(),
+        };
+        // SAFETY: `input` borrows `data` for the call and `output` is a valid out-parameter.
+        let status = unsafe { (filter.apply)(&input, &mut output) };
+        if status != 0 {
+            if !output.data.is_null() {
+                let _ = self.take_image(output);
+            }
+            return Err(eyre!("Filter {} failed (code {status})", self.filters[index]));
+        }
+        self.take_image(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    thread_local! {
+        static FREED: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe extern "C" fn count_free(image: *mut EdolviewImage) {
+        FREED.set(FREED.get() + 1);
+        (*image).data = std::ptr::null_mut();
+    }
+
+    unsafe extern "C" fn fail_load(_path: *const c_char, _output: *mut EdolviewImage) -> i32 {
+        1
+    }
+
+    unsafe extern "C" fn fail_filter(_input: *const EdolviewImage, _output: *mut EdolviewImage) -> i32 {
+        1
+    }
+
+    fn descriptor() -> EdolviewPluginV1 {
+        EdolviewPluginV1 {
+            abi_version: PLUGIN_ABI_VERSION,
+            name: c"Test".as_ptr(),
+            extensions: c" .RAW, bin ,,".as_ptr(),
+            load: Some(fail_load),
+            filter_count: 0,
+            filters: std::ptr::null(),
+            free_image: Some(count_free),
+        }
+    }
+
+    fn image(width: i32, height: i32, channels: i32, data: &mut [f32]) -> EdolviewImage {
+        EdolviewImage {
+            width,
+            height,
+            channels,
+            data: data.as_mut_ptr(),
+        }
+    }
+
+    #[test]
+    fn descriptors_are_validated_before_use() {
+        let info = unsafe { read_descriptor(&descriptor()) }.unwrap();
+        assert_eq!(info.name, "Test");
+        assert_eq!(info.extensions, ["raw", "bin"]);
+        assert!(info.filters.is_empty());
+
+        let filters = [EdolviewFilter {
+            name: c"Invert".as_ptr(),
+            apply: fail_filter,
+        }];
+        let with_filters = EdolviewPluginV1 {
+            load: None,
+            filter_count: 1,
+            filters: filters.as_ptr(),
+            ..descriptor()
+        };
+        let info = unsafe { read_descriptor(&with_filters) }.unwrap();
+        // Extensions only count for plugins that can load.
+        assert!(info.extensions.is_empty());
+        assert_eq!(info.filters, ["Invert"]);
+
+        let rejected = [
+            (None, "returned null"),
+            (
+                Some(EdolviewPluginV1 {
+                    abi_version: PLUGIN_ABI_VERSION + 1,
+                    ..descriptor()
+                }),
+                "ABI version 2",
+            ),
+            (
+                Some(EdolviewPluginV1 {
+                    free_image: None,
+                    ..descriptor()
+                }),
+                "free_image",
+            ),
+            (
+                Some(EdolviewPluginV1 {
+                    filter_count: 3,
+                    ..descriptor()
+                }),
+                "no filter array",
+            ),
+        ];
+        for (desc, message) in rejected {
+            let ptr = desc.as_ref().map_or(std::ptr::null(), |desc| desc as *const _);
+            let err = unsafe { read_descriptor(ptr) }.err().unwrap();
+            assert!(err.to_string().contains(message), "{err}");
+        }
+    }
+
+    #[test]
+    fn plugin_images_are_checked_and_always_released() {
+        let desc = descriptor();
+        let mut pixels = [0.0, 0.25, 0.5, 0.75, 1.0, 1.25];
+        FREED.set(0);
+        let image_data = take_image(&desc, image(3, 1, 2, &mut pixels)).unwrap();
+        assert_eq!(
+            (image_data.spec().width, image_data.spec().height, image_data.spec().channels),
+            (3, 1, 2)
+        );
+        assert_eq!(image_data.get_pixel_at(2, 0).unwrap().to_vec(), vec![1.0, 1.25]);
+        assert_eq!(FREED.get(), 1);
+
+        let null = EdolviewImage {
+            width: 1,
+            height: 1,
+            channels: 1,
+            data: std::ptr::null_mut(),
+        };
+        assert!(take_image(&desc, null).is_err());
+        for (width, height, channels) in [(0, 1, 1), (1, -1, 1), (1, 1, 0), (1, 1, 5)] {
+            assert!(take_image(&desc, image(width, height, channels, &mut pixels)).is_err());
+        }
+        // Sizes whose sample count overflows or exceeds the decode limits are rejected before `data` is read,
+        // so the six-float buffer is never overrun.
+        for (width, height, channels) in [(i32::MAX, i32::MAX, 4), (1 << 20, 1 << 20, 4), (1 << 21, 1, 1)] {
+            let err = take_image(&desc, image(width, height, channels, &mut pixels)).err().unwrap();
+            assert!(err.to_string().contains("limit"), "{width}x{height}x{channels}: {err}");
+        }
+        assert_eq!(FREED.get(), 9);
+    }
+
+    #[test]
+    fn libraries_that_fail_to_load_are_reported() {
+        let dir = std::env::temp_dir().join(format!("edolview-plugins-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let library = dir.join(format!("broken.{}", std::env::consts::DLL_EXTENSION));
+        std::fs::write(&library, b"not a library").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"ignored").unwrap();
+
+        let registry = PluginRegistry::load_dir(&dir);
+        assert!(registry.plugins.is_empty());
+        assert_eq!(registry.errors.len(), 1);
+        assert!(registry.errors[0].contains("broken"), "{}", registry.errors[0]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,181 @@
+//! Journal of the open files and view, rewritten every few seconds while the app runs and removed when it closes,
+//! so a session that ended in a crash can be restored on the next launch.
+//!
+//! Every running window keeps its own journal and holds a lock on it; a journal whose lock can be taken belongs to
+//! a process that is gone.
+
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::{FileView, ViewPreset};
+
+/// How often the journal is rewritten when the session changed.
+pub const JOURNAL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SessionJournal {
+    /// File-backed images in image list order.
+    pub files: Vec<PathBuf>,
+    /// Zoom, pan and marquee of the shown file.
+    #[serde(default)]
+    pub view: Option<FileView>,
+    /// Colormaps and display settings.
+    #[serde(default)]
+    pub display: Option<ViewPreset>,
+}
+
+impl SessionJournal {
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+/// The journal of this process.
+pub struct RecoveryJournal {
+    path: PathBuf,
+    lock_path: PathBuf,
+    /// Locked for the lifetime of the process; the OS releases the lock if it crashes.
+    _lock: File,
+    last_body: String,
+    last_write: Option<Instant>,
+}
+
+impl RecoveryJournal {
+    pub fn start() -> Result<Self, String> {
+        Self::start_in(&recovery_dir())
+    }
+
+    fn start_in(dir: &Path) -> Result<Self, String> {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create recovery directory '{}': {e}", dir.display()))?;
+        let name = format!("session-{}", std::process::id());
+        let path = dir.join(format!("{name}.json"));
+        let lock_path = dir.join(format!("{name}.lock"));
+        let lock = File::create(&lock_path).map_err(|e| format!("Failed to create '{}': {e}", lock_path.display()))?;
+        lock.try_lock()
+            .map_err(|e| format!("Failed to lock '{}': {e}", lock_path.display()))?;
+        Ok(Self {
+            path,
+            lock_path,
+            _lock: lock,
+            last_body: String::new(),
+            last_write: None,
+        })
+    }
+
+    /// Whether [`JOURNAL_INTERVAL`] has passed since the last write.
+    pub fn is_due(&self) -> bool {
+        self.last_write.is_none_or(|time| time.elapsed() >= JOURNAL_INTERVAL)
+    }
+
+    /// Write `journal` unless it is unchanged since the last write. An empty session removes the journal, as there
+    /// is nothing to restore.
+    pub fn write(&mut self, journal: &SessionJournal) -> Result<(), String> {
+        self.last_write = Some(Instant::now());
+        let body = if journal.is_empty() {
+            String::new()
+        } else {
+            serde_json::to_string_pretty(journal).map_err(|e| format!("Failed to serialize session journal: {e}"))?
+        };
+        if body == self.last_body {
+            return Ok(());
+        }
+        let result = if body.is_empty() {
+            fs::remove_file(&self.path).or_else(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => Ok(()),
+                _ => Err(e),
+            })
+        } else {
+            let temp_path = self.path.with_extension("json.tmp");
+            fs::write(&temp_path, &body).and_then(|()| fs::rename(&temp_path, &self.path))
+        };
+        result.map_err(|e| format!("Failed to write session journal '{}': {e}", self.path.display()))?;
+        self.last_body = body;
+        Ok(())
+    }
+
+    /// Remove the journal on a normal exit.
+    pub fn finish(self) {
+        let _ = fs::remove_file(&self.path);
+        let _ = fs::remove_file(&self.lock_path);
+    }
+}
+
+/// The most recent journal left behind by a session that did not close normally. Every abandoned journal is
+/// removed, so it is offered once.
+pub fn take_abandoned() -> Option<SessionJournal> {
+    take_abandoned_in(&recovery_dir())
+}
+
+fn take_abandoned_in(dir: &Path) -> Option<SessionJournal> {
+    let entries = fs::read_dir(dir).ok()?;
+    let mut abandoned = Vec::new();
+    for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        if path.extension().is_none_or(|ext| ext != "lock") {
+            continue;
+        }
+        let Ok(lock) = File::options().write(true).open(&path) else {
+            continue;
+        };
+        if lock.try_lock().is_err() {
+            continue;
+        }
+        let journal_path = path.with_extension("json");
+        let modified = fs::metadata(&journal_path).and_then(|meta| meta.modified()).ok();
+        let journal = fs::read_to_string(&journal_path)
+            .ok()
+            .and_then(|body| serde_json::from_str::<SessionJournal>(&body).ok());
+        let _ = fs::remove_file(&journal_path);
+        drop(lock);
+        let _ = fs::remove_file(&path);
+        if let Some(journal) = journal.filter(|journal| !journal.is_empty()) {
+            abandoned.push((modified, journal));
+        }
+    }
+    abandoned
+        .into_iter()
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, journal)| journal)
+}
+
+fn recovery_dir() -> PathBuf {
+    crate::util::path_ext::app_config_dir().join("recovery")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_journals_of_ended_sessions_are_taken() {
+        let dir = std::env::temp_dir().join(format!("edolview-recovery-{}", std::process::id()));
+        let journal = SessionJournal {
+            files: vec![PathBuf::from("/images/a.exr"), PathBuf::from("/images/b.exr")],
+            ..SessionJournal::default()
+        };
+
+        let mut running = RecoveryJournal::start_in(&dir).unwrap();
+        running.write(&journal).unwrap();
+        assert!(take_abandoned_in(&dir).is_none());
+        assert!(running.path.exists());
+
+        // A crash leaves the files behind but releases the lock.
+        let RecoveryJournal { path, _lock, .. } = running;
+        drop(_lock);
+        let restored = take_abandoned_in(&dir).unwrap();
+        assert_eq!(restored.files, journal.files);
+        assert!(!path.exists());
+        assert!(take_abandoned_in(&dir).is_none());
+
+        let mut closed = RecoveryJournal::start_in(&dir).unwrap();
+        closed.write(&journal).unwrap();
+        closed.write(&SessionJournal::default()).unwrap();
+        assert!(!closed.path.exists());
+        closed.finish();
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
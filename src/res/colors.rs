@@ -28,13 +28,73 @@ pub const MULTI_LINE_PLOT_COLORS: [Color32; 8] = [
 ];
 pub const MULTI_LINE_PLOT_GRID_STROKE: Color32 = Color32::from_gray(36);
 
+pub const ANNOTATION_DEFAULT_COLOR: Color32 = Color32::from_rgb(255, 64, 64);
+pub const ANNOTATION_ERASER_STROKE: Color32 = Color32::from_gray(230);
+pub const SELECTION_MASK_STROKE: Color32 = Color32::from_rgb(255, 210, 80);
+
+pub const IMAGE_BORDER_STROKE: Color32 = Color32::from_gray(160);
+
 pub const PIXEL_VALUE_CHANNEL_COLORS: [Color32; 4] = [Color32::RED, Color32::GREEN, Color32::BLUE, Color32::GRAY];
 
+pub const VIEWER_OSD_TEXT: Color32 = Color32::from_gray(240);
+pub const VIEWER_OSD_FILL: Color32 = Color32::from_black_alpha(170);
+pub const PROOFING_BADGE_TEXT: Color32 = Color32::from_rgb(255, 190, 80);
+pub const VIEWER_OFFSCREEN_CAPTION: Color32 = Color32::from_gray(170);
+/// Arrow pointing at an image panned out of view; it brightens under the pointer since a click recenters the image.
+pub const VIEWER_OFFSCREEN_ARROW_FILL: Color32 = Color32::from_rgba_premultiplied(235, 235, 235, 235);
+pub const VIEWER_OFFSCREEN_ARROW_HOVER_FILL: Color32 = Color32::WHITE;
+pub const COLORMAP_RAMP_OUTLINE: Color32 = Color32::from_black_alpha(150);
+pub const COLORMAP_RAMP_LABEL: Color32 = Color32::from_rgba_premultiplied(220, 220, 220, 220);
+/// Crosshair and pixel outline of the probe shared by the grid comparison panes.
+pub const GRID_PROBE_STROKE: Color32 = Color32::from_rgb(255, 210, 0);
+
+pub const PRINT_PREVIEW_PAPER_FILL: Color32 = Color32::WHITE;
+pub const PRINT_PREVIEW_MARGIN_STROKE: Color32 = Color32::LIGHT_GRAY;
+
 pub const TOAST_INFO_ICON_COLOR: Color32 = Color32::from_rgb(0, 155, 255);
 pub const TOAST_WARNING_ICON_COLOR: Color32 = Color32::from_rgb(255, 212, 0);
 pub const TOAST_ERROR_ICON_COLOR: Color32 = Color32::from_rgb(255, 32, 0);
 pub const TOAST_SUCCESS_ICON_COLOR: Color32 = Color32::from_rgb(0, 255, 32);
 
+/// Channel-tinted pixel value text that stays readable on a displayed pixel of the given luma (0..1).
+pub fn pixel_value_text_color(channel: usize, background_luma: f32) -> Color32 {
+    let base = PIXEL_VALUE_CHANNEL_COLORS[channel.min(3)];
+    if background_luma > 0.5 {
+        base.lerp_to_gamma(Color32::BLACK, 0.6)
+    } else {
+        base.lerp_to_gamma(Color32::WHITE, 0.6)
+    }
+}
+
+/// Marker of image list entries with identical pixels; each set of duplicates gets its own hue.
+pub fn duplicate_marker_color(content_hash: u64) -> Color32 {
+    let hue = (content_hash % 360) as f32 / 360.0;
+    eframe::egui::ecolor::Hsva::new(hue, 0.6, 0.9, 1.0).into()
+}
+
+/// Chip of label `index` in the image list; labels are spread evenly around the hue circle.
+pub fn label_color(index: usize) -> Color32 {
+    let hue = index as f32 / crate::model::LABEL_COUNT as f32;
+    eframe::egui::ecolor::Hsva::new(hue, 0.65, 0.8, 1.0).into()
+}
+
+/// Key number on the label chips of the image list and the label name list.
+pub const LABEL_CHIP_TEXT: Color32 = Color32::WHITE;
+
+/// Strokes of vector overlay class `index`; golden-ratio hue steps keep neighboring classes apart.
+pub fn overlay_class_color(index: usize) -> Color32 {
+    let hue = (index as f32 * 0.618_034).fract();
+    eframe::egui::ecolor::Hsva::new(hue, 0.75, 1.0, 1.0).into()
+}
+
+/// Ink of vector overlays drawn over the class colors: keypoint outlines and class tag text.
+pub const OVERLAY_LABEL_INK: Color32 = Color32::BLACK;
+
+/// Texture tint of the segmentation mask layer drawn at `opacity` (0..1).
+pub fn segmentation_mask_tint(opacity: f32) -> Color32 {
+    Color32::from_white_alpha((opacity.clamp(0.0, 1.0) * 255.0) as u8)
+}
+
 pub fn selection_handle_clipped_fill() -> Color32 {
     Color32::from_rgba_unmultiplied(255, 174, 174, 240)
 }
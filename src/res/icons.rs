@@ -7,6 +7,7 @@ use crate::ui::icon::{self, IconExt};
 const SHOW_BACKGROUND: &[u8] = include_bytes!("icons/show_background.svg");
 const SHOW_PIXEL_VALUE: &[u8] = include_bytes!("icons/show_pixel_value.svg");
 const SHOW_CROSSHAIR: &[u8] = include_bytes!("icons/show_crosshair.svg");
+const SHOW_IMAGE_BORDER: &[u8] = include_bytes!("icons/show_image_border.svg");
 
 const SCALE_LINEAR: &[u8] = include_bytes!("icons/scale_linear.svg");
 const SCALE_INVERSE: &[u8] = include_bytes!("icons/scale_inverse.svg");
@@ -14,6 +15,8 @@ const SCALE_LOG: &[u8] = include_bytes!("icons/scale_log.svg");
 const SCALE_ABSOLUTE: &[u8] = include_bytes!("icons/scale_absolute.svg");
 
 const NORMALIZE: &[u8] = include_bytes!("icons/normalize.svg");
+const PICK_BLACK_POINT: &[u8] = include_bytes!("icons/pick_black_point.svg");
+const PICK_WHITE_POINT: &[u8] = include_bytes!("icons/pick_white_point.svg");
 const DOWNLOADING: &[u8] = include_bytes!("icons/downloading.svg");
 
 const REDUCE_COLUMN: &[u8] = include_bytes!("icons/reduce_column.svg");
@@ -28,6 +31,7 @@ pub struct Icons {
     show_background: OnceLock<egui::TextureHandle>,
     show_pixel_value: OnceLock<egui::TextureHandle>,
     show_crosshair: OnceLock<egui::TextureHandle>,
+    show_image_border: OnceLock<egui::TextureHandle>,
 
     scale_linear: OnceLock<egui::TextureHandle>,
     scale_inverse: OnceLock<egui::TextureHandle>,
@@ -35,6 +39,8 @@ pub struct Icons {
     scale_absolute: OnceLock<egui::TextureHandle>,
 
     normalize: OnceLock<egui::TextureHandle>,
+    pick_black_point: OnceLock<egui::TextureHandle>,
+    pick_white_point: OnceLock<egui::TextureHandle>,
 
     downloading: OnceLock<egui::TextureHandle>,
 
@@ -53,6 +59,7 @@ impl Icons {
             show_background: OnceLock::new(),
             show_pixel_value: OnceLock::new(),
             show_crosshair: OnceLock::new(),
+            show_image_border: OnceLock::new(),
 
             scale_linear: OnceLock::new(),
             scale_inverse: OnceLock::new(),
@@ -60,6 +67,8 @@ impl Icons {
             scale_absolute: OnceLock::new(),
 
             normalize: OnceLock::new(),
+            pick_black_point: OnceLock::new(),
+            pick_white_point: OnceLock::new(),
 
             downloading: OnceLock::new(),
 
@@ -93,6 +102,13 @@ impl Icons {
             .to_icon()
     }
 
+    #[inline]
+    pub fn get_show_image_border<'c>(&self, ctx: &egui::Context) -> egui::Image<'c> {
+        self.show_image_border
+            .get_or_init(|| icon::load_svg_icon_texture(ctx, "show_image_border", SHOW_IMAGE_BORDER))
+            .to_icon()
+    }
+
     #[inline]
     pub fn get_scale_linear<'c>(&self, ctx: &egui::Context) -> egui::Image<'c> {
         self.scale_linear
@@ -121,6 +137,20 @@ impl Icons {
             .to_icon()
     }
 
+    #[inline]
+    pub fn get_pick_black_point<'c>(&self, ctx: &egui::Context) -> egui::Image<'c> {
+        self.pick_black_point
+            .get_or_init(|| icon::load_svg_icon_texture(ctx, "pick_black_point", PICK_BLACK_POINT))
+            .to_icon()
+    }
+
+    #[inline]
+    pub fn get_pick_white_point<'c>(&self, ctx: &egui::Context) -> egui::Image<'c> {
+        self.pick_white_point
+            .get_or_init(|| icon::load_svg_icon_texture(ctx, "pick_white_point", PICK_WHITE_POINT))
+            .to_icon()
+    }
+
     #[inline]
     pub fn get_normalize<'c>(&self, ctx: &egui::Context) -> egui::Image<'c> {
         self.normalize
@@ -13,8 +13,47 @@ pub const BOOKMARK_PANEL_TOGGLE: Ksc = Ksc::new(Modifiers::NONE, Key::B);
 pub const BOOKMARK_ADD: Ksc = Ksc::new(Modifiers::COMMAND, Key::B);
 pub const BOOKMARK_PREV: Ksc = Ksc::new(Modifiers::NONE, Key::OpenBracket);
 pub const BOOKMARK_NEXT: Ksc = Ksc::new(Modifiers::NONE, Key::CloseBracket);
+pub const SCRIPT_CONSOLE_TOGGLE: Ksc = Ksc::new(Modifiers::NONE, Key::Backtick);
+pub const SCRIPT_RUN: Ksc = Ksc::new(Modifiers::COMMAND, Key::Enter);
+pub const PERF_HUD_TOGGLE: Ksc = Ksc::new(Modifiers::CTRL.plus(Modifiers::SHIFT), Key::P);
+pub const ASSET_SEARCH_FOCUS: Ksc = Ksc::new(Modifiers::COMMAND, Key::F);
+pub const GRID_TOGGLE: Ksc = Ksc::new(Modifiers::SHIFT, Key::N);
+pub const PROBE_WINDOW_TOGGLE: Ksc = Ksc::new(Modifiers::NONE, Key::I);
+pub const PROBE_HOLD: Ksc = Ksc::new(Modifiers::NONE, Key::H);
+pub const TEMPORAL_DIFF_TOGGLE: Ksc = Ksc::new(Modifiers::SHIFT, Key::D);
+pub const RECORD_VIEW_TOGGLE: Ksc = Ksc::new(Modifiers::CTRL.plus(Modifiers::SHIFT), Key::R);
+
+pub const TOGGLE_BACKGROUND: Ksc = Ksc::new(Modifiers::SHIFT, Key::G);
+pub const TOGGLE_PIXEL_VALUE: Ksc = Ksc::new(Modifiers::SHIFT, Key::V);
+pub const TOGGLE_CROSSHAIR: Ksc = Ksc::new(Modifiers::SHIFT, Key::C);
+pub const TOGGLE_IMAGE_BORDER: Ksc = Ksc::new(Modifiers::SHIFT, Key::F);
+pub const TOGGLE_ALPHA_VIEW: Ksc = Ksc::new(Modifiers::SHIFT, Key::A);
+
+pub const EXPOSURE_UP: Ksc = Ksc::new(Modifiers::NONE, Key::Period);
+pub const EXPOSURE_DOWN: Ksc = Ksc::new(Modifiers::NONE, Key::Comma);
+pub const GAMMA_UP: Ksc = Ksc::new(Modifiers::SHIFT, Key::Period);
+pub const GAMMA_DOWN: Ksc = Ksc::new(Modifiers::SHIFT, Key::Comma);
 
 pub const RESET_VIEW: Ksc = Ksc::new(Modifiers::NONE, Key::R);
+pub const RESET_VIEW_SETTINGS: Ksc = Ksc::new(Modifiers::SHIFT, Key::R);
+pub const LOCK_VIEW: Ksc = Ksc::new(Modifiers::SHIFT, Key::L);
+pub const ANNOTATE_TOGGLE: Ksc = Ksc::new(Modifiers::NONE, Key::D);
+pub const ANNOTATION_UNDO: Ksc = Ksc::new(Modifiers::COMMAND, Key::Z);
+pub const CROP_EXPORT_TOGGLE: Ksc = Ksc::new(Modifiers::SHIFT, Key::E);
+pub const CROP_EXPORT_SAVE: Ksc = Ksc::new(Modifiers::NONE, Key::Enter);
+pub const LABELING_TOGGLE: Ksc = Ksc::new(Modifiers::SHIFT, Key::T);
+/// `0` to `5` stars; in labeling mode the number keys set labels instead.
+pub const RATING_SHORTCUTS: [Ksc; 6] = [
+    Ksc::new(Modifiers::NONE, Key::Num0),
+    Ksc::new(Modifiers::NONE, Key::Num1),
+    Ksc::new(Modifiers::NONE, Key::Num2),
+    Ksc::new(Modifiers::NONE, Key::Num3),
+    Ksc::new(Modifiers::NONE, Key::Num4),
+    Ksc::new(Modifiers::NONE, Key::Num5),
+];
+pub const FLAG_PICK: Ksc = Ksc::new(Modifiers::NONE, Key::P);
+pub const FLAG_REJECT: Ksc = Ksc::new(Modifiers::NONE, Key::X);
+pub const FLAG_CLEAR: Ksc = Ksc::new(Modifiers::NONE, Key::U);
 
 pub const FULLSCREEN_TOGGLE: Ksc = Ksc::new(Modifiers::NONE, Key::F11);
 
@@ -50,6 +89,7 @@ const fn preset_shortcuts(modifiers: Modifiers) -> [Ksc; PRESET_KEYS.len()] {
 
 pub const PRESET_SAVE_SHORTCUTS: [Ksc; PRESET_KEYS.len()] = preset_shortcuts(Modifiers::COMMAND);
 pub const PRESET_APPLY_SHORTCUTS: [Ksc; PRESET_KEYS.len()] = preset_shortcuts(Modifiers::ALT);
+pub const LABEL_SHORTCUTS: [Ksc; PRESET_KEYS.len()] = preset_shortcuts(Modifiers::NONE);
 
 pub const MODIFIER_NAMES: ModifierNames = ModifierNames {
     is_short: false,
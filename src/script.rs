@@ -0,0 +1,328 @@
+//! Lua scripting for automating repetitive viewer workflows.
+//!
+//! Scripts run on a worker thread. Every `edolview.*` call is sent to the UI thread as a [`ScriptCommand`] and
+//! blocks until the UI thread has applied it, so scripts observe the same state the viewer shows.
+
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+};
+
+use color_eyre::eyre::{eyre, Result};
+use eframe::egui;
+use mlua::{HookTriggers, Lua, MultiValue};
+
+use crate::{
+    model::{AppState, ComparisonMode, Recti},
+    util::math_ext::vec2i,
+};
+
+pub enum ScriptCommand {
+    Open(PathBuf),
+    Assets,
+    SetPrimary(String),
+    SetSecondary(Option<String>),
+    SetComparison(ComparisonMode),
+    Selection,
+    Select(Recti),
+    Get(String),
+    Set(String, f64),
+    /// Save the displayed selection (or whole image) like File > Save Image.
+    Export(PathBuf),
+}
+
+pub enum ScriptReply {
+    None,
+    Hash(String),
+    Assets(Vec<(String, String)>),
+    Rect(Recti),
+    Number(f64),
+}
+
+pub enum ScriptEvent {
+    Output(String),
+    Request(ScriptCommand, mpsc::Sender<Result<ScriptReply, String>>),
+    Finished(Result<(), String>),
+}
+
+/// Display parameters readable and writable through `edolview.get` / `edolview.set`.
+pub const SCRIPT_PARAMS: &[&str] = &[
+    "exposure",
+    "gamma",
+    "offset",
+    "min",
+    "max",
+    "auto_minmax",
+    "channel",
+    "lut_strength",
+    "comparison_blend",
+];
+
+#[derive(Clone)]
+struct Bridge {
+    tx: mpsc::Sender<ScriptEvent>,
+    ctx: egui::Context,
+}
+
+impl Bridge {
+    fn send(&self, event: ScriptEvent) {
+        let _ = self.tx.send(event);
+        self.ctx.request_repaint();
+    }
+
+    fn call(&self, command: ScriptCommand) -> mlua::Result<ScriptReply> {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.send(ScriptEvent::Request(command, reply_tx));
+        reply_rx
+            .recv()
+            .map_err(|_| mlua::Error::runtime("Viewer is no longer available"))?
+            .map_err(mlua::Error::runtime)
+    }
+}
+
+/// Run `source` on a worker thread. Setting `stop` aborts the script at the next instruction check.
+pub fn run_script(source: String, tx: mpsc::Sender<ScriptEvent>, stop: Arc<AtomicBool>, ctx: egui::Context) {
+    std::thread::spawn(move || {
+        let bridge = Bridge { tx, ctx };
+        let result = execute(&source, bridge.clone(), stop).map_err(|e| e.to_string());
+        bridge.send(ScriptEvent::Finished(result));
+    });
+}
+
+fn execute(source: &str, bridge: Bridge, stop: Arc<AtomicBool>) -> mlua::Result<()> {
+    let lua = Lua::new();
+    lua.set_hook(HookTriggers::new().every_nth_instruction(1000), move |_, _| {
+        if stop.load(Ordering::Relaxed) {
+            Err(mlua::Error::runtime("Script stopped"))
+        } else {
+            Ok(())
+        }
+    });
+
+    let globals = lua.globals();
+    let output = bridge.clone();
+    globals.set(
+        "print",
+        lua.create_function(move |lua, args: MultiValue| {
+            let tostring: mlua::Function = lua.globals().get("tostring")?;
+            let line = args
+                .into_iter()
+                .map(|value| tostring.call::<_, String>(value))
+                .collect::<mlua::Result<Vec<_>>>()?
+                .join("\t");
+            output.send(ScriptEvent::Output(line));
+            Ok(())
+        })?,
+    )?;
+
+    let api = lua.create_table()?;
+    let b = bridge.clone();
+    api.set(
+        "open",
+        lua.create_function(move |_, path: String| match b.call(ScriptCommand::Open(path.into()))? {
+            ScriptReply::Hash(hash) => Ok(hash),
+            _ => Ok(String::new()),
+        })?,
+    )?;
+    let b = bridge.clone();
+    api.set(
+        "assets",
+        lua.create_function(move |lua, ()| {
+            let list = lua.create_table()?;
+            if let ScriptReply::Assets(assets) = b.call(ScriptCommand::Assets)? {
+                for (hash, name) in assets {
+                    let entry = lua.create_table()?;
+                    entry.set("hash", hash)?;
+                    entry.set("name", name)?;
+                    list.push(entry)?;
+                }
+            }
+            Ok(list)
+        })?,
+    )?;
+    let b = bridge.clone();
+    api.set(
+        "set_primary",
+        lua.create_function(move |_, hash: String| b.call(ScriptCommand::SetPrimary(hash)).map(|_| ()))?,
+    )?;
+    let b = bridge.clone();
+    api.set(
+        "set_secondary",
+        lua.create_function(move |_, hash: Option<String>| b.call(ScriptCommand::SetSecondary(hash)).map(|_| ()))?,
+    )?;
+    let b = bridge.clone();
+    api.set(
+        "compare",
+        lua.create_function(move |_, mode: String| {
+            let mode = match mode.to_ascii_lowercase().as_str() {
+                "diff" => ComparisonMode::Diff,
+                "blend" => ComparisonMode::Blend,
+                "split" => ComparisonMode::Split,
+                "anaglyph" => ComparisonMode::Anaglyph,
+                _ => return Err(mlua::Error::runtime(format!("Unknown comparison mode '{mode}'"))),
+            };
+            b.call(ScriptCommand::SetComparison(mode)).map(|_| ())
+        })?,
+    )?;
+    let b = bridge.clone();
+    api.set(
+        "selection",
+        lua.create_function(move |_, ()| match b.call(ScriptCommand::Selection)? {
+            ScriptReply::Rect(rect) => Ok((rect.min.x, rect.min.y, rect.width(), rect.height())),
+            _ => Ok((0, 0, 0, 0)),
+        })?,
+    )?;
+    let b = bridge.clone();
+    api.set(
+        "select",
+        lua.create_function(move |_, (x, y, w, h): (i32, i32, i32, i32)| {
+            b.call(ScriptCommand::Select(Recti::from_min_size(vec2i(x, y), vec2i(w, h))))
+                .map(|_| ())
+        })?,
+    )?;
+    let b = bridge.clone();
+    api.set(
+        "get",
+        lua.create_function(move |_, name: String| match b.call(ScriptCommand::Get(name))? {
+            ScriptReply::Number(value) => Ok(value),
+            _ => Ok(0.0),
+        })?,
+    )?;
+    let b = bridge.clone();
+    api.set(
+        "set",
+        lua.create_function(move |_, (name, value): (String, f64)| {
+            b.call(ScriptCommand::Set(name, value)).map(|_| ())
+        })?,
+    )?;
+    let b = bridge.clone();
+    api.set(
+        "export",
+        lua.create_function(move |_, path: String| b.call(ScriptCommand::Export(path.into())).map(|_| ()))?,
+    )?;
+    api.set("params", lua.create_sequence_from(SCRIPT_PARAMS.iter().copied())?)?;
+    globals.set("edolview", api)?;
+
+    lua.load(source).set_name("script").exec()
+}
+
+impl ScriptCommand {
+    /// Apply a command that only touches the app state. `Export` needs the viewer and is handled by the caller.
+    pub fn apply(self, state: &mut AppState) -> Result<ScriptReply> {
+        match self {
+            Self::Open(path) => {
+                state.load_from_path(path)?;
+                let hash = state.asset_primary.as_ref().map(|asset| asset.hash().to_string());
+                Ok(hash.map(ScriptReply::Hash).unwrap_or(ScriptReply::None))
+            }
+            Self::Assets => Ok(ScriptReply::Assets(
+                state
+                    .assets
+                    .iter()
+                    .map(|(hash, asset)| (hash.clone(), asset.name().to_string()))
+                    .collect(),
+            )),
+            Self::SetPrimary(hash) => {
+                if !state.assets.contains_key(&hash) {
+                    return Err(eyre!("Unknown asset '{hash}'"));
+                }
+                state.set_asset_primary_by_hash(&hash);
+                Ok(ScriptReply::None)
+            }
+            Self::SetSecondary(Some(hash)) => {
+                if !state.assets.contains_key(&hash) {
+                    return Err(eyre!("Unknown asset '{hash}'"));
+                }
+                state.set_asset_secondary_by_hash(&hash);
+                Ok(ScriptReply::None)
+            }
+            Self::SetSecondary(None) => {
+                state.set_secondary_asset(None);
+                Ok(ScriptReply::None)
+            }
+            Self::SetComparison(mode) => {
+                state.comparison_mode = mode;
+                state.update_asset();
+                Ok(ScriptReply::None)
+            }
+            Self::Selection => Ok(ScriptReply::Rect(state.marquee_rect)),
+            Self::Select(rect) => {
+                state.set_marquee_rect(rect);
+                Ok(ScriptReply::Rect(state.marquee_rect))
+            }
+            Self::Get(name) => {
+                let params = &state.shader_params;
+                let value = match name.as_str() {
+                    "exposure" => params.exposure,
+                    "gamma" => params.gamma,
+                    "offset" => params.offset,
+                    "min" => params.min_v,
+                    "max" => params.max_v,
+                    "auto_minmax" => params.auto_minmax as i32 as f32,
+                    "channel" => state.channel_index as f32,
+                    "lut_strength" => params.lut_strength,
+                    "comparison_blend" => state.comparison_blend,
+                    _ => return Err(eyre!("Unknown parameter '{name}'")),
+                };
+                Ok(ScriptReply::Number(value as f64))
+            }
+            Self::Set(name, value) => {
+                let params = &mut state.shader_params;
+                let v = value as f32;
+                match name.as_str() {
+                    "exposure" => params.exposure = v,
+                    "gamma" => params.gamma = v,
+                    "offset" => params.offset = v,
+                    "min" => params.min_v = v,
+                    "max" => params.max_v = v,
+                    "auto_minmax" => params.auto_minmax = v != 0.0,
+                    "channel" => state.channel_index = value as i32,
+                    "lut_strength" => params.lut_strength = v.clamp(0.0, 1.0),
+                    "comparison_blend" => state.comparison_blend = v.clamp(0.0, 1.0),
+                    _ => return Err(eyre!("Unknown parameter '{name}'")),
+                }
+                if name == "comparison_blend" {
+                    state.update_asset();
+                }
+                Ok(ScriptReply::None)
+            }
+            Self::Export(_) => Err(eyre!("Export must be handled by the viewer")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_commands_round_trip_through_app_state() {
+        let mut state = AppState::empty();
+        let (tx, rx) = mpsc::channel();
+        let source = "edolview.set('exposure', 1.5)\nprint(edolview.get('exposure'), pcall(edolview.get, 'unknown'))";
+        run_script(
+            source.to_string(),
+            tx,
+            Arc::new(AtomicBool::new(false)),
+            egui::Context::default(),
+        );
+
+        let mut output = Vec::new();
+        let result = loop {
+            match rx.recv().unwrap() {
+                ScriptEvent::Output(line) => output.push(line),
+                ScriptEvent::Request(command, reply) => {
+                    let _ = reply.send(command.apply(&mut state).map_err(|e| e.to_string()));
+                }
+                ScriptEvent::Finished(result) => break result,
+            }
+        };
+        assert_eq!(result, Ok(()));
+        assert_eq!(output.len(), 1);
+        assert!(output[0].starts_with("1.5\tfalse\t"), "{output:?}");
+        assert_eq!(state.shader_params.exposure, 1.5);
+    }
+}
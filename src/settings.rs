@@ -1,8 +1,16 @@
-use std::{fs, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
+use eframe::egui;
 use serde::{Deserialize, Serialize};
 
-use crate::ui::gpu::ShaderParams;
+use crate::{
+    model::{DecodeLimits, PixelType, FILE_DRAG_SUPPORTED, LABEL_COUNT},
+    ui::gpu::{MagnificationFilter, ShaderParams},
+};
 
 pub const VIEW_PRESET_COUNT: usize = 9;
 
@@ -24,6 +32,666 @@ pub enum AngleDisplayUnit {
     Radians,
 }
 
+/// How the sidebar image list is split into collapsible groups.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[derive(Default)]
+pub enum AssetGrouping {
+    #[default]
+    None,
+    Directory,
+    NamePrefix,
+    Manual,
+    Content,
+}
+
+impl AssetGrouping {
+    pub const ALL: [Self; 5] = [
+        Self::None,
+        Self::Directory,
+        Self::NamePrefix,
+        Self::Manual,
+        Self::Content,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::None => "No groups",
+            Self::Directory => "By directory",
+            Self::NamePrefix => "By name prefix",
+            Self::Manual => "Manual",
+            Self::Content => "By identical pixels",
+        }
+    }
+}
+
+/// What happens to the source file's EXIF, XMP and ICC metadata when a render is saved or copied as a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[derive(Default)]
+pub enum ExportMetadata {
+    #[default]
+    Strip,
+    Preserve,
+}
+
+impl ExportMetadata {
+    pub const ALL: [Self; 2] = [Self::Strip, Self::Preserve];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Strip => "Strip metadata",
+            Self::Preserve => "Keep source metadata",
+        }
+    }
+}
+
+/// What image saves and copies write.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportData {
+    /// The image as displayed, with the current display settings, as 8-bit RGBA.
+    #[default]
+    Display,
+    /// The stored samples in the source's pixel type: 8 and 16-bit images as PNG, others as OpenEXR.
+    Original,
+    /// The stored values as 32-bit floats in OpenEXR.
+    RawFloat,
+}
+
+impl ExportData {
+    pub const ALL: [Self; 3] = [Self::Display, Self::Original, Self::RawFloat];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Display => "Display-mapped 8-bit",
+            Self::Original => "Original data type",
+            Self::RawFloat => "Raw float data",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Display => "What you see: exposure, colormap and the other display settings baked into 8-bit RGBA",
+            Self::Original => "Stored samples without display mapping: 8 and 16-bit images as PNG, others as OpenEXR",
+            Self::RawFloat => "Stored values as 32-bit float OpenEXR, without display mapping",
+        }
+    }
+
+    /// File extension of sample exports of `dtype` images, or `None` for display renders, which are PNG or JPEG.
+    pub fn sample_extension(self, dtype: PixelType) -> Option<&'static str> {
+        match self {
+            Self::Display => None,
+            Self::Original if matches!(dtype, PixelType::U8 | PixelType::U16) => Some("png"),
+            Self::Original | Self::RawFloat => Some("exr"),
+        }
+    }
+}
+
+/// Output size of display exports. Sample exports are always at image pixel size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportSize {
+    /// One output pixel per image pixel.
+    #[default]
+    Original,
+    /// The size shown on screen at the current zoom.
+    CurrentZoom,
+    /// [`ExportOptions::scale_percent`] of the image pixel size.
+    Scale,
+    /// [`ExportOptions::resolution`], with the region fitted or cropped as [`ExportOptions::aspect`] says.
+    Resolution,
+}
+
+impl ExportSize {
+    pub const ALL: [Self; 4] = [Self::Original, Self::CurrentZoom, Self::Scale, Self::Resolution];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Original => "Original size",
+            Self::CurrentZoom => "Current zoom",
+            Self::Scale => "Scale",
+            Self::Resolution => "Resolution",
+        }
+    }
+}
+
+/// How a region whose aspect ratio differs from the target resolution is sized.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AspectFit {
+    /// Fit the whole region inside the resolution; the output is smaller along one side.
+    #[default]
+    Fit,
+    /// Fill the exact resolution, cropping the centered region along one side.
+    Fill,
+}
+
+impl AspectFit {
+    pub const ALL: [Self; 2] = [Self::Fit, Self::Fill];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Fit => "Fit",
+            Self::Fill => "Fill",
+        }
+    }
+}
+
+/// Size and placement of a display export: the output is `width`×`height` pixels of the region scaled by `scale`,
+/// starting `offset` output pixels into it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExportLayout {
+    pub width: u32,
+    pub height: u32,
+    pub scale: f32,
+    pub offset: (f32, f32),
+}
+
+/// Choices shared by every image copy and save action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub data: ExportData,
+    pub size: ExportSize,
+    /// Output size for [`ExportSize::Scale`], in percent of the image pixel size.
+    pub scale_percent: u32,
+    /// Target `[width, height]` for [`ExportSize::Resolution`].
+    pub resolution: [u32; 2],
+    pub aspect: AspectFit,
+    /// Metadata of display exports; sample exports carry none.
+    pub metadata: ExportMetadata,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            data: ExportData::default(),
+            size: ExportSize::default(),
+            scale_percent: 200,
+            resolution: [512, 512],
+            aspect: AspectFit::default(),
+            metadata: ExportMetadata::default(),
+        }
+    }
+}
+
+impl ExportOptions {
+    /// Layout of a display export of a `width`×`height` region viewed at `zoom`.
+    pub fn layout(&self, (width, height): (u32, u32), zoom: f32) -> ExportLayout {
+        let (width, height) = (width.max(1) as f32, height.max(1) as f32);
+        let scaled = |scale: f32| ExportLayout {
+            width: (width * scale).round().max(1.0) as u32,
+            height: (height * scale).round().max(1.0) as u32,
+            scale,
+            offset: (0.0, 0.0),
+        };
+        match self.size {
+            ExportSize::Original => scaled(1.0),
+            ExportSize::CurrentZoom => scaled(zoom),
+            ExportSize::Scale => scaled(self.scale_percent.max(1) as f32 / 100.0),
+            ExportSize::Resolution => {
+                let [target_w, target_h] = self.resolution.map(|side| side.max(1));
+                let (scale_x, scale_y) = (target_w as f32 / width, target_h as f32 / height);
+                match self.aspect {
+                    AspectFit::Fit => scaled(scale_x.min(scale_y)),
+                    AspectFit::Fill => {
+                        let scale = scale_x.max(scale_y);
+                        ExportLayout {
+                            width: target_w,
+                            height: target_h,
+                            scale,
+                            offset: (
+                                (width * scale - target_w as f32) / 2.0,
+                                (height * scale - target_h as f32) / 2.0,
+                            ),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Short description of the display export size, for buttons.
+    pub fn size_label(&self) -> String {
+        match self.size {
+            ExportSize::Scale => format!("{}%", self.scale_percent),
+            ExportSize::Resolution => format!(
+                "{}\u{d7}{} {}",
+                self.resolution[0],
+                self.resolution[1],
+                self.aspect.label().to_lowercase()
+            ),
+            size => size.label().to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[derive(Default)]
+pub enum BackgroundKind {
+    #[default]
+    Checker,
+    Black,
+    White,
+    Gray,
+    Custom,
+}
+
+impl BackgroundKind {
+    pub const ALL: [Self; 5] = [Self::Checker, Self::Black, Self::White, Self::Gray, Self::Custom];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Checker => "Checkerboard",
+            Self::Black => "Black",
+            Self::White => "White",
+            Self::Gray => "Gray",
+            Self::Custom => "Custom color",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackgroundSettings {
+    pub kind: BackgroundKind,
+    /// Checker cell size in physical pixels.
+    pub checker_size: u32,
+    /// Checker colors; `None` follows the theme.
+    pub checker_colors: Option<[[u8; 3]; 2]>,
+    pub custom_color: [u8; 3],
+}
+
+impl Default for BackgroundSettings {
+    fn default() -> Self {
+        Self {
+            kind: BackgroundKind::Checker,
+            checker_size: 16,
+            checker_colors: None,
+            custom_color: [64, 64, 64],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[derive(Default)]
+pub enum PixelValueFormat {
+    /// Floats with adaptive precision, integer data as stored.
+    #[default]
+    Auto,
+    /// Normalized value as a float, i.e. 0..1 for integer data.
+    Float,
+    /// Integer data as stored, floats scaled to 0..255.
+    ScaledInt,
+    /// Hexadecimal of the scaled integer, padded to the data width.
+    Hex,
+}
+
+impl PixelValueFormat {
+    pub const ALL: [Self; 4] = [Self::Auto, Self::Float, Self::ScaledInt, Self::Hex];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Auto => "Auto",
+            Self::Float => "Normalized float",
+            Self::ScaledInt => "Scaled integer",
+            Self::Hex => "Hexadecimal",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PixelValueSettings {
+    pub format: PixelValueFormat,
+    /// Only show the value of the selected channel when a single channel is displayed.
+    pub selected_channel_only: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[derive(Default)]
+pub enum PythonSnippetStyle {
+    #[default]
+    Numpy,
+    Matplotlib,
+    OpenCv,
+}
+
+impl PythonSnippetStyle {
+    pub const ALL: [Self; 3] = [Self::Numpy, Self::Matplotlib, Self::OpenCv];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Numpy => "NumPy crop",
+            Self::Matplotlib => "Matplotlib crop and show",
+            Self::OpenCv => "OpenCV ROI",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PythonSnippetSettings {
+    pub style: PythonSnippetStyle,
+    pub variable_name: String,
+    /// Include the import and load lines when the image has a file path.
+    pub include_load: bool,
+}
+
+impl Default for PythonSnippetSettings {
+    fn default() -> Self {
+        Self {
+            style: PythonSnippetStyle::Numpy,
+            variable_name: "img".to_string(),
+            include_load: true,
+        }
+    }
+}
+
+/// Limits on how many images received over the socket stay in the image list.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SocketHistorySettings {
+    /// Replace the asset with the same name instead of adding another entry.
+    pub replace_same_name: bool,
+    /// Entries kept per name when not replacing; 0 keeps all of them.
+    pub max_per_name: usize,
+    /// Pixel memory of all socket assets, in MiB, before the oldest are evicted; 0 disables the budget.
+    pub memory_budget_mib: usize,
+}
+
+impl Default for SocketHistorySettings {
+    fn default() -> Self {
+        Self {
+            replace_same_name: true,
+            max_per_name: 20,
+            memory_budget_mib: 2048,
+        }
+    }
+}
+
+/// Regression check setup: an image opened from `render_dir` is compared against the file at the same relative
+/// path under `reference_dir`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoCompareSettings {
+    pub enabled: bool,
+    pub render_dir: Option<PathBuf>,
+    pub reference_dir: Option<PathBuf>,
+}
+
+impl AutoCompareSettings {
+    /// Reference file for `path`, when auto-compare is enabled and `path` is inside the render directory.
+    pub fn reference_path(&self, path: &Path) -> Option<PathBuf> {
+        if !self.enabled {
+            return None;
+        }
+        let relative = path.strip_prefix(self.render_dir.as_ref()?).ok()?;
+        Some(self.reference_dir.as_ref()?.join(relative))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MouseButton {
+    Primary,
+    Secondary,
+    Middle,
+}
+
+impl MouseButton {
+    pub const ALL: [Self; 3] = [Self::Primary, Self::Secondary, Self::Middle];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Primary => "Left",
+            Self::Secondary => "Right",
+            Self::Middle => "Middle",
+        }
+    }
+
+    pub fn pointer_button(self) -> egui::PointerButton {
+        match self {
+            Self::Primary => egui::PointerButton::Primary,
+            Self::Secondary => egui::PointerButton::Secondary,
+            Self::Middle => egui::PointerButton::Middle,
+        }
+    }
+}
+
+/// Modifier key a mouse binding requires.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BindingModifier {
+    /// Applies whatever modifiers are held, unless a binding requiring a held modifier matches.
+    Any,
+    Shift,
+    /// Ctrl, or Cmd on macOS.
+    Command,
+    Alt,
+}
+
+impl BindingModifier {
+    pub const ALL: [Self; 4] = [Self::Any, Self::Shift, Self::Command, Self::Alt];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Any => "Any",
+            Self::Shift => "Shift",
+            Self::Command => "Ctrl/Cmd",
+            Self::Alt => "Alt",
+        }
+    }
+
+    fn is_held(self, modifiers: egui::Modifiers) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Shift => modifiers.shift,
+            Self::Command => modifiers.command,
+            Self::Alt => modifiers.alt,
+        }
+    }
+}
+
+/// What dragging in the viewer does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DragAction {
+    Pan,
+    /// Draw a marquee, or trace with the lasso tool.
+    Select,
+    /// Read the pixel under the pointer and copy its value on release.
+    ColorPick,
+    /// Drag the image, or the selection, out of the window as a file into another application.
+    DragOut,
+    Nothing,
+}
+
+impl DragAction {
+    pub const ALL: [Self; 5] = [Self::Pan, Self::Select, Self::ColorPick, Self::DragOut, Self::Nothing];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Pan => "Pan",
+            Self::Select => "Select",
+            Self::ColorPick => "Pick color",
+            Self::DragOut => "Drag out",
+            Self::Nothing => "Nothing",
+        }
+    }
+
+    /// Whether this platform can perform the action; dragging out needs a native drag source.
+    pub fn is_available(self) -> bool {
+        self != Self::DragOut || FILE_DRAG_SUPPORTED
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MouseBinding {
+    pub button: MouseButton,
+    pub modifier: BindingModifier,
+    pub action: DragAction,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MouseBindings {
+    pub bindings: Vec<MouseBinding>,
+}
+
+impl Default for MouseBindings {
+    /// Shift-drag selects and a plain drag pans, with every button; where dragging out is supported, Alt-drag with
+    /// the primary button drags the image out of the window.
+    fn default() -> Self {
+        let drag_out = FILE_DRAG_SUPPORTED.then_some(MouseBinding {
+            button: MouseButton::Primary,
+            modifier: BindingModifier::Alt,
+            action: DragAction::DragOut,
+        });
+        let bindings = drag_out
+            .into_iter()
+            .chain(MouseButton::ALL.into_iter().flat_map(|button| {
+                [
+                    MouseBinding {
+                        button,
+                        modifier: BindingModifier::Shift,
+                        action: DragAction::Select,
+                    },
+                    MouseBinding {
+                        button,
+                        modifier: BindingModifier::Any,
+                        action: DragAction::Pan,
+                    },
+                ]
+            }))
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl MouseBindings {
+    /// Action of a drag with `button` while `modifiers` are held. Bindings requiring a held modifier come before
+    /// those for any modifiers; unbound drags do nothing. Bindings of actions this platform lacks, e.g. from
+    /// settings written on another platform, are skipped.
+    pub fn action(&self, button: MouseButton, modifiers: egui::Modifiers) -> DragAction {
+        let matching = |binding: &&MouseBinding| {
+            binding.button == button && binding.modifier.is_held(modifiers) && binding.action.is_available()
+        };
+        self.bindings
+            .iter()
+            .filter(matching)
+            .find(|binding| binding.modifier != BindingModifier::Any)
+            .or_else(|| self.bindings.iter().find(matching))
+            .map_or(DragAction::Nothing, |binding| binding.action)
+    }
+}
+
+/// Zoom, pan and marquee of a file when it was last shown.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FileView {
+    pub path: PathBuf,
+    pub zoom_level: f32,
+    pub pan: [f32; 2],
+    /// Marquee as `[x, y, width, height]`, empty when nothing was selected.
+    pub marquee: [i32; 4],
+}
+
+/// Views of recently shown files, least recently shown first, so reopening a file returns to where it was left.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileViewHistory {
+    pub enabled: bool,
+    /// Files remembered before the least recently shown one is forgotten.
+    pub max_entries: usize,
+    pub entries: Vec<FileView>,
+}
+
+impl Default for FileViewHistory {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_entries: 200,
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl FileViewHistory {
+    pub fn get(&self, path: &Path) -> Option<&FileView> {
+        self.entries.iter().rev().find(|view| view.path == path)
+    }
+
+    /// Remember `view` as the most recent one, forgetting the oldest entries beyond `max_entries`.
+    pub fn remember(&mut self, view: FileView) {
+        self.entries.retain(|entry| entry.path != view.path);
+        self.entries.push(view);
+        self.truncate();
+    }
+
+    pub fn truncate(&mut self) {
+        let excess = self.entries.len().saturating_sub(self.max_entries);
+        self.entries.drain(..excess);
+    }
+}
+
+/// A folder whose new images are added to the image list as they are written.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchFolder {
+    pub path: PathBuf,
+    /// Comma-separated file name globs such as `*.exr, beauty_*`; empty accepts every supported image.
+    #[serde(default)]
+    pub include: String,
+    /// Show each new image as soon as it is loaded.
+    #[serde(default)]
+    pub auto_select: bool,
+}
+
+/// Names of the labels assigned with the number keys in labeling mode.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LabelSettings {
+    pub names: [String; LABEL_COUNT],
+}
+
+impl Default for LabelSettings {
+    fn default() -> Self {
+        Self {
+            names: std::array::from_fn(|index| format!("label{}", index + 1)),
+        }
+    }
+}
+
+impl LabelSettings {
+    /// Names for display and export, with `labelN` in place of empty names.
+    pub fn resolved_names(&self) -> [String; LABEL_COUNT] {
+        std::array::from_fn(|index| match self.names[index].trim() {
+            "" => format!("label{}", index + 1),
+            name => name.to_string(),
+        })
+    }
+}
+
+/// Where the crop export mode writes marquee crops.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CropExportSettings {
+    pub directory: Option<PathBuf>,
+    /// File names are the prefix and a zero-padded number, such as `crop_00012.png`.
+    pub prefix: String,
+}
+
+impl Default for CropExportSettings {
+    fn default() -> Self {
+        Self {
+            directory: None,
+            prefix: "crop_".to_string(),
+        }
+    }
+}
+
 impl ExternalOpenMode {
     pub fn label(self) -> &'static str {
         match self {
@@ -46,25 +714,91 @@ pub struct AppSettings {
     pub ui_state: PersistentUiState,
     #[serde(default = "default_view_presets")]
     pub view_presets: Vec<Option<ViewPreset>>,
+    #[serde(default)]
+    pub python_snippet: PythonSnippetSettings,
+    #[serde(default)]
+    pub socket_history: SocketHistorySettings,
+    /// ICC profile of the monitor; when set, the viewer output is converted from sRGB to it.
+    #[serde(default)]
+    pub monitor_profile: Option<PathBuf>,
+    #[serde(default)]
+    pub auto_compare: AutoCompareSettings,
+    #[serde(default)]
+    pub watch_folders: Vec<WatchFolder>,
+    #[serde(default)]
+    pub crop_export: CropExportSettings,
+    #[serde(default)]
+    pub labels: LabelSettings,
+    #[serde(default)]
+    pub decode_limits: DecodeLimits,
+    /// Ease the view into place when fitting or centering instead of jumping.
+    #[serde(default = "default_animate_view")]
+    pub animate_view: bool,
+    /// Align image pixels with device pixels at integer zooms.
+    #[serde(default = "default_snap_to_pixel_grid")]
+    pub snap_to_pixel_grid: bool,
+    #[serde(default)]
+    pub file_views: FileViewHistory,
+    #[serde(default)]
+    pub mouse_bindings: MouseBindings,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ViewPreset {
     pub colormap_rgb: String,
     pub colormap_mono: String,
     pub shader_params: ShaderParams,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// Visibility of the viewer overlays, remembered separately for each asset type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OverlayVisibility {
+    pub background: bool,
+    pub pixel_value: bool,
+    pub crosshair: bool,
+    pub image_border: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PersistentUiState {
     pub is_show_background: bool,
     pub is_show_pixel_value: bool,
     pub is_show_crosshair: bool,
+    #[serde(default)]
+    pub is_show_image_border: bool,
     pub is_show_sidebar: bool,
     pub is_show_statusbar: bool,
-    pub copy_use_original_size: bool,
+    #[serde(default)]
+    pub export_options: ExportOptions,
+    #[serde(default)]
+    pub lock_view: bool,
     #[serde(default)]
     pub angle_display_unit: AngleDisplayUnit,
+    #[serde(default)]
+    pub background: BackgroundSettings,
+    #[serde(default)]
+    pub overlays_by_asset_type: BTreeMap<String, OverlayVisibility>,
+    #[serde(default)]
+    pub pixel_value: PixelValueSettings,
+    /// `.cube` file applied as a look LUT, reloaded on startup.
+    #[serde(default)]
+    pub look_lut: Option<PathBuf>,
+    #[serde(default)]
+    pub asset_grouping: AssetGrouping,
+    #[serde(default)]
+    pub is_show_asset_thumbnails: bool,
+    #[serde(default)]
+    pub magnification_filter: MagnificationFilter,
+    /// Colormaps and display settings of the last session, without per-image calibration.
+    #[serde(default)]
+    pub display: Option<ViewPreset>,
+    #[serde(default)]
+    pub show_histogram: bool,
+    #[serde(default)]
+    pub show_statistics: bool,
+    /// Inner size of the main window in points when it was last closed unmaximized.
+    #[serde(default)]
+    pub window_size: Option<[f32; 2]>,
 }
 
 impl Default for AppSettings {
@@ -74,6 +808,18 @@ impl Default for AppSettings {
             integral_table_precompute: default_integral_table_precompute(),
             ui_state: PersistentUiState::default(),
             view_presets: default_view_presets(),
+            python_snippet: PythonSnippetSettings::default(),
+            socket_history: SocketHistorySettings::default(),
+            monitor_profile: None,
+            auto_compare: AutoCompareSettings::default(),
+            watch_folders: Vec::new(),
+            crop_export: CropExportSettings::default(),
+            labels: LabelSettings::default(),
+            decode_limits: DecodeLimits::default(),
+            animate_view: default_animate_view(),
+            snap_to_pixel_grid: default_snap_to_pixel_grid(),
+            file_views: FileViewHistory::default(),
+            mouse_bindings: MouseBindings::default(),
         }
     }
 }
@@ -84,10 +830,23 @@ impl Default for PersistentUiState {
             is_show_background: true,
             is_show_pixel_value: true,
             is_show_crosshair: false,
+            is_show_image_border: false,
             is_show_sidebar: true,
             is_show_statusbar: true,
-            copy_use_original_size: true,
+            export_options: ExportOptions::default(),
+            lock_view: false,
             angle_display_unit: AngleDisplayUnit::Degrees,
+            background: BackgroundSettings::default(),
+            overlays_by_asset_type: BTreeMap::new(),
+            pixel_value: PixelValueSettings::default(),
+            look_lut: None,
+            asset_grouping: AssetGrouping::default(),
+            is_show_asset_thumbnails: false,
+            magnification_filter: MagnificationFilter::default(),
+            display: None,
+            show_histogram: false,
+            show_statistics: false,
+            window_size: None,
         }
     }
 }
@@ -146,3 +905,125 @@ fn default_view_presets() -> Vec<Option<ViewPreset>> {
 const fn default_integral_table_precompute() -> bool {
     true
 }
+
+const fn default_animate_view() -> bool {
+    true
+}
+
+const fn default_snap_to_pixel_grid() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_view(path: &str) -> FileView {
+        FileView {
+            path: PathBuf::from(path),
+            zoom_level: 1.0,
+            pan: [0.0, 0.0],
+            marquee: [0; 4],
+        }
+    }
+
+    fn paths(history: &FileViewHistory) -> Vec<&Path> {
+        history.entries.iter().map(|view| view.path.as_path()).collect()
+    }
+
+    #[test]
+    fn file_view_history_keeps_the_most_recent_views() {
+        let mut history = FileViewHistory {
+            max_entries: 2,
+            ..FileViewHistory::default()
+        };
+        for path in ["a.exr", "b.exr", "a.exr"] {
+            history.remember(file_view(path));
+        }
+        assert_eq!(paths(&history), [Path::new("b.exr"), Path::new("a.exr")]);
+
+        history.remember(FileView {
+            zoom_level: 4.0,
+            ..file_view("c.exr")
+        });
+        assert_eq!(paths(&history), [Path::new("a.exr"), Path::new("c.exr")]);
+        assert_eq!(history.get(Path::new("c.exr")).map(|view| view.zoom_level), Some(4.0));
+        assert!(history.get(Path::new("b.exr")).is_none());
+
+        history.max_entries = 1;
+        history.truncate();
+        assert_eq!(paths(&history), [Path::new("c.exr")]);
+        history.max_entries = 0;
+        history.truncate();
+        assert!(history.entries.is_empty());
+    }
+
+    #[test]
+    fn mouse_bindings_prefer_held_modifiers() {
+        let mut bindings = MouseBindings::default();
+        bindings.bindings.push(MouseBinding {
+            button: MouseButton::Middle,
+            modifier: BindingModifier::Alt,
+            action: DragAction::ColorPick,
+        });
+        let shift = egui::Modifiers::SHIFT;
+        assert_eq!(bindings.action(MouseButton::Primary, egui::Modifiers::NONE), DragAction::Pan);
+        assert_eq!(bindings.action(MouseButton::Primary, shift), DragAction::Select);
+        let alt_drag = if FILE_DRAG_SUPPORTED {
+            DragAction::DragOut
+        } else {
+            DragAction::Pan
+        };
+        assert_eq!(bindings.action(MouseButton::Primary, egui::Modifiers::ALT), alt_drag);
+        assert_eq!(
+            bindings.action(MouseButton::Middle, egui::Modifiers::ALT),
+            DragAction::ColorPick
+        );
+        assert_eq!(bindings.action(MouseButton::Middle, egui::Modifiers::CTRL), DragAction::Pan);
+
+        bindings.bindings.retain(|binding| binding.button != MouseButton::Secondary);
+        assert_eq!(bindings.action(MouseButton::Secondary, shift), DragAction::Nothing);
+    }
+
+    #[test]
+    fn ui_state_keeps_display_settings_and_window_size() {
+        let old = r#"{"is_show_background":true,"is_show_pixel_value":true,"is_show_crosshair":false,
+            "is_show_sidebar":true,"is_show_statusbar":true}"#;
+        let state: PersistentUiState = serde_json::from_str(old).unwrap();
+        assert_eq!(state, PersistentUiState::default());
+
+        let state = PersistentUiState {
+            display: Some(ViewPreset {
+                colormap_rgb: "rgb".to_owned(),
+                colormap_mono: "viridis".to_owned(),
+                shader_params: ShaderParams {
+                    exposure: 1.5,
+                    ..ShaderParams::default()
+                },
+            }),
+            show_histogram: true,
+            window_size: Some([1600.0, 900.0]),
+            ..PersistentUiState::default()
+        };
+        let restored: PersistentUiState = serde_json::from_str(&serde_json::to_string(&state).unwrap()).unwrap();
+        assert_eq!(restored, state);
+    }
+
+    #[test]
+    fn export_layout_follows_size_choice() {
+        let mut options = ExportOptions::default();
+        let layout = |options: &ExportOptions| {
+            let layout = options.layout((400, 200), 0.5);
+            (layout.width, layout.height, layout.scale, layout.offset)
+        };
+        assert_eq!(layout(&options), (400, 200, 1.0, (0.0, 0.0)));
+        options.size = ExportSize::CurrentZoom;
+        assert_eq!(layout(&options), (200, 100, 0.5, (0.0, 0.0)));
+        options.size = ExportSize::Scale;
+        assert_eq!(layout(&options), (800, 400, 2.0, (0.0, 0.0)));
+        options.size = ExportSize::Resolution;
+        assert_eq!(layout(&options), (512, 256, 1.28, (0.0, 0.0)));
+        options.aspect = AspectFit::Fill;
+        assert_eq!(layout(&options), (512, 512, 2.56, (256.0, 0.0)));
+    }
+}
@@ -133,6 +133,18 @@ pub const BASE_SUPPORTED_IMAGE_FORMATS: &[SupportedImageFormat] = &[
         ext: "flo",
         mime: "application/x-middlebury-flow",
     },
+    SupportedImageFormat {
+        ext: "yuv",
+        mime: "video/x-raw-yuv",
+    },
+    SupportedImageFormat {
+        ext: "raw",
+        mime: "application/x-edolview-raw",
+    },
+    SupportedImageFormat {
+        ext: "bin",
+        mime: "application/x-edolview-raw",
+    },
 ];
 
 #[cfg(feature = "avif")]
@@ -192,3 +204,15 @@ pub fn is_supported_image_extension(ext: &str) -> bool {
 pub fn is_heif_extension(ext: &str) -> bool {
     HEIF_SUPPORTED_IMAGE_FORMATS.iter().any(|format| format.ext == ext)
 }
+
+/// Headerless sample dumps whose layout is described by the user when opened.
+pub fn is_raw_binary_extension(ext: &str) -> bool {
+    matches!(ext, "raw" | "bin")
+}
+
+/// Containers that are browsed like a directory of images rather than decoded as an image.
+pub const ARCHIVE_EXTENSIONS: &[&str] = &["zip", "tar"];
+
+pub fn is_archive_extension(ext: &str) -> bool {
+    ARCHIVE_EXTENSIONS.contains(&ext)
+}
@@ -5,8 +5,11 @@ use rfd::FileDialog;
 use std::{
     collections::HashSet,
     fs,
-    path::PathBuf,
-    sync::{atomic::Ordering, mpsc, Arc, Mutex},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
     thread,
     time::{Duration, Instant},
     vec,
@@ -16,24 +19,49 @@ use std::{
 use crate::util::timer::ScopedTimer;
 use crate::{
     model::{
-        start_server_with_retry, AppState, AssetType, ComparisonMode, FileAsset, Image, ImageData, MeanDim, Recti,
-        SocketAsset, StatisticsScope, StatisticsType, StatisticsUpdate, StatisticsWorker,
+        can_have_note, compute_timeline, estimate_white_balance, probe_assets, probe_csv, read_clipboard, read_note,
+        search_notes, start_server_with_retry, unpack_histogram, write_note, AlphaCoverage, AppState, AssetType,
+        AsyncMean, ClipboardPayload, ColorLut3d, ComparisonMode, FileAsset, Flag, GridLayout, HeatmapOverlay, Image,
+        ImageData, ImageSpec, ImageStatistics, LensDistortion, MaskedStatistics, MeanDim, Metric, NoteMatch, PixelType,
+        PointPickTarget, PointPicker, PreviousFrame, PrintLayout, Proofing, Rating, RatingFilter, RawLayoutRequired,
+        Recti, SaveAsOptions, SegmentationMask, SelectionMask, SharedAsset, SocketAsset, StatisticsFormat,
+        StatisticsMetrics, StatisticsReport, StatisticsScope, StatisticsType, StatisticsUpdate, StatisticsWorker,
+        Timeline, TimelineFrame, TimelineRequest, TimelineSource, TimelineStatistic, VectorOverlay, WatchFolders,
+        YuvFrameRequired, GRID_MAX_PANES, LABEL_COUNT, MAX_STARS, WATCH_FOLDER_SETTLE_DELAY, WHITE_POINT_RANGE,
     },
+    recovery::{RecoveryJournal, SessionJournal},
     res::{
-        icons::Icons, KeyboardShortcutExt, ASSET_SECONDARY_SELECTION_FILL, CONTROL_LISTENER_UNAVAILABLE_TEXT,
-        DANGER_TEXT, NOTICE_ERROR_TEXT, NOTICE_WARNING_TEXT, STATISTICS_MAX_TOGGLE_FILL, STATISTICS_MIN_TOGGLE_FILL,
-        UPDATE_ACCENT_FILL, UPDATE_ACCENT_TEXT,
+        duplicate_marker_color, icons::Icons, label_color, KeyboardShortcutExt, ASSET_SECONDARY_SELECTION_FILL,
+        CONTROL_LISTENER_UNAVAILABLE_TEXT, DANGER_TEXT, LABEL_CHIP_TEXT, NOTICE_ERROR_TEXT, NOTICE_WARNING_TEXT,
+        STATISTICS_MAX_TOGGLE_FILL, STATISTICS_MIN_TOGGLE_FILL, UPDATE_ACCENT_FILL, UPDATE_ACCENT_TEXT,
     },
+    script::{ScriptCommand, ScriptEvent, ScriptReply},
+    settings::{FileView, ViewPreset},
     ui::{
         component::{
             channel_toggle_ui, display_controls_ui, display_profile_slider, draw_histogram, draw_multi_line_plot,
             egui_ext::{ComboBoxExt, Size, UiExt},
-            show_bookmark_window, BookmarkJumpMode, CopyExport, ExportAction, SaveExport, Toast, ToastUi, ToastsExt,
+            export_options_ui, show_bookmark_window, show_contact_sheet_dialog, show_lens_window, show_mask_legend,
+            show_memory_window, show_overlay_legend, show_perf_hud, show_print_dialog, show_probe_window,
+            show_raw_layout_dialog, show_record_view_dialog, show_save_as_dialog, show_script_console,
+            show_sequence_export_dialog, show_timeline_window, show_yuv_frame_dialog, BookmarkJumpMode,
+            ContactSheetDialog, CopyExport, ExportAction, PerfHud, PrintDialog, RawLayoutDialog, RecordViewDialog,
+            SaveAsDialog, SaveExport, ScriptConsoleLine, SequenceExportDialog, TimelineWindow, Toast, ToastUi,
+            ToastsExt, YuvFrameDialog,
         },
+        directory_export::{ContactSheetOptions, DirectoryExport, SequenceOptions},
         fonts::{apply_fallback_fonts, spawn_fallback_font_loader, LoadedFallbackFonts},
-        ImageViewer,
+        gpu::{CfaPattern, CfaView, ColorVisionMode, MagnificationFilter, ShaderParams},
+        thumbnails::{ThumbnailCache, THUMBNAIL_DISPLAY_SIZE},
+        view_recording::{RecordingOptions, ViewRecording},
+        ImageViewer, ViewTransform,
+    },
+    util::{
+        concurrency::{mpsc_with_notify, NotifierSender},
+        math_ext::{vec2i, Vec2i},
+        series::SeriesRef,
+        str_ext::fuzzy_match,
     },
-    util::{concurrency::mpsc_with_notify, math_ext::vec2i, series::SeriesRef},
 };
 
 #[derive(PartialEq, Clone)]
@@ -48,6 +76,14 @@ struct Bookmark {
     rect: Recti,
 }
 
+/// View and selection kept by "Lock View" for images of `size`.
+struct LockedView {
+    size: (i32, i32),
+    transform: ViewTransform,
+    marquee_rect: Recti,
+    selection_mask: Option<Arc<SelectionMask>>,
+}
+
 #[derive(Clone, PartialEq, Eq)]
 enum UpdateStatus {
     Idle,
@@ -62,7 +98,18 @@ struct PendingImageSaveDialog {
     rx: mpsc::Receiver<Option<(PathBuf, String)>>,
 }
 
-enum StartupPathLoadResult {
+enum TimelineEvent {
+    Progress(usize, usize),
+    Done(color_eyre::eyre::Result<Timeline>),
+}
+
+enum PathLoadResult {
+    /// Reduced or partially decoded preview of a large image that is still loading.
+    Partial {
+        path: PathBuf,
+        hash: String,
+        image: ImageData,
+    },
     Loaded {
         path: PathBuf,
         hash: String,
@@ -120,15 +167,72 @@ pub struct ViewerApp {
     state: AppState,
     viewer: ImageViewer,
     last_path: Option<PathBuf>,
-    startup_paths: Vec<PathBuf>,
-    startup_path_rx: Option<mpsc::Receiver<StartupPathLoadResult>>,
+    pending_paths: Vec<PathBuf>,
+    pending_path_rx: Option<mpsc::Receiver<PathLoadResult>>,
+    /// Hash of the primary asset last looked up for an auto-compare reference.
+    auto_compare_checked: Option<String>,
+    /// Image id of the primary asset seen by [`Self::sync_view_lock`].
+    view_lock_image: Option<u64>,
+    /// View and selection kept while "Lock View" is on, with the image size they belong to.
+    view_lock: Option<LockedView>,
+    /// Image id of the primary asset seen by [`Self::sync_file_view`].
+    file_view_image: Option<u64>,
+    /// View of the shown file, remembered in the settings when another image is shown.
+    shown_file_view: Option<FileView>,
+    /// Views were remembered since the settings were last written; they are written on exit.
+    file_views_changed: bool,
+    /// Journal of this session, for restoring it after a crash.
+    recovery_journal: Option<RecoveryJournal>,
+    /// Session left behind by a crash, offered for restoring until accepted or dismissed.
+    recovered_session: Option<SessionJournal>,
+    /// View of the restored session's shown file, applied once its files have loaded.
+    recovered_view: Option<FileView>,
+    /// Reference being loaded, with the hash of the primary asset it is for.
+    auto_compare_rx: Option<(String, mpsc::Receiver<PathLoadResult>)>,
+    /// Compare the primary image with the frame before it in its sequence.
+    temporal_diff: bool,
+    /// Primary asset whose previous frame was last looked up.
+    temporal_diff_checked: Option<String>,
+    /// Previous frame loading for the primary asset hash.
+    temporal_diff_rx: Option<(String, mpsc::Receiver<PathLoadResult>)>,
+    watch_folders: WatchFolders,
+    /// Loaded watch folder images and whether to show them.
+    watch_folder_load_tx: mpsc::Sender<(PathLoadResult, bool)>,
+    watch_folder_load_rx: mpsc::Receiver<(PathLoadResult, bool)>,
+    /// Ask for the layout of `.yuv` and raw binary files that failed to load without one.
+    yuv_dialog: Option<YuvFrameDialog>,
+    raw_dialog: Option<RawLayoutDialog>,
+    print_dialog: Option<PrintDialog>,
+    print_layout: PrintLayout,
+    save_as_dialog: Option<SaveAsDialog>,
+    save_as_options: SaveAsOptions,
+    save_as_rx: Option<mpsc::Receiver<Result<PathBuf, Report>>>,
+    /// Inner size of the window while it is neither maximized nor fullscreen, remembered on exit.
+    window_size: Option<[f32; 2]>,
+    sequence_dialog: Option<SequenceExportDialog>,
+    sequence_options: SequenceOptions,
+    contact_sheet_dialog: Option<ContactSheetDialog>,
+    contact_sheet_options: ContactSheetOptions,
+    record_dialog: Option<RecordViewDialog>,
+    record_options: RecordingOptions,
+    view_recording: Option<ViewRecording>,
+    directory_export: Option<DirectoryExport>,
+    output_lut_rx: Option<mpsc::Receiver<Result<Option<ColorLut3d>, Report>>>,
+    look_lut_rx: Option<mpsc::Receiver<(PathBuf, Result<ColorLut3d, Report>)>>,
+    vector_overlay_rx: Option<mpsc::Receiver<Result<VectorOverlay, Report>>>,
+    segmentation_mask_rx: Option<mpsc::Receiver<Result<SegmentationMask, Report>>>,
+    clipboard_rx: Option<mpsc::Receiver<Result<ClipboardPayload, Report>>>,
     tmp_marquee_rect: Recti,
+    /// Text of the status bar zoom field; follows the viewer zoom while the field is not being edited.
+    zoom_input: String,
     marquee_rect_text: String,
+    status_mean: AsyncMean,
     is_start_background_event_handlers_called: bool,
 
     // Marquee change callbacks
     last_marquee_rect_for_cb: Recti,
     last_marquee_asset_hash: Option<String>,
+    last_selection_mask: Option<Arc<SelectionMask>>,
     bookmarks: Vec<Bookmark>,
     active_bookmark_index: Option<usize>,
     bookmark_jump_mode: BookmarkJumpMode,
@@ -137,8 +241,12 @@ pub struct ViewerApp {
 
     show_histogram: bool,
     show_histogram_channels: [bool; 4],
+    histogram_log_scale: bool,
+    /// Marquee region and image hash of the last region histogram request, so it is requested once per change.
+    region_histogram_request: Option<(Recti, Option<String>)>,
 
     show_statistics: bool,
+    statistics_metrics: StatisticsMetrics,
     show_statistics_min_overlay_channels: [bool; 4],
     show_statistics_max_overlay_channels: [bool; 4],
 
@@ -148,6 +256,8 @@ pub struct ViewerApp {
 
     toasts: Vec<Toast>,
 
+    /// Kept so the socket listener can be restarted without rebuilding the receiving side.
+    socket_tx: NotifierSender<SocketAsset>,
     socket_rx: mpsc::Receiver<SocketAsset>,
     socket_nx: Option<mpsc::Receiver<()>>,
     socket_server: Option<crate::model::SocketServer>,
@@ -167,18 +277,75 @@ pub struct ViewerApp {
     update_status: UpdateStatus,
     update_toast_shown: bool,
     close_for_update: bool,
+    output_lut_initialized: bool,
     pending_update_confirmation: Option<crate::update::AvailableUpdate>,
     app_settings: crate::settings::AppSettings,
     show_settings_modal: bool,
     show_bookmarks_modal: bool,
+    /// Name typed into the image list's "Group" menu.
+    new_asset_group_name: String,
+    thumbnails: ThumbnailCache,
+    show_perf_hud: bool,
+    perf_hud: PerfHud,
+    show_memory_window: bool,
+    show_probe_window: bool,
+    show_lens_window: bool,
+    /// Keep `probe_pixel` while the pointer moves elsewhere.
+    probe_hold: bool,
+    /// Pixel read from every image of the shown size: the last one under the pointer, unless held.
+    probe_pixel: Option<Vec2i>,
+    show_script_console: bool,
+    script_source: String,
+    script_output: Vec<ScriptConsoleLine>,
+    script_rx: Option<mpsc::Receiver<ScriptEvent>>,
+    script_stop: Arc<AtomicBool>,
+    /// Reply to a script export, sent once the viewer has taken the save request.
+    script_export_reply: Option<mpsc::Sender<Result<ScriptReply, String>>>,
+    /// Enter saves the marquee crop to the crop export folder and advances to the next image.
+    crop_export_mode: bool,
+    /// A crop save is waiting for the viewer; the next image is shown once it has been taken.
+    crop_export_advance: bool,
+    /// Lowest number for the next crop, so quick saves do not reuse a number before its file is written.
+    crop_export_next_number: u32,
+    /// Shown file, whose note is in `note_text` when `note_available`.
+    note_image: Option<PathBuf>,
+    note_available: bool,
+    note_text: String,
+    /// `note_text` has edits not yet written to the sidecar.
+    note_dirty: bool,
+    note_query: String,
+    note_matches: Vec<NoteMatch>,
+    note_search_rx: Option<mpsc::Receiver<Vec<NoteMatch>>>,
+    timeline_window: TimelineWindow,
+    timeline_rx: Option<mpsc::Receiver<TimelineEvent>>,
+    timeline_cancel: Arc<AtomicBool>,
+    /// Fuzzy filter of the image list.
+    asset_query: String,
+    /// Index of the search result `enter` shows, among the entries passing `asset_query`.
+    asset_search_cursor: usize,
     control_rx: mpsc::Receiver<Vec<PathBuf>>,
     control_instance: Option<crate::control::ControlInstance>,
     last_control_touch: Instant,
+    /// UI state that differs from the saved one, and when it last changed.
+    pending_ui_state: Option<(crate::settings::PersistentUiState, Instant)>,
     was_focused_last_frame: bool,
     last_image_save_dir: Option<PathBuf>,
     pending_image_save_dialog: Option<PendingImageSaveDialog>,
 }
 
+const SOCKET_HOST: &str = "127.0.0.1";
+const SOCKET_PORT: u16 = 21734;
+/// Width in points of the stripe marking image list entries with identical pixels.
+const DUPLICATE_MARKER_WIDTH: f32 = 3.0;
+/// Side in points of the label chips in the image list.
+const LABEL_CHIP_SIZE: f32 = 14.0;
+const ASSET_SEARCH_ID: &str = "asset_search";
+
+/// Part of an asset name the image list search matches: the file name of file assets.
+fn asset_list_name(name: &str) -> &str {
+    Path::new(name).file_name().and_then(|name| name.to_str()).unwrap_or(name)
+}
+
 impl Drop for ViewerApp {
     fn drop(&mut self) {
         if let Some(socket_server) = self.socket_server.as_mut() {
@@ -196,6 +363,79 @@ impl Drop for ViewerApp {
     }
 }
 
+fn background_settings_ui(ui: &mut egui::Ui, background: &mut crate::settings::BackgroundSettings) {
+    use crate::settings::BackgroundKind;
+
+    for kind in BackgroundKind::ALL {
+        ui.radio_value(&mut background.kind, kind, kind.label());
+    }
+    ui.separator();
+    match background.kind {
+        BackgroundKind::Checker => {
+            ui.horizontal(|ui| {
+                ui.label("Cell size");
+                ui.add(egui::DragValue::new(&mut background.checker_size).range(2..=256).suffix(" px"));
+            });
+            let mut use_theme_colors = background.checker_colors.is_none();
+            if ui.checkbox(&mut use_theme_colors, "Theme colors").changed() {
+                background.checker_colors = (!use_theme_colors).then_some([[48, 48, 48], [80, 80, 80]]);
+            }
+            if let Some(colors) = &mut background.checker_colors {
+                ui.horizontal(|ui| {
+                    ui.color_edit_button_srgb(&mut colors[0]);
+                    ui.color_edit_button_srgb(&mut colors[1]);
+                });
+            }
+        }
+        BackgroundKind::Custom => {
+            ui.horizontal(|ui| {
+                ui.label("Color");
+                ui.color_edit_button_srgb(&mut background.custom_color);
+            });
+        }
+        BackgroundKind::Black | BackgroundKind::White | BackgroundKind::Gray => {}
+    }
+}
+
+fn pixel_value_settings_ui(ui: &mut egui::Ui, pixel_value: &mut crate::settings::PixelValueSettings) {
+    use crate::settings::PixelValueFormat;
+
+    for format in PixelValueFormat::ALL {
+        ui.radio_value(&mut pixel_value.format, format, format.label());
+    }
+    ui.separator();
+    ui.checkbox(&mut pixel_value.selected_channel_only, "Selected channel only");
+}
+
+/// Changes to the persistent UI state are written once it stayed unchanged this long, and on exit.
+const UI_STATE_SAVE_DELAY: Duration = Duration::from_secs(2);
+
+/// Zoom scales offered by the status bar zoom field.
+const ZOOM_PRESETS: [f32; 10] = [0.0625, 0.125, 0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0, 32.0];
+/// Smallest and largest zoom the zoom field accepts; typed values outside are clamped.
+const ZOOM_RANGE: (f32, f32) = (1.0 / 256.0, 256.0);
+
+/// Zoom as a percentage with up to two decimals, enough for every preset to read back exactly.
+fn format_zoom(scale: f32) -> String {
+    let percent = format!("{:.2}", scale * 100.0);
+    format!("{}%", percent.trim_end_matches('0').trim_end_matches('.'))
+}
+
+/// Parse a zoom typed as a percentage (`250%`), a scale factor (`2.5`, `2.5x`) or a ratio of screen to image
+/// pixels (`1:4`), clamped to [`ZOOM_RANGE`].
+fn parse_zoom(text: &str) -> Option<f32> {
+    let text = text.trim();
+    let parse = |number: &str| number.trim().parse::<f32>().ok();
+    let scale = if let Some(percent) = text.strip_suffix('%') {
+        parse(percent)? / 100.0
+    } else if let Some((screen, image)) = text.split_once(':') {
+        parse(screen)? / parse(image)?
+    } else {
+        parse(text.strip_suffix(['x', 'X']).unwrap_or(text))?
+    };
+    (scale.is_finite() && scale > 0.0).then(|| scale.clamp(ZOOM_RANGE.0, ZOOM_RANGE.1))
+}
+
 fn marquee_angle_radian(rect: Recti) -> Option<f32> {
     let rect = rect.validate();
     if rect.empty() {
@@ -205,6 +445,18 @@ fn marquee_angle_radian(rect: Recti) -> Option<f32> {
     Some((rect.height() as f32).atan2(rect.width() as f32))
 }
 
+/// Colored square with the label's key number.
+fn paint_label_chip(painter: &egui::Painter, rect: egui::Rect, label: usize) {
+    painter.rect_filled(rect, 3.0, label_color(label));
+    painter.text(
+        rect.center(),
+        egui::Align2::CENTER_CENTER,
+        (label + 1).to_string(),
+        egui::FontId::proportional(10.0),
+        LABEL_CHIP_TEXT,
+    );
+}
+
 fn marquee_angle(rect: Recti, unit: crate::settings::AngleDisplayUnit) -> Option<MarqueeAngleDisplay> {
     let angle_radians = marquee_angle_radian(rect)?;
     Some(match unit {
@@ -243,40 +495,70 @@ impl ViewerApp {
             .has_selection()
     }
 
-    pub fn new() -> Self {
+    /// `safe_mode` starts without the socket listener, watchers, custom colormaps and display LUTs, drawing with the
+    /// simplest shader path.
+    pub fn new(safe_mode: bool) -> Self {
         let mut state = AppState::empty();
+        if safe_mode {
+            state.enable_safe_mode();
+        }
         let marquee_rect = state.marquee_rect;
         let app_settings = crate::settings::AppSettings::load().unwrap_or_else(|err| {
             eprintln!("Failed to load settings: {err}");
             crate::settings::AppSettings::default()
         });
         let persisted_ui_state = app_settings.ui_state.clone();
+        let recovered_session = crate::recovery::take_abandoned();
+        let recovery_journal = RecoveryJournal::start()
+            .map_err(|err| eprintln!("Failed to start session journal: {err}"))
+            .ok();
         crate::model::MEAN_PROCESSOR.set_precompute_enabled(app_settings.integral_table_precompute);
+        crate::model::set_decode_limits(app_settings.decode_limits);
+        state.ratings = crate::model::Ratings::load().unwrap_or_else(|err| {
+            eprintln!("Failed to load ratings: {err}");
+            crate::model::Ratings::default()
+        });
         state.is_show_background = persisted_ui_state.is_show_background;
+        state.background = persisted_ui_state.background;
         state.is_show_pixel_value = persisted_ui_state.is_show_pixel_value;
+        state.pixel_value = persisted_ui_state.pixel_value;
         state.is_show_crosshair = persisted_ui_state.is_show_crosshair;
+        state.is_show_image_border = persisted_ui_state.is_show_image_border;
+        state.overlays_by_asset_type = persisted_ui_state.overlays_by_asset_type.clone();
         state.is_show_sidebar = persisted_ui_state.is_show_sidebar;
         state.is_show_statusbar = persisted_ui_state.is_show_statusbar;
-        state.copy_use_original_size = persisted_ui_state.copy_use_original_size;
+        state.export_options = persisted_ui_state.export_options;
+        state.lock_view = persisted_ui_state.lock_view;
+        state.asset_grouping = persisted_ui_state.asset_grouping;
+        state.is_show_asset_thumbnails = persisted_ui_state.is_show_asset_thumbnails;
+        state.magnification_filter = persisted_ui_state.magnification_filter;
+        if let Some(preset) = persisted_ui_state.display.clone() {
+            state.set_view_preset(preset);
+        }
 
         // Start socket server for receiving images
-        let host = "127.0.0.1";
-        let port = 21734;
         let (socket_tx, socket_rx, socket_nx) = mpsc_with_notify::<SocketAsset>();
         let socket_state = state.socket_state.clone();
         let socket_info = state.socket_info.clone();
 
         let (statistics_tx, statistics_rx) = mpsc::channel::<Vec<StatisticsUpdate>>();
         let (control_tx, control_rx, control_nx) = mpsc_with_notify::<Vec<PathBuf>>();
+        let (watch_folder_load_tx, watch_folder_load_rx) = mpsc::channel();
 
         let mut toasts = Vec::new();
-        let socket_server = match start_server_with_retry(host, port, socket_tx, socket_state, socket_info) {
-            Ok(server) => Some(server),
-            Err(err) => {
-                state.socket_state.is_socket_active.store(false, Ordering::Relaxed);
-                eprintln!("Failed to start socket server: {err}");
-                toasts.add_error(format!("Failed to start socket server: {err}"));
-                None
+        let socket_server = if safe_mode {
+            state.socket_state.is_socket_active.store(false, Ordering::Relaxed);
+            toasts.add_info("Safe mode: socket listener, watchers, plugins and custom colormaps are off".to_owned());
+            None
+        } else {
+            match start_server_with_retry(SOCKET_HOST, SOCKET_PORT, socket_tx.clone(), socket_state, socket_info) {
+                Ok(server) => Some(server),
+                Err(err) => {
+                    state.socket_state.is_socket_active.store(false, Ordering::Relaxed);
+                    eprintln!("Failed to start socket server: {err}");
+                    toasts.add_error(format!("Failed to start socket server: {err}"));
+                    None
+                }
             }
         };
         let control_instance = match crate::control::start_control_listener(control_tx) {
@@ -289,31 +571,79 @@ impl ViewerApp {
 
         // Load optional user override and platform CJK fallback fonts off the UI thread.
         let font_rx = spawn_fallback_font_loader();
+        let mut viewer = ImageViewer::new();
+        viewer.set_safe_mode(safe_mode);
+        viewer.set_animate_view(app_settings.animate_view);
+        viewer.set_snap_to_pixel_grid(app_settings.snap_to_pixel_grid);
+        viewer.set_mouse_bindings(app_settings.mouse_bindings.clone());
 
         Self {
             state,
-            viewer: ImageViewer::new(),
+            viewer,
 
             last_path: None,
-            startup_paths: Vec::new(),
-            startup_path_rx: None,
+            pending_paths: Vec::new(),
+            pending_path_rx: None,
+            auto_compare_checked: None,
+            view_lock_image: None,
+            view_lock: None,
+            file_view_image: None,
+            shown_file_view: None,
+            file_views_changed: false,
+            recovery_journal,
+            recovered_session,
+            recovered_view: None,
+            auto_compare_rx: None,
+            temporal_diff: false,
+            temporal_diff_checked: None,
+            temporal_diff_rx: None,
+            watch_folders: WatchFolders::default(),
+            watch_folder_load_tx,
+            watch_folder_load_rx,
+            yuv_dialog: None,
+            raw_dialog: None,
+            print_dialog: None,
+            print_layout: PrintLayout::default(),
+            save_as_dialog: None,
+            save_as_options: SaveAsOptions::default(),
+            save_as_rx: None,
+            window_size: None,
+            sequence_dialog: None,
+            sequence_options: SequenceOptions::default(),
+            contact_sheet_dialog: None,
+            contact_sheet_options: ContactSheetOptions::default(),
+            record_dialog: None,
+            record_options: RecordingOptions::default(),
+            view_recording: None,
+            directory_export: None,
+            output_lut_rx: None,
+            look_lut_rx: None,
+            vector_overlay_rx: None,
+            segmentation_mask_rx: None,
+            clipboard_rx: None,
 
             tmp_marquee_rect: marquee_rect,
+            zoom_input: String::new(),
             marquee_rect_text: marquee_rect.to_string(),
+            status_mean: AsyncMean::default(),
             is_start_background_event_handlers_called: false,
 
             last_marquee_rect_for_cb: marquee_rect,
             last_marquee_asset_hash: None,
+            last_selection_mask: None,
             bookmarks: Vec::new(),
             active_bookmark_index: None,
             bookmark_jump_mode: BookmarkJumpMode::Center,
 
             show_plot_channels: [true, true, true, false],
 
-            show_histogram: false,
+            show_histogram: persisted_ui_state.show_histogram,
             show_histogram_channels: [true, true, true, false],
+            histogram_log_scale: false,
+            region_histogram_request: None,
 
-            show_statistics: false,
+            show_statistics: persisted_ui_state.show_statistics,
+            statistics_metrics: StatisticsMetrics::default(),
             show_statistics_min_overlay_channels: [false; 4],
             show_statistics_max_overlay_channels: [false; 4],
 
@@ -323,6 +653,7 @@ impl ViewerApp {
 
             icons: Icons::new(),
 
+            socket_tx,
             socket_rx,
             socket_nx: Some(socket_nx),
             socket_server,
@@ -342,13 +673,45 @@ impl ViewerApp {
             update_status: UpdateStatus::Idle,
             update_toast_shown: false,
             close_for_update: false,
+            output_lut_initialized: false,
             pending_update_confirmation: None,
             app_settings,
             show_settings_modal: false,
             show_bookmarks_modal: false,
+            new_asset_group_name: String::new(),
+            thumbnails: ThumbnailCache::default(),
+            show_perf_hud: false,
+            perf_hud: PerfHud::default(),
+            show_memory_window: false,
+            show_probe_window: false,
+            show_lens_window: false,
+            probe_hold: false,
+            probe_pixel: None,
+            show_script_console: false,
+            script_source: String::new(),
+            script_output: Vec::new(),
+            script_rx: None,
+            script_stop: Arc::new(AtomicBool::new(false)),
+            script_export_reply: None,
+            crop_export_mode: false,
+            crop_export_advance: false,
+            crop_export_next_number: 1,
+            note_image: None,
+            note_available: false,
+            note_text: String::new(),
+            note_dirty: false,
+            note_query: String::new(),
+            note_matches: Vec::new(),
+            note_search_rx: None,
+            timeline_window: TimelineWindow::default(),
+            timeline_rx: None,
+            timeline_cancel: Arc::new(AtomicBool::new(false)),
+            asset_query: String::new(),
+            asset_search_cursor: 0,
             control_rx,
             control_instance,
             last_control_touch: Instant::now(),
+            pending_ui_state: None,
             was_focused_last_frame: false,
             last_image_save_dir: None,
             pending_image_save_dialog: None,
@@ -357,36 +720,97 @@ impl ViewerApp {
 
     #[inline]
     pub fn with_paths(mut self, paths: Vec<PathBuf>) -> Self {
-        self.startup_paths = paths;
+        self.pending_paths = paths;
         self
     }
 
-    fn start_startup_path_loading(&mut self, ctx: &egui::Context) {
-        if self.startup_path_rx.is_some() || self.startup_paths.is_empty() {
+    /// Rebuild the output LUT from the monitor profile setting on a worker thread.
+    fn rebuild_output_lut(&mut self, ctx: &egui::Context) {
+        let monitor_profile = self.app_settings.monitor_profile.clone();
+        let (tx, rx) = mpsc::channel();
+        self.output_lut_rx = Some(rx);
+
+        let repaint_ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = monitor_profile.map(|path| ColorLut3d::from_icc_profile(&path)).transpose();
+            let _ = tx.send(result);
+            repaint_ctx.request_repaint();
+        });
+    }
+
+    /// Load a `.cube` look LUT on a worker thread; it replaces the current one once parsed.
+    fn load_look_lut(&mut self, ctx: &egui::Context, path: PathBuf) {
+        let (tx, rx) = mpsc::channel();
+        self.look_lut_rx = Some(rx);
+
+        let repaint_ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = ColorLut3d::load_cube(&path);
+            let _ = tx.send((path, result));
+            repaint_ctx.request_repaint();
+        });
+    }
+
+    fn load_vector_overlay(&mut self, ctx: &egui::Context, path: PathBuf) {
+        let (tx, rx) = mpsc::channel();
+        self.vector_overlay_rx = Some(rx);
+
+        let repaint_ctx = ctx.clone();
+        thread::spawn(move || {
+            let _ = tx.send(VectorOverlay::load(&path));
+            repaint_ctx.request_repaint();
+        });
+    }
+
+    fn load_segmentation_mask(&mut self, ctx: &egui::Context, path: PathBuf) {
+        let (tx, rx) = mpsc::channel();
+        self.segmentation_mask_rx = Some(rx);
+
+        let repaint_ctx = ctx.clone();
+        thread::spawn(move || {
+            let _ = tx.send(SegmentationMask::load(&path));
+            repaint_ctx.request_repaint();
+        });
+    }
+
+    fn start_pending_path_loading(&mut self, ctx: &egui::Context) {
+        if self.pending_path_rx.is_some() || self.pending_paths.is_empty() {
             return;
         }
 
-        let paths = std::mem::take(&mut self.startup_paths);
+        let paths = std::mem::take(&mut self.pending_paths);
         let (tx, rx) = mpsc::channel();
-        self.startup_path_rx = Some(rx);
+        self.pending_path_rx = Some(rx);
 
         let load_ctx = ctx.clone();
         thread::spawn(move || {
             let mut seen_hashes = HashSet::new();
 
             for path in paths {
-                let result = match FileAsset::hash_from_path(&path) {
-                    Ok(hash) => {
+                let resolved = crate::model::resolve_archive_path(&path)
+                    .and_then(|path| FileAsset::hash_from_path(&path).map(|hash| (path, hash)));
+                let result = match resolved {
+                    Ok((path, hash)) => {
                         if seen_hashes.insert(hash.clone()) {
-                            match ImageData::load_from_path(&path) {
-                                Ok(image) => StartupPathLoadResult::Loaded { path, hash, image },
-                                Err(err) => StartupPathLoadResult::Failed { path, error: err },
+                            let on_partial = &mut |image| {
+                                let partial = PathLoadResult::Partial {
+                                    path: path.clone(),
+                                    hash: hash.clone(),
+                                    image,
+                                };
+                                if tx.send(partial).is_ok() {
+                                    Self::request_root_repaint(&load_ctx);
+                                }
+                            };
+                            match ImageData::load_from_path_incremental(&path, on_partial) {
+                                Ok(image) => PathLoadResult::Loaded { path, hash, image },
+                                Err(err) => PathLoadResult::Failed { path, error: err },
                             }
                         } else {
-                            StartupPathLoadResult::Reused { path, hash }
+                            PathLoadResult::Reused { path, hash }
                         }
                     }
-                    Err(err) => StartupPathLoadResult::Failed { path, error: err },
+                    Err(err) => PathLoadResult::Failed { path, error: err },
                 };
 
                 if tx.send(result).is_err() {
@@ -404,9 +828,37 @@ impl ViewerApp {
         toasts.add_error(format!("{message}: {path_str}"));
     }
 
-    fn open_from_clipboard(&mut self) {
-        self.state.load_from_clipboard().unwrap_or_else(|e| {
-            Self::load_fail(&mut self.toasts, "Failed to load image from clipboard", None, &e);
+    /// Like [`Self::load_fail`], but a `.yuv` or raw binary file without a known layout opens its layout dialog
+    /// instead.
+    fn load_fail_or_ask_layout(
+        toasts: &mut Vec<Toast>,
+        yuv_dialog: &mut Option<YuvFrameDialog>,
+        raw_dialog: &mut Option<RawLayoutDialog>,
+        message: &str,
+        path: Option<&PathBuf>,
+        e: &Report,
+    ) {
+        if let Some(YuvFrameRequired(path)) = e.downcast_ref() {
+            *yuv_dialog = Some(YuvFrameDialog::new(path.clone()));
+        } else if let Some(RawLayoutRequired(path)) = e.downcast_ref() {
+            *raw_dialog = Some(RawLayoutDialog::new(path.clone()));
+        } else {
+            Self::load_fail(toasts, message, path, e);
+        }
+    }
+
+    /// Read the clipboard on a worker thread; a second request while one is running is ignored.
+    fn open_from_clipboard(&mut self, ctx: &egui::Context) {
+        if self.clipboard_rx.is_some() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        self.clipboard_rx = Some(rx);
+
+        let repaint_ctx = ctx.clone();
+        thread::spawn(move || {
+            let _ = tx.send(read_clipboard());
+            repaint_ctx.request_repaint();
         });
     }
 
@@ -444,8 +896,12 @@ impl ViewerApp {
     }
 
     fn save_export_text(&mut self, payload: SaveExport) {
+        let extension = Path::new(payload.suggested_file_name)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("csv");
         let Some(path) = FileDialog::new()
-            .add_filter("CSV file", &["csv"])
+            .add_filter(format!("{} file", extension.to_uppercase()), &[extension])
             .set_file_name(payload.suggested_file_name)
             .save_file()
         else {
@@ -483,6 +939,12 @@ impl ViewerApp {
             .or_else(|| self.state.path.clone())
     }
 
+    /// Extension of data exports of the shown image, or `None` for display renders.
+    fn sample_export_extension(&self) -> Option<&'static str> {
+        let dtype = self.active_display_asset()?.image().spec().dtype;
+        self.state.export_options.data.sample_extension(dtype)
+    }
+
     fn default_image_export_file_name(&self) -> String {
         let active_path = self.active_display_file_path();
         let base_name = active_path
@@ -492,12 +954,181 @@ impl ViewerApp {
             .filter(|stem| !stem.is_empty())
             .unwrap_or("image");
 
+        let extension = self.sample_export_extension().unwrap_or("png");
         let rect = self.state.marquee_rect.validate();
         if rect.empty() {
-            format!("{base_name}.png")
+            format!("{base_name}.{extension}")
         } else {
             let (x, y, width, height) = rect.xywh();
-            format!("{base_name}-{x}_{y}_{width}_{height}.png")
+            format!("{base_name}-{x}_{y}_{width}_{height}.{extension}")
+        }
+    }
+
+    fn start_sequence_export(&mut self, ctx: &egui::Context, options: SequenceOptions) {
+        let Some(asset) = &self.state.asset else {
+            return;
+        };
+        let spec = asset.image().spec();
+        let paths = self.state.file_nav.files_in_dir.clone();
+        let dir = self.state.file_nav.dir_path.clone();
+        let name = dir
+            .as_ref()
+            .and_then(|dir| dir.file_name())
+            .map_or_else(|| "sequence".to_string(), |name| name.to_string_lossy().to_string());
+        let extension = options.format.extension();
+        let mut dialog = FileDialog::new()
+            .add_filter(options.format.label(), &[extension])
+            .set_title("Export Sequence")
+            .set_file_name(format!("{name}.{extension}"));
+        if let Some(dir) = dir {
+            dialog = dialog.set_directory(dir);
+        }
+        let Some(output) = dialog.save_file() else {
+            return;
+        };
+        let size = options.frame_size(spec.width as u32, spec.height as u32);
+        let display = (&self.state.shader_params, self.state.channel_index);
+        self.start_directory_export(DirectoryExport::sequence(ctx, paths, output, options, size, display));
+    }
+
+    fn start_contact_sheet(&mut self, ctx: &egui::Context, options: ContactSheetOptions) {
+        let paths = self.state.file_nav.files_in_dir.clone();
+        let dir = self.state.file_nav.dir_path.clone();
+        let name = dir.as_ref().and_then(|dir| dir.file_name()).map_or_else(
+            || "contact-sheet".to_string(),
+            |name| format!("{}-contact-sheet", name.to_string_lossy()),
+        );
+        let mut dialog = FileDialog::new()
+            .add_filter("PNG image", &["png"])
+            .set_title("Create Contact Sheet")
+            .set_file_name(format!("{name}.png"));
+        if let Some(dir) = dir {
+            dialog = dialog.set_directory(dir);
+        }
+        let Some(output) = dialog.save_file() else {
+            return;
+        };
+        let display = (&self.state.shader_params, self.state.channel_index);
+        self.start_directory_export(DirectoryExport::contact_sheet(ctx, paths, output, options, display));
+    }
+
+    fn start_directory_export(&mut self, export: color_eyre::eyre::Result<DirectoryExport>) {
+        match export {
+            Ok(export) => self.directory_export = Some(export),
+            Err(e) => {
+                eprintln!("Failed to export directory: {e}");
+                self.toasts.add_error(format!("Failed to export directory: {e}"));
+            }
+        }
+    }
+
+    fn update_directory_export(&mut self, ctx: &egui::Context, frame: &eframe::Frame) {
+        let (Some(export), Some(render_state)) = (&mut self.directory_export, frame.wgpu_render_state()) else {
+            return;
+        };
+        match export.update(ctx, render_state) {
+            Some(Ok(message)) => {
+                self.toasts.add_success(message);
+                self.directory_export = None;
+                return;
+            }
+            Some(Err(e)) => {
+                eprintln!("Failed to export directory: {e}");
+                self.toasts.add_error(format!("Failed to export directory: {e}"));
+                self.directory_export = None;
+                return;
+            }
+            None => {}
+        }
+
+        let (done, total) = export.progress();
+        let mut cancelled = false;
+        egui::Window::new("Exporting")
+            .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -48.0))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.add(
+                    egui::ProgressBar::new(done as f32 / total.max(1) as f32)
+                        .text(format!("{done} / {total}"))
+                        .desired_width(240.0),
+                );
+                cancelled = ui.button("Cancel").clicked();
+            });
+        if cancelled {
+            self.directory_export = None;
+            self.toasts.add_info("Export cancelled".to_string());
+        }
+    }
+
+    /// Stop a running recording, or ask for the options of a new one.
+    fn toggle_view_recording(&mut self) {
+        if let Some(recording) = self.view_recording.as_mut().filter(|recording| recording.is_recording()) {
+            recording.stop();
+            return;
+        }
+        if self.view_recording.is_some() || self.state.asset.is_none() {
+            return;
+        }
+        if let Some(size) = self.viewer.viewport_size_px() {
+            self.record_dialog = Some(RecordViewDialog::new(
+                self.record_options,
+                (size.x.round() as u32, size.y.round() as u32),
+            ));
+        }
+    }
+
+    fn start_view_recording(&mut self, ctx: &egui::Context, options: RecordingOptions) {
+        let Some(viewport) = self.viewer.viewport_size_px() else {
+            return;
+        };
+        let name = self
+            .active_display_file_path()
+            .as_ref()
+            .and_then(|path| path.file_stem())
+            .map_or_else(|| "view".to_string(), |stem| stem.to_string_lossy().to_string());
+        let mut dialog = FileDialog::new()
+            .add_filter("GIF animation", &["gif"])
+            .set_title("Record View")
+            .set_file_name(format!("{name}-recording.gif"));
+        if let Some(dir) = self.last_image_save_dir.clone() {
+            dialog = dialog.set_directory(dir);
+        }
+        let Some(output) = dialog.save_file() else {
+            return;
+        };
+        let size = options.frame_size(viewport.x.round() as u32, viewport.y.round() as u32);
+        match ViewRecording::start(ctx, output, options, size) {
+            Ok(recording) => self.view_recording = Some(recording),
+            Err(e) => {
+                eprintln!("Failed to start recording: {e}");
+                self.toasts.add_error(format!("Failed to start recording: {e}"));
+            }
+        }
+    }
+
+    /// Request the due frame of a running recording, and report the file once it is written.
+    fn update_view_recording(&mut self, ctx: &egui::Context) {
+        let Some(recording) = &mut self.view_recording else {
+            return;
+        };
+        if let Some(result) = recording.poll_done() {
+            match result {
+                Ok(message) => self.toasts.add_success(message),
+                Err(e) => {
+                    eprintln!("Failed to record view: {e}");
+                    self.toasts.add_error(format!("Failed to record view: {e}"));
+                }
+            }
+            self.view_recording = None;
+            return;
+        }
+        let (completion, until_next) = recording.next_frame();
+        if let Some(completion) = completion {
+            self.viewer.request_view_capture(recording.size(), completion);
+        }
+        if recording.is_recording() {
+            ctx.request_repaint_after(until_next);
         }
     }
 
@@ -509,11 +1140,14 @@ impl ViewerApp {
             "Save Image As"
         };
 
-        let mut dialog = FileDialog::new()
-            .add_filter("PNG image", &["png"])
-            .add_filter("JPEG image", &["jpg", "jpeg"])
-            .set_title(title)
-            .set_file_name(self.default_image_export_file_name());
+        let dialog = match self.sample_export_extension() {
+            None => FileDialog::new()
+                .add_filter("PNG image", &["png"])
+                .add_filter("JPEG image", &["jpg", "jpeg"]),
+            Some("exr") => FileDialog::new().add_filter("OpenEXR image", &["exr"]),
+            Some(extension) => FileDialog::new().add_filter("PNG image", &[extension]),
+        };
+        let mut dialog = dialog.set_title(title).set_file_name(self.default_image_export_file_name());
 
         if let Some(directory) = self.last_image_save_dir.clone().or_else(|| {
             self.active_display_file_path()
@@ -525,6 +1159,49 @@ impl ViewerApp {
         dialog
     }
 
+    /// Ask where to save the shown image, or its selection, and encode it as `options` asks on a worker thread.
+    fn save_image_as(&mut self, ctx: &egui::Context, options: SaveAsOptions) {
+        let Some(image) = self.active_display_asset().map(|asset| asset.image().clone()) else {
+            return;
+        };
+        let spec = image.spec();
+        let selection = self.state.marquee_rect.validate();
+        let rect = if selection.empty() {
+            Recti::from_min_size(vec2i(0, 0), vec2i(spec.width, spec.height))
+        } else {
+            selection
+        };
+        let format = options.format;
+        let file_name = Path::new(&self.default_image_export_file_name()).with_extension(format.extension());
+        let mut dialog = FileDialog::new()
+            .set_title("Save As")
+            .add_filter(format.label(), &[format.extension()])
+            .set_file_name(file_name.to_string_lossy());
+        if let Some(directory) = self.last_image_save_dir.clone().or_else(|| {
+            self.active_display_file_path()
+                .and_then(|path| path.parent().map(PathBuf::from))
+        }) {
+            dialog = dialog.set_directory(directory);
+        }
+        let Some(path) = dialog.save_file() else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            self.last_image_save_dir = Some(parent.to_path_buf());
+        }
+
+        let (tx, rx) = mpsc::channel();
+        self.save_as_rx = Some(rx);
+        let repaint_ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = crate::model::encode_save_as(&image, rect, &options)
+                .and_then(|bytes| Ok(fs::write(&path, bytes)?))
+                .map(|()| path);
+            let _ = tx.send(result);
+            Self::request_root_repaint(&repaint_ctx);
+        });
+    }
+
     fn request_viewer_image_save(&mut self, ctx: &egui::Context) {
         if self.pending_image_save_dialog.is_some() {
             return;
@@ -567,13 +1244,16 @@ impl ViewerApp {
         }
     }
 
-    fn save_view_preset(&mut self, slot: usize) {
-        let preset = crate::settings::ViewPreset {
+    fn current_view_preset(&self) -> ViewPreset {
+        ViewPreset {
             colormap_rgb: self.state.colormap_rgb.clone(),
             colormap_mono: self.state.colormap_mono.clone(),
             shader_params: self.state.shader_params.clone(),
-        };
+        }
+    }
 
+    fn save_view_preset(&mut self, slot: usize) {
+        let preset = self.current_view_preset();
         self.app_settings.set_view_preset(slot, preset);
         match self.app_settings.save() {
             Ok(()) => self.toasts.add_success(format!("Saved view preset {}", slot + 1)),
@@ -590,15 +1270,7 @@ impl ViewerApp {
             return;
         };
 
-        self.state.shader_params = preset.shader_params;
-
-        if self.state.colormap_rgb_list.contains(&preset.colormap_rgb) {
-            self.state.colormap_rgb = preset.colormap_rgb;
-        }
-        if self.state.colormap_mono_list.contains(&preset.colormap_mono) {
-            self.state.colormap_mono = preset.colormap_mono;
-        }
-
+        self.state.set_view_preset(preset);
         ctx.request_repaint();
         self.toasts.add_success(format!("Applied view preset {}", slot + 1));
     }
@@ -608,23 +1280,65 @@ impl ViewerApp {
             is_show_background: self.state.is_show_background,
             is_show_pixel_value: self.state.is_show_pixel_value,
             is_show_crosshair: self.state.is_show_crosshair,
+            is_show_image_border: self.state.is_show_image_border,
             is_show_sidebar: self.state.is_show_sidebar,
             is_show_statusbar: self.state.is_show_statusbar,
-            copy_use_original_size: self.state.copy_use_original_size,
+            export_options: self.state.export_options,
+            lock_view: self.state.lock_view,
             angle_display_unit: self.app_settings.ui_state.angle_display_unit,
+            background: self.state.background,
+            overlays_by_asset_type: self.state.overlays_by_asset_type_snapshot(),
+            pixel_value: self.state.pixel_value,
+            look_lut: self.state.look_lut.as_ref().map(|(path, _)| path.clone()),
+            asset_grouping: self.state.asset_grouping,
+            is_show_asset_thumbnails: self.state.is_show_asset_thumbnails,
+            magnification_filter: self.state.magnification_filter,
+            // Safe mode offers only the default colormaps; keep the user's display settings for the next launch.
+            display: if self.state.safe_mode {
+                self.app_settings.ui_state.display.clone()
+            } else {
+                Some(ViewPreset {
+                    shader_params: self.state.shader_params.view_params(),
+                    ..self.current_view_preset()
+                })
+            },
+            show_histogram: self.show_histogram,
+            show_statistics: self.show_statistics,
+            window_size: self.app_settings.ui_state.window_size,
         }
     }
 
-    fn save_persistent_ui_state_if_needed(&mut self) {
+    fn save_persistent_ui_state_if_needed(&mut self, ctx: &egui::Context) {
+        // Write once the state settled, e.g. after a drag on a display slider, rather than on every change.
         let ui_state = self.current_persistent_ui_state();
         if self.app_settings.ui_state == ui_state {
+            self.pending_ui_state = None;
+            return;
+        }
+        let changed_at = match &self.pending_ui_state {
+            Some((pending, changed_at)) if *pending == ui_state => *changed_at,
+            _ => Instant::now(),
+        };
+        let elapsed = changed_at.elapsed();
+        if elapsed < UI_STATE_SAVE_DELAY {
+            self.pending_ui_state = Some((ui_state, changed_at));
+            ctx.request_repaint_after(UI_STATE_SAVE_DELAY - elapsed);
+            return;
+        }
+        // The release that ends a drag repaints, and the state is written then.
+        if ctx.input(|i| i.pointer.any_down()) {
+            self.pending_ui_state = Some((ui_state, changed_at));
             return;
         }
 
+        self.pending_ui_state = None;
         self.app_settings.ui_state = ui_state;
-        if let Err(err) = self.app_settings.save() {
-            eprintln!("Failed to save UI settings: {err}");
-            self.toasts.add_error("Failed to save UI settings".to_string());
+        match self.app_settings.save() {
+            Ok(()) => self.file_views_changed = false,
+            Err(err) => {
+                eprintln!("Failed to save UI settings: {err}");
+                self.toasts.add_error("Failed to save UI settings".to_string());
+            }
         }
     }
 
@@ -858,6 +1572,50 @@ impl ViewerApp {
             });
     }
 
+    /// Start the socket listener, preferring the port it last ran on so clients can reconnect unchanged.
+    fn start_socket_server(&mut self) {
+        if self.socket_server.is_some() {
+            return;
+        }
+        let port = match self.state.socket_info.lock().unwrap().port {
+            0 => SOCKET_PORT,
+            port => port,
+        };
+        match start_server_with_retry(
+            SOCKET_HOST,
+            port,
+            self.socket_tx.clone(),
+            self.state.socket_state.clone(),
+            self.state.socket_info.clone(),
+        ) {
+            Ok(server) => {
+                self.state.socket_state.is_socket_active.store(true, Ordering::Relaxed);
+                self.socket_server = Some(server);
+            }
+            Err(err) => {
+                eprintln!("Failed to start socket server: {err}");
+                self.toasts.add_error(format!("Failed to start socket server: {err}"));
+            }
+        }
+    }
+
+    fn stop_socket_server(&mut self) {
+        let Some(mut socket_server) = self.socket_server.take() else {
+            return;
+        };
+        if let Err(err) = socket_server.shutdown() {
+            eprintln!("Failed to stop socket listener: {err}");
+            self.toasts.add_error(format!("Failed to stop socket listener: {err}"));
+        }
+        self.state.socket_state.is_socket_active.store(false, Ordering::Relaxed);
+        self.state.socket_info.lock().unwrap().address.clear();
+    }
+
+    fn restart_socket_server(&mut self) {
+        self.stop_socket_server();
+        self.start_socket_server();
+    }
+
     fn show_settings_dialog(&mut self, ctx: &egui::Context) {
         if !self.show_settings_modal {
             return;
@@ -932,46 +1690,449 @@ impl ViewerApp {
                 ui.add_space(12.0);
                 ui.separator();
                 ui.add_space(12.0);
-                ui.heading("Mean calculation");
+                ui.heading("Navigation");
                 ui.add_space(8.0);
 
-                let precompute_changed = ui
-                    .checkbox(
-                        &mut self.app_settings.integral_table_precompute,
-                        "Precompute integral table",
-                    )
+                if ui
+                    .checkbox(&mut self.app_settings.animate_view, "Animate fit and center")
+                    .on_hover_text("Ease pan and zoom into place when fitting or centering the view instead of jumping.")
+                    .changed()
+                {
+                    self.viewer.set_animate_view(self.app_settings.animate_view);
+                    if let Err(err) = self.app_settings.save() {
+                        self.toasts.add_error(err);
+                    }
+                }
+
+                if ui
+                    .checkbox(&mut self.app_settings.snap_to_pixel_grid, "Snap to pixel grid")
                     .on_hover_text(
-                        "Keeps marquee mean and plot queries fast after loading, but uses 8 bytes per channel for every image pixel. Disable it to minimize CPU memory; mean queries then run on the GPU.",
+                        "At integer zooms, align image pixels with screen pixels so they stay sharp on scaled displays.",
                     )
+                    .changed()
+                {
+                    self.viewer.set_snap_to_pixel_grid(self.app_settings.snap_to_pixel_grid);
+                    if let Err(err) = self.app_settings.save() {
+                        self.toasts.add_error(err);
+                    }
+                }
+
+                let file_views = &mut self.app_settings.file_views;
+                let mut file_views_changed = ui
+                    .checkbox(&mut file_views.enabled, "Remember view per file")
+                    .on_hover_text("Return to the last zoom, pan and marquee when a file is shown again.")
                     .changed();
-                if precompute_changed {
-                    let enabled = self.app_settings.integral_table_precompute;
-                    crate::model::MEAN_PROCESSOR.set_precompute_enabled(enabled);
-                    if enabled {
-                        if let Some(asset) = &self.state.asset {
-                            crate::model::MEAN_PROCESSOR.precompute_async(asset.image());
+                ui.add_enabled_ui(file_views.enabled, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Files remembered");
+                        file_views_changed |= ui
+                            .add(egui::DragValue::new(&mut file_views.max_entries).range(1..=10000))
+                            .changed();
+                        if ui
+                            .add_enabled(!file_views.entries.is_empty(), egui::Button::new("Forget All"))
+                            .clicked()
+                        {
+                            file_views.entries.clear();
+                            file_views_changed = true;
                         }
+                    });
+                });
+                if file_views_changed {
+                    file_views.truncate();
+                    if !file_views.enabled {
+                        self.shown_file_view = None;
                     }
                     if let Err(err) = self.app_settings.save() {
                         self.toasts.add_error(err);
                     }
                 }
 
-                ui.add_space(10.0);
-                if let Some(control_instance) = &self.control_instance {
-                    ui.label(format!("Local control address: {}", control_instance.address()));
-                } else {
-                    ui.colored_label(
-                        CONTROL_LISTENER_UNAVAILABLE_TEXT,
-                        "Local control listener is unavailable, so send-to-existing-window mode cannot work in this session.",
-                    );
-                }
-            });
-
-        self.show_settings_modal = keep_open;
-    }
+                ui.add_space(12.0);
+                ui.separator();
+                ui.add_space(12.0);
+                ui.heading("Mouse bindings");
+                ui.add_space(8.0);
 
-    fn show_bookmarks_dialog(&mut self, ctx: &egui::Context) {
+                let mut bindings_changed = false;
+                let mut remove_binding = None;
+                let bindings = &mut self.app_settings.mouse_bindings.bindings;
+                egui::Grid::new("mouse_bindings").num_columns(4).show(ui, |ui| {
+                    ui.label("Button");
+                    ui.label("Modifier");
+                    ui.label("Drag");
+                    ui.end_row();
+                    for (index, binding) in bindings.iter_mut().enumerate() {
+                        egui::ComboBox::from_id_salt(("binding_button", index))
+                            .selected_text(binding.button.label())
+                            .show_ui(ui, |ui| {
+                                for button in crate::settings::MouseButton::ALL {
+                                    bindings_changed |=
+                                        ui.selectable_value(&mut binding.button, button, button.label()).changed();
+                                }
+                            });
+                        egui::ComboBox::from_id_salt(("binding_modifier", index))
+                            .selected_text(binding.modifier.label())
+                            .show_ui(ui, |ui| {
+                                for modifier in crate::settings::BindingModifier::ALL {
+                                    bindings_changed |= ui
+                                        .selectable_value(&mut binding.modifier, modifier, modifier.label())
+                                        .changed();
+                                }
+                            });
+                        egui::ComboBox::from_id_salt(("binding_action", index))
+                            .selected_text(binding.action.label())
+                            .show_ui(ui, |ui| {
+                                for action in crate::settings::DragAction::ALL.into_iter().filter(|action| action.is_available()) {
+                                    bindings_changed |=
+                                        ui.selectable_value(&mut binding.action, action, action.label()).changed();
+                                }
+                            });
+                        if ui.button("Remove").clicked() {
+                            remove_binding = Some(index);
+                        }
+                        ui.end_row();
+                    }
+                });
+                if let Some(index) = remove_binding {
+                    bindings.remove(index);
+                    bindings_changed = true;
+                }
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Add Binding")
+                        .on_hover_text("Bindings with a held modifier take precedence over those for any modifier.")
+                        .clicked()
+                    {
+                        bindings.push(crate::settings::MouseBinding {
+                            button: crate::settings::MouseButton::Middle,
+                            modifier: crate::settings::BindingModifier::Any,
+                            action: crate::settings::DragAction::Pan,
+                        });
+                        bindings_changed = true;
+                    }
+                    if ui.button("Reset to Defaults").clicked() {
+                        *bindings = crate::settings::MouseBindings::default().bindings;
+                        bindings_changed = true;
+                    }
+                });
+                if bindings_changed {
+                    self.viewer.set_mouse_bindings(self.app_settings.mouse_bindings.clone());
+                    if let Err(err) = self.app_settings.save() {
+                        self.toasts.add_error(err);
+                    }
+                }
+
+                ui.add_space(12.0);
+                ui.separator();
+                ui.add_space(12.0);
+                ui.heading("Mean calculation");
+                ui.add_space(8.0);
+
+                let precompute_changed = ui
+                    .checkbox(
+                        &mut self.app_settings.integral_table_precompute,
+                        "Precompute integral table",
+                    )
+                    .on_hover_text(
+                        "Keeps marquee mean and plot queries fast after loading, but uses 8 bytes per channel for every 16 image pixels. Disable it to minimize CPU memory; mean queries then run on the GPU.",
+                    )
+                    .changed();
+                if precompute_changed {
+                    let enabled = self.app_settings.integral_table_precompute;
+                    crate::model::MEAN_PROCESSOR.set_precompute_enabled(enabled);
+                    if enabled {
+                        if let Some(asset) = &self.state.asset {
+                            crate::model::MEAN_PROCESSOR.precompute_async(asset.image());
+                        }
+                    }
+                    if let Err(err) = self.app_settings.save() {
+                        self.toasts.add_error(err);
+                    }
+                }
+
+                ui.add_space(12.0);
+                ui.separator();
+                ui.add_space(12.0);
+                ui.heading("Decoding limits");
+                ui.add_space(8.0);
+
+                let limits = &mut self.app_settings.decode_limits;
+                let mut limits_changed = false;
+                ui.horizontal(|ui| {
+                    ui.label("Max width or height");
+                    limits_changed |= ui
+                        .add(egui::DragValue::new(&mut limits.max_dimension).range(1024..=16_777_216).suffix(" px"))
+                        .on_hover_text("Files declaring larger dimensions are rejected before any pixels are decoded.")
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Max decoded size");
+                    limits_changed |= ui
+                        .add(egui::DragValue::new(&mut limits.max_memory_mib).range(64..=1_048_576).suffix(" MiB"))
+                        .on_hover_text(
+                            "Largest image accepted, counted as 4 bytes per channel. Protects against corrupt headers that would allocate huge buffers.",
+                        )
+                        .changed();
+                });
+                if limits_changed {
+                    crate::model::set_decode_limits(*limits);
+                    if let Err(err) = self.app_settings.save() {
+                        self.toasts.add_error(err);
+                    }
+                }
+
+                ui.add_space(12.0);
+                ui.separator();
+                ui.add_space(12.0);
+                ui.heading("Python snippet");
+                ui.add_space(8.0);
+
+                let snippet = &mut self.app_settings.python_snippet;
+                let mut snippet_changed = false;
+                for style in crate::settings::PythonSnippetStyle::ALL {
+                    snippet_changed |= ui.radio_value(&mut snippet.style, style, style.label()).changed();
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Variable name");
+                    snippet_changed |= ui
+                        .add(egui::TextEdit::singleline(&mut snippet.variable_name).desired_width(80.0))
+                        .changed();
+                });
+                snippet_changed |= ui
+                    .checkbox(&mut snippet.include_load, "Include import and load lines")
+                    .on_hover_text("Load the image from its file path when it has one.")
+                    .changed();
+                if snippet_changed {
+                    if let Err(err) = self.app_settings.save() {
+                        self.toasts.add_error(err);
+                    }
+                }
+
+                ui.add_space(12.0);
+                ui.separator();
+                ui.add_space(12.0);
+                ui.heading("Auto-compare");
+                ui.add_space(8.0);
+
+                let auto_compare = &mut self.app_settings.auto_compare;
+                let mut auto_compare_changed = ui
+                    .checkbox(&mut auto_compare.enabled, "Compare renders with their reference on load")
+                    .on_hover_text("Opening an image from the render directory sets the file at the same relative path in the reference directory as the secondary image.")
+                    .changed();
+                egui::Grid::new("auto_compare_dirs").num_columns(3).show(ui, |ui| {
+                    for (label, dir) in [
+                        ("Render directory", &mut auto_compare.render_dir),
+                        ("Reference directory", &mut auto_compare.reference_dir),
+                    ] {
+                        ui.label(label);
+                        let text = dir.as_ref().map_or_else(|| "Not set".to_string(), |dir| dir.display().to_string());
+                        ui.add(egui::Label::new(text).truncate());
+                        if ui.button("Choose...").clicked() {
+                            if let Some(picked) = FileDialog::new().pick_folder() {
+                                *dir = Some(picked);
+                                auto_compare_changed = true;
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+                if auto_compare_changed {
+                    // Look up the reference of the current image again under the new mapping.
+                    self.auto_compare_checked = None;
+                    if let Err(err) = self.app_settings.save() {
+                        self.toasts.add_error(err);
+                    }
+                }
+
+                ui.add_space(12.0);
+                ui.separator();
+                ui.add_space(12.0);
+                ui.heading("Watch folders");
+                ui.add_space(8.0);
+
+                let mut watch_folders_changed = false;
+                let mut remove_watch_folder = None;
+                egui::Grid::new("watch_folders").num_columns(4).show(ui, |ui| {
+                    for (index, folder) in self.app_settings.watch_folders.iter_mut().enumerate() {
+                        ui.add(egui::Label::new(folder.path.display().to_string()).truncate())
+                            .on_hover_text(folder.path.display().to_string());
+                        watch_folders_changed |= ui
+                            .add(
+                                egui::TextEdit::singleline(&mut folder.include)
+                                    .hint_text("*.exr, beauty_*")
+                                    .desired_width(110.0),
+                            )
+                            .on_hover_text("Comma-separated file name globs; empty adds every supported image.")
+                            .lost_focus();
+                        watch_folders_changed |= ui
+                            .checkbox(&mut folder.auto_select, "Show")
+                            .on_hover_text("Show each new image as soon as it is loaded.")
+                            .changed();
+                        if ui.button("Remove").clicked() {
+                            remove_watch_folder = Some(index);
+                        }
+                        ui.end_row();
+                    }
+                });
+                if let Some(index) = remove_watch_folder {
+                    self.app_settings.watch_folders.remove(index);
+                    watch_folders_changed = true;
+                }
+                if ui
+                    .button("Add Folder...")
+                    .on_hover_text("New images written to the folder are added to the image list, whichever directory is open.")
+                    .clicked()
+                {
+                    if let Some(path) = FileDialog::new().pick_folder() {
+                        self.app_settings.watch_folders.push(crate::settings::WatchFolder {
+                            path,
+                            include: String::new(),
+                            auto_select: false,
+                        });
+                        watch_folders_changed = true;
+                    }
+                }
+                if watch_folders_changed {
+                    self.configure_watch_folders(ctx);
+                    if let Err(err) = self.app_settings.save() {
+                        self.toasts.add_error(err);
+                    }
+                }
+
+                ui.add_space(12.0);
+                ui.separator();
+                ui.add_space(12.0);
+                ui.heading("Display output");
+                ui.add_space(8.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("Monitor profile");
+                    let profile_name = self
+                        .app_settings
+                        .monitor_profile
+                        .as_ref()
+                        .and_then(|path| path.file_name())
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| "None (sRGB)".to_string());
+                    ui.label(egui::RichText::new(profile_name).strong()).on_hover_text(
+                        self.app_settings
+                            .monitor_profile
+                            .as_ref()
+                            .map(|path| path.display().to_string())
+                            .unwrap_or_else(|| "The viewer output is shown as sRGB".to_string()),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    let mut profile_changed = false;
+                    if ui
+                        .button("Choose ICC Profile...")
+                        .on_hover_text("Convert the viewer output from sRGB to this monitor profile so colors match color-managed apps")
+                        .clicked()
+                    {
+                        if let Some(path) = FileDialog::new().add_filter("ICC profile", &["icc", "icm"]).pick_file() {
+                            self.app_settings.monitor_profile = Some(path);
+                            profile_changed = true;
+                        }
+                    }
+                    if ui
+                        .add_enabled(self.app_settings.monitor_profile.is_some(), egui::Button::new("Clear"))
+                        .clicked()
+                    {
+                        self.app_settings.monitor_profile = None;
+                        profile_changed = true;
+                    }
+                    if profile_changed {
+                        self.rebuild_output_lut(ctx);
+                        if let Err(err) = self.app_settings.save() {
+                            self.toasts.add_error(err);
+                        }
+                    }
+                });
+
+                ui.add_space(12.0);
+                ui.separator();
+                ui.add_space(12.0);
+                ui.heading("Plugins");
+                ui.add_space(8.0);
+
+                let registry = crate::plugin::plugins();
+                ui.label(format!("Plugin directory: {}", crate::plugin::plugin_dir().display()));
+                if registry.plugins.is_empty() {
+                    ui.label(egui::RichText::new("No plugins loaded").color(ui.visuals().weak_text_color()));
+                }
+                for plugin in &registry.plugins {
+                    ui.label(egui::RichText::new(&plugin.name).strong())
+                        .on_hover_text(plugin.path.display().to_string());
+                    if !plugin.extensions.is_empty() {
+                        ui.label(format!("Formats: {}", plugin.extensions.join(", ")));
+                    }
+                    if !plugin.filters.is_empty() {
+                        ui.label(format!("Filters: {}", plugin.filters.join(", ")));
+                    }
+                }
+                for error in &registry.errors {
+                    ui.colored_label(NOTICE_ERROR_TEXT, error);
+                }
+
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if self.socket_server.is_some() {
+                        let address = self.state.socket_info.lock().unwrap().address.clone();
+                        ui.label(format!("Socket listener: {address}"));
+                        if ui.button("Stop").clicked() {
+                            self.stop_socket_server();
+                        }
+                        if ui.button("Restart").clicked() {
+                            self.restart_socket_server();
+                        }
+                    } else {
+                        ui.label("Socket listener: stopped");
+                        if ui.button("Start").clicked() {
+                            self.start_socket_server();
+                        }
+                    }
+                });
+
+                let history = &mut self.app_settings.socket_history;
+                let mut history_changed = ui
+                    .checkbox(&mut history.replace_same_name, "Replace received images with the same name")
+                    .changed();
+                ui.horizontal(|ui| {
+                    ui.label("Keep per name");
+                    history_changed |= ui
+                        .add(egui::DragValue::new(&mut history.max_per_name).range(0..=1000))
+                        .on_hover_text(
+                            "Images kept for each name. When replacing, earlier versions can be scrubbed in the sidebar. 0 keeps every received image.",
+                        )
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Socket memory budget");
+                    history_changed |= ui
+                        .add(egui::DragValue::new(&mut history.memory_budget_mib).range(0..=65536).suffix(" MiB"))
+                        .on_hover_text("The oldest received images are removed beyond this budget. 0 disables it.")
+                        .changed();
+                });
+                if history_changed {
+                    if let Err(err) = self.app_settings.save() {
+                        self.toasts.add_error(err);
+                    }
+                }
+
+                if let Some(control_instance) = &self.control_instance {
+                    ui.label(format!("Local control address: {}", control_instance.address()));
+                } else {
+                    ui.colored_label(
+                        CONTROL_LISTENER_UNAVAILABLE_TEXT,
+                        "Local control listener is unavailable, so send-to-existing-window mode cannot work in this session.",
+                    );
+                }
+            });
+
+        self.show_settings_modal = keep_open;
+    }
+
+    fn show_bookmarks_dialog(&mut self, ctx: &egui::Context) {
         let bookmark_rects: Vec<_> = self.bookmarks.iter().map(|bookmark| bookmark.rect).collect();
         let actions = show_bookmark_window(
             ctx,
@@ -1006,6 +2167,103 @@ impl ViewerApp {
         }
     }
 
+    fn show_script_console_window(&mut self, ctx: &egui::Context) {
+        let actions = show_script_console(
+            ctx,
+            &mut self.show_script_console,
+            &mut self.script_source,
+            &self.script_output,
+            self.script_rx.is_some(),
+            &crate::res::SCRIPT_RUN.format_sys(),
+        );
+
+        if actions.run && self.script_rx.is_none() {
+            let (tx, rx) = mpsc::channel();
+            self.script_stop = Arc::new(AtomicBool::new(false));
+            crate::script::run_script(self.script_source.clone(), tx, self.script_stop.clone(), ctx.clone());
+            self.script_rx = Some(rx);
+        }
+        if actions.stop {
+            self.script_stop.store(true, Ordering::Relaxed);
+        }
+        if actions.clear_output {
+            self.script_output.clear();
+        }
+        if actions.open_file {
+            if let Some(path) = FileDialog::new().add_filter("Lua script", &["lua"]).pick_file() {
+                match fs::read_to_string(&path) {
+                    Ok(source) => self.script_source = source,
+                    Err(err) => {
+                        eprintln!("Failed to read script {}: {err}", path.display());
+                        self.toasts.add_error(format!("Failed to read script: {err}"));
+                    }
+                }
+            }
+        }
+        if actions.save_file {
+            if let Some(path) = FileDialog::new()
+                .add_filter("Lua script", &["lua"])
+                .set_file_name("script.lua")
+                .save_file()
+            {
+                if let Err(err) = fs::write(&path, &self.script_source) {
+                    eprintln!("Failed to save script {}: {err}", path.display());
+                    self.toasts.add_error(format!("Failed to save script: {err}"));
+                }
+            }
+        }
+    }
+
+    /// Apply commands from a running script until the queue is empty or an export has to wait for the viewer.
+    fn poll_script(&mut self, ctx: &egui::Context) {
+        if let Some(reply) = &self.script_export_reply {
+            if self.viewer.has_pending_save() {
+                return;
+            }
+            let _ = reply.send(Ok(ScriptReply::None));
+            self.script_export_reply = None;
+        }
+        let Some(rx) = &self.script_rx else {
+            return;
+        };
+
+        loop {
+            match rx.try_recv() {
+                Ok(ScriptEvent::Output(text)) => self.script_output.push(ScriptConsoleLine { text, is_error: false }),
+                Ok(ScriptEvent::Request(ScriptCommand::Export(path), reply)) => {
+                    if self.state.asset.is_none() {
+                        let _ = reply.send(Err("No image to export".to_string()));
+                        continue;
+                    }
+                    let source_label = self.active_display_source_label().to_string();
+                    self.viewer.request_save(path, source_label);
+                    self.script_export_reply = Some(reply);
+                    ctx.request_repaint();
+                    return;
+                }
+                Ok(ScriptEvent::Request(command, reply)) => {
+                    let _ = reply.send(command.apply(&mut self.state).map_err(|e| e.to_string()));
+                }
+                Ok(ScriptEvent::Finished(result)) => {
+                    if let Err(err) = result {
+                        eprintln!("Script failed: {err}");
+                        self.script_output.push(ScriptConsoleLine {
+                            text: err,
+                            is_error: true,
+                        });
+                    }
+                    self.script_rx = None;
+                    return;
+                }
+                Err(mpsc::TryRecvError::Empty) => return,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.script_rx = None;
+                    return;
+                }
+            }
+        }
+    }
+
     fn refresh_control_registration(&mut self, ctx: &egui::Context) {
         let Some(control_instance) = &self.control_instance else {
             return;
@@ -1042,6 +2300,29 @@ impl ViewerApp {
                 .lock()
                 .unwrap()
                 .run_minmax(img.clone(), img.spec().dtype.alpha(), scope.clone());
+
+            if self.statistics_metrics.std {
+                self.statistics_worker
+                    .lock()
+                    .unwrap()
+                    .run_std(img.clone(), img.spec().dtype.alpha(), scope.clone());
+            }
+
+            if img.spec().alpha_channel().is_some() {
+                self.statistics_worker
+                    .lock()
+                    .unwrap()
+                    .run_alpha_coverage(img.clone(), scope.clone());
+            }
+
+            if let Some(mask) = &self.state.selection_mask {
+                self.statistics_worker.lock().unwrap().run_masked(
+                    img.clone(),
+                    Arc::clone(mask),
+                    img.spec().dtype.alpha(),
+                    scope.clone(),
+                );
+            }
         }
 
         if let Some(a1) = &self.state.asset_primary {
@@ -1059,49 +2340,385 @@ impl ViewerApp {
                     scope.clone(),
                 );
 
-                // Temporarily disable SSIM due to performance issue
-                // self.statistics_worker.run_ssim(img1.clone(), img2.clone(), scope.clone());
+                let metrics = self.statistics_metrics;
+                let mut worker = self.statistics_worker.lock().unwrap();
+                if metrics.mae {
+                    worker.run_mae(img1.clone(), img2.clone(), img1.spec().dtype.alpha(), scope.clone());
+                }
+                if metrics.ssim {
+                    worker.run_ssim(img1.clone(), img2.clone(), scope.clone());
+                }
+                if metrics.ms_ssim {
+                    worker.run_ms_ssim(img1.clone(), img2.clone(), scope.clone());
+                }
             }
         }
     }
 
-    fn on_marquee_changed(&mut self) {
-        self.update_statistics();
+    /// Alpha coverage of the current image, if it has alpha and the result belongs to it.
+    fn current_alpha_coverage(&self) -> Option<AlphaCoverage> {
+        let coverage = &self.state.statistics.alpha_coverage;
+        let hash = self.state.asset.as_ref()?.hash();
+        coverage
+            .value
+            .filter(|_| coverage.scope.as_ref().and_then(|scope| scope.asset_hash.as_deref()) == Some(hash))
     }
 
-    fn active_display_asset(&self) -> Option<&crate::model::SharedAsset> {
-        if self.state.is_comparison() && self.state.comparison_mode == ComparisonMode::Split {
-            if self.state.cursor_on_secondary {
-                return self.state.asset_secondary.as_ref().or(self.state.asset_primary.as_ref());
-            }
-            return self.state.asset_primary.as_ref().or(self.state.asset_secondary.as_ref());
-        }
-
-        self.state.asset.as_ref()
+    /// Statistics of the active lasso/polygon selection, if they are up to date.
+    fn current_masked_statistics(&self) -> Option<&MaskedStatistics> {
+        let mask = self.state.selection_mask.as_ref()?;
+        let masked = &self.state.statistics.masked;
+        let scope = masked.scope.as_ref()?;
+        let asset_hash = self.state.asset.as_ref().map(|asset| asset.hash().to_string());
+        (scope.rect == mask.bounds() && scope.asset_hash == asset_hash)
+            .then_some(masked.value.as_ref())
+            .flatten()
     }
 
-    #[cfg(debug_assertions)]
-    fn statistics_rect_mismatch_message(&self) -> Option<String> {
-        if !self.show_statistics {
-            return None;
+    /// Histogram shown in the sidebar: of the lasso/polygon selection, else of the marquee region once it has been
+    /// computed, else of the whole image.
+    fn current_histogram(&self) -> Option<&Vec<Vec<f32>>> {
+        if let Some(masked) = self.current_masked_statistics() {
+            return Some(&masked.hist);
         }
-
-        let statistics_scope = self.state.statistics.min_max.scope.as_ref()?;
-        let current_asset_hash = self.state.asset.as_ref().map(|asset| asset.hash().to_string());
-        if statistics_scope.asset_hash == current_asset_hash {
-            return None;
+        let asset = self.state.asset.as_ref()?;
+        let rect = self.state.marquee_rect.validate();
+        let region = &self.state.statistics.histogram;
+        let is_current = region
+            .scope
+            .as_ref()
+            .is_some_and(|scope| scope.rect == rect && scope.asset_hash.as_deref() == Some(asset.hash()));
+        if !rect.empty() && is_current && !region.value.is_empty() {
+            return Some(&region.value);
         }
-
-        Some(format!(
-            "Statistics asset {:?} differs from current asset {:?}.",
-            statistics_scope.asset_hash, current_asset_hash
-        ))
+        Some(asset.image().hist())
     }
 
-    fn start_background_event_handlers(&mut self, ctx: &egui::Context) {
-        let state = &self.state;
-        let _ctx = ctx.clone();
-
+    /// Compute the histogram of the marquee region in the background when the region or the image has changed.
+    fn request_region_histogram(&mut self) {
+        let Some(asset) = &self.state.asset else {
+            return;
+        };
+        let rect = self.state.marquee_rect.validate();
+        let request = (rect, Some(asset.hash().to_string()));
+        if rect.empty() || self.region_histogram_request.as_ref() == Some(&request) {
+            return;
+        }
+        let scope = StatisticsScope {
+            request_id: self.next_statistics_request_id,
+            rect,
+            asset_hash: request.1.clone(),
+        };
+        self.next_statistics_request_id = self.next_statistics_request_id.saturating_add(1);
+        self.statistics_worker
+            .lock()
+            .unwrap()
+            .run_histogram(asset.image().clone(), scope);
+        self.region_histogram_request = Some(request);
+    }
+
+    /// Statistics of the marquee region (or the whole image), comparison metrics and the displayed histogram.
+    fn statistics_report(&self) -> Option<StatisticsReport> {
+        let primary = self.state.asset_primary.as_ref()?;
+        let spec = primary.image().spec();
+        let region = if self.state.marquee_rect.empty() {
+            Recti::from_min_size(vec2i(0, 0), vec2i(spec.width, spec.height))
+        } else {
+            self.state.marquee_rect
+        };
+        let comparison = self.state.is_comparison();
+        let secondary = self.state.asset_secondary.as_ref().filter(|_| comparison);
+        let images = [("primary", Some(primary)), ("secondary", secondary)]
+            .into_iter()
+            .filter_map(|(role, asset)| {
+                let asset = asset?;
+                ImageStatistics::compute(role, asset.name(), asset.image(), region)
+            })
+            .collect();
+        let mut metrics = if comparison {
+            let statistics = &self.state.statistics;
+            let value = &statistics.psnr_rmse.value;
+            let selected = self.statistics_metrics;
+            [
+                ("rmse", true, value.rmse),
+                ("psnr", true, value.psnr),
+                ("mse", selected.mse, value.rmse * value.rmse),
+                ("mae", selected.mae, statistics.mae.value),
+                ("ssim", selected.ssim, statistics.ssim.value),
+                ("ms_ssim", selected.ms_ssim, statistics.ms_ssim.value),
+            ]
+            .into_iter()
+            .filter(|(_, selected, _)| *selected)
+            .map(|(name, _, value)| Metric { name, value })
+            .collect()
+        } else {
+            Vec::new()
+        };
+        if let Some(coverage) = self.current_alpha_coverage() {
+            metrics.extend([
+                Metric {
+                    name: "alpha_transparent",
+                    value: coverage.transparent,
+                },
+                Metric {
+                    name: "alpha_partial",
+                    value: coverage.partial,
+                },
+                Metric {
+                    name: "alpha_opaque",
+                    value: coverage.opaque,
+                },
+            ]);
+        }
+        let histogram = self.current_histogram()?.clone();
+        let (x, y, width, height) = region.xywh();
+        Some(StatisticsReport {
+            region: [x, y, width, height],
+            images,
+            metrics,
+            histogram,
+        })
+    }
+
+    /// With "Lock View" on, restore the locked view and selection when the primary image changes to one of the
+    /// locked size, and keep the lock up to date while such an image is shown.
+    fn sync_view_lock(&mut self) {
+        let current = self.state.asset_primary.as_ref().map(|asset| {
+            let spec = asset.image().spec();
+            (asset.image().id(), (spec.width, spec.height))
+        });
+        let changed = current.map(|(id, _)| id) != self.view_lock_image;
+        self.view_lock_image = current.map(|(id, _)| id);
+        let Some((_, size)) = current.filter(|_| self.state.lock_view) else {
+            self.view_lock = None;
+            return;
+        };
+
+        match &self.view_lock {
+            Some(locked) if changed && locked.size == size => {
+                self.viewer.set_view_transform(locked.transform);
+                self.state.marquee_rect = locked.marquee_rect;
+                self.state.selection_mask = locked.selection_mask.clone();
+                self.tmp_marquee_rect = locked.marquee_rect;
+                self.marquee_rect_text = locked.marquee_rect.to_string();
+                self.update_statistics();
+            }
+            // Images of another size leave the lock untouched, so it applies again on returning.
+            Some(locked) if locked.size != size => {}
+            _ => {
+                self.view_lock = Some(LockedView {
+                    size,
+                    transform: self.viewer.view_transform(),
+                    marquee_rect: self.state.marquee_rect,
+                    selection_mask: self.state.selection_mask.clone(),
+                })
+            }
+        }
+    }
+
+    /// Remember the view of each shown file and return to it when the file is shown again, unless "Lock View"
+    /// is keeping the current view.
+    fn sync_file_view(&mut self) {
+        let history = &self.app_settings.file_views;
+        let current = self
+            .state
+            .asset_primary
+            .as_ref()
+            .filter(|asset| history.enabled && asset.asset_type() == AssetType::File)
+            .map(|asset| (asset.image().id(), PathBuf::from(asset.name())));
+        let id = current.as_ref().map(|(id, _)| *id);
+        if id != self.file_view_image {
+            self.file_view_image = id;
+            // Switching images must stay fast, so the settings file is not rewritten here.
+            self.file_views_changed |= self.remember_shown_file_view();
+
+            let remembered = current
+                .as_ref()
+                .filter(|_| !self.state.lock_view)
+                .and_then(|(_, path)| self.app_settings.file_views.get(path).cloned());
+            if let Some(view) = remembered {
+                self.apply_file_view(&view);
+            }
+        }
+
+        self.shown_file_view = current.map(|(_, path)| {
+            let transform = self.viewer.view_transform();
+            let marquee = self.state.marquee_rect;
+            FileView {
+                path,
+                zoom_level: transform.zoom_level,
+                pan: transform.pan.into(),
+                marquee: [marquee.min.x, marquee.min.y, marquee.width(), marquee.height()],
+            }
+        });
+    }
+
+    fn apply_file_view(&mut self, view: &FileView) {
+        self.viewer.set_view_transform(ViewTransform {
+            zoom_level: view.zoom_level,
+            pan: view.pan.into(),
+        });
+        let [x, y, width, height] = view.marquee;
+        self.state.reset_marquee_rect();
+        self.state
+            .set_marquee_rect(Recti::from_min_size(vec2i(x, y), vec2i(width, height)));
+        self.tmp_marquee_rect = self.state.marquee_rect;
+        self.marquee_rect_text = self.state.marquee_rect.to_string();
+    }
+
+    /// Path of the primary asset when it is a file.
+    fn primary_file_path(&self) -> Option<PathBuf> {
+        self.state
+            .asset_primary
+            .as_ref()
+            .filter(|asset| asset.asset_type() == AssetType::File)
+            .map(|asset| PathBuf::from(asset.name()))
+    }
+
+    /// Rewrite the session journal every [`crate::recovery::JOURNAL_INTERVAL`].
+    fn write_recovery_journal(&mut self) {
+        if !self.recovery_journal.as_ref().is_some_and(RecoveryJournal::is_due) {
+            return;
+        }
+        let view = self.primary_file_path().map(|path| {
+            let transform = self.viewer.view_transform();
+            let marquee = self.state.marquee_rect;
+            FileView {
+                path,
+                zoom_level: transform.zoom_level,
+                pan: transform.pan.into(),
+                marquee: [marquee.min.x, marquee.min.y, marquee.width(), marquee.height()],
+            }
+        });
+        let session = SessionJournal {
+            files: self
+                .state
+                .assets
+                .values()
+                .filter(|asset| asset.asset_type() == AssetType::File)
+                .map(|asset| PathBuf::from(asset.name()))
+                .collect(),
+            view,
+            display: Some(self.current_view_preset()),
+        };
+        if let Some(Err(err)) = self.recovery_journal.as_mut().map(|journal| journal.write(&session)) {
+            eprintln!("{err}");
+        }
+    }
+
+    /// Reopen the files of a crashed session. The shown file is queued again last so it ends up shown, and its view
+    /// is applied once everything has loaded.
+    fn restore_session(&mut self, session: SessionJournal) {
+        if let Some(display) = session.display {
+            self.state.set_view_preset(display);
+        }
+        self.pending_paths.extend(session.files);
+        if let Some(view) = &session.view {
+            self.pending_paths.push(view.path.clone());
+        }
+        self.recovered_view = session.view;
+    }
+
+    fn apply_recovered_view(&mut self) {
+        if self.pending_path_rx.is_some() || !self.pending_paths.is_empty() {
+            return;
+        }
+        let Some(view) = self.recovered_view.take() else {
+            return;
+        };
+        if self.primary_file_path().as_ref() == Some(&view.path) {
+            self.apply_file_view(&view);
+        }
+    }
+
+    fn show_recovery_dialog(&mut self, ctx: &egui::Context) {
+        let Some(session) = &self.recovered_session else {
+            return;
+        };
+
+        let mut restore = false;
+        let mut dismiss = false;
+        egui::Window::new("Restore Previous Session")
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.set_max_width(420.0);
+                ui.label("Edolview did not close normally last time.");
+                ui.label(match session.files.len() {
+                    1 => "Reopen the image that was open, with its view and display settings?".to_string(),
+                    count => format!("Reopen the {count} images that were open, with the view and display settings?"),
+                });
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    restore = ui.button("Restore").clicked();
+                    dismiss = ui.button("Discard").clicked();
+                });
+            });
+
+        if restore {
+            if let Some(session) = self.recovered_session.take() {
+                self.restore_session(session);
+            }
+        } else if dismiss {
+            self.recovered_session = None;
+        }
+    }
+
+    /// Move the view of the shown file into the settings, returning whether there was one.
+    fn remember_shown_file_view(&mut self) -> bool {
+        match self.shown_file_view.take() {
+            Some(view) => {
+                self.app_settings.file_views.remember(view);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn undo_annotation(&mut self) {
+        if let Some(asset) = &self.state.asset_primary {
+            self.state.annotations.undo(asset.hash());
+        }
+    }
+
+    fn on_marquee_changed(&mut self) {
+        self.update_statistics();
+    }
+
+    fn active_display_asset(&self) -> Option<&crate::model::SharedAsset> {
+        if self.state.is_comparison() && self.state.comparison_mode == ComparisonMode::Split {
+            if self.state.cursor_on_secondary {
+                return self.state.asset_secondary.as_ref().or(self.state.asset_primary.as_ref());
+            }
+            return self.state.asset_primary.as_ref().or(self.state.asset_secondary.as_ref());
+        }
+
+        self.state.asset.as_ref()
+    }
+
+    #[cfg(debug_assertions)]
+    fn statistics_rect_mismatch_message(&self) -> Option<String> {
+        if !self.show_statistics {
+            return None;
+        }
+
+        let statistics_scope = self.state.statistics.min_max.scope.as_ref()?;
+        let current_asset_hash = self.state.asset.as_ref().map(|asset| asset.hash().to_string());
+        if statistics_scope.asset_hash == current_asset_hash {
+            return None;
+        }
+
+        Some(format!(
+            "Statistics asset {:?} differs from current asset {:?}.",
+            statistics_scope.asset_hash, current_asset_hash
+        ))
+    }
+
+    fn start_background_event_handlers(&mut self, ctx: &egui::Context) {
+        let state = &self.state;
+        let _ctx = ctx.clone();
+
         let socket_nx = self.socket_nx.take().unwrap();
         let control_nx = self.control_nx.take().unwrap();
         let socket_state = state.socket_state.clone();
@@ -1157,9 +2774,15 @@ impl ViewerApp {
     }
 
     fn handle_event(&mut self, ctx: &egui::Context) {
+        self.poll_script(ctx);
+        if self.crop_export_advance && !self.viewer.has_pending_save() {
+            self.crop_export_advance = false;
+            self.navigate_next_file();
+        }
+
         match self.socket_rx.try_recv() {
             Ok(asset) => {
-                self.state.set_primary_asset(Arc::new(asset));
+                self.state.add_socket_asset(asset, &self.app_settings.socket_history);
             }
             Err(mpsc::TryRecvError::Empty) => {}
             Err(mpsc::TryRecvError::Disconnected) => {}
@@ -1188,6 +2811,39 @@ impl ViewerApp {
                                 result.value.iter().skip(1).step_by(2).cloned().collect();
                             self.state.statistics.min_max.scope = Some(result.scope.clone());
                         }
+                        StatisticsType::Masked => {
+                            is_pending_update |= result.is_pending;
+                            self.state.statistics.masked.value = MaskedStatistics::unpack(&result.value);
+                            self.state.statistics.masked.scope = Some(result.scope.clone());
+                        }
+                        StatisticsType::Std => {
+                            is_pending_update |= result.is_pending;
+                            self.state.statistics.std.value = result.value.clone();
+                            self.state.statistics.std.scope = Some(result.scope.clone());
+                        }
+                        StatisticsType::MAE => {
+                            is_pending_update |= result.is_pending;
+                            self.state.statistics.mae.value = result.value[0];
+                            self.state.statistics.mae.scope = Some(result.scope.clone());
+                        }
+                        StatisticsType::MSSSIM => {
+                            is_pending_update |= result.is_pending;
+                            self.state.statistics.ms_ssim.value = result.value[0];
+                            self.state.statistics.ms_ssim.scope = Some(result.scope.clone());
+                        }
+                        StatisticsType::Histogram => {
+                            // A request made while the worker was busy was dropped; make it again.
+                            if result.is_pending {
+                                self.region_histogram_request = None;
+                            }
+                            self.state.statistics.histogram.value = unpack_histogram(&result.value);
+                            self.state.statistics.histogram.scope = Some(result.scope.clone());
+                        }
+                        StatisticsType::AlphaCoverage => {
+                            is_pending_update |= result.is_pending;
+                            self.state.statistics.alpha_coverage.value = AlphaCoverage::unpack(&result.value);
+                            self.state.statistics.alpha_coverage.scope = Some(result.scope.clone());
+                        }
                         _ => {}
                     }
                 }
@@ -1200,148 +2856,885 @@ impl ViewerApp {
             Err(mpsc::TryRecvError::Disconnected) => {}
         }
 
-        loop {
-            match self.control_rx.try_recv() {
-                Ok(paths) => {
-                    for path in paths {
-                        if let Err(err) = self.state.load_from_path(path.clone()) {
-                            Self::load_fail(
-                                &mut self.toasts,
-                                "Failed to open externally requested file",
-                                Some(&path),
-                                &err,
-                            );
-                        }
-                    }
-                    if let Some(control_instance) = &self.control_instance {
-                        if let Err(err) = control_instance.touch_active() {
-                            eprintln!("Failed to update active window registry after external open: {err}");
-                        } else {
-                            self.last_control_touch = Instant::now();
-                        }
-                    }
-                    ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
-                    ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
-                    ctx.request_repaint();
-                }
-                Err(mpsc::TryRecvError::Empty) => break,
-                Err(mpsc::TryRecvError::Disconnected) => break,
+        loop {
+            match self.control_rx.try_recv() {
+                Ok(paths) => {
+                    for path in paths {
+                        if let Err(err) = self.state.load_from_path(path.clone()) {
+                            Self::load_fail_or_ask_layout(
+                                &mut self.toasts,
+                                &mut self.yuv_dialog,
+                                &mut self.raw_dialog,
+                                "Failed to open externally requested file",
+                                Some(&path),
+                                &err,
+                            );
+                        }
+                    }
+                    if let Some(control_instance) = &self.control_instance {
+                        if let Err(err) = control_instance.touch_active() {
+                            eprintln!("Failed to update active window registry after external open: {err}");
+                        } else {
+                            self.last_control_touch = Instant::now();
+                        }
+                    }
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+                    ctx.request_repaint();
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => break,
+            }
+        }
+
+        // Non-blocking check for fallback fonts and apply once.
+        if !self.fallback_font_applied {
+            if let Some(rx) = &self.font_rx {
+                match rx.try_recv() {
+                    Ok(fallback_fonts) => {
+                        apply_fallback_fonts(ctx, fallback_fonts);
+                        self.fallback_font_applied = true;
+                    }
+                    Err(mpsc::TryRecvError::Empty) => {}
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        // Channel failed; avoid retry spam.
+                        self.fallback_font_applied = true;
+                    }
+                }
+            } else {
+                self.fallback_font_applied = true; // No font to load.
+            }
+        }
+
+        if let Some(rx) = &self.clipboard_rx {
+            match rx.try_recv() {
+                Ok(result) => {
+                    self.clipboard_rx = None;
+                    match result {
+                        Ok(ClipboardPayload::Image(image)) => self.state.apply_clipboard_image(image),
+                        Ok(ClipboardPayload::Files(paths)) => self.pending_paths.extend(paths),
+                        Err(e) => Self::load_fail(&mut self.toasts, "Failed to load image from clipboard", None, &e),
+                    }
+                }
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => self.clipboard_rx = None,
+            }
+        }
+
+        if let Some(rx) = &self.save_as_rx {
+            match rx.try_recv() {
+                Ok(Ok(path)) => {
+                    self.toasts.add_success(format!("Saved image to {}", path.display()));
+                    self.save_as_rx = None;
+                }
+                Ok(Err(err)) => {
+                    eprintln!("Failed to save image: {err}");
+                    self.toasts.add_error(format!("Failed to save image: {err}"));
+                    self.save_as_rx = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => self.save_as_rx = None,
+            }
+        }
+
+        if let Some(rx) = &self.output_lut_rx {
+            match rx.try_recv() {
+                Ok(Ok(lut)) => {
+                    self.state.output_lut = lut.map(Arc::new);
+                    self.output_lut_rx = None;
+                }
+                Ok(Err(err)) => {
+                    eprintln!("Failed to load monitor profile: {err}");
+                    self.toasts.add_error(format!("Failed to load monitor profile: {err}"));
+                    self.state.output_lut = None;
+                    self.output_lut_rx = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => self.output_lut_rx = None,
+            }
+        }
+
+        if let Some(rx) = &self.look_lut_rx {
+            match rx.try_recv() {
+                Ok((path, Ok(lut))) => {
+                    self.state.look_lut = Some((path, Arc::new(lut)));
+                    self.look_lut_rx = None;
+                }
+                Ok((_, Err(err))) => {
+                    eprintln!("Failed to load LUT: {err}");
+                    self.toasts.add_error(format!("Failed to load LUT: {err}"));
+                    self.look_lut_rx = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => self.look_lut_rx = None,
+            }
+        }
+
+        if let Some(rx) = &self.vector_overlay_rx {
+            match rx.try_recv() {
+                Ok(Ok(overlay)) => {
+                    self.state.vector_overlay = Some(overlay);
+                    self.state.is_show_vector_overlay = true;
+                    self.vector_overlay_rx = None;
+                }
+                Ok(Err(err)) => {
+                    eprintln!("Failed to load overlay: {err}");
+                    self.toasts.add_error(format!("Failed to load overlay: {err}"));
+                    self.vector_overlay_rx = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => self.vector_overlay_rx = None,
+            }
+        }
+
+        if let Some(rx) = &self.segmentation_mask_rx {
+            match rx.try_recv() {
+                Ok(Ok(mask)) => {
+                    self.state.segmentation_mask = Some(mask);
+                    self.state.is_show_segmentation_mask = true;
+                    self.segmentation_mask_rx = None;
+                }
+                Ok(Err(err)) => {
+                    eprintln!("Failed to load segmentation mask: {err}");
+                    self.toasts.add_error(format!("Failed to load segmentation mask: {err}"));
+                    self.segmentation_mask_rx = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => self.segmentation_mask_rx = None,
+            }
+        }
+
+        if let Some(rx) = &self.note_search_rx {
+            match rx.try_recv() {
+                Ok(matches) => {
+                    self.note_matches = matches;
+                    self.note_search_rx = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => self.note_search_rx = None,
+            }
+        }
+
+        if let Some(rx) = &self.timeline_rx {
+            loop {
+                match rx.try_recv() {
+                    Ok(TimelineEvent::Progress(done, total)) => self.timeline_window.progress = Some((done, total)),
+                    Ok(TimelineEvent::Done(result)) => {
+                        match result {
+                            Ok(timeline) => self.timeline_window.timeline = Some(timeline),
+                            Err(err) if self.timeline_cancel.load(Ordering::Relaxed) => eprintln!("{err}"),
+                            Err(err) => {
+                                eprintln!("Failed to compute timeline: {err}");
+                                self.toasts.add_error(format!("Failed to compute timeline: {err}"));
+                            }
+                        }
+                        self.timeline_window.progress = None;
+                        self.timeline_rx = None;
+                        break;
+                    }
+                    Err(mpsc::TryRecvError::Empty) => break,
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        self.timeline_window.progress = None;
+                        self.timeline_rx = None;
+                        break;
+                    }
+                }
+            }
+        }
+
+        if let Some(rx) = &self.update_rx {
+            match rx.try_recv() {
+                Ok(result) => match result {
+                    Ok(Some(update)) => {
+                        if !self.update_toast_shown {
+                            self.toasts.add_info(format!("Update available: {}", update.version));
+                            self.update_toast_shown = true;
+                        }
+                        self.update_status = UpdateStatus::Available(update);
+                    }
+                    Ok(None) => {
+                        self.update_status = UpdateStatus::UpToDate;
+                    }
+                    Err(err) => {
+                        eprintln!("Failed to check for updates: {err}");
+                        self.update_status = UpdateStatus::Error(err);
+                    }
+                },
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    if self.update_status == UpdateStatus::Checking {
+                        self.update_status = UpdateStatus::Error("Update check disconnected".to_string());
+                    }
+                }
+            }
+        }
+
+        if let Some(rx) = &self.update_apply_rx {
+            match rx.try_recv() {
+                Ok(result) => match result {
+                    Ok(message) => {
+                        self.update_status = UpdateStatus::Applying(message);
+                        self.close_for_update = true;
+                    }
+                    Err(err) => {
+                        self.toasts.add_error(err.clone());
+                        self.update_status = UpdateStatus::Error(err);
+                    }
+                },
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    if matches!(self.update_status, UpdateStatus::Applying(_)) {
+                        self.update_status = UpdateStatus::Error("Update worker disconnected".to_string());
+                    }
+                }
+            }
+        }
+
+        if let Some(rx) = &self.pending_path_rx {
+            let mut should_clear_rx = false;
+
+            loop {
+                match rx.try_recv() {
+                    Ok(PathLoadResult::Partial { path, hash, image }) => {
+                        self.state.show_loading_preview(&path, &hash, image);
+                    }
+                    Ok(PathLoadResult::Loaded { path, hash, image }) => {
+                        self.state.apply_loaded_file_asset(path, hash, image);
+                    }
+                    Ok(PathLoadResult::Reused { path, hash }) => {
+                        self.state.set_file_asset_primary_by_hash_and_path(&hash, &path);
+                    }
+                    Ok(PathLoadResult::Failed { path, error }) => {
+                        Self::load_fail_or_ask_layout(
+                            &mut self.toasts,
+                            &mut self.yuv_dialog,
+                            &mut self.raw_dialog,
+                            "Failed to load image",
+                            Some(&path),
+                            &error,
+                        );
+                    }
+                    Err(mpsc::TryRecvError::Empty) => break,
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        should_clear_rx = true;
+                        break;
+                    }
+                }
+            }
+
+            if should_clear_rx {
+                self.pending_path_rx = None;
+            }
+        }
+    }
+
+    fn handle_window_commands(&mut self, ctx: &egui::Context) {
+        if self.close_for_update {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            return;
+        }
+
+        if self.state.path != self.last_path {
+            self.last_path = self.state.path.clone();
+            if let Some(p) = &self.state.path {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Title(format!("{} - edolview", p.display()).to_owned()));
+            } else {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Title("edolview".to_owned()));
+            }
+        }
+
+        if ctx.input_mut(|i| i.consume_shortcut(&crate::res::FULLSCREEN_TOGGLE)) {
+            let cur_full = ctx.input(|i| i.viewport().fullscreen.unwrap_or(false));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(!cur_full));
+        }
+    }
+
+    fn navigate_next_file(&mut self) {
+        if let Err(e) = self.state.navigate_next() {
+            let path = self.state.next_file_path();
+            Self::load_fail_or_ask_layout(
+                &mut self.toasts,
+                &mut self.yuv_dialog,
+                &mut self.raw_dialog,
+                "Failed to load navigated file",
+                path.as_ref(),
+                &e,
+            );
+        }
+    }
+
+    /// Save the marquee crop under the next free number in the crop export folder, then show the next image once
+    /// the viewer has taken the save.
+    fn save_crop_and_advance(&mut self, ctx: &egui::Context) {
+        if self.crop_export_advance || self.state.asset.is_none() {
+            return;
+        }
+        if self.state.marquee_rect.validate().empty() {
+            self.toasts.add_error("Select a region to crop first".to_string());
+            return;
+        }
+        let settings = &self.app_settings.crop_export;
+        let Some(directory) = settings.directory.clone() else {
+            self.toasts
+                .add_error("Choose a crop export folder first (File > Crop Export)".to_string());
+            return;
+        };
+        if let Err(err) = fs::create_dir_all(&directory) {
+            eprintln!("Failed to create crop export folder {}: {err}", directory.display());
+            self.toasts.add_error(format!("Failed to create crop export folder: {err}"));
+            return;
+        }
+        let extension = self.sample_export_extension().unwrap_or("png");
+        let (path, number) = crate::util::path_ext::next_numbered_path(
+            &directory,
+            &settings.prefix,
+            extension,
+            self.crop_export_next_number,
+        );
+        self.crop_export_next_number = number + 1;
+        let source_label = self.active_display_source_label().to_string();
+        self.viewer.request_save(path, source_label);
+        self.crop_export_advance = true;
+        ctx.request_repaint();
+    }
+
+    /// Add `label` to the shown image or remove it, and show the change on screen.
+    fn toggle_label(&mut self, label: usize) {
+        let Some(asset) = &self.state.asset_primary else {
+            return;
+        };
+        let name = &self.app_settings.labels.resolved_names()[label];
+        if self.state.labels.toggle(asset.name(), label) {
+            self.viewer.show_osd(format!("+ {name}"));
+        } else {
+            self.viewer.show_osd(format!("\u{2212} {name}"));
+        }
+    }
+
+    /// Load the note of the shown file when it changes, writing any unsaved edits of the previous file first.
+    fn sync_note(&mut self) {
+        let image = self.state.primary_file_path();
+        if image == self.note_image {
+            return;
+        }
+        self.save_note();
+        self.note_available = image.as_deref().is_some_and(can_have_note);
+        self.note_text = match image.as_deref().filter(|_| self.note_available).map(read_note).transpose() {
+            Ok(note) => note.unwrap_or_default(),
+            Err(err) => {
+                eprintln!("{err}");
+                self.toasts.add_error(err.to_string());
+                String::new()
+            }
+        };
+        self.note_image = image;
+    }
+
+    fn save_note(&mut self) {
+        let Some(image) = self.note_image.as_ref().filter(|_| self.note_available && self.note_dirty) else {
+            return;
+        };
+        self.note_dirty = false;
+        if let Err(err) = write_note(image, &self.note_text) {
+            eprintln!("{err}");
+            self.toasts.add_error(err.to_string());
+        }
+    }
+
+    /// Search the notes of the files in the folder of the shown file off the UI thread.
+    fn start_note_search(&mut self, ctx: &egui::Context) {
+        self.save_note();
+        let images = self.state.file_nav.files_in_dir.clone();
+        let query = self.note_query.clone();
+        let (tx, rx) = mpsc::channel();
+        self.note_search_rx = Some(rx);
+
+        let repaint_ctx = ctx.clone();
+        thread::spawn(move || {
+            let _ = tx.send(search_notes(&images, &query));
+            repaint_ctx.request_repaint();
+        });
+    }
+
+    fn notes_ui(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Note");
+        let response = ui.add(
+            egui::TextEdit::multiline(&mut self.note_text)
+                .desired_rows(3)
+                .desired_width(f32::INFINITY)
+                .hint_text("Inspection findings, stored next to the file"),
+        );
+        self.note_dirty |= response.changed();
+        if response.lost_focus() {
+            self.save_note();
+        }
+
+        ui.horizontal(|ui| {
+            let search = ui.add(
+                egui::TextEdit::singleline(&mut self.note_query)
+                    .desired_width(f32::INFINITY)
+                    .hint_text("Search notes in this folder"),
+            );
+            if search.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                self.start_note_search(ui.ctx());
+            }
+        });
+        if self.note_search_rx.is_some() {
+            ui.spinner();
+        }
+        let mut to_open = None;
+        for found in &self.note_matches {
+            let name = found.image.file_name().unwrap_or_default().to_string_lossy();
+            let selected = self.note_image.as_ref() == Some(&found.image);
+            if ui
+                .selectable_label(selected, format!("{name}: {}", found.snippet))
+                .on_hover_text(found.image.display().to_string())
+                .clicked()
+            {
+                to_open = Some(found.image.clone());
+            }
+        }
+        if let Some(path) = to_open {
+            self.pending_paths.push(path);
+        }
+    }
+
+    fn show_pixel_probe(&mut self, ctx: &egui::Context) {
+        if !self.probe_hold {
+            self.probe_pixel = self.state.cursor_pos.or(self.probe_pixel);
+        }
+        if !self.show_probe_window {
+            return;
+        }
+        let current = self.state.asset_primary.clone();
+        let samples = match (self.probe_pixel, &current) {
+            (Some(pixel), Some(asset)) => {
+                let spec = asset.image().spec();
+                probe_assets(&self.state.assets, (spec.width, spec.height), pixel)
+            }
+            _ => Vec::new(),
+        };
+        let actions = show_probe_window(
+            ctx,
+            &mut self.show_probe_window,
+            &mut self.probe_hold,
+            self.probe_pixel,
+            &samples,
+            current.as_ref().map(|asset| asset.hash()),
+            &crate::res::PROBE_HOLD.format_sys(),
+        );
+        if let Some(pixel) = self.probe_pixel.filter(|_| actions.copy_csv) {
+            self.handle_export_action(ExportAction::Copy(CopyExport {
+                title: "Pixel probe",
+                text: probe_csv(pixel, &samples),
+            }));
+        }
+    }
+
+    /// Frames of the timeline source as it is now.
+    fn timeline_frames(&self) -> Vec<TimelineFrame> {
+        match self.timeline_window.source {
+            TimelineSource::Directory => self
+                .state
+                .file_nav
+                .files_in_dir
+                .iter()
+                .cloned()
+                .map(TimelineFrame::File)
+                .collect(),
+            TimelineSource::SocketHistory => self
+                .state
+                .socket_history_images()
+                .into_iter()
+                .map(TimelineFrame::Loaded)
+                .collect(),
+        }
+    }
+
+    /// The pixel or region the timeline statistic is measured in.
+    fn timeline_target(&self) -> String {
+        let rect = self.state.marquee_rect;
+        match self.timeline_window.statistic {
+            TimelineStatistic::PixelValue => match self.probe_pixel {
+                Some(pixel) => format!("Pixel {}, {}", pixel.x, pixel.y),
+                None => "No pixel under the pointer".to_string(),
+            },
+            _ if rect.empty() => "Whole image".to_string(),
+            _ => format!(
+                "Selection {}\u{00d7}{} at {}, {}",
+                rect.width(),
+                rect.height(),
+                rect.min.x,
+                rect.min.y
+            ),
+        }
+    }
+
+    /// Measure the timeline statistic of every frame off the UI thread.
+    fn start_timeline(&mut self, ctx: &egui::Context) {
+        let statistic = self.timeline_window.statistic;
+        let pixel = match (statistic, self.probe_pixel) {
+            (_, Some(pixel)) => pixel,
+            (TimelineStatistic::PixelValue, None) => {
+                self.toasts.add_error("Point at a pixel to plot its value".to_string());
+                return;
+            }
+            // The pixel is unused by the region statistics.
+            (_, None) => vec2i(0, 0),
+        };
+        let request = TimelineRequest {
+            frames: self.timeline_frames(),
+            statistic,
+            pixel,
+            rect: self.state.marquee_rect,
+            reference: self.timeline_window.reference.as_ref().map(|(_, image)| image.clone()),
+        };
+        self.timeline_cancel.store(true, Ordering::Relaxed);
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.timeline_cancel = cancel.clone();
+        let (tx, rx) = mpsc::channel();
+        self.timeline_rx = Some(rx);
+        self.timeline_window.progress = Some((0, request.frames.len()));
+
+        let repaint_ctx = ctx.clone();
+        thread::spawn(move || {
+            let progress = |done, total| {
+                let _ = tx.send(TimelineEvent::Progress(done, total));
+                repaint_ctx.request_repaint();
+            };
+            let result = compute_timeline(request, progress, &cancel);
+            let _ = tx.send(TimelineEvent::Done(result));
+            repaint_ctx.request_repaint();
+        });
+    }
+
+    fn show_vector_overlay_legend(&mut self, ctx: &egui::Context) {
+        let Some(overlay) = &mut self.state.vector_overlay else {
+            return;
+        };
+        let counts = self
+            .state
+            .asset
+            .as_ref()
+            .map(|asset| overlay.class_counts(asset.name()))
+            .unwrap_or_default();
+        let actions = show_overlay_legend(ctx, overlay, &mut self.state.is_show_vector_overlay, &counts);
+        if actions.unload {
+            self.state.vector_overlay = None;
+        }
+    }
+
+    fn show_segmentation_mask_legend(&mut self, ctx: &egui::Context) {
+        let Some(mask) = &mut self.state.segmentation_mask else {
+            return;
+        };
+        let image_size = self.state.asset.as_ref().map(|asset| {
+            let spec = asset.image().spec();
+            (spec.width as u32, spec.height as u32)
+        });
+        let actions = show_mask_legend(ctx, mask, &mut self.state.is_show_segmentation_mask, image_size);
+        if actions.unload {
+            self.state.segmentation_mask = None;
+        }
+    }
+
+    fn show_lens_distortion_window(&mut self, ctx: &egui::Context) {
+        let Some(lens) = &mut self.state.lens_distortion else {
+            return;
+        };
+        let image_size = self.state.asset.as_ref().map(|asset| {
+            let spec = asset.image().spec();
+            (spec.width, spec.height)
+        });
+        let actions = show_lens_window(ctx, &mut self.show_lens_window, lens, image_size);
+        if actions.changed && self.state.shader_params.undistort.is_some() {
+            self.state.shader_params.undistort = Some(*lens);
+        }
+        if let Some(e) = actions.error {
+            eprintln!("Failed to read lens parameters: {e}");
+            self.toasts.add_error(format!("Failed to read lens parameters: {e}"));
+        }
+    }
+
+    fn show_timeline(&mut self, ctx: &egui::Context) {
+        if !self.timeline_window.open {
+            return;
+        }
+        let frame_count = match self.timeline_window.source {
+            TimelineSource::Directory => self.state.file_nav.files_in_dir.len(),
+            TimelineSource::SocketHistory => self.state.socket_history_images().len(),
+        };
+        let target = self.timeline_target();
+        let actions = show_timeline_window(ctx, &mut self.timeline_window, frame_count, &target);
+        if actions.cancel || !self.timeline_window.open {
+            self.timeline_cancel.store(true, Ordering::Relaxed);
+        }
+        if actions.use_shown_as_reference {
+            if let Some(asset) = &self.state.asset_primary {
+                self.timeline_window.reference = Some((asset.name().to_string(), asset.image().clone()));
             }
         }
+        if actions.compute {
+            self.start_timeline(ctx);
+        }
+        if let Some(export) = actions.export {
+            self.handle_export_action(export);
+        }
+    }
 
-        // Non-blocking check for fallback fonts and apply once.
-        if !self.fallback_font_applied {
-            if let Some(rx) = &self.font_rx {
-                match rx.try_recv() {
-                    Ok(fallback_fonts) => {
-                        apply_fallback_fonts(ctx, fallback_fonts);
-                        self.fallback_font_applied = true;
-                    }
-                    Err(mpsc::TryRecvError::Empty) => {}
-                    Err(mpsc::TryRecvError::Disconnected) => {
-                        // Channel failed; avoid retry spam.
-                        self.fallback_font_applied = true;
-                    }
-                }
-            } else {
-                self.fallback_font_applied = true; // No font to load.
+    /// Grid comparison settings and the picked images, shown once an image was added to the grid.
+    fn grid_ui(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Grid");
+        ui.horizontal(|ui| {
+            ui.checkbox(
+                &mut self.state.grid.enabled,
+                format!("Show grid ({})", crate::res::GRID_TOGGLE.format_sys()),
+            )
+            .on_hover_text("Show the picked images side by side with synchronized pan and zoom.");
+            for layout in GridLayout::ALL {
+                ui.radio_value(&mut self.state.grid.layout, layout, layout.label());
             }
+        });
+        let assets = self.state.grid.assets(&self.state.assets);
+        let capacity = self.state.grid.layout.capacity();
+        if assets.len() < 2 {
+            ui.weak("Add at least two images with \"Add to Grid\" in the image list menu.");
+        } else if self.state.grid.len() > capacity {
+            ui.weak(format!("Only the first {capacity} images fit this layout."));
         }
-
-        if let Some(rx) = &self.update_rx {
-            match rx.try_recv() {
-                Ok(result) => match result {
-                    Ok(Some(update)) => {
-                        if !self.update_toast_shown {
-                            self.toasts.add_info(format!("Update available: {}", update.version));
-                            self.update_toast_shown = true;
-                        }
-                        self.update_status = UpdateStatus::Available(update);
-                    }
-                    Ok(None) => {
-                        self.update_status = UpdateStatus::UpToDate;
-                    }
-                    Err(err) => {
-                        eprintln!("Failed to check for updates: {err}");
-                        self.update_status = UpdateStatus::Error(err);
-                    }
-                },
-                Err(mpsc::TryRecvError::Empty) => {}
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    if self.update_status == UpdateStatus::Checking {
-                        self.update_status = UpdateStatus::Error("Update check disconnected".to_string());
-                    }
+        let mut to_remove = None;
+        for (pane, asset) in assets.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.small_button("\u{00d7}").on_hover_text("Remove from Grid").clicked() {
+                    to_remove = Some(asset.hash().to_string());
                 }
-            }
+                ui.add(egui::Label::new(format!("{}. {}", pane + 1, asset.name())).truncate())
+                    .on_hover_text(asset.name());
+            });
+        }
+        if let Some(hash) = to_remove {
+            self.state.grid.toggle(&hash);
+        }
+        if ui.button("Clear Grid").clicked() {
+            self.state.grid.clear();
+            self.state.grid.enabled = false;
         }
+    }
 
-        if let Some(rx) = &self.update_apply_rx {
-            match rx.try_recv() {
-                Ok(result) => match result {
-                    Ok(message) => {
-                        self.update_status = UpdateStatus::Applying(message);
-                        self.close_for_update = true;
-                    }
-                    Err(err) => {
-                        self.toasts.add_error(err.clone());
-                        self.update_status = UpdateStatus::Error(err);
-                    }
-                },
-                Err(mpsc::TryRecvError::Empty) => {}
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    if matches!(self.update_status, UpdateStatus::Applying(_)) {
-                        self.update_status = UpdateStatus::Error("Update worker disconnected".to_string());
-                    }
+    /// Settings of the heatmap blended over the shown image, shown once an image was picked as the heatmap.
+    fn heatmap_ui(&mut self, ui: &mut egui::Ui) {
+        let asset = self
+            .state
+            .heatmap
+            .as_ref()
+            .and_then(|heatmap| heatmap.asset(&self.state.assets));
+        let Some(heatmap) = self.state.heatmap.as_mut() else {
+            return;
+        };
+        let mut clear = false;
+        ui.horizontal(|ui| {
+            ui.heading("Heatmap");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                clear = ui.small_button("\u{00d7}").on_hover_text("Clear Heatmap Overlay").clicked();
+            });
+        });
+        match &asset {
+            Some(asset) => {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.state.is_show_heatmap, "Show");
+                    ui.add(egui::Label::new(asset.name()).truncate()).on_hover_text(asset.name());
+                });
+                if asset.image().spec().channels > 1 {
+                    ui.weak("Only the first channel is drawn.");
                 }
             }
+            None => {
+                ui.weak("The heatmap image is no longer in the image list.");
+            }
+        }
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("heatmap_colormap")
+                .combo(ui, &mut heatmap.colormap, &self.state.colormap_mono_list)
+                .response
+                .on_hover_text("Colormap");
+            ui.add(egui::Slider::new(&mut heatmap.opacity, 0.0..=1.0).text("Opacity"));
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut heatmap.auto_range, "Auto range")
+                .on_hover_text("Map the heatmap's own minimum and maximum to the ends of the colormap");
+            ui.add_enabled_ui(!heatmap.auto_range, |ui| {
+                ui.add(egui::DragValue::new(&mut heatmap.min_v).speed(0.01).prefix("min "));
+                ui.add(egui::DragValue::new(&mut heatmap.max_v).speed(0.01).prefix("max "));
+            });
+        });
+        if clear {
+            self.state.heatmap = None;
         }
+    }
 
-        if let Some(rx) = &self.startup_path_rx {
-            let mut should_clear_rx = false;
+    /// Change the rating of the shown file, save the ratings and show the new rating on screen.
+    fn rate_primary_file(&mut self, change: impl FnOnce(&mut Rating)) {
+        let Some(path) = self.state.primary_file_path() else {
+            return;
+        };
+        let mut rating = self.state.ratings.get(&path);
+        change(&mut rating);
+        self.state.ratings.set(&path, rating);
+        self.viewer.show_osd(rating.label());
+        if let Err(err) = self.state.ratings.save() {
+            eprintln!("Failed to save ratings: {err}");
+            self.toasts.add_error(format!("Failed to save ratings: {err}"));
+        }
+    }
 
-            loop {
-                match rx.try_recv() {
-                    Ok(StartupPathLoadResult::Loaded { path, hash, image }) => {
-                        self.state.apply_loaded_file_asset(path, hash, image);
-                    }
-                    Ok(StartupPathLoadResult::Reused { path, hash }) => {
-                        self.state.set_file_asset_primary_by_hash_and_path(&hash, &path);
-                    }
-                    Ok(StartupPathLoadResult::Failed { path, error }) => {
-                        Self::load_fail(&mut self.toasts, "Failed to load image", Some(&path), &error);
+    fn rating_ui(&mut self, ui: &mut egui::Ui) {
+        let rating = self.state.primary_file_path().map(|path| self.state.ratings.get(&path));
+        let mut new_rating = None;
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(rating.is_some(), |ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+                let rating = rating.unwrap_or_default();
+                for star in 1..=MAX_STARS {
+                    let text = if star <= rating.stars { "\u{2605}" } else { "\u{2606}" };
+                    if ui
+                        .add(egui::Button::new(text).frame(false))
+                        .on_hover_text(format!("{star} stars ({star}); click the current rating to clear it"))
+                        .clicked()
+                    {
+                        let stars = if rating.stars == star { 0 } else { star };
+                        new_rating = Some(Rating { stars, ..rating });
                     }
-                    Err(mpsc::TryRecvError::Empty) => break,
-                    Err(mpsc::TryRecvError::Disconnected) => {
-                        should_clear_rx = true;
-                        break;
+                }
+                ui.add_space(6.0);
+                for (flag, text, shortcut) in [
+                    (Flag::Pick, "Pick", crate::res::FLAG_PICK),
+                    (Flag::Reject, "Reject", crate::res::FLAG_REJECT),
+                ] {
+                    let selected = rating.flag == flag;
+                    if ui
+                        .selectable_label(selected, text)
+                        .on_hover_text(format!(
+                            "{text} ({}, {} clears)",
+                            shortcut.format_sys(),
+                            crate::res::FLAG_CLEAR.format_sys()
+                        ))
+                        .clicked()
+                    {
+                        let flag = if selected { Flag::None } else { flag };
+                        new_rating = Some(Rating { flag, ..rating });
                     }
                 }
-            }
-
-            if should_clear_rx {
-                self.startup_path_rx = None;
-            }
+            });
+        });
+        if let Some(new_rating) = new_rating {
+            self.rate_primary_file(|rating| *rating = new_rating);
         }
+        egui::ComboBox::from_id_salt("rating_filter")
+            .selected_text(format!("Navigate: {}", self.state.rating_filter.label()))
+            .show_ui(ui, |ui| {
+                for filter in RatingFilter::ALL {
+                    ui.selectable_value(&mut self.state.rating_filter, filter, filter.label());
+                }
+            })
+            .response
+            .on_hover_text("Files visited by previous/next file navigation");
     }
 
-    fn handle_window_commands(&mut self, ctx: &egui::Context) {
-        if self.close_for_update {
-            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-            return;
+    fn labels_menu_ui(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(
+            &mut self.state.labeling,
+            format!("Labeling mode ({})", crate::res::LABELING_TOGGLE.format_sys()),
+        )
+        .on_hover_text("Number keys 1-9 add or remove labels of the shown image");
+        let counts = self.state.labels.counts();
+        let mut changed = false;
+        egui::Grid::new("label_names").num_columns(3).show(ui, |ui| {
+            for (index, name) in self.app_settings.labels.names.iter_mut().enumerate() {
+                let (chip, _) = ui.allocate_exact_size(egui::Vec2::splat(LABEL_CHIP_SIZE), egui::Sense::hover());
+                paint_label_chip(ui.painter(), chip, index);
+                changed |= ui.add(egui::TextEdit::singleline(name).desired_width(120.0)).lost_focus();
+                ui.label(counts[index].to_string()).on_hover_text("Labeled images");
+                ui.end_row();
+            }
+        });
+        if changed {
+            if let Err(err) = self.app_settings.save() {
+                eprintln!("Failed to save label names: {err}");
+                self.toasts.add_error("Failed to save label names".to_string());
+            }
         }
 
-        if self.state.path != self.last_path {
-            self.last_path = self.state.path.clone();
-            if let Some(p) = &self.state.path {
-                ctx.send_viewport_cmd(egui::ViewportCommand::Title(format!("{} - edolview", p.display()).to_owned()));
-            } else {
-                ctx.send_viewport_cmd(egui::ViewportCommand::Title("edolview".to_owned()));
+        ui.separator();
+        let mut export = None;
+        ui.add_enabled_ui(!self.state.labels.is_empty(), |ui| {
+            let names = self.app_settings.labels.resolved_names();
+            for format in StatisticsFormat::ALL {
+                if ui.button(format!("Copy as {}", format.name())).clicked() {
+                    export = Some(ExportAction::Copy(CopyExport {
+                        title: "labels",
+                        text: self.state.labels.encode(format, &names),
+                    }));
+                }
             }
+            for format in StatisticsFormat::ALL {
+                if ui.button(format!("Save as {}...", format.name())).clicked() {
+                    export = Some(ExportAction::Save(SaveExport {
+                        title: "labels",
+                        suggested_file_name: match format {
+                            StatisticsFormat::Csv => "labels.csv",
+                            StatisticsFormat::Json => "labels.json",
+                        },
+                        text: self.state.labels.encode(format, &names),
+                    }));
+                }
+            }
+            ui.separator();
+            ui.visuals_mut().override_text_color = Some(DANGER_TEXT);
+            if ui.button("Clear All Labels").clicked() {
+                self.state.labels.clear();
+            }
+        });
+        if let Some(export) = export {
+            ui.close();
+            self.handle_export_action(export);
         }
+    }
 
-        if ctx.input_mut(|i| i.consume_shortcut(&crate::res::FULLSCREEN_TOGGLE)) {
-            let cur_full = ctx.input(|i| i.viewport().fullscreen.unwrap_or(false));
-            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(!cur_full));
+    fn crop_export_menu_ui(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(
+            &mut self.crop_export_mode,
+            format!("Crop export mode ({})", crate::res::CROP_EXPORT_TOGGLE.format_sys()),
+        )
+        .on_hover_text(format!(
+            "{} saves the marquee crop to the folder below and advances to the next image",
+            crate::res::CROP_EXPORT_SAVE.format_sys()
+        ));
+        let settings = &mut self.app_settings.crop_export;
+        let mut changed = false;
+        let folder_label = settings
+            .directory
+            .as_ref()
+            .map_or_else(|| "Choose folder...".to_string(), |dir| dir.display().to_string());
+        if ui.button(folder_label).on_hover_text("Folder the crops are saved to").clicked() {
+            if let Some(dir) = FileDialog::new().pick_folder() {
+                settings.directory = Some(dir);
+                self.crop_export_next_number = 1;
+                changed = true;
+            }
+        }
+        ui.horizontal(|ui| {
+            ui.label("Prefix");
+            let response = ui.add(egui::TextEdit::singleline(&mut settings.prefix).desired_width(100.0));
+            if response.changed() {
+                self.crop_export_next_number = 1;
+            }
+            changed |= response.lost_focus();
+        });
+        if changed {
+            if let Err(err) = self.app_settings.save() {
+                eprintln!("Failed to save crop export settings: {err}");
+                self.toasts.add_error("Failed to save crop export settings".to_string());
+            }
         }
     }
 
@@ -1354,11 +3747,66 @@ impl ViewerApp {
         let mut apply_view_preset = None;
         let mut save_view_preset = None;
         let mut toggle_bookmark_panel = false;
+        let mut toggle_script_console = false;
+        let mut toggle_perf_hud = false;
+        let mut toggle_view_recording = false;
+        let mut focus_asset_search = false;
+        let mut toggle_grid = false;
+        let mut toggle_probe_window = false;
+        let mut toggle_temporal_diff = false;
         let mut add_bookmark = false;
         let mut navigate_prev_bookmark = false;
         let mut navigate_next_bookmark = false;
         let mut open_from_clipboard = false;
+        let mut save_crop = false;
+        let mut toggle_label = None;
+        let mut set_stars = None;
+        let mut set_flag = None;
         ctx.input_mut(|i| {
+            // Overlay toggles use Shift, so consume them before plain-key shortcuts that would also match.
+            if i.consume_shortcut(&crate::res::TOGGLE_BACKGROUND) {
+                self.state.is_show_background = !self.state.is_show_background;
+            }
+            if i.consume_shortcut(&crate::res::TOGGLE_PIXEL_VALUE) {
+                self.state.is_show_pixel_value = !self.state.is_show_pixel_value;
+            }
+            if i.consume_shortcut(&crate::res::TOGGLE_CROSSHAIR) {
+                self.state.is_show_crosshair = !self.state.is_show_crosshair;
+            }
+            if i.consume_shortcut(&crate::res::TOGGLE_IMAGE_BORDER) {
+                self.state.is_show_image_border = !self.state.is_show_image_border;
+            }
+            if i.consume_shortcut(&crate::res::TOGGLE_ALPHA_VIEW) {
+                self.state.alpha_view = !self.state.alpha_view;
+            }
+            if i.consume_shortcut(&crate::res::LOCK_VIEW) {
+                self.state.lock_view = !self.state.lock_view;
+            }
+            toggle_temporal_diff |= i.consume_shortcut(&crate::res::TEMPORAL_DIFF_TOGGLE);
+            if i.consume_shortcut(&crate::res::ANNOTATE_TOGGLE) {
+                self.state.annotate = !self.state.annotate;
+            }
+            if i.consume_shortcut(&crate::res::ANNOTATION_UNDO) {
+                self.undo_annotation();
+            }
+            if i.consume_shortcut(&crate::res::RESET_VIEW_SETTINGS) {
+                self.state.shader_params = ShaderParams::default();
+                self.viewer.show_osd("View settings reset");
+            }
+            let gamma_step = (i.consume_shortcut(&crate::res::GAMMA_UP) as i32
+                - i.consume_shortcut(&crate::res::GAMMA_DOWN) as i32) as f32;
+            if gamma_step != 0.0 {
+                let gamma = &mut self.state.shader_params.gamma;
+                *gamma = (*gamma + gamma_step * 0.1).clamp(0.1, 5.0);
+                self.viewer.show_osd(format!("Gamma {:.2}", *gamma));
+            }
+            let exposure_step = (i.consume_shortcut(&crate::res::EXPOSURE_UP) as i32
+                - i.consume_shortcut(&crate::res::EXPOSURE_DOWN) as i32) as f32;
+            if exposure_step != 0.0 {
+                let exposure = &mut self.state.shader_params.exposure;
+                *exposure = (*exposure + exposure_step * 0.25).clamp(-5.0, 5.0);
+                self.viewer.show_osd(format!("Exposure {:+.2} EV", *exposure));
+            }
             for slot in 0..crate::settings::VIEW_PRESET_COUNT {
                 if i.consume_shortcut(&crate::res::PRESET_SAVE_SHORTCUTS[slot]) {
                     save_view_preset = Some(slot);
@@ -1369,20 +3817,56 @@ impl ViewerApp {
                     break;
                 }
             }
+            if i.consume_shortcut(&crate::res::LABELING_TOGGLE) {
+                self.state.labeling = !self.state.labeling;
+                self.viewer.show_osd(if self.state.labeling {
+                    "Labeling on"
+                } else {
+                    "Labeling off"
+                });
+            }
+            if self.state.labeling {
+                toggle_label = (0..LABEL_COUNT).find(|&label| i.consume_shortcut(&crate::res::LABEL_SHORTCUTS[label]));
+            } else {
+                set_stars =
+                    (0..=MAX_STARS).find(|&stars| i.consume_shortcut(&crate::res::RATING_SHORTCUTS[stars as usize]));
+            }
+            if i.consume_shortcut(&crate::res::FLAG_PICK) {
+                set_flag = Some(Flag::Pick);
+            }
+            if i.consume_shortcut(&crate::res::FLAG_REJECT) {
+                set_flag = Some(Flag::Reject);
+            }
+            if i.consume_shortcut(&crate::res::FLAG_CLEAR) {
+                set_flag = Some(Flag::None);
+            }
             if i.consume_shortcut(&crate::res::SELECT_ALL_SC) {
                 if let Some(asset) = &self.state.asset {
-                    let spec = asset.image().spec();
-                    let img_rect = Recti::from_min_size(vec2i(0, 0), vec2i(spec.width, spec.height));
+                    let (width, height) = asset.image().spec().full_size();
+                    let img_rect = Recti::from_min_size(vec2i(0, 0), vec2i(width, height));
                     self.state.marquee_rect = img_rect;
                     self.tmp_marquee_rect = img_rect;
                     self.marquee_rect_text = img_rect.to_string();
                 }
             }
-            if i.consume_shortcut(&crate::res::SELECT_NONE_SC) {
+            if i.consume_shortcut(&crate::res::SELECT_NONE_SC) && self.state.point_picker.take().is_none() {
                 self.state.reset_marquee_rect();
             }
             request_save |= i.consume_shortcut(&crate::res::SAVE_IMAGE_SC);
+            if i.consume_shortcut(&crate::res::CROP_EXPORT_TOGGLE) {
+                self.crop_export_mode = !self.crop_export_mode;
+            }
+            save_crop |= self.crop_export_mode && i.consume_shortcut(&crate::res::CROP_EXPORT_SAVE);
             toggle_bookmark_panel |= i.consume_shortcut(&crate::res::BOOKMARK_PANEL_TOGGLE);
+            toggle_script_console |= i.consume_shortcut(&crate::res::SCRIPT_CONSOLE_TOGGLE);
+            toggle_perf_hud |= i.consume_shortcut(&crate::res::PERF_HUD_TOGGLE);
+            toggle_view_recording |= i.consume_shortcut(&crate::res::RECORD_VIEW_TOGGLE);
+            focus_asset_search |= i.consume_shortcut(&crate::res::ASSET_SEARCH_FOCUS);
+            toggle_grid |= i.consume_shortcut(&crate::res::GRID_TOGGLE);
+            toggle_probe_window |= i.consume_shortcut(&crate::res::PROBE_WINDOW_TOGGLE);
+            if i.consume_shortcut(&crate::res::PROBE_HOLD) {
+                self.probe_hold = !self.probe_hold;
+            }
             add_bookmark |= i.consume_shortcut(&crate::res::BOOKMARK_ADD);
             navigate_prev_bookmark |= i.consume_shortcut(&crate::res::BOOKMARK_PREV);
             navigate_next_bookmark |= i.consume_shortcut(&crate::res::BOOKMARK_NEXT);
@@ -1390,15 +3874,19 @@ impl ViewerApp {
                 || Self::open_from_clipboard_shortcut_released(i);
             if i.consume_shortcut(&crate::res::NAVIGATE_PREV) {
                 if let Err(e) = self.state.navigate_prev() {
-                    let path = self.state.file_nav.navigate_prev();
-                    Self::load_fail(&mut self.toasts, "Failed to load navigated file", path.as_ref(), &e);
+                    let path = self.state.prev_file_path();
+                    Self::load_fail_or_ask_layout(
+                        &mut self.toasts,
+                        &mut self.yuv_dialog,
+                        &mut self.raw_dialog,
+                        "Failed to load navigated file",
+                        path.as_ref(),
+                        &e,
+                    );
                 }
             }
             if i.consume_shortcut(&crate::res::NAVIGATE_NEXT) {
-                if let Err(e) = self.state.navigate_next() {
-                    let path = self.state.file_nav.navigate_next();
-                    Self::load_fail(&mut self.toasts, "Failed to load navigated file", path.as_ref(), &e);
-                }
+                self.navigate_next_file();
             }
             if i.consume_shortcut(&crate::res::NAVIGATE_ASSET_PREV) {
                 self.state.navigate_asset_prev();
@@ -1419,12 +3907,57 @@ impl ViewerApp {
         if request_save && self.state.asset.is_some() {
             self.request_viewer_image_save(ctx);
         }
+        if save_crop {
+            self.save_crop_and_advance(ctx);
+        }
+        if let Some(label) = toggle_label {
+            self.toggle_label(label);
+        }
+        if let Some(stars) = set_stars {
+            self.rate_primary_file(|rating| rating.stars = stars);
+        }
+        if let Some(flag) = set_flag {
+            self.rate_primary_file(|rating| rating.flag = flag);
+        }
         if let Some(slot) = save_view_preset {
             self.save_view_preset(slot);
             ctx.request_repaint();
         } else if let Some(slot) = apply_view_preset {
             self.apply_view_preset(slot, ctx);
         }
+        if toggle_script_console {
+            self.show_script_console = !self.show_script_console;
+            ctx.request_repaint();
+        }
+        if toggle_probe_window {
+            self.show_probe_window = !self.show_probe_window;
+            ctx.request_repaint();
+        }
+        if toggle_temporal_diff {
+            self.set_temporal_diff(!self.temporal_diff);
+            ctx.request_repaint();
+        }
+        if toggle_grid {
+            self.state.grid.enabled = !self.state.grid.enabled;
+            if self.state.grid.enabled && self.state.grid_assets().is_none() {
+                self.viewer.show_osd("Add at least two images to the grid");
+            }
+            ctx.request_repaint();
+        }
+        if focus_asset_search {
+            self.state.is_show_sidebar = true;
+            ctx.memory_mut(|m| m.request_focus(egui::Id::new(ASSET_SEARCH_ID)));
+            ctx.request_repaint();
+        }
+        if toggle_perf_hud {
+            self.show_perf_hud = !self.show_perf_hud;
+            self.perf_hud.reset();
+            ctx.request_repaint();
+        }
+        if toggle_view_recording {
+            self.toggle_view_recording();
+            ctx.request_repaint();
+        }
         if toggle_bookmark_panel {
             self.show_bookmarks_modal = !self.show_bookmarks_modal;
             ctx.request_repaint();
@@ -1436,56 +3969,291 @@ impl ViewerApp {
         } else if navigate_next_bookmark {
             self.navigate_bookmark(1, ctx);
         }
-        if open_from_clipboard {
-            self.open_from_clipboard();
+        if open_from_clipboard {
+            self.open_from_clipboard(ctx);
+        }
+    }
+
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped_files = ctx.input(|i| i.raw.dropped_files.clone());
+        // Decoded on the loader thread so large files can show partial rows while they load.
+        self.pending_paths.extend(dropped_files.into_iter().filter_map(|f| f.path));
+    }
+
+    fn run_logic(&mut self, ctx: &egui::Context) {
+        #[cfg(debug_assertions)]
+        let _timer = ScopedTimer::new("ui.app.logic");
+
+        self.poll_pending_image_save_dialog(ctx);
+
+        ctx.set_visuals(Visuals::dark());
+
+        if !self.is_start_background_event_handlers_called {
+            self.start_background_event_handlers(ctx);
+            self.configure_watch_folders(ctx);
+            self.is_start_background_event_handlers_called = true;
+        }
+
+        self.start_pending_path_loading(ctx);
+        if !self.output_lut_initialized && !self.state.safe_mode {
+            self.output_lut_initialized = true;
+            self.rebuild_output_lut(ctx);
+            if let Some(path) = self.app_settings.ui_state.look_lut.clone() {
+                self.load_look_lut(ctx, path);
+            }
+        }
+        self.handle_event(ctx);
+        self.refresh_control_registration(ctx);
+        self.handle_window_commands(ctx);
+
+        if self.close_for_update {
+            return;
+        }
+
+        self.handle_global_shortcuts(ctx);
+        self.handle_dropped_files(ctx);
+
+        self.state.sync_overlays_with_asset_type();
+
+        self.state.validate_marquee_rect();
+        self.state.process_watcher_events();
+        self.state.content_hashes.update(ctx, &self.state.assets);
+        self.update_auto_compare(ctx);
+        self.update_temporal_diff(ctx);
+        self.ingest_watch_folders(ctx);
+        self.sync_file_view();
+        self.track_window_size(ctx);
+        self.apply_recovered_view();
+        self.write_recovery_journal();
+        self.sync_note();
+        self.sync_view_lock();
+    }
+
+    fn track_window_size(&mut self, ctx: &egui::Context) {
+        let (inner_rect, maximized, fullscreen) = ctx.input(|i| {
+            let viewport = i.viewport();
+            (viewport.inner_rect, viewport.maximized, viewport.fullscreen)
+        });
+        if maximized == Some(true) || fullscreen == Some(true) {
+            return;
+        }
+        if let Some(rect) = inner_rect {
+            self.window_size = Some(rect.size().into());
         }
     }
 
-    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
-        let dropped_files = ctx.input(|i| i.raw.dropped_files.clone());
-        if dropped_files.is_empty() {
+    fn configure_watch_folders(&mut self, ctx: &egui::Context) {
+        if self.state.safe_mode {
             return;
         }
+        for error in self.watch_folders.configure(ctx, &self.app_settings.watch_folders) {
+            eprintln!("{error}");
+            self.toasts.add_error(error);
+        }
+    }
+
+    /// Load settled watch folder images on worker threads and add loaded ones to the image list.
+    fn ingest_watch_folders(&mut self, ctx: &egui::Context) {
+        for file in self.watch_folders.poll() {
+            let (tx, load_ctx) = (self.watch_folder_load_tx.clone(), ctx.clone());
+            thread::spawn(move || {
+                let path = file.path;
+                let result = match FileAsset::hash_from_path(&path)
+                    .and_then(|hash| ImageData::load_from_path(&path).map(|image| (hash, image)))
+                {
+                    Ok((hash, image)) => PathLoadResult::Loaded { path, hash, image },
+                    Err(error) => PathLoadResult::Failed { path, error },
+                };
+                let _ = tx.send((result, file.auto_select));
+                Self::request_root_repaint(&load_ctx);
+            });
+        }
+        if self.watch_folders.has_pending() {
+            ctx.request_repaint_after(WATCH_FOLDER_SETTLE_DELAY);
+        }
 
-        for f in dropped_files {
-            if let Some(path) = f.path {
-                match self.state.load_from_path(path.clone()) {
-                    Ok(_) => {}
-                    Err(e) => {
-                        Self::load_fail(&mut self.toasts, "Failed to load dropped file", Some(&path), &e);
+        while let Ok((result, auto_select)) = self.watch_folder_load_rx.try_recv() {
+            match result {
+                PathLoadResult::Loaded { path, hash, image } => {
+                    self.state.add_file_asset(&path, hash.clone(), image);
+                    if auto_select {
+                        self.state.set_file_asset_primary_by_hash_and_path(&hash, &path);
                     }
                 }
+                PathLoadResult::Failed { path, error } => {
+                    Self::load_fail(&mut self.toasts, "Failed to load watched image", Some(&path), &error);
+                }
+                PathLoadResult::Partial { .. } | PathLoadResult::Reused { .. } => {}
             }
         }
     }
 
-    fn run_logic(&mut self, ctx: &egui::Context) {
-        #[cfg(debug_assertions)]
-        let _timer = ScopedTimer::new("ui.app.logic");
+    /// When the primary image is a render with a reference under the configured mapping, load the reference
+    /// as the secondary asset and compute the comparison metrics.
+    fn update_auto_compare(&mut self, ctx: &egui::Context) {
+        if let Some((primary_hash, rx)) = &self.auto_compare_rx {
+            let is_current = self
+                .state
+                .asset_primary
+                .as_ref()
+                .is_some_and(|asset| asset.hash() == primary_hash);
+            match rx.try_recv() {
+                Ok(PathLoadResult::Loaded { path, hash, image }) => {
+                    self.auto_compare_rx = None;
+                    if is_current {
+                        let path = path.to_string_lossy().to_string();
+                        self.set_auto_compare_reference(Arc::new(FileAsset::new(path, hash, image)));
+                    }
+                }
+                Ok(PathLoadResult::Failed { path, error }) => {
+                    self.auto_compare_rx = None;
+                    Self::load_fail(&mut self.toasts, "Failed to load reference image", Some(&path), &error);
+                }
+                Ok(_) | Err(mpsc::TryRecvError::Disconnected) => self.auto_compare_rx = None,
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+        }
 
-        self.poll_pending_image_save_dialog(ctx);
+        let Some(primary) = self
+            .state
+            .asset_primary
+            .as_ref()
+            .filter(|asset| asset.asset_type() == AssetType::File)
+        else {
+            return;
+        };
+        if self.auto_compare_checked.as_deref() == Some(primary.hash()) {
+            return;
+        }
+        let primary_hash = primary.hash().to_string();
+        self.auto_compare_checked = Some(primary_hash.clone());
+        let Some(reference) = self
+            .app_settings
+            .auto_compare
+            .reference_path(std::path::Path::new(primary.name()))
+        else {
+            return;
+        };
+        if !reference.is_file() {
+            self.toasts
+                .add_warning(format!("No reference image at {}", reference.display()));
+            return;
+        }
 
-        ctx.set_visuals(Visuals::dark());
+        let hash = match FileAsset::hash_from_path(&reference) {
+            Ok(hash) => hash,
+            Err(err) => {
+                return Self::load_fail(&mut self.toasts, "Failed to read reference image", Some(&reference), &err)
+            }
+        };
+        if let Some(asset) = self.state.assets.get(&hash).cloned() {
+            self.set_auto_compare_reference(asset);
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let load_ctx = ctx.clone();
+        thread::spawn(move || {
+            let result = match ImageData::load_from_path(&reference) {
+                Ok(image) => PathLoadResult::Loaded {
+                    path: reference,
+                    hash,
+                    image,
+                },
+                Err(error) => PathLoadResult::Failed { path: reference, error },
+            };
+            let _ = tx.send(result);
+            Self::request_root_repaint(&load_ctx);
+        });
+        self.auto_compare_rx = Some((primary_hash, rx));
+    }
 
-        if !self.is_start_background_event_handlers_called {
-            self.start_background_event_handlers(ctx);
-            self.is_start_background_event_handlers_called = true;
+    fn set_temporal_diff(&mut self, enabled: bool) {
+        self.temporal_diff = enabled;
+        self.temporal_diff_checked = None;
+        self.temporal_diff_rx = None;
+        if enabled {
+            self.state.comparison_mode = ComparisonMode::Diff;
+        } else {
+            self.state.set_secondary_asset(None);
         }
+    }
 
-        self.start_startup_path_loading(ctx);
-        self.handle_event(ctx);
-        self.refresh_control_registration(ctx);
-        self.handle_window_commands(ctx);
+    /// While the temporal difference is on, keep the frame before the primary image as the secondary asset, loading
+    /// it in the background when it is a file that is not listed yet.
+    fn update_temporal_diff(&mut self, ctx: &egui::Context) {
+        if let Some((primary_hash, rx)) = &self.temporal_diff_rx {
+            let is_current = self
+                .state
+                .asset_primary
+                .as_ref()
+                .is_some_and(|asset| asset.hash() == primary_hash);
+            match rx.try_recv() {
+                Ok(PathLoadResult::Loaded { path, hash, image }) => {
+                    self.temporal_diff_rx = None;
+                    if is_current {
+                        let path = path.to_string_lossy().to_string();
+                        self.state
+                            .set_secondary_asset(Some(Arc::new(FileAsset::new(path, hash, image))));
+                    }
+                }
+                Ok(PathLoadResult::Failed { path, error }) => {
+                    self.temporal_diff_rx = None;
+                    Self::load_fail(&mut self.toasts, "Failed to load previous frame", Some(&path), &error);
+                }
+                Ok(_) | Err(mpsc::TryRecvError::Disconnected) => self.temporal_diff_rx = None,
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+        }
 
-        if self.close_for_update {
+        if !self.temporal_diff {
             return;
         }
+        let Some(primary) = self.state.asset_primary.clone() else {
+            return;
+        };
+        if self.temporal_diff_checked.as_deref() == Some(primary.hash()) {
+            return;
+        }
+        let primary_hash = primary.hash().to_string();
+        self.temporal_diff_checked = Some(primary_hash.clone());
+        match self.state.previous_frame(&primary) {
+            Some(PreviousFrame::Asset(previous)) => self.state.set_secondary_asset(Some(previous)),
+            Some(PreviousFrame::File(path)) => {
+                let hash = match FileAsset::hash_from_path(&path) {
+                    Ok(hash) => hash,
+                    Err(err) => {
+                        return Self::load_fail(&mut self.toasts, "Failed to read previous frame", Some(&path), &err)
+                    }
+                };
+                let (tx, rx) = mpsc::channel();
+                let load_ctx = ctx.clone();
+                thread::spawn(move || {
+                    let result = match ImageData::load_from_path(&path) {
+                        Ok(image) => PathLoadResult::Loaded { path, hash, image },
+                        Err(error) => PathLoadResult::Failed { path, error },
+                    };
+                    let _ = tx.send(result);
+                    Self::request_root_repaint(&load_ctx);
+                });
+                self.temporal_diff_rx = Some((primary_hash, rx));
+            }
+            // The first frame has nothing to compare with.
+            None => self.state.set_secondary_asset(None),
+        }
+    }
 
-        self.handle_global_shortcuts(ctx);
-        self.handle_dropped_files(ctx);
-
-        self.state.validate_marquee_rect();
-        self.state.process_watcher_events();
+    fn set_auto_compare_reference(&mut self, reference: SharedAsset) {
+        if self
+            .state
+            .asset_primary
+            .as_ref()
+            .is_some_and(|primary| primary.hash() == reference.hash())
+        {
+            return;
+        }
+        self.state.set_secondary_asset(Some(reference));
+        self.show_statistics = true;
+        self.update_statistics();
     }
 
     fn paint_asset_drag_preview(&self, ctx: &egui::Context) {
@@ -1516,11 +4284,31 @@ impl eframe::App for ViewerApp {
         self.run_logic(ctx);
     }
 
+    fn on_exit(&mut self) {
+        self.save_note();
+        if let Some(journal) = self.recovery_journal.take() {
+            journal.finish();
+        }
+        crate::model::remove_temp_exports();
+        let mut ui_state = self.current_persistent_ui_state();
+        if self.window_size.is_some() {
+            ui_state.window_size = self.window_size;
+        }
+        let ui_state_changed = ui_state != self.app_settings.ui_state;
+        self.app_settings.ui_state = ui_state;
+        if self.remember_shown_file_view() || self.file_views_changed || ui_state_changed {
+            if let Err(err) = self.app_settings.save() {
+                eprintln!("Failed to save settings: {err}");
+            }
+        }
+    }
+
     fn ui(&mut self, ui: &mut egui::Ui, frame: &mut eframe::Frame) {
         #[cfg(debug_assertions)]
         let _timer = ScopedTimer::new("ui.app.ui");
 
         let ctx = ui.ctx().clone();
+        let frame_start = self.show_perf_hud.then(|| self.perf_hud.begin_frame());
 
         if let Some(render_state) = frame.wgpu_render_state() {
             crate::model::install_gpu_compute(
@@ -1558,14 +4346,26 @@ impl eframe::App for ViewerApp {
                 ui.menu_button("File", |ui| {
                     if ui.button("Open...").clicked() {
                         ui.close();
-                        let supported_extensions = crate::supported_image::supported_image_extensions();
+                        let supported_extensions = crate::plugin::openable_extensions();
                         if let Some(path) = FileDialog::new()
                             .add_filter("Images", supported_extensions.as_slice())
+                            .add_filter("Image archives", crate::supported_image::ARCHIVE_EXTENSIONS)
                             .pick_file()
                         {
                             match self.state.load_from_path(path.clone()) {
-                                Ok(_) => self.viewer.reset_view(),
-                                Err(e) => Self::load_fail(&mut self.toasts, "Failed to open file", Some(&path), &e),
+                                Ok(_) => {
+                                    if !self.state.lock_view {
+                                        self.viewer.reset_view();
+                                    }
+                                }
+                                Err(e) => Self::load_fail_or_ask_layout(
+                                    &mut self.toasts,
+                                    &mut self.yuv_dialog,
+                                    &mut self.raw_dialog,
+                                    "Failed to open file",
+                                    Some(&path),
+                                    &e,
+                                ),
                             }
                         }
                     }
@@ -1578,8 +4378,129 @@ impl eframe::App for ViewerApp {
                         .on_hover_text("Load image from clipboard")
                         .clicked()
                     {
-                        self.open_from_clipboard();
+                        self.open_from_clipboard(&ctx);
+                    }
+
+                    ui.separator();
+                    let save_as_source = self.active_display_asset().map(|asset| {
+                        let spec = asset.image().spec();
+                        let rect = self.state.marquee_rect.validate();
+                        let size = if rect.empty() {
+                            (spec.width as u32, spec.height as u32)
+                        } else {
+                            (rect.width() as u32, rect.height() as u32)
+                        };
+                        (spec.dtype, size, !rect.empty())
+                    });
+                    if ui
+                        .add_enabled(
+                            save_as_source.is_some() && self.save_as_rx.is_none(),
+                            egui::Button::new("Save As..."),
+                        )
+                        .on_hover_text(
+                            "Write the stored samples of the image or selection as PNG, JPEG, TIFF, EXR or PFM",
+                        )
+                        .clicked()
+                    {
+                        ui.close();
+                        if let Some((dtype, size, is_selection)) = save_as_source {
+                            self.save_as_dialog =
+                                Some(SaveAsDialog::new(self.save_as_options, dtype, size, is_selection));
+                        }
+                    }
+                    let print_size = self.state.asset.as_ref().map(|asset| {
+                        let spec = asset.image().spec();
+                        let rect = self.state.marquee_rect.validate();
+                        if rect.empty() {
+                            (spec.width as u32, spec.height as u32)
+                        } else {
+                            (rect.width() as u32, rect.height() as u32)
+                        }
+                    });
+                    if ui
+                        .add_enabled(print_size.is_some(), egui::Button::new("Print..."))
+                        .on_hover_text("Print the image or selection as displayed")
+                        .clicked()
+                    {
+                        ui.close();
+                        if let Some(size) = print_size {
+                            self.print_dialog = Some(PrintDialog::new(self.print_layout, size));
+                        }
+                    }
+                    let frame_count = self.state.file_nav.files_in_dir.len();
+                    if ui
+                        .add_enabled(
+                            print_size.is_some() && frame_count > 1 && self.directory_export.is_none(),
+                            egui::Button::new("Export Sequence..."),
+                        )
+                        .on_hover_text("Render the images of this directory into a GIF or MP4")
+                        .clicked()
+                    {
+                        ui.close();
+                        if let Some(asset) = &self.state.asset {
+                            let spec = asset.image().spec();
+                            self.sequence_dialog = Some(SequenceExportDialog::new(
+                                self.sequence_options,
+                                frame_count,
+                                (spec.width as u32, spec.height as u32),
+                            ));
+                        }
+                    }
+                    if ui
+                        .add_enabled(
+                            frame_count > 0 && self.directory_export.is_none(),
+                            egui::Button::new("Create Contact Sheet..."),
+                        )
+                        .on_hover_text("Tile thumbnails of the images in this directory into one image")
+                        .clicked()
+                    {
+                        ui.close();
+                        self.contact_sheet_dialog =
+                            Some(ContactSheetDialog::new(self.contact_sheet_options, frame_count));
+                    }
+                    if ui
+                        .add_enabled(
+                            self.state.asset.is_some() && self.view_recording.is_none(),
+                            egui::Button::new("Record View as GIF..."),
+                        )
+                        .on_hover_text(format!(
+                            "Record panning and zooming in the image view ({})",
+                            crate::res::RECORD_VIEW_TOGGLE.format_sys()
+                        ))
+                        .clicked()
+                    {
+                        ui.close();
+                        self.toggle_view_recording();
+                    }
+                    if ui
+                        .button("Load Overlay...")
+                        .on_hover_text(
+                            "Draw boxes, polygons and keypoints from a COCO JSON or CSV file over the images",
+                        )
+                        .clicked()
+                    {
+                        ui.close();
+                        if let Some(path) = FileDialog::new().add_filter("Overlay", &["json", "csv"]).pick_file() {
+                            self.load_vector_overlay(&ctx, path);
+                        }
+                    }
+                    if ui
+                        .button("Load Segmentation Mask...")
+                        .on_hover_text("Show a paletted or integer label image as a colored layer over the image")
+                        .clicked()
+                    {
+                        ui.close();
+                        let supported_extensions = crate::plugin::openable_extensions();
+                        if let Some(path) = FileDialog::new()
+                            .add_filter("Images", supported_extensions.as_slice())
+                            .pick_file()
+                        {
+                            self.load_segmentation_mask(&ctx, path);
+                        }
                     }
+                    ui.menu_button("Crop Export", |ui| self.crop_export_menu_ui(ui));
+                    ui.menu_button("Labels", |ui| self.labels_menu_ui(ui));
+                    ui.separator();
 
                     if ui.button("Exit").clicked() {
                         ui.close();
@@ -1596,8 +4517,8 @@ impl eframe::App for ViewerApp {
                     let rect = self.state.marquee_rect.validate();
                     if rect.empty() {
                         if let Some(asset) = &self.state.asset {
-                            let spec = asset.image().spec();
-                            let img_rect = Recti::from_min_size(vec2i(0, 0), vec2i(spec.width, spec.height));
+                            let (width, height) = asset.image().spec().full_size();
+                            let img_rect = Recti::from_min_size(vec2i(0, 0), vec2i(width, height));
                             self.viewer.fit_rect(img_rect);
                         }
                     } else {
@@ -1608,8 +4529,8 @@ impl eframe::App for ViewerApp {
                     let rect = self.state.marquee_rect.validate();
                     if rect.empty() {
                         if let Some(asset) = &self.state.asset {
-                            let spec = asset.image().spec();
-                            let img_rect = Recti::from_min_size(vec2i(0, 0), vec2i(spec.width, spec.height));
+                            let (width, height) = asset.image().spec().full_size();
+                            let img_rect = Recti::from_min_size(vec2i(0, 0), vec2i(width, height));
                             self.viewer.center_rect(img_rect);
                         }
                     } else {
@@ -1617,39 +4538,125 @@ impl eframe::App for ViewerApp {
                     }
                 }
 
+                if let Some(recording) = self.view_recording.as_mut().filter(|recording| recording.is_recording()) {
+                    ui.colored_label(DANGER_TEXT, format!("\u{25cf} REC {:.1} s", recording.elapsed().as_secs_f32()));
+                    if ui
+                        .button("Stop")
+                        .on_hover_text(format!(
+                            "Stop recording and write the GIF ({})",
+                            crate::res::RECORD_VIEW_TOGGLE.format_sys()
+                        ))
+                        .clicked()
+                    {
+                        recording.stop();
+                    }
+                }
+
+                ui.toggle_value(&mut self.state.lock_view, "Lock View").on_hover_text(format!(
+                    "Keep zoom, pan and selection when switching between images of the same size ({})",
+                    crate::res::LOCK_VIEW.format_sys()
+                ));
+                ui.toggle_value(&mut self.state.annotate, "Annotate").on_hover_text(format!(
+                    "Draw on the image; drag with the right button or the pen's barrel button to erase ({})",
+                    crate::res::ANNOTATE_TOGGLE.format_sys()
+                ));
+                if self.state.annotate {
+                    ui.color_edit_button_srgba(&mut self.state.annotation_color);
+                    ui.add(
+                        egui::DragValue::new(&mut self.state.annotation_width)
+                            .range(0.5..=50.0)
+                            .speed(0.1)
+                            .suffix(" pt"),
+                    )
+                    .on_hover_text("Stroke width at full pen pressure");
+                    if ui
+                        .button("Undo")
+                        .on_hover_text(format!("Remove the last stroke ({})", crate::res::ANNOTATION_UNDO.format_sys()))
+                        .clicked()
+                    {
+                        self.undo_annotation();
+                    }
+                    if ui.button("Clear").on_hover_text("Remove all strokes on this image").clicked() {
+                        if let Some(asset) = &self.state.asset_primary {
+                            self.state.annotations.clear(asset.hash());
+                        }
+                    }
+                }
+
                 ui.separator();
-                ui.checkbox(&mut self.state.copy_use_original_size, "Copy at original size")
-                    .on_hover_text(format!(
-                        "When enabled, {} copies marquee at image pixel size (ignores zoom).",
-                        crate::res::COPY_SC.format_sys()
-                    ));
+                let copy_size = if self.state.export_options.data == crate::settings::ExportData::Display {
+                    self.state.export_options.size_label()
+                } else {
+                    self.state.export_options.data.label().to_string()
+                };
+                ui.menu_button(format!("Copy: {copy_size}"), |ui| {
+                    export_options_ui(ui, &mut self.state.export_options)
+                })
+                .response
+                .on_hover_text(format!(
+                    "Size and data of {} marquee copies and of saves",
+                    crate::res::COPY_SC.format_sys()
+                ));
                 ui.toggle_icon(
                     &mut self.state.is_show_background,
                     self.icons.get_show_background(&ctx),
-                    "Show Background",
-                );
+                    &format!(
+                        "Show Background ({}, right-click for options)",
+                        crate::res::TOGGLE_BACKGROUND.format_sys()
+                    ),
+                )
+                .context_menu(|ui| background_settings_ui(ui, &mut self.state.background));
                 ui.toggle_icon(
                     &mut self.state.is_show_pixel_value,
                     self.icons.get_show_pixel_value(&ctx),
-                    "Show Pixel Value (Zoom in to see values)",
-                );
+                    &format!(
+                        "Show Pixel Value ({}, zoom in to see values, right-click for options)",
+                        crate::res::TOGGLE_PIXEL_VALUE.format_sys()
+                    ),
+                )
+                .context_menu(|ui| pixel_value_settings_ui(ui, &mut self.state.pixel_value));
                 ui.toggle_icon(
                     &mut self.state.is_show_crosshair,
                     self.icons.get_show_crosshair(&ctx),
-                    "Show Crosshair",
+                    &format!("Show Crosshair ({})", crate::res::TOGGLE_CROSSHAIR.format_sys()),
+                );
+                ui.toggle_icon(
+                    &mut self.state.is_show_image_border,
+                    self.icons.get_show_image_border(&ctx),
+                    &format!("Show Image Border ({})", crate::res::TOGGLE_IMAGE_BORDER.format_sys()),
                 );
 
                 ui.visuals_mut().override_text_color = Some(ui.visuals().weak_text_color());
                 let socket_address = self.state.socket_info.lock().unwrap().address.clone();
-                ui.label(socket_address.clone())
+                let socket_running = self.socket_server.is_some();
+                let socket_label = if socket_running {
+                    socket_address.as_str()
+                } else {
+                    "Socket stopped"
+                };
+                ui.label(socket_label)
                     .on_hover_text("Socket Listener Address")
                     .context_menu(|ui| {
-                        if ui.button("Copy Address").clicked() {
-                            arboard::Clipboard::new()
-                                .and_then(|mut cb| cb.set_text(socket_address))
-                                .unwrap_or_else(|e| {
-                                    eprintln!("Failed to copy socket address to clipboard: {e}");
-                                });
+                        if socket_running {
+                            if ui.button("Copy Address").clicked() {
+                                arboard::Clipboard::new()
+                                    .and_then(|mut cb| cb.set_text(socket_address.clone()))
+                                    .unwrap_or_else(|e| {
+                                        eprintln!("Failed to copy socket address to clipboard: {e}");
+                                    });
+                                ui.close();
+                            }
+                            ui.separator();
+                            if ui.button("Stop Listener").clicked() {
+                                self.stop_socket_server();
+                                ui.close();
+                            }
+                            if ui.button("Restart Listener").clicked() {
+                                self.restart_socket_server();
+                                ui.close();
+                            }
+                        } else if ui.button("Start Listener").clicked() {
+                            self.start_socket_server();
                             ui.close();
                         }
                     });
@@ -1673,6 +4680,40 @@ impl eframe::App for ViewerApp {
 
                     ui.toggle_value(&mut self.state.is_show_statusbar, "Status Bar");
                     ui.toggle_value(&mut self.state.is_show_sidebar, "Sidebar");
+                    ui.toggle_value(&mut self.show_memory_window, "Memory")
+                        .on_hover_text("Show memory used by each image and its caches");
+                    ui.toggle_value(&mut self.show_probe_window, "Probe").on_hover_text(format!(
+                        "Show the pixel under the pointer in every image of the same size ({})",
+                        crate::res::PROBE_WINDOW_TOGGLE.format_sys()
+                    ));
+                    let mut temporal_diff = self.temporal_diff;
+                    if ui
+                        .toggle_value(&mut temporal_diff, "Temporal Diff")
+                        .on_hover_text(format!(
+                            "Show the difference from the previous frame of the folder or socket history ({})",
+                            crate::res::TEMPORAL_DIFF_TOGGLE.format_sys()
+                        ))
+                        .changed()
+                    {
+                        self.set_temporal_diff(temporal_diff);
+                    }
+                    ui.toggle_value(&mut self.timeline_window.open, "Timeline").on_hover_text(
+                        "Plot a pixel value, selection mean or PSNR over the files in the folder or the socket history",
+                    );
+                    if ui
+                        .toggle_value(&mut self.show_perf_hud, "Perf")
+                        .on_hover_text(format!(
+                            "Show frame, decode and upload timings ({})",
+                            crate::res::PERF_HUD_TOGGLE.format_sys()
+                        ))
+                        .changed()
+                    {
+                        self.perf_hud.reset();
+                    }
+                    ui.toggle_value(&mut self.show_script_console, "Console").on_hover_text(format!(
+                        "Show script console ({})",
+                        crate::res::SCRIPT_CONSOLE_TOGGLE.format_sys()
+                    ));
                     ui.toggle_value(&mut self.show_bookmarks_modal, "Bookmarks")
                         .on_hover_text(format!(
                             "Show bookmark panel ({})",
@@ -1684,42 +4725,87 @@ impl eframe::App for ViewerApp {
 
         self.show_update_confirmation_dialog(&ctx);
         self.show_update_progress_dialog(&ctx);
+        self.show_recovery_dialog(&ctx);
         self.show_settings_dialog(&ctx);
         self.show_bookmarks_dialog(&ctx);
+        self.show_script_console_window(&ctx);
+        if let Some((path, frame)) = show_yuv_frame_dialog(&ctx, &mut self.yuv_dialog) {
+            crate::model::set_yuv_file_frame(&path, frame);
+            self.pending_paths.push(path);
+        }
+        if let Some((path, layout)) = show_raw_layout_dialog(&ctx, &mut self.raw_dialog) {
+            crate::model::set_raw_file_layout(&path, layout);
+            self.pending_paths.push(path);
+        }
+        if let Some(options) = show_sequence_export_dialog(&ctx, &mut self.sequence_dialog) {
+            self.sequence_options = options;
+            self.start_sequence_export(&ctx, options);
+        }
+        if let Some(options) = show_contact_sheet_dialog(&ctx, &mut self.contact_sheet_dialog) {
+            self.contact_sheet_options = options;
+            self.start_contact_sheet(&ctx, options);
+        }
+        self.update_directory_export(&ctx, frame);
+        if let Some(options) =
+            show_record_view_dialog(&ctx, &mut self.record_dialog, &crate::res::RECORD_VIEW_TOGGLE.format_sys())
+        {
+            self.record_options = options;
+            self.start_view_recording(&ctx, options);
+        }
+        self.update_view_recording(&ctx);
+        if let Some(options) = show_save_as_dialog(&ctx, &mut self.save_as_dialog) {
+            self.save_as_options = options;
+            self.save_image_as(&ctx, options);
+        }
+        if let Some(layout) = show_print_dialog(&ctx, &mut self.print_dialog) {
+            self.print_layout = layout;
+            self.viewer.request_print(layout);
+            ctx.request_repaint();
+        }
 
         if self.state.is_show_statusbar {
             egui::Panel::bottom("bottom").show(ui, |ui| {
                 ui.columns_sized(
                     [
                         Size::exact(400.0),
-                        Size::exact(160.0),
+                        Size::exact(240.0),
                         Size::exact(40.0),
                         Size::remainder(1.0),
                         Size::exact(108.0),
                     ],
                     |columns| {
                         columns[0].vertical(|ui| {
-                            if let Some(asset) = self.active_display_asset() {
+                            if let Some(asset) = self.active_display_asset().cloned() {
                                 let cursor_image = asset.image();
                                 let spec = cursor_image.spec();
                                 let dtype = cursor_image.spec().dtype;
 
-                                let cursor_color = if let Ok(pixel) = cursor_image.get_pixel_at(
-                                    self.state.cursor_pos.map_or(-1, |p| p.x),
-                                    self.state.cursor_pos.map_or(-1, |p| p.y),
-                                ) {
-                                    pixel.iter().cloned().collect()
-                                } else {
-                                    vec![0.0; spec.channels as usize]
-                                };
-                                ui.label_with_colored_rect(cursor_color, dtype);
+                                // When zoomed out, a screen pixel covers several image pixels; report their average.
+                                let footprint = 1.0 / self.viewer.zoom();
+                                let cursor_color = self
+                                    .state
+                                    .cursor_pos
+                                    .and_then(|p| {
+                                        cursor_image
+                                            .sample_area(p.x as f32 + 0.5, p.y as f32 + 0.5, footprint)
+                                            .ok()
+                                    })
+                                    .unwrap_or_else(|| vec![0.0; spec.channels as usize]);
+                                let cursor_response = ui.label_with_colored_rect(cursor_color, dtype);
+                                if footprint > 1.0 {
+                                    cursor_response.on_hover_text(format!(
+                                        "Average of the {footprint:.1}×{footprint:.1} image pixels under one screen pixel"
+                                    ));
+                                }
 
-                                let rect = self.state.marquee_rect;
-                                let rect_image = asset.image();
-                                let mean_color = if let Ok(mean) = rect_image.mean_value_in_rect(rect, MeanDim::All) {
-                                    mean.iter().map(|&v| v as f32).collect()
+                                let repaint_ctx = ui.ctx().clone();
+                                let mean = self.status_mean.get(cursor_image, self.state.marquee_rect, move || {
+                                    repaint_ctx.request_repaint()
+                                });
+                                let mean_color = if mean.is_empty() && !self.state.marquee_rect.validate().empty() {
+                                    vec![0.0; spec.channels as usize]
                                 } else {
-                                    vec![0.0; rect_image.spec().channels as usize]
+                                    mean.iter().map(|&v| v as f32).collect()
                                 };
                                 ui.label_with_colored_rect(mean_color, dtype);
                             }
@@ -1727,7 +4813,23 @@ impl eframe::App for ViewerApp {
 
                         columns[1].vertical(|ui| {
                             if let Some(cursor_pos) = self.state.cursor_pos {
-                                ui.label(format!("Cursor: {}, {}", cursor_pos.x, cursor_pos.y));
+                                // Past 100% the cursor moves within a pixel, so also show where inside it is.
+                                match self.state.cursor_subpixel.filter(|_| self.viewer.zoom() > 1.0) {
+                                    Some(subpixel) => {
+                                        let subpixel_text = format!("{:.2}, {:.2}", subpixel.x, subpixel.y);
+                                        ui.label(format!("Cursor: {}, {} ({subpixel_text})", cursor_pos.x, cursor_pos.y))
+                                            .on_hover_text("Sub-pixel position; pixel centers are at .50")
+                                            .context_menu(|ui| {
+                                                if ui.button("Copy Sub-pixel Position").clicked() {
+                                                    ui.ctx().copy_text(subpixel_text.clone());
+                                                    ui.close();
+                                                }
+                                            });
+                                    }
+                                    None => {
+                                        ui.label(format!("Cursor: {}, {}", cursor_pos.x, cursor_pos.y));
+                                    }
+                                }
                             } else {
                                 ui.label("Cursor: -, -");
                             }
@@ -1750,6 +4852,41 @@ impl eframe::App for ViewerApp {
                                         });
                                     ui.close();
                                 }
+                                let snippet_settings = self.app_settings.python_snippet.clone();
+                                let mut snippet_style = None;
+                                if ui
+                                    .button(format!("Copy Python Snippet ({})", snippet_settings.style.label()))
+                                    .on_hover_text("Snippet style and options can be changed in Settings.")
+                                    .clicked()
+                                {
+                                    snippet_style = Some(snippet_settings.style);
+                                }
+                                ui.menu_button("Copy Python Snippet As", |ui| {
+                                    for style in crate::settings::PythonSnippetStyle::ALL {
+                                        if ui.button(style.label()).clicked() {
+                                            snippet_style = Some(style);
+                                        }
+                                    }
+                                });
+                                if let Some(style) = snippet_style {
+                                    let path =
+                                        self.active_display_file_path().filter(|_| snippet_settings.include_load);
+                                    let channels =
+                                        self.active_display_asset().map_or(1, |asset| asset.image().spec().channels);
+                                    let snippet = crate::util::python_snippet::python_snippet(
+                                        style,
+                                        self.state.marquee_rect,
+                                        channels,
+                                        &snippet_settings.variable_name,
+                                        path.as_deref(),
+                                    );
+                                    arboard::Clipboard::new()
+                                        .and_then(|mut cb| cb.set_text(snippet))
+                                        .unwrap_or_else(|e| {
+                                            eprintln!("Failed to copy python snippet to clipboard: {e}");
+                                        });
+                                    ui.close();
+                                }
                                 if ui.button("Copy x1, y1, x2, y2").clicked() {
                                     let rect = self.state.marquee_rect.validate();
                                     let rect_str =
@@ -1784,17 +4921,49 @@ impl eframe::App for ViewerApp {
                                 });
                         });
 
+                        columns[3].vertical(|ui| self.rating_ui(ui));
+
                         columns[4].with_layout(egui::Layout::top_down(egui::Align::RIGHT), |ui| {
                             if let Some(asset) = &self.state.asset {
                                 let spec = asset.image().spec();
-                                ui.add(
-                                    egui::Label::new(format!("{}×{} | {}", spec.width, spec.height, spec.dtype.name()))
-                                        .extend(),
-                                );
+                                let (width, height) = spec.full_size();
+                                let mut info = format!("{width}×{height} | {}", spec.dtype.name());
+                                if let Some(source_format) = asset.image().source_format() {
+                                    info.push_str(&format!(" | {source_format}"));
+                                }
+                                ui.add(egui::Label::new(info).extend());
                             } else {
                                 ui.label("No image loaded");
                             }
-                            ui.label(format!("Zoom: {:.2}x", self.viewer.zoom()));
+                            // Right to left so the field lines up with the right-aligned image info above.
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                                let zoom = self.viewer.zoom();
+                                egui::ComboBox::from_id_salt("zoom_presets")
+                                    .selected_text("")
+                                    .width(0.0)
+                                    .show_ui(ui, |ui| {
+                                        for preset in ZOOM_PRESETS {
+                                            let selected = (zoom - preset).abs() < preset * 1e-3;
+                                            if ui.selectable_label(selected, format_zoom(preset)).clicked() {
+                                                self.viewer.set_zoom_about_center(preset);
+                                            }
+                                        }
+                                    });
+                                let response = ui
+                                    .add(egui::TextEdit::singleline(&mut self.zoom_input).desired_width(56.0))
+                                    .on_hover_text(
+                                        "Zoom. Type a percentage such as 250%, a scale factor such as 2.5 or a ratio such as 1:4 and press Enter.",
+                                    );
+                                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                                    match parse_zoom(&self.zoom_input) {
+                                        Some(scale) => self.viewer.set_zoom_about_center(scale),
+                                        None => self.toasts.add_warning(format!("Invalid zoom: {}", self.zoom_input)),
+                                    }
+                                }
+                                if !response.has_focus() {
+                                    self.zoom_input = format_zoom(self.viewer.zoom());
+                                }
+                            });
                         });
                     },
                 );
@@ -1811,11 +4980,17 @@ impl eframe::App for ViewerApp {
                     });
                     ui.style_mut().spacing.slider_rail_height = 4.0;
 
-                    let channels = self.state.asset.as_ref().map(|a| a.image().spec().channels).unwrap_or(0);
-                    let is_mono = self.state.channel_index != -1 || channels == 1;
+                    let spec = self.state.asset.as_ref().map(|a| a.image().spec());
+                    let spec = spec.unwrap_or_else(|| ImageSpec::new(0, 0, 0, PixelType::U8));
+                    let channels = spec.channels;
+                    let gray_alpha = self.state.shader_params.shows_gray_alpha(&spec, self.state.channel_index);
+                    // Channel names follow the preference even while a single channel is selected.
+                    let gray_alpha_layout = self.state.shader_params.shows_gray_alpha(&spec, -1);
+                    let is_mono = self.state.shader_params.is_mono(&spec, self.state.channel_index);
+                    let show_alpha_toggle = !is_mono || gray_alpha;
 
                     ui.horizontal(|ui| {
-                        let sizes = if is_mono {
+                        let sizes = if !show_alpha_toggle {
                             ui.calc_sizes([Size::exact(58.0), Size::remainder(1.0), Size::exact(0.0)])
                         } else {
                             ui.calc_sizes([Size::exact(58.0), Size::remainder(1.0), Size::exact(54.0)])
@@ -1830,12 +5005,16 @@ impl eframe::App for ViewerApp {
                             egui::ComboBox::from_id_salt("channel_index")
                                 .combo_i32_with(ui, &mut self.state.channel_index, &channel_values, |v| match v {
                                     -1 => {
-                                        if channels > 1 {
+                                        if gray_alpha {
+                                            "Gray+A".to_string()
+                                        } else if channels > 1 {
                                             "Color".to_string()
                                         } else {
                                             "Mono".to_string()
                                         }
                                     }
+                                    0 if gray_alpha_layout => "Gray".to_string(),
+                                    1 if gray_alpha_layout => "Alpha".to_string(),
                                     0 => "Red".to_string(),
                                     1 => "Green".to_string(),
                                     2 => "Blue".to_string(),
@@ -1863,7 +5042,7 @@ impl eframe::App for ViewerApp {
                         .response
                         .on_hover_text("Colormap");
 
-                        if !is_mono {
+                        if show_alpha_toggle {
                             ui.allocate_ui(vec2(sizes[2], ui.spacing().interact_size.y), |ui| {
                                 ui.checkbox(&mut self.state.shader_params.use_alpha, "Alpha");
                             });
@@ -1871,7 +5050,34 @@ impl eframe::App for ViewerApp {
                     });
 
                     ui.separator();
-                    ui.checkbox(&mut self.state.shader_params.use_per_channel, "Per-channel controls");
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.state.shader_params.use_per_channel, "Per-channel controls");
+                        ui.add_enabled(
+                            spec.alpha_channel().is_some(),
+                            egui::Checkbox::new(&mut self.state.alpha_view, "Alpha view"),
+                        )
+                        .on_hover_text(format!(
+                            "Show the alpha channel as grayscale, ignoring the display settings ({})",
+                            crate::res::TOGGLE_ALPHA_VIEW.format_sys()
+                        ));
+                        if channels == 2 && spec.gray_alpha {
+                            ui.checkbox(&mut self.state.shader_params.gray_alpha, "Gray+alpha").on_hover_text(
+                                "Show gray+alpha images as luminance with alpha instead of red and green",
+                            );
+                        }
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui
+                                .button("Reset View Settings")
+                                .on_hover_text(format!(
+                                    "Restore default display settings ({})",
+                                    crate::res::RESET_VIEW_SETTINGS.format_sys()
+                                ))
+                                .clicked()
+                            {
+                                self.state.shader_params = ShaderParams::default();
+                            }
+                        });
+                    });
 
                     if let Some(asset) = &self.state.asset {
                         let image = asset.image();
@@ -1885,6 +5091,8 @@ impl eframe::App for ViewerApp {
                                     ui.horizontal(|ui| {
                                         let label = match (channels, i) {
                                             (1, 0) => "C".to_string(),
+                                            (2, 0) if gray_alpha_layout => "L".to_string(),
+                                            (2, 1) if gray_alpha_layout => "A".to_string(),
                                             (_, 0) => "R".to_string(),
                                             (_, 1) => "G".to_string(),
                                             (_, 2) => "B".to_string(),
@@ -1901,6 +5109,7 @@ impl eframe::App for ViewerApp {
                                             &mut self.state.shader_params.auto_minmax_channels[i as usize],
                                             &mut self.state.shader_params.min_v_channels[i as usize],
                                             &mut self.state.shader_params.max_v_channels[i as usize],
+                                            &mut self.state.point_picker,
                                         );
                                     });
                                 }
@@ -1915,6 +5124,7 @@ impl eframe::App for ViewerApp {
                                         &mut self.state.shader_params.auto_minmax,
                                         &mut self.state.shader_params.min_v,
                                         &mut self.state.shader_params.max_v,
+                                        &mut self.state.point_picker,
                                     );
                                 });
                             }
@@ -1924,11 +5134,211 @@ impl eframe::App for ViewerApp {
                     ui.separator();
 
                     display_profile_slider(ui, &mut self.state.shader_params.offset, -5.0, 5.0, 0.0, "Offset")
-                        .on_hover_text("Add a constant offset to the displayed values.");
+                        .on_hover_text("Add a constant offset to the displayed values. Double-click to reset.");
                     display_profile_slider(ui, &mut self.state.shader_params.exposure, -5.0, 5.0, 0.0, "Exposure")
-                        .on_hover_text("Adjust brightness in exposure stops.");
+                        .on_hover_text(format!(
+                            "Adjust brightness in exposure stops. ({} / {} to step by 0.25 EV, double-click to reset)",
+                            crate::res::EXPOSURE_DOWN.format_sys(),
+                            crate::res::EXPOSURE_UP.format_sys()
+                        ));
                     display_profile_slider(ui, &mut self.state.shader_params.gamma, 0.1, 5.0, 1.0, "Gamma")
-                        .on_hover_text("Apply gamma correction to the display.");
+                        .on_hover_text(format!(
+                            "Apply gamma correction to the display. ({} / {} to step by 0.1, double-click to reset)",
+                            crate::res::GAMMA_DOWN.format_sys(),
+                            crate::res::GAMMA_UP.format_sys()
+                        ));
+
+                    ui.horizontal(|ui| {
+                        ui.label("LUT");
+                        let lut_name = self
+                            .state
+                            .look_lut
+                            .as_ref()
+                            .and_then(|(path, _)| path.file_name())
+                            .map(|name| name.to_string_lossy().into_owned());
+                        if ui
+                            .button(lut_name.as_deref().unwrap_or("Load .cube..."))
+                            .on_hover_text("Apply a 3D LUT (.cube) as a display transform, e.g. to check against a grading LUT")
+                            .clicked()
+                        {
+                            if let Some(path) = FileDialog::new().add_filter("Cube LUT", &["cube"]).pick_file() {
+                                self.load_look_lut(&ctx, path);
+                            }
+                        }
+                        if self.state.look_lut.is_some() && ui.button("✕").on_hover_text("Remove LUT").clicked() {
+                            self.state.look_lut = None;
+                        }
+                    });
+                    if self.state.look_lut.is_some() {
+                        display_profile_slider(ui, &mut self.state.shader_params.lut_strength, 0.0, 1.0, 1.0, "Strength")
+                            .on_hover_text("Blend between the original colors and the LUT result. Double-click to reset.");
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.label("Color vision");
+                        let color_vision = &mut self.state.shader_params.color_vision;
+                        egui::ComboBox::from_id_salt("color_vision")
+                            .selected_text(color_vision.label())
+                            .show_ui(ui, |ui| {
+                                for mode in ColorVisionMode::ALL {
+                                    ui.selectable_value(color_vision, mode, mode.label());
+                                }
+                            })
+                            .response
+                            .on_hover_text("Simulate a color vision deficiency on the displayed image");
+                        ui.checkbox(&mut self.state.shader_params.lightness_only, "L*")
+                            .on_hover_text("Show only the perceptual lightness of the colormapped image and the colormap's lightness ramp");
+                    });
+                    ui.horizontal(|ui| {
+                        let proofing = &mut self.state.proofing;
+                        ui.checkbox(&mut proofing.enabled, "Proofing").on_hover_text(
+                            "Simulate a dimmer display or another white point on screen; copies and saves are not affected",
+                        );
+                        ui.add_enabled_ui(proofing.enabled, |ui| {
+                            ui.add(
+                                egui::DragValue::new(&mut proofing.brightness)
+                                    .range(0.05..=1.0)
+                                    .speed(0.01)
+                                    .custom_formatter(|value, _| format!("{:.0}%", value * 100.0))
+                                    .custom_parser(|text| text.trim_end_matches('%').trim().parse::<f64>().ok().map(|v| v / 100.0)),
+                            )
+                            .on_hover_text("Brightness of the simulated display");
+                            ui.add(
+                                egui::DragValue::new(&mut proofing.white_point)
+                                    .range(WHITE_POINT_RANGE)
+                                    .speed(10.0)
+                                    .suffix(" K"),
+                            )
+                            .on_hover_text("White point of the simulated display; lower is warmer");
+                            if ui.button("Reset").on_hover_text("Full brightness and a D65 white point").clicked() {
+                                *proofing = Proofing {
+                                    enabled: true,
+                                    ..Proofing::default()
+                                };
+                            }
+                        });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Magnification");
+                        let filter = &mut self.state.magnification_filter;
+                        egui::ComboBox::from_id_salt("magnification_filter")
+                            .selected_text(filter.label())
+                            .show_ui(ui, |ui| {
+                                for mode in MagnificationFilter::ALL {
+                                    ui.selectable_value(filter, mode, mode.label());
+                                }
+                            })
+                            .response
+                            .on_hover_text("Resampling when zoomed in. Nearest shows exact pixel values; the others smooth gradients");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Quantize");
+                        let bits = &mut self.state.shader_params.quantize_bits;
+                        let bits_label = |bits: u8| if bits == 0 { "Off".to_string() } else { format!("{bits}-bit") };
+                        egui::ComboBox::from_id_salt("quantize_bits")
+                            .selected_text(bits_label(*bits))
+                            .show_ui(ui, |ui| {
+                                for option in [0, 4, 5, 6, 8, 10, 12, 16] {
+                                    ui.selectable_value(bits, option, bits_label(option));
+                                }
+                            })
+                            .response
+                            .on_hover_text("Preview the displayed image rounded to an integer format, as after an 8-bit or 10-bit export");
+                        let quantized = *bits != 0;
+                        ui.add_enabled(quantized, egui::Checkbox::new(&mut self.state.shader_params.quantize_dither, "Dither"))
+                            .on_hover_text("Add noise before rounding, which trades banding for grain");
+                    });
+                    if channels >= 3 {
+                        ui.horizontal(|ui| {
+                            ui.label("White balance");
+                            let picker = PointPicker {
+                                target: PointPickTarget::Neutral,
+                                channel: -1,
+                            };
+                            let mut picking = self.state.point_picker == Some(picker);
+                            if ui
+                                .toggle_icon(
+                                    &mut picking,
+                                    self.icons.get_pick_white_point(&ctx),
+                                    "Pick a neutral patch: click a gray or white area to balance the color around it",
+                                )
+                                .clicked()
+                            {
+                                self.state.point_picker = picking.then_some(picker);
+                            }
+                            if ui
+                                .button("Gray World")
+                                .on_hover_text("Balance so the average color of the whole image is gray")
+                                .clicked()
+                            {
+                                if let Some(image) = self.state.asset.as_ref().map(|asset| asset.image().clone()) {
+                                    let rect = Recti::from_min_size(vec2i(0, 0), vec2i(spec.width, spec.height));
+                                    match estimate_white_balance(&image, rect) {
+                                        Ok(gains) => self.state.shader_params.white_balance = Some(gains),
+                                        Err(e) => {
+                                            eprintln!("Failed to estimate white balance: {e}");
+                                            self.toasts.add_error(format!("Failed to estimate white balance: {e}"));
+                                        }
+                                    }
+                                }
+                            }
+                            if let Some([r, g, b]) = self.state.shader_params.white_balance {
+                                ui.weak(format!("×{r:.2} ×{g:.2} ×{b:.2}"))
+                                    .on_hover_text("Gains applied to the red, green and blue values");
+                                if ui.button("✕").on_hover_text("Remove white balance").clicked() {
+                                    self.state.shader_params.white_balance = None;
+                                }
+                            }
+                        });
+                    }
+                    if channels == 1 {
+                        ui.horizontal(|ui| {
+                            ui.label("Bayer");
+                            let view = &mut self.state.shader_params.cfa_view;
+                            egui::ComboBox::from_id_salt("cfa_view")
+                                .selected_text(view.label())
+                                .show_ui(ui, |ui| {
+                                    for mode in CfaView::ALL {
+                                        ui.selectable_value(view, mode, mode.label());
+                                    }
+                                })
+                                .response
+                                .on_hover_text("Show single-channel raw sensor data through its color filter array");
+                            ui.add_enabled_ui(*view != CfaView::Off, |ui| {
+                                let pattern = &mut self.state.shader_params.cfa_pattern;
+                                egui::ComboBox::from_id_salt("cfa_pattern")
+                                    .selected_text(pattern.label())
+                                    .show_ui(ui, |ui| {
+                                        for mode in CfaPattern::ALL {
+                                            ui.selectable_value(pattern, mode, mode.label());
+                                        }
+                                    })
+                                    .response
+                                    .on_hover_text("Filter colors of the top-left 2×2 block");
+                            });
+                        });
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Lens");
+                        let mut undistort = self.state.shader_params.undistort.is_some();
+                        if ui
+                            .checkbox(&mut undistort, "Undistort")
+                            .on_hover_text("Show the image undistorted with the camera intrinsics and distortion coefficients")
+                            .changed()
+                        {
+                            let lens = *self
+                                .state
+                                .lens_distortion
+                                .get_or_insert_with(|| LensDistortion::for_image(spec.width, spec.height));
+                            self.state.shader_params.undistort = undistort.then_some(lens);
+                            self.show_lens_window |= undistort;
+                        }
+                        if ui.toggle_value(&mut self.show_lens_window, "Parameters").clicked() {
+                            self.state
+                                .lens_distortion
+                                .get_or_insert_with(|| LensDistortion::for_image(spec.width, spec.height));
+                        }
+                    });
 
                     let desired_size_plot = egui::vec2(ui.available_width(), 100.0);
                     if let Some(asset) = self.active_display_asset() {
@@ -2033,13 +5443,20 @@ impl eframe::App for ViewerApp {
 
                     ui.separator();
 
-                    ui.checkbox(&mut self.show_histogram, "Show Histogram")
-                        .on_hover_text("Show the histogram of the current image.");
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.show_histogram, "Show Histogram").on_hover_text(
+                            "Show the histogram of the current image, or of the marquee or lasso/polygon selection.",
+                        );
+                        if self.show_histogram {
+                            ui.checkbox(&mut self.histogram_log_scale, "Log Scale")
+                                .on_hover_text("Scale the bars by the logarithm of the pixel count.");
+                        }
+                    });
 
                     if self.show_histogram {
+                        self.request_region_histogram();
                         let desired_size = egui::vec2(ui.available_width(), 100.0);
-                        if let Some(asset) = &self.state.asset {
-                            let hist = asset.image().hist();
+                        if let Some(hist) = self.current_histogram() {
                             let max = hist.iter().flatten().copied().fold(f32::NAN, f32::max);
 
                             if !hist.is_empty() {
@@ -2052,13 +5469,21 @@ impl eframe::App for ViewerApp {
                                         SeriesRef::new(&display_hist),
                                         &self.show_histogram_channels,
                                         max,
+                                        self.histogram_log_scale,
                                     ) {
                                         self.handle_export_action(export);
                                     }
                                     channel_toggle_ui(ui, &mut self.show_histogram_channels, channels as usize);
                                 } else {
                                     if let Some(export) =
-                                        draw_histogram(ui, desired_size, SeriesRef::new(&display_hist), &[true], max)
+                                        draw_histogram(
+                                        ui,
+                                        desired_size,
+                                        SeriesRef::new(&display_hist),
+                                        &[true],
+                                        max,
+                                        self.histogram_log_scale,
+                                    )
                                     {
                                         self.handle_export_action(export);
                                     }
@@ -2077,25 +5502,76 @@ impl eframe::App for ViewerApp {
 
                     ui.separator();
 
-                    if ui
-                        .checkbox(&mut self.show_statistics, "Show Statistics")
-                        .on_hover_text("Show min/max and comparison metrics for the current selection.")
-                        .clicked()
-                        && self.show_statistics {
-                        self.update_statistics();
-                    }
+                    ui.horizontal(|ui| {
+                        if ui
+                            .checkbox(&mut self.show_statistics, "Show Statistics")
+                            .on_hover_text("Show min/max and comparison metrics for the current selection.")
+                            .clicked()
+                            && self.show_statistics
+                        {
+                            self.update_statistics();
+                        }
+                        if self.show_statistics {
+                            let before = self.statistics_metrics;
+                            ui.menu_button("Metrics", |ui| {
+                                let metrics = &mut self.statistics_metrics;
+                                ui.checkbox(&mut metrics.std, "Std")
+                                    .on_hover_text("Standard deviation per channel");
+                                ui.separator();
+                                ui.weak("Comparison");
+                                ui.checkbox(&mut metrics.mse, "MSE").on_hover_text("Mean squared error");
+                                ui.checkbox(&mut metrics.mae, "MAE").on_hover_text("Mean absolute error");
+                                ui.checkbox(&mut metrics.ssim, "SSIM")
+                                    .on_hover_text("Structural similarity; slow on large regions");
+                                ui.checkbox(&mut metrics.ms_ssim, "MS-SSIM")
+                                    .on_hover_text("Structural similarity over five scales; slow on large regions");
+                            })
+                            .response
+                            .on_hover_text("Choose the metrics computed for the selection.");
+                            if self.statistics_metrics != before {
+                                self.update_statistics();
+                            }
+                        }
+                    });
 
                     if self.show_statistics {
-                        let min_values = &self.state.statistics.min_max.value.min;
-                        let max_values = &self.state.statistics.min_max.value.max;
+                        let busy_types: Vec<StatisticsType> = {
+                            let worker = self.statistics_worker.lock().unwrap();
+                            [
+                                StatisticsType::MinMax,
+                                StatisticsType::Std,
+                                StatisticsType::PSNRRMSE,
+                                StatisticsType::MAE,
+                                StatisticsType::SSIM,
+                                StatisticsType::MSSSIM,
+                            ]
+                            .into_iter()
+                            .filter(|stat_type| worker.is_busy(stat_type))
+                            .collect()
+                        };
+                        let busy = |stat_type: StatisticsType| busy_types.contains(&stat_type);
+                        let busy_spinner = |ui: &mut egui::Ui, is_busy: bool| {
+                            if is_busy {
+                                ui.add(egui::Spinner::new().size(12.0)).on_hover_text("Computing...");
+                            }
+                        };
+                        let statistics = &self.state.statistics;
+                        let min_values = &statistics.min_max.value.min;
+                        let max_values = &statistics.min_max.value.max;
                         let num_channels = min_values.len().min(max_values.len()).min(4);
+                        let std_values = &statistics.std.value;
+                        let is_std_busy = busy(StatisticsType::Std);
+                        let is_min_max_busy = busy(StatisticsType::MinMax);
 
                         if num_channels > 0 {
                             egui::Grid::new("statistics_min_max_grid")
                                 .num_columns(num_channels + 1)
                                 .striped(true)
                                 .show(ui, |ui| {
-                                    ui.label("Min:").on_hover_text("Minimum value per channel.");
+                                    ui.horizontal(|ui| {
+                                        ui.label("Min:").on_hover_text("Minimum value per channel.");
+                                        busy_spinner(ui, is_min_max_busy);
+                                    });
                                     for (i, &min_value) in min_values.iter().take(num_channels).enumerate() {
                                         statistics_overlay_toggle(
                                             ui,
@@ -2118,28 +5594,175 @@ impl eframe::App for ViewerApp {
                                         );
                                     }
                                     ui.end_row();
+
+                                    if self.statistics_metrics.std {
+                                        ui.horizontal(|ui| {
+                                            ui.label("Std:").on_hover_text("Standard deviation per channel.");
+                                            busy_spinner(ui, is_std_busy);
+                                        });
+                                        for std in std_values.iter().take(num_channels) {
+                                            ui.label(format!("{std:.4}"));
+                                        }
+                                        ui.end_row();
+                                    }
                                 });
                         }
 
-                        if self.state.is_comparison() {
-                            egui::Grid::new("statistics_metrics_grid").num_columns(2).striped(true).show(ui, |ui| {
-                                ui.label("RMSE:");
-                                ui.label(format!("{:.4}", self.state.statistics.psnr_rmse.value.rmse));
-                                ui.end_row();
+                        if let Some(masked) = self.current_masked_statistics() {
+                            egui::Grid::new("statistics_masked_grid")
+                                .num_columns(masked.mean.len() + 1)
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    ui.label("Mask:").on_hover_text("Pixels inside the lasso/polygon selection.");
+                                    ui.label(format!("{} px", masked.count));
+                                    ui.end_row();
+
+                                    ui.label("Mean:").on_hover_text("Mean value per channel inside the selection.");
+                                    for mean in masked.mean.iter().take(4) {
+                                        ui.label(format!("{mean:.4}"));
+                                    }
+                                    ui.end_row();
+
+                                    ui.label("Std:")
+                                        .on_hover_text("Standard deviation per channel inside the selection.");
+                                    for std in masked.std.iter().take(4) {
+                                        ui.label(format!("{std:.4}"));
+                                    }
+                                    ui.end_row();
+                                });
+                        }
 
-                                ui.label("PSNR:");
-                                ui.label(format!("{:.4}", self.state.statistics.psnr_rmse.value.psnr));
+                        if let Some(coverage) = self.current_alpha_coverage() {
+                            egui::Grid::new("statistics_alpha_grid").num_columns(4).striped(true).show(ui, |ui| {
+                                ui.label("Alpha:").on_hover_text(
+                                    "Share of pixels that are fully transparent, partially transparent and opaque.",
+                                );
+                                ui.label(format!("{:.1}% clear", coverage.transparent * 100.0))
+                                    .on_hover_text("Alpha of 0");
+                                ui.label(format!("{:.1}% partial", coverage.partial * 100.0))
+                                    .on_hover_text("Alpha between 0 and 1");
+                                ui.label(format!("{:.1}% opaque", coverage.opaque * 100.0))
+                                    .on_hover_text("Alpha of 1");
                                 ui.end_row();
+                            });
+                        }
 
-                                // ui.label("SSIM:");
-                                // ui.label(format!("{:.4}", self.state.statistics.ssim));
-                                // ui.end_row();
+                        if self.state.is_comparison() {
+                            let metrics = self.statistics_metrics;
+                            let psnr_rmse = &statistics.psnr_rmse.value;
+                            let rows = [
+                                ("RMSE:", true, psnr_rmse.rmse, StatisticsType::PSNRRMSE),
+                                ("PSNR:", true, psnr_rmse.psnr, StatisticsType::PSNRRMSE),
+                                ("MSE:", metrics.mse, psnr_rmse.rmse * psnr_rmse.rmse, StatisticsType::PSNRRMSE),
+                                ("MAE:", metrics.mae, statistics.mae.value, StatisticsType::MAE),
+                                ("SSIM:", metrics.ssim, statistics.ssim.value, StatisticsType::SSIM),
+                                ("MS-SSIM:", metrics.ms_ssim, statistics.ms_ssim.value, StatisticsType::MSSSIM),
+                            ];
+                            egui::Grid::new("statistics_metrics_grid").num_columns(2).striped(true).show(ui, |ui| {
+                                for (label, _, value, stat_type) in rows.into_iter().filter(|row| row.1) {
+                                    ui.horizontal(|ui| {
+                                        ui.label(label);
+                                        busy_spinner(ui, busy(stat_type));
+                                    });
+                                    ui.label(format!("{value:.4}"));
+                                    ui.end_row();
+                                }
                             });
                         }
+
+                        let mut export = None;
+                        ui.menu_button("Export", |ui| {
+                            for format in StatisticsFormat::ALL {
+                                if ui.button(format!("Copy as {}", format.name())).clicked() {
+                                    export = self.statistics_report().map(|report| {
+                                        ExportAction::Copy(CopyExport {
+                                            title: "statistics",
+                                            text: report.encode(format),
+                                        })
+                                    });
+                                }
+                            }
+                            for format in StatisticsFormat::ALL {
+                                if ui.button(format!("Save as {}...", format.name())).clicked() {
+                                    export = self.statistics_report().map(|report| {
+                                        ExportAction::Save(SaveExport {
+                                            title: "statistics",
+                                            suggested_file_name: format.file_name(),
+                                            text: report.encode(format),
+                                        })
+                                    });
+                                }
+                            }
+                        })
+                        .response
+                        .on_hover_text(
+                            "Export per-channel min/max/mean/std, comparison metrics and the histogram.",
+                        );
+                        if let Some(export) = export {
+                            self.handle_export_action(export);
+                        }
                     }
 
                     ui.separator();
 
+                    let versions = self
+                        .state
+                        .asset_primary
+                        .as_ref()
+                        .and_then(|asset| Some((asset, self.state.socket_versions(asset.hash())?)));
+                    if let Some((asset, versions)) = versions {
+                        let latest = versions.len();
+                        let current = versions.iter().position(|v| Arc::ptr_eq(v, asset)).map_or(latest, |i| i + 1);
+                        let mut step = current;
+                        ui.horizontal(|ui| {
+                            ui.label("Version");
+                            ui.add(egui::Slider::new(&mut step, 1..=latest).text(format!("of {latest}")))
+                                .on_hover_text("Earlier images received with the same name");
+                        });
+                        ui.separator();
+                        if step != current {
+                            let version = versions[step - 1].clone();
+                            self.state.set_primary_asset(version);
+                        }
+                    }
+
+                    if let Some(asset) = self.state.asset_primary.as_ref() {
+                        let image = asset.image();
+                        let content = self.state.content_hashes.get(image).map_or_else(
+                            || {
+                                if self.state.content_hashes.is_pending(image) {
+                                    "Hashing...".to_string()
+                                } else {
+                                    "Unavailable".to_string()
+                                }
+                            },
+                            |content| format!("{content:016x}"),
+                        );
+                        ui.horizontal(|ui| {
+                            ui.label("Content hash:")
+                                .on_hover_text("XXH64 of the decoded pixels. Identical images share a hash.");
+                            ui.add(egui::Label::new(egui::RichText::new(content).monospace()).selectable(true));
+                        });
+                        ui.separator();
+                    }
+
+                    if self.note_available {
+                        self.notes_ui(ui);
+                        ui.separator();
+                    }
+
+                    if let Some(asset) = self.state.asset_primary.as_ref().filter(|a| !a.metadata().is_empty()) {
+                        ui.heading("Metadata");
+                        egui::Grid::new("asset_metadata_grid").num_columns(2).striped(true).show(ui, |ui| {
+                            for (key, value) in asset.metadata() {
+                                ui.label(format!("{key}:"));
+                                ui.add(egui::Label::new(value).truncate()).on_hover_text(value);
+                                ui.end_row();
+                            }
+                        });
+                        ui.separator();
+                    }
+
                     if self.state.is_comparison() {
                         ui.heading("Comparison");
                         let previous_comparison_mode = self.state.comparison_mode;
@@ -2158,6 +5781,13 @@ impl eframe::App for ViewerApp {
                                 .radio_value(&mut self.state.comparison_mode, ComparisonMode::Split, "Split")
                                 .on_hover_text("Show the primary and secondary images side by side with synchronized pan and zoom.")
                                 .changed();
+                            comparison_mode_changed |= ui
+                                .radio_value(&mut self.state.comparison_mode, ComparisonMode::Anaglyph, "Anaglyph")
+                                .on_hover_text(
+                                    "Red-cyan anaglyph of a stereo pair: red from the primary (left eye), green and \
+                                     blue from the secondary (right eye).",
+                                )
+                                .changed();
                         });
                         comparison_changed |= comparison_mode_changed;
                         if self.state.comparison_mode == ComparisonMode::Blend {
@@ -2165,6 +5795,27 @@ impl eframe::App for ViewerApp {
                                 .add(egui::Slider::new(&mut self.state.comparison_blend, 0.0..=1.0).text("Blend"))
                                 .changed();
                         }
+                        if self.state.comparison_mode == ComparisonMode::Diff {
+                            comparison_changed |= ui
+                                .add(
+                                    egui::Slider::new(&mut self.state.comparison_diff_gain, 1.0..=100.0)
+                                        .logarithmic(true)
+                                        .text("Gain"),
+                                )
+                                .on_hover_text("Amplify the difference to make faint changes visible")
+                                .changed();
+                        }
+                        if matches!(self.state.comparison_mode, ComparisonMode::Split | ComparisonMode::Anaglyph)
+                            && ui
+                                .button("Swap Eyes")
+                                .on_hover_text(
+                                    "Exchange the primary and secondary images, e.g. for crossed-eye viewing of a \
+                                     side-by-side pair or a cyan-red anaglyph.",
+                                )
+                                .clicked()
+                        {
+                            self.state.swap_comparison_assets();
+                        }
                         if comparison_changed {
                             self.state.update_asset();
                             if comparison_mode_changed
@@ -2180,21 +5831,94 @@ impl eframe::App for ViewerApp {
                         ui.separator();
                     }
 
+                    if !self.state.grid.is_empty() {
+                        self.grid_ui(ui);
+                        ui.separator();
+                    }
+
+                    if self.state.heatmap.is_some() {
+                        self.heatmap_ui(ui);
+                        ui.separator();
+                    }
+
                     ui.horizontal(|ui| {
                         ui.heading("Image List");
-                        ui.with_layout(egui::Layout::top_down(egui::Align::RIGHT), |ui| {
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                             ui.button("Clear").clicked().then(|| {
-                                self.state.assets.clear();
+                                self.state.remove_assets(|_| true);
                                 self.state.clear_asset();
                             });
+                            egui::ComboBox::from_id_salt("asset_grouping")
+                                .selected_text(self.state.asset_grouping.label())
+                                .show_ui(ui, |ui| {
+                                    for grouping in crate::settings::AssetGrouping::ALL {
+                                        ui.selectable_value(&mut self.state.asset_grouping, grouping, grouping.label());
+                                    }
+                                });
+                            ui.toggle_value(&mut self.state.is_show_asset_thumbnails, "Thumbnails");
                         });
                     });
+
+                    let search_id = egui::Id::new(ASSET_SEARCH_ID);
+                    let mut cursor_step = 0isize;
+                    if ui.memory(|m| m.has_focus(search_id)) {
+                        ui.input_mut(|i| {
+                            if i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown) {
+                                cursor_step += 1;
+                            }
+                            if i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp) {
+                                cursor_step -= 1;
+                            }
+                            // The first `esc` clears the search, the next one leaves the field.
+                            if !self.asset_query.is_empty() && i.consume_key(egui::Modifiers::NONE, egui::Key::Escape) {
+                                self.asset_query.clear();
+                            }
+                        });
+                    }
+                    let search = ui.add(
+                        egui::TextEdit::singleline(&mut self.asset_query)
+                            .id(search_id)
+                            .desired_width(f32::INFINITY)
+                            .hint_text(format!("Search ({})", crate::res::ASSET_SEARCH_FOCUS.format_sys())),
+                    );
+                    if search.changed() {
+                        self.asset_search_cursor = 0;
+                    }
+                    let search_enter = search.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+                    let searching = !self.asset_query.trim().is_empty();
+                    let mut asset_groups = self.state.asset_groups();
+                    if searching {
+                        for group in &mut asset_groups {
+                            group.rows.retain(|(_, _, asset)| fuzzy_match(&self.asset_query, asset_list_name(asset.name())));
+                        }
+                        asset_groups.retain(|group| !group.rows.is_empty());
+                    }
+                    let search_results: Vec<&(usize, String, SharedAsset)> =
+                        asset_groups.iter().flat_map(|group| &group.rows).collect();
+                    self.asset_search_cursor = self
+                        .asset_search_cursor
+                        .saturating_add_signed(cursor_step)
+                        .min(search_results.len().saturating_sub(1));
+                    let search_cursor_hash = search_results
+                        .get(self.asset_search_cursor)
+                        .filter(|_| searching)
+                        .map(|(_, hash, _)| hash.clone());
+                    let mut search_pick = None;
+                    if searching && search_enter {
+                        search_pick = search_results.get(self.asset_search_cursor).map(|(_, _, asset)| asset.clone());
+                        // Keep the field focused so the next result is one arrow key away.
+                        search.request_focus();
+                    }
+                    if searching && search_results.is_empty() {
+                        ui.weak("No matching images");
+                    }
                     let asset_primary_hash = self.state.asset_primary.as_ref().map(|asset| asset.hash().to_owned());
                     let asset_secondary_hash = self.state.asset_secondary.as_ref().map(|asset| asset.hash().to_owned());
 
                     egui::ScrollArea::vertical().auto_shrink([false, true]).show(ui, |ui| {
                         let list_clip_rect = ui.clip_rect();
-                        let mut to_set_primary: Option<_> = None;
+                        let mut to_set_primary: Option<_> = search_pick;
                         let mut to_set_secondary: Option<_> = None;
                         let mut deselect_secondary = false;
                         let mut to_remove: HashSet<_> = HashSet::new();
@@ -2202,22 +5926,38 @@ impl eframe::App for ViewerApp {
                         let mut reorder_request: Option<(String, usize)> = None;
                         let mut first_row_rect: Option<egui::Rect> = None;
                         let mut last_row_rect: Option<egui::Rect> = None;
-                        let asset_rows: Vec<_> = self
-                            .state
-                            .assets
-                            .iter()
-                            .enumerate()
-                            .map(|(index, (hash, asset))| (index, hash.clone(), asset.clone()))
-                            .collect();
-
-                        asset_rows.into_iter().for_each(|(asset_index, hash, asset)| {
+                        let mut to_assign_group: Option<(String, Option<String>)> = None;
+                        let mut to_toggle_grid: Option<String> = None;
+                        let mut to_toggle_heatmap: Option<String> = None;
+                        let mut group_to_remove: Option<Vec<String>> = None;
+                        let mut group_to_compare: Option<(SharedAsset, SharedAsset)> = None;
+                        let manual_group_names = self.state.manual_group_names();
+                        let duplicates = self.state.content_hashes.duplicates(&self.state.assets);
+
+                        let mut show_row = |ui: &mut egui::Ui, (asset_index, hash, asset): (usize, String, SharedAsset)| {
                             let name = asset.name();
                             let available_width = ui.available_width();
+                            let thumbnail = self
+                                .state
+                                .is_show_asset_thumbnails
+                                .then(|| self.thumbnails.get(&asset))
+                                .flatten()
+                                .map(|texture| {
+                                    let size = texture.size_vec2();
+                                    let display_size = size * (THUMBNAIL_DISPLAY_SIZE / size.max_elem());
+                                    egui::Image::from_texture(egui::load::SizedTexture::new(texture.id(), display_size))
+                                });
 
                             let style = ui.style();
                             let font_id = style.text_styles.get(&egui::TextStyle::Button).cloned().unwrap_or_default();
                             let padding = style.spacing.button_padding.x * 2.0;
-                            let target_width = (available_width - padding - 5.0).max(0.0);
+                            let thumbnail_width = thumbnail
+                                .as_ref()
+                                .map_or(0.0, |_| THUMBNAIL_DISPLAY_SIZE + style.spacing.icon_spacing);
+                            let labels: Vec<usize> = self.state.labels.labels(name).collect();
+                            let labels_width = labels.len() as f32 * (LABEL_CHIP_SIZE + 2.0);
+                            let target_width =
+                                (available_width - padding - thumbnail_width - labels_width - 5.0).max(0.0);
 
                             // Truncate name with ellipsis if too long. Example: "very_long_filename.png" -> "...lename.png"
                             let display_name = ui.fonts_mut(|fonts| {
@@ -2254,25 +5994,64 @@ impl eframe::App for ViewerApp {
                                 }
                             });
 
+                            let row_button = |selected: bool| {
+                                match thumbnail.clone() {
+                                    Some(image) => egui::Button::selectable(selected, (image, display_name.as_str())),
+                                    None => egui::Button::selectable(selected, display_name.as_str()),
+                                }
+                                .sense(egui::Sense::click_and_drag())
+                            };
                             let row = ui
                                 .with_layout(egui::Layout::top_down_justified(egui::Align::LEFT), |ui| {
                                     let btn = if Some(hash.as_str()) == asset_primary_hash.as_deref() {
-                                        ui.add(
-                                            egui::Button::selectable(true, &display_name)
-                                                .sense(egui::Sense::click_and_drag()),
-                                        )
+                                        ui.add(row_button(true))
                                     } else if Some(hash.as_str()) == asset_secondary_hash.as_deref() {
                                         ui.style_mut().visuals.selection.bg_fill = ASSET_SECONDARY_SELECTION_FILL;
-                                        ui.add(
-                                            egui::Button::selectable(true, &display_name)
-                                                .sense(egui::Sense::click_and_drag()),
-                                        )
+                                        ui.add(row_button(true))
                                     } else {
-                                        ui.add(
-                                            egui::Button::selectable(false, &display_name)
-                                                .sense(egui::Sense::click_and_drag()),
-                                        )
+                                        ui.add(row_button(false))
+                                    };
+                                    let content_hash = self.state.content_hashes.get(asset.image());
+                                    let btn = match content_hash.and_then(|content| duplicates.get(&content)) {
+                                        Some(names) => {
+                                            let stripe = egui::Rect::from_min_size(
+                                                btn.rect.left_top(),
+                                                vec2(DUPLICATE_MARKER_WIDTH, btn.rect.height()),
+                                            );
+                                            ui.painter().rect_filled(
+                                                stripe,
+                                                1.0,
+                                                duplicate_marker_color(content_hash.unwrap_or_default()),
+                                            );
+                                            let others: Vec<&str> = names
+                                                .iter()
+                                                .map(String::as_str)
+                                                .filter(|other| *other != name)
+                                                .collect();
+                                            btn.on_hover_text(format!("Identical pixels to:\n{}", others.join("\n")))
+                                        }
+                                        None => btn,
                                     };
+                                    if search_cursor_hash.as_ref() == Some(&hash) {
+                                        ui.painter().rect_stroke(
+                                            btn.rect,
+                                            2.0,
+                                            (1.0, ui.visuals().selection.stroke.color),
+                                            egui::StrokeKind::Inside,
+                                        );
+                                        if cursor_step != 0 {
+                                            btn.scroll_to_me(None);
+                                        }
+                                    }
+                                    let mut chip_right = btn.rect.right() - 4.0;
+                                    for &label in labels.iter().rev() {
+                                        let chip = egui::Rect::from_center_size(
+                                            egui::pos2(chip_right - LABEL_CHIP_SIZE / 2.0, btn.rect.center().y),
+                                            egui::Vec2::splat(LABEL_CHIP_SIZE),
+                                        );
+                                        paint_label_chip(ui.painter(), chip, label);
+                                        chip_right -= LABEL_CHIP_SIZE + 2.0;
+                                    }
                                     btn.context_menu(|ui| {
                                         ui.visuals_mut().override_text_color = Some(DANGER_TEXT);
                                         if ui.button("Delete").clicked() {
@@ -2304,6 +6083,32 @@ impl eframe::App for ViewerApp {
                                             }
                                             ui.close();
                                         }
+                                        let grid_label = if self.state.grid.contains(&hash) {
+                                            "Remove from Grid"
+                                        } else {
+                                            "Add to Grid"
+                                        };
+                                        if ui.button(grid_label).clicked() {
+                                            to_toggle_grid = Some(hash.clone());
+                                            ui.close();
+                                        }
+                                        let is_heatmap =
+                                            self.state.heatmap.as_ref().is_some_and(|heatmap| heatmap.hash == hash);
+                                        let heatmap_label = if is_heatmap {
+                                            "Clear Heatmap Overlay"
+                                        } else {
+                                            "Use as Heatmap Overlay"
+                                        };
+                                        if ui
+                                            .button(heatmap_label)
+                                            .on_hover_text(
+                                                "Blend this single-channel image through a colormap over the shown image",
+                                            )
+                                            .clicked()
+                                        {
+                                            to_toggle_heatmap = Some(hash.clone());
+                                            ui.close();
+                                        }
                                         ui.separator();
 
                                         match asset.asset_type() {
@@ -2331,6 +6136,32 @@ impl eframe::App for ViewerApp {
                                             crate::model::AssetType::Clipboard => {}
                                             _ => {}
                                         }
+
+                                        ui.menu_button("Group", |ui| {
+                                            for group_name in &manual_group_names {
+                                                if ui.button(group_name).clicked() {
+                                                    to_assign_group = Some((hash.clone(), Some(group_name.clone())));
+                                                    ui.close();
+                                                }
+                                            }
+                                            ui.horizontal(|ui| {
+                                                ui.add(
+                                                    egui::TextEdit::singleline(&mut self.new_asset_group_name)
+                                                        .hint_text("New group")
+                                                        .desired_width(100.0),
+                                                );
+                                                let name = self.new_asset_group_name.trim();
+                                                if ui.add_enabled(!name.is_empty(), egui::Button::new("Add")).clicked() {
+                                                    to_assign_group = Some((hash.clone(), Some(name.to_string())));
+                                                    self.new_asset_group_name.clear();
+                                                    ui.close();
+                                                }
+                                            });
+                                            if ui.button("Remove from Group").clicked() {
+                                                to_assign_group = Some((hash.clone(), None));
+                                                ui.close();
+                                            }
+                                        });
                                     });
 
                                     btn.dnd_set_drag_payload(hash.clone());
@@ -2396,7 +6227,34 @@ impl eframe::App for ViewerApp {
                                     reorder_request = Some((dragged_hash, insertion_index));
                                 }
                             }
-                        });
+                        };
+
+                        for group in asset_groups {
+                            let Some(label) = group.label else {
+                                group.rows.into_iter().for_each(|row| show_row(ui, row));
+                                continue;
+                            };
+                            let hashes: Vec<String> = group.rows.iter().map(|(_, hash, _)| hash.clone()).collect();
+                            let first_two = match group.rows.as_slice() {
+                                [(_, _, first), (_, _, second), ..] => Some((first.clone(), second.clone())),
+                                _ => None,
+                            };
+                            let header = egui::CollapsingHeader::new(format!("{label} ({})", group.rows.len()))
+                                .id_salt(("asset_group", &label))
+                                .default_open(true)
+                                .show(ui, |ui| group.rows.into_iter().for_each(|row| show_row(ui, row)));
+                            header.header_response.context_menu(|ui| {
+                                if ui.add_enabled(first_two.is_some(), egui::Button::new("Compare First Two")).clicked() {
+                                    group_to_compare = first_two.clone();
+                                    ui.close();
+                                }
+                                ui.visuals_mut().override_text_color = Some(DANGER_TEXT);
+                                if ui.button("Delete Group").clicked() {
+                                    group_to_remove = Some(hashes.clone());
+                                    ui.close();
+                                }
+                            });
+                        }
 
                         let pointer_pos = ui.ctx().pointer_interact_pos();
                         let pointer_in_list_x = pointer_pos
@@ -2463,10 +6321,40 @@ impl eframe::App for ViewerApp {
                             self.state.set_secondary_asset(None);
                         }
 
+                        if let Some((first, second)) = group_to_compare {
+                            self.state.set_primary_asset(first);
+                            self.state.set_secondary_asset(Some(second));
+                        }
+                        if let Some(hash) = to_toggle_grid {
+                            if !self.state.grid.toggle(&hash) {
+                                self.toasts.add_error(format!("The grid holds at most {GRID_MAX_PANES} images"));
+                            }
+                        }
+                        if let Some(hash) = to_toggle_heatmap {
+                            if self.state.heatmap.as_ref().is_some_and(|heatmap| heatmap.hash == hash) {
+                                self.state.heatmap = None;
+                            } else {
+                                self.state.heatmap = Some(HeatmapOverlay::new(hash));
+                                self.state.is_show_heatmap = true;
+                            }
+                        }
+                        if let Some((hash, group)) = to_assign_group {
+                            match group {
+                                Some(group) => {
+                                    self.state.manual_asset_groups.insert(hash, group);
+                                    self.state.asset_grouping = crate::settings::AssetGrouping::Manual;
+                                }
+                                None => {
+                                    self.state.manual_asset_groups.remove(&hash);
+                                }
+                            }
+                        }
+                        to_remove.extend(group_to_remove.unwrap_or_default());
+
                         if to_retain.is_empty() {
-                            self.state.assets.retain(|hash, _| !to_remove.contains(hash));
+                            self.state.remove_assets(|hash| to_remove.contains(hash));
                         } else {
-                            self.state.assets.retain(|hash, _| to_retain.contains(hash));
+                            self.state.remove_assets(|hash| !to_retain.contains(hash));
                         }
 
                         if let Some((hash, insertion_index)) = reorder_request {
@@ -2481,10 +6369,17 @@ impl eframe::App for ViewerApp {
                             }
                         }
                     });
+
+                    if let Some(render_state) = frame.wgpu_render_state().filter(|_| self.state.is_show_asset_thumbnails) {
+                        self.thumbnails.update(ui.ctx(), render_state, &self.state.assets);
+                    }
                 });
         }
 
         self.paint_asset_drag_preview(&ctx);
+        if let Some(asset) = &self.state.asset {
+            self.thumbnails.remember_display(asset.hash(), &self.state.shader_params);
+        }
 
         egui::CentralPanel::default()
             .frame(egui::Frame::new().inner_margin(0))
@@ -2564,8 +6459,12 @@ impl eframe::App for ViewerApp {
         let current_rect = self.state.marquee_rect;
         let current_asset_hash = self.state.asset.as_ref().map(|a| a.hash().to_string());
 
-        let rect_changed = current_rect != self.last_marquee_rect_for_cb;
-        self.save_persistent_ui_state_if_needed();
+        let mask_changed = match (&self.state.selection_mask, &self.last_selection_mask) {
+            (Some(current), Some(last)) => !Arc::ptr_eq(current, last),
+            (current, last) => current.is_some() != last.is_some(),
+        };
+        let rect_changed = current_rect != self.last_marquee_rect_for_cb || mask_changed;
+        self.save_persistent_ui_state_if_needed(&ctx);
         let content_changed = rect_changed || current_asset_hash != self.last_marquee_asset_hash;
         if rect_changed || content_changed {
             self.on_marquee_changed();
@@ -2573,11 +6472,32 @@ impl eframe::App for ViewerApp {
 
         if rect_changed {
             self.last_marquee_rect_for_cb = current_rect;
+            self.last_selection_mask = self.state.selection_mask.clone();
         }
         if current_asset_hash != self.last_marquee_asset_hash {
             self.last_marquee_asset_hash = current_asset_hash;
         }
 
+        self.show_pixel_probe(&ctx);
+        self.show_timeline(&ctx);
+        self.show_vector_overlay_legend(&ctx);
+        self.show_segmentation_mask_legend(&ctx);
+        self.show_lens_distortion_window(&ctx);
+
+        if self.show_memory_window {
+            let rows = self.state.memory_report();
+            if show_memory_window(&ctx, &mut self.show_memory_window, &rows) {
+                self.state.free_caches();
+            }
+        }
+
+        if let Some(frame_start) = frame_start {
+            self.perf_hud.end_frame(frame_start);
+            show_perf_hud(&ctx, &self.perf_hud, self.state.assets.len(), self.state.asset_memory_bytes());
+            // Keep frames coming so the frame times measure rendering rather than idle gaps.
+            ctx.request_repaint();
+        }
+
         // Debug window
         #[cfg(debug_assertions)]
         {
@@ -2585,3 +6505,52 @@ impl eframe::App for ViewerApp {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_zoom_accepts_percentages_factors_and_ratios() {
+        for (text, scale) in [
+            ("50%", 0.5),
+            (" 250 % ", 2.5),
+            ("2x", 2.0),
+            ("2.5X", 2.5),
+            ("1:4", 0.25),
+            ("3 : 1", 3.0),
+            ("4", 4.0),
+            ("0.125", 0.125),
+        ] {
+            assert_eq!(parse_zoom(text), Some(scale), "{text}");
+        }
+    }
+
+    #[test]
+    fn parse_zoom_rejects_invalid_input() {
+        for text in [
+            "", " ", "%", "x", ":", "-50%", "-2", "0", "0%", "0x", "1:0", "0:1", "NaN", "inf", "abc", "2xx", "1:2:3",
+        ] {
+            assert_eq!(parse_zoom(text), None, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn parse_zoom_clamps_to_the_zoom_range() {
+        assert_eq!(parse_zoom("100000%"), Some(ZOOM_RANGE.1));
+        assert_eq!(parse_zoom("1:100000"), Some(ZOOM_RANGE.0));
+        assert_eq!(parse_zoom("1e-9"), Some(ZOOM_RANGE.0));
+    }
+
+    #[test]
+    fn zoom_presets_survive_a_format_parse_round_trip() {
+        for preset in ZOOM_PRESETS {
+            let text = format_zoom(preset);
+            let parsed = parse_zoom(&text).unwrap();
+            assert!((parsed - preset).abs() <= preset * 1e-3, "{preset} -> {text} -> {parsed}");
+        }
+        assert_eq!(format_zoom(0.0625), "6.25%");
+        assert_eq!(format_zoom(2.0), "200%");
+        assert_eq!(format_zoom(1.0 / 3.0), "33.33%");
+    }
+}
@@ -0,0 +1,142 @@
+use eframe::egui;
+
+use crate::{
+    model::SequenceFormat,
+    ui::directory_export::{ContactSheetOptions, SequenceOptions},
+};
+
+pub struct SequenceExportDialog {
+    options: SequenceOptions,
+    frame_count: usize,
+    /// Size of the current image, which sets the frame size.
+    image_size: (u32, u32),
+}
+
+impl SequenceExportDialog {
+    pub fn new(options: SequenceOptions, frame_count: usize, image_size: (u32, u32)) -> Self {
+        Self {
+            options,
+            frame_count,
+            image_size,
+        }
+    }
+}
+
+/// Ask for the format, frame rate and resolution of a directory export. Returns the options once confirmed.
+pub fn show_sequence_export_dialog(
+    ctx: &egui::Context,
+    dialog: &mut Option<SequenceExportDialog>,
+) -> Option<SequenceOptions> {
+    let state = dialog.as_mut()?;
+    let mut open = true;
+    let mut confirmed = false;
+    let mut cancelled = false;
+
+    egui::Window::new("Export Sequence")
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .collapsible(false)
+        .resizable(false)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            let options = &mut state.options;
+            egui::Grid::new("sequence_export_grid").num_columns(2).show(ui, |ui| {
+                ui.label("Format");
+                egui::ComboBox::from_id_salt("sequence_export_format")
+                    .selected_text(options.format.label())
+                    .show_ui(ui, |ui| {
+                        for format in SequenceFormat::ALL {
+                            ui.selectable_value(&mut options.format, format, format.label());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Frame rate");
+                ui.add(egui::DragValue::new(&mut options.fps).range(1..=60).suffix(" fps"));
+                ui.end_row();
+
+                ui.label("Resolution");
+                ui.add(egui::DragValue::new(&mut options.scale_percent).range(5..=200).suffix(" %"));
+                ui.end_row();
+            });
+
+            let (width, height) = options.frame_size(state.image_size.0, state.image_size.1);
+            ui.weak(format!(
+                "{} images in this directory, {width}×{height} at {:.1} s",
+                state.frame_count,
+                state.frame_count as f32 / options.fps.max(1) as f32
+            ));
+            ui.weak("Frames use the current colormap and display settings.");
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                confirmed = ui.button("Export...").clicked();
+                cancelled = ui.button("Cancel").clicked();
+            });
+        });
+
+    if confirmed {
+        return dialog.take().map(|state| state.options);
+    }
+    if cancelled || !open {
+        *dialog = None;
+    }
+    None
+}
+
+pub struct ContactSheetDialog {
+    options: ContactSheetOptions,
+    image_count: usize,
+}
+
+impl ContactSheetDialog {
+    pub fn new(options: ContactSheetOptions, image_count: usize) -> Self {
+        Self { options, image_count }
+    }
+}
+
+/// Ask for the columns and thumbnail size of a contact sheet. Returns the options once confirmed.
+pub fn show_contact_sheet_dialog(
+    ctx: &egui::Context,
+    dialog: &mut Option<ContactSheetDialog>,
+) -> Option<ContactSheetOptions> {
+    let state = dialog.as_mut()?;
+    let mut open = true;
+    let mut confirmed = false;
+    let mut cancelled = false;
+
+    egui::Window::new("Create Contact Sheet")
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .collapsible(false)
+        .resizable(false)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            let options = &mut state.options;
+            egui::Grid::new("contact_sheet_grid").num_columns(2).show(ui, |ui| {
+                ui.label("Columns");
+                ui.add(egui::DragValue::new(&mut options.columns).range(1..=32));
+                ui.end_row();
+
+                ui.label("Thumbnail size");
+                ui.add(egui::DragValue::new(&mut options.thumbnail_size).range(32..=1024).suffix(" px"));
+                ui.end_row();
+            });
+
+            let rows = state.image_count.div_ceil(options.columns.max(1) as usize);
+            ui.weak(format!("{} images in this directory, {rows} row(s)", state.image_count));
+            ui.weak("Thumbnails use the current colormap and display settings.");
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                confirmed = ui.button("Create...").clicked();
+                cancelled = ui.button("Cancel").clicked();
+            });
+        });
+
+    if confirmed {
+        return dialog.take().map(|state| state.options);
+    }
+    if cancelled || !open {
+        *dialog = None;
+    }
+    None
+}
@@ -1,7 +1,7 @@
 use eframe::egui::{self, Color32};
 
 use crate::{
-    model::ImageData,
+    model::{ImageData, PointPickTarget, PointPicker},
     res::icons::Icons,
     switch,
     ui::{
@@ -42,6 +42,7 @@ pub fn display_controls_ui(
     auto_minmax: &mut bool,
     min_v: &mut f32,
     max_v: &mut f32,
+    point_picker: &mut Option<PointPicker>,
 ) {
     let ctx = ui.ctx().clone();
     let normalize_enabled = scale_mode.auto_normalize_enabled();
@@ -96,17 +97,35 @@ pub fn display_controls_ui(
         }
     }
 
+    let mut picker_toggle = |ui: &mut egui::Ui, target: PointPickTarget, icon: egui::Image, name: &str| {
+        let picker = PointPicker { target, channel };
+        let mut active = *point_picker == Some(picker);
+        if ui.toggle_icon(&mut active, icon, name).clicked() {
+            *point_picker = active.then_some(picker);
+        }
+    };
+
     ui.columns_sized(
         [
+            Size::exact(16.0),
             Size::remainder(1.0),
             Size::exact(24.0),
             Size::remainder(1.0),
+            Size::exact(16.0),
             Size::exact(original_spacing.x),
             Size::exact(16.0),
         ],
         |columns| {
+            // Black point picker
+            picker_toggle(
+                &mut columns[0],
+                PointPickTarget::Black,
+                icons.get_pick_black_point(&ctx),
+                "Pick black point: click a pixel in the image to set the min value",
+            );
+
             // Min control
-            columns[0].add_enabled_ui(!locked, |ui| {
+            columns[1].add_enabled_ui(!locked, |ui| {
                 ui.add(
                     egui::DragValue::new(switch!(locked => &mut tmp_min, min_v))
                         .speed(0.01)
@@ -122,7 +141,7 @@ pub fn display_controls_ui(
             });
 
             // Swap min/max button
-            if columns[1]
+            if columns[2]
                 .add_enabled_ui(!locked, |ui| ui.button("↔").on_hover_text("Switch min/max"))
                 .inner
                 .clicked()
@@ -131,7 +150,7 @@ pub fn display_controls_ui(
             }
 
             // Max control
-            columns[2].add_enabled_ui(!locked, |ui| {
+            columns[3].add_enabled_ui(!locked, |ui| {
                 ui.add(
                     egui::DragValue::new(switch!(locked => &mut tmp_max, max_v))
                         .speed(0.01)
@@ -146,13 +165,21 @@ pub fn display_controls_ui(
                 });
             });
 
+            // White point picker
+            picker_toggle(
+                &mut columns[4],
+                PointPickTarget::White,
+                icons.get_pick_white_point(&ctx),
+                "Pick white point: click a pixel in the image to set the max value",
+            );
+
             // Auto min/max toggle
             let auto_tip = if normalize_enabled {
                 "Use min / max values of image for normalization"
             } else {
                 "Auto min/max disabled for Inverse/Log modes"
             };
-            columns[4].add_enabled_ui(normalize_enabled, |ui| {
+            columns[6].add_enabled_ui(normalize_enabled, |ui| {
                 ui.toggle_icon(auto_minmax, icons.get_normalize(&ctx), auto_tip)
                     .context_menu(|ui| {
                         if ui
@@ -179,7 +206,7 @@ pub fn display_profile_slider(
     text: &str,
 ) -> egui::Response {
     ui.spacing_mut().slider_width = ui.available_width() - 128.0;
-    let response = ui.add(
+    let mut response = ui.add(
         CustomSlider::new(value, min..=max)
             .text(text)
             .step_by(0.01)
@@ -190,6 +217,10 @@ pub fn display_profile_slider(
             .trailing_color_pos(Color32::from_hex("#4EADE4").unwrap())
             .trailing_color_neg(Color32::from_hex("#FF6B6B").unwrap()),
     );
+    if response.double_clicked() {
+        *value = baseline as f32;
+        response.mark_changed();
+    }
     response.context_menu(|ui| {
         if ui.button("Reset").clicked() {
             *value = baseline as f32;
@@ -0,0 +1,54 @@
+use eframe::egui;
+
+use crate::settings::{AspectFit, ExportData, ExportMetadata, ExportOptions, ExportSize};
+
+/// Choices shared by the copy and save actions, for the toolbar and the viewer's context menu.
+pub fn export_options_ui(ui: &mut egui::Ui, options: &mut ExportOptions) {
+    for data in ExportData::ALL {
+        ui.radio_value(&mut options.data, data, data.label())
+            .on_hover_text(data.description());
+    }
+    ui.separator();
+    let display = options.data == ExportData::Display;
+    ui.add_enabled_ui(display, |ui| {
+        export_size_ui(ui, options);
+        ui.separator();
+        for metadata in ExportMetadata::ALL {
+            ui.radio_value(&mut options.metadata, metadata, metadata.label());
+        }
+    })
+    .response
+    .on_disabled_hover_text("Data exports are always at image pixel size and carry no source metadata");
+}
+
+fn export_size_ui(ui: &mut egui::Ui, options: &mut ExportOptions) {
+    for size in ExportSize::ALL {
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut options.size, size, size.label());
+            ui.add_enabled_ui(options.size == size, |ui| match size {
+                ExportSize::Scale => {
+                    ui.add(egui::DragValue::new(&mut options.scale_percent).range(5..=3200).suffix("%"));
+                }
+                ExportSize::Resolution => {
+                    let [width, height] = &mut options.resolution;
+                    ui.add(egui::DragValue::new(width).range(1..=65536));
+                    ui.label("\u{d7}");
+                    ui.add(egui::DragValue::new(height).range(1..=65536));
+                    for aspect in AspectFit::ALL {
+                        ui.radio_value(&mut options.aspect, aspect, aspect.label());
+                    }
+                }
+                _ => {}
+            });
+        })
+        .response
+        .on_hover_text(match size {
+            ExportSize::Original => "One output pixel per image pixel",
+            ExportSize::CurrentZoom => "The size shown on screen",
+            ExportSize::Scale => "A multiple of the image pixel size",
+            ExportSize::Resolution => {
+                "Fit the whole region inside the resolution, or fill it exactly by cropping the region's center"
+            }
+        });
+    }
+}
@@ -17,12 +17,15 @@ struct HistogramHoverState {
     lines: Vec<(String, Color32)>,
 }
 
+/// Bars of each channel in `series` that is set in `mask`, scaled to `max_freq`. With `log_scale` the bar heights
+/// follow `ln(1 + count)`, so sparse bins stay visible next to a dominant peak; the hover readout keeps the counts.
 pub fn draw_histogram(
     ui: &mut egui::Ui,
     desired_size: Vec2,
     series: SeriesRef<'_, f32>,
     mask: &[bool],
     max_freq: f32,
+    log_scale: bool,
 ) -> Option<ExportAction> {
     #[cfg(debug_assertions)]
     let _timer = ScopedTimer::new("ui.histogram_plot.draw");
@@ -58,6 +61,13 @@ pub fn draw_histogram(
     let bin_width = full_w / bins as f32;
     let inner_w = bin_width.max(0.0);
 
+    let bar_height = |v: f32| {
+        if log_scale {
+            v.ln_1p() / max_freq.ln_1p()
+        } else {
+            v / max_freq
+        }
+    };
     let bar_colors = histogram_bar_colors();
     let label_colors = HISTOGRAM_LABEL_COLORS;
 
@@ -75,7 +85,7 @@ pub fn draw_histogram(
             if v <= 0.0 {
                 continue;
             }
-            let h_px = (v / max_freq).clamp(0.0, 1.0) * rect.height();
+            let h_px = bar_height(v).clamp(0.0, 1.0) * rect.height();
             if h_px <= 0.0 {
                 continue;
             }
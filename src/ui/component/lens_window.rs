@@ -0,0 +1,101 @@
+use eframe::egui;
+
+use crate::model::LensDistortion;
+
+#[derive(Default)]
+pub struct LensWindowActions {
+    pub changed: bool,
+    pub error: Option<String>,
+}
+
+/// Intrinsics and distortion coefficients of the undistort preview. `image_size` is the size of the shown image,
+/// used to reset the principal point to its center.
+pub fn show_lens_window(
+    ctx: &egui::Context,
+    open: &mut bool,
+    lens: &mut LensDistortion,
+    image_size: Option<(i32, i32)>,
+) -> LensWindowActions {
+    let mut actions = LensWindowActions::default();
+    if !*open {
+        return actions;
+    }
+    let before = *lens;
+
+    egui::Window::new("Lens Distortion")
+        .default_pos(egui::pos2(320.0, 200.0))
+        .collapsible(false)
+        .resizable(false)
+        .open(open)
+        .show(ctx, |ui| {
+            ui.weak("OpenCV pinhole model; pixel centers are at integer coordinates.");
+            egui::Grid::new("lens_parameters").num_columns(4).show(ui, |ui| {
+                let value = |ui: &mut egui::Ui, label: &str, value: &mut f32, speed: f64| {
+                    ui.label(label);
+                    ui.add(egui::DragValue::new(value).speed(speed).max_decimals(8));
+                };
+                value(ui, "fx", &mut lens.fx, 1.0);
+                value(ui, "fy", &mut lens.fy, 1.0);
+                ui.end_row();
+                value(ui, "cx", &mut lens.cx, 0.5);
+                value(ui, "cy", &mut lens.cy, 0.5);
+                ui.end_row();
+                value(ui, "k1", &mut lens.k1, 0.001);
+                value(ui, "k2", &mut lens.k2, 0.001);
+                ui.end_row();
+                value(ui, "p1", &mut lens.p1, 0.0001);
+                value(ui, "p2", &mut lens.p2, 0.0001);
+                ui.end_row();
+                value(ui, "k3", &mut lens.k3, 0.001);
+                ui.end_row();
+            });
+            if !lens.is_valid() {
+                ui.colored_label(crate::res::NOTICE_WARNING_TEXT, "The focal lengths must be positive");
+            }
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                let paste = |apply: &dyn Fn(&mut LensDistortion, &str) -> color_eyre::Result<()>,
+                             lens: &mut LensDistortion| {
+                    arboard::Clipboard::new()
+                        .and_then(|mut clipboard| clipboard.get_text())
+                        .map_err(|e| e.to_string())
+                        .and_then(|text| apply(lens, &text).map_err(|e| e.to_string()))
+                };
+                if ui
+                    .button("Paste Camera Matrix")
+                    .on_hover_text("Read the 3×3 camera matrix from the clipboard, as printed by numpy or OpenCV")
+                    .clicked()
+                {
+                    actions.error = paste(&LensDistortion::set_camera_matrix, lens).err();
+                }
+                if ui
+                    .button("Paste Coefficients")
+                    .on_hover_text("Read k1, k2, p1, p2 and optionally k3 from the clipboard")
+                    .clicked()
+                {
+                    actions.error = paste(&LensDistortion::set_coefficients, lens).err();
+                }
+            });
+            ui.horizontal(|ui| {
+                if let Some((width, height)) = image_size {
+                    if ui
+                        .button("Reset")
+                        .on_hover_text("No distortion, centered on the shown image")
+                        .clicked()
+                    {
+                        *lens = LensDistortion::for_image(width, height);
+                    }
+                }
+                if ui.button("Copy Coefficients").clicked() {
+                    let text = format!("{}, {}, {}, {}, {}", lens.k1, lens.k2, lens.p1, lens.p2, lens.k3);
+                    if let Err(e) = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+                        actions.error = Some(e.to_string());
+                    }
+                }
+            });
+        });
+
+    actions.changed = *lens != before;
+    actions
+}
@@ -0,0 +1,74 @@
+use eframe::egui;
+
+use crate::model::SegmentationMask;
+
+#[derive(Default)]
+pub struct MaskLegendActions {
+    pub unload: bool,
+}
+
+/// Classes of the loaded segmentation mask with their colors, visibility and pixel counts, and the layer opacity.
+/// `image_size` is the size of the shown image, to point out a mask that does not match it.
+pub fn show_mask_legend(
+    ctx: &egui::Context,
+    mask: &mut SegmentationMask,
+    shown: &mut bool,
+    image_size: Option<(u32, u32)>,
+) -> MaskLegendActions {
+    let mut actions = MaskLegendActions::default();
+    let mut open = true;
+
+    egui::Window::new("Segmentation Mask")
+        .id(egui::Id::new("segmentation_mask_legend"))
+        .default_pos(egui::pos2(320.0, 200.0))
+        .resizable(true)
+        .default_size(egui::vec2(240.0, 260.0))
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(shown, "Show");
+                ui.strong(&mask.name);
+            });
+            if let Some((width, height)) = image_size.filter(|&size| size != (mask.width, mask.height)) {
+                ui.colored_label(
+                    crate::res::NOTICE_WARNING_TEXT,
+                    format!("Mask is {}×{}, stretched over {width}×{height}", mask.width, mask.height),
+                );
+            }
+            ui.add(egui::Slider::new(&mut mask.opacity, 0.0..=1.0).text("Opacity"));
+            ui.horizontal(|ui| {
+                if ui.button("All").clicked() {
+                    mask.set_all_visible(true);
+                }
+                if ui.button("None").clicked() {
+                    mask.set_all_visible(false);
+                }
+            });
+            ui.separator();
+
+            let total = (mask.width as usize * mask.height as usize).max(1);
+            let mut toggled = None;
+            egui::ScrollArea::vertical().auto_shrink([false, true]).show(ui, |ui| {
+                for (index, class) in mask.classes.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        let (swatch, _) = ui.allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+                        ui.painter().rect_filled(swatch, 2.0, class.color);
+                        let mut visible = class.visible;
+                        if ui.checkbox(&mut visible, format!("Label {}", class.label)).changed() {
+                            toggled = Some((index, visible));
+                        }
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            let percent = class.pixels as f64 * 100.0 / total as f64;
+                            ui.label(format!("{} ({percent:.1}%)", class.pixels));
+                        });
+                    });
+                }
+            });
+            if let Some((index, visible)) = toggled {
+                mask.set_visible(index, visible);
+            }
+        });
+
+    actions.unload = !open;
+    actions
+}
@@ -0,0 +1,71 @@
+use eframe::egui;
+use egui_extras::{Column, TableBuilder};
+
+use crate::{model::ImageMemory, util::perf::format_bytes};
+
+/// Per-asset memory table. Returns true when "Free Caches" was clicked.
+pub fn show_memory_window(ctx: &egui::Context, open: &mut bool, rows: &[(String, ImageMemory)]) -> bool {
+    if !*open {
+        return false;
+    }
+
+    let mut free_caches = false;
+    let mut total = ImageMemory::default();
+    for (_, memory) in rows {
+        total += *memory;
+    }
+
+    egui::Window::new("Memory")
+        .default_pos(egui::pos2(320.0, 120.0))
+        .collapsible(false)
+        .resizable(true)
+        .default_size(egui::vec2(560.0, 360.0))
+        .open(open)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                free_caches = ui
+                    .button("Free Caches")
+                    .on_hover_text("Release GPU textures and integral tables of images that are not on screen")
+                    .clicked();
+                ui.label(format!(
+                    "Total: {} pixels, {} analysis, {} GPU",
+                    format_bytes(total.pixels as u64),
+                    format_bytes(total.analysis as u64),
+                    format_bytes(total.gpu as u64)
+                ));
+            });
+            ui.weak("GPU sizes are estimates from texture dimensions and format.");
+            ui.separator();
+
+            let bytes_cell = |ui: &mut egui::Ui, bytes: usize| {
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.monospace(format_bytes(bytes as u64));
+                });
+            };
+            TableBuilder::new(ui)
+                .striped(true)
+                .column(Column::remainder().at_least(160.0).clip(true))
+                .columns(Column::auto().at_least(80.0), 3)
+                .header(20.0, |mut header| {
+                    for title in ["Asset", "Pixels", "Analysis", "GPU"] {
+                        header.col(|ui| {
+                            ui.strong(title);
+                        });
+                    }
+                })
+                .body(|mut body| {
+                    for (name, memory) in rows {
+                        body.row(18.0, |mut row| {
+                            row.col(|ui| {
+                                ui.label(name).on_hover_text(name);
+                            });
+                            row.col(|ui| bytes_cell(ui, memory.pixels));
+                            row.col(|ui| bytes_cell(ui, memory.analysis));
+                            row.col(|ui| bytes_cell(ui, memory.gpu));
+                        });
+                    }
+                });
+        });
+
+    free_caches
+}
@@ -1,18 +1,48 @@
 mod bookmark_window;
 mod channel_toggle;
 mod custom_slider;
+mod directory_export_dialog;
 mod display_controls;
 pub mod egui_ext;
 mod export_action;
+mod export_options;
 mod histogram_plot;
+mod lens_window;
+mod mask_legend;
+mod memory_window;
 mod multi_line_plot;
+mod overlay_legend;
+mod perf_hud;
+mod print_dialog;
+mod probe_window;
+mod raw_dialog;
+mod record_view_dialog;
+mod save_as_dialog;
+mod script_console;
+mod timeline_window;
 mod toast;
+mod yuv_dialog;
 
 pub use bookmark_window::*;
 pub use channel_toggle::*;
 pub use custom_slider::*;
+pub use directory_export_dialog::*;
 pub use display_controls::*;
 pub use export_action::*;
+pub use export_options::*;
 pub use histogram_plot::*;
+pub use lens_window::*;
+pub use mask_legend::*;
+pub use memory_window::*;
 pub use multi_line_plot::*;
+pub use overlay_legend::*;
+pub use perf_hud::*;
+pub use print_dialog::*;
+pub use probe_window::*;
+pub use raw_dialog::*;
+pub use record_view_dialog::*;
+pub use save_as_dialog::*;
+pub use script_console::*;
+pub use timeline_window::*;
 pub use toast::*;
+pub use yuv_dialog::*;
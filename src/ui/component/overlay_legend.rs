@@ -0,0 +1,66 @@
+use eframe::egui;
+
+use crate::{model::VectorOverlay, res::overlay_class_color};
+
+#[derive(Default)]
+pub struct OverlayLegendActions {
+    pub unload: bool,
+}
+
+/// Classes of the loaded overlay with their colors, visibility and object count in the shown image.
+pub fn show_overlay_legend(
+    ctx: &egui::Context,
+    overlay: &mut VectorOverlay,
+    shown: &mut bool,
+    counts: &[usize],
+) -> OverlayLegendActions {
+    let mut actions = OverlayLegendActions::default();
+    let mut open = true;
+
+    egui::Window::new("Overlay")
+        .id(egui::Id::new("vector_overlay_legend"))
+        .default_pos(egui::pos2(320.0, 160.0))
+        .resizable(true)
+        .default_size(egui::vec2(220.0, 240.0))
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(shown, "Show").on_hover_text(&overlay.name);
+                ui.strong(&overlay.name);
+            });
+            ui.horizontal(|ui| {
+                if ui.button("All").clicked() {
+                    overlay.visible.fill(true);
+                }
+                if ui.button("None").clicked() {
+                    overlay.visible.fill(false);
+                }
+            });
+            if overlay.has_scores() {
+                ui.add(egui::Slider::new(&mut overlay.min_score, 0.0..=1.0).text("Min score"))
+                    .on_hover_text("Hide detections with a lower confidence");
+            }
+            ui.separator();
+
+            egui::ScrollArea::vertical().auto_shrink([false, true]).show(ui, |ui| {
+                for (index, (name, visible)) in overlay.classes.iter().zip(&mut overlay.visible).enumerate() {
+                    ui.horizontal(|ui| {
+                        let (swatch, _) = ui.allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+                        ui.painter().rect_filled(swatch, 2.0, overlay_class_color(index));
+                        ui.checkbox(visible, name);
+                        let count = counts.get(index).copied().unwrap_or(0);
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if count > 0 {
+                                ui.label(count.to_string());
+                            } else {
+                                ui.weak("0");
+                            }
+                        });
+                    });
+                }
+            });
+        });
+
+    actions.unload = !open;
+    actions
+}
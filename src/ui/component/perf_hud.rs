@@ -0,0 +1,86 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use eframe::egui;
+
+use crate::util::perf::{counters, format_bytes};
+
+const FRAME_SAMPLES: usize = 120;
+
+/// Frame timing collected while the performance HUD is open.
+#[derive(Default)]
+pub struct PerfHud {
+    last_frame: Option<Instant>,
+    /// Time between consecutive frames, newest last.
+    frame_intervals: VecDeque<Duration>,
+    /// CPU time spent building the UI in the previous frame.
+    ui_time: Duration,
+}
+
+impl PerfHud {
+    pub fn begin_frame(&mut self) -> Instant {
+        let now = Instant::now();
+        if let Some(last) = self.last_frame.replace(now) {
+            if self.frame_intervals.len() == FRAME_SAMPLES {
+                self.frame_intervals.pop_front();
+            }
+            self.frame_intervals.push_back(now - last);
+        }
+        now
+    }
+
+    pub fn end_frame(&mut self, started: Instant) {
+        self.ui_time = started.elapsed();
+    }
+
+    /// Forget frame times, e.g. after the HUD was hidden and idle gaps would skew the averages.
+    pub fn reset(&mut self) {
+        self.last_frame = None;
+        self.frame_intervals.clear();
+    }
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+pub fn show_perf_hud(ctx: &egui::Context, hud: &PerfHud, asset_count: usize, asset_bytes: usize) {
+    let mut lines = Vec::new();
+    if hud.frame_intervals.is_empty() {
+        lines.push("Frame      -".to_string());
+    } else {
+        let total: Duration = hud.frame_intervals.iter().sum();
+        let avg = total / hud.frame_intervals.len() as u32;
+        let max = hud.frame_intervals.iter().max().copied().unwrap_or_default();
+        let fps = if avg.is_zero() { 0.0 } else { 1.0 / avg.as_secs_f64() };
+        lines.push(format!(
+            "Frame      {:6.2} ms avg  {:6.2} ms max  {fps:5.1} fps",
+            ms(avg),
+            ms(max)
+        ));
+    }
+    lines.push(format!("UI         {:6.2} ms", ms(hud.ui_time)));
+    for (name, counter) in counters() {
+        lines.push(format!(
+            "{name:<10} {:6.2} ms last  {:6.2} ms avg  ({})",
+            ms(counter.last),
+            ms(counter.average()),
+            counter.count
+        ));
+    }
+    lines.push(format!("Assets     {asset_count}, {}", format_bytes(asset_bytes as u64)));
+
+    egui::Area::new(egui::Id::new("perf_hud"))
+        .order(egui::Order::Foreground)
+        .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-8.0, 40.0))
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                for line in lines {
+                    ui.label(egui::RichText::new(line).monospace().small());
+                }
+            });
+        });
+}
@@ -0,0 +1,115 @@
+use eframe::egui;
+
+use crate::{
+    model::{PaperSize, PrintLayout},
+    res::{PRINT_PREVIEW_MARGIN_STROKE, PRINT_PREVIEW_PAPER_FILL},
+};
+
+/// Height of the page preview in points on screen.
+const PREVIEW_HEIGHT: f32 = 220.0;
+
+pub struct PrintDialog {
+    layout: PrintLayout,
+    /// Size in pixels of the image or selection that will be printed.
+    image_size: (u32, u32),
+}
+
+impl PrintDialog {
+    pub fn new(layout: PrintLayout, image_size: (u32, u32)) -> Self {
+        Self { layout, image_size }
+    }
+}
+
+/// Ask for the page layout, previewing where the image lands. Returns the layout once the user confirms it.
+pub fn show_print_dialog(ctx: &egui::Context, dialog: &mut Option<PrintDialog>) -> Option<PrintLayout> {
+    let state = dialog.as_mut()?;
+    let mut open = true;
+    let mut confirmed = false;
+    let mut cancelled = false;
+
+    egui::Window::new("Print")
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .collapsible(false)
+        .resizable(false)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            let layout = &mut state.layout;
+            egui::Grid::new("print_layout_grid").num_columns(2).show(ui, |ui| {
+                ui.label("Paper");
+                egui::ComboBox::from_id_salt("print_paper")
+                    .selected_text(layout.paper.label())
+                    .show_ui(ui, |ui| {
+                        for paper in PaperSize::ALL {
+                            ui.selectable_value(&mut layout.paper, paper, paper.label());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Orientation");
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut layout.landscape, false, "Portrait");
+                    ui.selectable_value(&mut layout.landscape, true, "Landscape");
+                });
+                ui.end_row();
+
+                ui.label("Scale");
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut layout.fit_to_page, true, "Fit to page");
+                    ui.selectable_value(&mut layout.fit_to_page, false, "100%");
+                });
+                ui.end_row();
+
+                ui.label("Margins");
+                ui.add(
+                    egui::DragValue::new(&mut layout.margin_mm)
+                        .range(0.0..=50.0)
+                        .speed(0.5)
+                        .suffix(" mm"),
+                );
+                ui.end_row();
+            });
+
+            ui.separator();
+            let (page_w, page_h) = layout.page_size();
+            let scale = PREVIEW_HEIGHT / page_w.max(page_h);
+            let (response, painter) = ui.allocate_painter(egui::vec2(page_w, page_h) * scale, egui::Sense::hover());
+            let page = response.rect;
+            let to_screen = |x: f32, y: f32, w: f32, h: f32| {
+                egui::Rect::from_min_size(page.min + egui::vec2(x, y) * scale, egui::vec2(w, h) * scale)
+            };
+            let margin = layout.margin_points();
+            let (x, y, w, h) = layout.placement(state.image_size.0, state.image_size.1);
+            let visuals = ui.visuals();
+            painter.rect_filled(page, 0.0, PRINT_PREVIEW_PAPER_FILL);
+            painter
+                .with_clip_rect(page)
+                .rect_filled(to_screen(x, y, w, h), 0.0, visuals.selection.bg_fill);
+            painter.rect_stroke(
+                to_screen(margin, margin, page_w - 2.0 * margin, page_h - 2.0 * margin),
+                0.0,
+                egui::Stroke::new(1.0, PRINT_PREVIEW_MARGIN_STROKE),
+                egui::StrokeKind::Inside,
+            );
+            painter.rect_stroke(page, 0.0, visuals.window_stroke, egui::StrokeKind::Outside);
+            ui.weak(format!(
+                "{}×{} px at {:.0} dpi",
+                state.image_size.0,
+                state.image_size.1,
+                state.image_size.0 as f32 / (w / 72.0)
+            ));
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                confirmed = ui.button("Print").clicked();
+                cancelled = ui.button("Cancel").clicked();
+            });
+        });
+
+    if confirmed {
+        return dialog.take().map(|state| state.layout);
+    }
+    if cancelled || !open {
+        *dialog = None;
+    }
+    None
+}
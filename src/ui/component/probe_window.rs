@@ -0,0 +1,87 @@
+use eframe::egui;
+use egui_extras::{Column, TableBuilder};
+
+use crate::{
+    model::{probe_channels, ProbeSample},
+    util::math_ext::Vec2i,
+};
+
+#[derive(Default)]
+pub struct ProbeWindowActions {
+    pub copy_csv: bool,
+}
+
+/// Values of `pixel` in every image of the shown size, with the row of `current_hash` highlighted.
+pub fn show_probe_window(
+    ctx: &egui::Context,
+    open: &mut bool,
+    hold: &mut bool,
+    pixel: Option<Vec2i>,
+    samples: &[ProbeSample],
+    current_hash: Option<&str>,
+    hold_shortcut: &str,
+) -> ProbeWindowActions {
+    let mut actions = ProbeWindowActions::default();
+    if !*open {
+        return actions;
+    }
+
+    egui::Window::new("Pixel Probe")
+        .default_pos(egui::pos2(320.0, 160.0))
+        .collapsible(false)
+        .resizable(true)
+        .default_size(egui::vec2(420.0, 240.0))
+        .open(open)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                match pixel {
+                    Some(pixel) => ui.strong(format!("Pixel {}, {}", pixel.x, pixel.y)),
+                    None => ui.weak("Move the pointer over the image"),
+                };
+                ui.checkbox(hold, format!("Hold ({hold_shortcut})"))
+                    .on_hover_text("Keep probing this pixel while the pointer moves elsewhere");
+                actions.copy_csv = ui
+                    .add_enabled(!samples.is_empty(), egui::Button::new("Copy CSV"))
+                    .on_hover_text("Copy the table with one row per image")
+                    .clicked();
+            });
+            ui.weak("Images with the size of the shown image, in image list order.");
+            ui.separator();
+
+            let channels = probe_channels(samples);
+            TableBuilder::new(ui)
+                .striped(true)
+                .column(Column::remainder().at_least(160.0).clip(true))
+                .columns(Column::auto().at_least(64.0), channels)
+                .header(20.0, |mut header| {
+                    header.col(|ui| {
+                        ui.strong("Asset");
+                    });
+                    for channel in 0..channels {
+                        header.col(|ui| {
+                            ui.strong(format!("C{channel}"));
+                        });
+                    }
+                })
+                .body(|mut body| {
+                    for sample in samples {
+                        let current = current_hash == Some(sample.hash.as_str());
+                        body.row(18.0, |mut row| {
+                            row.set_selected(current);
+                            row.col(|ui| {
+                                ui.label(&sample.name).on_hover_text(&sample.name);
+                            });
+                            for channel in 0..channels {
+                                row.col(|ui| {
+                                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                        ui.monospace(sample.values.get(channel).map_or("", String::as_str));
+                                    });
+                                });
+                            }
+                        });
+                    }
+                });
+        });
+
+    actions
+}
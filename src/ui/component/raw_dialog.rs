@@ -0,0 +1,222 @@
+use std::{path::PathBuf, sync::mpsc};
+
+use eframe::egui;
+
+use crate::{
+    model::{raw_preview, PixelType, RawLayout},
+    res::NOTICE_ERROR_TEXT,
+};
+
+/// Longest side of the preview, in sampled pixels.
+const PREVIEW_SIZE: u32 = 256;
+
+pub struct RawLayoutDialog {
+    path: PathBuf,
+    layout: RawLayout,
+    file_rx: Option<mpsc::Receiver<Result<Vec<u8>, String>>>,
+    file: Option<Result<Vec<u8>, String>>,
+    preview: Option<(RawLayout, Result<egui::TextureHandle, String>)>,
+}
+
+impl RawLayoutDialog {
+    /// Start reading `path` in the background for the preview.
+    pub fn new(path: PathBuf) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let read_path = path.clone();
+        std::thread::spawn(move || {
+            let bytes = match crate::model::split_archive_entry_path(&read_path) {
+                Some((archive, entry)) => crate::model::read_archive_entry(&archive, &entry),
+                None => std::fs::read(&read_path).map_err(Into::into),
+            };
+            let _ = tx.send(bytes.map_err(|e| e.to_string()));
+        });
+        Self {
+            path,
+            layout: RawLayout::default(),
+            file_rx: Some(rx),
+            file: None,
+            preview: None,
+        }
+    }
+
+    fn receive_file(&mut self) {
+        let Some(rx) = &self.file_rx else {
+            return;
+        };
+        match rx.try_recv() {
+            Ok(result) => {
+                if let Ok(bytes) = &result {
+                    self.layout = guess_layout(bytes.len() as u64);
+                }
+                self.file = Some(result);
+                self.file_rx = None;
+            }
+            Err(mpsc::TryRecvError::Empty) => {}
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.file = Some(Err("file reader stopped".to_string()));
+                self.file_rx = None;
+            }
+        }
+    }
+
+    /// Rebuild the preview when the layout changed since it was drawn. Returns whether it did.
+    fn update_preview(&mut self, ctx: &egui::Context) -> bool {
+        let Some(Ok(file)) = &self.file else {
+            return false;
+        };
+        if self.preview.as_ref().is_some_and(|(layout, _)| *layout == self.layout) {
+            return false;
+        }
+        let texture = raw_preview(file, &self.layout, PREVIEW_SIZE)
+            .map(|(w, h, rgba)| {
+                let image = egui::ColorImage::from_rgba_unmultiplied([w as usize, h as usize], &rgba);
+                ctx.load_texture("raw_layout_preview", image, egui::TextureOptions::NEAREST)
+            })
+            .map_err(|e| e.to_string());
+        self.preview = Some((self.layout, texture));
+        true
+    }
+}
+
+/// A square single-channel layout filling the whole file, trying wider sample types when bytes would remain.
+fn guess_layout(file_bytes: u64) -> RawLayout {
+    let candidates = [PixelType::U8, PixelType::U16, PixelType::F32]
+        .into_iter()
+        .flat_map(|pixel_type| [1_u32, 3, 4].map(|channels| (pixel_type, channels)));
+    for (pixel_type, channels) in candidates {
+        let sample_bytes = pixel_type.bytes() as u64 * channels as u64;
+        if file_bytes == 0 || !file_bytes.is_multiple_of(sample_bytes) {
+            continue;
+        }
+        let pixels = file_bytes / sample_bytes;
+        let side = (pixels as f64).sqrt().round() as u64;
+        if side * side == pixels && side <= u32::MAX as u64 {
+            return RawLayout {
+                width: side as u32,
+                height: side as u32,
+                channels,
+                pixel_type,
+                ..RawLayout::default()
+            };
+        }
+    }
+    RawLayout::default()
+}
+
+/// Ask for the layout of a headerless binary file, previewing it live. Returns the layout once confirmed.
+pub fn show_raw_layout_dialog(
+    ctx: &egui::Context,
+    dialog: &mut Option<RawLayoutDialog>,
+) -> Option<(PathBuf, RawLayout)> {
+    let state = dialog.as_mut()?;
+    state.receive_file();
+
+    let mut open = true;
+    let mut confirmed = false;
+    let mut cancelled = false;
+
+    let file_name = state
+        .path
+        .file_name()
+        .map_or_else(String::new, |n| n.to_string_lossy().to_string());
+    egui::Window::new("Open Raw Data")
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .collapsible(false)
+        .resizable(false)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.label(file_name);
+            let layout = &mut state.layout;
+            egui::Grid::new("raw_layout_grid").num_columns(2).show(ui, |ui| {
+                ui.label("Size");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut layout.width).range(1..=65536));
+                    ui.label("×");
+                    ui.add(egui::DragValue::new(&mut layout.height).range(1..=65536));
+                });
+                ui.end_row();
+
+                ui.label("Channels");
+                ui.add(egui::DragValue::new(&mut layout.channels).range(1..=4));
+                ui.end_row();
+
+                ui.label("Type");
+                egui::ComboBox::from_id_salt("raw_layout_type")
+                    .selected_text(layout.pixel_type.name())
+                    .show_ui(ui, |ui| {
+                        for pixel_type in PixelType::ALL {
+                            ui.selectable_value(&mut layout.pixel_type, pixel_type, pixel_type.name());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Byte order");
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut layout.big_endian, false, "Little endian");
+                    ui.selectable_value(&mut layout.big_endian, true, "Big endian");
+                });
+                ui.end_row();
+
+                ui.label("Header");
+                ui.add(egui::DragValue::new(&mut layout.header_bytes).suffix(" bytes"));
+                ui.end_row();
+            });
+
+            ui.separator();
+            let mut fits = false;
+            match &state.file {
+                None => {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::Spinner::new());
+                        ui.label("Reading file…");
+                    });
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(NOTICE_ERROR_TEXT, format!("Cannot preview: {e}"));
+                    fits = true;
+                }
+                Some(Ok(file)) => {
+                    let used = layout.data_bytes().and_then(|n| n.checked_add(layout.header_bytes));
+                    let file_bytes = file.len() as u64;
+                    match used {
+                        Some(used) if used <= file_bytes => {
+                            fits = true;
+                            if used < file_bytes {
+                                ui.weak(format!("{} trailing bytes are ignored", file_bytes - used));
+                            }
+                        }
+                        _ => {}
+                    }
+                    match &state.preview {
+                        Some((_, Ok(texture))) => {
+                            let size = texture.size_vec2();
+                            let scale = PREVIEW_SIZE as f32 / size.max_elem();
+                            ui.add(egui::Image::new(texture).fit_to_exact_size(size * scale));
+                        }
+                        Some((_, Err(e))) => {
+                            ui.colored_label(NOTICE_ERROR_TEXT, e);
+                        }
+                        None => {}
+                    }
+                }
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                confirmed = ui.add_enabled(fits, egui::Button::new("Open")).clicked();
+                cancelled = ui.button("Cancel").clicked();
+            });
+        });
+
+    if state.file.is_none() || state.update_preview(ctx) {
+        ctx.request_repaint();
+    }
+    if confirmed {
+        let state = dialog.take()?;
+        return Some((state.path, state.layout));
+    }
+    if cancelled || !open {
+        *dialog = None;
+    }
+    None
+}
@@ -0,0 +1,79 @@
+use eframe::egui;
+
+use crate::ui::view_recording::RecordingOptions;
+
+pub struct RecordViewDialog {
+    options: RecordingOptions,
+    /// Size of the viewport in device pixels, which sets the frame size.
+    viewport_size: (u32, u32),
+}
+
+impl RecordViewDialog {
+    pub fn new(options: RecordingOptions, viewport_size: (u32, u32)) -> Self {
+        Self { options, viewport_size }
+    }
+}
+
+/// Ask for the frame rate, size and length of a viewport recording. Returns the options once confirmed.
+pub fn show_record_view_dialog(
+    ctx: &egui::Context,
+    dialog: &mut Option<RecordViewDialog>,
+    toggle_shortcut: &str,
+) -> Option<RecordingOptions> {
+    let state = dialog.as_mut()?;
+    let mut open = true;
+    let mut confirmed = false;
+    let mut cancelled = false;
+
+    egui::Window::new("Record View")
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .collapsible(false)
+        .resizable(false)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            let options = &mut state.options;
+            egui::Grid::new("record_view_grid").num_columns(2).show(ui, |ui| {
+                ui.label("Frame rate");
+                ui.add(egui::DragValue::new(&mut options.fps).range(1..=30).suffix(" fps"));
+                ui.end_row();
+
+                ui.label("Resolution");
+                ui.add(egui::DragValue::new(&mut options.scale_percent).range(10..=100).suffix(" %"));
+                ui.end_row();
+
+                ui.label("Length");
+                ui.horizontal(|ui| {
+                    let mut until_stopped = options.duration_secs == 0;
+                    if ui.checkbox(&mut until_stopped, "Until stopped").changed() {
+                        options.duration_secs = if until_stopped {
+                            0
+                        } else {
+                            RecordingOptions::default().duration_secs
+                        };
+                    }
+                    if !until_stopped {
+                        ui.add(egui::DragValue::new(&mut options.duration_secs).range(1..=60).suffix(" s"));
+                    }
+                });
+                ui.end_row();
+            });
+
+            let (width, height) = options.frame_size(state.viewport_size.0, state.viewport_size.1);
+            ui.weak(format!("{width}×{height} GIF of the image view, without overlays."));
+            ui.weak(format!("Stop early with {toggle_shortcut}."));
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                confirmed = ui.button("Record...").clicked();
+                cancelled = ui.button("Cancel").clicked();
+            });
+        });
+
+    if confirmed {
+        return dialog.take().map(|state| state.options);
+    }
+    if cancelled || !open {
+        *dialog = None;
+    }
+    None
+}
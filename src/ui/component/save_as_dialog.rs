@@ -0,0 +1,99 @@
+use eframe::egui;
+
+use crate::model::{PixelType, SampleDepth, SaveAsOptions, SaveFormat};
+
+pub struct SaveAsDialog {
+    options: SaveAsOptions,
+    /// Pixel type of the image that will be saved.
+    dtype: PixelType,
+    /// Size in pixels of the image or selection that will be saved.
+    size: (u32, u32),
+    is_selection: bool,
+}
+
+impl SaveAsDialog {
+    pub fn new(options: SaveAsOptions, dtype: PixelType, size: (u32, u32), is_selection: bool) -> Self {
+        Self {
+            options,
+            dtype,
+            size,
+            is_selection,
+        }
+    }
+}
+
+/// Ask for the format and sample depth of a Save As. Returns the options once the user confirms them.
+pub fn show_save_as_dialog(ctx: &egui::Context, dialog: &mut Option<SaveAsDialog>) -> Option<SaveAsOptions> {
+    let state = dialog.as_mut()?;
+    let mut open = true;
+    let mut confirmed = false;
+    let mut cancelled = false;
+
+    egui::Window::new("Save As")
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .collapsible(false)
+        .resizable(false)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            let options = &mut state.options;
+            egui::Grid::new("save_as_grid").num_columns(2).show(ui, |ui| {
+                ui.label("Format");
+                egui::ComboBox::from_id_salt("save_as_format")
+                    .selected_text(options.format.label())
+                    .show_ui(ui, |ui| {
+                        for format in SaveFormat::ALL {
+                            ui.selectable_value(&mut options.format, format, format.label());
+                        }
+                    });
+                ui.end_row();
+
+                let depths = options.format.depths();
+                if options.depth.is_some_and(|depth| !depths.contains(&depth)) {
+                    options.depth = None;
+                }
+                ui.label("Bit depth");
+                let same_depth = options.format.resolve_depth(SampleDepth::of(state.dtype));
+                let same_label = format!("Same as image ({})", same_depth.label());
+                let selected = options
+                    .depth
+                    .map_or_else(|| same_label.clone(), |depth| depth.label().to_owned());
+                egui::ComboBox::from_id_salt("save_as_depth")
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut options.depth, None, same_label.as_str());
+                        for &depth in depths {
+                            ui.selectable_value(&mut options.depth, Some(depth), depth.label());
+                        }
+                    });
+                ui.end_row();
+
+                if options.format == SaveFormat::Jpeg {
+                    ui.label("Quality");
+                    ui.add(egui::Slider::new(&mut options.jpeg_quality, 1..=100));
+                    ui.end_row();
+                }
+            });
+
+            ui.separator();
+            let region = if state.is_selection { "Selection" } else { "Image" };
+            ui.weak(format!(
+                "{region}: {}×{} px, {} samples",
+                state.size.0,
+                state.size.1,
+                state.dtype.name()
+            ));
+            ui.separator();
+            ui.horizontal(|ui| {
+                confirmed = ui.button("Save...").clicked();
+                cancelled = ui.button("Cancel").clicked();
+            });
+        });
+
+    if confirmed {
+        return dialog.take().map(|state| state.options);
+    }
+    if cancelled || !open {
+        *dialog = None;
+    }
+    None
+}
@@ -0,0 +1,99 @@
+use eframe::egui;
+
+use crate::res::NOTICE_ERROR_TEXT;
+
+#[derive(Default)]
+pub struct ScriptConsoleActions {
+    pub run: bool,
+    pub stop: bool,
+    pub open_file: bool,
+    pub save_file: bool,
+    pub clear_output: bool,
+}
+
+/// Console output line; errors are drawn in the error color.
+pub struct ScriptConsoleLine {
+    pub text: String,
+    pub is_error: bool,
+}
+
+pub fn show_script_console(
+    ctx: &egui::Context,
+    open: &mut bool,
+    source: &mut String,
+    output: &[ScriptConsoleLine],
+    running: bool,
+    run_shortcut_label: &str,
+) -> ScriptConsoleActions {
+    if !*open {
+        return ScriptConsoleActions::default();
+    }
+
+    let mut actions = ScriptConsoleActions::default();
+
+    egui::Window::new("Script Console")
+        .default_pos(egui::pos2(320.0, 120.0))
+        .collapsible(false)
+        .resizable(true)
+        .default_size(egui::vec2(520.0, 480.0))
+        .open(open)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if running {
+                    actions.stop = ui.button("Stop").clicked();
+                } else {
+                    actions.run = ui
+                        .button("Run")
+                        .on_hover_text(format!("Run the script ({run_shortcut_label})"))
+                        .clicked();
+                }
+                actions.open_file = ui.add_enabled(!running, egui::Button::new("Open...")).clicked();
+                actions.save_file = ui.button("Save As...").clicked();
+                if running {
+                    ui.spinner();
+                }
+            });
+            ui.weak("Lua 5.4 with an `edolview` table: open, assets, set_primary, set_secondary, compare, selection, select, get, set, export.");
+
+            let editor = egui::ScrollArea::vertical()
+                .id_salt("script_source")
+                .max_height(ui.available_height() * 0.6)
+                .show(ui, |ui| {
+                    ui.add(
+                        egui::TextEdit::multiline(source)
+                            .code_editor()
+                            .desired_rows(12)
+                            .desired_width(f32::INFINITY),
+                    )
+                })
+                .inner;
+            if editor.has_focus()
+                && ui.input_mut(|i| i.consume_shortcut(&crate::res::SCRIPT_RUN))
+                && !running
+            {
+                actions.run = true;
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Output");
+                actions.clear_output = ui.add_enabled(!output.is_empty(), egui::Button::new("Clear")).clicked();
+            });
+            egui::ScrollArea::vertical()
+                .id_salt("script_output")
+                .auto_shrink([false, false])
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for line in output {
+                        let text = egui::RichText::new(&line.text).monospace();
+                        if line.is_error {
+                            ui.label(text.color(NOTICE_ERROR_TEXT));
+                        } else {
+                            ui.label(text);
+                        }
+                    }
+                });
+        });
+
+    actions
+}
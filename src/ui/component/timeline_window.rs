@@ -0,0 +1,112 @@
+use eframe::egui;
+
+use crate::{
+    model::{ImageData, Timeline, TimelineSource, TimelineStatistic},
+    ui::component::{draw_multi_line_plot, ExportAction},
+    util::series::SeriesRef,
+};
+
+#[derive(Default)]
+pub struct TimelineWindow {
+    pub open: bool,
+    pub source: TimelineSource,
+    pub statistic: TimelineStatistic,
+    /// Name and pixels of the image PSNR is measured against.
+    pub reference: Option<(String, ImageData)>,
+    pub timeline: Option<Timeline>,
+    /// Frames measured so far and in total while a computation runs.
+    pub progress: Option<(usize, usize)>,
+}
+
+#[derive(Default)]
+pub struct TimelineWindowActions {
+    pub compute: bool,
+    pub cancel: bool,
+    pub use_shown_as_reference: bool,
+    pub export: Option<ExportAction>,
+}
+
+/// Statistic of `target` over the frames of the chosen sequence, plotted once computed. `frame_count` is the number
+/// of frames the chosen source has now.
+pub fn show_timeline_window(
+    ctx: &egui::Context,
+    window: &mut TimelineWindow,
+    frame_count: usize,
+    target: &str,
+) -> TimelineWindowActions {
+    let mut actions = TimelineWindowActions::default();
+    if !window.open {
+        return actions;
+    }
+
+    let mut open = true;
+    egui::Window::new("Timeline")
+        .default_pos(egui::pos2(320.0, 200.0))
+        .collapsible(false)
+        .resizable(true)
+        .default_size(egui::vec2(480.0, 300.0))
+        .open(&mut open)
+        .show(ctx, |ui| {
+            egui::Grid::new("timeline_settings_grid").num_columns(2).show(ui, |ui| {
+                ui.label("Frames");
+                egui::ComboBox::from_id_salt("timeline_source")
+                    .selected_text(window.source.label())
+                    .show_ui(ui, |ui| {
+                        for source in TimelineSource::ALL {
+                            ui.selectable_value(&mut window.source, source, source.label());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Statistic");
+                egui::ComboBox::from_id_salt("timeline_statistic")
+                    .selected_text(window.statistic.label())
+                    .show_ui(ui, |ui| {
+                        for statistic in TimelineStatistic::ALL {
+                            ui.selectable_value(&mut window.statistic, statistic, statistic.label());
+                        }
+                    });
+                ui.end_row();
+
+                if window.statistic == TimelineStatistic::Psnr {
+                    ui.label("Reference");
+                    ui.horizontal(|ui| {
+                        match &window.reference {
+                            Some((name, _)) => ui.label(name),
+                            None => ui.weak("None"),
+                        };
+                        actions.use_shown_as_reference = ui.button("Use shown image").clicked();
+                    });
+                    ui.end_row();
+                }
+            });
+            ui.weak(format!("{target}, {frame_count} frames"));
+
+            ui.horizontal(|ui| match window.progress {
+                Some((done, total)) => {
+                    ui.spinner();
+                    ui.label(format!("{done} / {total}"));
+                    actions.cancel = ui.button("Cancel").clicked();
+                }
+                None => {
+                    actions.compute = ui.add_enabled(frame_count > 0, egui::Button::new("Compute")).clicked();
+                    if let Some(timeline) = window.timeline.as_ref().filter(|timeline| timeline.skipped > 0) {
+                        ui.weak(format!("{} frames skipped", timeline.skipped))
+                            .on_hover_text("Frames that failed to load or do not match the region or reference");
+                    }
+                }
+            });
+            ui.separator();
+
+            if let Some(timeline) = &window.timeline {
+                let series: Vec<&[f64]> = timeline.series.iter().map(Vec::as_slice).collect();
+                let mask = vec![true; series.len()];
+                let size = egui::vec2(ui.available_width(), ui.available_height().max(120.0));
+                actions.export =
+                    draw_multi_line_plot(ui, size, SeriesRef::new(&series), &mask, timeline.value_scale, "frame", 0);
+            }
+        });
+    window.open = open;
+
+    actions
+}
@@ -0,0 +1,126 @@
+use std::path::PathBuf;
+
+use eframe::egui;
+
+use crate::model::{YuvFormat, YuvFrame};
+
+/// Frame sizes tried, largest first, when guessing the layout of a headerless `.yuv` file from its length.
+const COMMON_FRAME_SIZES: [(u32, u32); 8] = [
+    (3840, 2160),
+    (2560, 1440),
+    (1920, 1080),
+    (1280, 720),
+    (640, 480),
+    (352, 288),
+    (320, 240),
+    (176, 144),
+];
+
+pub struct YuvFrameDialog {
+    path: PathBuf,
+    /// Zero when the length is unknown, e.g. for archive entries.
+    file_bytes: u64,
+    width: u32,
+    height: u32,
+    format: YuvFormat,
+}
+
+impl YuvFrameDialog {
+    pub fn new(path: PathBuf) -> Self {
+        let file_bytes = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let guess = COMMON_FRAME_SIZES
+            .iter()
+            .flat_map(|&(width, height)| YuvFormat::ALL.map(|format| (width, height, format)))
+            .find(|&(width, height, format)| {
+                format
+                    .frame_bytes(width, height)
+                    .is_some_and(|bytes| file_bytes > 0 && file_bytes.is_multiple_of(bytes as u64))
+            });
+        let (width, height, format) = guess.unwrap_or((1920, 1080, YuvFormat::I420));
+        Self {
+            path,
+            file_bytes,
+            width,
+            height,
+            format,
+        }
+    }
+
+    fn frame(&self) -> YuvFrame {
+        YuvFrame {
+            width: self.width,
+            height: self.height,
+            format: self.format,
+        }
+    }
+}
+
+/// Ask for the frame size and layout of a `.yuv` file. Returns the choice once the user confirms it.
+pub fn show_yuv_frame_dialog(ctx: &egui::Context, dialog: &mut Option<YuvFrameDialog>) -> Option<(PathBuf, YuvFrame)> {
+    let state = dialog.as_mut()?;
+    let mut open = true;
+    let mut confirmed = false;
+    let mut cancelled = false;
+
+    let file_name = state
+        .path
+        .file_name()
+        .map_or_else(String::new, |n| n.to_string_lossy().to_string());
+    egui::Window::new("Open YUV Frame")
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .collapsible(false)
+        .resizable(false)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.label(file_name);
+            egui::Grid::new("yuv_frame_grid").num_columns(2).show(ui, |ui| {
+                ui.label("Size");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut state.width).range(1..=16384));
+                    ui.label("×");
+                    ui.add(egui::DragValue::new(&mut state.height).range(1..=16384));
+                });
+                ui.end_row();
+
+                ui.label("Format");
+                egui::ComboBox::from_id_salt("yuv_frame_format")
+                    .selected_text(state.format.label())
+                    .show_ui(ui, |ui| {
+                        for format in YuvFormat::ALL {
+                            ui.selectable_value(&mut state.format, format, format.label());
+                        }
+                    });
+                ui.end_row();
+            });
+
+            let frame_bytes = state.frame().bytes().unwrap_or(usize::MAX) as u64;
+            let fits = state.file_bytes == 0 || state.file_bytes >= frame_bytes;
+            if state.file_bytes > 0 {
+                let frames = state.file_bytes / frame_bytes;
+                let text = if !fits {
+                    format!("File is smaller than one frame ({frame_bytes} bytes)")
+                } else if state.file_bytes.is_multiple_of(frame_bytes) {
+                    format!("{frames} frame(s); the first is shown")
+                } else {
+                    format!("{frames} frame(s) with {} trailing bytes", state.file_bytes % frame_bytes)
+                };
+                ui.weak(text);
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                confirmed = ui.add_enabled(fits, egui::Button::new("Open")).clicked();
+                cancelled = ui.button("Cancel").clicked();
+            });
+        });
+
+    if confirmed {
+        let state = dialog.take()?;
+        let frame = state.frame();
+        return Some((state.path, frame));
+    }
+    if cancelled || !open {
+        *dialog = None;
+    }
+    None
+}
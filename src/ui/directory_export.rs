@@ -0,0 +1,296 @@
+//! Renders the images of a directory, one at a time, through the viewer's display settings into a GIF or video,
+//! or into a contact sheet.
+
+use std::{
+    path::PathBuf,
+    sync::{mpsc, Arc},
+};
+
+use color_eyre::eyre::{eyre, Result};
+use eframe::{egui, egui_wgpu};
+
+use crate::{
+    model::{
+        contact_sheet_tile_size, save_contact_sheet, ContactSheetTile, Image, ImageData, SequenceFormat, SequenceWriter,
+    },
+    ui::gpu::{GpuRenderer, ShaderParams},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SequenceOptions {
+    pub format: SequenceFormat,
+    pub fps: u32,
+    /// Output size relative to the current image.
+    pub scale_percent: u32,
+}
+
+impl Default for SequenceOptions {
+    fn default() -> Self {
+        Self {
+            format: SequenceFormat::Gif,
+            fps: 10,
+            scale_percent: 100,
+        }
+    }
+}
+
+impl SequenceOptions {
+    /// Frame size for an image of `width`×`height`; rounded down to even sizes for video.
+    pub fn frame_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let scaled = |v: u32| {
+            let v = ((v as u64 * self.scale_percent as u64 / 100) as u32).max(2);
+            match self.format {
+                SequenceFormat::Gif => v,
+                SequenceFormat::Mp4 => v & !1,
+            }
+        };
+        (scaled(width), scaled(height))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContactSheetOptions {
+    pub columns: u32,
+    /// Longest side of each thumbnail in pixels.
+    pub thumbnail_size: u32,
+}
+
+impl Default for ContactSheetOptions {
+    fn default() -> Self {
+        Self {
+            columns: 6,
+            thumbnail_size: 256,
+        }
+    }
+}
+
+enum Stage {
+    Loading(mpsc::Receiver<Result<ImageData>>),
+    Rendering(mpsc::Receiver<Result<Vec<u8>, String>>, (u32, u32)),
+    Finishing,
+}
+
+enum Target {
+    Sequence {
+        size: (u32, u32),
+        frames_tx: Option<mpsc::Sender<Vec<u8>>>,
+    },
+    ContactSheet {
+        options: ContactSheetOptions,
+        tiles: Vec<ContactSheetTile>,
+    },
+}
+
+pub struct DirectoryExport {
+    paths: Vec<PathBuf>,
+    output: PathBuf,
+    next: usize,
+    skipped: usize,
+    channel_index: i32,
+    shader: ShaderParams,
+    stage: Stage,
+    target: Target,
+    /// Result of writing the output file.
+    done_rx: Option<mpsc::Receiver<Result<()>>>,
+}
+
+impl DirectoryExport {
+    /// Start rendering `paths` into frames of `size`. The display settings are captured now so later edits do
+    /// not change frames halfway through.
+    pub fn sequence(
+        ctx: &egui::Context,
+        paths: Vec<PathBuf>,
+        output: PathBuf,
+        options: SequenceOptions,
+        size: (u32, u32),
+        display: (&ShaderParams, i32),
+    ) -> Result<Self> {
+        let mut writer = SequenceWriter::create(&output, options.format, size, options.fps)?;
+        let (frames_tx, frames_rx) = mpsc::channel::<Vec<u8>>();
+        let (done_tx, done_rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for rgba in frames_rx {
+                if let Err(e) = writer.write_frame(rgba) {
+                    let _ = done_tx.send(Err(e));
+                    return;
+                }
+            }
+            let _ = done_tx.send(writer.finish());
+        });
+        let target = Target::Sequence {
+            size,
+            frames_tx: Some(frames_tx),
+        };
+        Self::start(ctx, paths, output, display, target, Some(done_rx))
+    }
+
+    /// Start rendering `paths` into thumbnails, which are tiled and saved once all are done.
+    pub fn contact_sheet(
+        ctx: &egui::Context,
+        paths: Vec<PathBuf>,
+        output: PathBuf,
+        options: ContactSheetOptions,
+        display: (&ShaderParams, i32),
+    ) -> Result<Self> {
+        let target = Target::ContactSheet {
+            options,
+            tiles: Vec::with_capacity(paths.len()),
+        };
+        Self::start(ctx, paths, output, display, target, None)
+    }
+
+    fn start(
+        ctx: &egui::Context,
+        paths: Vec<PathBuf>,
+        output: PathBuf,
+        display: (&ShaderParams, i32),
+        target: Target,
+        done_rx: Option<mpsc::Receiver<Result<()>>>,
+    ) -> Result<Self> {
+        let first = paths.first().cloned().ok_or_else(|| eyre!("No images to export"))?;
+        Ok(Self {
+            paths,
+            output,
+            next: 0,
+            skipped: 0,
+            channel_index: display.1,
+            shader: display.0.clone(),
+            stage: Stage::Loading(spawn_load(ctx, first)),
+            target,
+            done_rx,
+        })
+    }
+
+    /// Images handled so far and in total.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.next, self.paths.len())
+    }
+
+    /// Advance the export. Returns a message once the file is written, or the error that stopped it.
+    pub fn update(&mut self, ctx: &egui::Context, render_state: &egui_wgpu::RenderState) -> Option<Result<String>> {
+        if let Some(Ok(result)) = self.done_rx.as_ref().map(mpsc::Receiver::try_recv) {
+            return Some(result.map(|()| self.finished_message()));
+        }
+
+        match &self.stage {
+            Stage::Loading(rx) => match rx.try_recv() {
+                Ok(Ok(image)) => {
+                    let spec = image.spec();
+                    let size = match &self.target {
+                        Target::Sequence { size, .. } => *size,
+                        Target::ContactSheet { options, .. } => {
+                            contact_sheet_tile_size(spec.width as u32, spec.height as u32, options.thumbnail_size)
+                        }
+                    };
+                    let (tx, rx) = mpsc::channel();
+                    let repaint_ctx = ctx.clone();
+                    let completion = Arc::new(move |rgba| {
+                        let _ = tx.send(rgba);
+                        repaint_ctx.request_repaint();
+                    });
+                    let egui_renderer = render_state.renderer.read();
+                    let Some(renderer) = egui_renderer.callback_resources.get::<GpuRenderer>() else {
+                        return Some(Err(eyre!("The image renderer is not available")));
+                    };
+                    if let Err(e) = renderer.render_image(
+                        &render_state.device,
+                        &render_state.queue,
+                        &image,
+                        size,
+                        self.channel_index,
+                        &self.shader,
+                        completion,
+                    ) {
+                        return Some(Err(e));
+                    }
+                    self.stage = Stage::Rendering(rx, size);
+                }
+                Ok(Err(e)) => {
+                    eprintln!("Skipping {} in directory export: {e}", self.paths[self.next].display());
+                    self.skipped += 1;
+                    self.advance(ctx);
+                }
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.skipped += 1;
+                    self.advance(ctx);
+                }
+            },
+            Stage::Rendering(rx, (width, height)) => match rx.try_recv() {
+                Ok(Ok(rgba)) => {
+                    match &mut self.target {
+                        Target::Sequence { frames_tx, .. } => {
+                            if let Some(frames_tx) = frames_tx {
+                                let _ = frames_tx.send(rgba);
+                            }
+                        }
+                        Target::ContactSheet { tiles, .. } => tiles.push(ContactSheetTile {
+                            name: self.paths[self.next]
+                                .file_name()
+                                .map_or_else(String::new, |name| name.to_string_lossy().to_string()),
+                            width: *width,
+                            height: *height,
+                            rgba,
+                        }),
+                    }
+                    self.advance(ctx);
+                }
+                Ok(Err(e)) => return Some(Err(eyre!(e))),
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => return Some(Err(eyre!("Frame rendering stopped"))),
+            },
+            Stage::Finishing => {}
+        }
+        None
+    }
+
+    fn advance(&mut self, ctx: &egui::Context) {
+        self.next += 1;
+        if let Some(path) = self.paths.get(self.next) {
+            self.stage = Stage::Loading(spawn_load(ctx, path.clone()));
+            ctx.request_repaint();
+            return;
+        }
+
+        self.stage = Stage::Finishing;
+        match &mut self.target {
+            // Closing the channel lets the writer finish the file.
+            Target::Sequence { frames_tx, .. } => *frames_tx = None,
+            Target::ContactSheet { options, tiles } => {
+                let (tiles, options, output) = (std::mem::take(tiles), *options, self.output.clone());
+                let (done_tx, done_rx) = mpsc::channel();
+                let repaint_ctx = ctx.clone();
+                std::thread::spawn(move || {
+                    let _ = done_tx.send(save_contact_sheet(&output, &tiles, options.columns, options.thumbnail_size));
+                    repaint_ctx.request_repaint();
+                });
+                self.done_rx = Some(done_rx);
+            }
+        }
+        ctx.request_repaint();
+    }
+
+    fn finished_message(&self) -> String {
+        let written = self.paths.len() - self.skipped;
+        let mut message = match self.target {
+            Target::Sequence { .. } => format!("Exported {written} frames to {}", self.output.display()),
+            Target::ContactSheet { .. } => {
+                format!("Saved contact sheet of {written} images to {}", self.output.display())
+            }
+        };
+        if self.skipped > 0 {
+            message.push_str(&format!(" ({} unreadable images skipped)", self.skipped));
+        }
+        message
+    }
+}
+
+fn spawn_load(ctx: &egui::Context, path: PathBuf) -> mpsc::Receiver<Result<ImageData>> {
+    let (tx, rx) = mpsc::channel();
+    let repaint_ctx = ctx.clone();
+    std::thread::spawn(move || {
+        let _ = tx.send(ImageData::load_from_path(&path));
+        repaint_ctx.request_repaint();
+    });
+    rx
+}
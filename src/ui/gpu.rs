@@ -1,20 +1,43 @@
-use std::{borrow::Cow, num::NonZeroU64, path::PathBuf, sync::Arc};
+use std::{
+    borrow::Cow,
+    num::NonZeroU64,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
 use bytemuck::{Pod, Zeroable};
 use color_eyre::eyre::{eyre, Result};
 use eframe::{
     egui::{self, Color32, Vec2},
-    egui_wgpu::{self, wgpu},
+    egui_wgpu::{
+        self,
+        wgpu::{self, util::DeviceExt as _},
+    },
 };
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    model::{GpuImageTexture, Image, MinMaxTotal},
+    model::{ColorLut3d, GpuImageTexture, Image, ImageSpec, LensDistortion, MinMaxTotal, GRID_MAX_PANES},
     util::path_ext::exe_dir_or_cwd,
 };
 
 const IMAGE_SHADER_CODE: &str = include_str!("gpu_image.frag");
-const PARAM_SLOT_COUNT: u64 = 3;
+const PARAM_SLOT_COUNT: u64 = HEATMAP_PARAM_SLOT as u64 + 1;
+pub const RAMP_COLOR_PARAM_SLOT: u32 = 3;
+pub const RAMP_LIGHTNESS_PARAM_SLOT: u32 = 4;
+/// Copies and saves of the viewed image, rewritten for every tile.
+const EXPORT_PARAM_SLOT: u32 = 2;
+/// Thumbnails and other renders of images that are not on screen.
+const OFFSCREEN_PARAM_SLOT: u32 = 5;
+/// First of the slots of the grid comparison panes, one per pane.
+pub const GRID_PARAM_SLOT: u32 = 6;
+/// The heatmap blended over the viewed image, shared by both panes of a split view.
+pub const HEATMAP_PARAM_SLOT: u32 = GRID_PARAM_SLOT + GRID_MAX_PANES as u32;
+/// Largest side of one export tile. Larger exports are rendered tile by tile, so no single texture or readback
+/// buffer has to hold the whole image.
+const EXPORT_TILE_SIZE: u32 = 4096;
+/// Largest export assembled on the CPU, in pixels (4 GiB of RGBA8).
+const MAX_EXPORT_PIXELS: u64 = 1 << 30;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum ScaleMode {
@@ -29,9 +52,125 @@ impl ScaleMode {
     pub fn auto_normalize_enabled(self) -> bool {
         matches!(self, Self::Linear | Self::Absolute)
     }
+
+    /// Map a pixel value the way `apply_scale_mode` in the image shader does before comparing it with the range.
+    pub fn apply(self, v: f32) -> f32 {
+        let sign = if v == 0.0 { 0.0 } else { v.signum() };
+        match self {
+            Self::Linear => v,
+            Self::Inverse => sign / (v.abs() + 1e-12),
+            Self::Log => sign * v.abs().ln_1p(),
+            Self::Absolute => v.abs(),
+        }
+    }
+}
+
+/// Simulated color vision deficiency applied after the colormap (Machado et al. 2009, full severity).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ColorVisionMode {
+    #[default]
+    Normal = 0,
+    Protanopia = 1,
+    Deuteranopia = 2,
+    Tritanopia = 3,
+}
+
+impl ColorVisionMode {
+    pub const ALL: [Self; 4] = [Self::Normal, Self::Protanopia, Self::Deuteranopia, Self::Tritanopia];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Normal => "Normal vision",
+            Self::Protanopia => "Protanopia",
+            Self::Deuteranopia => "Deuteranopia",
+            Self::Tritanopia => "Tritanopia",
+        }
+    }
+}
+
+/// How single-channel images holding raw Bayer sensor data are shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum CfaView {
+    #[default]
+    Off = 0,
+    /// Each sample tinted with the color of its filter.
+    Pattern = 1,
+    /// Missing colors averaged from same-color neighbors in the 3×3 window.
+    Demosaic = 2,
+}
+
+impl CfaView {
+    pub const ALL: [Self; 3] = [Self::Off, Self::Pattern, Self::Demosaic];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::Pattern => "CFA colors",
+            Self::Demosaic => "Bilinear demosaic",
+        }
+    }
+}
+
+/// Filter colors of the top-left 2×2 block, row by row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum CfaPattern {
+    #[default]
+    Rggb,
+    Bggr,
+    Grbg,
+    Gbrg,
+}
+
+impl CfaPattern {
+    pub const ALL: [Self; 4] = [Self::Rggb, Self::Bggr, Self::Grbg, Self::Gbrg];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Rggb => "RGGB",
+            Self::Bggr => "BGGR",
+            Self::Grbg => "GRBG",
+            Self::Gbrg => "GBRG",
+        }
+    }
+
+    /// Position of the red sample within the 2×2 block; blue sits on the opposite diagonal.
+    fn red_offset(self) -> [u32; 2] {
+        match self {
+            Self::Rggb => [0, 0],
+            Self::Bggr => [1, 1],
+            Self::Grbg => [1, 0],
+            Self::Gbrg => [0, 1],
+        }
+    }
+}
+
+/// Resampling used when a screen pixel covers less than one image pixel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum MagnificationFilter {
+    /// Hard pixel edges for pixel-accurate inspection.
+    #[default]
+    Nearest = 0,
+    Linear = 1,
+    /// Catmull-Rom over a 4×4 neighborhood.
+    Bicubic = 2,
+    /// Lanczos-3 over a 6×6 neighborhood.
+    Lanczos = 3,
+}
+
+impl MagnificationFilter {
+    pub const ALL: [Self; 4] = [Self::Nearest, Self::Linear, Self::Bicubic, Self::Lanczos];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Nearest => "Nearest",
+            Self::Linear => "Linear",
+            Self::Bicubic => "Bicubic",
+            Self::Lanczos => "Lanczos",
+        }
+    }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ShaderParams {
     pub use_alpha: bool,
     pub offset: f32,
@@ -46,6 +185,81 @@ pub struct ShaderParams {
     pub max_v_channels: [f32; 4],
     pub auto_minmax_channels: [bool; 4],
     pub scale_mode_channels: [ScaleMode; 4],
+    #[serde(default)]
+    pub color_vision: ColorVisionMode,
+    /// Render only the CIE L* lightness of the colormapped result to check perceptual uniformity.
+    #[serde(default)]
+    pub lightness_only: bool,
+    /// Blend between the original color (0) and the look LUT result (1).
+    #[serde(default = "default_lut_strength")]
+    pub lut_strength: f32,
+    /// Show 2-channel images that their file declares as gray+alpha as luminance with alpha instead of red and
+    /// green. Other 2-channel data such as optical flow always shows as red and green.
+    #[serde(default = "default_gray_alpha")]
+    pub gray_alpha: bool,
+    /// Bayer visualization of single-channel images. Samples are read per pixel, so the magnification filter
+    /// does not apply.
+    #[serde(default)]
+    pub cfa_view: CfaView,
+    #[serde(default)]
+    pub cfa_pattern: CfaPattern,
+    /// Round the displayed values to this many bits per channel to preview an integer export; 0 is off.
+    #[serde(default)]
+    pub quantize_bits: u8,
+    /// Add noise of one quantization step before rounding, as exporters that dither do.
+    #[serde(default)]
+    pub quantize_dither: bool,
+    /// Per-channel RGB gains applied to the stored values before the value mapping, to judge color casts.
+    #[serde(default)]
+    pub white_balance: Option<[f32; 3]>,
+    /// Show the image undistorted with these intrinsics and coefficients, to check a lens calibration.
+    #[serde(default)]
+    pub undistort: Option<LensDistortion>,
+}
+
+fn default_lut_strength() -> f32 {
+    1.0
+}
+
+fn default_gray_alpha() -> bool {
+    true
+}
+
+impl ShaderParams {
+    /// True when an image shown with `channel_index` is drawn as gray plus alpha through the mono colormap.
+    pub fn shows_gray_alpha(&self, spec: &ImageSpec, channel_index: i32) -> bool {
+        self.gray_alpha && spec.gray_alpha && spec.channels == 2 && channel_index == -1
+    }
+
+    pub fn shows_cfa(&self, spec: &ImageSpec) -> bool {
+        self.cfa_view != CfaView::Off && spec.channels == 1
+    }
+
+    /// True when the image goes through the mono colormap rather than the RGB one.
+    pub fn is_mono(&self, spec: &ImageSpec, channel_index: i32) -> bool {
+        (channel_index != -1 || spec.channels == 1 || self.shows_gray_alpha(spec, channel_index))
+            && !self.shows_cfa(spec)
+    }
+
+    /// These parameters without the calibration of one particular image, its white balance, lens undistortion
+    /// and Bayer pattern, which are reset to their defaults. This is what carries over to the next session.
+    pub fn view_params(&self) -> Self {
+        let defaults = Self::default();
+        Self {
+            white_balance: defaults.white_balance,
+            undistort: defaults.undistort,
+            cfa_pattern: defaults.cfa_pattern,
+            ..self.clone()
+        }
+    }
+
+    /// Largest code value of the simulated integer format, or 0 when quantization is off.
+    fn quantize_levels(&self) -> f32 {
+        match self.quantize_bits {
+            0 => 0.0,
+            bits => ((1u32 << bits.min(16)) - 1) as f32,
+        }
+    }
 }
 
 impl Default for ShaderParams {
@@ -64,10 +278,36 @@ impl Default for ShaderParams {
             max_v_channels: [1.0; 4],
             auto_minmax_channels: [false; 4],
             scale_mode_channels: [ScaleMode::Linear; 4],
+            color_vision: ColorVisionMode::Normal,
+            lightness_only: false,
+            lut_strength: default_lut_strength(),
+            gray_alpha: default_gray_alpha(),
+            cfa_view: CfaView::Off,
+            cfa_pattern: CfaPattern::Rggb,
+            quantize_bits: 0,
+            quantize_dither: false,
+            white_balance: None,
+            undistort: None,
         }
     }
 }
 
+/// Display parameters for thumbnails of images that were not shown yet. Images with values outside `[0, 1]` are
+/// log-scaled over their own range so HDR and high bit-depth data stays legible at a glance.
+pub fn thumbnail_shader_params(min_max: &MinMaxTotal) -> ShaderParams {
+    let (min, max) = (min_max.total_min(), min_max.total_max());
+    if (min >= 0.0 && max <= 1.0) || max <= min || !min.is_finite() || !max.is_finite() {
+        return ShaderParams::default();
+    }
+    let log_scale = |v: f32| v.signum() * (v.abs() + 1.0).ln();
+    ShaderParams {
+        min_v: log_scale(min),
+        max_v: log_scale(max),
+        scale_mode: ScaleMode::Log,
+        ..ShaderParams::default()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct MinMaxOverlay {
     pub enabled: bool,
@@ -97,6 +337,24 @@ impl Default for MinMaxOverlay {
     }
 }
 
+/// Colors and cell size of the backdrop drawn behind the image. A solid backdrop uses the same color twice.
+#[derive(Clone, Copy, Debug)]
+pub struct BackgroundStyle {
+    pub color_a: Color32,
+    pub color_b: Color32,
+    pub checker_size: f32,
+}
+
+impl BackgroundStyle {
+    pub fn solid(color: Color32) -> Self {
+        Self {
+            color_a: color,
+            color_b: color,
+            checker_size: 1.0,
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
 struct GpuParams {
@@ -116,6 +374,26 @@ struct GpuParams {
     background_color_a: [f32; 4],
     background_color_b: [f32; 4],
     background: [f32; 4],
+    /// x: color vision mode, y: lightness only, z: colormap ramp, w: magnification filter.
+    display: [f32; 4],
+    /// x: look LUT enabled, y: look LUT strength, z: output LUT enabled.
+    lut: [f32; 4],
+    /// x: channel 0 is luminance and channel 1 is alpha, y: [`CfaView`], zw: red sample offset of the CFA.
+    image_layout: [f32; 4],
+    /// x: quantization levels minus one (0 is off), y: dither.
+    quantize: [f32; 4],
+    /// xyz: RGB white balance gains, w: enabled.
+    white_balance: [f32; 4],
+    /// x: opacity the drawn image is blended over what is below it with.
+    layer: [f32; 4],
+    /// xy: focal length, zw: principal point of the undistort preview, in pixels.
+    lens_intrinsics: [f32; 4],
+    /// k1, k2, p1, p2 of the undistort preview.
+    lens_distortion: [f32; 4],
+    /// x: k3, y: undistort enabled.
+    lens_options: [f32; 4],
+    /// xyz: linear RGB gains of the proofed display, w: enabled.
+    proofing: [f32; 4],
 }
 
 impl GpuParams {
@@ -129,8 +407,7 @@ impl GpuParams {
         position: Vec2,
         shader: &ShaderParams,
         overlay: &MinMaxOverlay,
-        background_a: Color32,
-        background_b: Color32,
+        background: &BackgroundStyle,
     ) -> Self {
         let mut min_values = shader.min_v_channels;
         let mut max_values = shader.max_v_channels;
@@ -186,11 +463,82 @@ impl GpuParams {
             overlay_scope: overlay.scope_rect.map(|value| value as f32),
             overlay_min_values: overlay.min_values,
             overlay_max_values: overlay.max_values,
-            background_color_a: color_to_linear_f32(background_a),
-            background_color_b: color_to_linear_f32(background_b),
-            background: [16.0, 0.0, 0.0, 0.0],
+            background_color_a: color_to_linear_f32(background.color_a),
+            background_color_b: color_to_linear_f32(background.color_b),
+            background: [background.checker_size.max(1.0), 0.0, 0.0, 0.0],
+            display: [
+                shader.color_vision as i32 as f32,
+                shader.lightness_only as u8 as f32,
+                0.0,
+                0.0,
+            ],
+            lut: [0.0, shader.lut_strength, 0.0, 0.0],
+            image_layout: [0.0; 4],
+            quantize: [shader.quantize_levels(), shader.quantize_dither as u8 as f32, 0.0, 0.0],
+            white_balance: [1.0, 1.0, 1.0, 0.0],
+            layer: [1.0, 0.0, 0.0, 0.0],
+            lens_intrinsics: [1.0, 1.0, 0.0, 0.0],
+            lens_distortion: [0.0; 4],
+            lens_options: [0.0; 4],
+            proofing: [1.0, 1.0, 1.0, 0.0],
+        }
+    }
+
+    fn set_image_layout(&mut self, shader: &ShaderParams, spec: &ImageSpec, channel_index: i32) {
+        let [red_x, red_y] = shader.cfa_pattern.red_offset();
+        let cfa_view = if shader.shows_cfa(spec) {
+            shader.cfa_view
+        } else {
+            CfaView::Off
+        };
+        self.image_layout = [
+            shader.shows_gray_alpha(spec, channel_index) as u8 as f32,
+            cfa_view as i32 as f32,
+            red_x as f32,
+            red_y as f32,
+        ];
+        self.white_balance = match shader.white_balance {
+            Some([r, g, b]) if !shader.is_mono(spec, channel_index) => [r, g, b, 1.0],
+            _ => [1.0, 1.0, 1.0, 0.0],
+        };
+        self.lens_options[1] = 0.0;
+        if let Some(lens) = shader.undistort.filter(LensDistortion::is_valid) {
+            self.lens_intrinsics = [lens.fx, lens.fy, lens.cx, lens.cy];
+            self.lens_distortion = [lens.k1, lens.k2, lens.p1, lens.p2];
+            self.lens_options = [lens.k3, 1.0, 0.0, 0.0];
         }
     }
+
+    fn set_proofing(&mut self, gains: Option<[f32; 3]>) {
+        self.proofing = match gains {
+            Some([r, g, b]) => [r, g, b, 1.0],
+            None => [1.0, 1.0, 1.0, 0.0],
+        };
+    }
+
+    /// Parameters for drawing a 0..1 ramp through the current colormap instead of the image texture.
+    fn colormap_ramp(size: Vec2, shader: &ShaderParams, lightness_only: bool) -> Self {
+        let ramp_shader = ShaderParams {
+            use_alpha: false,
+            color_vision: shader.color_vision,
+            lightness_only,
+            lut_strength: shader.lut_strength,
+            ..ShaderParams::default()
+        };
+        let mut params = Self::image(
+            size,
+            size,
+            -1,
+            &crate::model::empty_minmax(),
+            1.0,
+            Vec2::ZERO,
+            &ramp_shader,
+            &MinMaxOverlay::default(),
+            &BackgroundStyle::solid(Color32::TRANSPARENT),
+        );
+        params.display[2] = 1.0;
+        params
+    }
 }
 
 fn color_to_linear_f32(color: Color32) -> [f32; 4] {
@@ -202,6 +550,10 @@ fn color_to_linear_f32(color: Color32) -> [f32; 4] {
 pub enum ImageSlot {
     Primary,
     Secondary,
+    /// Pane of the grid comparison, `0..GRID_MAX_PANES`.
+    Grid(usize),
+    /// Heatmap blended over the image panes through its own colormap.
+    Heatmap,
 }
 
 #[derive(Clone, Copy)]
@@ -209,6 +561,8 @@ pub struct PaneDraw {
     pub viewport_px: egui::Rect,
     pub slot: ImageSlot,
     pub uniform_slot: u32,
+    /// Zoom used to pick a downsampled pyramid level; `None` always samples the full-resolution texture.
+    pub pyramid_zoom: Option<f32>,
 }
 
 pub type ExportCompletion = Arc<dyn Fn(std::result::Result<Vec<u8>, String>) + Send + Sync>;
@@ -218,12 +572,20 @@ pub struct ExportRequest {
     pub width: u32,
     pub height: u32,
     pub slot: ImageSlot,
+    pub params: ExportParams,
     pub completion: ExportCompletion,
 }
 
+/// Display parameters of an export from [`GpuRenderer::export_params`], kept on the CPU so each tile can be
+/// rendered with its own offset.
+#[derive(Clone, Copy)]
+pub struct ExportParams(GpuParams);
+
 #[derive(Clone)]
 pub struct ImagePaintCallback {
     pub panes: Vec<PaneDraw>,
+    /// Colormap ramp strips drawn on top of the image panes without a background.
+    pub ramp_panes: Vec<PaneDraw>,
     pub show_background: bool,
     pub export: Option<ExportRequest>,
 }
@@ -237,16 +599,14 @@ impl egui_wgpu::CallbackTrait for ImagePaintCallback {
         _encoder: &mut wgpu::CommandEncoder,
         resources: &mut egui_wgpu::CallbackResources,
     ) -> Vec<wgpu::CommandBuffer> {
-        if let (Some(export), Some(renderer)) = (self.export.as_ref(), resources.get_mut::<GpuRenderer>()) {
-            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("edolview export encoder"),
-            });
-            match renderer.encode_export(device, &mut encoder, export) {
-                Ok(readback) => {
-                    queue.submit([encoder.finish()]);
-                    readback.map();
-                }
-                Err(error) => (export.completion)(Err(error.to_string())),
+        if let Some(export) = self.export.as_ref() {
+            let result = match resources.get::<GpuRenderer>() {
+                Some(renderer) => renderer.render_export(device, queue, export),
+                None => Err(eyre!("The GPU renderer is not available")),
+            };
+            if let Err(error) = result {
+                eprintln!("Failed to render export: {error}");
+                (export.completion)(Err(format!("Failed to render export: {error}")));
             }
         }
         Vec::new()
@@ -260,6 +620,7 @@ impl egui_wgpu::CallbackTrait for ImagePaintCallback {
     ) {
         if let Some(renderer) = resources.get::<GpuRenderer>() {
             renderer.paint(pass, &self.panes, self.show_background);
+            renderer.paint(pass, &self.ramp_panes, false);
         }
     }
 }
@@ -267,9 +628,33 @@ impl egui_wgpu::CallbackTrait for ImagePaintCallback {
 struct GpuImage {
     _texture: Arc<GpuImageTexture>,
     bind_group: wgpu::BindGroup,
+    /// Pyramid levels as `(factor, bind group)`, finest first.
+    levels: Vec<(u32, wgpu::BindGroup)>,
     image_id: u64,
 }
 
+impl GpuImage {
+    /// The coarsest level that still has at least one texel per output pixel at `zoom`.
+    fn bind_group_for(&self, zoom: Option<f32>) -> &wgpu::BindGroup {
+        let Some(zoom) = zoom else {
+            return &self.bind_group;
+        };
+        self.levels
+            .iter()
+            .rev()
+            .find(|(factor, _)| zoom * *factor as f32 <= 1.0)
+            .map_or(&self.bind_group, |(_, bind_group)| bind_group)
+    }
+}
+
+/// 3D LUTs applied to the final color: a look LUT (e.g. a grading `.cube`) followed by the monitor output transform.
+struct GpuDisplayLuts {
+    _textures: [wgpu::Texture; 2],
+    bind_group: wgpu::BindGroup,
+    look: Option<Arc<ColorLut3d>>,
+    output: Option<Arc<ColorLut3d>>,
+}
+
 struct ExportReadback {
     buffer: Arc<wgpu::Buffer>,
     row_bytes: u32,
@@ -303,24 +688,116 @@ impl ExportReadback {
     }
 }
 
+/// Tiles of one export gathered into the full image. The completion runs once, when the last tile arrives or
+/// when the first one fails.
+struct TileAssembly {
+    pixels: Vec<u8>,
+    width: u32,
+    remaining: usize,
+    completion: Option<ExportCompletion>,
+}
+
+impl TileAssembly {
+    fn new(width: u32, height: u32, tiles: usize, completion: ExportCompletion) -> Result<Arc<Mutex<Self>>> {
+        let len = width as usize * height as usize * 4;
+        let mut pixels = Vec::new();
+        pixels
+            .try_reserve_exact(len)
+            .map_err(|_| eyre!("Not enough memory for a {width}x{height} export"))?;
+        pixels.resize(len, 0);
+        Ok(Arc::new(Mutex::new(Self {
+            pixels,
+            width,
+            remaining: tiles,
+            completion: Some(completion),
+        })))
+    }
+
+    /// Completion of the `tile_width` wide tile at `(x, y)`, copying its rows into place.
+    fn tile_completion(assembly: &Arc<Mutex<Self>>, x: u32, y: u32, tile_width: u32) -> ExportCompletion {
+        let assembly = Arc::clone(assembly);
+        Arc::new(move |result| {
+            let mut assembly = assembly.lock().unwrap();
+            let Some(completion) = assembly.completion.clone() else {
+                return;
+            };
+            let result = match result {
+                Ok(tile) => {
+                    let row_bytes = tile_width as usize * 4;
+                    let full_row_bytes = assembly.width as usize * 4;
+                    for (index, row) in tile.chunks_exact(row_bytes).enumerate() {
+                        let start = (y as usize + index) * full_row_bytes + x as usize * 4;
+                        assembly.pixels[start..start + row_bytes].copy_from_slice(row);
+                    }
+                    assembly.remaining -= 1;
+                    if assembly.remaining > 0 {
+                        return;
+                    }
+                    Ok(std::mem::take(&mut assembly.pixels))
+                }
+                Err(error) => Err(error),
+            };
+            assembly.abandon();
+            drop(assembly);
+            completion(result);
+        })
+    }
+
+    /// Drop the pixels and ignore the remaining tiles.
+    fn abandon(&mut self) {
+        self.completion = None;
+        self.pixels = Vec::new();
+    }
+}
+
+/// Tiles covering `width`×`height` as `(x, y, width, height)`, row by row.
+fn export_tiles(width: u32, height: u32, tile_size: u32) -> Vec<(u32, u32, u32, u32)> {
+    let tile_size = tile_size.max(1) as usize;
+    (0..height)
+        .step_by(tile_size)
+        .flat_map(|y| {
+            (0..width)
+                .step_by(tile_size)
+                .map(move |x| (x, y, (width - x).min(tile_size as u32), (height - y).min(tile_size as u32)))
+        })
+        .collect()
+}
+
 pub struct GpuRenderer {
     target_format: wgpu::TextureFormat,
     bind_group_layout: wgpu::BindGroupLayout,
+    lut_bind_group_layout: wgpu::BindGroupLayout,
     pipeline_layout: wgpu::PipelineLayout,
     uniform_buffer: wgpu::Buffer,
     uniform_stride: u64,
     background_pipeline: wgpu::RenderPipeline,
     image_pipeline: wgpu::RenderPipeline,
     export_pipeline: wgpu::RenderPipeline,
+    /// Thumbnails ignore the user's colormap: RGB images use the plain RGB map, single-channel images gray.
+    thumbnail_pipelines: [wgpu::RenderPipeline; 2],
     primary: Option<GpuImage>,
     secondary: Option<GpuImage>,
+    grid: [Option<GpuImage>; GRID_MAX_PANES],
+    heatmap: Option<GpuImage>,
+    heatmap_pipeline: wgpu::RenderPipeline,
+    heatmap_colormap: String,
+    display_luts: GpuDisplayLuts,
+    magnification_filter: MagnificationFilter,
+    proofing: Option<[f32; 3]>,
+    /// Default colormaps, nearest sampling and no pyramid, whatever is requested.
+    safe_mode: bool,
     last_colormap: String,
     last_is_mono: bool,
     last_error: Option<String>,
 }
 
 impl GpuRenderer {
-    pub fn new(device: &wgpu::Device, target_format: wgpu::TextureFormat, _stream_rgba_uploads: bool) -> Result<Self> {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        target_format: wgpu::TextureFormat,
+        _stream_rgba_uploads: bool,
+    ) -> Result<Self> {
         let uniform_stride = align_to(
             std::mem::size_of::<GpuParams>() as u64,
             device.limits().min_uniform_buffer_offset_alignment as u64,
@@ -356,27 +833,56 @@ impl GpuRenderer {
                 },
             ],
         });
+        let lut_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("edolview display LUT bind group layout"),
+            entries: &[0, 1].map(|binding| wgpu::BindGroupLayoutEntry {
+                binding,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D3,
+                    multisampled: false,
+                },
+                count: None,
+            }),
+        });
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("edolview image pipeline layout"),
-            bind_group_layouts: &[Some(&bind_group_layout)],
+            bind_group_layouts: &[Some(&bind_group_layout), Some(&lut_bind_group_layout)],
             immediate_size: 0,
         });
+        let display_luts = upload_display_luts(device, queue, &lut_bind_group_layout, None, None);
         let background_pipeline = create_background_pipeline(device, &pipeline_layout, target_format);
         let fragment_module = compile_fragment_module(device, "rgb", false)?;
         let image_pipeline = create_image_pipeline(device, &pipeline_layout, target_format, &fragment_module);
         let export_pipeline =
             create_image_pipeline(device, &pipeline_layout, wgpu::TextureFormat::Rgba8Unorm, &fragment_module);
+        let mono_module = compile_fragment_module(device, "gray", true)?;
+        let thumbnail_pipelines = [&fragment_module, &mono_module]
+            .map(|module| create_image_pipeline(device, &pipeline_layout, wgpu::TextureFormat::Rgba8Unorm, module));
+        let heatmap_module = compile_fragment_module(device, "inferno", true)?;
+        let heatmap_pipeline = create_image_pipeline(device, &pipeline_layout, target_format, &heatmap_module);
         Ok(Self {
             target_format,
             bind_group_layout,
+            lut_bind_group_layout,
             pipeline_layout,
             uniform_buffer,
             uniform_stride,
             background_pipeline,
             image_pipeline,
             export_pipeline,
+            thumbnail_pipelines,
             primary: None,
             secondary: None,
+            grid: Default::default(),
+            heatmap: None,
+            heatmap_pipeline,
+            heatmap_colormap: "inferno".to_owned(),
+            display_luts,
+            magnification_filter: MagnificationFilter::default(),
+            proofing: None,
+            safe_mode: false,
             last_colormap: "rgb".to_owned(),
             last_is_mono: false,
             last_error: None,
@@ -393,6 +899,8 @@ impl GpuRenderer {
         let current = match slot {
             ImageSlot::Primary => &mut self.primary,
             ImageSlot::Secondary => &mut self.secondary,
+            ImageSlot::Grid(pane) => &mut self.grid[pane],
+            ImageSlot::Heatmap => &mut self.heatmap,
         };
         let Some(image) = image else {
             *current = None;
@@ -401,11 +909,67 @@ impl GpuRenderer {
         if current.as_ref().is_some_and(|gpu_image| gpu_image.image_id == image.id()) {
             return Ok(());
         }
-        *current = Some(upload_image(device, &self.bind_group_layout, &self.uniform_buffer, image)?);
+        *current = Some(upload_image(
+            device,
+            &self.bind_group_layout,
+            &self.uniform_buffer,
+            image,
+            !self.safe_mode,
+        )?);
         Ok(())
     }
 
+    /// Drop the textures of the grid panes from `first_unused` on, e.g. after leaving grid mode.
+    pub fn release_grid_images(&mut self, first_unused: usize) {
+        self.grid.iter_mut().skip(first_unused).for_each(|image| *image = None);
+    }
+
+    /// Upload the look and output LUTs when either changed; `None` disables that transform.
+    pub fn sync_display_luts(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        look: Option<&Arc<ColorLut3d>>,
+        output: Option<&Arc<ColorLut3d>>,
+    ) {
+        let same = |current: &Option<Arc<ColorLut3d>>, next: Option<&Arc<ColorLut3d>>| match (current, next) {
+            (Some(current), Some(next)) => Arc::ptr_eq(current, next),
+            (None, None) => true,
+            _ => false,
+        };
+        if !same(&self.display_luts.look, look) || !same(&self.display_luts.output, output) {
+            self.display_luts =
+                upload_display_luts(device, queue, &self.lut_bind_group_layout, look.cloned(), output.cloned());
+        }
+    }
+
+    /// Filter used by the following [`Self::write_params`] calls when the image is magnified.
+    pub fn set_magnification_filter(&mut self, filter: MagnificationFilter) {
+        self.magnification_filter = if self.safe_mode {
+            MagnificationFilter::default()
+        } else {
+            filter
+        };
+    }
+
+    /// Draw with the simplest shader path: the built-in `rgb` and `gray` colormaps, nearest sampling and
+    /// full-resolution textures only. Set before the first image is synced.
+    pub fn set_safe_mode(&mut self, enabled: bool) {
+        self.safe_mode = enabled;
+    }
+
+    /// Display gains of the following on-screen draws, from [`crate::model::Proofing::gains`]. Exports and
+    /// thumbnails are never proofed.
+    pub fn set_proofing(&mut self, gains: Option<[f32; 3]>) {
+        self.proofing = gains;
+    }
+
     pub fn update_colormap(&mut self, device: &wgpu::Device, name: &str, is_mono: bool) {
+        let name = match (self.safe_mode, is_mono) {
+            (false, _) => name,
+            (true, false) => "rgb",
+            (true, true) => "gray",
+        };
         if self.last_colormap == name && self.last_is_mono == is_mono {
             return;
         }
@@ -426,6 +990,65 @@ impl GpuRenderer {
         }
     }
 
+    /// Mono colormap of the heatmap, kept apart from the colormap of the image below it.
+    pub fn update_heatmap_colormap(&mut self, device: &wgpu::Device, name: &str) {
+        let name = if self.safe_mode { "gray" } else { name };
+        if self.heatmap_colormap == name {
+            return;
+        }
+        match compile_fragment_module(device, name, true) {
+            Ok(module) => {
+                self.heatmap_pipeline =
+                    create_image_pipeline(device, &self.pipeline_layout, self.target_format, &module);
+                self.heatmap_colormap = name.to_owned();
+            }
+            Err(error) => {
+                let message = error.to_string();
+                eprintln!("{message}");
+                self.last_error = Some(message);
+            }
+        }
+    }
+
+    /// Parameters of the heatmap, stretched over an image of `image_size` drawn at `scale` and `position`. Its
+    /// first channel maps `min_v..max_v` onto the heatmap colormap, blended with `opacity`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn write_heatmap_params(
+        &self,
+        queue: &wgpu::Queue,
+        viewport_size: Vec2,
+        image_size: Vec2,
+        scale: f32,
+        position: Vec2,
+        (min_v, max_v): (f32, f32),
+        opacity: f32,
+    ) {
+        let shader = ShaderParams {
+            use_alpha: false,
+            min_v,
+            max_v,
+            ..ShaderParams::default()
+        };
+        let mut params = GpuParams::image(
+            viewport_size,
+            image_size,
+            0,
+            &crate::model::empty_minmax(),
+            scale,
+            position,
+            &shader,
+            &MinMaxOverlay::default(),
+            &BackgroundStyle::solid(Color32::TRANSPARENT),
+        );
+        params.display[3] = self.magnification_filter as i32 as f32;
+        params.layer[0] = opacity.clamp(0.0, 1.0);
+        queue.write_buffer(
+            &self.uniform_buffer,
+            self.uniform_stride * HEATMAP_PARAM_SLOT as u64,
+            bytemuck::bytes_of(&params),
+        );
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn write_params(
         &self,
@@ -439,11 +1062,77 @@ impl GpuRenderer {
         position: Vec2,
         shader: &ShaderParams,
         overlay: &MinMaxOverlay,
-        background_a: Color32,
-        background_b: Color32,
+        background: &BackgroundStyle,
+        apply_output_lut: bool,
+        image_spec: &ImageSpec,
     ) {
         debug_assert!((slot as u64) < PARAM_SLOT_COUNT);
-        let params = GpuParams::image(
+        let params = self.image_params(
+            viewport_size,
+            image_size,
+            channel_index,
+            min_max,
+            scale,
+            position,
+            shader,
+            overlay,
+            background,
+            apply_output_lut,
+            image_spec,
+        );
+        queue.write_buffer(
+            &self.uniform_buffer,
+            self.uniform_stride * slot as u64,
+            bytemuck::bytes_of(&params),
+        );
+    }
+
+    /// Parameters for rendering the viewed image into an `output_size` export, without overlays or the monitor
+    /// output LUT.
+    #[allow(clippy::too_many_arguments)]
+    pub fn export_params(
+        &self,
+        output_size: Vec2,
+        image_size: Vec2,
+        channel_index: i32,
+        min_max: &MinMaxTotal,
+        scale: f32,
+        position: Vec2,
+        shader: &ShaderParams,
+        background: &BackgroundStyle,
+        image_spec: &ImageSpec,
+    ) -> ExportParams {
+        ExportParams(self.image_params(
+            output_size,
+            image_size,
+            channel_index,
+            min_max,
+            scale,
+            position,
+            shader,
+            &MinMaxOverlay::default(),
+            background,
+            false,
+            image_spec,
+        ))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn image_params(
+        &self,
+        viewport_size: Vec2,
+        image_size: Vec2,
+        channel_index: i32,
+        min_max: &MinMaxTotal,
+        scale: f32,
+        position: Vec2,
+        shader: &ShaderParams,
+        overlay: &MinMaxOverlay,
+        background: &BackgroundStyle,
+        apply_output_lut: bool,
+        image_spec: &ImageSpec,
+    ) -> GpuParams {
+        let mut params = GpuParams::image(
             viewport_size,
             image_size,
             channel_index,
@@ -452,9 +1141,31 @@ impl GpuRenderer {
             position,
             shader,
             overlay,
-            background_a,
-            background_b,
+            background,
         );
+        params.lut[0] = self.display_luts.look.is_some() as u8 as f32;
+        params.lut[2] = (apply_output_lut && self.display_luts.output.is_some()) as u8 as f32;
+        if apply_output_lut {
+            params.set_proofing(self.proofing);
+        }
+        params.display[3] = self.magnification_filter as i32 as f32;
+        params.set_image_layout(shader, image_spec, channel_index);
+        params
+    }
+
+    pub fn write_ramp_params(
+        &self,
+        queue: &wgpu::Queue,
+        slot: u32,
+        size: Vec2,
+        shader: &ShaderParams,
+        lightness_only: bool,
+    ) {
+        debug_assert!((slot as u64) < PARAM_SLOT_COUNT);
+        let mut params = GpuParams::colormap_ramp(size, shader, lightness_only);
+        params.lut[0] = self.display_luts.look.is_some() as u8 as f32;
+        params.lut[2] = self.display_luts.output.is_some() as u8 as f32;
+        params.set_proofing(self.proofing);
         queue.write_buffer(
             &self.uniform_buffer,
             self.uniform_stride * slot as u64,
@@ -470,6 +1181,8 @@ impl GpuRenderer {
         match slot {
             ImageSlot::Primary => self.primary.as_ref(),
             ImageSlot::Secondary => self.secondary.as_ref(),
+            ImageSlot::Grid(pane) => self.grid.get(pane)?.as_ref(),
+            ImageSlot::Heatmap => self.heatmap.as_ref(),
         }
     }
 
@@ -492,30 +1205,202 @@ impl GpuRenderer {
                 1.0,
             );
             let offset = (self.uniform_stride * pane.uniform_slot as u64) as u32;
-            pass.set_bind_group(0, &image.bind_group, &[offset]);
-            if show_background {
+            pass.set_bind_group(0, image.bind_group_for(pane.pyramid_zoom), &[offset]);
+            pass.set_bind_group(1, &self.display_luts.bind_group, &[]);
+            let is_heatmap = matches!(pane.slot, ImageSlot::Heatmap);
+            if show_background && !is_heatmap {
                 pass.set_pipeline(&self.background_pipeline);
                 pass.draw(0..4, 0..1);
             }
-            pass.set_pipeline(&self.image_pipeline);
+            pass.set_pipeline(if is_heatmap {
+                &self.heatmap_pipeline
+            } else {
+                &self.image_pipeline
+            });
             pass.draw(0..4, 0..1);
         }
     }
 
-    fn encode_export(
-        &mut self,
-        device: &wgpu::Device,
-        encoder: &mut wgpu::CommandEncoder,
-        request: &ExportRequest,
-    ) -> Result<ExportReadback> {
+    /// Render `request` tile by tile and assemble the tiles on the CPU. GPU errors while rendering a tile, such as
+    /// running out of memory, are returned instead of leaving the completion waiting.
+    fn render_export(&self, device: &wgpu::Device, queue: &wgpu::Queue, request: &ExportRequest) -> Result<()> {
         let image = self
             .image(request.slot)
             .ok_or_else(|| eyre!("The requested image is not on the GPU"))?;
+        let (width, height) = (request.width, request.height);
+        if width == 0 || height == 0 {
+            return Err(eyre!("The export region is empty"));
+        }
+        if width as u64 * height as u64 > MAX_EXPORT_PIXELS {
+            return Err(eyre!(
+                "A {width}x{height} export is too large; select a smaller region or export at original size"
+            ));
+        }
+        let tiles = export_tiles(width, height, EXPORT_TILE_SIZE.min(device.limits().max_texture_dimension_2d));
+        let assembly = if tiles.len() > 1 {
+            Some(TileAssembly::new(width, height, tiles.len(), Arc::clone(&request.completion))?)
+        } else {
+            None
+        };
+        let abandon = || {
+            if let Some(assembly) = &assembly {
+                assembly.lock().unwrap().abandon();
+            }
+        };
+        for (x, y, tile_width, tile_height) in tiles {
+            let mut params = request.params.0;
+            params.viewport_image[0] = tile_width as f32;
+            params.viewport_image[1] = tile_height as f32;
+            params.transform[1] -= x as f32;
+            params.transform[2] -= y as f32;
+            queue.write_buffer(
+                &self.uniform_buffer,
+                self.uniform_stride * EXPORT_PARAM_SLOT as u64,
+                bytemuck::bytes_of(&params),
+            );
+            let completion = match &assembly {
+                Some(assembly) => TileAssembly::tile_completion(assembly, x, y, tile_width),
+                None => Arc::clone(&request.completion),
+            };
+
+            let validation_scope = device.push_error_scope(wgpu::ErrorFilter::Validation);
+            let memory_scope = device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("edolview export encoder"),
+            });
+            let readback = self.encode_readback(
+                device,
+                &mut encoder,
+                &self.export_pipeline,
+                &image.bind_group,
+                EXPORT_PARAM_SLOT,
+                (tile_width, tile_height),
+                completion,
+            );
+            queue.submit([encoder.finish()]);
+            let memory_error = pollster::block_on(memory_scope.pop());
+            let validation_error = pollster::block_on(validation_scope.pop());
+            if let Some(error) = memory_error.or(validation_error) {
+                abandon();
+                return Err(eyre!("GPU error while rendering a {tile_width}x{tile_height} tile: {error}"));
+            }
+            readback.map();
+        }
+        Ok(())
+    }
+
+    /// Render `image` into a `width`×`height` RGBA8 thumbnail with `shader` and the default colormaps, and hand
+    /// the pixels to `completion` once the readback finishes.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_thumbnail(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &crate::model::ImageData,
+        width: u32,
+        height: u32,
+        shader: &ShaderParams,
+        completion: ExportCompletion,
+    ) -> Result<()> {
+        let pipeline = &self.thumbnail_pipelines[shader.is_mono(&image.spec(), -1) as usize];
+        self.render_offscreen(device, queue, image, (width, height), -1, shader, pipeline, completion)
+    }
+
+    /// Render `image` fitted and centered into a `width`×`height` RGBA8 frame with the current colormap, display
+    /// LUTs and `shader` settings, for exports of images that are not on screen.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_image(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &crate::model::ImageData,
+        (width, height): (u32, u32),
+        channel_index: i32,
+        shader: &ShaderParams,
+        completion: ExportCompletion,
+    ) -> Result<()> {
+        self.render_offscreen(
+            device,
+            queue,
+            image,
+            (width, height),
+            channel_index,
+            shader,
+            &self.export_pipeline,
+            completion,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_offscreen(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &crate::model::ImageData,
+        (width, height): (u32, u32),
+        channel_index: i32,
+        shader: &ShaderParams,
+        pipeline: &wgpu::RenderPipeline,
+        completion: ExportCompletion,
+    ) -> Result<()> {
+        let gpu_image = upload_image(device, &self.bind_group_layout, &self.uniform_buffer, image, !self.safe_mode)?;
+        let spec = image.spec();
+        let (image_w, image_h) = (spec.width.max(1) as f32, spec.height.max(1) as f32);
+        let scale = (width as f32 / image_w).min(height as f32 / image_h);
+        let position = Vec2::new(width as f32 - image_w * scale, height as f32 - image_h * scale) / 2.0;
+        let mut params = GpuParams::image(
+            Vec2::new(width as f32, height as f32),
+            Vec2::new(image_w, image_h),
+            channel_index,
+            image.minmax(),
+            scale,
+            position,
+            shader,
+            &MinMaxOverlay::default(),
+            &BackgroundStyle::solid(Color32::TRANSPARENT),
+        );
+        params.lut[0] = self.display_luts.look.is_some() as u8 as f32;
+        params.set_image_layout(shader, &spec, channel_index);
+        queue.write_buffer(
+            &self.uniform_buffer,
+            self.uniform_stride * OFFSCREEN_PARAM_SLOT as u64,
+            bytemuck::bytes_of(&params),
+        );
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("edolview offscreen encoder"),
+        });
+        let readback = self.encode_readback(
+            device,
+            &mut encoder,
+            pipeline,
+            gpu_image.bind_group_for(Some(scale)),
+            OFFSCREEN_PARAM_SLOT,
+            (width, height),
+            completion,
+        );
+        queue.submit([encoder.finish()]);
+        readback.map();
+        Ok(())
+    }
+
+    /// Draw one full-target quad into a new RGBA8 texture and copy it to a mappable buffer.
+    #[allow(clippy::too_many_arguments)]
+    fn encode_readback(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::RenderPipeline,
+        bind_group: &wgpu::BindGroup,
+        uniform_slot: u32,
+        (width, height): (u32, u32),
+        completion: ExportCompletion,
+    ) -> ExportReadback {
         let target = device.create_texture(&wgpu::TextureDescriptor {
             label: Some("edolview export target"),
             size: wgpu::Extent3d {
-                width: request.width,
-                height: request.height,
+                width,
+                height,
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
@@ -543,17 +1428,18 @@ impl GpuRenderer {
                 occlusion_query_set: None,
                 multiview_mask: None,
             });
-            let offset = (self.uniform_stride * 2) as u32;
-            pass.set_pipeline(&self.export_pipeline);
-            pass.set_bind_group(0, &image.bind_group, &[offset]);
+            let offset = (self.uniform_stride * uniform_slot as u64) as u32;
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, bind_group, &[offset]);
+            pass.set_bind_group(1, &self.display_luts.bind_group, &[]);
             pass.draw(0..4, 0..1);
         }
 
-        let row_bytes = request.width * 4;
+        let row_bytes = width * 4;
         let padded_row_bytes = align_to(row_bytes as u64, wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64) as u32;
         let buffer = Arc::new(device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("edolview export readback"),
-            size: padded_row_bytes as u64 * request.height as u64,
+            size: padded_row_bytes as u64 * height as u64,
             usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
             mapped_at_creation: false,
         }));
@@ -564,20 +1450,20 @@ impl GpuRenderer {
                 layout: wgpu::TexelCopyBufferLayout {
                     offset: 0,
                     bytes_per_row: Some(padded_row_bytes),
-                    rows_per_image: Some(request.height),
+                    rows_per_image: Some(height),
                 },
             },
             target.size(),
         );
 
-        Ok(ExportReadback {
+        ExportReadback {
             buffer,
             row_bytes,
             padded_row_bytes,
-            width: request.width,
-            height: request.height,
-            completion: Arc::clone(&request.completion),
-        })
+            width,
+            height,
+            completion,
+        }
     }
 }
 
@@ -590,34 +1476,101 @@ fn upload_image(
     layout: &wgpu::BindGroupLayout,
     uniform_buffer: &wgpu::Buffer,
     image: &impl Image,
+    with_pyramid: bool,
 ) -> Result<GpuImage> {
     #[cfg(debug_assertions)]
     let _timer = crate::util::timer::ScopedTimer::new("Upload texture");
 
     let texture = image.gpu_texture()?;
+    let create_bind_group = |view: &wgpu::TextureView| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("edolview image bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: uniform_buffer,
+                        offset: 0,
+                        size: NonZeroU64::new(std::mem::size_of::<GpuParams>() as u64),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+            ],
+        })
+    };
+    Ok(GpuImage {
+        bind_group: create_bind_group(&texture.view),
+        levels: if with_pyramid {
+            image
+                .gpu_pyramid()
+                .iter()
+                .map(|(factor, level)| (*factor, create_bind_group(&level.view)))
+                .collect()
+        } else {
+            Vec::new()
+        },
+        _texture: texture,
+        image_id: image.id(),
+    })
+}
+
+/// Upload the display LUTs as 3D textures. A missing LUT is bound as a 2×2×2 identity table so the layout stays valid.
+fn upload_display_luts(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    layout: &wgpu::BindGroupLayout,
+    look: Option<Arc<ColorLut3d>>,
+    output: Option<Arc<ColorLut3d>>,
+) -> GpuDisplayLuts {
+    let identity = ColorLut3d::from_fn(2, |rgb| rgb);
+    let textures = [&look, &output].map(|lut| {
+        let table = lut.as_deref().unwrap_or(&identity);
+        let size = table.size() as u32;
+        device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("edolview display LUT"),
+                size: wgpu::Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: size,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D3,
+                format: wgpu::TextureFormat::Rgba32Float,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            bytemuck::cast_slice(table.data()),
+        )
+    });
+    let views = textures.each_ref().map(|texture| texture.create_view(&Default::default()));
     let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        label: Some("edolview image bind group"),
+        label: Some("edolview display LUT bind group"),
         layout,
         entries: &[
             wgpu::BindGroupEntry {
                 binding: 0,
-                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                    buffer: uniform_buffer,
-                    offset: 0,
-                    size: NonZeroU64::new(std::mem::size_of::<GpuParams>() as u64),
-                }),
+                resource: wgpu::BindingResource::TextureView(&views[0]),
             },
             wgpu::BindGroupEntry {
                 binding: 1,
-                resource: wgpu::BindingResource::TextureView(&texture.view),
+                resource: wgpu::BindingResource::TextureView(&views[1]),
             },
         ],
     });
-    Ok(GpuImage {
-        _texture: texture,
+    GpuDisplayLuts {
+        _textures: textures,
         bind_group,
-        image_id: image.id(),
-    })
+        look,
+        output,
+    }
 }
 
 fn compile_fragment_module(device: &wgpu::Device, colormap: &str, is_mono: bool) -> Result<wgpu::ShaderModule> {
@@ -672,7 +1625,7 @@ fn build_fragment_source(colormap: &str, is_mono: bool) -> Result<String> {
         .replace("#version 330 core", "#version 450 core")
         .replace("out vec4 frag_color;", "layout(location = 0) out vec4 frag_color;")
         .replace("in vec2 v_tex_coord;", "layout(location = 0) in vec2 v_tex_coord;")
-        .replace("texture2D(u_texture, v_tex_coord)", "sample_image(v_tex_coord)")
+        .replace("texture2D(u_texture, tex_coord)", "sample_image(tex_coord)")
         .replace("%colormap_function%", &colormap_code)
         .replace("%color_process%", color_process);
 
@@ -694,6 +1647,16 @@ layout(set = 0, binding = 0, std140) uniform Params {
     vec4 background_color_a;
     vec4 background_color_b;
     vec4 background;
+    vec4 display;
+    vec4 lut;
+    vec4 image_layout;
+    vec4 quantize;
+    vec4 white_balance;
+    vec4 layer;
+    vec4 lens_intrinsics;
+    vec4 lens_distortion;
+    vec4 lens_options;
+    vec4 proofing;
 } p;
 layout(set = 0, binding = 1) uniform texture2D u_texture;
 
@@ -728,25 +1691,99 @@ layout(set = 0, binding = 1) uniform texture2D u_texture;
 #define u_min_max_scope ivec4(p.overlay_scope)
 #define u_min_max_min_values p.overlay_min_values
 #define u_min_max_max_values p.overlay_max_values
+#define u_color_vision int(p.display.x)
+#define u_lightness_only int(p.display.y)
+#define u_colormap_ramp int(p.display.z)
+#define u_magnification_filter int(p.display.w)
+#define u_look_lut int(p.lut.x)
+#define u_look_lut_strength p.lut.y
+#define u_output_lut int(p.lut.z)
+#define u_gray_alpha int(p.image_layout.x)
+#define u_cfa_view int(p.image_layout.y)
+#define u_cfa_red ivec2(p.image_layout.zw)
+#define u_quantize_levels p.quantize.x
+#define u_quantize_dither int(p.quantize.y)
+#define u_white_balance_gains p.white_balance.xyz
+#define u_white_balance int(p.white_balance.w)
+#define u_layer_opacity p.layer.x
+#define u_lens_focal p.lens_intrinsics.xy
+#define u_lens_center p.lens_intrinsics.zw
+#define u_lens_k1 p.lens_distortion.x
+#define u_lens_k2 p.lens_distortion.y
+#define u_lens_p1 p.lens_distortion.z
+#define u_lens_p2 p.lens_distortion.w
+#define u_lens_k3 p.lens_options.x
+#define u_undistort int(p.lens_options.y)
+#define u_proofing_gains p.proofing.xyz
+#define u_proofing int(p.proofing.w)
 
+layout(set = 1, binding = 0) uniform texture3D u_look_lut_texture;
+layout(set = 1, binding = 1) uniform texture3D u_output_lut_texture;
+%lut_functions%
 vec4 load_clamped(ivec2 pixel) {
     ivec2 extent = textureSize(u_texture, 0);
     return texelFetch(u_texture, clamp(pixel, ivec2(0), extent - ivec2(1)), 0);
 }
 
-vec4 sample_image(vec2 uv) {
-    ivec2 extent = textureSize(u_texture, 0);
-    if (p.transform.x >= 1.0) {
-        return load_clamped(ivec2(floor(uv * vec2(extent))));
-    }
-    vec2 texel = uv * vec2(extent) - vec2(0.5);
+vec4 sample_linear(vec2 texel) {
     ivec2 lo = ivec2(floor(texel));
     vec2 f = fract(texel);
     vec4 top = mix(load_clamped(lo), load_clamped(lo + ivec2(1, 0)), f.x);
     vec4 bottom = mix(load_clamped(lo + ivec2(0, 1)), load_clamped(lo + ivec2(1, 1)), f.x);
     return mix(top, bottom, f.y);
 }
+
+float kernel_weight(float x, int filter_kind) {
+    x = abs(x);
+    if (filter_kind == 2) {
+        // Catmull-Rom
+        if (x < 1.0) { return (1.5 * x - 2.5) * x * x + 1.0; }
+        if (x < 2.0) { return ((-0.5 * x + 2.5) * x - 4.0) * x + 2.0; }
+        return 0.0;
+    }
+    // Lanczos-3
+    if (x < 1e-5) { return 1.0; }
+    if (x >= 3.0) { return 0.0; }
+    float px = 3.14159265 * x;
+    return 3.0 * sin(px) * sin(px / 3.0) / (px * px);
+}
+
+vec4 sample_kernel(vec2 texel, int filter_kind) {
+    int radius = filter_kind == 2 ? 2 : 3;
+    ivec2 base = ivec2(floor(texel));
+    vec2 f = texel - vec2(base);
+    vec4 sum = vec4(0.0);
+    float weight_sum = 0.0;
+    for (int j = 1 - radius; j <= radius; j++) {
+        float wy = kernel_weight(float(j) - f.y, filter_kind);
+        for (int i = 1 - radius; i <= radius; i++) {
+            float w = kernel_weight(float(i) - f.x, filter_kind) * wy;
+            sum += load_clamped(base + ivec2(i, j)) * w;
+            weight_sum += w;
+        }
+    }
+    return sum / weight_sum;
+}
+
+vec4 sample_image(vec2 uv) {
+    ivec2 extent = textureSize(u_texture, 0);
+    vec2 texel = uv * vec2(extent) - vec2(0.5);
+    if (p.transform.x >= 1.0) {
+        if (u_magnification_filter == 1) {
+            return sample_linear(texel);
+        }
+        if (u_magnification_filter >= 2) {
+            return sample_kernel(texel, u_magnification_filter);
+        }
+        return load_clamped(ivec2(floor(uv * vec2(extent))));
+    }
+    return sample_linear(texel);
+}
 "#;
+    let lut_functions = ["look_lut", "output_lut"]
+        .map(|name| LUT_FUNCTION_GLSL.replace("%name%", name))
+        .join("\n");
+    let declarations = declarations.replace("%lut_functions%", &lut_functions);
     Ok(base.replacen("#version 450 core", &format!("#version 450 core\n{declarations}"), 1))
 }
 
@@ -840,13 +1877,29 @@ fn create_background_pipeline(
     })
 }
 
+/// Trilinear lookup into a 3D LUT texture, instantiated once per LUT binding.
+const LUT_FUNCTION_GLSL: &str = r#"
+vec3 apply_%name%(vec3 color) {
+    int size = textureSize(u_%name%_texture, 0).x;
+    vec3 coord = clamp(color, 0.0, 1.0) * float(size - 1);
+    ivec3 lo = min(ivec3(floor(coord)), ivec3(size - 2));
+    vec3 f = coord - vec3(lo);
+    vec3 c00 = mix(texelFetch(u_%name%_texture, lo, 0).rgb, texelFetch(u_%name%_texture, lo + ivec3(1, 0, 0), 0).rgb, f.x);
+    vec3 c10 = mix(texelFetch(u_%name%_texture, lo + ivec3(0, 1, 0), 0).rgb, texelFetch(u_%name%_texture, lo + ivec3(1, 1, 0), 0).rgb, f.x);
+    vec3 c01 = mix(texelFetch(u_%name%_texture, lo + ivec3(0, 0, 1), 0).rgb, texelFetch(u_%name%_texture, lo + ivec3(1, 0, 1), 0).rgb, f.x);
+    vec3 c11 = mix(texelFetch(u_%name%_texture, lo + ivec3(0, 1, 1), 0).rgb, texelFetch(u_%name%_texture, lo + ivec3(1, 1, 1), 0).rgb, f.x);
+    return mix(mix(c00, c10, f.y), mix(c01, c11, f.y), f.z);
+}
+"#;
+
 const PARAMS_WGSL: &str = r#"
 struct Params {
     viewport_image: vec4<f32>, transform: vec4<f32>, color: vec4<f32>, global_scale: vec4<f32>,
     min_values: vec4<f32>, max_values: vec4<f32>, scale_modes: vec4<f32>, overlay_flags: vec4<f32>,
     overlay_show_min: vec4<f32>, overlay_show_max: vec4<f32>, overlay_scope: vec4<f32>,
     overlay_min_values: vec4<f32>, overlay_max_values: vec4<f32>,
-    background_color_a: vec4<f32>, background_color_b: vec4<f32>, background: vec4<f32>,
+    background_color_a: vec4<f32>, background_color_b: vec4<f32>, background: vec4<f32>, display: vec4<f32>,
+    lut: vec4<f32>, image_layout: vec4<f32>,
 };
 @group(0) @binding(0) var<uniform> p: Params;
 "#;
@@ -907,6 +1960,39 @@ struct BackgroundOut { @builtin(position) position: vec4<f32>, @location(0) uv:
 mod tests {
     use super::*;
 
+    #[test]
+    fn view_params_drop_the_image_calibration() {
+        let params = ShaderParams {
+            exposure: 1.5,
+            cfa_view: CfaView::Demosaic,
+            cfa_pattern: CfaPattern::Bggr,
+            white_balance: Some([2.0, 1.0, 1.5]),
+            ..ShaderParams::default()
+        };
+        let view = params.view_params();
+        assert_eq!((view.exposure, view.cfa_view), (1.5, CfaView::Demosaic));
+        assert_eq!((view.cfa_pattern, view.white_balance), (CfaPattern::Rggb, None));
+        assert!(view.undistort.is_none());
+    }
+
+    #[test]
+    fn export_tiles_cover_the_output_once() {
+        assert_eq!(export_tiles(100, 50, 4096), [(0, 0, 100, 50)]);
+        let tiles = export_tiles(10, 7, 4);
+        assert_eq!(
+            tiles,
+            [
+                (0, 0, 4, 4),
+                (4, 0, 4, 4),
+                (8, 0, 2, 4),
+                (0, 4, 4, 3),
+                (4, 4, 4, 3),
+                (8, 4, 2, 3)
+            ]
+        );
+        assert_eq!(tiles.iter().map(|&(_, _, w, h)| w * h).sum::<u32>(), 70);
+    }
+
     #[test]
     fn all_bundled_colormaps_parse_as_wgpu_glsl() {
         for (directory, is_mono) in [("colormap/mono", true), ("colormap/rgb", false)] {
@@ -925,6 +2011,73 @@ mod tests {
         }
     }
 
+    #[test]
+    fn colormap_kind_follows_channel_layout() {
+        let spec = |channels| ImageSpec::new(4, 4, channels, crate::model::PixelType::U8);
+        let gray_alpha = ImageSpec {
+            gray_alpha: true,
+            ..spec(2)
+        };
+        let mut shader = ShaderParams::default();
+        assert!(shader.is_mono(&spec(1), -1));
+        assert!(
+            shader.is_mono(&gray_alpha, -1),
+            "declared gray+alpha defaults to the mono colormap"
+        );
+        assert!(
+            !shader.is_mono(&spec(2), -1),
+            "other 2-channel data such as flow stays red/green"
+        );
+        assert!(!shader.is_mono(&spec(3), -1));
+        assert!(shader.is_mono(&spec(3), 0));
+
+        shader.gray_alpha = false;
+        assert!(!shader.is_mono(&gray_alpha, -1));
+
+        shader.cfa_view = CfaView::Demosaic;
+        assert!(!shader.is_mono(&spec(1), -1), "Bayer views produce color");
+        assert!(
+            shader.is_mono(&spec(3), 1),
+            "only single-channel images are treated as Bayer data"
+        );
+
+        let mut params = GpuParams::colormap_ramp(Vec2::ONE, &shader, false);
+        shader.cfa_pattern = CfaPattern::Grbg;
+        params.set_image_layout(&shader, &spec(1), -1);
+        assert_eq!(params.image_layout, [0.0, 2.0, 1.0, 0.0]);
+        params.set_image_layout(&shader, &spec(3), -1);
+        assert_eq!(params.image_layout[1], 0.0);
+
+        shader.white_balance = Some([0.5, 1.0, 2.0]);
+        params.set_image_layout(&shader, &spec(3), -1);
+        assert_eq!(params.white_balance, [0.5, 1.0, 2.0, 1.0]);
+        params.set_image_layout(&shader, &spec(3), 0);
+        assert_eq!(params.white_balance[3], 0.0, "single channel views show the stored values");
+
+        let mut lens = LensDistortion::for_image(64, 32);
+        lens.k1 = -0.25;
+        shader.undistort = Some(lens);
+        params.set_image_layout(&shader, &spec(3), -1);
+        assert_eq!(params.lens_intrinsics, [64.0, 64.0, 31.5, 15.5]);
+        assert_eq!(params.lens_options, [0.0, 1.0, 0.0, 0.0]);
+        shader.undistort = Some(LensDistortion { fx: 0.0, ..lens });
+        params.set_image_layout(&shader, &spec(3), -1);
+        assert_eq!(params.lens_options[1], 0.0, "a zero focal length cannot be undistorted");
+    }
+
+    #[test]
+    fn thumbnails_log_scale_only_out_of_range_images() {
+        let ldr = MinMaxTotal::new(vec![0.0, 0.1, 0.2], vec![0.9, 1.0, 0.8]);
+        assert_eq!(thumbnail_shader_params(&ldr).scale_mode, ScaleMode::Linear);
+
+        let hdr = MinMaxTotal::new(vec![-1.0, 0.0, 0.0], vec![4.0, 250.0, 1.0]);
+        let params = thumbnail_shader_params(&hdr);
+        assert_eq!(params.scale_mode, ScaleMode::Log);
+        assert!(!params.auto_minmax);
+        assert!((params.min_v + 2.0_f32.ln()).abs() < 1e-6);
+        assert!((params.max_v - 251.0_f32.ln()).abs() < 1e-6);
+    }
+
     #[test]
     fn large_rgb_uploads_are_split_to_fit_storage_binding_limits() {
         let rows = crate::model::rgb_rows_per_chunk(4096, 128 * 1024 * 1024);
@@ -0,0 +1,269 @@
+//! Golden-image tests for the display shader. Known inputs are rendered offscreen through [`GpuRenderer`] with a
+//! set of [`ShaderParams`] combinations and compared with the PNGs in `tests/fixtures/golden`.
+//!
+//! Run with `EDOLVIEW_UPDATE_GOLDEN=1` to rewrite the golden files after an intended display change, and review
+//! the new images before committing them.
+
+use std::{
+    path::PathBuf,
+    sync::{mpsc, Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+use crate::{
+    model::{test_gpu_compute, Image, ImageData, ImageSpec, PixelType},
+    ui::gpu::{GpuRenderer, ScaleMode, ShaderParams},
+};
+
+const WIDTH: i32 = 32;
+const HEIGHT: i32 = 16;
+/// Largest per-channel difference allowed between a render and its golden image. Drivers may round the final
+/// 8-bit conversion differently.
+const TOLERANCE: u8 = 2;
+
+struct GoldenCase {
+    name: &'static str,
+    image: fn() -> ImageData,
+    channel_index: i32,
+    colormap: &'static str,
+    shader: ShaderParams,
+}
+
+/// RGBA ramp from -0.5 to 2.0 across the width with constant channel offsets, like an HDR render with
+/// negative values.
+fn hdr_rgba() -> ImageData {
+    let pixels = (0..HEIGHT)
+        .flat_map(|y| {
+            (0..WIDTH).flat_map(move |x| {
+                let v = -0.5 + 2.5 * x as f32 / (WIDTH - 1) as f32;
+                let a = y as f32 / (HEIGHT - 1) as f32;
+                [v, v * 0.5 + 0.1, 1.0 - v, a]
+            })
+        })
+        .collect();
+    ImageData::from_f32(ImageSpec::new(WIDTH, HEIGHT, 4, PixelType::F32), pixels).unwrap()
+}
+
+/// Single-channel ramp over `[0, 1]` with a brighter lower half.
+fn gray_ramp() -> ImageData {
+    let pixels = (0..HEIGHT)
+        .flat_map(|y| (0..WIDTH).map(move |x| x as f32 / (WIDTH - 1) as f32 * if y < HEIGHT / 2 { 0.5 } else { 1.0 }))
+        .collect();
+    ImageData::from_f32(ImageSpec::new(WIDTH, HEIGHT, 1, PixelType::F32), pixels).unwrap()
+}
+
+/// 8-bit RGB stored as normalized values, for the display path of ordinary integer images.
+fn u8_rgb() -> ImageData {
+    let pixels = (0..HEIGHT)
+        .flat_map(|y| {
+            (0..WIDTH).flat_map(move |x| [x * 8, y * 16, 255 - x * 8].map(|v| v.clamp(0, 255) as f32 / 255.0))
+        })
+        .collect();
+    ImageData::from_f32(ImageSpec::new(WIDTH, HEIGHT, 3, PixelType::U8), pixels).unwrap()
+}
+
+fn cases() -> Vec<GoldenCase> {
+    let case = |name, image, channel_index, colormap, shader| GoldenCase {
+        name,
+        image,
+        channel_index,
+        colormap,
+        shader,
+    };
+    vec![
+        case("u8_rgb_default", u8_rgb, -1, "rgb", ShaderParams::default()),
+        case("hdr_default", hdr_rgba, -1, "rgb", ShaderParams::default()),
+        case(
+            "hdr_exposure_gamma_offset",
+            hdr_rgba,
+            -1,
+            "rgb",
+            ShaderParams {
+                exposure: -1.0,
+                gamma: 2.2,
+                offset: 0.1,
+                ..ShaderParams::default()
+            },
+        ),
+        case(
+            "hdr_auto_minmax",
+            hdr_rgba,
+            -1,
+            "rgb",
+            ShaderParams {
+                auto_minmax: true,
+                ..ShaderParams::default()
+            },
+        ),
+        case(
+            "hdr_negative_min",
+            hdr_rgba,
+            -1,
+            "rgb",
+            ShaderParams {
+                min_v: -0.5,
+                max_v: 2.0,
+                ..ShaderParams::default()
+            },
+        ),
+        case(
+            "hdr_log_scale",
+            hdr_rgba,
+            -1,
+            "rgb",
+            ShaderParams {
+                min_v: 0.0,
+                max_v: 2.0,
+                scale_mode: ScaleMode::Log,
+                ..ShaderParams::default()
+            },
+        ),
+        case(
+            "hdr_per_channel",
+            hdr_rgba,
+            -1,
+            "rgb",
+            ShaderParams {
+                use_per_channel: true,
+                min_v_channels: [-0.5, 0.0, -1.0, 0.0],
+                max_v_channels: [2.0, 1.0, 1.5, 1.0],
+                ..ShaderParams::default()
+            },
+        ),
+        case(
+            "hdr_no_alpha",
+            hdr_rgba,
+            -1,
+            "rgb",
+            ShaderParams {
+                use_alpha: false,
+                ..ShaderParams::default()
+            },
+        ),
+        case("hdr_channel_green_gray", hdr_rgba, 1, "gray", ShaderParams::default()),
+        case(
+            "hdr_quantize_3bit",
+            hdr_rgba,
+            -1,
+            "rgb",
+            ShaderParams {
+                quantize_bits: 3,
+                ..ShaderParams::default()
+            },
+        ),
+        case(
+            "hdr_quantize_3bit_dither",
+            hdr_rgba,
+            -1,
+            "rgb",
+            ShaderParams {
+                quantize_bits: 3,
+                quantize_dither: true,
+                ..ShaderParams::default()
+            },
+        ),
+        case("gray_viridis", gray_ramp, -1, "viridis", ShaderParams::default()),
+        case(
+            "gray_inverted_range",
+            gray_ramp,
+            -1,
+            "gray",
+            ShaderParams {
+                min_v: 1.0,
+                max_v: 0.0,
+                ..ShaderParams::default()
+            },
+        ),
+    ]
+}
+
+fn renderer() -> &'static Mutex<GpuRenderer> {
+    static RENDERER: OnceLock<Mutex<GpuRenderer>> = OnceLock::new();
+    RENDERER.get_or_init(|| {
+        let compute = test_gpu_compute();
+        let renderer = GpuRenderer::new(compute.device(), compute.queue(), wgpu::TextureFormat::Rgba8Unorm, false)
+            .expect("GPU renderer");
+        Mutex::new(renderer)
+    })
+}
+
+/// Render `case` at the image's own size, so every output pixel samples exactly one input pixel.
+fn render(case: &GoldenCase) -> Vec<u8> {
+    let compute = test_gpu_compute();
+    let image = (case.image)();
+    let is_mono = case.shader.is_mono(&image.spec(), case.channel_index);
+    let mut renderer = renderer().lock().unwrap();
+    renderer.update_colormap(compute.device(), case.colormap, is_mono);
+    assert!(renderer.last_error().is_none(), "{}: {:?}", case.name, renderer.last_error());
+
+    let (tx, rx) = mpsc::channel();
+    let tx = Mutex::new(tx);
+    renderer
+        .render_image(
+            compute.device(),
+            compute.queue(),
+            &image,
+            (WIDTH as u32, HEIGHT as u32),
+            case.channel_index,
+            &case.shader,
+            Arc::new(move |result| {
+                let _ = tx.lock().unwrap().send(result);
+            }),
+        )
+        .expect("render");
+    compute.wait_idle().unwrap();
+    rx.recv_timeout(Duration::from_secs(10))
+        .expect("readback")
+        .unwrap_or_else(|error| panic!("{}: {error}", case.name))
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(format!("tests/fixtures/golden/{name}.png"))
+}
+
+/// Number of pixels whose channels differ by more than [`TOLERANCE`], and the largest difference.
+fn compare(actual: &[u8], expected: &[u8]) -> (usize, u8) {
+    actual
+        .chunks_exact(4)
+        .zip(expected.chunks_exact(4))
+        .fold((0, 0), |(count, largest), (a, e)| {
+            let diff = a.iter().zip(e).map(|(a, e)| a.abs_diff(*e)).max().unwrap_or(0);
+            (count + (diff > TOLERANCE) as usize, largest.max(diff))
+        })
+}
+
+#[test]
+fn display_shader_matches_golden_images() {
+    let update = std::env::var_os("EDOLVIEW_UPDATE_GOLDEN").is_some();
+    let mut failures = Vec::new();
+    for case in cases() {
+        let actual = render(&case);
+        let path = golden_path(case.name);
+        if update {
+            image::save_buffer(&path, &actual, WIDTH as u32, HEIGHT as u32, image::ColorType::Rgba8).unwrap();
+            continue;
+        }
+        let expected = match image::open(&path) {
+            Ok(expected) => expected.into_rgba8().into_raw(),
+            Err(error) => {
+                failures.push(format!("{}: cannot read {}: {error}", case.name, path.display()));
+                continue;
+            }
+        };
+        let (mismatched, largest) = compare(&actual, &expected);
+        if actual.len() != expected.len() || mismatched > 0 {
+            let actual_path = std::env::temp_dir().join(format!("edolview-golden-{}.png", case.name));
+            let _ = image::save_buffer(&actual_path, &actual, WIDTH as u32, HEIGHT as u32, image::ColorType::Rgba8);
+            failures.push(format!(
+                "{}: {mismatched} pixels differ by up to {largest}, render saved to {}",
+                case.name,
+                actual_path.display()
+            ));
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "display shader output changed (set EDOLVIEW_UPDATE_GOLDEN=1 to accept):\n{}",
+        failures.join("\n")
+    );
+}
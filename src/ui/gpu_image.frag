@@ -42,6 +42,31 @@ uniform float u_min_max_compare_epsilon;
 uniform vec4 u_min_max_min_values;
 uniform vec4 u_min_max_max_values;
 
+uniform int u_color_vision; // 0: normal, 1: protanopia, 2: deuteranopia, 3: tritanopia
+uniform int u_lightness_only; // 0: off, 1: show CIE L* of the colormapped color as gray
+uniform int u_colormap_ramp; // 0: image, 1: horizontal 0..1 ramp instead of the texture
+uniform int u_look_lut; // 0: off, 1: blend the look LUT (e.g. a grading .cube) into the colormapped color
+uniform float u_look_lut_strength;
+uniform int u_output_lut; // 0: off, 1: map the final color through the output LUT (e.g. monitor profile)
+uniform int u_gray_alpha; // 0: off, 1: 2-channel image with luminance in r and alpha in g
+uniform int u_cfa_view; // 0: off, 1: tint samples with their filter color, 2: bilinear demosaic
+uniform ivec2 u_cfa_red; // position of the red sample in the 2x2 Bayer block
+uniform float u_quantize_levels; // 0: off, otherwise 2^bits - 1
+uniform int u_quantize_dither; // 0: off, 1: add one step of noise before rounding
+uniform vec3 u_white_balance_gains;
+uniform int u_white_balance; // 0: off, 1: multiply the stored RGB values by u_white_balance_gains
+uniform float u_layer_opacity; // 1: opaque, less for a layer blended over another image
+uniform int u_undistort; // 0: off, 1: show the image undistorted with the lens parameters below
+uniform vec2 u_lens_focal; // fx, fy in pixels
+uniform vec2 u_lens_center; // cx, cy in pixels
+uniform float u_lens_k1;
+uniform float u_lens_k2;
+uniform float u_lens_p1;
+uniform float u_lens_p2;
+uniform float u_lens_k3;
+uniform vec3 u_proofing_gains;
+uniform int u_proofing; // 0: off, 1: multiply the linear display color by u_proofing_gains
+
 #define PI 3.1415926535897932384626433832795
 #define EPS 1e-12
 
@@ -131,6 +156,42 @@ vec3 rgb2lab(vec3 rgb) {
     return xyz2lab(rgb2xyz(rgb));
 }
 
+// Machado, Oliveira and Fernandes (2009) simulation matrices at full severity, applied in linear RGB.
+mat3 protanopia_mat = mat3(
+    0.152286, 1.052583, -0.204868,
+    0.114503, 0.786281, 0.099216,
+    -0.003882, -0.048116, 1.051998
+);
+
+mat3 deuteranopia_mat = mat3(
+    0.367322, 0.860646, -0.227968,
+    0.280085, 0.672501, 0.047413,
+    -0.011820, 0.042940, 0.968881
+);
+
+mat3 tritanopia_mat = mat3(
+    1.255528, -0.076749, -0.178779,
+    -0.078411, 0.930809, 0.147602,
+    0.004733, 0.691367, 0.303900
+);
+
+vec3 simulate_color_vision(vec3 rgb)
+{
+    if (u_color_vision == 0) {
+        return rgb;
+    }
+
+    vec3 linear = inv_gamma_f(clamp(rgb, 0.0, 1.0));
+    if (u_color_vision == 1) {
+        linear = linear * protanopia_mat;
+    } else if (u_color_vision == 2) {
+        linear = linear * deuteranopia_mat;
+    } else {
+        linear = linear * tritanopia_mat;
+    }
+    return gamma_f(clamp(linear, 0.0, 1.0));
+}
+
 float color_proc(float v)
 {
     float tmp = v * exp2(u_exposure) + u_offset;
@@ -207,11 +268,107 @@ bool should_check_min_max_channel(int channel)
     return channel < 3 || (channel == 3 && u_use_alpha != 0);
 }
 
+// 0: red, 1: green, 2: blue
+int cfa_channel(ivec2 pixel)
+{
+    ivec2 phase = ivec2(pixel.x & 1, pixel.y & 1);
+    if (phase == u_cfa_red) {
+        return 0;
+    }
+    if (phase == ivec2(1) - u_cfa_red) {
+        return 2;
+    }
+    return 1;
+}
+
+vec3 cfa_mask(int channel)
+{
+    return vec3(equal(ivec3(channel), ivec3(0, 1, 2)));
+}
+
+vec4 cfa_color(vec2 uv)
+{
+    ivec2 extent = max(ivec2(u_image_size), ivec2(1));
+    ivec2 pixel = clamp(ivec2(uv * u_image_size), ivec2(0), extent - ivec2(1));
+    float center = texelFetch(u_texture, pixel, 0).r;
+    vec3 center_mask = cfa_mask(cfa_channel(pixel));
+    if (u_cfa_view == 1) {
+        return vec4(center * center_mask, 1.0);
+    }
+
+    // In a Bayer mosaic the same-color samples of the 3x3 window are exactly the bilinear neighbors.
+    vec3 sum = vec3(0.0);
+    vec3 count = vec3(0.0);
+    for (int j = -1; j <= 1; ++j) {
+        for (int i = -1; i <= 1; ++i) {
+            ivec2 neighbor = pixel + ivec2(i, j);
+            if (any(lessThan(neighbor, ivec2(0))) || any(greaterThanEqual(neighbor, extent))) {
+                continue;
+            }
+            vec3 mask = cfa_mask(cfa_channel(neighbor));
+            sum += texelFetch(u_texture, neighbor, 0).r * mask;
+            count += mask;
+        }
+    }
+    vec3 rgb = mix(sum / max(count, vec3(1.0)), vec3(center), center_mask);
+    return vec4(rgb, 1.0);
+}
+
+// Interleaved gradient noise (Jimenez 2014), fixed per image pixel so the dither does not crawl when panning.
+float dither_noise(ivec2 pixel)
+{
+    return fract(52.9829189 * fract(dot(vec2(pixel), vec2(0.06711056, 0.00583715))));
+}
+
+vec3 quantize(vec3 rgb)
+{
+    vec3 code = clamp(rgb, 0.0, 1.0) * u_quantize_levels;
+    if (u_quantize_dither != 0) {
+        ivec2 extent = max(ivec2(u_image_size), ivec2(1));
+        ivec2 pixel = clamp(ivec2(v_tex_coord * u_image_size), ivec2(0), extent - ivec2(1));
+        code = clamp(floor(code + dither_noise(pixel)), 0.0, u_quantize_levels);
+    } else {
+        code = floor(code + 0.5);
+    }
+    return code / u_quantize_levels;
+}
+
+// Where the undistorted point `uv` (0..1 over the image) lands in the captured image, with OpenCV's
+// radial-tangential model. Pixel centers are at integer coordinates, as in OpenCV.
+vec2 distort_coord(vec2 uv)
+{
+    vec2 n = (uv * u_image_size - vec2(0.5) - u_lens_center) / u_lens_focal;
+    float r2 = dot(n, n);
+    float radial = 1.0 + r2 * (u_lens_k1 + r2 * (u_lens_k2 + r2 * u_lens_k3));
+    vec2 tangential = vec2(
+        2.0 * u_lens_p1 * n.x * n.y + u_lens_p2 * (r2 + 2.0 * n.x * n.x),
+        u_lens_p1 * (r2 + 2.0 * n.y * n.y) + 2.0 * u_lens_p2 * n.x * n.y);
+    return ((n * radial + tangential) * u_lens_focal + u_lens_center + vec2(0.5)) / u_image_size;
+}
+
 %colormap_function%
 
 void main()
 {
-    vec4 tex = texture2D(u_texture, v_tex_coord);
+    vec2 tex_coord = v_tex_coord;
+    if (u_undistort != 0) {
+        tex_coord = distort_coord(v_tex_coord);
+        // Outside the capture there is nothing to show.
+        if (any(lessThan(tex_coord, vec2(0.0))) || any(greaterThan(tex_coord, vec2(1.0)))) {
+            discard;
+        }
+    }
+    vec4 tex = texture2D(u_texture, tex_coord);
+    if (u_colormap_ramp != 0) {
+        tex = vec4(vec3(v_tex_coord.x), 1.0);
+    } else if (u_cfa_view != 0) {
+        tex = cfa_color(tex_coord);
+    }
+    if (u_white_balance != 0) {
+        tex.rgb *= u_white_balance_gains;
+    }
+    // Gray+alpha images use channel 1 as stored, before the value mapping below.
+    float gray_alpha = tex.g;
 
     if (u_use_per_channel != 0) {
         tex.r = (apply_scale_mode(tex.r, u_scale_mode0) - u_min_v0) / (u_max_v0 - u_min_v0);
@@ -232,6 +389,10 @@ void main()
         tex.r = tex.a;
     }
 
+    if (u_gray_alpha != 0) {
+        tex.a = gray_alpha;
+    }
+
     float alpha;
     if (u_channel_index != -1 || u_use_alpha == 0) {
         alpha = 1.0;
@@ -241,11 +402,28 @@ void main()
 
     %color_process%
 
-    frag_color = vec4(cm.r, cm.g, cm.b, alpha);
+    if (u_look_lut != 0) {
+        cm = mix(cm, apply_look_lut(cm), u_look_lut_strength);
+    }
+    if (u_quantize_levels > 0.0) {
+        cm = quantize(cm);
+    }
+    cm = simulate_color_vision(cm);
+    if (u_lightness_only != 0) {
+        float lightness = rgb2lab(clamp(cm, 0.0, 1.0)).x;
+        cm = lab2rgb(vec3(lightness, 0.0, 0.0));
+    }
+    if (u_proofing != 0) {
+        cm = gamma_f(inv_gamma_f(clamp(cm, 0.0, 1.0)) * u_proofing_gains);
+    }
+    if (u_output_lut != 0) {
+        cm = apply_output_lut(cm);
+    }
+    frag_color = vec4(cm.r, cm.g, cm.b, alpha * u_layer_opacity);
 
     if (u_min_max_overlay_enabled != 0) {
         ivec2 image_extent = max(ivec2(u_image_size), ivec2(1));
-        ivec2 texel_coord = clamp(ivec2(v_tex_coord * u_image_size), ivec2(0), image_extent - ivec2(1));
+        ivec2 texel_coord = clamp(ivec2(tex_coord * u_image_size), ivec2(0), image_extent - ivec2(1));
 
         if (min_max_scope_contains(texel_coord)) {
             vec4 scaled_texel = texelFetch(u_texture, texel_coord, 0) * u_min_max_value_scale;
@@ -1,27 +1,93 @@
 use color_eyre::eyre::{eyre, Result};
 use eframe::egui::{self, vec2};
-use image::ImageEncoder;
 use std::{
-    io::BufWriter,
     path::{Path, PathBuf},
-    sync::{Arc, Mutex},
+    sync::{mpsc, Arc, Mutex},
+    time::{Duration, Instant},
 };
 
-use crate::model::{empty_minmax, AppState, Image, MeanDim, PixelType, Recti};
+use crate::model::{
+    empty_minmax, AnnotationStroke, AppState, AssetType, ChannelExpr, FilteredAsset, Image, ImageData, ImageSpec,
+    MeanDim, OverlayShape, PixelType, PointPickTarget, PrintLayout, Proofing, Recti, SelectionMask, SelectionTool,
+    SharedAsset, SourceMetadata, StrokePoint, VectorOverlay, CHANNEL_MATH_PRESETS,
+};
 use crate::res::{
-    selection_handle_clipped_fill, KeyboardShortcutExt, PIXEL_VALUE_CHANNEL_COLORS, SELECTION_HANDLE_CLIPPED_STROKE,
+    overlay_class_color, pixel_value_text_color, segmentation_mask_tint, selection_handle_clipped_fill,
+    KeyboardShortcutExt, ANNOTATION_ERASER_STROKE, COLORMAP_RAMP_LABEL, COLORMAP_RAMP_OUTLINE, GRID_PROBE_STROKE,
+    IMAGE_BORDER_STROKE, OVERLAY_LABEL_INK, PROOFING_BADGE_TEXT, SELECTION_HANDLE_CLIPPED_STROKE,
+    SELECTION_MASK_STROKE, VIEWER_OFFSCREEN_ARROW_FILL, VIEWER_OFFSCREEN_ARROW_HOVER_FILL, VIEWER_OFFSCREEN_CAPTION,
+    VIEWER_OSD_FILL, VIEWER_OSD_TEXT,
+};
+use crate::settings::{
+    BackgroundKind, BackgroundSettings, DragAction, ExportData, ExportMetadata, ExportOptions, ExportSize,
+    MouseBindings, MouseButton, PixelValueFormat,
+};
+use crate::ui::component::{egui_ext::UiExt, export_options_ui};
+use crate::ui::gpu::{
+    BackgroundStyle, ExportCompletion, ExportRequest, GpuRenderer, ImagePaintCallback, ImageSlot, MinMaxOverlay,
+    PaneDraw, ShaderParams, GRID_PARAM_SLOT, HEATMAP_PARAM_SLOT, RAMP_COLOR_PARAM_SLOT, RAMP_LIGHTNESS_PARAM_SLOT,
 };
-use crate::ui::component::egui_ext::UiExt;
-use crate::ui::gpu::{ExportRequest, GpuRenderer, ImagePaintCallback, ImageSlot, MinMaxOverlay, PaneDraw};
 use crate::util::func_ext::FuncExt;
-use crate::util::math_ext::vec2i;
+use crate::util::math_ext::{vec2i, Vec2i};
+
+/// Zoom level and pan of the viewer, for restoring a view later.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewTransform {
+    pub zoom_level: f32,
+    pub pan: egui::Vec2,
+}
 
 enum ExportToast {
     Success(String),
     Error(String),
 }
 
-type PendingExportRequest = (Option<String>, Option<(PathBuf, String)>, i32, i32, egui::Vec2, f32);
+/// Where a copy delivers the exported image.
+#[derive(Clone, Default)]
+enum CopyTarget {
+    /// Pixels on the clipboard.
+    #[default]
+    Clipboard,
+    /// A temporary file on the clipboard, for targets such as chat apps and file managers that only take files.
+    File,
+    /// A temporary file that is dragged out of the window once written, if the drag is still held by then.
+    Drag(PendingDrag),
+}
+
+/// Hands the export for a drag-out gesture back to the UI thread, which has to start the platform drag.
+#[derive(Clone)]
+struct PendingDrag {
+    file: Arc<Mutex<Option<PathBuf>>>,
+    repaint_ctx: egui::Context,
+}
+
+impl CopyTarget {
+    /// Put the temporary export at `path` on the clipboard, or queue it for dragging.
+    fn deliver_file(&self, path: PathBuf) -> Result<()> {
+        match self {
+            Self::Drag(pending) => {
+                let previous = pending.file.lock().map_err(|_| eyre!("Drag state is poisoned"))?.replace(path);
+                if let Some(previous) = previous {
+                    crate::model::discard_temp_export(&previous);
+                }
+                pending.repaint_ctx.request_repaint();
+                Ok(())
+            }
+            Self::Clipboard | Self::File => crate::model::write_clipboard_files(&[path]),
+        }
+    }
+}
+
+/// Copy source, save target, output size, crop offset, scale, and the stored 16-bit pixels to copy alongside.
+type PendingExportRequest = (
+    Option<String>,
+    Option<(PathBuf, String)>,
+    i32,
+    i32,
+    egui::Vec2,
+    f32,
+    Option<(ImageData, Recti, ExportData)>,
+);
 
 enum DragMode {
     None,
@@ -32,6 +98,19 @@ enum DragMode {
         start_image_pos: egui::Pos2,
         start_on_secondary: bool,
     },
+    /// Freehand selection; the traced points are collected in `AppState::selection_draft`.
+    Lasso {
+        start_on_secondary: bool,
+    },
+    /// Drawing an annotation stroke, collected in `ImageViewer::annotation_draft` until released.
+    Annotating,
+    /// Erasing the annotation strokes under the pointer.
+    Erasing,
+    /// Reading the pixel under the pointer, with the last pixel read and its values.
+    ColorPick {
+        on_secondary: bool,
+        picked: Option<(Vec2i, Vec<f32>)>,
+    },
     Resizing {
         handle: ResizeHandle,
         start_rect: Recti,
@@ -48,6 +127,132 @@ enum ResizeHandle {
     BottomRight,
 }
 
+/// Radius of the annotation eraser, in screen points.
+const ANNOTATION_ERASER_RADIUS: f32 = 8.0;
+
+/// Length of the eased pan/zoom transition of fit and center actions.
+const VIEW_ANIMATION_DURATION: Duration = Duration::from_millis(250);
+
+/// Eased transition from one view to another, advanced each frame by [`ImageViewer::step_view_animation`].
+struct ViewAnimation {
+    start: Instant,
+    from_level: f32,
+    from_pan: egui::Vec2,
+    to_level: f32,
+    to_pan: egui::Vec2,
+}
+
+/// Draw `stroke` as segments whose width follows the pen pressure, with round joins.
+fn draw_annotation_stroke(
+    painter: &egui::Painter,
+    stroke: &AnnotationStroke,
+    to_view: impl Fn(egui::Pos2) -> egui::Pos2,
+    points_per_pixel: f32,
+) {
+    if let [point] = stroke.points.as_slice() {
+        let radius = stroke.width_at(point) * points_per_pixel * 0.5;
+        painter.circle_filled(to_view(point.pos), radius, stroke.color);
+        return;
+    }
+    for segment in stroke.points.windows(2) {
+        let width = stroke.width_at(&segment[1]) * points_per_pixel;
+        let end = to_view(segment[1].pos);
+        painter.line_segment([to_view(segment[0].pos), end], (width, stroke.color));
+        painter.circle_filled(end, width * 0.5, stroke.color);
+    }
+}
+
+/// Draw the shown objects of `overlay` for the image named `image_name`, each with a tag of its class and score.
+fn draw_vector_overlay(
+    painter: &egui::Painter,
+    overlay: &VectorOverlay,
+    image_name: &str,
+    to_view: impl Fn(egui::Pos2) -> egui::Pos2,
+) {
+    for object in overlay.objects(image_name).filter(|object| overlay.is_shown(object)) {
+        let color = overlay_class_color(object.class);
+        let stroke = egui::Stroke::new(2.0, color);
+        for shape in &object.shapes {
+            match shape {
+                OverlayShape::Box(rect) => {
+                    let rect = egui::Rect::from_two_pos(to_view(rect.min), to_view(rect.max));
+                    painter.rect_stroke(rect, 0.0, stroke, egui::StrokeKind::Middle);
+                }
+                OverlayShape::Polygon(points) => {
+                    painter.add(egui::Shape::closed_line(points.iter().copied().map(&to_view).collect(), stroke));
+                }
+                OverlayShape::Points(points) => {
+                    for point in points {
+                        painter.circle(to_view(*point), 3.0, color, (1.0, OVERLAY_LABEL_INK));
+                    }
+                }
+            }
+        }
+        let Some(anchor) = object.anchor() else {
+            continue;
+        };
+        let mut label = overlay.classes.get(object.class).cloned().unwrap_or_default();
+        if let Some(score) = object.score {
+            label.push_str(&format!(" {score:.2}"));
+        }
+        let galley = painter.layout_no_wrap(label, egui::FontId::proportional(11.0), OVERLAY_LABEL_INK);
+        let rect = egui::Align2::LEFT_BOTTOM.anchor_size(to_view(anchor), galley.size());
+        painter.rect_filled(rect.expand(1.0), 0.0, color);
+        painter.galley(rect.min, galley, OVERLAY_LABEL_INK);
+    }
+}
+
+/// Whether `point` is inside the convex polygon `points`, given in either winding order.
+fn convex_polygon_contains(points: &[egui::Pos2], point: egui::Pos2) -> bool {
+    let sides = points.iter().zip(points.iter().cycle().skip(1)).map(|(a, b)| {
+        let (edge, to_point) = (*b - *a, point - *a);
+        edge.x * to_point.y - edge.y * to_point.x
+    });
+    let (mut positive, mut negative) = (false, false);
+    for side in sides {
+        positive |= side > 0.0;
+        negative |= side < 0.0;
+    }
+    !(positive && negative)
+}
+
+/// Whether the display range follows the image min/max rather than the fixed range.
+fn uses_auto_minmax(shader: &ShaderParams) -> bool {
+    shader.auto_minmax && !shader.use_per_channel || shader.auto_minmax_channels.iter().any(|&b| b)
+}
+
+/// Cells of a `columns`×`rows` grid over `rect`, row by row, with their edges on device pixels.
+fn grid_pane_rects(rect: egui::Rect, columns: usize, rows: usize, pixels_per_point: f32) -> Vec<egui::Rect> {
+    let edge = |from: f32, to: f32, index: usize, count: usize| {
+        let pos = from + (to - from) * index as f32 / count as f32;
+        (pos * pixels_per_point).round() / pixels_per_point
+    };
+    (0..rows)
+        .flat_map(|row| {
+            (0..columns).map(move |column| {
+                egui::Rect::from_min_max(
+                    egui::pos2(
+                        edge(rect.min.x, rect.max.x, column, columns),
+                        edge(rect.min.y, rect.max.y, row, rows),
+                    ),
+                    egui::pos2(
+                        edge(rect.min.x, rect.max.x, column + 1, columns),
+                        edge(rect.min.y, rect.max.y, row + 1, rows),
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+/// `rect` with its edges moved to the nearest device pixel boundaries.
+fn snap_to_device_pixels(rect: egui::Rect, pixels_per_point: f32) -> egui::Rect {
+    egui::Rect::from_min_max(
+        ((rect.min.to_vec2() * pixels_per_point).round() / pixels_per_point).to_pos2(),
+        ((rect.max.to_vec2() * pixels_per_point).round() / pixels_per_point).to_pos2(),
+    )
+}
+
 fn min_max_compare_epsilon(dtype: PixelType) -> f32 {
     match dtype {
         PixelType::F16 => 0.000_976_562_5,
@@ -63,10 +268,42 @@ pub struct ImageViewer {
     dragging: bool,
     drag_mode: DragMode,
     copy_requested: Option<String>,
+    copy_target: CopyTarget,
+    /// Export written for a drag-out gesture, waiting for the UI thread to start the platform drag.
+    drag_out_file: Arc<Mutex<Option<PathBuf>>>,
+    print_requested: Option<PrintLayout>,
     save_dialog_requested: bool,
     save_requested: Option<(PathBuf, String)>,
     export_toasts: Arc<Mutex<Vec<ExportToast>>>,
     last_viewport_size_px: Option<egui::Vec2>,
+    /// Scale factor of the previous frame, to keep the view in place when the window moves to another monitor.
+    last_pixels_per_point: Option<f32>,
+    /// Round the pan to whole device pixels at integer zooms so image pixels stay crisp.
+    snap_to_pixel_grid: bool,
+    selection_step: i32,
+    mouse_bindings: MouseBindings,
+    channel_math_input: String,
+    annotation_draft: Option<AnnotationStroke>,
+    /// Colored layer of the segmentation mask and the mask revision it shows.
+    mask_texture: Option<(u64, egui::TextureHandle)>,
+    /// Force of the pen or touch contact that is down, when the device reports one.
+    pen_pressure: Option<f32>,
+    wand_rx: Option<mpsc::Receiver<Option<SelectionMask>>>,
+    /// Bounding box of the image content for "Expand to Content", scanned on a worker thread.
+    content_rect_rx: Option<mpsc::Receiver<Result<Option<Recti>>>>,
+    /// Result of a plugin filter running on a worker thread, with the name of the new asset.
+    filter_rx: Option<mpsc::Receiver<(String, Result<ImageData>)>>,
+    /// Transient on-screen message, e.g. the new exposure after a shortcut.
+    osd: Option<(String, Instant)>,
+    /// Animate fit and center actions instead of jumping to the new view.
+    animate_view: bool,
+    view_animation: Option<ViewAnimation>,
+    /// Create the renderer in safe mode, see [`GpuRenderer::set_safe_mode`].
+    safe_mode: bool,
+    /// The grid comparison was shown last frame; entering it fits the first image into a pane.
+    grid_shown: bool,
+    /// Frame size and receiver of a viewport render requested by a running recording.
+    view_capture: Option<((u32, u32), ExportCompletion)>,
 
     last_shader_error: Option<String>,
     last_reported_shader_error: Option<String>,
@@ -81,10 +318,30 @@ impl ImageViewer {
             dragging: false,
             drag_mode: DragMode::None,
             copy_requested: None,
+            copy_target: CopyTarget::Clipboard,
+            drag_out_file: Arc::new(Mutex::new(None)),
+            print_requested: None,
             save_dialog_requested: false,
             save_requested: None,
             export_toasts: Arc::new(Mutex::new(Vec::new())),
             last_viewport_size_px: None,
+            last_pixels_per_point: None,
+            snap_to_pixel_grid: true,
+            selection_step: 1,
+            mouse_bindings: MouseBindings::default(),
+            channel_math_input: String::new(),
+            annotation_draft: None,
+            mask_texture: None,
+            pen_pressure: None,
+            wand_rx: None,
+            content_rect_rx: None,
+            filter_rx: None,
+            osd: None,
+            animate_view: true,
+            view_animation: None,
+            safe_mode: false,
+            grid_shown: false,
+            view_capture: None,
             last_shader_error: None,
             last_reported_shader_error: None,
         }
@@ -105,6 +362,15 @@ impl ImageViewer {
             });
             return;
         };
+        self.start_pending_drag_out(ui, frame);
+        self.step_view_animation(ui.ctx());
+        // Recordings only capture the single and split views; a frame requested during the grid is dropped.
+        let view_capture = self.view_capture.take();
+        if let Some(grid_assets) = app_state.grid_assets() {
+            self.show_grid(ui, frame, app_state, &grid_assets);
+            return;
+        }
+        self.grid_shown = false;
         let split_view = self.is_split_comparison(app_state);
         let primary_asset = app_state.asset_primary.clone().unwrap_or_else(|| asset.clone());
         let secondary_asset = if split_view {
@@ -116,11 +382,16 @@ impl ImageViewer {
         let secondary_image = secondary_asset.as_ref().map(|a| a.image());
         let image = asset.image();
         let render_primary_image = if split_view { primary_image } else { image };
+        let heatmap_asset = app_state.heatmap_asset();
+        let heatmap_image = heatmap_asset.as_ref().map(|a| a.image());
 
         // Determine if we need a (re)upload
         let spec = render_primary_image.spec();
-        let use_auto_minmax = app_state.shader_params.auto_minmax && !app_state.shader_params.use_per_channel
-            || app_state.shader_params.auto_minmax_channels.iter().any(|&b| b);
+        // Readouts, picks, selections and exports wait for the full decode: the pixels of a reduced loading preview
+        // do not line up with the full-image coordinates the view maps to.
+        let showing_preview =
+            spec.preview_scale > 1 || secondary_image.is_some_and(|image| image.spec().preview_scale > 1);
+        let use_auto_minmax = uses_auto_minmax(&app_state.shader_params);
         let min_max_primary = if use_auto_minmax {
             render_primary_image.minmax().clone()
         } else {
@@ -135,26 +406,14 @@ impl ImageViewer {
         };
 
         let gpu_ready = frame.wgpu_render_state().is_some_and(|render_state| {
-            let mut egui_renderer = render_state.renderer.write();
-            let resources = &mut egui_renderer.callback_resources;
-            if resources.get::<GpuRenderer>().is_none() {
-                match GpuRenderer::new(
-                    &render_state.device,
-                    render_state.target_format,
-                    render_state.adapter.get_info().backend == wgpu::Backend::Dx12,
-                ) {
-                    Ok(renderer) => {
-                        resources.insert(renderer);
-                    }
-                    Err(error) => {
-                        self.last_shader_error = Some(error.to_string());
-                        return false;
-                    }
-                }
+            if !self.ensure_gpu_renderer(render_state) {
+                return false;
             }
-            let Some(renderer) = resources.get_mut::<GpuRenderer>() else {
+            let mut egui_renderer = render_state.renderer.write();
+            let Some(renderer) = egui_renderer.callback_resources.get_mut::<GpuRenderer>() else {
                 return false;
             };
+            renderer.release_grid_images(0);
             if let Err(error) = renderer.sync_image(
                 &render_state.device,
                 &render_state.queue,
@@ -170,6 +429,18 @@ impl ImageViewer {
                 self.last_shader_error = Some(error.to_string());
                 return false;
             }
+            if let Err(error) =
+                renderer.sync_image(&render_state.device, &render_state.queue, ImageSlot::Heatmap, heatmap_image)
+            {
+                self.last_shader_error = Some(error.to_string());
+                return false;
+            }
+            renderer.sync_display_luts(
+                &render_state.device,
+                &render_state.queue,
+                app_state.look_lut.as_ref().map(|(_, lut)| lut),
+                app_state.output_lut.as_ref(),
+            );
             true
         });
 
@@ -178,11 +449,15 @@ impl ImageViewer {
             // Enable both drag (for panning / marquee) and click (for context menu)
             let (rect, resp) = ui.allocate_exact_size(available_points, egui::Sense::click_and_drag());
             let pixel_per_point = ui.ctx().pixels_per_point();
+            // The GPU viewport starts on a whole device pixel, so overlays must use the same snapped rect.
+            let rect = snap_to_device_pixels(rect, pixel_per_point);
             let rect_pixels = rect * pixel_per_point;
+            let viewport_size_px = vec2(rect_pixels.width(), rect_pixels.height());
+            self.follow_pixels_per_point(pixel_per_point, viewport_size_px);
 
             // Record viewport size in pixels for fit/center operations triggered from menus
-            self.last_viewport_size_px = Some(vec2(rect_pixels.width(), rect_pixels.height()));
-            let (left_pane_rect, right_pane_rect) = self.split_pane_rects(rect);
+            self.last_viewport_size_px = Some(viewport_size_px);
+            let (left_pane_rect, right_pane_rect) = self.split_pane_rects(rect, pixel_per_point);
             let active_primary_rect = if split_view { left_pane_rect } else { rect };
 
             // Pre-compute selection rect in view space (points) for handle interactions
@@ -221,11 +496,15 @@ impl ImageViewer {
                         self.view_to_image_coords(pointer_pos, rect, pixel_per_point, split_view);
                     let pixel_pos = vec2i(image_pos.x as i32, image_pos.y as i32);
                     // Check if coordinates are within image bounds
-                    if pixel_pos.x >= 0 && pixel_pos.x < spec.width && pixel_pos.y >= 0 && pixel_pos.y < spec.height {
+                    let in_bounds =
+                        pixel_pos.x >= 0 && pixel_pos.x < spec.width && pixel_pos.y >= 0 && pixel_pos.y < spec.height;
+                    if in_bounds && !showing_preview {
                         app_state.cursor_pos = Some(pixel_pos);
+                        app_state.cursor_subpixel = Some(image_pos);
                         app_state.cursor_on_secondary = cursor_on_secondary;
                     } else {
                         app_state.cursor_pos = None;
+                        app_state.cursor_subpixel = None;
                         app_state.cursor_on_secondary = split_view && cursor_on_secondary;
                     }
 
@@ -281,10 +560,33 @@ impl ImageViewer {
                 } else {
                     format!("Save Image As... ({})", crate::res::SAVE_IMAGE_SC.format_sys())
                 };
+                let copy_file_label = if has_selection {
+                    "Copy Selected Image as File"
+                } else {
+                    "Copy Image as File"
+                };
+                if ui
+                    .button(copy_file_label)
+                    .on_hover_text("Copy a temporary PNG file, for apps that only accept dropped or pasted files")
+                    .clicked()
+                {
+                    self.request_copy_file(if split_view && app_state.cursor_on_secondary {
+                        "secondary".to_string()
+                    } else if split_view {
+                        "primary".to_string()
+                    } else {
+                        "image".to_string()
+                    });
+                    ui.ctx().request_repaint();
+                    ui.close();
+                }
                 if ui.button(save_label).clicked() {
                     self.request_save_dialog();
                     ui.close();
                 }
+                ui.menu_button("Export Options", |ui| export_options_ui(ui, &mut app_state.export_options))
+                    .response
+                    .on_hover_text("What copies and saves write: the display render or the stored data");
                 ui.separator();
                 if ui.button("Copy Cursor Color").clicked() {
                     if let Some(cursor_pos) = app_state.cursor_pos {
@@ -323,6 +625,148 @@ impl ImageViewer {
                     }
                     ui.close();
                 }
+                ui.menu_button("Selection", |ui| {
+                    ui.label("Shift selection tool:");
+                    for tool in SelectionTool::ALL {
+                        if ui.radio_value(&mut app_state.selection_tool, tool, tool.label()).changed() {
+                            app_state.selection_draft.clear();
+                        }
+                    }
+                    match app_state.selection_tool {
+                        SelectionTool::Polygon => {
+                            ui.weak("Shift+click to add points, double-click to close.");
+                        }
+                        SelectionTool::Wand => {
+                            ui.horizontal(|ui| {
+                                ui.label("Tolerance");
+                                ui.add(
+                                    egui::DragValue::new(&mut app_state.wand_tolerance)
+                                        .range(0.0..=1.0)
+                                        .speed(0.001)
+                                        .max_decimals(4),
+                                )
+                                .on_hover_text("Per-channel tolerance in normalized values (0-1 for integer images).");
+                            });
+                            ui.weak("Shift+click to select a connected region.");
+                        }
+                        SelectionTool::Rectangle | SelectionTool::Lasso => {}
+                    }
+                    ui.separator();
+                    let rect = app_state.marquee_rect;
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut self.selection_step).range(1..=4096).suffix(" px"));
+                        if ui.add_enabled(has_selection, egui::Button::new("Grow")).clicked() {
+                            app_state.set_marquee_rect(rect.expand(self.selection_step));
+                        }
+                        if ui.add_enabled(has_selection, egui::Button::new("Shrink")).clicked() {
+                            app_state.set_marquee_rect(rect.expand(-self.selection_step));
+                        }
+                    });
+                    ui.separator();
+                    if ui.add_enabled(has_selection, egui::Button::new("Snap to Even Size")).clicked() {
+                        app_state.set_marquee_rect(rect.snap_size_to_multiple(2));
+                        ui.close();
+                    }
+                    if ui.add_enabled(has_selection, egui::Button::new("Snap to 8×8 Blocks")).clicked() {
+                        app_state.set_marquee_rect(rect.snap_to_grid(8));
+                        ui.close();
+                    }
+                    if ui
+                        .add_enabled(self.content_rect_rx.is_none(), egui::Button::new("Expand to Content"))
+                        .clicked()
+                    {
+                        let content_image = active_image.clone();
+                        let repaint_ctx = ui.ctx().clone();
+                        let (tx, rx) = mpsc::channel();
+                        std::thread::spawn(move || {
+                            let _ = tx.send(content_image.content_rect());
+                            repaint_ctx.request_repaint();
+                        });
+                        self.content_rect_rx = Some(rx);
+                        ui.close();
+                    }
+                    ui.separator();
+                    let mask = app_state.selection_mask.clone();
+                    if ui
+                        .add_enabled(mask.is_some(), egui::Button::new("Save Selection Mask as PNG..."))
+                        .clicked()
+                    {
+                        if let Some(mask) = mask {
+                            self.save_selection_mask(mask, spec.width, spec.height, ui.ctx().clone());
+                        }
+                        ui.close();
+                    }
+                });
+
+                let active_name = if split_view && app_state.cursor_on_secondary {
+                    secondary_asset.as_ref().map(|a| a.name()).unwrap_or(primary_asset.name())
+                } else if split_view {
+                    primary_asset.name()
+                } else {
+                    asset.name()
+                };
+                ui.menu_button("Channel Math", |ui| {
+                    let mut expression = None;
+                    ui.add_enabled_ui(self.filter_rx.is_none(), |ui| {
+                        for (label, preset) in CHANNEL_MATH_PRESETS {
+                            if ui.button(label).on_hover_text(preset).clicked() {
+                                expression = Some(preset.to_string());
+                            }
+                        }
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            let edit = ui.add(
+                                egui::TextEdit::singleline(&mut self.channel_math_input)
+                                    .hint_text("(r - g) / mean()")
+                                    .desired_width(140.0),
+                            );
+                            let submitted = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                            if ui.button("Apply").clicked() || submitted {
+                                expression = Some(self.channel_math_input.trim().to_string());
+                            }
+                        })
+                        .response
+                        .on_hover_text(
+                            "Channels r, g, b, a or c0, c1, ...; operators + - * /; functions min, max, mean, abs. \
+                             min(), max() and mean() use the color channels.",
+                        );
+                    });
+                    if let Some(expression) = expression.filter(|expression| !expression.is_empty()) {
+                        let input = active_image.clone();
+                        let name = format!("{expression} ({active_name})");
+                        let repaint_ctx = ui.ctx().clone();
+                        let (tx, rx) = mpsc::channel();
+                        std::thread::spawn(move || {
+                            let result = ChannelExpr::parse(&expression).and_then(|expr| expr.apply(&input));
+                            let _ = tx.send((name, result));
+                            repaint_ctx.request_repaint();
+                        });
+                        self.filter_rx = Some(rx);
+                        ui.close();
+                    }
+                });
+
+                let registry = crate::plugin::plugins();
+                if registry.plugins.iter().any(|plugin| !plugin.filters.is_empty()) {
+                    ui.menu_button("Filters", |ui| {
+                        for plugin in &registry.plugins {
+                            for (index, filter) in plugin.filters.iter().enumerate() {
+                                if ui.add_enabled(self.filter_rx.is_none(), egui::Button::new(filter)).clicked() {
+                                    let input = active_image.clone();
+                                    let name = format!("{filter} ({active_name})");
+                                    let repaint_ctx = ui.ctx().clone();
+                                    let (tx, rx) = mpsc::channel();
+                                    std::thread::spawn(move || {
+                                        let _ = tx.send((name, plugin.apply_filter(index, &input)));
+                                        repaint_ctx.request_repaint();
+                                    });
+                                    self.filter_rx = Some(rx);
+                                    ui.close();
+                                }
+                            }
+                        }
+                    });
+                }
             });
 
             // Begin interactions
@@ -330,18 +774,20 @@ impl ImageViewer {
             if !self.dragging && resp.hovered() && ui.input(|i| i.pointer.primary_pressed()) {
                 if let Some(pos) = ui.input(|i| i.pointer.hover_pos()) {
                     // If a marquee exists and a corner handle is pressed, start resizing right away
-                    let handle_under_mouse =
-                        if app_state.marquee_rect.width() > 0 && app_state.marquee_rect.height() > 0 {
-                            hit_test_resize_handle_with_rects(
-                                selection_rect_view,
-                                selection_rect_primary_clipped,
-                                secondary_selection_rect_view,
-                                selection_rect_secondary_clipped,
-                                pos,
-                            )
-                        } else {
-                            None
-                        };
+                    let handle_under_mouse = if !showing_preview
+                        && app_state.marquee_rect.width() > 0
+                        && app_state.marquee_rect.height() > 0
+                    {
+                        hit_test_resize_handle_with_rects(
+                            selection_rect_view,
+                            selection_rect_primary_clipped,
+                            secondary_selection_rect_view,
+                            selection_rect_secondary_clipped,
+                            pos,
+                        )
+                    } else {
+                        None
+                    };
 
                     if let Some((handle, selection_rect_view, selection_rect_clipped)) = handle_under_mouse {
                         let (start_pointer_image_pos, start_on_secondary) =
@@ -365,55 +811,251 @@ impl ImageViewer {
                 }
             }
 
-            if !self.dragging && resp.drag_started() {
+            // Eyedropper: a plain click sets the range end or the white balance from the clicked pixel.
+            if app_state.point_picker.is_some() && !showing_preview {
+                if resp.hovered() {
+                    ui.output_mut(|o| o.cursor_icon = egui::CursorIcon::Crosshair);
+                }
+                if let Some(pos) = resp.interact_pointer_pos().filter(|_| resp.clicked()) {
+                    let (image_pos, on_secondary) = self.view_to_image_coords(pos, rect, pixel_per_point, split_view);
+                    let picked_image = match secondary_image {
+                        Some(secondary_image) if on_secondary => secondary_image,
+                        _ => render_primary_image,
+                    };
+                    let pixel = vec2i(image_pos.x.floor() as i32, image_pos.y.floor() as i32);
+                    let picked = if app_state.point_picker.is_some_and(|p| p.target == PointPickTarget::Neutral) {
+                        app_state.apply_picked_neutral(picked_image, pixel)
+                    } else {
+                        picked_image
+                            .get_pixel_at(pixel.x, pixel.y)
+                            .map(|values| values.to_vec())
+                            .and_then(|values| app_state.apply_picked_point(&values))
+                    };
+                    if let Err(e) = picked {
+                        eprintln!("Failed to pick display point: {e}");
+                        if let Ok(mut toasts) = self.export_toasts.lock() {
+                            toasts.push(ExportToast::Error(format!("Failed to pick point: {e}")));
+                        }
+                    }
+                }
+            }
+
+            // Magic wand: Shift+click selects the connected region around the clicked pixel on a worker thread.
+            if app_state.selection_tool == SelectionTool::Wand
+                && ui.input(|i| i.modifiers.shift)
+                && resp.clicked()
+                && self.wand_rx.is_none()
+                && !showing_preview
+            {
+                if let Some(pos) = resp.interact_pointer_pos() {
+                    let (image_pos, on_secondary) = self.view_to_image_coords(pos, rect, pixel_per_point, split_view);
+                    let wand_image = match secondary_image {
+                        Some(secondary_image) if on_secondary => secondary_image.clone(),
+                        _ => render_primary_image.clone(),
+                    };
+                    let seed = vec2i(image_pos.x.floor() as i32, image_pos.y.floor() as i32);
+                    let wand_spec = wand_image.spec();
+                    if seed.x >= 0 && seed.y >= 0 && seed.x < wand_spec.width && seed.y < wand_spec.height {
+                        let tolerance = app_state.wand_tolerance;
+                        let repaint_ctx = ui.ctx().clone();
+                        let (tx, rx) = mpsc::channel();
+                        std::thread::spawn(move || {
+                            let _ = tx.send(SelectionMask::from_flood_fill(&wand_image, seed, tolerance));
+                            repaint_ctx.request_repaint();
+                        });
+                        self.wand_rx = Some(rx);
+                    }
+                }
+            }
+            if let Some(rx) = &self.wand_rx {
+                match rx.try_recv() {
+                    Ok(Some(mask)) => {
+                        app_state.set_selection_mask(Some(mask));
+                        self.wand_rx = None;
+                    }
+                    Ok(None) => {
+                        eprintln!("Magic wand: CPU pixel data is unavailable for this image");
+                        if let Ok(mut toasts) = self.export_toasts.lock() {
+                            toasts.push(ExportToast::Error("Magic wand is unavailable for this image".to_string()));
+                        }
+                        self.wand_rx = None;
+                    }
+                    Err(mpsc::TryRecvError::Empty) => {}
+                    Err(mpsc::TryRecvError::Disconnected) => self.wand_rx = None,
+                }
+            }
+            if let Some(rx) = &self.content_rect_rx {
+                match rx.try_recv() {
+                    Ok(Ok(Some(content))) => {
+                        app_state.set_marquee_rect(content);
+                        self.content_rect_rx = None;
+                    }
+                    Ok(Ok(None)) => {
+                        self.content_rect_rx = None;
+                        self.show_osd("No content to expand to");
+                    }
+                    Ok(Err(e)) => {
+                        eprintln!("Expand to Content failed: {e}");
+                        if let Ok(mut toasts) = self.export_toasts.lock() {
+                            toasts.push(ExportToast::Error(format!("Expand to Content failed: {e}")));
+                        }
+                        self.content_rect_rx = None;
+                    }
+                    Err(mpsc::TryRecvError::Empty) => {}
+                    Err(mpsc::TryRecvError::Disconnected) => self.content_rect_rx = None,
+                }
+            }
+
+            if let Some(rx) = &self.filter_rx {
+                match rx.try_recv() {
+                    Ok((name, Ok(filtered))) => {
+                        app_state.set_primary_asset(Arc::new(FilteredAsset::new(&name, filtered)));
+                        self.filter_rx = None;
+                    }
+                    Ok((name, Err(e))) => {
+                        eprintln!("Failed to apply {name}: {e}");
+                        if let Ok(mut toasts) = self.export_toasts.lock() {
+                            toasts.push(ExportToast::Error(format!("Failed to apply {name}: {e}")));
+                        }
+                        self.filter_rx = None;
+                    }
+                    Err(mpsc::TryRecvError::Empty) => {}
+                    Err(mpsc::TryRecvError::Disconnected) => self.filter_rx = None,
+                }
+            }
+
+            // Polygon tool: Shift+click adds a vertex; double-click or clicking the first vertex closes it.
+            if app_state.selection_tool == SelectionTool::Polygon && ui.input(|i| i.modifiers.shift) && !showing_preview
+            {
+                if let Some(pos) = resp.interact_pointer_pos().filter(|_| resp.clicked() || resp.double_clicked()) {
+                    let (image_pos, _) = self.view_to_image_coords(pos, rect, pixel_per_point, split_view);
+                    let close_radius = 6.0 * pixel_per_point / self.zoom();
+                    let draft = &mut app_state.selection_draft;
+                    let closes_at_start = draft.len() >= 3 && draft[0].distance(image_pos) <= close_radius;
+                    if resp.double_clicked() || closes_at_start {
+                        if draft.len() >= 3 {
+                            let points = std::mem::take(draft);
+                            app_state.set_selection_polygon(points);
+                        }
+                    } else {
+                        draft.push(image_pos);
+                    }
+                }
+            }
+
+            self.update_pen_pressure(ui);
+            // Two-finger gestures pan and zoom in every mode, replacing any drag the first finger started.
+            let multi_touch = ui.input(|i| i.multi_touch()).filter(|_| resp.hovered() || self.dragging);
+            if let Some(touch) = multi_touch {
+                self.dragging = false;
+                self.drag_mode = DragMode::None;
+                self.annotation_draft = None;
+                self.view_animation = None;
+                self.pan += touch.translation_delta * pixel_per_point;
+                if touch.zoom_delta != 1.0 {
+                    let local = (touch.center_pos - rect.min) * pixel_per_point;
+                    self.zoom_in(touch.zoom_delta.ln() / self.zoom_base.ln(), Some(local));
+                }
+            }
+
+            if !self.dragging && resp.drag_started() && multi_touch.is_none() {
                 self.dragging = true;
                 if let Some(pos) = resp.interact_pointer_pos() {
                     // If a marquee exists and a corner handle is grabbed, start resizing
-                    let handle_under_mouse =
-                        if app_state.marquee_rect.width() > 0 && app_state.marquee_rect.height() > 0 {
-                            hit_test_resize_handle_with_rects(
-                                selection_rect_view,
-                                selection_rect_primary_clipped,
-                                secondary_selection_rect_view,
-                                selection_rect_secondary_clipped,
-                                pos,
-                            )
-                        } else {
-                            None
-                        };
+                    let handle_under_mouse = if !showing_preview
+                        && app_state.marquee_rect.width() > 0
+                        && app_state.marquee_rect.height() > 0
+                    {
+                        hit_test_resize_handle_with_rects(
+                            selection_rect_view,
+                            selection_rect_primary_clipped,
+                            secondary_selection_rect_view,
+                            selection_rect_secondary_clipped,
+                            pos,
+                        )
+                    } else {
+                        None
+                    };
 
-                    self.drag_mode =
-                        if let Some((handle, selection_rect_view, selection_rect_clipped)) = handle_under_mouse {
-                            let (start_pointer_image_pos, start_on_secondary) =
-                                self.view_to_image_coords(pos, rect, pixel_per_point, split_view);
-                            DragMode::Resizing {
+                    self.drag_mode = if app_state.annotate && !ui.input(|i| i.modifiers.shift) {
+                        // The secondary button, where tablet drivers usually map the pen's barrel button, erases.
+                        if resp.dragged_by(egui::PointerButton::Secondary) {
+                            DragMode::Erasing
+                        } else {
+                            let (image_pos, _) = self.view_to_image_coords(pos, rect, pixel_per_point, split_view);
+                            let mut stroke = AnnotationStroke::new(
+                                app_state.annotation_color,
+                                app_state.annotation_width * pixel_per_point / self.zoom(),
+                            );
+                            stroke.points.push(StrokePoint {
+                                pos: image_pos,
+                                pressure: self.pen_pressure.unwrap_or(1.0),
+                            });
+                            self.annotation_draft = Some(stroke);
+                            DragMode::Annotating
+                        }
+                    } else if let Some((handle, selection_rect_view, selection_rect_clipped)) = handle_under_mouse {
+                        let (start_pointer_image_pos, start_on_secondary) =
+                            self.view_to_image_coords(pos, rect, pixel_per_point, split_view);
+                        DragMode::Resizing {
+                            handle,
+                            start_rect: self.resize_start_rect(
+                                app_state.marquee_rect,
                                 handle,
-                                start_rect: self.resize_start_rect(
-                                    app_state.marquee_rect,
-                                    handle,
-                                    selection_rect_view,
-                                    selection_rect_clipped,
-                                    rect,
-                                    pixel_per_point,
-                                    split_view,
-                                ),
-                                start_pointer_image_pos,
-                                start_on_secondary,
-                            }
-                        } else if ui.input(|i| i.modifiers.shift) {
-                            // Start marquee creation
-                            let (start_image_pos, start_on_secondary) =
-                                self.view_to_image_coords(pos, rect, pixel_per_point, split_view);
-                            DragMode::Marquee {
-                                start_image_pos,
-                                start_on_secondary,
+                                selection_rect_view,
+                                selection_rect_clipped,
+                                rect,
+                                pixel_per_point,
+                                split_view,
+                            ),
+                            start_pointer_image_pos,
+                            start_on_secondary,
+                        }
+                    } else {
+                        let (start_image_pos, start_on_secondary) =
+                            self.view_to_image_coords(pos, rect, pixel_per_point, split_view);
+                        let button = MouseButton::ALL
+                            .into_iter()
+                            .find(|button| resp.dragged_by(button.pointer_button()));
+                        let modifiers = ui.input(|i| i.modifiers);
+                        match button.map_or(DragAction::Nothing, |button| self.mouse_bindings.action(button, modifiers))
+                        {
+                            DragAction::Select | DragAction::ColorPick if showing_preview => DragMode::None,
+                            DragAction::Select => {
+                                app_state.selection_draft.clear();
+                                if app_state.selection_tool == SelectionTool::Lasso {
+                                    // Start lasso tracing
+                                    app_state.selection_draft.push(start_image_pos);
+                                    DragMode::Lasso { start_on_secondary }
+                                } else {
+                                    // Start marquee creation
+                                    DragMode::Marquee {
+                                        start_image_pos,
+                                        start_on_secondary,
+                                    }
+                                }
                             }
-                        } else {
-                            // Start panning
-                            DragMode::Panning {
+                            DragAction::Pan => DragMode::Panning {
                                 last_pixel_pos: pos * pixel_per_point,
+                            },
+                            DragAction::ColorPick => DragMode::ColorPick {
+                                on_secondary: start_on_secondary,
+                                picked: None,
+                            },
+                            DragAction::DragOut => {
+                                let source_label = if !split_view {
+                                    "image"
+                                } else if start_on_secondary {
+                                    "secondary"
+                                } else {
+                                    "primary"
+                                };
+                                self.request_drag_out(source_label.to_string(), ui.ctx().clone());
+                                DragMode::None
                             }
-                        };
+                            DragAction::Nothing => DragMode::None,
+                        }
+                    };
                 }
             }
 
@@ -437,6 +1079,65 @@ impl ImageViewer {
                             )
                             .cond_map(is_ctrl, |image_pos| enforce_square_from_anchor(start_image_pos, image_pos));
                         app_state.set_marquee_rect(Recti::bound_two_pos(start_image_pos, image_pos));
+                    } else if let DragMode::Lasso { start_on_secondary } = self.drag_mode {
+                        let image_pos = self.view_to_image_coords_in_fixed_pane(
+                            pos,
+                            rect,
+                            pixel_per_point,
+                            split_view,
+                            start_on_secondary,
+                        );
+                        // Skip points closer than one screen point to the previous one
+                        let min_step = pixel_per_point / self.zoom();
+                        if app_state
+                            .selection_draft
+                            .last()
+                            .is_none_or(|last| last.distance(image_pos) >= min_step)
+                        {
+                            app_state.selection_draft.push(image_pos);
+                        }
+                    } else if matches!(self.drag_mode, DragMode::Annotating) {
+                        let (image_pos, _) = self.view_to_image_coords(pos, rect, pixel_per_point, split_view);
+                        let min_step = pixel_per_point / self.zoom();
+                        let pressure = self.pen_pressure.unwrap_or(1.0);
+                        if let Some(stroke) = &mut self.annotation_draft {
+                            if stroke.points.last().is_none_or(|last| last.pos.distance(image_pos) >= min_step) {
+                                stroke.points.push(StrokePoint {
+                                    pos: image_pos,
+                                    pressure,
+                                });
+                            }
+                        }
+                    } else if let DragMode::ColorPick { on_secondary, .. } = self.drag_mode {
+                        let picked_image = match secondary_image {
+                            Some(secondary_image) if on_secondary => secondary_image,
+                            _ => render_primary_image,
+                        };
+                        let image_pos = self.view_to_image_coords_in_fixed_pane(
+                            pos,
+                            rect,
+                            pixel_per_point,
+                            split_view,
+                            on_secondary,
+                        );
+                        let pixel = vec2i(image_pos.x.floor() as i32, image_pos.y.floor() as i32);
+                        if let Ok(values) = picked_image.get_pixel_at(pixel.x, pixel.y) {
+                            let values = values.to_vec();
+                            self.show_osd(format_picked_pixel(
+                                pixel,
+                                &values,
+                                spec.dtype,
+                                app_state.pixel_value.format,
+                            ));
+                            self.drag_mode = DragMode::ColorPick {
+                                on_secondary,
+                                picked: Some((pixel, values)),
+                            };
+                        }
+                    } else if matches!(self.drag_mode, DragMode::Erasing) {
+                        let (image_pos, _) = self.view_to_image_coords(pos, rect, pixel_per_point, split_view);
+                        let radius = ANNOTATION_ERASER_RADIUS * pixel_per_point / self.zoom();
+                        app_state.annotations.erase_at(primary_asset.hash(), image_pos, radius);
                     } else if let DragMode::Panning {
                         last_pixel_pos: last_pos,
                     } = self.drag_mode
@@ -444,6 +1145,7 @@ impl ImageViewer {
                         let pos = pos * pixel_per_point;
                         let delta = pos - last_pos;
                         self.drag_mode = DragMode::Panning { last_pixel_pos: pos };
+                        self.view_animation = None;
                         self.pan += egui::vec2(delta.x, delta.y);
                     } else if let DragMode::Resizing {
                         handle,
@@ -493,6 +1195,22 @@ impl ImageViewer {
 
                 // End dragging either when egui reports drag stopped, or when primary is released
                 if resp.drag_stopped() || ui.input(|i| i.pointer.primary_released()) {
+                    if matches!(self.drag_mode, DragMode::Lasso { .. }) {
+                        let points = std::mem::take(&mut app_state.selection_draft);
+                        app_state.set_selection_polygon(points);
+                    }
+                    if let Some(stroke) = self.annotation_draft.take() {
+                        app_state.annotations.add(primary_asset.hash(), stroke);
+                    }
+                    if let DragMode::ColorPick {
+                        picked: Some((pixel, values)),
+                        ..
+                    } = &self.drag_mode
+                    {
+                        let text = format_picked_pixel(*pixel, values, spec.dtype, app_state.pixel_value.format);
+                        ui.ctx().copy_text(text.clone());
+                        self.show_osd(format!("Copied {text}"));
+                    }
                     self.dragging = false;
                     self.drag_mode = DragMode::None;
                 }
@@ -509,26 +1227,125 @@ impl ImageViewer {
 
             // Queue clipboard/save export operations for the wgpu callback.
             let mut export_request: Option<PendingExportRequest> = None;
-            let copy_requested = self.copy_requested.take();
-            let save_requested = self.save_requested.take();
+            let mut metadata_source: Option<PathBuf> = None;
+            let (mut copy_requested, copy_target, mut save_requested) = if showing_preview {
+                (None, CopyTarget::default(), None)
+            } else {
+                (
+                    self.copy_requested.take(),
+                    std::mem::take(&mut self.copy_target),
+                    self.save_requested.take(),
+                )
+            };
+            // A print renders like a save to a temporary PDF at the image's own resolution.
+            let print_layout = (!showing_preview && save_requested.is_none())
+                .then(|| self.print_requested.take())
+                .flatten();
+            if print_layout.is_some() {
+                let stamp = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |elapsed| elapsed.as_millis());
+                let path = std::env::temp_dir().join("edolview").join(format!("print-{stamp}.pdf"));
+                save_requested = Some((path, "image".to_string()));
+            }
             if copy_requested.is_some() || save_requested.is_some() {
                 let export_rect = self.copy_rect(app_state, spec.width, spec.height);
-                if !export_rect.empty() {
-                    let scale_for_export = if app_state.copy_use_original_size {
-                        1.0
+                let export_options = app_state.export_options;
+                // Prints always show the display.
+                let export_data = if print_layout.is_some() {
+                    ExportData::Display
+                } else {
+                    export_options.data
+                };
+                let source_image = |source: &str| match source {
+                    "secondary" => secondary_image,
+                    _ => Some(render_primary_image),
+                };
+                if !export_rect.empty() && export_data != ExportData::Display {
+                    // Stored samples need no render: saves and file copies are encoded on a worker thread.
+                    if let Some((path, source)) = save_requested.take() {
+                        if let Some(image) = source_image(&source) {
+                            save_samples_async(
+                                path,
+                                image.clone(),
+                                export_rect,
+                                export_data,
+                                self.export_toasts.clone(),
+                                ui.ctx().clone(),
+                                source,
+                            );
+                        }
+                    }
+                    let file_only = |image: &ImageData| {
+                        !matches!(copy_target, CopyTarget::Clipboard)
+                            || export_data.sample_extension(image.spec().dtype) != Some("png")
+                    };
+                    if let Some(source) = copy_requested.take_if(|source| source_image(source).is_some_and(file_only)) {
+                        if let Some(image) = source_image(&source) {
+                            copy_samples_as_file_async(
+                                image.clone(),
+                                export_rect,
+                                export_data,
+                                copy_target.clone(),
+                                self.export_toasts.clone(),
+                                ui.ctx().clone(),
+                                source,
+                            );
+                        }
+                    }
+                }
+                if export_rect.empty() {
+                    if let Ok(mut toasts) = self.export_toasts.lock() {
+                        toasts.push(ExportToast::Error(
+                            "Nothing to export: the region is outside the image".to_string(),
+                        ));
+                    }
+                } else if copy_requested.is_some() || save_requested.is_some() {
+                    let size = if export_data != ExportData::Display || print_layout.is_some() {
+                        ExportSize::Original
                     } else {
-                        self.zoom()
+                        export_options.size
                     };
-                    let out_w = (export_rect.width() as f32 * scale_for_export).round().max(1.0) as i32;
-                    let out_h = (export_rect.height() as f32 * scale_for_export).round().max(1.0) as i32;
+                    let layout = ExportOptions { size, ..export_options }
+                        .layout((export_rect.width() as u32, export_rect.height() as u32), self.zoom());
+                    let scale_for_export = layout.scale;
+                    let (out_w, out_h) = (layout.width as i32, layout.height as i32);
                     let position = egui::vec2(
-                        -(export_rect.min.x as f32) * scale_for_export,
-                        -(export_rect.min.y as f32) * scale_for_export,
+                        -(export_rect.min.x as f32) * scale_for_export - layout.offset.0,
+                        -(export_rect.min.y as f32) * scale_for_export - layout.offset.1,
                     );
-                    export_request = Some((copy_requested, save_requested, out_w, out_h, position, scale_for_export));
+                    // Sample copies that fit a PNG go on the clipboard next to the display render, for apps that
+                    // only read plain images.
+                    let sample_png = copy_requested
+                        .as_deref()
+                        .and_then(source_image)
+                        .filter(|_| export_data != ExportData::Display)
+                        .map(|image| (image.clone(), export_rect, export_data));
+                    // Metadata is read from the exported pane's file, and only when the user keeps it.
+                    let export_asset = match save_requested.as_ref().map(|(_, source)| source.as_str()) {
+                        Some("secondary") => secondary_asset.as_ref(),
+                        Some(_) => Some(&primary_asset),
+                        None if copy_requested.as_deref() == Some("secondary") => secondary_asset.as_ref(),
+                        None => Some(&primary_asset),
+                    };
+                    metadata_source = export_asset
+                        .filter(|_| export_options.metadata == ExportMetadata::Preserve && print_layout.is_none())
+                        .filter(|asset| asset.asset_type() == AssetType::File)
+                        .map(|asset| PathBuf::from(asset.name()));
+                    export_request = Some((
+                        copy_requested,
+                        save_requested,
+                        out_w,
+                        out_h,
+                        position,
+                        scale_for_export,
+                        sample_png,
+                    ));
                 }
             }
 
+            self.snap_pan_to_pixel_grid();
+
             if let Some(render_state) = frame.wgpu_render_state() {
                 let viewport_size = vec2(rect_pixels.width(), rect_pixels.height());
                 let left_pane_pixels = left_pane_rect * pixel_per_point;
@@ -536,20 +1353,28 @@ impl ImageViewer {
                 let pane_viewport_size = vec2(left_pane_pixels.width(), left_pane_pixels.height());
                 let image_size = vec2(spec.width as f32, spec.height as f32);
 
-                let scale = self.zoom();
+                let scale = draw_scale(self.zoom(), &spec);
                 let position = self.pan;
 
                 let visuals = ui.visuals().clone();
-                let shader_params = app_state.shader_params.clone();
-                let channel_index = app_state.channel_index;
+                let alpha_channel = app_state.alpha_view.then(|| spec.alpha_channel()).flatten();
+                let shader_params = if alpha_channel.is_some() {
+                    ShaderParams::default()
+                } else {
+                    app_state.shader_params.clone()
+                };
+                let channel_index = alpha_channel.map_or(app_state.channel_index, |channel| channel as i32);
 
-                let is_mono = channel_index != -1 || spec.channels == 1;
-                let colormap = if is_mono {
+                let is_mono = shader_params.is_mono(&spec, channel_index);
+                let colormap = if alpha_channel.is_some() {
+                    "gray".to_string()
+                } else if is_mono {
                     app_state.colormap_mono.clone()
                 } else {
                     app_state.colormap_rgb.clone()
                 };
                 let is_show_background = app_state.is_show_background;
+                let background_style = background_style(&app_state.background, &visuals);
                 let export_toasts = self.export_toasts.clone();
                 let repaint_ctx = ui.ctx().clone();
                 let render_primary_asset_hash = if split_view {
@@ -578,10 +1403,11 @@ impl ImageViewer {
                         )
                     })
                     .unwrap_or_default();
-                let disabled_min_max_overlay = MinMaxOverlay::default();
                 let mut egui_renderer = render_state.renderer.write();
                 if let Some(renderer) = egui_renderer.callback_resources.get_mut::<GpuRenderer>() {
                     renderer.update_colormap(&render_state.device, colormap.as_str(), is_mono);
+                    renderer.set_magnification_filter(app_state.magnification_filter);
+                    renderer.set_proofing(app_state.proofing.gains());
                     renderer.write_params(
                         &render_state.queue,
                         0,
@@ -593,8 +1419,9 @@ impl ImageViewer {
                         position,
                         &shader_params,
                         &primary_min_max_overlay,
-                        visuals.extreme_bg_color,
-                        visuals.faint_bg_color,
+                        &background_style,
+                        true,
+                        &spec,
                     );
                     if split_view {
                         renderer.write_params(
@@ -608,13 +1435,31 @@ impl ImageViewer {
                             position,
                             &shader_params,
                             &secondary_min_max_overlay,
-                            visuals.extreme_bg_color,
-                            visuals.faint_bg_color,
+                            &background_style,
+                            true,
+                            &spec,
+                        );
+                    }
+                    if let Some((heatmap, heatmap_image)) = app_state.heatmap.as_ref().zip(heatmap_image) {
+                        renderer.update_heatmap_colormap(&render_state.device, &heatmap.colormap);
+                        let heatmap_min_max = if heatmap.auto_range {
+                            heatmap_image.minmax().clone()
+                        } else {
+                            empty_minmax()
+                        };
+                        renderer.write_heatmap_params(
+                            &render_state.queue,
+                            if split_view { pane_viewport_size } else { viewport_size },
+                            image_size,
+                            scale,
+                            position,
+                            heatmap.range(&heatmap_min_max),
+                            heatmap.opacity,
                         );
                     }
 
-                    let export =
-                        export_request.map(|(copy_requested, save_requested, out_w, out_h, crop_pos, export_scale)| {
+                    let export = export_request.map(
+                        |(copy_requested, save_requested, out_w, out_h, crop_pos, export_scale, sample_png)| {
                             let export_secondary = save_requested
                                 .as_ref()
                                 .map(|(_, source)| source == "secondary")
@@ -625,9 +1470,7 @@ impl ImageViewer {
                             } else {
                                 ImageSlot::Primary
                             };
-                            renderer.write_params(
-                                &render_state.queue,
-                                2,
+                            let params = renderer.export_params(
                                 egui::vec2(out_w as f32, out_h as f32),
                                 image_size,
                                 channel_index,
@@ -639,9 +1482,8 @@ impl ImageViewer {
                                 export_scale,
                                 crop_pos,
                                 &shader_params,
-                                &disabled_min_max_overlay,
-                                visuals.extreme_bg_color,
-                                visuals.faint_bg_color,
+                                &background_style,
+                                &spec,
                             );
 
                             let completion_toasts = export_toasts.clone();
@@ -650,6 +1492,7 @@ impl ImageViewer {
                                 width: out_w as u32,
                                 height: out_h as u32,
                                 slot,
+                                params,
                                 completion: Arc::new(move |result| match result {
                                     Ok(rgba) => match (&copy_requested, &save_requested) {
                                         (Some(copy_source), Some((path, save_source))) => {
@@ -657,6 +1500,9 @@ impl ImageViewer {
                                                 out_w,
                                                 out_h,
                                                 rgba.clone(),
+                                                sample_png.as_ref(),
+                                                &copy_target,
+                                                metadata_source.as_deref(),
                                                 &completion_toasts,
                                                 copy_source,
                                             );
@@ -665,19 +1511,37 @@ impl ImageViewer {
                                                 out_w,
                                                 out_h,
                                                 rgba,
+                                                metadata_source.clone(),
                                                 completion_toasts.clone(),
                                                 completion_repaint.clone(),
                                                 save_source.clone(),
                                             );
                                         }
-                                        (Some(copy_source), None) => {
-                                            copy_image_to_clipboard(out_w, out_h, rgba, &completion_toasts, copy_source)
-                                        }
+                                        (Some(copy_source), None) => copy_image_to_clipboard(
+                                            out_w,
+                                            out_h,
+                                            rgba,
+                                            sample_png.as_ref(),
+                                            &copy_target,
+                                            metadata_source.as_deref(),
+                                            &completion_toasts,
+                                            copy_source,
+                                        ),
+                                        (None, Some((path, _))) if print_layout.is_some() => print_image_async(
+                                            path.clone(),
+                                            out_w,
+                                            out_h,
+                                            rgba,
+                                            print_layout.unwrap_or_default(),
+                                            completion_toasts.clone(),
+                                            completion_repaint.clone(),
+                                        ),
                                         (None, Some((path, save_source))) => save_image_async(
                                             path.clone(),
                                             out_w,
                                             out_h,
                                             rgba,
+                                            metadata_source.clone(),
                                             completion_toasts.clone(),
                                             completion_repaint.clone(),
                                             save_source.clone(),
@@ -692,7 +1556,33 @@ impl ImageViewer {
                                     }
                                 }),
                             }
-                        });
+                        },
+                    );
+                    // A copy or save this frame takes the export slot; the recording skips the frame.
+                    let export = export.or_else(|| {
+                        let ((out_w, out_h), completion) = view_capture?;
+                        let source = if split_view { pane_viewport_size } else { viewport_size };
+                        let out_size = vec2(out_w as f32, out_h as f32);
+                        let fit = (out_size.x / source.x).min(out_size.y / source.y);
+                        let offset = (out_size - source * fit) * 0.5;
+                        Some(ExportRequest {
+                            width: out_w,
+                            height: out_h,
+                            slot: ImageSlot::Primary,
+                            params: renderer.export_params(
+                                out_size,
+                                image_size,
+                                channel_index,
+                                &min_max_primary,
+                                scale * fit,
+                                position * fit + offset,
+                                &shader_params,
+                                &background_style,
+                                &spec,
+                            ),
+                            completion,
+                        })
+                    });
                     self.last_shader_error = renderer.last_error().map(str::to_owned);
                     if self.last_shader_error.is_none() {
                         self.last_reported_shader_error = None;
@@ -702,25 +1592,102 @@ impl ImageViewer {
                         viewport_px: if split_view { left_pane_pixels } else { rect_pixels },
                         slot: ImageSlot::Primary,
                         uniform_slot: 0,
+                        // The min/max overlay compares exact texels, so it needs the full-resolution texture.
+                        pyramid_zoom: (!primary_min_max_overlay.enabled).then_some(scale),
                     }];
                     if split_view {
                         panes.push(PaneDraw {
                             viewport_px: right_pane_pixels,
                             slot: ImageSlot::Secondary,
                             uniform_slot: 1,
+                            pyramid_zoom: (!secondary_min_max_overlay.enabled).then_some(scale),
                         });
                     }
+                    if heatmap_image.is_some() {
+                        // The heatmap is stretched over the image of every pane.
+                        let heatmap_panes: Vec<_> = panes
+                            .iter()
+                            .map(|pane| PaneDraw {
+                                slot: ImageSlot::Heatmap,
+                                uniform_slot: HEATMAP_PARAM_SLOT,
+                                pyramid_zoom: Some(scale),
+                                ..*pane
+                            })
+                            .collect();
+                        panes.extend(heatmap_panes);
+                    }
+
+                    let ramp_rects = shader_params.lightness_only.then(|| colormap_ramp_rects(rect));
+                    let mut ramp_panes = Vec::new();
+                    if let Some((color_ramp_rect, lightness_ramp_rect)) = ramp_rects {
+                        for (ramp_rect, uniform_slot, lightness_only) in [
+                            (color_ramp_rect, RAMP_COLOR_PARAM_SLOT, false),
+                            (lightness_ramp_rect, RAMP_LIGHTNESS_PARAM_SLOT, true),
+                        ] {
+                            let ramp_pixels = ramp_rect * pixel_per_point;
+                            renderer.write_ramp_params(
+                                &render_state.queue,
+                                uniform_slot,
+                                vec2(ramp_pixels.width(), ramp_pixels.height()),
+                                &shader_params,
+                                lightness_only,
+                            );
+                            ramp_panes.push(PaneDraw {
+                                viewport_px: ramp_pixels,
+                                slot: ImageSlot::Primary,
+                                uniform_slot,
+                                pyramid_zoom: None,
+                            });
+                        }
+                    }
                     drop(egui_renderer);
                     ui.painter().add(eframe::egui_wgpu::Callback::new_paint_callback(
                         rect,
                         ImagePaintCallback {
                             panes,
+                            ramp_panes,
                             show_background: is_show_background,
                             export,
                         },
                     ));
                 }
 
+                if let Some((color_ramp_rect, lightness_ramp_rect)) =
+                    shader_params.lightness_only.then(|| colormap_ramp_rects(rect))
+                {
+                    let painter = ui.painter();
+                    let font_id = egui::FontId::proportional(11.0);
+                    for (ramp_rect, label) in [(color_ramp_rect, "Colormap"), (lightness_ramp_rect, "L*")] {
+                        painter.rect_stroke(ramp_rect, 0.0, (1.0, COLORMAP_RAMP_OUTLINE), egui::StrokeKind::Outside);
+                        painter.text(
+                            ramp_rect.right_center() + vec2(6.0, 0.0),
+                            egui::Align2::LEFT_CENTER,
+                            label,
+                            font_id.clone(),
+                            COLORMAP_RAMP_LABEL,
+                        );
+                    }
+                }
+
+                // Draw a 1px outline around the image extent
+                if app_state.is_show_image_border {
+                    let painter = ui.painter();
+                    let (full_width, full_height) = spec.full_size();
+                    let image_size_px = egui::vec2(full_width as f32, full_height as f32) * self.zoom();
+                    for pane_rect in std::iter::once(active_primary_rect).chain(split_view.then_some(right_pane_rect)) {
+                        let image_rect_view = egui::Rect::from_min_size(
+                            pane_rect.min + self.pan / pixel_per_point,
+                            image_size_px / pixel_per_point,
+                        );
+                        painter.with_clip_rect(pane_rect).rect_stroke(
+                            image_rect_view,
+                            0.0,
+                            (1.0 / pixel_per_point, IMAGE_BORDER_STROKE),
+                            egui::StrokeKind::Outside,
+                        );
+                    }
+                }
+
                 // Draw marquee rectangle
                 let selection_rects =
                     std::iter::once((selection_rect_view, selection_rect_primary_clipped, active_primary_rect)).chain(
@@ -796,6 +1763,97 @@ impl ImageViewer {
                     }
                 }
 
+                // Draw the selection mask outline and the lasso/polygon still being drawn
+                let mask_outline = app_state.selection_mask.as_ref().map(|mask| mask.outline());
+                if mask_outline.is_some() || !app_state.selection_draft.is_empty() {
+                    let stroke = egui::Stroke::new(1.5, SELECTION_MASK_STROKE);
+                    let hover_pos = ui.input(|i| i.pointer.hover_pos());
+                    for pane_rect in std::iter::once(active_primary_rect).chain(split_view.then_some(right_pane_rect)) {
+                        let painter = ui.painter().with_clip_rect(pane_rect);
+                        let to_view =
+                            |p: &egui::Pos2| pane_rect.min + (self.pan + p.to_vec2() * self.zoom()) / pixel_per_point;
+                        for segment in mask_outline.into_iter().flatten() {
+                            painter.line_segment([to_view(&segment[0]), to_view(&segment[1])], stroke);
+                        }
+                        if !app_state.selection_draft.is_empty() {
+                            let mut draft: Vec<_> = app_state.selection_draft.iter().map(to_view).collect();
+                            if app_state.selection_tool == SelectionTool::Polygon {
+                                draft.extend(hover_pos.filter(|p| pane_rect.contains(*p)));
+                            }
+                            painter.add(egui::Shape::line(draft, stroke));
+                        }
+                    }
+                }
+
+                // Draw the segmentation mask stretched over the image, under the annotations
+                match app_state
+                    .segmentation_mask
+                    .as_ref()
+                    .filter(|_| app_state.is_show_segmentation_mask)
+                {
+                    Some(mask) => {
+                        if self
+                            .mask_texture
+                            .as_ref()
+                            .is_none_or(|(revision, _)| *revision != mask.revision())
+                        {
+                            let size = [mask.width as usize, mask.height as usize];
+                            let image = egui::ColorImage::from_rgba_unmultiplied(size, &mask.rgba());
+                            let texture =
+                                ui.ctx().load_texture("segmentation_mask", image, egui::TextureOptions::NEAREST);
+                            self.mask_texture = Some((mask.revision(), texture));
+                        }
+                        if let Some((_, texture)) = &self.mask_texture {
+                            let (width, height) = primary_asset.image().spec().full_size();
+                            let size = egui::vec2(width as f32, height as f32);
+                            let uv = egui::Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(1.0, 1.0));
+                            let tint = segmentation_mask_tint(mask.opacity);
+                            for pane_rect in
+                                std::iter::once(active_primary_rect).chain(split_view.then_some(right_pane_rect))
+                            {
+                                let origin = pane_rect.min + self.pan / pixel_per_point;
+                                let rect = egui::Rect::from_min_size(origin, size * self.zoom() / pixel_per_point);
+                                ui.painter().with_clip_rect(pane_rect).image(texture.id(), rect, uv, tint);
+                            }
+                        }
+                    }
+                    None => self.mask_texture = None,
+                }
+
+                // Draw annotation strokes over every pane, and the eraser outline while erasing
+                let strokes = app_state.annotations.strokes(primary_asset.hash());
+                if !strokes.is_empty() || self.annotation_draft.is_some() {
+                    let points_per_pixel = self.zoom() / pixel_per_point;
+                    for pane_rect in std::iter::once(active_primary_rect).chain(split_view.then_some(right_pane_rect)) {
+                        let painter = ui.painter().with_clip_rect(pane_rect);
+                        let to_view =
+                            |p: egui::Pos2| pane_rect.min + (self.pan + p.to_vec2() * self.zoom()) / pixel_per_point;
+                        for stroke in strokes.iter().chain(&self.annotation_draft) {
+                            draw_annotation_stroke(&painter, stroke, to_view, points_per_pixel);
+                        }
+                    }
+                }
+                if let Some(overlay) = app_state.vector_overlay.as_ref().filter(|_| app_state.is_show_vector_overlay) {
+                    for pane_rect in std::iter::once(active_primary_rect).chain(split_view.then_some(right_pane_rect)) {
+                        let painter = ui.painter().with_clip_rect(pane_rect);
+                        let to_view =
+                            |p: egui::Pos2| pane_rect.min + (self.pan + p.to_vec2() * self.zoom()) / pixel_per_point;
+                        draw_vector_overlay(&painter, overlay, primary_asset.name(), to_view);
+                    }
+                }
+                if app_state.annotate && resp.hovered() {
+                    ui.output_mut(|o| o.cursor_icon = egui::CursorIcon::Crosshair);
+                    if matches!(self.drag_mode, DragMode::Erasing) {
+                        if let Some(pointer) = ui.input(|i| i.pointer.hover_pos()) {
+                            ui.painter().circle_stroke(
+                                pointer,
+                                ANNOTATION_ERASER_RADIUS,
+                                (1.0, ANNOTATION_ERASER_STROKE),
+                            );
+                        }
+                    }
+                }
+
                 // Draw crosshair
                 if app_state.is_show_crosshair {
                     if let Some(cursor_px) = app_state.cursor_pos {
@@ -838,12 +1896,17 @@ impl ImageViewer {
                 };
 
                 // Draw per-pixel values when zoomed-in sufficiently and enabled
-                if app_state.is_show_pixel_value && self.zoom() > 64.0 {
+                if app_state.is_show_pixel_value && self.zoom() > 64.0 && !showing_preview {
                     let painter = ui.painter();
                     // Determine font size relative to on-screen pixel size
                     let font_size = 16.0 / pixel_per_point;
                     let spacing = font_size * 0.1;
                     let font_id = egui::FontId::monospace(font_size);
+                    // Characters that fit in one image pixel; monospace glyphs are roughly 0.6em wide.
+                    let max_chars = (self.zoom() * 0.9 / (16.0 * 0.6)) as usize;
+                    let shader_params = &app_state.shader_params;
+                    let value_format = app_state.pixel_value.format;
+                    let channel_only = app_state.pixel_value.selected_channel_only;
                     let pane_iter = std::iter::once((active_primary_rect, primary_image))
                         .chain(split_view.then(|| (right_pane_rect, secondary_image.unwrap())));
                     for (pane_rect, pane_image) in pane_iter {
@@ -868,18 +1931,20 @@ impl ImageViewer {
                                         egui::vec2((i as f32 + 0.5) * self.zoom(), (j as f32 + 0.5) * self.zoom());
                                     let center_pt = pane_rect.min + (self.pan + center_px) / pixel_per_point;
 
-                                    let total_h = (num_c as f32) * font_size;
-                                    for (c_idx, v) in vals.iter().enumerate() {
-                                        let y_offset = -total_h * 0.5 + (font_size + spacing) * (c_idx as f32 + 0.5);
+                                    let luma = displayed_luma(&vals, shader_params);
+                                    let dtype = pane_image.spec().dtype;
+                                    let shown_channels = match usize::try_from(app_state.channel_index) {
+                                        Ok(c) if channel_only && c < num_c => c..c + 1,
+                                        _ => 0..num_c,
+                                    };
+                                    let total_h = (shown_channels.len() as f32) * font_size;
+                                    for (row, c_idx) in shown_channels.enumerate() {
+                                        let v = vals[c_idx];
+                                        let y_offset = -total_h * 0.5 + (font_size + spacing) * (row as f32 + 0.5);
                                         let pos = egui::pos2(center_pt.x, center_pt.y + y_offset);
 
-                                        let color = PIXEL_VALUE_CHANNEL_COLORS[c_idx.min(3)];
-
-                                        let text = if pane_image.spec().dtype.is_floating() {
-                                            format!("{:.4}", (*v as f64) * pane_image.spec().dtype.alpha())
-                                        } else {
-                                            format!("{:.0}", (*v as f64) * pane_image.spec().dtype.alpha())
-                                        };
+                                        let color = pixel_value_text_color(c_idx, luma);
+                                        let text = format_pixel_value(v as f64, dtype, value_format, max_chars);
                                         painter.text(pos, egui::Align2::CENTER_CENTER, text, font_id.clone(), color);
                                     }
                                 }
@@ -896,14 +1961,18 @@ impl ImageViewer {
                     );
                 }
 
+                draw_proofing_badge(ui, rect, &app_state.proofing);
+                self.draw_osd(ui, rect);
+
                 // Draw a continuous-position arrow on the viewport edge pointing toward the offscreen image
                 // when the image is fully outside the viewport.
                 if !split_view {
                     let scale = self.zoom();
+                    let (full_width, full_height) = spec.full_size();
                     let image_rect_view = {
                         let min_view = rect.min + (self.pan) / pixel_per_point;
                         let max_view = rect.min
-                            + (self.pan + egui::vec2(spec.width as f32 * scale, spec.height as f32 * scale))
+                            + (self.pan + egui::vec2(full_width as f32 * scale, full_height as f32 * scale))
                                 / pixel_per_point;
                         egui::Rect::from_min_max(min_view, max_view)
                     };
@@ -1023,7 +2092,6 @@ impl ImageViewer {
                                 let head_len = 32.0;
                                 let head_width = 32.0;
                                 let stroke_col = egui::Color32::from_black_alpha(150);
-                                let fill_col = egui::Color32::from_white_alpha(235);
                                 let shadow_col = egui::Color32::from_black_alpha(40);
 
                                 let d = dir;
@@ -1047,6 +2115,29 @@ impl ImageViewer {
                                 let h1 = shaft_end - perp * half_head_w;
                                 let h2 = tip;
 
+                                // The arrow is a button that brings the image back into view.
+                                let shaft = vec![s0, s1, s2, s3];
+                                let head = vec![h0, h1, h2];
+                                let arrow_hovered = resp.hovered()
+                                    && ui.input(|i| i.pointer.hover_pos()).is_some_and(|pointer| {
+                                        convex_polygon_contains(&shaft, pointer)
+                                            || convex_polygon_contains(&head, pointer)
+                                    });
+                                if arrow_hovered {
+                                    ui.output_mut(|o| o.cursor_icon = egui::CursorIcon::PointingHand);
+                                    if resp.clicked() {
+                                        self.center_rect(Recti::from_min_size(
+                                            vec2i(0, 0),
+                                            vec2i(full_width, full_height),
+                                        ));
+                                    }
+                                }
+                                let fill_col = if arrow_hovered {
+                                    VIEWER_OFFSCREEN_ARROW_HOVER_FILL
+                                } else {
+                                    VIEWER_OFFSCREEN_ARROW_FILL
+                                };
+
                                 // Optional subtle shadow (draw first)
                                 let shadow_offset = egui::vec2(1.0, 1.0);
                                 let shadow_poly = vec![
@@ -1062,44 +2153,433 @@ impl ImageViewer {
                                     egui::Stroke::NONE,
                                 ));
 
-                                // Main shapes
-                                let shaft = vec![s0, s1, s2, s3];
-                                painter.add(egui::Shape::convex_polygon(
-                                    shaft,
-                                    fill_col,
-                                    egui::Stroke {
-                                        width: 1.0,
-                                        color: stroke_col,
-                                    },
-                                ));
-                                painter.add(egui::Shape::convex_polygon(
-                                    vec![h0, h1, h2],
-                                    fill_col,
-                                    egui::Stroke {
-                                        width: 1.0,
-                                        color: stroke_col,
-                                    },
-                                ));
-                            }
-                        }
-                    }
+                                // Main shapes
+                                painter.add(egui::Shape::convex_polygon(
+                                    shaft,
+                                    fill_col,
+                                    egui::Stroke {
+                                        width: 1.0,
+                                        color: stroke_col,
+                                    },
+                                ));
+                                painter.add(egui::Shape::convex_polygon(
+                                    head,
+                                    fill_col,
+                                    egui::Stroke {
+                                        width: 1.0,
+                                        color: stroke_col,
+                                    },
+                                ));
+
+                                let caption = painter.layout_no_wrap(
+                                    "Image offscreen \u{2014} click the arrow to recenter".to_string(),
+                                    egui::FontId::proportional(12.0),
+                                    VIEWER_OFFSCREEN_CAPTION,
+                                );
+                                // Keep the caption beside the arrow base, inside the viewport.
+                                let size = caption.size();
+                                let along = (size.x * d.x.abs() + size.y * d.y.abs()) * 0.5 + 8.0;
+                                let bounds = rect.shrink(4.0);
+                                let caption_min =
+                                    (base - d * along - size * 0.5).max(bounds.min).min(bounds.max - size);
+                                painter.galley(caption_min, caption, VIEWER_OFFSCREEN_CAPTION);
+                            }
+                        }
+                    }
+                }
+            }
+        } else {
+            ui.colored_label(egui::Color32::RED, "No texture uploaded");
+        }
+    }
+
+    /// Create the GPU renderer on first use. Returns `false`, with the error kept for reporting, when that fails.
+    fn ensure_gpu_renderer(&mut self, render_state: &eframe::egui_wgpu::RenderState) -> bool {
+        let mut egui_renderer = render_state.renderer.write();
+        let resources = &mut egui_renderer.callback_resources;
+        if resources.get::<GpuRenderer>().is_some() {
+            return true;
+        }
+        match GpuRenderer::new(
+            &render_state.device,
+            &render_state.queue,
+            render_state.target_format,
+            render_state.adapter.get_info().backend == wgpu::Backend::Dx12,
+        ) {
+            Ok(mut renderer) => {
+                renderer.set_safe_mode(self.safe_mode);
+                resources.insert(renderer);
+                true
+            }
+            Err(error) => {
+                self.last_shader_error = Some(error.to_string());
+                false
+            }
+        }
+    }
+
+    /// N-up grid comparison of `assets`, row by row, with one shared pan and zoom. The pixel under the pointer is
+    /// marked and read out in every pane.
+    fn show_grid(
+        &mut self,
+        ui: &mut egui::Ui,
+        frame: &mut eframe::Frame,
+        app_state: &mut AppState,
+        assets: &[SharedAsset],
+    ) {
+        let gpu_ready = frame.wgpu_render_state().is_some_and(|render_state| {
+            if !self.ensure_gpu_renderer(render_state) {
+                return false;
+            }
+            let mut egui_renderer = render_state.renderer.write();
+            let Some(renderer) = egui_renderer.callback_resources.get_mut::<GpuRenderer>() else {
+                return false;
+            };
+            for (pane, asset) in assets.iter().enumerate() {
+                let slot = ImageSlot::Grid(pane);
+                if let Err(error) =
+                    renderer.sync_image(&render_state.device, &render_state.queue, slot, Some(asset.image()))
+                {
+                    self.last_shader_error = Some(error.to_string());
+                    return false;
+                }
+            }
+            renderer.release_grid_images(assets.len());
+            renderer.sync_display_luts(
+                &render_state.device,
+                &render_state.queue,
+                app_state.look_lut.as_ref().map(|(_, lut)| lut),
+                app_state.output_lut.as_ref(),
+            );
+            true
+        });
+        if !gpu_ready {
+            ui.colored_label(egui::Color32::RED, "No texture uploaded");
+            return;
+        }
+
+        let (rect, resp) = ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+        let pixel_per_point = ui.ctx().pixels_per_point();
+        let rect = snap_to_device_pixels(rect, pixel_per_point);
+        let (columns, rows) = app_state.grid.layout.dims();
+        let pane_rects = grid_pane_rects(rect, columns, rows, pixel_per_point);
+        let pane_size_px = pane_rects[0].size() * pixel_per_point;
+        self.follow_pixels_per_point(pixel_per_point, pane_size_px);
+        // Fit, center and keyboard zoom work on one pane.
+        self.last_viewport_size_px = Some(pane_size_px);
+        if !self.grid_shown {
+            self.grid_shown = true;
+            let (width, height) = assets[0].image().spec().full_size();
+            self.fit_rect(Recti::from_min_size(vec2i(0, 0), vec2i(width, height)));
+        }
+
+        let hovered = resp.hover_pos().and_then(|pointer| {
+            pane_rects
+                .iter()
+                .find(|pane| pane.contains(pointer))
+                .map(|pane| (*pane, pointer))
+        });
+        if let Some((pane, pointer)) = hovered {
+            let scroll = ui.raw_scroll_delta_y();
+            if scroll.abs() > 0.0 {
+                self.zoom_in(scroll.signum(), Some((pointer - pane.min) * pixel_per_point));
+            }
+        }
+        if resp.dragged() {
+            self.view_animation = None;
+            self.pan += resp.drag_delta() * pixel_per_point;
+        }
+        self.snap_pan_to_pixel_grid();
+        // The status bar reads the shown asset, which is not what the pointer is over here.
+        app_state.cursor_pos = None;
+        app_state.cursor_subpixel = None;
+        let probe = hovered.map(|(pane, pointer)| {
+            let pos = self.pane_view_to_image_coords(pointer, pane, pixel_per_point);
+            vec2i(pos.x.floor() as i32, pos.y.floor() as i32)
+        });
+
+        let scale = self.zoom();
+        if let Some(render_state) = frame.wgpu_render_state() {
+            let shader_params = &app_state.shader_params;
+            let first_spec = assets[0].image().spec();
+            let is_mono = shader_params.is_mono(&first_spec, app_state.channel_index);
+            let colormap = if is_mono {
+                &app_state.colormap_mono
+            } else {
+                &app_state.colormap_rgb
+            };
+            let background_style = background_style(&app_state.background, ui.visuals());
+            let mut egui_renderer = render_state.renderer.write();
+            if let Some(renderer) = egui_renderer.callback_resources.get_mut::<GpuRenderer>() {
+                renderer.update_colormap(&render_state.device, colormap, is_mono);
+                renderer.set_magnification_filter(app_state.magnification_filter);
+                renderer.set_proofing(app_state.proofing.gains());
+                let mut panes = Vec::with_capacity(assets.len());
+                for (index, (asset, pane_rect)) in assets.iter().zip(&pane_rects).enumerate() {
+                    let image = asset.image();
+                    let spec = image.spec();
+                    let min_max = if uses_auto_minmax(shader_params) {
+                        image.minmax().clone()
+                    } else {
+                        empty_minmax()
+                    };
+                    let pane_pixels = *pane_rect * pixel_per_point;
+                    let uniform_slot = GRID_PARAM_SLOT + index as u32;
+                    renderer.write_params(
+                        &render_state.queue,
+                        uniform_slot,
+                        pane_pixels.size(),
+                        vec2(spec.width as f32, spec.height as f32),
+                        app_state.channel_index,
+                        &min_max,
+                        draw_scale(scale, &spec),
+                        self.pan,
+                        shader_params,
+                        &MinMaxOverlay::default(),
+                        &background_style,
+                        true,
+                        &spec,
+                    );
+                    panes.push(PaneDraw {
+                        viewport_px: pane_pixels,
+                        slot: ImageSlot::Grid(index),
+                        uniform_slot,
+                        pyramid_zoom: Some(draw_scale(scale, &spec)),
+                    });
+                }
+                self.last_shader_error = renderer.last_error().map(str::to_owned);
+                drop(egui_renderer);
+                ui.painter().add(eframe::egui_wgpu::Callback::new_paint_callback(
+                    rect,
+                    ImagePaintCallback {
+                        panes,
+                        ramp_panes: Vec::new(),
+                        show_background: app_state.is_show_background,
+                        export: None,
+                    },
+                ));
+            }
+        }
+
+        let font_id = egui::FontId::proportional(12.0);
+        for (index, pane_rect) in pane_rects.iter().enumerate() {
+            let painter = ui.painter().with_clip_rect(*pane_rect);
+            let Some(asset) = assets.get(index) else {
+                painter.text(
+                    pane_rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    "Empty",
+                    font_id.clone(),
+                    VIEWER_OFFSCREEN_CAPTION,
+                );
+                continue;
+            };
+            let name = Path::new(asset.name())
+                .file_name()
+                .map_or_else(|| asset.name().to_string(), |name| name.to_string_lossy().into_owned());
+            let mut captions = vec![(name, egui::Align2::LEFT_TOP)];
+
+            if let Some(pixel) = probe {
+                let pixel_min =
+                    pane_rect.min + (self.pan + vec2(pixel.x as f32, pixel.y as f32) * scale) / pixel_per_point;
+                let pixel_rect = egui::Rect::from_min_size(pixel_min, egui::Vec2::splat(scale / pixel_per_point));
+                let stroke = egui::Stroke::new(1.0, GRID_PROBE_STROKE);
+                let center = pixel_rect.center();
+                painter.line_segment(
+                    [
+                        egui::pos2(pane_rect.left(), center.y),
+                        egui::pos2(pane_rect.right(), center.y),
+                    ],
+                    stroke,
+                );
+                painter.line_segment(
+                    [
+                        egui::pos2(center.x, pane_rect.top()),
+                        egui::pos2(center.x, pane_rect.bottom()),
+                    ],
+                    stroke,
+                );
+                painter.rect_stroke(pixel_rect, 0.0, stroke, egui::StrokeKind::Outside);
+
+                let image = asset.image();
+                let spec = image.spec();
+                // A loading preview holds reduced pixels, so the probe waits for the full image.
+                if spec.preview_scale == 1 {
+                    let probe_text = match image.get_pixel_at(pixel.x, pixel.y) {
+                        Ok(values) => format_picked_pixel(pixel, &values, spec.dtype, app_state.pixel_value.format),
+                        Err(_) => format!("{}, {}: outside the image", pixel.x, pixel.y),
+                    };
+                    captions.push((probe_text, egui::Align2::LEFT_BOTTOM));
                 }
             }
-        } else {
-            ui.colored_label(egui::Color32::RED, "No texture uploaded");
+
+            for (text, align) in captions {
+                let galley = painter.layout_no_wrap(text, font_id.clone(), VIEWER_OSD_TEXT);
+                let inner = pane_rect.shrink(8.0);
+                let anchor = if align == egui::Align2::LEFT_TOP {
+                    inner.left_top()
+                } else {
+                    inner.left_bottom()
+                };
+                let text_rect = align.anchor_size(anchor, galley.size());
+                painter.rect_filled(text_rect.expand2(egui::vec2(6.0, 3.0)), 4.0, VIEWER_OSD_FILL);
+                painter.galley(text_rect.min, galley, VIEWER_OSD_TEXT);
+            }
+        }
+
+        let divider = egui::Stroke::new(1.0, egui::Color32::from_white_alpha(96));
+        for pane in pane_rects.iter().take(columns).skip(1) {
+            let x = pane.left();
+            ui.painter()
+                .line_segment([egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())], divider);
+        }
+        for row in 1..rows {
+            let y = pane_rects[row * columns].top();
+            ui.painter()
+                .line_segment([egui::pos2(rect.left(), y), egui::pos2(rect.right(), y)], divider);
         }
+
+        draw_proofing_badge(ui, rect, &app_state.proofing);
+        self.draw_osd(ui, rect);
     }
 
     pub fn reset_view(&mut self) {
+        self.view_animation = None;
         self.zoom_level = 0.0;
         self.pan = egui::Vec2::ZERO;
     }
 
+    pub fn view_transform(&self) -> ViewTransform {
+        match &self.view_animation {
+            Some(animation) => ViewTransform {
+                zoom_level: animation.to_level,
+                pan: animation.to_pan,
+            },
+            None => ViewTransform {
+                zoom_level: self.zoom_level,
+                pan: self.pan,
+            },
+        }
+    }
+
+    pub fn set_view_transform(&mut self, transform: ViewTransform) {
+        self.view_animation = None;
+        self.zoom_level = transform.zoom_level;
+        self.pan = transform.pan;
+    }
+
+    pub fn set_safe_mode(&mut self, enabled: bool) {
+        self.safe_mode = enabled;
+    }
+
+    pub fn set_animate_view(&mut self, enabled: bool) {
+        self.animate_view = enabled;
+        if !enabled {
+            self.finish_view_animation();
+        }
+    }
+
+    /// Turn the jump from `from` (zoom level and pan) to the current view into an animation, if enabled.
+    fn animate_from(&mut self, (from_level, from_pan): (f32, egui::Vec2)) {
+        if !self.animate_view || (from_level == self.zoom_level && from_pan == self.pan) {
+            return;
+        }
+        self.view_animation = Some(ViewAnimation {
+            start: Instant::now(),
+            from_level,
+            from_pan,
+            to_level: self.zoom_level,
+            to_pan: self.pan,
+        });
+        self.zoom_level = from_level;
+        self.pan = from_pan;
+    }
+
+    fn finish_view_animation(&mut self) {
+        if let Some(animation) = self.view_animation.take() {
+            self.zoom_level = animation.to_level;
+            self.pan = animation.to_pan;
+        }
+    }
+
+    /// Move the view along the running animation. The image point at the viewport center travels in a straight
+    /// line while the zoom level changes linearly, so the motion reads as one camera move.
+    fn step_view_animation(&mut self, ctx: &egui::Context) {
+        let Some(animation) = &self.view_animation else {
+            return;
+        };
+        let t = animation.start.elapsed().as_secs_f32() / VIEW_ANIMATION_DURATION.as_secs_f32();
+        if t >= 1.0 {
+            self.finish_view_animation();
+            return;
+        }
+        let eased = 1.0 - (1.0 - t).powi(3);
+        let anchor = self.last_viewport_size_px.unwrap_or(egui::Vec2::ZERO) * 0.5;
+        let scale = |level: f32| self.zoom_base.powf(level);
+        let from_center = (anchor - animation.from_pan) / scale(animation.from_level);
+        let to_center = (anchor - animation.to_pan) / scale(animation.to_level);
+        let level = animation.from_level + (animation.to_level - animation.from_level) * eased;
+        let center = from_center + (to_center - from_center) * eased;
+        self.pan = anchor - center * scale(level);
+        self.zoom_level = level;
+        ctx.request_repaint();
+    }
+
+    /// Screen pixels per image pixel. Levels meant as 100%, 200%, 50% and so on come out of `powf` slightly off,
+    /// so scales within rounding error of an integer or its reciprocal are made exact.
     pub fn zoom(&self) -> f32 {
-        self.zoom_base.powf(self.zoom_level)
+        let scale = self.zoom_base.powf(self.zoom_level);
+        let (value, invert) = if scale >= 1.0 {
+            (scale, false)
+        } else {
+            (scale.recip(), true)
+        };
+        let rounded = value.round();
+        if (value - rounded).abs() > value * 1e-5 {
+            scale
+        } else if invert {
+            rounded.recip()
+        } else {
+            rounded
+        }
+    }
+
+    pub fn set_mouse_bindings(&mut self, bindings: MouseBindings) {
+        self.mouse_bindings = bindings;
+    }
+
+    pub fn set_snap_to_pixel_grid(&mut self, enabled: bool) {
+        self.snap_to_pixel_grid = enabled;
+    }
+
+    /// At integer zooms and their reciprocals, round the pan to whole device pixels so every image pixel edge
+    /// lands on a device pixel edge.
+    fn snap_pan_to_pixel_grid(&mut self) {
+        if !self.snap_to_pixel_grid || self.view_animation.is_some() {
+            return;
+        }
+        let scale = self.zoom();
+        let factor = scale.max(scale.recip());
+        if (factor - factor.round()).abs() <= factor * 1e-5 {
+            self.pan = self.pan.round();
+        }
+    }
+
+    /// Keep the image point at the viewport center in place when the scale factor changes, e.g. after moving the
+    /// window to a monitor with a different scale. The zoom stays in image pixels per device pixel.
+    fn follow_pixels_per_point(&mut self, pixels_per_point: f32, viewport_size_px: egui::Vec2) {
+        let previous = self.last_pixels_per_point.replace(pixels_per_point);
+        if previous.is_none_or(|previous| previous == pixels_per_point) {
+            return;
+        }
+        if let Some(old_viewport) = self.last_viewport_size_px {
+            self.finish_view_animation();
+            self.pan += (viewport_size_px - old_viewport) * 0.5;
+        }
     }
 
     pub fn zoom_in(&mut self, level: f32, center: Option<egui::Vec2>) {
+        self.view_animation = None;
         let old_scale = self.zoom();
         self.zoom_level += level;
         let new_scale = self.zoom();
@@ -1114,6 +2594,15 @@ impl ImageViewer {
         self.pan = self.pan * k + local * (1.0 - k);
     }
 
+    /// Set the zoom to `scale` screen pixels per image pixel, keeping the point at the viewport center fixed.
+    pub fn set_zoom_about_center(&mut self, scale: f32) {
+        if !scale.is_finite() || scale <= 0.0 {
+            return;
+        }
+        let level = scale.ln() / self.zoom_base.ln();
+        self.zoom_in(level - self.zoom_level, None);
+    }
+
     fn is_split_comparison(&self, app_state: &AppState) -> bool {
         app_state.is_comparison()
             && app_state.comparison_mode == crate::model::ComparisonMode::Split
@@ -1121,14 +2610,88 @@ impl ImageViewer {
             && app_state.asset_secondary.is_some()
     }
 
-    fn split_pane_rects(&self, rect: egui::Rect) -> (egui::Rect, egui::Rect) {
-        let divider_x = rect.center().x;
+    fn split_pane_rects(&self, rect: egui::Rect, pixel_per_point: f32) -> (egui::Rect, egui::Rect) {
+        let divider_x = (rect.center().x * pixel_per_point).round() / pixel_per_point;
         (
             egui::Rect::from_min_max(rect.min, egui::pos2(divider_x, rect.max.y)),
             egui::Rect::from_min_max(egui::pos2(divider_x, rect.min.y), rect.max),
         )
     }
 
+    /// Track the force of the pen or touch contact; `None` once it is lifted or when the device reports none.
+    fn update_pen_pressure(&mut self, ui: &egui::Ui) {
+        ui.input(|i| {
+            for event in &i.events {
+                if let egui::Event::Touch { phase, force, .. } = event {
+                    self.pen_pressure = match phase {
+                        egui::TouchPhase::Start | egui::TouchPhase::Move => *force,
+                        egui::TouchPhase::End | egui::TouchPhase::Cancel => None,
+                    };
+                }
+            }
+        });
+    }
+
+    /// Show `text` briefly over the top of the viewer.
+    pub fn show_osd(&mut self, text: impl Into<String>) {
+        self.osd = Some((text.into(), Instant::now()));
+    }
+
+    fn draw_osd(&mut self, ui: &egui::Ui, rect: egui::Rect) {
+        const VISIBLE: Duration = Duration::from_millis(1200);
+        const FADE: Duration = Duration::from_millis(300);
+
+        let Some((text, shown_at)) = &self.osd else {
+            return;
+        };
+        let elapsed = shown_at.elapsed();
+        if elapsed >= VISIBLE {
+            self.osd = None;
+            return;
+        }
+        let remaining = VISIBLE - elapsed;
+        let opacity = (remaining.as_secs_f32() / FADE.as_secs_f32()).min(1.0);
+
+        let painter = ui.painter().with_clip_rect(rect);
+        let galley = painter.layout_no_wrap(
+            text.clone(),
+            egui::FontId::proportional(18.0),
+            VIEWER_OSD_TEXT.gamma_multiply(opacity),
+        );
+        let text_rect = egui::Rect::from_center_size(rect.center_top() + egui::vec2(0.0, 40.0), galley.size());
+        painter.rect_filled(
+            text_rect.expand2(egui::vec2(12.0, 6.0)),
+            6.0,
+            VIEWER_OSD_FILL.gamma_multiply(opacity),
+        );
+        painter.galley(text_rect.min, galley, VIEWER_OSD_TEXT);
+        ui.ctx().request_repaint_after(remaining.min(Duration::from_millis(16)));
+    }
+
+    fn save_selection_mask(&self, mask: Arc<SelectionMask>, width: i32, height: i32, repaint_ctx: egui::Context) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("PNG image", &["png"])
+            .set_file_name("mask.png")
+            .save_file()
+        else {
+            return;
+        };
+        let export_toasts = self.export_toasts.clone();
+        std::thread::spawn(move || {
+            let toast = match mask.save_png(&path, width, height) {
+                Ok(()) => ExportToast::Success(format!("Saved selection mask to {}", path.display())),
+                Err(err) => {
+                    eprintln!("Failed to save selection mask to {}: {err}", path.display());
+                    ExportToast::Error(format!("Failed to save selection mask to {}", path.display()))
+                }
+            };
+            if let Ok(mut toasts) = export_toasts.lock() {
+                toasts.push(toast);
+            }
+            repaint_ctx.request_repaint();
+        });
+    }
+
     fn selection_rect_in_view(&self, pane_rect: egui::Rect, rect: Recti, pixel_per_point: f32) -> egui::Rect {
         let r = rect.to_rect();
         let k = self.zoom() / pixel_per_point;
@@ -1141,8 +2704,7 @@ impl ImageViewer {
         pane_rect: egui::Rect,
         pixel_per_point: f32,
     ) -> egui::Pos2 {
-        let local_pos = (view_pos - pane_rect.min) * pixel_per_point;
-        ((local_pos - self.pan) / self.zoom()).to_pos2()
+        view_to_image_pos((view_pos - pane_rect.min) * pixel_per_point, self.pan, self.zoom())
     }
 
     fn view_to_image_coords_in_fixed_pane(
@@ -1154,7 +2716,7 @@ impl ImageViewer {
         on_secondary: bool,
     ) -> egui::Pos2 {
         let pane_rect = if split_view {
-            let (left_rect, right_rect) = self.split_pane_rects(rect);
+            let (left_rect, right_rect) = self.split_pane_rects(rect, pixel_per_point);
             if on_secondary {
                 right_rect
             } else {
@@ -1174,7 +2736,7 @@ impl ImageViewer {
         split_view: bool,
     ) -> (egui::Pos2, bool) {
         if split_view {
-            let (left_rect, right_rect) = self.split_pane_rects(rect);
+            let (left_rect, right_rect) = self.split_pane_rects(rect, pixel_per_point);
             if right_rect.contains(view_pos) {
                 return (self.pane_view_to_image_coords(view_pos, right_rect, pixel_per_point), true);
             }
@@ -1215,6 +2777,8 @@ impl ImageViewer {
         let Some(viewport_px) = self.last_viewport_size_px else {
             return;
         };
+        self.view_animation = None;
+        let from = (self.zoom_level, self.pan);
         let rw = rect.width().max(1) as f32;
         let rh = rect.height().max(1) as f32;
         let vw = viewport_px.x.max(1.0);
@@ -1238,12 +2802,15 @@ impl ImageViewer {
         let viewport_cx = vw * 0.5;
         let viewport_cy = vh * 0.5;
         self.pan = egui::vec2(viewport_cx - rect_cx * scale, viewport_cy - rect_cy * scale);
+        self.animate_from(from);
     }
 
     pub fn fit_split(&mut self, image_width: i32, image_height: i32) {
         let Some(viewport_px) = self.last_viewport_size_px else {
             return;
         };
+        self.view_animation = None;
+        let from = (self.zoom_level, self.pan);
 
         let rw = image_width.max(1) as f32;
         let rh = image_height.max(1) as f32;
@@ -1263,6 +2830,7 @@ impl ImageViewer {
         let viewport_cx = vw * 0.5;
         let viewport_cy = vh * 0.5;
         self.pan = egui::vec2(viewport_cx - rect_cx * scale, viewport_cy - rect_cy * scale);
+        self.animate_from(from);
     }
 
     // Center the given image-space rectangle in the viewport without changing zoom.
@@ -1270,6 +2838,8 @@ impl ImageViewer {
         let Some(viewport_px) = self.last_viewport_size_px else {
             return;
         };
+        self.view_animation = None;
+        let from = (self.zoom_level, self.pan);
         let vw = viewport_px.x.max(1.0);
         let vh = viewport_px.y.max(1.0);
         let scale = self.zoom();
@@ -1278,10 +2848,73 @@ impl ImageViewer {
         let viewport_cx = vw * 0.5;
         let viewport_cy = vh * 0.5;
         self.pan = egui::vec2(viewport_cx - rect_cx * scale, viewport_cy - rect_cy * scale);
+        self.animate_from(from);
     }
 
     pub fn request_copy(&mut self, source_label: String) {
         self.copy_requested = Some(source_label);
+        self.copy_target = CopyTarget::Clipboard;
+    }
+
+    /// Copy as a temporary PNG file, for targets such as chat apps and file managers that only take files.
+    pub fn request_copy_file(&mut self, source_label: String) {
+        self.copy_requested = Some(source_label);
+        self.copy_target = CopyTarget::File;
+    }
+
+    /// Export like a file copy, then drag the file out of the window while the button that started it is held.
+    fn request_drag_out(&mut self, source_label: String, repaint_ctx: egui::Context) {
+        self.copy_requested = Some(source_label);
+        self.copy_target = CopyTarget::Drag(PendingDrag {
+            file: self.drag_out_file.clone(),
+            repaint_ctx,
+        });
+    }
+
+    /// Start the platform drag for an export queued by [`Self::request_drag_out`]. An export that finished after
+    /// the button was released is dropped.
+    fn start_pending_drag_out(&mut self, ui: &egui::Ui, frame: &eframe::Frame) {
+        let Some(path) = self.drag_out_file.lock().ok().and_then(|mut file| file.take()) else {
+            return;
+        };
+        if !ui.input(|i| i.pointer.any_down()) {
+            crate::model::discard_temp_export(&path);
+            return;
+        }
+        // The platform drag takes over the pointer, so egui may never see the release that ends this drag.
+        self.dragging = false;
+        self.drag_mode = DragMode::None;
+        let report = {
+            let path = path.clone();
+            let toasts = self.export_toasts.clone();
+            let repaint_ctx = ui.ctx().clone();
+            move |err: color_eyre::Report| {
+                eprintln!("Failed to drag {} out of the window: {err}", path.display());
+                crate::model::discard_temp_export(&path);
+                if let Ok(mut toasts) = toasts.lock() {
+                    toasts.push(ExportToast::Error(format!("Failed to drag the image out of the window: {err}")));
+                }
+                repaint_ctx.request_repaint();
+            }
+        };
+        let on_error = report.clone();
+        if let Err(err) = crate::model::start_file_drag(frame, &path, on_error) {
+            report(err);
+        }
+    }
+
+    /// Render the view into a `size` frame for `completion` on the next paint, without overlays.
+    pub fn request_view_capture(&mut self, size: (u32, u32), completion: ExportCompletion) {
+        self.view_capture = Some((size, completion));
+    }
+
+    /// Size of the image viewport in device pixels, once it was shown.
+    pub fn viewport_size_px(&self) -> Option<egui::Vec2> {
+        self.last_viewport_size_px
+    }
+
+    pub fn request_print(&mut self, layout: PrintLayout) {
+        self.print_requested = Some(layout);
     }
 
     pub fn request_save_dialog(&mut self) {
@@ -1296,6 +2929,10 @@ impl ImageViewer {
         self.save_requested = Some((path, source_label));
     }
 
+    pub fn has_pending_save(&self) -> bool {
+        self.save_requested.is_some()
+    }
+
     pub fn is_marquee_interaction_active(&self) -> bool {
         self.dragging && matches!(self.drag_mode, DragMode::Marquee { .. } | DragMode::Resizing { .. })
     }
@@ -1406,82 +3043,194 @@ impl ImageViewer {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn copy_image_to_clipboard(
     width: i32,
     height: i32,
     bytes: Vec<u8>,
+    sample_png: Option<&(ImageData, Recti, ExportData)>,
+    target: &CopyTarget,
+    metadata_source: Option<&Path>,
     export_toasts: &Arc<Mutex<Vec<ExportToast>>>,
     source_label: &str,
 ) {
-    if let Ok(mut clipboard) = arboard::Clipboard::new() {
-        let img = arboard::ImageData {
-            width: width as usize,
-            height: height as usize,
-            bytes: std::borrow::Cow::Owned(bytes),
-        };
-        let copy_result = clipboard.set_image(img);
+    let copy_result = if !matches!(target, CopyTarget::Clipboard) {
+        crate::model::temp_export_path(&format!("{source_label}.png"))
+            .and_then(|path| {
+                save_rendered_image(&path, width, height, bytes, &read_export_metadata(metadata_source))?;
+                Ok(path)
+            })
+            .and_then(|path| target.deliver_file(path))
+    } else {
+        let png = sample_png.and_then(|(image, rect, data)| {
+            crate::model::encode_samples(image, *rect, *data)
+                .inspect_err(|err| eprintln!("Failed to encode sample PNG for clipboard: {err}"))
+                .ok()
+        });
+        crate::model::write_clipboard_image(width as usize, height as usize, bytes, png)
+    };
+    if let Some(toast) = copy_toast(copy_result, source_label, target) {
         if let Ok(mut toasts) = export_toasts.lock() {
-            match copy_result {
-                Ok(()) => {
-                    let message = if source_label == "image" {
-                        "Copied image to clipboard".to_string()
-                    } else {
-                        format!("Copied {source_label} image to clipboard")
-                    };
-                    toasts.push(ExportToast::Success(message))
-                }
-                Err(err) => {
-                    eprintln!("Failed to copy image to clipboard: {err}");
-                    let message = if source_label == "image" {
-                        "Failed to copy image to clipboard".to_string()
-                    } else {
-                        format!("Failed to copy {source_label} image to clipboard")
-                    };
-                    toasts.push(ExportToast::Error(message));
-                }
-            }
+            toasts.push(toast);
+        }
+    }
+}
+
+/// Toast for a finished copy. A drag-out only reports failures, since the drag itself shows that it worked.
+fn copy_toast(result: Result<()>, source_label: &str, target: &CopyTarget) -> Option<ExportToast> {
+    Some(match result {
+        Ok(()) if matches!(target, CopyTarget::Drag(_)) => return None,
+        Err(err) if matches!(target, CopyTarget::Drag(_)) => {
+            eprintln!("Failed to prepare image for dragging: {err}");
+            ExportToast::Error(format!("Failed to prepare the {source_label} image for dragging"))
+        }
+        Ok(()) => {
+            let message = if source_label == "image" {
+                "Copied image to clipboard".to_string()
+            } else {
+                format!("Copied {source_label} image to clipboard")
+            };
+            ExportToast::Success(message)
+        }
+        Err(err) => {
+            eprintln!("Failed to copy image to clipboard: {err}");
+            let message = if source_label == "image" {
+                "Failed to copy image to clipboard".to_string()
+            } else {
+                format!("Failed to copy {source_label} image to clipboard")
+            };
+            ExportToast::Error(message)
+        }
+    })
+}
+
+fn save_toast(result: Result<()>, path: &Path, source_label: &str) -> ExportToast {
+    match result {
+        Ok(()) => {
+            let message = if source_label == "image" {
+                format!("Saved image to {}", path.display())
+            } else {
+                format!("Saved {source_label} image to {}", path.display())
+            };
+            ExportToast::Success(message)
+        }
+        Err(err) => {
+            eprintln!("Failed to save image to {}: {err}", path.display());
+            let message = if source_label == "image" {
+                format!("Failed to save image to {}: {err}", path.display())
+            } else {
+                format!("Failed to save {source_label} image to {}: {err}", path.display())
+            };
+            ExportToast::Error(message)
         }
-    } else if let Ok(mut toasts) = export_toasts.lock() {
-        eprintln!("Failed to open clipboard for image copy");
-        let message = if source_label == "image" {
-            "Failed to copy image to clipboard".to_string()
+    }
+}
+
+/// Write the stored samples of `rect` to `path` on a worker thread. The extension has to match the format
+/// `data` is encoded as.
+fn save_samples_async(
+    path: PathBuf,
+    image: ImageData,
+    rect: Recti,
+    data: ExportData,
+    export_toasts: Arc<Mutex<Vec<ExportToast>>>,
+    repaint_ctx: egui::Context,
+    source_label: String,
+) {
+    std::thread::spawn(move || {
+        let expected = data.sample_extension(image.spec().dtype).unwrap_or("png");
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+        let result = if extension.as_deref() == Some(expected) {
+            crate::model::encode_samples(&image, rect, data).and_then(|bytes| Ok(std::fs::write(&path, bytes)?))
         } else {
-            format!("Failed to copy {source_label} image to clipboard")
+            Err(eyre!("{} exports of this image are saved as .{expected}", data.label()))
         };
-        toasts.push(ExportToast::Error(message));
-    }
+        if let Ok(mut toasts) = export_toasts.lock() {
+            toasts.push(save_toast(result, &path, &source_label));
+        }
+        repaint_ctx.request_repaint();
+    });
+}
+
+/// Copy the stored samples of `rect` as a temporary file, for data such as floats that clipboards cannot hold.
+fn copy_samples_as_file_async(
+    image: ImageData,
+    rect: Recti,
+    data: ExportData,
+    target: CopyTarget,
+    export_toasts: Arc<Mutex<Vec<ExportToast>>>,
+    repaint_ctx: egui::Context,
+    source_label: String,
+) {
+    std::thread::spawn(move || {
+        let extension = data.sample_extension(image.spec().dtype).unwrap_or("png");
+        let result = crate::model::temp_export_path(&format!("{source_label}.{extension}"))
+            .and_then(|path| {
+                std::fs::write(&path, crate::model::encode_samples(&image, rect, data)?)?;
+                Ok(path)
+            })
+            .and_then(|path| target.deliver_file(path));
+        if let Some(toast) = copy_toast(result, &source_label, &target) {
+            if let Ok(mut toasts) = export_toasts.lock() {
+                toasts.push(toast);
+            }
+        }
+        repaint_ctx.request_repaint();
+    });
 }
 
+#[allow(clippy::too_many_arguments)]
 fn save_image_async(
     path: PathBuf,
     width: i32,
     height: i32,
     bytes: Vec<u8>,
+    metadata_source: Option<PathBuf>,
     export_toasts: Arc<Mutex<Vec<ExportToast>>>,
     repaint_ctx: egui::Context,
     source_label: String,
 ) {
     std::thread::spawn(move || {
-        let toast = match save_rendered_image(&path, width, height, bytes) {
-            Ok(()) => {
-                let message = if source_label == "image" {
-                    format!("Saved image to {}", path.display())
-                } else {
-                    format!("Saved {source_label} image to {}", path.display())
-                };
-                ExportToast::Success(message)
-            }
+        let metadata = read_export_metadata(metadata_source.as_deref());
+        let toast = save_toast(
+            save_rendered_image(&path, width, height, bytes, &metadata),
+            &path,
+            &source_label,
+        );
+
+        if let Ok(mut toasts) = export_toasts.lock() {
+            toasts.push(toast);
+        }
+        repaint_ctx.request_repaint();
+    });
+}
+
+fn print_image_async(
+    path: PathBuf,
+    width: i32,
+    height: i32,
+    bytes: Vec<u8>,
+    layout: PrintLayout,
+    export_toasts: Arc<Mutex<Vec<ExportToast>>>,
+    repaint_ctx: egui::Context,
+) {
+    std::thread::spawn(move || {
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .map_err(Into::into)
+            .and_then(|()| crate::model::write_print_pdf(&path, width as u32, height as u32, &bytes, &layout))
+            .and_then(|()| crate::model::send_to_printer(&path));
+        let toast = match result {
+            Ok(()) => ExportToast::Success("Sent image to the printer".to_string()),
             Err(err) => {
-                eprintln!("Failed to save image to {}: {err}", path.display());
-                let message = if source_label == "image" {
-                    format!("Failed to save image to {}", path.display())
-                } else {
-                    format!("Failed to save {source_label} image to {}", path.display())
-                };
-                ExportToast::Error(message)
+                eprintln!("Failed to print {}: {err}", path.display());
+                ExportToast::Error(format!("Failed to print image: {err}"))
             }
         };
-
         if let Ok(mut toasts) = export_toasts.lock() {
             toasts.push(toast);
         }
@@ -1489,7 +3238,23 @@ fn save_image_async(
     });
 }
 
-fn save_rendered_image(path: &Path, width: i32, height: i32, rgba_bytes: Vec<u8>) -> Result<()> {
+/// Metadata of the exported image's source file, or none when it is stripped or cannot be read.
+fn read_export_metadata(source: Option<&Path>) -> SourceMetadata {
+    source.map_or_else(SourceMetadata::default, |path| {
+        SourceMetadata::read(path).unwrap_or_else(|err| {
+            eprintln!("Failed to read metadata of {}: {err}", path.display());
+            SourceMetadata::default()
+        })
+    })
+}
+
+fn save_rendered_image(
+    path: &Path,
+    width: i32,
+    height: i32,
+    rgba_bytes: Vec<u8>,
+    metadata: &SourceMetadata,
+) -> Result<()> {
     if width <= 0 || height <= 0 {
         return Err(eyre!("Invalid export size: {}x{}", width, height));
     }
@@ -1501,28 +3266,166 @@ fn save_rendered_image(path: &Path, width: i32, height: i32, rgba_bytes: Vec<u8>
         .unwrap_or_else(|| "png".to_owned());
 
     match extension.as_str() {
-        "png" => image::save_buffer_with_format(
+        "png" => std::fs::write(
             path,
-            &rgba_bytes,
-            width as u32,
-            height as u32,
-            image::ColorType::Rgba8,
-            image::ImageFormat::Png,
+            crate::model::encode_png_with_metadata(width as u32, height as u32, &rgba_bytes, metadata)?,
         )?,
         "jpg" | "jpeg" => {
             let mut rgb = Vec::with_capacity(width as usize * height as usize * 3);
             for pixel in rgba_bytes.chunks_exact(4) {
                 rgb.extend_from_slice(&pixel[..3]);
             }
-            let file = std::fs::File::create(path)?;
-            let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(BufWriter::new(file), 95);
-            encoder.write_image(&rgb, width as u32, height as u32, image::ExtendedColorType::Rgb8)?;
+            std::fs::write(
+                path,
+                crate::model::encode_jpeg_with_metadata(width as u32, height as u32, &rgb, 95, metadata)?,
+            )?;
         }
         other => return Err(eyre!("Unsupported export extension: {other}")),
     }
     Ok(())
 }
 
+/// Rough luma of a pixel as displayed, used to pick a readable overlay text color.
+/// Only exposure, offset and gamma are taken into account; colormaps and min/max scaling are ignored.
+fn displayed_luma(vals: &[f32], shader_params: &ShaderParams) -> f32 {
+    let display = |v: f32| {
+        let v = (v * shader_params.exposure.exp2() + shader_params.offset).clamp(0.0, 1.0);
+        v.powf(1.0 / shader_params.gamma)
+    };
+    match vals {
+        [] => 0.0,
+        [v] | [v, _] => display(*v),
+        [r, g, b, ..] => 0.299 * display(*r) + 0.587 * display(*g) + 0.114 * display(*b),
+    }
+}
+
+/// `x, y: v0, v1, ...` for a picked pixel, with the values in the pixel value overlay format.
+fn format_picked_pixel(pixel: Vec2i, values: &[f32], dtype: PixelType, format: PixelValueFormat) -> String {
+    let values: Vec<_> = values
+        .iter()
+        .map(|&v| format_pixel_value(v as f64, dtype, format, 10))
+        .collect();
+    format!("{}, {}: {}", pixel.x, pixel.y, values.join(", "))
+}
+
+/// Format a normalized pixel value for the overlay in the requested format.
+fn format_pixel_value(value: f64, dtype: PixelType, format: PixelValueFormat, max_chars: usize) -> String {
+    // Floats are scaled to 8-bit for the integer formats; integer data goes back to its stored range.
+    let (scale, hex_digits) = if dtype.is_floating() {
+        (u8::MAX as f64, 2)
+    } else {
+        (dtype.alpha(), dtype.bytes() * 2)
+    };
+    match format {
+        PixelValueFormat::Auto if dtype.is_floating() => format_float_value(value, max_chars),
+        PixelValueFormat::Auto | PixelValueFormat::ScaledInt => format!("{:.0}", value * scale),
+        PixelValueFormat::Float => format_float_value(value, max_chars),
+        PixelValueFormat::Hex => {
+            let scaled = (value * scale).round();
+            if !scaled.is_finite() {
+                return format!("{scaled}");
+            }
+            let scaled = if dtype.is_floating() {
+                scaled.clamp(0.0, scale)
+            } else {
+                scaled
+            };
+            let sign = if scaled < 0.0 { "-" } else { "" };
+            format!("{sign}{:0hex_digits$X}", scaled.abs() as u64)
+        }
+    }
+}
+
+/// Format a float to fit in roughly `max_chars` characters. Precision shrinks as the integer part grows,
+/// and falls back to scientific notation when the value would not be readable otherwise.
+fn format_float_value(value: f64, max_chars: usize) -> String {
+    if !value.is_finite() {
+        return format!("{value}");
+    }
+    let int_digits = value.abs().max(1.0).log10().floor() as usize + 1;
+    let sign = usize::from(value < 0.0);
+    let decimals = max_chars.saturating_sub(int_digits + sign + 1).clamp(1, 6);
+    let too_small = value != 0.0 && value.abs() < 0.5 * 10f64.powi(-(decimals as i32));
+    if too_small || int_digits + sign > max_chars.max(1) {
+        format!("{value:.1e}")
+    } else if int_digits + sign + 1 >= max_chars {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.decimals$}")
+    }
+}
+
+fn background_style(background: &BackgroundSettings, visuals: &egui::Visuals) -> BackgroundStyle {
+    match background.kind {
+        BackgroundKind::Checker => {
+            let (color_a, color_b) = match background.checker_colors {
+                Some([a, b]) => (
+                    egui::Color32::from_rgb(a[0], a[1], a[2]),
+                    egui::Color32::from_rgb(b[0], b[1], b[2]),
+                ),
+                None => (visuals.extreme_bg_color, visuals.faint_bg_color),
+            };
+            BackgroundStyle {
+                color_a,
+                color_b,
+                checker_size: background.checker_size as f32,
+            }
+        }
+        BackgroundKind::Black => BackgroundStyle::solid(egui::Color32::BLACK),
+        BackgroundKind::White => BackgroundStyle::solid(egui::Color32::WHITE),
+        BackgroundKind::Gray => BackgroundStyle::solid(egui::Color32::from_gray(128)),
+        BackgroundKind::Custom => {
+            let [r, g, b] = background.custom_color;
+            BackgroundStyle::solid(egui::Color32::from_rgb(r, g, b))
+        }
+    }
+}
+
+/// Persistent marker in the top-right corner while proofing changes the shown colors, so a proofed view is not
+/// mistaken for the real one.
+fn draw_proofing_badge(ui: &egui::Ui, rect: egui::Rect, proofing: &Proofing) {
+    if !proofing.enabled {
+        return;
+    }
+    let painter = ui.painter().with_clip_rect(rect);
+    let galley = painter.layout_no_wrap(proofing.label(), egui::FontId::proportional(13.0), PROOFING_BADGE_TEXT);
+    let text_rect = egui::Align2::RIGHT_TOP.anchor_size(rect.right_top() + egui::vec2(-12.0, 10.0), galley.size());
+    let badge_rect = text_rect.expand2(egui::vec2(8.0, 4.0));
+    painter.rect_filled(badge_rect, 4.0, VIEWER_OSD_FILL);
+    painter.rect_stroke(
+        badge_rect,
+        4.0,
+        egui::Stroke::new(1.5, PROOFING_BADGE_TEXT),
+        egui::StrokeKind::Inside,
+    );
+    painter.galley(text_rect.min, galley, PROOFING_BADGE_TEXT);
+}
+
+/// Strips at the bottom-left of the viewport showing the colormap and its L* lightness ramp.
+fn colormap_ramp_rects(rect: egui::Rect) -> (egui::Rect, egui::Rect) {
+    let margin = 12.0;
+    let strip_height = 12.0;
+    let width = (rect.width() - margin * 2.0 - 64.0).clamp(0.0, 256.0);
+    let lightness = egui::Rect::from_min_size(
+        egui::pos2(rect.left() + margin, rect.bottom() - margin - strip_height),
+        vec2(width, strip_height),
+    );
+    let color = lightness.translate(vec2(0.0, -strip_height - 4.0));
+    (color, lightness)
+}
+
+/// Device pixels per stored pixel of an image viewed at `zoom` device pixels per full-image pixel. A reduced loading
+/// preview is drawn `preview_scale` times larger so it covers the image that replaces it.
+fn draw_scale(zoom: f32, spec: &ImageSpec) -> f32 {
+    zoom * spec.preview_scale as f32
+}
+
+/// Full-image position under `local_px`, a device-pixel offset from the pane origin. It does not depend on the stored
+/// size, so a preview and the full image map a view point to the same coordinate.
+fn view_to_image_pos(local_px: egui::Vec2, pan: egui::Vec2, zoom: f32) -> egui::Pos2 {
+    ((local_px - pan) / zoom).to_pos2()
+}
+
 fn hit_test_handles(selection_rect: egui::Rect, pointer: egui::Pos2) -> Option<ResizeHandle> {
     // Slightly larger hit area than the visual handle for easier grabbing.
     let handle_size = 16.0; // hit area in points
@@ -1613,3 +3516,23 @@ fn rect_with_handle_at(rect: Recti, handle: ResizeHandle, handle_pos: egui::Pos2
     }
     adjusted.validate()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn previews_map_view_points_to_full_image_coordinates() {
+        let full = ImageSpec::new(4096, 2048, 3, PixelType::U8);
+        let mut preview = ImageSpec::new(1024, 512, 3, PixelType::U8);
+        preview.preview_scale = 4;
+        assert_eq!(preview.full_size(), full.full_size());
+
+        let (zoom, pan) = (0.5, egui::vec2(10.0, -20.0));
+        // Stored pixel (100, 50) of the 1/4 preview covers full-image pixel (400, 200).
+        let full_px = pan + egui::vec2(400.0, 200.0) * draw_scale(zoom, &full);
+        let preview_px = pan + egui::vec2(100.0, 50.0) * draw_scale(zoom, &preview);
+        assert_eq!(full_px, preview_px);
+        assert_eq!(view_to_image_pos(preview_px, pan, zoom), egui::pos2(400.0, 200.0));
+    }
+}
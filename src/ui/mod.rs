@@ -4,7 +4,12 @@ pub mod gpu;
 pub mod icon;
 
 mod app;
+mod directory_export;
+#[cfg(test)]
+mod gpu_golden;
 mod image_viewer;
+mod thumbnails;
+mod view_recording;
 
 pub use app::ViewerApp;
-pub use image_viewer::ImageViewer;
+pub use image_viewer::{ImageViewer, ViewTransform};
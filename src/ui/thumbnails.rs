@@ -0,0 +1,218 @@
+//! Image list thumbnails rendered off screen with the viewer's image pipeline.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{mpsc, Arc},
+};
+
+use eframe::{egui, egui_wgpu};
+
+use crate::{
+    model::{Image, ImageData, SharedAsset},
+    ui::gpu::{thumbnail_shader_params, GpuRenderer, ShaderParams},
+};
+
+/// Longest side of a rendered thumbnail in pixels.
+const THUMBNAIL_SIZE: u32 = 96;
+/// Longest side of a thumbnail in the image list, in points.
+pub const THUMBNAIL_DISPLAY_SIZE: f32 = 32.0;
+/// Renders started per frame, so adding many images does not stall a single frame on uploads.
+const MAX_RENDERS_PER_FRAME: usize = 2;
+
+/// Image id and display revision a thumbnail is rendered from. Revision 0 is the automatic mapping of images that
+/// were not shown yet.
+type ThumbnailKey = (u64, u64);
+
+struct ThumbnailResult {
+    hash: String,
+    key: ThumbnailKey,
+    size: [usize; 2],
+    rgba: Result<Vec<u8>, String>,
+}
+
+pub struct ThumbnailCache {
+    textures: HashMap<String, (ThumbnailKey, egui::TextureHandle)>,
+    /// Thumbnails with a render in flight or a failed render, which are not requested again.
+    started: HashSet<ThumbnailKey>,
+    requested: Vec<(String, ImageData, ThumbnailKey)>,
+    /// Display settings each asset was last shown with and their revision, which its thumbnail is rendered with.
+    displays: HashMap<String, (u64, ShaderParams)>,
+    last_revision: u64,
+    tx: mpsc::Sender<ThumbnailResult>,
+    rx: mpsc::Receiver<ThumbnailResult>,
+}
+
+impl Default for ThumbnailCache {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self {
+            textures: HashMap::new(),
+            started: HashSet::new(),
+            requested: Vec::new(),
+            displays: HashMap::new(),
+            last_revision: 0,
+            tx,
+            rx,
+        }
+    }
+}
+
+fn thumbnail_size(width: i32, height: i32) -> (u32, u32) {
+    let (width, height) = (width.max(1) as u32, height.max(1) as u32);
+    let scale = (THUMBNAIL_SIZE as f32 / width.max(height) as f32).min(1.0);
+    let scaled = |v: u32| ((v as f32 * scale).round() as u32).max(1);
+    (scaled(width), scaled(height))
+}
+
+/// Spread the brightness of RGBA8 `pixels` evenly over the 8-bit range by equalizing the histogram of each
+/// pixel's largest channel, scaling its channels together to keep the hue. Transparent pixels are not counted.
+fn equalize_histogram(pixels: &mut [u8]) {
+    let value = |px: &[u8]| px[0].max(px[1]).max(px[2]);
+    let mut histogram = [0u32; 256];
+    for px in pixels.chunks_exact(4).filter(|px| px[3] > 0) {
+        histogram[value(px) as usize] += 1;
+    }
+    let mut cdf = histogram;
+    for i in 1..cdf.len() {
+        cdf[i] += cdf[i - 1];
+    }
+    let total = cdf[255];
+    let Some(&first) = cdf.iter().find(|&&count| count > 0) else {
+        return;
+    };
+    if total == first {
+        return;
+    }
+    let mapped = cdf.map(|count| (count.saturating_sub(first) as f32 * 255.0 / (total - first) as f32).round());
+    for px in pixels.chunks_exact_mut(4) {
+        let v = value(px);
+        if v == 0 {
+            continue;
+        }
+        let gain = mapped[v as usize] / v as f32;
+        for channel in &mut px[..3] {
+            *channel = (*channel as f32 * gain).round().min(255.0) as u8;
+        }
+    }
+}
+
+impl ThumbnailCache {
+    /// The thumbnail of `asset`, queuing a render when it is missing, shows an older image of the same asset or was
+    /// rendered with other display settings than the asset was last shown with.
+    pub fn get(&mut self, asset: &SharedAsset) -> Option<egui::TextureHandle> {
+        let image = asset.image();
+        let revision = self.displays.get(asset.hash()).map_or(0, |(revision, _)| *revision);
+        let key = (image.id(), revision);
+        let current = self.textures.get(asset.hash());
+        if current.is_none_or(|(current_key, _)| *current_key != key) && !self.started.contains(&key) {
+            self.requested.push((asset.hash().to_string(), image.clone(), key));
+        }
+        current.map(|(_, texture)| texture.clone())
+    }
+
+    /// Remember that the asset `hash` is shown with `params`, so its thumbnail looks like it did in the viewer.
+    pub fn remember_display(&mut self, hash: &str, params: &ShaderParams) {
+        if self.displays.get(hash).is_some_and(|(_, shown)| shown == params) {
+            return;
+        }
+        self.last_revision += 1;
+        self.displays.insert(hash.to_string(), (self.last_revision, params.clone()));
+    }
+
+    /// Collect finished renders, drop thumbnails of removed assets and start queued renders.
+    pub fn update(
+        &mut self,
+        ctx: &egui::Context,
+        render_state: &egui_wgpu::RenderState,
+        assets: &indexmap::IndexMap<String, SharedAsset>,
+    ) {
+        while let Ok(result) = self.rx.try_recv() {
+            match result.rgba {
+                Ok(rgba) => {
+                    let image = egui::ColorImage::from_rgba_unmultiplied(result.size, &rgba);
+                    let texture =
+                        ctx.load_texture(format!("thumbnail:{}", result.hash), image, egui::TextureOptions::LINEAR);
+                    self.started.remove(&result.key);
+                    self.textures.insert(result.hash, (result.key, texture));
+                }
+                Err(error) => eprintln!("Failed to render thumbnail for {}: {error}", result.hash),
+            }
+        }
+        self.textures.retain(|hash, _| assets.contains_key(hash));
+        self.displays.retain(|hash, _| assets.contains_key(hash));
+
+        let requested = std::mem::take(&mut self.requested);
+        if requested.is_empty() {
+            return;
+        }
+        let egui_renderer = render_state.renderer.read();
+        let Some(renderer) = egui_renderer.callback_resources.get::<GpuRenderer>() else {
+            return;
+        };
+        for (hash, image, key) in requested.into_iter().take(MAX_RENDERS_PER_FRAME) {
+            if !self.started.insert(key) {
+                continue;
+            }
+            // Images that were not shown yet get an automatic mapping, equalized so that dark HDR data is legible.
+            let (shader, equalize) = match self.displays.get(&hash).filter(|(revision, _)| *revision == key.1) {
+                Some((_, params)) => (params.clone(), false),
+                None => (thumbnail_shader_params(image.minmax()), true),
+            };
+            let spec = image.spec();
+            let (width, height) = thumbnail_size(spec.width, spec.height);
+            let tx = self.tx.clone();
+            let repaint_ctx = ctx.clone();
+            let result_hash = hash.clone();
+            let completion = Arc::new(move |rgba: Result<Vec<u8>, String>| {
+                let rgba = rgba.map(|mut rgba| {
+                    if equalize {
+                        equalize_histogram(&mut rgba);
+                    }
+                    rgba
+                });
+                let _ = tx.send(ThumbnailResult {
+                    hash: result_hash.clone(),
+                    key,
+                    size: [width as usize, height as usize],
+                    rgba,
+                });
+                repaint_ctx.request_repaint();
+            });
+            if let Err(error) = renderer.render_thumbnail(
+                &render_state.device,
+                &render_state.queue,
+                &image,
+                width,
+                height,
+                &shader,
+                completion,
+            ) {
+                eprintln!("Failed to render thumbnail for {hash}: {error}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(values: &[u8]) -> Vec<u8> {
+        values.iter().flat_map(|&v| [v, v, v, 255]).collect()
+    }
+
+    #[test]
+    fn equalization_spreads_dark_thumbnails_over_the_full_range() {
+        let mut pixels = gray(&[2, 2, 4, 8]);
+        equalize_histogram(&mut pixels);
+        assert_eq!(pixels, gray(&[0, 0, 128, 255]));
+
+        let mut tinted = vec![4, 2, 0, 255, 8, 4, 0, 255];
+        equalize_histogram(&mut tinted);
+        assert_eq!(tinted, [0, 0, 0, 255, 255, 128, 0, 255]);
+
+        let mut flat = gray(&[7, 7]);
+        equalize_histogram(&mut flat);
+        assert_eq!(flat, gray(&[7, 7]));
+    }
+}
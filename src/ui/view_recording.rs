@@ -0,0 +1,140 @@
+//! Records the viewer viewport into a GIF while the user pans and zooms, rendering each frame through the
+//! offscreen export path.
+
+use std::{
+    path::PathBuf,
+    sync::{mpsc, Arc},
+    time::{Duration, Instant},
+};
+
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    model::{SequenceFormat, SequenceWriter},
+    ui::gpu::ExportCompletion,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordingOptions {
+    pub fps: u32,
+    /// Frame size relative to the viewport in device pixels.
+    pub scale_percent: u32,
+    /// Stop after this many seconds; 0 records until stopped.
+    pub duration_secs: u32,
+}
+
+impl Default for RecordingOptions {
+    fn default() -> Self {
+        Self {
+            fps: 15,
+            scale_percent: 50,
+            duration_secs: 5,
+        }
+    }
+}
+
+impl RecordingOptions {
+    /// Frame size for a viewport of `width`×`height` device pixels.
+    pub fn frame_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let scaled = |v: u32| ((v as u64 * self.scale_percent as u64 / 100) as u32).max(2);
+        (scaled(width), scaled(height))
+    }
+}
+
+pub struct ViewRecording {
+    output: PathBuf,
+    options: RecordingOptions,
+    size: (u32, u32),
+    started: Instant,
+    /// Frames requested so far; frame `n` is due `n / fps` seconds after the start.
+    requested: u32,
+    /// Closed by [`Self::stop`]; the writer finishes once the frames still rendering arrive.
+    frames_tx: Option<mpsc::Sender<Vec<u8>>>,
+    done_rx: mpsc::Receiver<Result<()>>,
+}
+
+impl ViewRecording {
+    /// Start writing frames of `size` to `output`.
+    pub fn start(
+        ctx: &eframe::egui::Context,
+        output: PathBuf,
+        options: RecordingOptions,
+        size: (u32, u32),
+    ) -> Result<Self> {
+        let mut writer = SequenceWriter::create(&output, SequenceFormat::Gif, size, options.fps)?;
+        let (frames_tx, frames_rx) = mpsc::channel::<Vec<u8>>();
+        let (done_tx, done_rx) = mpsc::channel();
+        let repaint_ctx = ctx.clone();
+        std::thread::spawn(move || {
+            for rgba in frames_rx {
+                if let Err(e) = writer.write_frame(rgba) {
+                    let _ = done_tx.send(Err(e));
+                    repaint_ctx.request_repaint();
+                    return;
+                }
+            }
+            let _ = done_tx.send(writer.finish());
+            repaint_ctx.request_repaint();
+        });
+        Ok(Self {
+            output,
+            options,
+            size,
+            started: Instant::now(),
+            requested: 0,
+            frames_tx: Some(frames_tx),
+            done_rx,
+        })
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.frames_tx.is_some()
+    }
+
+    /// Stop taking frames and let the writer finish the file.
+    pub fn stop(&mut self) {
+        self.frames_tx = None;
+    }
+
+    /// Completion for the next frame when it is due, and the time until the frame after it. Frames missed while the
+    /// app was busy are skipped rather than recorded late.
+    pub fn next_frame(&mut self) -> (Option<ExportCompletion>, Duration) {
+        let interval = Duration::from_secs(1) / self.options.fps.max(1);
+        let elapsed = self.elapsed();
+        if self.options.duration_secs > 0 && elapsed >= Duration::from_secs(self.options.duration_secs as u64) {
+            self.stop();
+        }
+        let Some(frames_tx) = self.frames_tx.clone() else {
+            return (None, interval);
+        };
+        let due = interval * self.requested;
+        if elapsed < due {
+            return (None, due - elapsed);
+        }
+        self.requested = (elapsed.as_nanos() / interval.as_nanos()) as u32 + 1;
+        let completion: ExportCompletion = Arc::new(move |result| match result {
+            Ok(rgba) => {
+                let _ = frames_tx.send(rgba);
+            }
+            Err(e) => eprintln!("Failed to render a recorded frame: {e}"),
+        });
+        (Some(completion), interval * self.requested - elapsed)
+    }
+
+    /// The outcome once recording stopped and the file is written.
+    pub fn poll_done(&self) -> Option<Result<String>> {
+        match self.done_rx.try_recv() {
+            Ok(result) => Some(result.map(|()| format!("Recorded {}", self.output.display()))),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err(eyre!("The GIF writer stopped"))),
+        }
+    }
+}
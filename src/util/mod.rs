@@ -5,6 +5,8 @@ pub mod expression;
 pub mod func_ext;
 pub mod math_ext;
 pub mod path_ext;
+pub mod perf;
+pub mod python_snippet;
 pub mod series;
 pub mod str_ext;
 pub mod timer;
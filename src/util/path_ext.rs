@@ -48,6 +48,30 @@ pub fn safe_temp_dir() -> PathBuf {
     p
 }
 
+/// `{prefix}{number}.{extension}` in `dir`, numbered one past the highest existing `{prefix}{number}.*` file and
+/// at least `min_number`. Returns the path and its number.
+pub fn next_numbered_path(dir: &Path, prefix: &str, extension: &str, min_number: u32) -> (PathBuf, u32) {
+    let names = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok());
+    let number = next_number(names, prefix).max(min_number);
+    (dir.join(format!("{prefix}{number:05}.{extension}")), number)
+}
+
+fn next_number(names: impl Iterator<Item = String>, prefix: &str) -> u32 {
+    names
+        .filter_map(|name| {
+            let stem = name.strip_prefix(prefix)?.split('.').next()?;
+            (!stem.is_empty() && stem.bytes().all(|b| b.is_ascii_digit()))
+                .then(|| stem.parse::<u32>().ok())
+                .flatten()
+        })
+        .max()
+        .map_or(1, |number| number.saturating_add(1))
+}
+
 pub fn exe_dir_or_cwd() -> PathBuf {
     if let Ok(exe_path) = env::current_exe() {
         if let Some(dir) = exe_path.parent() {
@@ -104,3 +128,22 @@ pub fn app_config_dir() -> PathBuf {
     let _ = fs::create_dir_all(&path);
     path
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_continue_after_existing_files() {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>().into_iter();
+        assert_eq!(next_number(names(&[]), "crop_"), 1);
+        assert_eq!(
+            next_number(
+                names(&["crop_00003.png", "crop_00010.exr", "crop_x.png", "other_00099.png"]),
+                "crop_"
+            ),
+            11
+        );
+        assert_eq!(next_number(names(&["crop_.png", "crop_7"]), "crop_"), 8);
+    }
+}
@@ -0,0 +1,83 @@
+//! Timing counters shown in the performance HUD. Unlike [`super::timer::ScopedTimer`], these are recorded in
+//! release builds too, so slowness reports can be diagnosed without a debug build.
+
+use std::{
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use indexmap::IndexMap;
+
+#[derive(Clone, Copy, Default)]
+pub struct PerfCounter {
+    pub last: Duration,
+    pub total: Duration,
+    pub count: u32,
+}
+
+impl PerfCounter {
+    pub fn average(&self) -> Duration {
+        if self.count == 0 {
+            Duration::ZERO
+        } else {
+            self.total / self.count
+        }
+    }
+}
+
+static COUNTERS: LazyLock<Mutex<IndexMap<&'static str, PerfCounter>>> = LazyLock::new(Default::default);
+
+pub fn record(name: &'static str, elapsed: Duration) {
+    let mut counters = COUNTERS.lock().unwrap();
+    let counter = counters.entry(name).or_default();
+    counter.last = elapsed;
+    counter.total += elapsed;
+    counter.count = counter.count.saturating_add(1);
+}
+
+/// Snapshot of all counters in the order they were first recorded.
+pub fn counters() -> Vec<(&'static str, PerfCounter)> {
+    COUNTERS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, counter)| (*name, *counter))
+        .collect()
+}
+
+/// Records the time until drop into the counter `name`.
+pub struct PerfScope {
+    name: &'static str,
+    t0: Instant,
+}
+
+impl PerfScope {
+    #[must_use]
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            t0: Instant::now(),
+        }
+    }
+}
+
+impl Drop for PerfScope {
+    fn drop(&mut self) {
+        record(self.name, self.t0.elapsed());
+    }
+}
+
+/// Human readable byte count using binary units.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
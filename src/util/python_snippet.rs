@@ -0,0 +1,62 @@
+use std::path::Path;
+
+use crate::{model::Recti, settings::PythonSnippetStyle};
+
+/// Build a Python snippet that reproduces the selected region. When `path` is given, the snippet also loads
+/// the image into `var`; otherwise `var` is assumed to already hold the image array.
+pub fn python_snippet(style: PythonSnippetStyle, rect: Recti, channels: i32, var: &str, path: Option<&Path>) -> String {
+    let rect = rect.validate();
+    let (x0, y0, x1, y1) = (rect.min.x, rect.min.y, rect.max.x, rect.max.y);
+    let var = if var.trim().is_empty() { "img" } else { var.trim() };
+    // `{:?}` escapes backslashes and quotes the same way a Python string literal needs them.
+    let path = path.map(|path| format!("{:?}", path.to_string_lossy()));
+    let channel_slice = if channels > 1 { ", :" } else { "" };
+
+    let mut lines = Vec::new();
+    match style {
+        PythonSnippetStyle::Numpy => {
+            if let Some(path) = &path {
+                lines.push("import imageio.v3 as iio".to_string());
+                lines.push(format!("{var} = iio.imread({path})"));
+            }
+            lines.push(format!("crop = {var}[{y0}:{y1}, {x0}:{x1}{channel_slice}]"));
+        }
+        PythonSnippetStyle::Matplotlib => {
+            lines.push("import matplotlib.pyplot as plt".to_string());
+            if let Some(path) = &path {
+                lines.push("import imageio.v3 as iio".to_string());
+                lines.push(format!("{var} = iio.imread({path})"));
+            }
+            let cmap = if channels == 1 { ", cmap=\"gray\"" } else { "" };
+            lines.push(format!("plt.imshow({var}[{y0}:{y1}, {x0}:{x1}]{cmap})"));
+            lines.push("plt.show()".to_string());
+        }
+        PythonSnippetStyle::OpenCv => {
+            if let Some(path) = &path {
+                lines.push("import cv2".to_string());
+                lines.push(format!("{var} = cv2.imread({path}, cv2.IMREAD_UNCHANGED)"));
+            }
+            lines.push(format!("x, y, w, h = {x0}, {y0}, {}, {}", rect.width(), rect.height()));
+            lines.push(format!("roi = {var}[y:y + h, x:x + w]"));
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_snippets_with_and_without_path() {
+        let rect: Recti = "10, 20, 30, 40".parse().unwrap();
+        assert_eq!(
+            python_snippet(PythonSnippetStyle::Numpy, rect, 3, "img", None),
+            "crop = img[20:60, 10:40, :]"
+        );
+        assert_eq!(
+            python_snippet(PythonSnippetStyle::OpenCv, rect, 1, "a", Some(Path::new("x.png"))),
+            "import cv2\na = cv2.imread(\"x.png\", cv2.IMREAD_UNCHANGED)\nx, y, w, h = 10, 20, 30, 40\nroi = a[y:y + h, x:x + w]"
+        );
+    }
+}
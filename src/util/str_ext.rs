@@ -8,3 +8,28 @@ impl<T: std::fmt::Display> Join for Vec<T> {
         <[String]>::join(&strings, sep)
     }
 }
+
+/// Whether the characters of `query` appear in `text` in order, ignoring case and whitespace in the query, so `ref03`
+/// finds `reference_0003.exr`.
+pub fn fuzzy_match(query: &str, text: &str) -> bool {
+    let mut text = text.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .all(|wanted| text.any(|c| c == wanted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_match_finds_ordered_subsequences() {
+        assert!(fuzzy_match("ref03", "reference_0003.exr"));
+        assert!(fuzzy_match("REF exr", "reference_0003.exr"));
+        assert!(fuzzy_match("", "anything"));
+        assert!(!fuzzy_match("exrref", "reference_0003.exr"));
+        assert!(!fuzzy_match("refx", "ref"));
+    }
+}